@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use glob::glob;
 use luanext_core::ParsedModule;
 use luanext_typechecker::module_resolver::dependency_graph::EdgeKind;
@@ -15,6 +15,9 @@ use tracing_subscriber::EnvFilter;
 #[command(name = "luanext")]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input files to compile
     #[arg(value_name = "FILE")]
     files: Vec<PathBuf>,
@@ -115,6 +118,10 @@ struct Cli {
     #[arg(long)]
     no_optimize: bool,
 
+    /// Optimize for output size (`optimizer.goal: size`) at the configured level
+    #[arg(long, conflicts_with = "no_optimize")]
+    optimize_size: bool,
+
     /// Enable optimizer profiling (logs pass timings)
     #[arg(long)]
     profile_optimizer: bool,
@@ -123,6 +130,43 @@ struct Cli {
     #[arg(long)]
     no_parallel_optimization: bool,
 
+    /// Report applied and missed optimizations per source location (text or json)
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "text",
+        require_equals = true,
+        value_parser = ["text", "json"]
+    )]
+    opt_remarks: Option<String>,
+
+    /// Print the optimizer passes that run at the configured level, then exit
+    #[arg(long)]
+    print_pass_pipeline: bool,
+
+    /// Report how many bytes each optimizer pass saves per module
+    #[arg(long)]
+    size_report: bool,
+
+    /// Check AST invariants after every optimizer pass and fail on the pass that breaks one
+    #[arg(long)]
+    verify_optimizer: bool,
+
+    /// Instrument the output to count calls, loops and branches, written to FILE on exit
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 0..=1,
+        default_missing_value = "luanext.profile",
+        require_equals = true
+    )]
+    profile_generate: Option<PathBuf>,
+
+    /// Guide inlining, cloning, loop unrolling and branch layout with a recorded profile
+    #[arg(long, value_name = "FILE", conflicts_with = "profile_generate")]
+    profile_use: Option<PathBuf>,
+
     /// Force full type check (disable incremental type checking)
     #[arg(long)]
     force_full_check: bool,
@@ -138,6 +182,46 @@ struct Cli {
     /// Reflection metadata mode (selective, full, none)
     #[arg(long, value_name = "MODE", default_value = "selective")]
     reflection: String,
+
+    /// Emit LuaLS annotations (---@class, ---@param, ...) in generated Lua
+    #[arg(long)]
+    emit_annotations: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Convert a plain Lua project to LuaNext (.luax) sources
+    Migrate {
+        /// Directory containing the .lua sources
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Write the .luax files here instead of next to the sources
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Find the optimizer transformation that makes a file fail a Lua check script
+    BisectOpt {
+        /// The .luax file to compile
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Lua script run after the compiled file; it fails by raising an error
+        #[arg(long, value_name = "SCRIPT")]
+        check: PathBuf,
+    },
+
+    /// Generate .d.luax declaration files from LuaLS annotations
+    Typings {
+        /// Annotated .lua files
+        #[arg(value_name = "FILES", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Write the .d.luax files here instead of next to the sources
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
@@ -168,6 +252,19 @@ fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    match &cli.command {
+        Some(Command::Migrate { dir, out_dir }) => {
+            return migrate_project(dir, out_dir.as_deref());
+        }
+        Some(Command::Typings { files, out_dir }) => {
+            return generate_typings(files, out_dir.as_deref());
+        }
+        Some(Command::BisectOpt { file, check }) => {
+            return bisect_optimizer(&cli, file, check);
+        }
+        None => {}
+    }
+
     // Load configuration (skip config file discovery for --emit mode)
     let (config, files) = if cli.emit.is_some() {
         // --emit mode: ignore config, only compile specified files
@@ -187,6 +284,11 @@ fn main() -> anyhow::Result<()> {
         expand_glob_patterns(&files, &config)?
     };
 
+    // --print-pass-pipeline only describes the optimizer; no input files needed
+    if cli.print_pass_pipeline {
+        return print_pass_pipeline(&cli, &config);
+    }
+
     // Validate that we have input files
     if files.is_empty() {
         eprintln!("Error: No input files specified. Use --help for usage information.");
@@ -295,6 +397,215 @@ print(greet(user))
     Ok(())
 }
 
+/// Convert every .lua file under `dir` to .luax and write a migration report
+fn migrate_project(dir: &Path, out_dir: Option<&Path>) -> anyhow::Result<()> {
+    use luanext_core::migrate::{self, SourceFile};
+    use walkdir::WalkDir;
+
+    let mut sources = Vec::new();
+    for entry in WalkDir::new(dir)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !(name.starts_with('.') || name == "node_modules")
+        })
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().is_none_or(|e| e != "lua") {
+            continue;
+        }
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        sources.push(SourceFile {
+            path: path.strip_prefix(dir).unwrap_or(path).to_path_buf(),
+            source,
+        });
+    }
+
+    if sources.is_empty() {
+        anyhow::bail!("No .lua files found in {}", dir.display());
+    }
+
+    let report = migrate::migrate_project(&sources);
+    let out_dir = out_dir.unwrap_or(dir);
+    for file in &report.files {
+        let output_path = out_dir.join(&file.path);
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&output_path, &file.source)?;
+        info!("Migrated: {}", output_path.display());
+    }
+
+    let report_path = out_dir.join("migration-report.md");
+    std::fs::write(&report_path, report.render())?;
+    println!(
+        "Migrated {} file(s); {} item(s) need manual attention (see {})",
+        report.files.len(),
+        report.note_count(),
+        report_path.display()
+    );
+
+    Ok(())
+}
+
+/// Generate `.d.luax` declaration files from LuaLS-annotated Lua files
+fn generate_typings(files: &[PathBuf], out_dir: Option<&Path>) -> anyhow::Result<()> {
+    let mut warning_count = 0;
+    for file in files {
+        let source = std::fs::read_to_string(file)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?;
+        let typings = luanext_core::typings::generate(&source);
+        for warning in &typings.warnings {
+            eprintln!(
+                "{}:{}: warning: {}",
+                file.display(),
+                warning.line,
+                warning.message
+            );
+        }
+        warning_count += typings.warnings.len();
+
+        let output_path = match out_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)?;
+                dir.join(
+                    file.with_extension("d.luax")
+                        .file_name()
+                        .unwrap_or_default(),
+                )
+            }
+            None => file.with_extension("d.luax"),
+        };
+        std::fs::write(&output_path, &typings.source)?;
+        info!("Generated: {}", output_path.display());
+    }
+
+    println!(
+        "Generated {} declaration file(s) with {} warning(s)",
+        files.len(),
+        warning_count
+    );
+    Ok(())
+}
+
+/// Bisect the optimizer for the transformation that makes `file` fail `check`
+fn bisect_optimizer(cli: &Cli, file: &Path, check: &Path) -> anyhow::Result<()> {
+    use luanext_core::codegen::{CodeGenerator, LuaTarget};
+    use luanext_core::config::OptimizationLevel;
+    use luanext_core::diagnostics::CollectingDiagnosticHandler;
+    use luanext_core::optimizer::bisect;
+    use luanext_parser::string_interner::StringInterner;
+    use std::sync::Arc;
+
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let check_script = std::fs::read_to_string(check)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", check.display(), e))?;
+
+    let level = OptimizationLevel::Aggressive;
+    let goal = optimization_goal(cli)?;
+    let pipeline = load_pass_pipeline(cli)?;
+
+    let arena = bumpalo::Bump::new();
+    let (interner, common_ids) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+
+    let mut lexer = luanext_parser::lexer::Lexer::new(&source, handler.clone(), &interner);
+    let tokens = lexer.tokenize()?;
+    let mut parser = luanext_parser::parser::Parser::new(
+        tokens,
+        handler.clone(),
+        &interner,
+        &common_ids,
+        &arena,
+    );
+    let ast = parser.parse()?;
+    let mut type_checker =
+        luanext_core::TypeChecker::new_with_stdlib(handler.clone(), &interner, &common_ids, &arena)
+            .map_err(|e| anyhow::anyhow!("Failed to load standard library: {:?}", e))?;
+    type_checker
+        .check_program(&ast)
+        .map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e.message))?;
+    let program = luanext_core::MutableProgram::from_program(&ast);
+
+    // The compiled chunk runs first and is registered as a module, so the
+    // check script can inspect its globals or `require` its exports
+    let module_name = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut run_check = |lua: &str| {
+        let Ok(runtime) = mlua::Lua::new_with(mlua::StdLib::ALL_SAFE, mlua::LuaOptions::default())
+        else {
+            return false;
+        };
+        let harness = format!(
+            "local chunk = assert(load({}, \"={}\"))\npackage.loaded[\"{}\"] = chunk() or true",
+            lua_long_string(lua),
+            module_name,
+            module_name
+        );
+        runtime.load(&harness).exec().is_ok() && runtime.load(&check_script).exec().is_ok()
+    };
+
+    // The check runs in the embedded Lua, which matches the default target
+    let result = bisect::bisect(
+        &program,
+        &arena,
+        level,
+        goal,
+        LuaTarget::default(),
+        &pipeline,
+        handler,
+        interner.clone(),
+        &mut |program| CodeGenerator::new(interner.clone()).generate(program),
+        &mut run_check,
+    )
+    .map_err(|e| anyhow::anyhow!("Cannot bisect {}: {}", file.display(), e))?;
+
+    println!(
+        "First failing pass: {} ({} of the enabled passes)",
+        result.culprit_pass(),
+        result.passes.len()
+    );
+    println!(
+        "Smallest failing set: the first {} of {} transformation(s)",
+        result.failing_transformations, result.total_transformations
+    );
+    if let Some(culprit) = &result.culprit {
+        println!(
+            "Culprit: transformation #{} by {} (iteration {}) in the statement at {}:{}:{}",
+            culprit.index,
+            culprit.passes.join(", "),
+            culprit.iteration,
+            file.display(),
+            culprit.span.line,
+            culprit.span.column
+        );
+    }
+
+    let failing_path = file.with_extension("bisect.lua");
+    std::fs::write(&failing_path, &result.failing_lua)?;
+    println!("Failing output written to {}", failing_path.display());
+    Ok(())
+}
+
+/// Quote `code` as a Lua long string, picking a level that doesn't occur in it
+fn lua_long_string(code: &str) -> String {
+    let mut level = 0;
+    while code.contains(&format!("]{}]", "=".repeat(level))) {
+        level += 1;
+    }
+    let equals = "=".repeat(level);
+    // The newlines keep a trailing `]` in `code` from closing the string early
+    format!("[{}[\n{}\n]{}]", equals, code, equals)
+}
+
 /// Parse the Lua target version string
 fn parse_lua_target(target: &str) -> anyhow::Result<luanext_core::codegen::LuaTarget> {
     use luanext_core::codegen::LuaTarget;
@@ -1042,6 +1353,8 @@ struct DependencyResult {
     ordered_files: Vec<PathBuf>,
     /// Per-file alias require path mappings: file_path → (alias_source → resolved_require_path)
     alias_maps: std::collections::HashMap<PathBuf, std::collections::HashMap<String, String>>,
+    /// Canonical paths of imported asset files, compiled as synthesized modules
+    asset_files: Vec<PathBuf>,
 }
 
 /// Discover dependencies and determine compilation order
@@ -1059,6 +1372,7 @@ fn discover_dependencies(
     let mut dep_graph = DependencyGraph::new();
     let mut file_map: HashMap<PathBuf, PathBuf> = HashMap::with_capacity(files.len());
     let mut alias_maps: HashMap<PathBuf, HashMap<String, String>> = HashMap::new();
+    let mut asset_files: Vec<PathBuf> = Vec::new();
 
     info!("Discovering dependencies for {} files...", files.len());
 
@@ -1098,6 +1412,36 @@ fn discover_dependencies(
         let mut file_alias_map: HashMap<String, String> = HashMap::new();
 
         for import in &imports {
+            // Assets are resolved relative to the importing file, without extension probing
+            if luanext_core::assets::is_asset_path(&import.source) {
+                let asset_path: PathBuf = file_path
+                    .parent()
+                    .unwrap_or_else(|| Path::new("."))
+                    .join(&import.source)
+                    .components()
+                    .collect();
+                // A missing or malformed asset fails the build: its importers
+                // cannot be type checked or run without it
+                if let Err(e) = load_asset(fs, &asset_path) {
+                    anyhow::bail!(
+                        "Failed to load asset '{}' in {}: {}",
+                        import.source,
+                        file_path.display(),
+                        e
+                    );
+                }
+                let asset_canonical = asset_path
+                    .canonicalize()
+                    .unwrap_or_else(|_| asset_path.clone());
+                if !file_map.contains_key(&asset_canonical) {
+                    file_map.insert(asset_canonical.clone(), asset_path.clone());
+                    dep_graph.add_module(ModuleId::new(asset_canonical.clone()), Vec::new());
+                    asset_files.push(asset_canonical.clone());
+                }
+                dependencies.push((ModuleId::new(asset_canonical), import.kind));
+                continue;
+            }
+
             match resolver.resolve(&import.source, file_path) {
                 Ok(dep_id) => {
                     // If this was an alias import, compute the resolved require path
@@ -1150,9 +1494,21 @@ fn discover_dependencies(
     Ok(DependencyResult {
         ordered_files,
         alias_maps,
+        asset_files,
     })
 }
 
+/// Read and parse a JSON, YAML or text asset
+fn load_asset(
+    fs: &std::sync::Arc<dyn luanext_core::fs::FileSystem>,
+    path: &Path,
+) -> anyhow::Result<luanext_core::assets::Asset> {
+    let kind = luanext_core::assets::AssetKind::from_path(path)
+        .ok_or_else(|| anyhow::anyhow!("{} is not an asset file", path.display()))?;
+    let contents = fs.read_file(path)?;
+    Ok(luanext_core::assets::Asset::parse(kind, &contents)?)
+}
+
 /// Result of compiling a single file
 struct CompilationResult {
     file_path: PathBuf,
@@ -1173,6 +1529,10 @@ struct CompilationOutput {
     output_path: PathBuf,
     /// Module to save to cache after compilation (stale files only)
     cache_entry: Option<CacheEntryData>,
+    /// Optimization remarks, collected only with `--opt-remarks`
+    remarks: Vec<luanext_core::optimizer::remarks::Remark>,
+    /// Bytes saved per pass, computed only with `--size-report`
+    size_report: Option<luanext_core::optimizer::size_report::SizeReport>,
 }
 
 struct CompilationError {
@@ -1204,7 +1564,11 @@ fn parse_single_file_with_interner<'arena>(
     interner: &luanext_parser::string_interner::StringInterner,
     common_ids: &luanext_parser::string_interner::CommonIdentifiers,
 ) -> anyhow::Result<luanext_core::ParsedModule<'arena>> {
-    let source = file_system.read_file(file_path)?;
+    // Assets are compiled as a synthesized module exporting their data
+    let source = match luanext_core::assets::AssetKind::from_path(file_path) {
+        Some(_) => load_asset(file_system, file_path)?.to_luanext_module(),
+        None => file_system.read_file(file_path)?,
+    };
 
     let handler =
         std::sync::Arc::new(luanext_core::diagnostics::CollectingDiagnosticHandler::new());
@@ -1258,7 +1622,25 @@ fn compile(
     let project_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
     let compiler_config = CompilerConfig::default();
     let mut container = luanext_core::di::DiContainer::production(compiler_config);
-    let use_cache = !cli.no_cache;
+    // Multi-entry bundles (`bundle.entries` in the config) and code-split bundles
+    // (`--out-file` with dynamic `import()`) need every module's AST, so cached
+    // modules cannot be skipped
+    let multi_entry_config = match project_config_path(&cli) {
+        Some(path) => luanext_core::codegen::multi_entry::MultiEntryConfig::from_config_file(&path)
+            .map_err(|e| anyhow::anyhow!(e))?
+            .filter(|bundle| !bundle.entries.is_empty()),
+        None => None,
+    };
+    // Profile builds emit different Lua for unchanged sources, so they are
+    // never served from or written to the cache
+    let use_cache = !cli.no_cache
+        && multi_entry_config.is_none()
+        && cli.out_file.is_none()
+        && cli.profile_generate.is_none()
+        && cli.profile_use.is_none();
+    let pass_pipeline = load_pass_pipeline(&cli)?;
+    let optimization_goal = optimization_goal(&cli)?;
+    let function_hints = load_function_hints(&cli)?;
     let use_incremental_check = !cli.force_full_check;
 
     // --- Incremental type checking setup ---
@@ -1300,7 +1682,7 @@ fn compile(
     }
 
     // Determine which files need recompilation
-    let stale_files: FxHashSet<PathBuf>;
+    let mut stale_files: FxHashSet<PathBuf>;
     let cached_modules: HashMap<PathBuf, CachedModule>;
 
     if use_cache {
@@ -1382,6 +1764,8 @@ fn compile(
     };
     let ordered_files = dep_result.ordered_files;
     let alias_maps = dep_result.alias_maps;
+    // Imported assets are not on the command line and are always recompiled
+    stale_files.extend(dep_result.asset_files);
     info!("⏱️  Dependency discovery: {:?}", dep_start.elapsed());
 
     // --- Parallel parsing of stale files ---
@@ -1578,7 +1962,8 @@ fn compile(
                 }
 
                 // Build cache entry to save after parallel section
-                let cache_entry = if use_cache {
+                let is_asset = luanext_core::assets::is_asset_path(file_path);
+                let cache_entry = if use_cache && !is_asset {
                     // Get dependencies for cache invalidation
                     let dependencies: Vec<PathBuf> = type_checker
                         .get_module_dependencies()
@@ -1683,13 +2068,7 @@ fn compile(
     // --- Phase 1.5: Whole-program analysis (for O3+ optimizations) ---
     // Build cross-module analysis before parallel codegen
     // Use config optimization_level, but allow CLI flags to override
-    let optimization_level = if cli.optimize {
-        luanext_core::config::OptimizationLevel::Aggressive
-    } else if cli.no_optimize {
-        luanext_core::config::OptimizationLevel::None
-    } else {
-        config.compiler_options.optimization_level.effective()
-    };
+    let optimization_level = optimization_level(&cli, &config);
     info!("Optimization level: {:?}", optimization_level);
 
     // --- Phase 1.4: Build module graph for LTO (O2+) ---
@@ -1751,6 +2130,60 @@ fn compile(
         info!("⏱️  Whole-program analysis: {:?}", wpa_start.elapsed());
     }
 
+    // --- Phase 1.55: Multi-entry bundles (one bundle per configured entry) ---
+    if let Some(ref bundle_config) = multi_entry_config {
+        if !cli.no_emit {
+            let multi_start = Instant::now();
+            write_multi_entry_bundles(
+                bundle_config,
+                &checked_modules,
+                &registry,
+                &file_system,
+                target,
+                !cli.no_scope_hoist,
+            )?;
+            info!("⏱️  Multi-entry bundling: {:?}", multi_start.elapsed());
+        }
+        if typecheck_failures.get() {
+            std::process::exit(1);
+        }
+        info!("✅ Compilation completed successfully!");
+        info!("⏱️  TOTAL TIME: {:?}", compile_start.elapsed());
+        return Ok(());
+    }
+
+    // --- Phase 1.56: Code splitting (bundle split at dynamic `import()`) ---
+    if let Some(ref out_file) = cli.out_file {
+        let has_dynamic_imports = checked_modules.iter().any(|m| {
+            !luanext_core::codegen::code_splitting::collect_dynamic_imports(
+                m.ast.statements,
+                &m.interner,
+            )
+            .is_empty()
+        });
+        if has_dynamic_imports && cli.emit.is_none() {
+            if !cli.no_emit {
+                let split_start = Instant::now();
+                write_split_bundle(
+                    out_file,
+                    cli.files.first().map(PathBuf::as_path),
+                    &checked_modules,
+                    &file_system,
+                    target,
+                    !cli.no_tree_shake,
+                    !cli.no_scope_hoist,
+                )?;
+                info!("⏱️  Code-split bundling: {:?}", split_start.elapsed());
+            }
+            if typecheck_failures.get() {
+                std::process::exit(1);
+            }
+            info!("✅ Compilation completed successfully!");
+            info!("⏱️  TOTAL TIME: {:?}", compile_start.elapsed());
+            return Ok(());
+        }
+    }
+
     // --- Phase 1.6: Tree shaking (reachability analysis for bundles) ---
     let tree_shaking_enabled = cli.out_file.is_some() && !cli.no_tree_shake;
     let reachable_set: Option<luanext_core::codegen::tree_shaking::ReachableSet> =
@@ -1824,7 +2257,9 @@ fn compile(
     // --- Phase 1.7: LTO module filtering (O2+) ---
     // Filter out unreachable modules based on LTO analysis
     if let Some(ref graph) = module_graph_opt {
-        if optimization_level >= luanext_core::config::OptimizationLevel::Moderate {
+        if optimization_level >= luanext_core::config::OptimizationLevel::Moderate
+            && !pass_pipeline.is_disabled("unused-module-elimination")
+        {
             use luanext_core::optimizer::UnusedModuleEliminationPass;
 
             let lto_pass = UnusedModuleEliminationPass::new(graph.clone());
@@ -1837,7 +2272,20 @@ fn compile(
                     .file_path
                     .canonicalize()
                     .unwrap_or_else(|_| module.file_path.clone());
-                lto_compile_set.contains(&canonical)
+                let keep = lto_compile_set.contains(&canonical);
+                if !keep {
+                    if let Some(format) = &cli.opt_remarks {
+                        let remark = luanext_core::optimizer::remarks::Remark {
+                            pass: "unused-module-elimination",
+                            kind: luanext_core::optimizer::remarks::RemarkKind::Applied,
+                            span: luanext_parser::span::Span::new(0, 0, 1, 1),
+                            message: "module is unreachable from every entry point; not compiled"
+                                .to_string(),
+                        };
+                        print_remark(&module.file_path, &remark, format);
+                    }
+                }
+                keep
             });
 
             let eliminated = pre_lto_count - checked_modules_filtered.len();
@@ -1851,6 +2299,31 @@ fn compile(
         }
     }
 
+    // Asset modules are written as `return <data>` instead of being compiled;
+    // every importer requires that one module, so they all share its table
+    let mut asset_outputs: Vec<(PathBuf, String)> = Vec::new();
+    for module in &checked_modules_filtered {
+        if luanext_core::assets::is_asset_path(&module.file_path) {
+            let asset = load_asset(&file_system, &module.file_path)?;
+            asset_outputs.push((
+                asset_output_path(&module.file_path, &cli),
+                asset.to_lua_module(),
+            ));
+        }
+    }
+    checked_modules_filtered
+        .retain(|module| !luanext_core::assets::is_asset_path(&module.file_path));
+
+    // --profile-use: counts recorded by a --profile-generate build
+    let profile = cli
+        .profile_use
+        .as_ref()
+        .map(|path| {
+            luanext_core::optimizer::profile::Profile::from_file(path)
+                .map_err(|e| anyhow::anyhow!("Cannot read profile {}: {}", path.display(), e))
+        })
+        .transpose()?;
+
     // --- Phase 2: Parallel code generation ---
     // Each module's codegen is independent - can run in parallel
     let codegen_start = Instant::now();
@@ -1858,66 +2331,160 @@ fn compile(
     let results: Vec<CompilationResult> = checked_modules_filtered
         .into_par_iter()
         .map(|module| {
-            let output_format = parse_output_format(&cli.format);
-            let mut builder = CodeGeneratorBuilder::new(module.interner.clone())
-                .target(target)
-                .output_format(output_format)
-                .optimization_level(optimization_level)
-                .alias_require_map(module.alias_require_map.clone());
-
-            if module.enable_source_map {
-                builder = builder.source_map(module.file_path.to_string_lossy().to_string());
-            }
+            // --size-report emits every module once per pass, each time with a fresh generator
+            let make_generator = || {
+                let output_format = parse_output_format(&cli.format);
+                let mut builder = CodeGeneratorBuilder::new(module.interner.clone())
+                    .target(target)
+                    .output_format(output_format)
+                    .optimization_level(optimization_level)
+                    .annotations(cli.emit_annotations)
+                    .alias_require_map(module.alias_require_map.clone());
+
+                if module.enable_source_map {
+                    builder = builder.source_map(module.file_path.to_string_lossy().to_string());
+                }
 
-            // Pass whole-program analysis if available
-            if let Some(ref analysis) = whole_program_analysis {
-                builder = builder.with_whole_program_analysis(analysis.clone());
-            }
+                // Pass whole-program analysis if available
+                if let Some(ref analysis) = whole_program_analysis {
+                    builder = builder.with_whole_program_analysis(analysis.clone());
+                }
 
-            // Pass tree shaking reachability info if enabled
-            if let Some(ref reachable) = reachable_set {
-                let module_id = module.file_path.to_string_lossy().to_string();
-                if let Some(exports) = reachable.get_reachable_exports(&module_id) {
-                    let exports_set: std::collections::HashSet<String> =
-                        exports.iter().cloned().collect();
-                    builder = builder.with_tree_shaking(exports_set);
+                // Pass tree shaking reachability info if enabled
+                if let Some(ref reachable) = reachable_set {
+                    let module_id = module.file_path.to_string_lossy().to_string();
+                    if let Some(exports) = reachable.get_reachable_exports(&module_id) {
+                        let exports_set: std::collections::HashSet<String> =
+                            exports.iter().cloned().collect();
+                        builder = builder.with_tree_shaking(exports_set);
+                    }
                 }
-            }
+                builder.build()
+            };
 
-            let mut generator = builder.build();
+            let mut generator = make_generator();
+            // Declared before the AST so it outlives nodes the optimizer allocates
+            let optimizer_arena = bumpalo::Bump::new();
             // Convert arena-allocated Program to mutable AST for codegen
             let mut mutable_ast = luanext_core::MutableProgram::from_program(&module.ast);
 
             // Apply LTO AST transformation passes (O2+)
-            if let Some(ref graph) = module_graph_opt {
-                if optimization_level >= luanext_core::config::OptimizationLevel::Moderate {
-                    use luanext_core::optimizer::passes::{
-                        DeadExportEliminationPass, DeadImportEliminationPass,
-                        ReExportFlatteningPass,
-                    };
+            let ((), mut remarks) = with_remarks(cli.opt_remarks.is_some(), || {
+                if let Some(ref graph) = module_graph_opt {
+                    if optimization_level >= luanext_core::config::OptimizationLevel::Moderate {
+                        use luanext_core::optimizer::passes::{
+                            DeadExportEliminationPass, DeadImportEliminationPass,
+                            ReExportFlatteningPass,
+                        };
 
-                    // O3: Re-export flattening (runs first to enable more dead code elimination)
-                    if optimization_level >= luanext_core::config::OptimizationLevel::Aggressive {
-                        let mut reexport_pass =
-                            ReExportFlatteningPass::new(graph.clone(), module.interner.clone());
-                        reexport_pass.set_current_module(&module.file_path);
-                        mutable_ast.statements = reexport_pass.apply(&mutable_ast.statements);
-                    }
+                        // O3: Re-export flattening (runs first to enable more dead code elimination)
+                        if optimization_level >= luanext_core::config::OptimizationLevel::Aggressive
+                            && !pass_pipeline.is_disabled("reexport-flattening")
+                        {
+                            let mut reexport_pass =
+                                ReExportFlatteningPass::new(graph.clone(), module.interner.clone());
+                            reexport_pass.set_current_module(&module.file_path);
+                            mutable_ast.statements = reexport_pass.apply(&mutable_ast.statements);
+                        }
 
-                    // O2+: Dead import/export elimination
-                    let mut import_pass =
-                        DeadImportEliminationPass::new(graph.clone(), module.interner.clone());
-                    let mut export_pass =
-                        DeadExportEliminationPass::new(graph.clone(), module.interner.clone());
+                        // O2+: Dead import/export elimination
+                        let mut import_pass =
+                            DeadImportEliminationPass::new(graph.clone(), module.interner.clone());
+                        let mut export_pass =
+                            DeadExportEliminationPass::new(graph.clone(), module.interner.clone());
 
-                    import_pass.set_current_module(&module.file_path);
-                    export_pass.set_current_module(&module.file_path);
+                        import_pass.set_current_module(&module.file_path);
+                        export_pass.set_current_module(&module.file_path);
 
-                    let after_import = import_pass.apply(&mutable_ast.statements);
-                    let after_export = export_pass.apply(&after_import);
+                        if !pass_pipeline.is_disabled("dead-import-elimination") {
+                            mutable_ast.statements = import_pass.apply(&mutable_ast.statements);
+                        }
+                        if !pass_pipeline.is_disabled("dead-export-elimination") {
+                            mutable_ast.statements = export_pass.apply(&mutable_ast.statements);
+                        }
+                    }
+                }
+            });
 
-                    mutable_ast.statements = after_export;
+            // --size-report measures the program as the LTO passes left it,
+            // before the AST optimizer below changes it
+            let size_report = (cli.size_report
+                && optimization_level > luanext_core::config::OptimizationLevel::None)
+                .then(|| {
+                    luanext_core::optimizer::size_report::measure(
+                        &mutable_ast,
+                        &optimizer_arena,
+                        optimization_level,
+                        optimization_goal,
+                        target,
+                        &pass_pipeline,
+                        Arc::new(CollectingDiagnosticHandler::new()),
+                        module.interner.clone(),
+                        &mut |program| make_generator().generate(program),
+                    )
+                })
+                .transpose()
+                .unwrap_or_else(|err| {
+                    warn!("Size report for {:?} failed: {}", module.file_path, err);
+                    None
+                });
+
+            // --profile-generate counts the program as written, so the
+            // counters go in before the AST optimizer moves code around
+            if let Some(path) = &cli.profile_generate {
+                luanext_core::optimizer::profile::instrument(
+                    &mut mutable_ast,
+                    &optimizer_arena,
+                    &module.interner,
+                );
+                generator = generator.with_profile_instrumentation(
+                    profile_module_id(&module.file_path, &project_root),
+                    project_root.join(path).to_string_lossy().to_string(),
+                );
+            }
+
+            // Apply the AST optimizer (O1+). --opt-remarks and
+            // --verify-optimizer only observe it, so they leave the Lua as is
+            if optimization_level > luanext_core::config::OptimizationLevel::None {
+                let handler = Arc::new(CollectingDiagnosticHandler::new());
+                let mut optimizer = luanext_core::optimizer::Optimizer::new(
+                    optimization_level,
+                    handler,
+                    module.interner.clone(),
+                );
+                optimizer.set_goal(optimization_goal);
+                optimizer.set_target(target);
+                optimizer.set_pass_pipeline(pass_pipeline.clone());
+                if !function_hints.is_empty() {
+                    optimizer.set_function_hints(function_hints.clone());
+                }
+                if let Some(module_profile) = profile.as_ref().and_then(|profile| {
+                    profile.module(&profile_module_id(&module.file_path, &project_root))
+                }) {
+                    optimizer.set_profile(module_profile);
                 }
+                if let Some(analysis) = &whole_program_analysis {
+                    optimizer.set_whole_program_analysis(analysis.clone());
+                }
+                if cli.opt_remarks.is_some() {
+                    optimizer.enable_remarks();
+                }
+                if cli.verify_optimizer {
+                    optimizer.enable_verifier();
+                }
+                if let Err(err) = optimizer.optimize(&mut mutable_ast, &optimizer_arena) {
+                    if cli.verify_optimizer {
+                        return CompilationResult {
+                            file_path: module.file_path,
+                            result: Err(CompilationError {
+                                diagnostics: Vec::new(),
+                                source: err,
+                            }),
+                        };
+                    }
+                    warn!("Optimization of {:?} failed: {}", module.file_path, err);
+                }
+                remarks.extend(optimizer.take_remarks());
             }
 
             let lua_code = generator.generate(&mutable_ast);
@@ -1930,6 +2497,8 @@ fn compile(
                     source_map,
                     output_path: module.output_path,
                     cache_entry: module.cache_entry,
+                    remarks,
+                    size_report,
                 }),
             }
         })
@@ -1993,6 +2562,15 @@ fn compile(
     for result in &results {
         match &result.result {
             Ok(output) => {
+                if let Some(format) = &cli.opt_remarks {
+                    for remark in &output.remarks {
+                        print_remark(&result.file_path, remark, format);
+                    }
+                }
+                if let Some(report) = &output.size_report {
+                    print_size_report(&result.file_path, report);
+                }
+
                 // --emit mode: collect code for stdout
                 if cli.emit.is_some() {
                     if !emit_code.is_empty() {
@@ -2076,6 +2654,17 @@ fn compile(
         info!("Generated bundle: {:?}", out_file);
     }
 
+    // Write asset modules next to the code that requires them
+    if !cli.no_emit {
+        for (asset_path, code) in &asset_outputs {
+            if let Some(parent) = asset_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(asset_path, code)?;
+            info!("Generated asset module: {:?}", asset_path);
+        }
+    }
+
     if had_errors || typecheck_failures.get() {
         std::process::exit(1);
     }
@@ -2096,6 +2685,345 @@ fn compile(
     Ok(())
 }
 
+/// Optimization level from the config, overridden by --optimize / --no-optimize.
+/// --optimize-size only changes the goal, see [`optimization_goal`].
+fn optimization_level(
+    cli: &Cli,
+    config: &luanext_core::config::CompilerConfig,
+) -> luanext_core::config::OptimizationLevel {
+    if cli.optimize {
+        luanext_core::config::OptimizationLevel::Aggressive
+    } else if cli.no_optimize {
+        luanext_core::config::OptimizationLevel::None
+    } else {
+        config.compiler_options.optimization_level.effective()
+    }
+}
+
+/// The `optimizer.passes` section of the project config, or the default pipeline
+fn load_pass_pipeline(
+    cli: &Cli,
+) -> anyhow::Result<luanext_core::optimizer::pipeline::PassPipelineConfig> {
+    use luanext_core::optimizer::pipeline::PassPipelineConfig;
+
+    match project_config_path(cli) {
+        Some(path) => PassPipelineConfig::from_config_file(&path).map_err(|e| anyhow::anyhow!(e)),
+        None => Ok(PassPipelineConfig::default()),
+    }
+}
+
+/// `optimizer.hints` from the project config
+fn load_function_hints(
+    cli: &Cli,
+) -> anyhow::Result<luanext_core::optimizer::hints::FreeFunctionHints> {
+    use luanext_core::optimizer::hints::FreeFunctionHints;
+
+    match project_config_path(cli) {
+        Some(path) => FreeFunctionHints::from_config_file(&path).map_err(|e| anyhow::anyhow!(e)),
+        None => Ok(FreeFunctionHints::default()),
+    }
+}
+
+/// `optimizer.goal` from the project config, overridden by --optimize-size
+fn optimization_goal(
+    cli: &Cli,
+) -> anyhow::Result<luanext_core::optimizer::pipeline::OptimizationGoal> {
+    use luanext_core::optimizer::pipeline::OptimizationGoal;
+
+    if cli.optimize_size {
+        return Ok(OptimizationGoal::Size);
+    }
+    match project_config_path(cli) {
+        Some(path) => OptimizationGoal::from_config_file(&path).map_err(|e| anyhow::anyhow!(e)),
+        None => Ok(OptimizationGoal::default()),
+    }
+}
+
+/// How a module is named in profile files: its path relative to the project
+/// root, with `/` separators so profiles carry over between platforms
+fn profile_module_id(file_path: &Path, project_root: &Path) -> String {
+    let file = file_path
+        .canonicalize()
+        .unwrap_or_else(|_| file_path.to_path_buf());
+    let root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    file.strip_prefix(&root)
+        .unwrap_or(&file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Print the passes that run at the configured level, in execution order
+fn print_pass_pipeline(
+    cli: &Cli,
+    config: &luanext_core::config::CompilerConfig,
+) -> anyhow::Result<()> {
+    use luanext_core::diagnostics::CollectingDiagnosticHandler;
+    use luanext_core::optimizer::Optimizer;
+    use luanext_parser::string_interner::StringInterner;
+    use std::sync::Arc;
+
+    let level = optimization_level(cli, config);
+    let goal = optimization_goal(cli)?;
+    let pipeline = load_pass_pipeline(cli)?;
+    let mut optimizer = Optimizer::new(
+        level,
+        Arc::new(CollectingDiagnosticHandler::new()),
+        Arc::new(StringInterner::new()),
+    );
+    optimizer.set_goal(goal);
+    optimizer.set_pass_pipeline(pipeline.clone());
+
+    println!(
+        "Optimization level: {:?} (max {} iterations)",
+        level,
+        optimizer.max_iterations()
+    );
+    println!("Optimization goal: {}", goal.as_str());
+    if level == luanext_core::config::OptimizationLevel::None {
+        println!("No passes run at this level");
+        return Ok(());
+    }
+
+    let passes = optimizer
+        .pass_pipeline()
+        .into_iter()
+        .chain(pipeline.link_time_passes(level));
+    let mut current_group = None;
+    for info in passes {
+        if current_group != Some(info.group) {
+            println!("{}:", info.group.as_str());
+            current_group = Some(info.group);
+        }
+        println!("  {}", info.name);
+    }
+    Ok(())
+}
+
+/// Print a `--size-report` table for one module to stderr
+fn print_size_report(file_path: &Path, report: &luanext_core::optimizer::size_report::SizeReport) {
+    eprintln!(
+        "{}: {} -> {} bytes ({} saved)",
+        file_path.display(),
+        report.original_bytes,
+        report.optimized_bytes,
+        report.bytes_saved()
+    );
+    for saving in &report.passes {
+        eprintln!("  {:<36} {:>8}", saving.pass, saving.bytes_saved);
+    }
+}
+
+/// Path of the project config file, if one is in use
+fn project_config_path(cli: &Cli) -> Option<PathBuf> {
+    if let Some(ref project_path) = cli.project {
+        return Some(project_path.clone());
+    }
+    let default_path = PathBuf::from("luanext.config.yaml");
+    default_path.exists().then_some(default_path)
+}
+
+/// Generate one bundle per configured entry from a single shared module graph
+fn write_multi_entry_bundles(
+    bundle_config: &luanext_core::codegen::multi_entry::MultiEntryConfig,
+    checked_modules: &[CheckedModule],
+    registry: &luanext_core::module_resolver::ModuleRegistry,
+    file_system: &std::sync::Arc<dyn luanext_core::fs::FileSystem>,
+    target: luanext_core::codegen::LuaTarget,
+    scope_hoisting_enabled: bool,
+) -> anyhow::Result<()> {
+    use luanext_core::codegen::multi_entry::{bundle_import_map, MultiEntryPlan};
+    use luanext_core::codegen::CodeGenerator;
+    use luanext_core::optimizer::analysis::module_graph::ModuleGraph;
+
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    // Map configured entries onto the compiled module paths
+    let mut entries = Vec::with_capacity(bundle_config.entries.len());
+    for entry in &bundle_config.entries {
+        let wanted = canonical(&entry.entry);
+        let module = checked_modules
+            .iter()
+            .find(|m| canonical(&m.file_path) == wanted)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Bundle entry '{}' ({}) is not among the compiled files",
+                    entry.name,
+                    entry.entry.display()
+                )
+            })?;
+        entries.push(module.file_path.clone());
+    }
+
+    let interner = checked_modules
+        .first()
+        .map(|m| m.interner.clone())
+        .unwrap_or_else(|| std::sync::Arc::new(StringInterner::new()));
+
+    let module_data: Vec<(PathBuf, &[luanext_parser::ast::statement::Statement])> = checked_modules
+        .iter()
+        .map(|m| (m.file_path.clone(), m.ast.statements))
+        .collect();
+    let graph = ModuleGraph::build(&module_data, interner.clone(), registry, &entries);
+
+    let plan = MultiEntryPlan::build(&graph, &entries, bundle_config.shared_file.is_some());
+
+    let known_modules: Vec<PathBuf> = checked_modules
+        .iter()
+        .map(|m| m.file_path.clone())
+        .collect();
+    let bundle_modules: Vec<(
+        String,
+        &luanext_parser::ast::Program,
+        std::collections::HashMap<String, String>,
+    )> = checked_modules
+        .iter()
+        .map(|m| {
+            (
+                m.file_path.to_string_lossy().to_string(),
+                &m.ast,
+                bundle_import_map(&m.file_path, &m.ast, &known_modules, &interner),
+            )
+        })
+        .collect();
+
+    // Assets are bundled as modules returning their data
+    let mut asset_modules = luanext_core::assets::AssetModules::new();
+    for module in checked_modules {
+        if luanext_core::assets::is_asset_path(&module.file_path) {
+            let asset = load_asset(file_system, &module.file_path)?;
+            asset_modules.insert(
+                module.file_path.to_string_lossy().to_string(),
+                asset.to_lua_expression(),
+            );
+        }
+    }
+
+    let shared_name = bundle_config
+        .shared_require_name()
+        .unwrap_or_else(|| "shared".to_string());
+    let output = CodeGenerator::generate_multi_entry_bundles(
+        &bundle_modules,
+        &plan,
+        &shared_name,
+        target,
+        interner,
+        scope_hoisting_enabled,
+        &asset_modules,
+    );
+
+    for (entry, (_, code)) in bundle_config.entries.iter().zip(&output.bundles) {
+        if let Some(parent) = entry.out_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&entry.out_file, code)?;
+        info!("Generated bundle '{}': {:?}", entry.name, entry.out_file);
+    }
+
+    if let (Some(shared_code), Some(shared_file)) = (&output.shared, &bundle_config.shared_file) {
+        if let Some(parent) = shared_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(shared_file, shared_code)?;
+        info!(
+            "Generated shared modules ({} module(s)): {:?}",
+            plan.shared.len(),
+            shared_file
+        );
+    }
+
+    Ok(())
+}
+
+/// Generate a bundle split at dynamic `import()` expressions.
+///
+/// The main bundle goes to `out_file`; chunk files are written next to it so the
+/// chunk loader can `require` them by name.
+fn write_split_bundle(
+    out_file: &Path,
+    entry: Option<&Path>,
+    checked_modules: &[CheckedModule],
+    file_system: &std::sync::Arc<dyn luanext_core::fs::FileSystem>,
+    target: luanext_core::codegen::LuaTarget,
+    tree_shaking_enabled: bool,
+    scope_hoisting_enabled: bool,
+) -> anyhow::Result<()> {
+    use luanext_core::codegen::multi_entry::bundle_import_map;
+    use luanext_core::codegen::CodeGenerator;
+
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    let entry = entry.ok_or_else(|| anyhow::anyhow!("Bundling requires an entry file"))?;
+    let wanted = canonical(entry);
+    let entry_module = checked_modules
+        .iter()
+        .find(|m| canonical(&m.file_path) == wanted)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Bundle entry {} is not among the compiled files",
+                entry.display()
+            )
+        })?;
+    let entry_module_id = entry_module.file_path.to_string_lossy().to_string();
+
+    let interner = entry_module.interner.clone();
+
+    let known_modules: Vec<PathBuf> = checked_modules
+        .iter()
+        .map(|m| m.file_path.clone())
+        .collect();
+    let bundle_modules: Vec<(
+        String,
+        &luanext_parser::ast::Program,
+        std::collections::HashMap<String, String>,
+    )> = checked_modules
+        .iter()
+        .map(|m| {
+            (
+                m.file_path.to_string_lossy().to_string(),
+                &m.ast,
+                bundle_import_map(&m.file_path, &m.ast, &known_modules, &interner),
+            )
+        })
+        .collect();
+
+    // Assets are bundled as modules returning their data
+    let mut asset_modules = luanext_core::assets::AssetModules::new();
+    for module in checked_modules {
+        if luanext_core::assets::is_asset_path(&module.file_path) {
+            let asset = load_asset(file_system, &module.file_path)?;
+            asset_modules.insert(
+                module.file_path.to_string_lossy().to_string(),
+                asset.to_lua_expression(),
+            );
+        }
+    }
+
+    let output = CodeGenerator::generate_split_bundle(
+        &bundle_modules,
+        &entry_module_id,
+        target,
+        interner,
+        tree_shaking_enabled,
+        scope_hoisting_enabled,
+        &asset_modules,
+    );
+
+    let out_dir = out_file.parent().unwrap_or_else(|| Path::new(""));
+    std::fs::create_dir_all(out_dir)?;
+    std::fs::write(out_file, &output.main)?;
+    info!("Generated bundle: {:?}", out_file);
+
+    for chunk in &output.chunks {
+        let chunk_path = out_dir.join(&chunk.file_name);
+        std::fs::write(&chunk_path, &chunk.code)?;
+        info!("Generated chunk '{}': {:?}", chunk.name, chunk_path);
+    }
+
+    Ok(())
+}
+
 /// Copy plain .lua files to the output directory
 fn copy_lua_files_to_output(cli: &Cli) -> anyhow::Result<()> {
     use std::fs;
@@ -2162,6 +3090,64 @@ fn determine_output_path(file_path: &Path, cli: &Cli) -> PathBuf {
     }
 }
 
+/// Output path of an asset module: the asset file name plus `.lua`, so
+/// `config.json` does not collide with the output of a `config.luax` module
+fn asset_output_path(file_path: &Path, cli: &Cli) -> PathBuf {
+    let file_name = format!(
+        "{}.lua",
+        file_path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let dir = if let Some(out_file) = &cli.out_file {
+        out_file.parent().map(Path::to_path_buf).unwrap_or_default()
+    } else if let Some(out_dir) = &cli.out_dir {
+        out_dir.clone()
+    } else {
+        file_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    };
+    dir.join(file_name)
+}
+
+/// Run `f`, collecting the optimization remarks it reports when `enabled`
+fn with_remarks<R>(
+    enabled: bool,
+    f: impl FnOnce() -> R,
+) -> (R, Vec<luanext_core::optimizer::remarks::Remark>) {
+    if enabled {
+        luanext_core::optimizer::remarks::collect(f)
+    } else {
+        (f(), Vec::new())
+    }
+}
+
+/// Print an optimization remark to stderr as `file:line:col: kind [pass]: message`
+/// or, for `--opt-remarks=json`, as one JSON object per line
+fn print_remark(file_path: &Path, remark: &luanext_core::optimizer::remarks::Remark, format: &str) {
+    if format == "json" {
+        let json = serde_json::json!({
+            "file": file_path.to_string_lossy(),
+            "line": remark.span.line,
+            "column": remark.span.column,
+            "kind": remark.kind.as_str(),
+            "pass": remark.pass,
+            "message": remark.message,
+        });
+        eprintln!("{}", json);
+    } else {
+        eprintln!(
+            "{}:{}:{}: {} [{}]: {}",
+            file_path.display(),
+            remark.span.line,
+            remark.span.column,
+            remark.kind.as_str(),
+            remark.pass,
+            remark.message
+        );
+    }
+}
+
 /// Print diagnostics from a vec (used by parallel compilation)
 fn print_diagnostics_from_vec(
     diagnostics: &[luanext_core::diagnostics::Diagnostic],
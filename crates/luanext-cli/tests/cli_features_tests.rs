@@ -441,3 +441,633 @@ fn test_file_with_spaces() {
 
     luanext_cmd().arg(&input_file).assert().success();
 }
+
+// ============================================================================
+// MIGRATION TESTS
+// ============================================================================
+
+/// Test `migrate` writes .luax files and a report next to the sources
+#[test]
+fn test_migrate_writes_luax_and_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    fs::create_dir_all(src.join("lib")).unwrap();
+    fs::write(
+        src.join("lib/util.lua"),
+        "local M = {}\nfunction M.double(x) return x * 2 end\nreturn M\n",
+    )
+    .unwrap();
+    fs::write(
+        src.join("main.lua"),
+        "local util = require(\"lib.util\")\nmodule(\"legacy\")\nprint(util.double(2))\n",
+    )
+    .unwrap();
+
+    luanext_cmd()
+        .arg("migrate")
+        .arg(&src)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Migrated 2 file(s); 1 item(s)"));
+
+    let util = fs::read_to_string(src.join("lib/util.luax")).unwrap();
+    assert!(util.contains("export function double(x)"));
+    let main = fs::read_to_string(src.join("main.luax")).unwrap();
+    assert!(main.contains("import * as util from \"./lib/util\""));
+    let report = fs::read_to_string(src.join("migration-report.md")).unwrap();
+    assert!(report.contains("`module()` is not supported"));
+}
+
+/// Test `migrate --out-dir` leaves the source directory untouched
+#[test]
+fn test_migrate_out_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    let out = temp_dir.path().join("migrated");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("main.lua"), "local count = 0\nprint(count)\n").unwrap();
+
+    luanext_cmd()
+        .arg("migrate")
+        .arg(&src)
+        .arg("--out-dir")
+        .arg(&out)
+        .assert()
+        .success();
+
+    assert!(!src.join("main.luax").exists());
+    let main = fs::read_to_string(out.join("main.luax")).unwrap();
+    assert!(main.contains("local count: number = 0"));
+    assert!(out.join("migration-report.md").exists());
+}
+
+/// Test `migrate` fails on a directory without Lua sources
+#[test]
+fn test_migrate_empty_dir() {
+    let temp_dir = TempDir::new().unwrap();
+
+    luanext_cmd()
+        .arg("migrate")
+        .arg(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No .lua files found"));
+}
+
+// ============================================================================
+// TYPINGS TESTS
+// ============================================================================
+
+/// Test `typings` writes a .d.luax next to each annotated file
+#[test]
+fn test_typings_writes_declarations() {
+    let temp_dir = TempDir::new().unwrap();
+    let lib = temp_dir.path().join("lib.lua");
+    fs::write(
+        &lib,
+        "local M = {}\n\n---@param x number\n---@return number\nfunction M.double(x) return x * 2 end\n\n\
+         ---@param s ??bad\nfunction M.broken(s) end\n\nreturn M\n",
+    )
+    .unwrap();
+
+    luanext_cmd()
+        .arg("typings")
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Generated 1 declaration file(s) with 1 warning(s)",
+        ))
+        .stderr(predicate::str::contains("lib.lua:7: warning:"));
+
+    let typings = fs::read_to_string(temp_dir.path().join("lib.d.luax")).unwrap();
+    assert!(typings.contains("export declare function double(x: number): number"));
+    assert!(typings.contains("export declare function broken(s: any): void"));
+}
+
+/// Test `typings --out-dir` collects declarations in one directory
+#[test]
+fn test_typings_out_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let out = temp_dir.path().join("types");
+    let log = temp_dir.path().join("log.lua");
+    fs::write(&log, "---@param msg string\nfunction log(msg) end\n").unwrap();
+
+    luanext_cmd()
+        .arg("typings")
+        .arg(&log)
+        .arg("--out-dir")
+        .arg(&out)
+        .assert()
+        .success();
+
+    assert!(!temp_dir.path().join("log.d.luax").exists());
+    let typings = fs::read_to_string(out.join("log.d.luax")).unwrap();
+    assert!(typings.contains("declare function log(msg: string): void"));
+}
+
+// ============================================================================
+// LUALS ANNOTATION TESTS
+// ============================================================================
+
+/// Test --emit-annotations adds LuaLS comments to the generated Lua
+#[test]
+fn test_emit_annotations() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("lib.luax");
+    let out_dir = temp_dir.path().join("out");
+    fs::write(
+        &input_file,
+        "function double(x: number): number {\n    return x * 2\n}\n",
+    )
+    .unwrap();
+
+    luanext_cmd()
+        .arg(&input_file)
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .arg("--emit-annotations")
+        .assert()
+        .success();
+
+    let lua = fs::read_to_string(out_dir.join("lib.lua")).unwrap();
+    assert!(lua.contains("---@param x number\n---@return number\nlocal function double(x)"));
+}
+
+// ============================================================================
+// OPTIMIZATION REMARKS TESTS
+// ============================================================================
+
+const UNROLLABLE_LOOP: &str = "for i = 1, 3 do\n    print(i)\nend\n";
+
+/// Test --opt-remarks prints applied transformations with their location
+#[test]
+fn test_opt_remarks_text() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("main.luax");
+    fs::write(&input_file, UNROLLABLE_LOOP).unwrap();
+
+    luanext_cmd()
+        .arg(&input_file)
+        .arg("--out-dir")
+        .arg(temp_dir.path().join("out"))
+        .arg("--optimize")
+        .arg("--opt-remarks")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "main.luax:1:1: applied [loop-unrolling]: unrolled loop with 3 iteration(s)",
+        ));
+
+    let lua = fs::read_to_string(temp_dir.path().join("out/main.lua")).unwrap();
+    assert!(!lua.contains("for"), "{}", lua);
+}
+
+/// Test --opt-remarks only reports, leaving the emitted Lua unchanged
+#[test]
+fn test_opt_remarks_do_not_change_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("main.luax");
+    fs::write(&input_file, UNROLLABLE_LOOP).unwrap();
+
+    for (dir, remarks) in [("plain", false), ("remarks", true)] {
+        let mut cmd = luanext_cmd();
+        cmd.arg(&input_file)
+            .arg("--out-dir")
+            .arg(temp_dir.path().join(dir))
+            .arg("--optimize");
+        if remarks {
+            cmd.arg("--opt-remarks");
+        }
+        cmd.assert().success();
+    }
+
+    let plain = fs::read_to_string(temp_dir.path().join("plain/main.lua")).unwrap();
+    let remarks = fs::read_to_string(temp_dir.path().join("remarks/main.lua")).unwrap();
+    assert!(!plain.contains("for"), "{}", plain);
+    assert_eq!(plain, remarks);
+}
+
+/// Test --opt-remarks=json prints one JSON object per remark
+#[test]
+fn test_opt_remarks_json() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("main.luax");
+    fs::write(&input_file, UNROLLABLE_LOOP).unwrap();
+
+    let output = luanext_cmd()
+        .arg(&input_file)
+        .arg("--out-dir")
+        .arg(temp_dir.path().join("out"))
+        .arg("--optimize")
+        .arg("--opt-remarks=json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let remark = stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|remark| remark["pass"] == "loop-unrolling")
+        .expect("no loop-unrolling remark");
+    assert_eq!(remark["kind"], "applied");
+    assert_eq!(remark["line"], 1);
+    assert_eq!(remark["message"], "unrolled loop with 3 iteration(s)");
+    assert!(remark["file"].as_str().unwrap().ends_with("main.luax"));
+}
+
+/// Test --opt-remarks rejects unknown formats
+#[test]
+fn test_opt_remarks_invalid_format() {
+    luanext_cmd()
+        .arg("main.luax")
+        .arg("--opt-remarks=yaml")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'yaml'"));
+}
+
+// ============================================================================
+// PASS PIPELINE TESTS
+// ============================================================================
+
+/// Test --print-pass-pipeline lists the passes of the level, grouped by traversal
+#[test]
+fn test_print_pass_pipeline() {
+    let temp_dir = TempDir::new().unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("--optimize")
+        .arg("--print-pass-pipeline")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Optimization level: Aggressive (max 10 iterations)",
+        ))
+        .stdout(predicate::str::contains(
+            "expression-transforms:\n  constant-folding\n  algebraic-simplification\n",
+        ))
+        .stdout(predicate::str::contains("  loop-unrolling\n"))
+        .stdout(predicate::str::contains(
+            "link-time:\n  unused-module-elimination\n  reexport-flattening\n",
+        ));
+}
+
+/// Test optimizer.passes in the config disables, reorders and limits passes
+#[test]
+fn test_configured_pass_pipeline() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = r#"
+optimizer:
+  passes:
+    disable: [loop-unrolling, dead-export-elimination]
+    order: [algebraic-simplification]
+    maxIterations: 3
+"#;
+    fs::write(temp_dir.path().join("luanext.config.yaml"), config).unwrap();
+    fs::write(temp_dir.path().join("main.luax"), UNROLLABLE_LOOP).unwrap();
+
+    let output = luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("--optimize")
+        .arg("--print-pass-pipeline")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("(max 3 iterations)"), "{}", stdout);
+    assert!(
+        stdout.contains("expression-transforms:\n  algebraic-simplification\n  constant-folding\n"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("loop-unrolling"), "{}", stdout);
+    assert!(!stdout.contains("dead-export-elimination"), "{}", stdout);
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("main.luax")
+        .arg("--out-dir")
+        .arg("out")
+        .arg("--optimize")
+        .arg("--opt-remarks")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[loop-unrolling]").not());
+
+    let lua = fs::read_to_string(temp_dir.path().join("out/main.lua")).unwrap();
+    assert!(lua.contains("for"), "{}", lua);
+}
+
+/// Test unknown pass names in optimizer.passes are rejected
+#[test]
+fn test_configured_pass_pipeline_unknown_pass() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = "optimizer:\n  passes:\n    disable: [loop-unroling]\n";
+    fs::write(temp_dir.path().join("luanext.config.yaml"), config).unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("--print-pass-pipeline")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "unknown optimizer pass `loop-unroling`",
+        ));
+}
+
+// ============================================================================
+// SIZE OPTIMIZATION TESTS
+// ============================================================================
+
+const REPEATED_STRINGS: &str = r#"
+function main(): string
+    local first = "connection refused by the remote host"
+    local second = "connection refused by the remote host"
+    return first .. "connection refused by the remote host" .. second
+end
+"#;
+
+/// Test --optimize-size swaps the code-growing passes for the size passes
+/// and keeps the level --optimize selects
+#[test]
+fn test_optimize_size_pass_pipeline() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("--optimize")
+        .arg("--optimize-size")
+        .arg("--print-pass-pipeline")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("Optimization level: Aggressive"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Optimization goal: size"), "{}", stdout);
+    assert!(stdout.contains("  code-outlining\n"), "{}", stdout);
+    assert!(stdout.contains("  identifier-minification\n"), "{}", stdout);
+    assert!(stdout.contains("  devirtualization\n"), "{}", stdout);
+    assert!(!stdout.contains("function-inlining"), "{}", stdout);
+    assert!(!stdout.contains("loop-unrolling"), "{}", stdout);
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("--no-optimize")
+        .arg("--optimize-size")
+        .arg("--print-pass-pipeline")
+        .assert()
+        .failure();
+}
+
+/// Test --optimize-size selects the same pipeline as `optimizer.goal: size`
+#[test]
+fn test_optimize_size_matches_config_goal() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let from_flag = luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("--optimize-size")
+        .arg("--print-pass-pipeline")
+        .output()
+        .unwrap();
+    assert!(from_flag.status.success());
+
+    fs::write(
+        temp_dir.path().join("luanext.config.yaml"),
+        "optimizer:\n  goal: size\n",
+    )
+    .unwrap();
+    let from_config = luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("--print-pass-pipeline")
+        .output()
+        .unwrap();
+    assert!(from_config.status.success());
+
+    assert_eq!(
+        String::from_utf8(from_flag.stdout).unwrap(),
+        String::from_utf8(from_config.stdout).unwrap()
+    );
+}
+
+/// Test --size-report prints the bytes saved per pass
+#[test]
+fn test_size_report() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.luax"), REPEATED_STRINGS).unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("main.luax")
+        .arg("--out-dir")
+        .arg("out")
+        .arg("--optimize-size")
+        .arg("--size-report")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("bytes ("))
+        .stderr(predicate::str::contains("string-constant-sharing"));
+
+    let lua = fs::read_to_string(temp_dir.path().join("out/main.lua")).unwrap();
+    assert_eq!(
+        lua.matches("connection refused by the remote host").count(),
+        1,
+        "{}",
+        lua
+    );
+}
+
+// ============================================================================
+// OPTIMIZER VERIFICATION TESTS
+// ============================================================================
+
+const LOOPS_AND_CLOSURES: &str = r#"
+function total(values: number[]): number
+    local sum = 0
+    for i = 1, #values do
+        if values[i] < 0 then
+            break
+        end
+        local scale = (x) => x * 2
+        sum = sum + scale(values[i])
+    end
+    return sum
+end
+
+print(total({1, 2, 3}))
+"#;
+
+/// Test --verify-optimizer runs every pass with the AST verifier enabled
+#[test]
+fn test_verify_optimizer() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.luax"), LOOPS_AND_CLOSURES).unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("main.luax")
+        .arg("--out-dir")
+        .arg("out")
+        .arg("--optimize")
+        .arg("--verify-optimizer")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("AST verification failed").not());
+
+    assert!(temp_dir.path().join("out/main.lua").exists());
+}
+
+// ============================================================================
+// OPTIMIZER BISECTION TESTS
+// ============================================================================
+
+const AREA: &str = r#"
+local width = 6 * 7
+local height = 2 + 3
+export const area = width * height
+"#;
+
+/// Test bisect-opt refuses a check the unoptimized output already fails
+#[test]
+fn test_bisect_opt_check_fails_unoptimized() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.luax"), AREA).unwrap();
+    fs::write(
+        temp_dir.path().join("check.lua"),
+        "assert(require(\"main\").area == 0)",
+    )
+    .unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("bisect-opt")
+        .arg("main.luax")
+        .arg("--check")
+        .arg("check.lua")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("fails without optimization"));
+}
+
+/// Test bisect-opt reports when the optimized output passes the check
+#[test]
+fn test_bisect_opt_nothing_to_bisect() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.luax"), AREA).unwrap();
+    fs::write(
+        temp_dir.path().join("check.lua"),
+        "assert(require(\"main\").area == 210)",
+    )
+    .unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("bisect-opt")
+        .arg("main.luax")
+        .arg("--check")
+        .arg("check.lua")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("passes with every optimizer pass"));
+}
+
+// ============================================================================
+// PROFILE-GUIDED OPTIMIZATION TESTS
+// ============================================================================
+
+/// Test --profile-generate instruments the output with profile counters
+#[test]
+fn test_profile_generate_instruments_output() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.luax"), UNROLLABLE_LOOP).unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("main.luax")
+        .arg("--out-dir")
+        .arg("out")
+        .arg("--profile-generate")
+        .assert()
+        .success();
+
+    let lua = fs::read_to_string(temp_dir.path().join("out/main.lua")).unwrap();
+    assert!(
+        lua.contains("local __luanext_hit = __LuaNextProfile.counter(\"main.luax\", "),
+        "{}",
+        lua
+    );
+    assert!(lua.contains("luanext.profile\")"), "{}", lua);
+    assert!(lua.contains("__luanext_hit(\"loop 1:1\")"), "{}", lua);
+}
+
+/// Test --profile-use skips loops the profile never saw run
+#[test]
+fn test_profile_use_cold_loop() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.luax"), UNROLLABLE_LOOP).unwrap();
+    fs::write(
+        temp_dir.path().join("cold.profile"),
+        "# luanext profile v1\nmodule\tmain.luax\n",
+    )
+    .unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("main.luax")
+        .arg("--out-dir")
+        .arg("out")
+        .arg("--optimize")
+        .arg("--opt-remarks")
+        .arg("--profile-use")
+        .arg("cold.profile")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "main.luax:1:1: missed [loop-unrolling]: loop never ran in the profile",
+        ));
+
+    let lua = fs::read_to_string(temp_dir.path().join("out/main.lua")).unwrap();
+    assert!(lua.contains("for i = 1, 3"), "{}", lua);
+}
+
+/// Test --profile-use fails on an unreadable profile
+#[test]
+fn test_profile_use_invalid_profile() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.luax"), UNROLLABLE_LOOP).unwrap();
+    fs::write(temp_dir.path().join("bad.profile"), "loop 1:1\t3\n").unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("main.luax")
+        .arg("--optimize")
+        .arg("--profile-use")
+        .arg("bad.profile")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Cannot read profile bad.profile"));
+}
+
+/// Test --profile-generate and --profile-use cannot be combined
+#[test]
+fn test_profile_generate_conflicts_with_profile_use() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.luax"), UNROLLABLE_LOOP).unwrap();
+
+    luanext_cmd()
+        .current_dir(&temp_dir)
+        .arg("main.luax")
+        .arg("--profile-generate")
+        .arg("--profile-use")
+        .arg("luanext.profile")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
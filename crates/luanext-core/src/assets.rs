@@ -0,0 +1,281 @@
+//! JSON, YAML and text assets imported as modules.
+//!
+//! ```text
+//! import config from "./config.json"
+//! import { port } from "./server.yaml"
+//! import banner from "./banner.txt"
+//! ```
+//!
+//! An asset is parsed into a [`serde_json::Value`] and used in two ways:
+//!
+//! - For type checking it becomes a synthesized LuaNext module
+//!   ([`Asset::to_luanext_module`]). The module exports the data as its
+//!   default export, plus one named export per top-level key that is a valid
+//!   identifier. Its type is inferred from the data ([`Asset::type_annotation`]).
+//! - For code generation it becomes a Lua table (or string) constructor
+//!   ([`Asset::to_lua_expression`]), emitted once as the body of the asset's
+//!   module: a Lua file of its own in require mode ([`Asset::to_lua_module`]),
+//!   a bundle module otherwise. Every import site requires that module, so all
+//!   importers share one copy of the data.
+
+use serde_json::Value;
+use std::path::Path;
+use thiserror::Error;
+
+/// Lua expression of each asset, keyed by bundle module ID
+pub type AssetModules = std::collections::HashMap<String, String>;
+
+/// Name of the binding holding the data in a synthesized asset module
+const ASSET_BINDING: &str = "__asset";
+
+/// Kind of asset, determined by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Json,
+    Yaml,
+    Text,
+}
+
+impl AssetKind {
+    /// Asset kind for a path or import source, if it names an asset
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(AssetKind::Json),
+            "yaml" | "yml" => Some(AssetKind::Yaml),
+            "txt" => Some(AssetKind::Text),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an import source or file path refers to an asset
+pub fn is_asset_path(path: impl AsRef<Path>) -> bool {
+    AssetKind::from_path(path.as_ref()).is_some()
+}
+
+#[derive(Debug, Error)]
+pub enum AssetError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+/// A parsed asset
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    pub kind: AssetKind,
+    pub value: Value,
+}
+
+impl Asset {
+    /// Parse asset contents. Text assets are kept verbatim as a string.
+    pub fn parse(kind: AssetKind, contents: &str) -> Result<Self, AssetError> {
+        let value = match kind {
+            AssetKind::Json => serde_json::from_str(contents)?,
+            AssetKind::Yaml => serde_yaml::from_str(contents)?,
+            AssetKind::Text => Value::String(contents.to_string()),
+        };
+        Ok(Asset { kind, value })
+    }
+
+    /// LuaNext type of the asset data
+    pub fn type_annotation(&self) -> String {
+        infer_type(&self.value)
+    }
+
+    /// Synthesized LuaNext source of the asset module
+    pub fn to_luanext_module(&self) -> String {
+        let mut source = format!(
+            "const {}: {} = {}\n",
+            ASSET_BINDING,
+            self.type_annotation(),
+            luanext_literal(&self.value)
+        );
+        if let Value::Object(map) = &self.value {
+            for key in map.keys().filter(|key| is_identifier(key)) {
+                source.push_str(&format!(
+                    "export const {} = {}.{}\n",
+                    key, ASSET_BINDING, key
+                ));
+            }
+        }
+        source.push_str(&format!("export default {}\n", ASSET_BINDING));
+        source
+    }
+
+    /// Lua constructor evaluating to the asset data
+    pub fn to_lua_expression(&self) -> String {
+        lua_literal(&self.value)
+    }
+
+    /// Lua source of the asset module written in require mode
+    pub fn to_lua_module(&self) -> String {
+        format!("return {}\n", self.to_lua_expression())
+    }
+}
+
+/// Infer the LuaNext type of a JSON value
+fn infer_type(value: &Value) -> String {
+    match value {
+        Value::Null => "nil".to_string(),
+        Value::Bool(_) => "boolean".to_string(),
+        Value::Number(_) => "number".to_string(),
+        Value::String(_) => "string".to_string(),
+        Value::Array(items) => {
+            if items.is_empty() {
+                "Array<unknown>".to_string()
+            } else {
+                format!("Array<{}>", union_of(items.iter()))
+            }
+        }
+        Value::Object(map) => {
+            if map.is_empty() {
+                "table".to_string()
+            } else if map.keys().all(|key| is_identifier(key)) {
+                let fields: Vec<String> = map
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, infer_type(value)))
+                    .collect();
+                format!("{{ {} }}", fields.join(", "))
+            } else {
+                format!("{{ [key: string]: {} }}", union_of(map.values()))
+            }
+        }
+    }
+}
+
+/// Union of the distinct types of `values`, in first-seen order
+fn union_of<'a>(values: impl Iterator<Item = &'a Value>) -> String {
+    let mut types: Vec<String> = Vec::new();
+    for value in values {
+        let ty = infer_type(value);
+        if !types.contains(&ty) {
+            types.push(ty);
+        }
+    }
+    types.join(" | ")
+}
+
+/// LuaNext expression for a JSON value
+fn luanext_literal(value: &Value) -> String {
+    match value {
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(luanext_literal).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) => table_constructor(map, luanext_literal),
+        scalar => lua_literal(scalar),
+    }
+}
+
+/// Lua expression for a JSON value
+fn lua_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "nil".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => quote_string(s),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(lua_literal).collect();
+            format!("{{{}}}", items.join(", "))
+        }
+        Value::Object(map) => table_constructor(map, lua_literal),
+    }
+}
+
+fn table_constructor(map: &serde_json::Map<String, Value>, emit: fn(&Value) -> String) -> String {
+    if map.is_empty() {
+        return "{}".to_string();
+    }
+    let fields: Vec<String> = map
+        .iter()
+        .map(|(key, value)| {
+            if is_identifier(key) {
+                format!("{} = {}", key, emit(value))
+            } else {
+                format!("[{}] = {}", quote_string(key), emit(value))
+            }
+        })
+        .collect();
+    format!("{{ {} }}", fields.join(", "))
+}
+
+/// Double-quoted string literal, valid in both Lua and LuaNext
+fn quote_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() && (c as u32) < 256 => {
+                out.push_str(&format!("\\{:03}", c as u32));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Keywords of Lua and LuaNext that cannot be used as table field names
+const KEYWORDS: &[&str] = &[
+    "and",
+    "break",
+    "do",
+    "else",
+    "elseif",
+    "end",
+    "false",
+    "for",
+    "function",
+    "goto",
+    "if",
+    "in",
+    "local",
+    "nil",
+    "not",
+    "or",
+    "repeat",
+    "return",
+    "then",
+    "true",
+    "until",
+    "while",
+    "const",
+    "class",
+    "interface",
+    "type",
+    "enum",
+    "import",
+    "export",
+    "default",
+    "from",
+    "as",
+    "new",
+    "extends",
+    "implements",
+    "namespace",
+    "declare",
+    "match",
+    "throw",
+    "try",
+    "catch",
+    "continue",
+];
+
+/// Whether `key` can be written as a bare field name
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !KEYWORDS.contains(&key)
+}
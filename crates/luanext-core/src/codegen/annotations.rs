@@ -0,0 +1,420 @@
+//! LuaLS annotation comments for generated Lua.
+//!
+//! When enabled, declarations are preceded by `---@class`, `---@field`,
+//! `---@param`, `---@return`, `---@enum` and `---@alias` comments so that
+//! plain Lua consumers using lua-language-server keep the type information.
+//!
+//! | LuaNext                  | LuaLS                      |
+//! |--------------------------|----------------------------|
+//! | `unknown`, `never`       | `any`                      |
+//! | `void`                   | `nil` (omitted as return)  |
+//! | `coroutine`              | `thread`                   |
+//! | `T?`                     | `T?`                       |
+//! | `T[]`, `[A, B]`          | `T[]`, `[A, B]`            |
+//! | `(a: T, ...b: U[]) -> R` | `fun(a: T, ...: U): R`     |
+//! | `{ [key: string]: V }`   | `{ [string]: V }`          |
+//! | `A & B`, `keyof T`, ...  | `any`                      |
+
+use super::CodeGenerator;
+use luanext_parser::ast::expression::Literal;
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::*;
+use luanext_parser::ast::types::{ObjectTypeMember, PrimitiveType, Type, TypeKind};
+
+impl CodeGenerator {
+    /// Annotations are comments, so minified output (no newlines) cannot carry them
+    fn annotations_enabled(&self) -> bool {
+        self.emit_annotations && !self.emitter.is_minified()
+    }
+
+    fn write_annotation(&mut self, annotation: &str) {
+        self.write_indent();
+        self.write("---@");
+        self.writeln(annotation);
+    }
+
+    /// `---@class` and `---@field` lines for a class
+    pub(super) fn annotate_class(&mut self, class_decl: &ClassDeclaration, class_name: &str) {
+        if !self.annotations_enabled() {
+            return;
+        }
+
+        let mut header = format!(
+            "class {}{}",
+            class_name,
+            self.type_parameter_list(class_decl.type_parameters)
+        );
+        if let Some(extends) = &class_decl.extends {
+            header.push_str(": ");
+            header.push_str(&self.lua_ls_type(extends));
+        }
+        self.write_annotation(&header);
+
+        for param in class_decl.primary_constructor.unwrap_or_default() {
+            let name = self.resolve(param.name.node);
+            // Private primary-constructor fields are stored as `self._name`
+            let field = match param.access {
+                Some(AccessModifier::Private) => format!("private _{}", name),
+                Some(AccessModifier::Protected) => format!("protected {}", name),
+                _ => name,
+            };
+            let ty = self.lua_ls_type(&param.type_annotation);
+            self.write_annotation(&format!("field {} {}", field, ty));
+        }
+        for member in class_decl.members.iter() {
+            if let ClassMember::Property(prop) = member {
+                let scope = match prop.access {
+                    Some(AccessModifier::Private) => "private ",
+                    Some(AccessModifier::Protected) => "protected ",
+                    _ => "",
+                };
+                let name = self.resolve(prop.name.node);
+                let ty = self.lua_ls_type(&prop.type_annotation);
+                self.write_annotation(&format!("field {}{} {}", scope, name, ty));
+            }
+        }
+    }
+
+    /// `---@param`/`---@return` lines for a function, method or constructor
+    pub(super) fn annotate_function(
+        &mut self,
+        type_parameters: Option<&[TypeParameter]>,
+        parameters: &[Parameter],
+        return_type: Option<&Type>,
+    ) {
+        if !self.annotations_enabled() {
+            return;
+        }
+
+        for type_param in type_parameters.unwrap_or_default() {
+            let name = self.resolve(type_param.name.node);
+            match type_param.constraint {
+                Some(constraint) => {
+                    let bound = self.lua_ls_type(constraint);
+                    self.write_annotation(&format!("generic {}: {}", name, bound));
+                }
+                None => self.write_annotation(&format!("generic {}", name)),
+            }
+        }
+
+        self.annotate_parameters(parameters);
+        if let Some(ret) = return_type.and_then(|ty| self.return_annotation(ty)) {
+            self.write_annotation(&format!("return {}", ret));
+        }
+    }
+
+    /// `---@param` lines and `---@return Class` for a class's `new`
+    pub(super) fn annotate_constructor(&mut self, parameters: &[Parameter], class_name: &str) {
+        if !self.annotations_enabled() {
+            return;
+        }
+
+        self.annotate_parameters(parameters);
+        self.write_annotation(&format!("return {}", class_name));
+    }
+
+    /// `---@param` lines and `---@return Class` for a primary constructor's `new`
+    pub(super) fn annotate_primary_constructor(
+        &mut self,
+        parameters: &[ConstructorParameter],
+        class_name: &str,
+    ) {
+        if !self.annotations_enabled() {
+            return;
+        }
+
+        for param in parameters {
+            let name = self.resolve(param.name.node);
+            let optional = if param.default.is_some() { "?" } else { "" };
+            let ty = self.lua_ls_type(&param.type_annotation);
+            self.write_annotation(&format!("param {}{} {}", name, optional, ty));
+        }
+        self.write_annotation(&format!("return {}", class_name));
+    }
+
+    fn annotate_parameters(&mut self, parameters: &[Parameter]) {
+        for param in parameters {
+            let name = match &param.pattern {
+                _ if param.is_rest => "...".to_string(),
+                Pattern::Identifier(ident) => self.resolve(ident.node),
+                // Destructured parameters have no name to attach a type to
+                _ => continue,
+            };
+            let ty = match (&param.type_annotation, param.is_rest) {
+                (Some(ty), true) => self.rest_element_type(ty),
+                (Some(ty), false) => self.lua_ls_type(ty),
+                (None, _) => "any".to_string(),
+            };
+            let optional = if !param.is_rest && (param.is_optional || param.default.is_some()) {
+                "?"
+            } else {
+                ""
+            };
+            self.write_annotation(&format!("param {}{} {}", name, optional, ty));
+        }
+    }
+
+    /// `---@enum` for plain enums, `---@class` with fields for rich enums
+    pub(super) fn annotate_enum(&mut self, enum_decl: &EnumDeclaration, enum_name: &str) {
+        if !self.annotations_enabled() {
+            return;
+        }
+
+        if enum_decl.fields.is_empty()
+            && enum_decl.constructor.is_none()
+            && enum_decl.methods.is_empty()
+        {
+            self.write_annotation(&format!("enum {}", enum_name));
+            return;
+        }
+
+        self.write_annotation(&format!("class {}", enum_name));
+        for field in enum_decl.fields.iter() {
+            let name = self.resolve(field.name.node);
+            let ty = self.lua_ls_type(&field.type_annotation);
+            self.write_annotation(&format!("field {} {}", name, ty));
+        }
+        for member in enum_decl.members.iter() {
+            let name = self.resolve(member.name.node);
+            self.write_annotation(&format!("field {} {}", name, enum_name));
+        }
+    }
+
+    /// Interfaces emit no Lua, so their annotation block stands on its own
+    pub(super) fn annotate_interface(&mut self, iface_decl: &InterfaceDeclaration) {
+        if !self.annotations_enabled() {
+            return;
+        }
+
+        let name = self.resolve(iface_decl.name.node);
+        let mut header = format!(
+            "class {}{}",
+            name,
+            self.type_parameter_list(iface_decl.type_parameters)
+        );
+        if !iface_decl.extends.is_empty() {
+            let parents: Vec<String> = iface_decl
+                .extends
+                .iter()
+                .map(|ty| self.lua_ls_type(ty))
+                .collect();
+            header.push_str(": ");
+            header.push_str(&parents.join(", "));
+        }
+
+        self.writeln("");
+        self.write_annotation(&header);
+        for member in iface_decl.members.iter() {
+            let field = match member {
+                InterfaceMember::Property(prop) => format!(
+                    "{}{} {}",
+                    self.resolve(prop.name.node),
+                    if prop.is_optional { "?" } else { "" },
+                    self.lua_ls_type(&prop.type_annotation)
+                ),
+                InterfaceMember::Method(method) => format!(
+                    "{} {}",
+                    self.resolve(method.name.node),
+                    self.function_type(
+                        Some(name.as_str()),
+                        method.parameters,
+                        Some(&method.return_type)
+                    )
+                ),
+                InterfaceMember::Index(index) => format!(
+                    "[{}] {}",
+                    index_key_type(&index.key_type),
+                    self.lua_ls_type(&index.value_type)
+                ),
+            };
+            self.write_annotation(&format!("field {}", field));
+        }
+        self.writeln("");
+    }
+
+    /// Type aliases are erased, leaving only the `---@alias` line
+    pub(super) fn annotate_type_alias(&mut self, alias: &TypeAliasDeclaration) {
+        if !self.annotations_enabled() {
+            return;
+        }
+
+        let name = self.resolve(alias.name.node);
+        let params = self.type_parameter_list(alias.type_parameters);
+        let ty = self.lua_ls_type(&alias.type_annotation);
+        self.write_annotation(&format!("alias {}{} {}", name, params, ty));
+        self.writeln("");
+    }
+
+    /// Convert a LuaNext type to LuaLS annotation syntax
+    pub(super) fn lua_ls_type(&self, ty: &Type) -> String {
+        match &ty.kind {
+            TypeKind::Primitive(primitive) => match primitive {
+                PrimitiveType::Nil | PrimitiveType::Void => "nil",
+                PrimitiveType::Boolean => "boolean",
+                PrimitiveType::Number => "number",
+                PrimitiveType::Integer => "integer",
+                PrimitiveType::String => "string",
+                PrimitiveType::Table => "table",
+                PrimitiveType::Coroutine | PrimitiveType::Thread => "thread",
+                PrimitiveType::Unknown | PrimitiveType::Never => "any",
+            }
+            .to_string(),
+            TypeKind::Reference(type_ref) => {
+                let name = self.resolve(type_ref.name.node);
+                match type_ref.type_arguments {
+                    Some(args) if !args.is_empty() => {
+                        let args: Vec<String> = args.iter().map(|t| self.lua_ls_type(t)).collect();
+                        format!("{}<{}>", name, args.join(", "))
+                    }
+                    _ => name,
+                }
+            }
+            TypeKind::Union(members) => members
+                .iter()
+                .map(|t| self.lua_ls_type(t))
+                .collect::<Vec<_>>()
+                .join(" | "),
+            TypeKind::Nullable(inner) => format!("{}?", wrap(self.lua_ls_type(inner))),
+            TypeKind::Array(inner) => format!("{}[]", wrap(self.lua_ls_type(inner))),
+            TypeKind::Tuple(elements) => format!(
+                "[{}]",
+                elements
+                    .iter()
+                    .map(|t| self.lua_ls_type(t))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeKind::Function(func) => {
+                self.function_type(None, func.parameters, Some(func.return_type))
+            }
+            TypeKind::Literal(literal) => match literal {
+                Literal::Nil => "nil".to_string(),
+                Literal::Boolean(b) => b.to_string(),
+                Literal::Number(n) => n.to_string(),
+                Literal::Integer(i) => i.to_string(),
+                Literal::String(s) => format!("\"{}\"", s.replace('"', "\\\"")),
+            },
+            TypeKind::Object(obj) => {
+                let fields: Vec<String> = obj
+                    .members
+                    .iter()
+                    .map(|member| match member {
+                        ObjectTypeMember::Property(prop) => format!(
+                            "{}{}: {}",
+                            self.resolve(prop.name.node),
+                            if prop.is_optional { "?" } else { "" },
+                            self.lua_ls_type(&prop.type_annotation)
+                        ),
+                        ObjectTypeMember::Method(method) => format!(
+                            "{}: {}",
+                            self.resolve(method.name.node),
+                            self.function_type(None, method.parameters, Some(&method.return_type))
+                        ),
+                        ObjectTypeMember::Index(index) => format!(
+                            "[{}]: {}",
+                            index_key_type(&index.key_type),
+                            self.lua_ls_type(&index.value_type)
+                        ),
+                    })
+                    .collect();
+                if fields.is_empty() {
+                    "table".to_string()
+                } else {
+                    format!("{{ {} }}", fields.join(", "))
+                }
+            }
+            TypeKind::Parenthesized(inner) | TypeKind::Variadic(inner) => self.lua_ls_type(inner),
+            TypeKind::Namespace(parts) => parts.join("."),
+            TypeKind::TemplateLiteral(_) => "string".to_string(),
+            TypeKind::TypePredicate(_) => "boolean".to_string(),
+            // No LuaLS counterpart
+            TypeKind::Intersection(_)
+            | TypeKind::TypeQuery(_)
+            | TypeKind::KeyOf(_)
+            | TypeKind::IndexAccess(_, _)
+            | TypeKind::Conditional(_)
+            | TypeKind::Mapped(_)
+            | TypeKind::Infer(_) => "any".to_string(),
+        }
+    }
+
+    /// `fun(a: T, ...: U): R`; `self_type` adds the implicit `self` of a method
+    fn function_type(
+        &self,
+        self_type: Option<&str>,
+        parameters: &[Parameter],
+        return_type: Option<&Type>,
+    ) -> String {
+        let mut params: Vec<String> = self_type
+            .map(|ty| format!("self: {}", ty))
+            .into_iter()
+            .collect();
+        for (i, param) in parameters.iter().enumerate() {
+            let name = match &param.pattern {
+                _ if param.is_rest => "...".to_string(),
+                Pattern::Identifier(ident) => self.resolve(ident.node),
+                _ => format!("arg{}", i + 1),
+            };
+            let optional = if param.is_optional && !param.is_rest {
+                "?"
+            } else {
+                ""
+            };
+            let ty = match &param.type_annotation {
+                Some(ty) if param.is_rest => self.rest_element_type(ty),
+                Some(ty) => self.lua_ls_type(ty),
+                None => "any".to_string(),
+            };
+            params.push(format!("{}{}: {}", name, optional, ty));
+        }
+
+        match return_type.and_then(|ty| self.return_annotation(ty)) {
+            Some(ret) => format!("fun({}): {}", params.join(", "), ret),
+            None => format!("fun({})", params.join(", ")),
+        }
+    }
+
+    /// Return type text, `None` for `void`; variadic returns become `T ...`
+    fn return_annotation(&self, ty: &Type) -> Option<String> {
+        match &ty.kind {
+            TypeKind::Primitive(PrimitiveType::Void) => None,
+            TypeKind::Variadic(inner) => Some(format!("{} ...", self.rest_element_type(inner))),
+            _ => Some(self.lua_ls_type(ty)),
+        }
+    }
+
+    /// Element type of a rest parameter or variadic return (`...args: T[]`)
+    fn rest_element_type(&self, ty: &Type) -> String {
+        match &ty.kind {
+            TypeKind::Array(element) => self.lua_ls_type(element),
+            _ => self.lua_ls_type(ty),
+        }
+    }
+
+    /// `<T, U>` for LuaLS generic class and alias names
+    fn type_parameter_list(&self, type_parameters: Option<&[TypeParameter]>) -> String {
+        match type_parameters {
+            Some(params) if !params.is_empty() => {
+                let names: Vec<String> = params.iter().map(|p| self.resolve(p.name.node)).collect();
+                format!("<{}>", names.join(", "))
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+fn index_key_type(key_type: &IndexKeyType) -> &'static str {
+    match key_type {
+        IndexKeyType::String => "string",
+        IndexKeyType::Number => "number",
+    }
+}
+
+/// Parenthesize a union or function type before a postfix `?` or `[]`
+fn wrap(ty: String) -> String {
+    if ty.contains(" | ") || ty.starts_with("fun(") {
+        format!("({})", ty)
+    } else {
+        ty
+    }
+}
@@ -40,6 +40,7 @@ use crate::optimizer::WholeProgramAnalysis;
 /// - `source_map`: Enable source map generation with a source file name
 /// - `mode`: Code generation mode - Require or Bundle (defaults to Require)
 /// - `optimization_level`: Optimization level O0-O3 (defaults to O0)
+/// - `annotations`: Emit LuaLS annotations for plain Lua consumers (defaults to off)
 ///
 /// # Example
 ///
@@ -62,6 +63,8 @@ pub struct CodeGeneratorBuilder {
     reachable_exports: Option<std::collections::HashSet<String>>,
     reflection_mode: ReflectionMode,
     alias_require_map: std::collections::HashMap<String, String>,
+    annotations: bool,
+    profile_instrumentation: Option<(String, String)>,
 }
 
 impl CodeGeneratorBuilder {
@@ -99,6 +102,8 @@ impl CodeGeneratorBuilder {
             reachable_exports: None,
             reflection_mode: ReflectionMode::default(),
             alias_require_map: Default::default(),
+            annotations: false,
+            profile_instrumentation: None,
         }
     }
 
@@ -291,6 +296,25 @@ impl CodeGeneratorBuilder {
         self
     }
 
+    /// Enables LuaLS annotations (`---@class`, `---@param`, ...) in the output.
+    ///
+    /// Lets plain Lua consumers of compiled libraries keep type information
+    /// in editors using lua-language-server. Ignored for minified output.
+    pub fn annotations(mut self, enabled: bool) -> Self {
+        self.annotations = enabled;
+        self
+    }
+
+    /// Embeds the profile counter runtime for `--profile-generate` builds.
+    ///
+    /// Counts recorded under `module_id` are written to `path` when the
+    /// program exits. The program must also be instrumented with
+    /// [`crate::optimizer::profile::instrument`].
+    pub fn profile_instrumentation(mut self, module_id: String, path: String) -> Self {
+        self.profile_instrumentation = Some((module_id, path));
+        self
+    }
+
     /// Sets the reachable exports for tree shaking in bundle mode.
     ///
     /// When tree shaking is enabled, exports not in this set will be skipped
@@ -352,6 +376,7 @@ impl CodeGeneratorBuilder {
         generator = generator.with_optimization_level(self.optimization_level);
         generator = generator.with_output_format(self.output_format);
         generator = generator.with_reflection_mode(self.reflection_mode);
+        generator = generator.with_annotations(self.annotations);
 
         if let Some(source_file) = self.source_map {
             generator = generator.with_source_map(source_file);
@@ -369,6 +394,10 @@ impl CodeGeneratorBuilder {
             generator = generator.with_alias_require_map(self.alias_require_map);
         }
 
+        if let Some((module_id, path)) = self.profile_instrumentation {
+            generator = generator.with_profile_instrumentation(module_id, path);
+        }
+
         generator
     }
 }
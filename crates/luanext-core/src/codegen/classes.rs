@@ -26,6 +26,7 @@ impl CodeGenerator {
 
         self.current_class_parent = base_class_name;
 
+        self.annotate_class(class_decl, class_name);
         self.write_indent();
         // Skip 'local' if this class was already forward-declared
         if !self.forward_declared_classes.contains(class_name) {
@@ -69,6 +70,7 @@ impl CodeGenerator {
         } else {
             // Generate default constructor
             self.writeln("");
+            self.annotate_constructor(&[], class_name);
             self.write_indent();
             self.write("function ");
             self.write(class_name);
@@ -160,7 +162,14 @@ impl CodeGenerator {
         if !class_decl.decorators.is_empty() {
             self.writeln("");
             for decorator in class_decl.decorators.iter() {
+                if self.is_optimization_hint(&decorator.expression) {
+                    continue;
+                }
                 self.write_indent();
+                if self.is_serializable_decorator(&decorator.expression) {
+                    self.writeln(&format!("__LuaNextJSON.serializable({})", class_name));
+                    continue;
+                }
                 self.write(class_name);
                 self.write(" = ");
                 self.generate_decorator_call(decorator, class_name);
@@ -350,10 +359,13 @@ impl CodeGenerator {
         }
 
         // -- Reflection metadata (gated on reflection mode) --
-        if self.should_emit_reflection() {
-            self.registered_types
-                .insert(class_name.to_string(), type_id);
+        // @serializable classes always carry field metadata for @std/json
+        let emit_reflection = self.should_emit_reflection();
+        if emit_reflection {
+            self.register_class_type(class_decl, class_name, type_id);
+        }
 
+        if emit_reflection || self.is_serializable_class(class_decl) {
             // __ownFields with v2 bit flags
             self.write_indent();
             self.write(class_name);
@@ -375,6 +387,20 @@ impl CodeGenerator {
             self.write_indent();
             self.writeln("}");
 
+            self.writeln("");
+            self.writeln(
+                &luanext_runtime::class::BUILD_ALL_FIELDS
+                    .replace("{}", class_name)
+                    .replace("{}", class_name)
+                    .replace("{}", class_name)
+                    .replace("{}", class_name)
+                    .replace("{}", class_name)
+                    .replace("{}", class_name)
+                    .replace("{}", class_name),
+            );
+        }
+
+        if emit_reflection {
             // __ownMethods with compact signatures
             self.write_indent();
             self.write(class_name);
@@ -409,17 +435,6 @@ impl CodeGenerator {
             self.write_indent();
             self.writeln("}");
 
-            self.writeln("");
-            self.writeln(
-                &luanext_runtime::class::BUILD_ALL_FIELDS
-                    .replace("{}", class_name)
-                    .replace("{}", class_name)
-                    .replace("{}", class_name)
-                    .replace("{}", class_name)
-                    .replace("{}", class_name)
-                    .replace("{}", class_name)
-                    .replace("{}", class_name),
-            );
             self.writeln("");
             self.writeln(
                 &luanext_runtime::class::BUILD_ALL_METHODS
@@ -457,7 +472,8 @@ impl CodeGenerator {
 
     /// Encode a type annotation as a compact type code string per v2 reflection spec.
     /// n=number, s=string, b=boolean, t=table, f=function, v=void, o=any/unknown
-    fn encode_type_code(&self, ty: &parser::ast::types::Type) -> String {
+    /// Generic references encode their arguments (`Box<n>`) and are registered as instantiations.
+    pub(super) fn encode_type_code(&mut self, ty: &parser::ast::types::Type) -> String {
         use parser::ast::types::{PrimitiveType, TypeKind};
         match &ty.kind {
             TypeKind::Primitive(p) => match p {
@@ -476,13 +492,27 @@ impl CodeGenerator {
                 .map(|t| self.encode_type_code(t))
                 .collect::<Vec<_>>()
                 .join("|"),
-            TypeKind::Reference(type_ref) => self.resolve(type_ref.name.node),
+            TypeKind::Reference(type_ref) => {
+                let name = self.resolve(type_ref.name.node);
+                match type_ref.type_arguments.filter(|args| !args.is_empty()) {
+                    Some(args) => {
+                        let codes: Vec<String> =
+                            args.iter().map(|arg| self.encode_type_code(arg)).collect();
+                        let code = format!("{}<{}>", name, codes.join(","));
+                        self.register_generic_instantiation(&code, &name, &codes);
+                        code
+                    }
+                    None => name,
+                }
+            }
             _ => "o".to_string(),
         }
     }
 
     pub fn generate_interface_declaration(&mut self, iface_decl: &InterfaceDeclaration) {
         let interface_name = self.resolve(iface_decl.name.node).to_string();
+        self.annotate_interface(iface_decl);
+        self.reflect_interface(iface_decl);
 
         // Record interface member names for assertType structural checks
         let member_names: Vec<String> = iface_decl
@@ -576,6 +606,7 @@ impl CodeGenerator {
             self.writeln("end");
 
             self.writeln("");
+            self.annotate_constructor(ctor.parameters, class_name);
             self.write_indent();
             self.write("function ");
             self.write(class_name);
@@ -629,6 +660,7 @@ impl CodeGenerator {
             self.writeln("end");
         } else {
             self.writeln("");
+            self.annotate_constructor(ctor.parameters, class_name);
             self.write_indent();
             self.write("function ");
             self.write(class_name);
@@ -737,6 +769,7 @@ impl CodeGenerator {
         self.writeln("end");
 
         self.writeln("");
+        self.annotate_primary_constructor(primary_params, class_name);
         self.write_indent();
         self.write("function ");
         self.write(class_name);
@@ -798,6 +831,11 @@ impl CodeGenerator {
         }
 
         self.writeln("");
+        self.annotate_function(
+            method.type_parameters,
+            method.parameters,
+            method.return_type.as_ref(),
+        );
         self.write_indent();
         self.write("function ");
         self.write(class_name);
@@ -831,6 +869,9 @@ impl CodeGenerator {
 
         if !method.decorators.is_empty() {
             for decorator in method.decorators.iter() {
+                if self.is_optimization_hint(&decorator.expression) {
+                    continue;
+                }
                 self.write_indent();
                 self.write(class_name);
                 self.write(".");
@@ -924,6 +965,9 @@ impl CodeGenerator {
         self.writeln("end");
 
         for decorator in op.decorators.iter() {
+            if self.is_optimization_hint(&decorator.expression) {
+                continue;
+            }
             self.write_indent();
             self.write(class_name);
             self.write(".");
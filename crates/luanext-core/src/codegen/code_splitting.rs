@@ -0,0 +1,811 @@
+//! Code splitting for bundle mode.
+//!
+//! A dynamic `import("./feature")` expression marks a split point. Modules that
+//! are only reachable through split points are moved out of the main bundle into
+//! chunk files that the bundle loads on first use:
+//!
+//! - The **main chunk** holds the entry module and its static import closure.
+//! - Each dynamically imported module gets its own **chunk** holding its static
+//!   closure, minus anything already in the main chunk.
+//! - Modules needed by more than one chunk go into a single **common chunk**
+//!   so they are loaded (and initialized) exactly once.
+//!
+//! Tree shaking and scope hoisting run per chunk: every dynamic import target
+//! is treated as an entry point for reachability, and hoisted declarations are
+//! local to the file they are emitted into.
+
+use super::scope_hoisting::HoistingContext;
+use super::tree_shaking::{ReachabilityAnalysis, ReachableSet};
+use super::{CodeGenMode, CodeGenerator, LuaTarget};
+use crate::assets::AssetModules;
+use luanext_parser::ast::expression::{
+    ArrayElement, ArrowBody, Expression, ExpressionKind, Literal, MatchArmBody, ObjectProperty,
+    TemplatePart,
+};
+use luanext_parser::ast::statement::{
+    ClassMember, ExportKind, ForStatement, ImportClause, Statement,
+};
+use luanext_parser::ast::Program;
+use luanext_parser::string_interner::StringInterner;
+use luanext_runtime::module;
+use rustc_hash::FxHashMap as HashMap;
+use rustc_hash::FxHashSet as HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Name of the chunk holding modules shared between several dynamic imports
+pub const COMMON_CHUNK_NAME: &str = "chunk_common";
+
+/// Bundle input: (module_id, program, import_map), as taken by `generate_bundle`
+pub type BundleModule<'a, 'arena> = (
+    String,
+    &'a Program<'arena>,
+    std::collections::HashMap<String, String>,
+);
+
+/// Return the module specifier of a dynamic `import("...")` call, if `expr` is one.
+///
+/// Only string-literal specifiers are split points; anything else is left to
+/// regular call generation.
+pub fn dynamic_import_source<'e>(
+    expr: &'e Expression,
+    interner: &StringInterner,
+) -> Option<&'e str> {
+    let ExpressionKind::Call(callee, args, _) = &expr.kind else {
+        return None;
+    };
+    let ExpressionKind::Identifier(name) = &callee.kind else {
+        return None;
+    };
+    if interner.resolve(*name) != "import" || args.len() != 1 || args[0].is_spread {
+        return None;
+    }
+    match &args[0].value.kind {
+        ExpressionKind::Literal(Literal::String(source)) => Some(source.as_str()),
+        _ => None,
+    }
+}
+
+/// Collect the specifiers of all dynamic imports in a module, in source order.
+pub fn collect_dynamic_imports(statements: &[Statement], interner: &StringInterner) -> Vec<String> {
+    let mut sources: Vec<String> = Vec::new();
+    walk_expressions(statements, |expr| {
+        if let Some(source) = dynamic_import_source(expr, interner) {
+            if !sources.iter().any(|s| s == source) {
+                sources.push(source.to_string());
+            }
+        }
+    });
+    sources
+}
+
+/// Call `visit` on every expression in `statements`, outer expressions before
+/// the expressions nested in them, including those in function bodies
+pub(super) fn walk_expressions(statements: &[Statement], visit: impl FnMut(&Expression)) {
+    ExpressionWalker { visit }.walk_statements(statements);
+}
+
+struct ExpressionWalker<F> {
+    visit: F,
+}
+
+impl<F: FnMut(&Expression)> ExpressionWalker<F> {
+    fn walk_statements(&mut self, statements: &[Statement]) {
+        for stmt in statements {
+            self.walk_statement(stmt);
+        }
+    }
+
+    fn walk_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Variable(decl) => self.walk_expression(&decl.initializer),
+            Statement::Function(func) => self.walk_statements(func.body.statements),
+            Statement::Expression(expr) => self.walk_expression(expr),
+            Statement::If(if_stmt) => {
+                self.walk_expression(&if_stmt.condition);
+                self.walk_statements(if_stmt.then_block.statements);
+                for else_if in if_stmt.else_ifs.iter() {
+                    self.walk_expression(&else_if.condition);
+                    self.walk_statements(else_if.block.statements);
+                }
+                if let Some(else_block) = &if_stmt.else_block {
+                    self.walk_statements(else_block.statements);
+                }
+            }
+            Statement::While(while_stmt) => {
+                self.walk_expression(&while_stmt.condition);
+                self.walk_statements(while_stmt.body.statements);
+            }
+            Statement::For(for_stmt) => match &**for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    self.walk_expression(&for_num.start);
+                    self.walk_expression(&for_num.end);
+                    if let Some(step) = &for_num.step {
+                        self.walk_expression(step);
+                    }
+                    self.walk_statements(for_num.body.statements);
+                }
+                ForStatement::Generic(for_gen) => {
+                    for iter in for_gen.iterators.iter() {
+                        self.walk_expression(iter);
+                    }
+                    self.walk_statements(for_gen.body.statements);
+                }
+            },
+            Statement::Repeat(repeat_stmt) => {
+                self.walk_statements(repeat_stmt.body.statements);
+                self.walk_expression(&repeat_stmt.until);
+            }
+            Statement::Return(ret) => {
+                for value in ret.values.iter() {
+                    self.walk_expression(value);
+                }
+            }
+            Statement::Block(block) => self.walk_statements(block.statements),
+            Statement::Try(try_stmt) => {
+                self.walk_statements(try_stmt.try_block.statements);
+                for catch_clause in try_stmt.catch_clauses.iter() {
+                    self.walk_statements(catch_clause.body.statements);
+                }
+                if let Some(finally_block) = &try_stmt.finally_block {
+                    self.walk_statements(finally_block.statements);
+                }
+            }
+            Statement::Class(class_decl) => {
+                for member in class_decl.members.iter() {
+                    match member {
+                        ClassMember::Method(method) => {
+                            if let Some(body) = &method.body {
+                                self.walk_statements(body.statements);
+                            }
+                        }
+                        ClassMember::Constructor(ctor) => {
+                            self.walk_statements(ctor.body.statements)
+                        }
+                        ClassMember::Getter(getter) => self.walk_statements(getter.body.statements),
+                        ClassMember::Setter(setter) => self.walk_statements(setter.body.statements),
+                        ClassMember::Operator(op) => self.walk_statements(op.body.statements),
+                        ClassMember::Property(_) => {}
+                    }
+                }
+            }
+            Statement::Export(export) => match &export.kind {
+                ExportKind::Declaration(inner) => self.walk_statement(inner),
+                ExportKind::Default(expr) => self.walk_expression(expr),
+                ExportKind::Named { .. } | ExportKind::All { .. } => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn walk_expression(&mut self, expr: &Expression) {
+        (self.visit)(expr);
+
+        match &expr.kind {
+            ExpressionKind::Binary(_, left, right)
+            | ExpressionKind::Pipe(left, right)
+            | ExpressionKind::ErrorChain(left, right)
+            | ExpressionKind::Index(left, right)
+            | ExpressionKind::OptionalIndex(left, right) => {
+                self.walk_expression(left);
+                self.walk_expression(right);
+            }
+            ExpressionKind::Assignment(target, _, value) => {
+                self.walk_expression(target);
+                self.walk_expression(value);
+            }
+            ExpressionKind::Unary(_, operand)
+            | ExpressionKind::Parenthesized(operand)
+            | ExpressionKind::TypeAssertion(operand, _)
+            | ExpressionKind::Member(operand, _)
+            | ExpressionKind::OptionalMember(operand, _) => self.walk_expression(operand),
+            ExpressionKind::Call(callee, args, _)
+            | ExpressionKind::OptionalCall(callee, args, _)
+            | ExpressionKind::New(callee, args, _)
+            | ExpressionKind::MethodCall(callee, _, args, _)
+            | ExpressionKind::OptionalMethodCall(callee, _, args, _) => {
+                self.walk_expression(callee);
+                for arg in args.iter() {
+                    self.walk_expression(&arg.value);
+                }
+            }
+            ExpressionKind::Conditional(cond, then_expr, else_expr) => {
+                self.walk_expression(cond);
+                self.walk_expression(then_expr);
+                self.walk_expression(else_expr);
+            }
+            ExpressionKind::Array(elements) => {
+                for elem in elements.iter() {
+                    match elem {
+                        ArrayElement::Expression(e) | ArrayElement::Spread(e) => {
+                            self.walk_expression(e)
+                        }
+                    }
+                }
+            }
+            ExpressionKind::Object(properties) => {
+                for prop in properties.iter() {
+                    match prop {
+                        ObjectProperty::Property { value, .. }
+                        | ObjectProperty::Spread { value, .. } => self.walk_expression(value),
+                        ObjectProperty::Computed { key, value, .. } => {
+                            self.walk_expression(key);
+                            self.walk_expression(value);
+                        }
+                    }
+                }
+            }
+            ExpressionKind::Function(func) => self.walk_statements(func.body.statements),
+            ExpressionKind::Arrow(arrow) => match &arrow.body {
+                ArrowBody::Expression(e) => self.walk_expression(e),
+                ArrowBody::Block(block) => self.walk_statements(block.statements),
+            },
+            ExpressionKind::Match(match_expr) => {
+                self.walk_expression(match_expr.value);
+                for arm in match_expr.arms.iter() {
+                    match &arm.body {
+                        MatchArmBody::Expression(e) => self.walk_expression(e),
+                        MatchArmBody::Block(block) => self.walk_statements(block.statements),
+                    }
+                }
+            }
+            ExpressionKind::Try(try_expr) => {
+                self.walk_expression(try_expr.expression);
+                self.walk_expression(try_expr.catch_expression);
+            }
+            ExpressionKind::Template(template) => {
+                for part in template.parts.iter() {
+                    if let TemplatePart::Expression(e) = part {
+                        self.walk_expression(e);
+                    }
+                }
+            }
+            ExpressionKind::Identifier(_)
+            | ExpressionKind::Literal(_)
+            | ExpressionKind::SelfKeyword
+            | ExpressionKind::SuperKeyword => {}
+        }
+    }
+}
+
+/// A group of modules emitted into one output file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Chunk {
+    /// Chunk name; chunk files are loaded with `require(name)`
+    pub name: String,
+    /// Module IDs in this chunk, in emission order
+    pub modules: Vec<String>,
+}
+
+/// Assignment of bundle modules to the main chunk and lazily loaded chunks
+#[derive(Debug, Clone, Default)]
+pub struct ChunkPlan {
+    /// Entry module and everything it imports statically
+    pub main: Chunk,
+    /// One chunk per dynamically imported module (empty chunks are omitted)
+    pub chunks: Vec<Chunk>,
+    /// Modules shared by more than one dynamic chunk
+    pub common: Option<Chunk>,
+    /// Dynamically imported module ID -> chunks to load before requiring it
+    pub chunk_map: Vec<(String, Vec<String>)>,
+}
+
+impl ChunkPlan {
+    /// Split `modules` into chunks starting from `entry_module_id`.
+    pub fn build(
+        modules: &[BundleModule<'_, '_>],
+        entry_module_id: &str,
+        interner: &StringInterner,
+    ) -> Self {
+        let graph = SplitGraph::build(modules, interner);
+
+        let main_modules = graph.closure(entry_module_id, &HashSet::default());
+        let main_set: HashSet<String> = main_modules.iter().cloned().collect();
+
+        // Discover split points breadth-first so chunk order follows the code
+        let mut roots: Vec<String> = Vec::new();
+        let mut scanned: HashSet<String> = HashSet::default();
+        let mut queue: std::collections::VecDeque<String> = main_modules.iter().cloned().collect();
+        while let Some(module_id) = queue.pop_front() {
+            if !scanned.insert(module_id.clone()) {
+                continue;
+            }
+            for target in graph.dynamic_deps(&module_id) {
+                if !main_set.contains(target) && !roots.contains(target) {
+                    roots.push(target.clone());
+                    queue.extend(graph.closure(target, &main_set));
+                }
+            }
+        }
+
+        let root_closures: Vec<(String, Vec<String>)> = roots
+            .iter()
+            .map(|root| (root.clone(), graph.closure(root, &main_set)))
+            .collect();
+
+        // A module needed by two or more chunks is moved to the common chunk
+        let mut usage: HashMap<&str, usize> = HashMap::default();
+        for (_, closure) in &root_closures {
+            for module_id in closure {
+                *usage.entry(module_id.as_str()).or_default() += 1;
+            }
+        }
+        let common_modules: Vec<String> = graph
+            .order
+            .iter()
+            .filter(|id| usage.get(id.as_str()).copied().unwrap_or(0) > 1)
+            .cloned()
+            .collect();
+        let common_set: HashSet<&str> = common_modules.iter().map(String::as_str).collect();
+
+        let mut chunks = Vec::new();
+        let mut chunk_map = Vec::new();
+        for (root, closure) in root_closures {
+            let own: Vec<String> = closure
+                .iter()
+                .filter(|id| !common_set.contains(id.as_str()))
+                .cloned()
+                .collect();
+
+            let mut required = Vec::new();
+            if closure.iter().any(|id| common_set.contains(id.as_str())) {
+                required.push(COMMON_CHUNK_NAME.to_string());
+            }
+            if !own.is_empty() {
+                let name = chunk_name(&root);
+                required.push(name.clone());
+                chunks.push(Chunk { name, modules: own });
+            }
+            chunk_map.push((root, required));
+        }
+
+        ChunkPlan {
+            main: Chunk {
+                name: "main".to_string(),
+                modules: main_modules,
+            },
+            chunks,
+            common: (!common_modules.is_empty()).then(|| Chunk {
+                name: COMMON_CHUNK_NAME.to_string(),
+                modules: common_modules,
+            }),
+            chunk_map,
+        }
+    }
+
+    /// Module IDs that are targets of a dynamic import outside the main chunk
+    pub fn dynamic_roots(&self) -> impl Iterator<Item = &str> {
+        self.chunk_map.iter().map(|(root, _)| root.as_str())
+    }
+
+    /// All lazily loaded chunks, common chunk first
+    pub fn lazy_chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.common.iter().chain(self.chunks.iter())
+    }
+}
+
+/// Derive a chunk name (and `require` name) from a module ID.
+///
+/// Sanitizing the ID is lossy (`a/b` and `a_b` both become `a_b`), so the name
+/// ends with a short hash of the full ID to keep it unique.
+pub fn chunk_name(module_id: &str) -> String {
+    let stem = module_id
+        .strip_suffix(".luax")
+        .or_else(|| module_id.strip_suffix(".lua"))
+        .unwrap_or(module_id);
+    let sanitized: String = stem
+        .trim_start_matches("./")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let hash = blake3::hash(module_id.as_bytes()).to_hex();
+    format!("chunk_{}_{}", sanitized, &hash[..8])
+}
+
+/// Static and dynamic import edges between bundle modules
+struct SplitGraph {
+    /// Module IDs in input order
+    order: Vec<String>,
+    static_deps: HashMap<String, Vec<String>>,
+    dynamic_deps: HashMap<String, Vec<String>>,
+}
+
+impl SplitGraph {
+    fn build(modules: &[BundleModule<'_, '_>], interner: &StringInterner) -> Self {
+        let order: Vec<String> = modules.iter().map(|(id, _, _)| id.clone()).collect();
+        let known: HashSet<&str> = order.iter().map(String::as_str).collect();
+
+        let mut static_deps = HashMap::default();
+        let mut dynamic_deps = HashMap::default();
+        for (module_id, program, import_map) in modules {
+            let statics: Vec<String> = Self::static_sources(program)
+                .into_iter()
+                .filter_map(|source| Self::resolve(&source, import_map, &known))
+                .collect();
+            let dynamics: Vec<String> = collect_dynamic_imports(program.statements, interner)
+                .into_iter()
+                .filter_map(|source| Self::resolve(&source, import_map, &known))
+                .collect();
+            static_deps.insert(module_id.clone(), statics);
+            dynamic_deps.insert(module_id.clone(), dynamics);
+        }
+
+        SplitGraph {
+            order,
+            static_deps,
+            dynamic_deps,
+        }
+    }
+
+    /// Sources of top-level imports and re-exports that produce runtime code
+    fn static_sources(program: &Program) -> Vec<String> {
+        let mut sources = Vec::new();
+        for stmt in program.statements.iter() {
+            match stmt {
+                Statement::Import(import) => {
+                    if !matches!(import.clause, ImportClause::TypeOnly(_)) {
+                        sources.push(import.source.clone());
+                    }
+                }
+                Statement::Export(export) => match &export.kind {
+                    ExportKind::Named {
+                        source: Some(source),
+                        is_type_only: false,
+                        ..
+                    }
+                    | ExportKind::All {
+                        source,
+                        is_type_only: false,
+                    } => sources.push(source.clone()),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        sources
+    }
+
+    fn resolve(
+        source: &str,
+        import_map: &std::collections::HashMap<String, String>,
+        known: &HashSet<&str>,
+    ) -> Option<String> {
+        if let Some(mapped) = import_map.get(source) {
+            if known.contains(mapped.as_str()) {
+                return Some(mapped.clone());
+            }
+        }
+        [
+            source.to_string(),
+            format!("{}.lua", source),
+            format!("{}/index.lua", source),
+        ]
+        .into_iter()
+        .find(|candidate| known.contains(candidate.as_str()))
+    }
+
+    fn dynamic_deps(&self, module_id: &str) -> &[String] {
+        self.dynamic_deps
+            .get(module_id)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Static import closure of `root` in depth-first preorder, stopping at `exclude`
+    fn closure(&self, root: &str, exclude: &HashSet<String>) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut visited: HashSet<String> = HashSet::default();
+        let mut stack = vec![root.to_string()];
+        while let Some(module_id) = stack.pop() {
+            if exclude.contains(&module_id) || !visited.insert(module_id.clone()) {
+                continue;
+            }
+            if let Some(deps) = self.static_deps.get(&module_id) {
+                stack.extend(deps.iter().rev().cloned());
+            }
+            result.push(module_id);
+        }
+        result
+    }
+}
+
+/// One lazily loaded chunk file
+#[derive(Debug, Clone)]
+pub struct ChunkOutput {
+    /// Chunk name, as passed to `require` by the chunk loader
+    pub name: String,
+    /// Suggested file name, relative to the main bundle
+    pub file_name: String,
+    /// Generated Lua code
+    pub code: String,
+}
+
+/// Output of a code-split bundle build
+#[derive(Debug, Clone)]
+pub struct SplitBundle {
+    /// Main bundle: runtime, chunk loader and the main chunk
+    pub main: String,
+    /// Lazily loaded chunks, common chunk first
+    pub chunks: Vec<ChunkOutput>,
+    /// How modules were assigned to chunks
+    pub plan: ChunkPlan,
+}
+
+impl CodeGenerator {
+    /// Generate a bundle split at dynamic `import()` expressions.
+    ///
+    /// # Arguments
+    /// * `modules` - Vector of (module_id, program, import_map) tuples
+    /// * `entry_module_id` - The ID of the entry point module
+    /// * `target` - Lua target version
+    /// * `interner` - The string interner used during parsing
+    /// * `tree_shaking_enabled` - Drop modules without reachable exports, per chunk
+    /// * `scope_hoisting_enabled` - Hoist declarations to the top of each chunk file
+    /// * `asset_modules` - Asset modules, emitted as their Lua constructor
+    ///
+    /// Chunk files must be loadable with `require(chunk.name)` from the main bundle.
+    pub fn generate_split_bundle(
+        modules: &[BundleModule<'_, '_>],
+        entry_module_id: &str,
+        target: LuaTarget,
+        interner: Arc<StringInterner>,
+        tree_shaking_enabled: bool,
+        scope_hoisting_enabled: bool,
+        asset_modules: &AssetModules,
+    ) -> SplitBundle {
+        let plan = ChunkPlan::build(modules, entry_module_id, &interner);
+
+        let reachable_set = if tree_shaking_enabled {
+            Some(Self::chunk_reachability(
+                modules,
+                entry_module_id,
+                &plan,
+                &interner,
+            ))
+        } else {
+            None
+        };
+
+        let by_id: HashMap<&str, &BundleModule<'_, '_>> =
+            modules.iter().map(|m| (m.0.as_str(), m)).collect();
+        let mut keep: HashSet<&str> = plan.dynamic_roots().collect();
+        keep.insert(entry_module_id);
+
+        let emit_options = ChunkEmitOptions {
+            target,
+            interner: &interner,
+            reachable_set: reachable_set.as_ref(),
+            keep: &keep,
+            scope_hoisting_enabled,
+            code_splitting_enabled: true,
+            asset_modules: Some(asset_modules),
+        };
+
+        let mut main = String::new();
+        main.push_str("-- LuaNext Bundle\n");
+        main.push_str("-- Generated by LuaNext compiler\n");
+        main.push('\n');
+        main.push_str(module::MODULE_PRELUDE);
+        main.push('\n');
+        main.push_str(module::CHUNK_LOADER_PRELUDE);
+        main.push('\n');
+
+        if !plan.chunk_map.is_empty() {
+            main.push_str("-- Chunk map\n");
+            for (root, chunks) in &plan.chunk_map {
+                let names: Vec<String> = chunks.iter().map(|c| format!("\"{}\"", c)).collect();
+                main.push_str(&format!(
+                    "__chunk_map[\"{}\"] = {{ {} }}\n",
+                    root,
+                    names.join(", ")
+                ));
+            }
+            main.push('\n');
+        }
+
+        main.push_str(&Self::emit_chunk_modules(
+            &plan.main,
+            entry_module_id,
+            &by_id,
+            &emit_options,
+        ));
+
+        main.push_str("-- Execute entry point\n");
+        main.push_str(&format!("__require(\"{}\")\n", entry_module_id));
+
+        let chunks = plan
+            .lazy_chunks()
+            .map(|chunk| {
+                let chunk_entry = chunk
+                    .modules
+                    .iter()
+                    .find(|id| keep.contains(id.as_str()))
+                    .map(String::as_str)
+                    .unwrap_or("");
+                let body = Self::emit_chunk_modules(chunk, chunk_entry, &by_id, &emit_options);
+
+                ChunkOutput {
+                    name: chunk.name.clone(),
+                    file_name: format!("{}.lua", chunk.name),
+                    code: chunk_file(&format!("LuaNext Chunk: {}", chunk.name), &body),
+                }
+            })
+            .collect();
+
+        SplitBundle { main, chunks, plan }
+    }
+
+    /// Reachability with the entry and every dynamic import target as roots
+    fn chunk_reachability(
+        modules: &[BundleModule<'_, '_>],
+        entry_module_id: &str,
+        plan: &ChunkPlan,
+        interner: &StringInterner,
+    ) -> ReachableSet {
+        let programs: HashMap<String, Program> = modules
+            .iter()
+            .map(|(id, program, _)| (id.clone(), (*program).clone()))
+            .collect();
+
+        let mut merged = ReachableSet::new();
+        for root in std::iter::once(entry_module_id).chain(plan.dynamic_roots()) {
+            let reachable = ReachabilityAnalysis::analyze(Path::new(root), &programs, interner);
+            merged.modules.extend(reachable.modules);
+            for (module_id, exports) in reachable.exports {
+                merged.exports.entry(module_id).or_default().extend(exports);
+            }
+        }
+        merged
+    }
+
+    /// Emit hoisted declarations and module wrappers for the modules of one chunk
+    pub(super) fn emit_chunk_modules(
+        chunk: &Chunk,
+        chunk_entry: &str,
+        by_id: &HashMap<&str, &BundleModule<'_, '_>>,
+        options: &ChunkEmitOptions<'_>,
+    ) -> String {
+        let mut output = String::new();
+        let asset_expression = |id: &str| options.asset_modules.and_then(|assets| assets.get(id));
+        let chunk_modules: Vec<&BundleModule> = chunk
+            .modules
+            .iter()
+            .filter(|id| asset_expression(id).is_none())
+            .filter_map(|id| by_id.get(id.as_str()).copied())
+            .collect();
+
+        // Assets have no code of their own; their module returns the data
+        for id in &chunk.modules {
+            if let Some(expr) = asset_expression(id) {
+                output.push_str(&format!("-- Asset: {}\n", id));
+                output.push_str(&format!(
+                    "__modules[\"{}\"] = function() return {} end\n",
+                    id, expr
+                ));
+                output.push('\n');
+            }
+        }
+
+        // Hoisted names are file-local, so each chunk gets its own context
+        let modules_for_analysis: Vec<(String, &Program)> = chunk_modules
+            .iter()
+            .map(|(id, program, _)| (id.clone(), *program))
+            .collect();
+        let hoisting_context = HoistingContext::analyze_modules(
+            &modules_for_analysis,
+            options.interner,
+            chunk_entry,
+            options.scope_hoisting_enabled,
+        );
+
+        if options.scope_hoisting_enabled && !hoisting_context.hoistable_by_module.is_empty() {
+            output.push_str("-- Hoisted declarations (scope hoisting)\n");
+            for (module_id, program, _) in &chunk_modules {
+                if let Some(hoistable) = hoisting_context.get_hoistable_declarations(module_id) {
+                    for stmt in program.statements.iter() {
+                        Self::generate_hoisted_declaration_if_needed(
+                            stmt,
+                            module_id,
+                            hoistable,
+                            &hoisting_context,
+                            options.interner,
+                            options.target,
+                            &mut output,
+                            &mut None,
+                        );
+                    }
+                }
+            }
+            output.push('\n');
+        }
+
+        for (module_id, program, import_map) in chunk_modules {
+            let must_keep = options.keep.contains(module_id.as_str());
+
+            if let Some(reachable) = options.reachable_set {
+                if !must_keep && !reachable.is_module_reachable(module_id) {
+                    continue;
+                }
+            }
+
+            if !must_keep && hoisting_context.is_module_fully_hoistable(module_id) {
+                output.push_str(&format!(
+                    "-- Module: {} (skipped - fully hoisted)\n",
+                    module_id
+                ));
+                continue;
+            }
+
+            if let Some(reachable) = options.reachable_set {
+                if !must_keep {
+                    if let Some(exports) = reachable.get_reachable_exports(module_id) {
+                        if exports.is_empty() {
+                            output.push_str(&format!(
+                                "-- Module: {} (skipped - no reachable exports)\n",
+                                module_id
+                            ));
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            let mut generator = CodeGenerator::new(options.interner.clone())
+                .with_target(options.target)
+                .with_mode(CodeGenMode::Bundle {
+                    module_id: module_id.clone(),
+                });
+            generator.import_map = import_map.clone();
+            generator.code_splitting_enabled = options.code_splitting_enabled;
+
+            let mutable_program = crate::MutableProgram::from_program(program);
+            let module_code = generator.generate(&mutable_program);
+
+            output.push_str(&format!("-- Module: {}\n", module_id));
+            output.push_str(&format!("__modules[\"{}\"] = function()\n", module_id));
+            for line in module_code.lines() {
+                if !line.is_empty() {
+                    output.push_str("    ");
+                }
+                output.push_str(line);
+                output.push('\n');
+            }
+            output.push_str("end\n");
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+/// Wrap emitted modules into a chunk file that registers them when called.
+pub(super) fn chunk_file(title: &str, body: &str) -> String {
+    let mut code = String::new();
+    code.push_str(&format!("-- {}\n", title));
+    code.push_str("-- Generated by LuaNext compiler\n");
+    code.push('\n');
+    code.push_str("return function(__modules, __require, __import)\n");
+    for line in body.lines() {
+        if !line.is_empty() {
+            code.push_str("    ");
+        }
+        code.push_str(line);
+        code.push('\n');
+    }
+    code.push_str("end\n");
+    code
+}
+
+/// Settings shared by every chunk of a split bundle
+pub(super) struct ChunkEmitOptions<'a> {
+    pub(super) target: LuaTarget,
+    pub(super) interner: &'a Arc<StringInterner>,
+    pub(super) reachable_set: Option<&'a ReachableSet>,
+    /// Modules that must keep their wrapper (entry and dynamic import targets)
+    pub(super) keep: &'a HashSet<&'a str>,
+    pub(super) scope_hoisting_enabled: bool,
+    /// Emit dynamic imports as `__import` (requires the chunk loader prelude)
+    pub(super) code_splitting_enabled: bool,
+    /// Asset modules, emitted as their Lua constructor instead of generated code
+    pub(super) asset_modules: Option<&'a AssetModules>,
+}
@@ -78,7 +78,17 @@ impl CodeGenerator {
     }
 
     pub fn is_built_in_decorator(&self, name: &str) -> bool {
-        matches!(name, "readonly" | "sealed" | "deprecated")
+        matches!(name, "readonly" | "sealed" | "deprecated" | "serializable")
+            || crate::optimizer::hints::is_hint_decorator(name)
+    }
+
+    /// Whether a decorator is an optimization hint (`@inline`, `@pure`, ...).
+    /// Hints only steer the optimizer and are stripped from the output.
+    pub fn is_optimization_hint(
+        &self,
+        expr: &luanext_parser::ast::statement::DecoratorExpression,
+    ) -> bool {
+        crate::optimizer::hints::hint_name(expr, &self.interner).is_some()
     }
 
     pub fn detect_decorators(&mut self, program: &luanext_parser::ast::Program) {
@@ -101,7 +111,10 @@ impl CodeGenerator {
         match stmt {
             Statement::Class(class_decl) => {
                 for decorator in class_decl.decorators.iter() {
-                    if self.is_decorator_built_in(&decorator.expression) {
+                    if self.is_decorator_built_in(&decorator.expression)
+                        && !self.is_optimization_hint(&decorator.expression)
+                        && !self.is_serializable_decorator(&decorator.expression)
+                    {
                         return true;
                     }
                 }
@@ -116,7 +129,9 @@ impl CodeGenerator {
                     };
 
                     for decorator in decorators {
-                        if self.is_decorator_built_in(&decorator.expression) {
+                        if self.is_decorator_built_in(&decorator.expression)
+                            && !self.is_optimization_hint(&decorator.expression)
+                        {
                             return true;
                         }
                     }
@@ -164,4 +179,56 @@ impl CodeGenerator {
         self.writeln(luanext_runtime::decorator::DECORATOR_RUNTIME);
         self.writeln("");
     }
+
+    /// Whether a decorator is `@serializable`. It is stripped from the
+    /// output; the class registers with the embedded JSON runtime instead.
+    pub fn is_serializable_decorator(
+        &self,
+        expr: &luanext_parser::ast::statement::DecoratorExpression,
+    ) -> bool {
+        use luanext_parser::ast::statement::DecoratorExpression;
+
+        match expr {
+            DecoratorExpression::Identifier(name) => self.resolve(name.node) == "serializable",
+            DecoratorExpression::Member {
+                object, property, ..
+            } => {
+                if let DecoratorExpression::Identifier(obj_name) = object {
+                    self.resolve(obj_name.node) == "LuaNext"
+                        && self.resolve(property.node) == "serializable"
+                } else {
+                    false
+                }
+            }
+            DecoratorExpression::Call { .. } => false,
+        }
+    }
+
+    /// Whether a class is marked `@serializable` for `@std/json`
+    pub fn is_serializable_class(&self, class_decl: &ClassDeclaration) -> bool {
+        class_decl
+            .decorators
+            .iter()
+            .any(|decorator| self.is_serializable_decorator(&decorator.expression))
+    }
+
+    /// Detect `@std/json` imports and `@serializable` classes, both of which
+    /// need the JSON runtime embedded in the module
+    pub fn detect_json_usage(&mut self, statements: &[Statement]) {
+        self.uses_json_runtime = statements.iter().any(|statement| match statement {
+            Statement::Import(import) => import.source == "@std/json",
+            Statement::Class(class_decl) => self.is_serializable_class(class_decl),
+            Statement::Export(export) => matches!(
+                &export.kind,
+                ExportKind::Declaration(Statement::Class(class_decl))
+                    if self.is_serializable_class(class_decl)
+            ),
+            _ => false,
+        });
+    }
+
+    pub fn embed_json_runtime(&mut self) {
+        self.writeln(luanext_runtime::json::JSON_MODULE);
+        self.writeln("");
+    }
 }
@@ -13,6 +13,7 @@ impl CodeGenerator {
             && enum_decl.constructor.is_none()
             && enum_decl.methods.is_empty()
         {
+            self.annotate_enum(enum_decl, &enum_name);
             self.write_indent();
             self.write("local ");
             self.write(&enum_name);
@@ -49,6 +50,8 @@ impl CodeGenerator {
         } else {
             self.generate_rich_enum_declaration(enum_decl, &enum_name);
         }
+
+        self.reflect_enum(enum_decl, &enum_name);
     }
 
     fn generate_rich_enum_declaration(
@@ -59,6 +62,7 @@ impl CodeGenerator {
         let mt_name = format!("{}__mt", enum_name);
 
         self.writeln("");
+        self.annotate_enum(enum_decl, enum_name);
         self.write_indent();
         self.writeln(&format!("local {} = {}", enum_name, "{}"));
 
@@ -125,6 +125,12 @@ impl CodeGenerator {
                         return;
                     }
                 }
+                if let Some(source) =
+                    super::code_splitting::dynamic_import_source(expr, &self.interner)
+                {
+                    self.generate_dynamic_import(source);
+                    return;
+                }
                 self.generate_call_expression(callee, args);
             }
             ExpressionKind::New(constructor, args, _type_args) => {
@@ -175,6 +175,16 @@ impl CodeGenerator {
                 self.write(" | ");
                 self.generate_expression(value);
             }
+            AssignmentOp::FloorDivideAssign if !self.strategy.supports_native_integer_divide() => {
+                let target_str = self.expression_to_string(target);
+                let value_str = self.expression_to_string(value);
+                self.write(&target_str);
+                self.write(" = ");
+                let result = self
+                    .strategy
+                    .generate_integer_divide(&target_str, &value_str);
+                self.write(&result);
+            }
             AssignmentOp::FloorDivideAssign => {
                 self.generate_expression(target);
                 self.write(" = ");
@@ -7,6 +7,9 @@ impl CodeGenerator {
             Literal::Nil => self.write("nil"),
             Literal::Boolean(b) => self.write(if *b { "true" } else { "false" }),
             Literal::Number(n) => self.write(&n.to_string()),
+            // `-9223372036854775808` would read as a float: the literal
+            // overflows before the negation
+            Literal::Integer(i64::MIN) => self.write("(-9223372036854775807 - 1)"),
             Literal::Integer(i) => self.write(&i.to_string()),
             Literal::String(s) => {
                 self.write("\"");
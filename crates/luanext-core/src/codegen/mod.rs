@@ -4,15 +4,19 @@ pub mod sourcemap;
 pub mod strategies;
 pub mod traits;
 
+pub mod annotations;
 pub mod classes;
+pub mod code_splitting;
 pub mod decorators;
 pub mod enums;
 pub mod expressions;
 pub mod modules;
+pub mod multi_entry;
 pub mod patterns;
 pub mod scope_hoisting;
 pub mod statements;
 pub mod tree_shaking;
+pub mod type_registry;
 
 pub use emitter::Emitter;
 
@@ -21,12 +25,12 @@ pub use sourcemap::{SourceMap, SourceMapBuilder};
 
 // Re-export types needed for builder API
 pub use super::config::OptimizationLevel;
+use luanext_parser::ast::expression::ExpressionKind;
 use luanext_parser::ast::pattern::Pattern;
 use luanext_parser::ast::statement::*;
 use luanext_parser::ast::Program;
 use luanext_parser::string_interner::{StringId, StringInterner};
 use luanext_runtime::module;
-use luanext_runtime::reflection;
 use rustc_hash::FxHashMap as HashMap;
 use std::sync::Arc;
 
@@ -150,11 +154,13 @@ pub struct CodeGenerator {
     /// Reflection: counter for assigning unique type IDs
     next_type_id: u32,
     /// Reflection: track registered types for __TypeRegistry
-    registered_types: std::collections::HashMap<String, u32>,
+    registered_types: std::collections::HashMap<String, type_registry::RegisteredType>,
     /// Reflection: generation mode (selective, full, none)
     reflection_mode: ReflectionMode,
     /// Reflection: whether current module imports @std/reflection
     has_reflection_import: bool,
+    /// JSON: whether current module imports @std/json or declares @serializable classes
+    uses_json_runtime: bool,
     /// Code generation strategy for Lua version-specific logic
     strategy: Box<dyn strategies::CodeGenStrategy>,
     /// Enforce access modifiers (private/protected/public) at runtime
@@ -167,6 +173,8 @@ pub struct CodeGenerator {
     tree_shaking_enabled: bool,
     /// Scope hoisting: whether scope hoisting is enabled for bundles
     scope_hoisting_enabled: bool,
+    /// Code splitting: dynamic imports load their chunk via `__import` (bundle mode)
+    code_splitting_enabled: bool,
     /// Track class names that have been forward-declared in the current block
     forward_declared_classes: std::collections::HashSet<String>,
     /// Alias source to resolved require path mapping (for Require mode path aliases)
@@ -175,6 +183,10 @@ pub struct CodeGenerator {
     export_all_sources: Vec<String>,
     /// Counter for generating unique `export * from` variable names
     export_all_counter: usize,
+    /// Emit LuaLS `---@` annotations above classes, functions and enums
+    emit_annotations: bool,
+    /// Profile counters: (module ID, profile file) for `--profile-generate` builds
+    profile_instrumentation: Option<(String, String)>,
 }
 
 impl CodeGenerator {
@@ -200,16 +212,20 @@ impl CodeGenerator {
             registered_types: Default::default(),
             reflection_mode: ReflectionMode::default(),
             has_reflection_import: false,
+            uses_json_runtime: false,
             strategy: Self::create_strategy(target),
             enforce_access_modifiers: false,
             whole_program_analysis: None,
             reachable_exports: None,
             tree_shaking_enabled: false,
             scope_hoisting_enabled: true,
+            code_splitting_enabled: false,
             forward_declared_classes: Default::default(),
             alias_require_map: Default::default(),
             export_all_sources: Vec::new(),
             export_all_counter: 0,
+            emit_annotations: false,
+            profile_instrumentation: None,
         }
     }
 
@@ -274,6 +290,18 @@ impl CodeGenerator {
         self
     }
 
+    pub fn with_annotations(mut self, enabled: bool) -> Self {
+        self.emit_annotations = enabled;
+        self
+    }
+
+    /// Embed the profile runtime and bind the counter that instrumented
+    /// programs call (see [`crate::optimizer::profile::instrument`])
+    pub fn with_profile_instrumentation(mut self, module_id: String, path: String) -> Self {
+        self.profile_instrumentation = Some((module_id, path));
+        self
+    }
+
     /// Whether reflection metadata should be emitted for classes in this module
     fn should_emit_reflection(&self) -> bool {
         match self.reflection_mode {
@@ -338,6 +366,23 @@ impl CodeGenerator {
             self.embed_runtime_library();
         }
 
+        // Embed the JSON runtime for @std/json imports and @serializable classes
+        self.detect_json_usage(&program.statements);
+        if self.uses_json_runtime {
+            self.embed_json_runtime();
+        }
+
+        // Embed the profile runtime for --profile-generate builds
+        if let Some((module_id, path)) = self.profile_instrumentation.clone() {
+            self.embed_profile_runtime(&module_id, &path);
+        }
+
+        // Polyfill `math.tointeger` on targets without an integer subtype
+        if !self.strategy.supports_native_integers() && self.uses_tointeger(&program.statements) {
+            self.writeln(luanext_runtime::integer::TOINTEGER_POLYFILL);
+            self.writeln("");
+        }
+
         // Emit forward declarations for all classes in the top-level program.
         // This enables mutual recursion between classes defined at module scope.
         self.emit_top_level_class_forward_declarations(&program.statements);
@@ -354,41 +399,35 @@ impl CodeGenerator {
 
         // Generate __TypeRegistry if reflection is active and types were registered
         if self.should_emit_reflection() && !self.registered_types.is_empty() {
-            self.writeln("");
-            self.writeln("-- ============================================================");
-            self.writeln("-- Type Registry for Reflection");
-            self.writeln("-- ============================================================");
-
-            // Generate registry tables (name -> id and id -> class)
-            // Use `local` to avoid polluting global scope (strict mode compatibility)
-            self.writeln("local __TypeRegistry = {}");
-            self.writeln("local __TypeIdToClass = {}");
-            self.writeln("");
-
-            // Collect into a Vec to avoid borrow checker issues
-            let type_entries: Vec<(String, u32)> = self
-                .registered_types
-                .iter()
-                .map(|(k, v)| (k.clone(), *v))
-                .collect();
+            self.generate_type_registry();
+        }
 
-            // Populate __TypeRegistry (name -> id mapping)
-            for (type_name, type_id) in &type_entries {
-                self.writeln(&format!("__TypeRegistry[\"{}\"] = {}", type_name, type_id));
-            }
-            self.writeln("");
+        self.emitter.clone_output()
+    }
 
-            // Populate __TypeIdToClass (id -> class constructor mapping)
-            for (type_name, type_id) in &type_entries {
-                self.writeln(&format!("__TypeIdToClass[{}] = {}", type_id, type_name));
+    /// Whether `statements` refer to `math.tointeger`
+    fn uses_tointeger(&self, statements: &[Statement]) -> bool {
+        let mut found = false;
+        code_splitting::walk_expressions(statements, |expr| {
+            if let ExpressionKind::Member(object, member) = &expr.kind {
+                found |= matches!(&object.kind, ExpressionKind::Identifier(name)
+                    if self.resolve(*name) == "math")
+                    && self.resolve(member.node) == "tointeger";
             }
-            self.writeln("");
-
-            // Generate Reflect module from runtime
-            self.writeln(reflection::REFLECTION_MODULE);
-        }
+        });
+        found
+    }
 
-        self.emitter.clone_output()
+    fn embed_profile_runtime(&mut self, module_id: &str, path: &str) {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        self.writeln(luanext_runtime::profile::PROFILE_RUNTIME);
+        self.writeln(&format!(
+            "local {} = __LuaNextProfile.counter({}, {})",
+            crate::optimizer::profile::HIT_FUNCTION,
+            quote(module_id),
+            quote(path)
+        ));
+        self.writeln("");
     }
 
     /// Generate a bundle from multiple modules
@@ -973,7 +1012,7 @@ mod tests {
     fn test_generate_variable_declaration() {
         let source = "const x = 42";
         let output = generate_code(source);
-        assert!(output.contains("local x = 42"));
+        assert!(output.contains("local x <const> = 42"));
     }
 
     #[test]
@@ -1097,7 +1136,7 @@ mod tests {
 
         // Should work with any target
         let output_54 = generate_code_with_target(source, LuaTarget::Lua54);
-        assert!(output_54.contains("local x = (a + b)"));
+        assert!(output_54.contains("local x <const> = (a + b)"));
 
         let output_53 = generate_code_with_target(source, LuaTarget::Lua53);
         assert!(output_53.contains("local x = (a + b)"));
@@ -1940,6 +1979,93 @@ end
         );
     }
 
+    #[test]
+    fn test_reflection_enum_registry() {
+        let source = r#"
+            enum Color { Red, Green }
+
+            enum Planet {
+                mass: number
+                Mercury(3.3e23),
+                Earth(5.9e24),
+            }
+        "#;
+        let output = generate_code_with_reflection(source, super::ReflectionMode::Full);
+        assert!(
+            output.contains(r#"__TypeInfo[1] = { kind = "enum", name = "Color", variants = { "Red", "Green" } }"#),
+            "simple enum descriptor: {}",
+            output
+        );
+        assert!(output.contains(
+            r#"__TypeInfo[2] = { kind = "enum", name = "Planet", variants = { "Mercury", "Earth" }, fields = { { name = "mass", type = "n" } } }"#
+        ));
+        // Rich enum values get class infrastructure, simple enums do not
+        assert!(output.contains("Planet.__typeId = 2"));
+        assert!(!output.contains("Color.__typeId"));
+        assert!(output.contains("__TypeIdToClass[1] = Color"));
+    }
+
+    #[test]
+    fn test_reflection_interface_registry() {
+        let source = r#"
+            interface Named {
+                name: string
+            }
+
+            interface Shape<T> extends Named {
+                readonly id: T
+                tag?: string
+                area(scale: number): number
+            }
+        "#;
+        let output = generate_code_with_reflection(source, super::ReflectionMode::Full);
+        assert!(output.contains(
+            r#"__TypeInfo[2] = { kind = "interface", name = "Shape", typeParams = { "T" }, extends = { "Named" }, members = { { name = "id", type = "T", optional = false, readonly = true }, { name = "tag", type = "s", optional = true, readonly = false } }, methods = { { name = "area", params = "n", ret = "n" } } }"#
+        ), "{}", output);
+        // Interfaces have no runtime value
+        assert!(!output.contains("__TypeIdToClass[2]"));
+    }
+
+    #[test]
+    fn test_reflection_alias_and_generic_registry() {
+        let source = r#"
+            class Box<T> {
+                value: T
+            }
+
+            class Shelf {
+                boxes: Box<number>[]
+            }
+
+            type Id = string | number
+        "#;
+        let output = generate_code_with_reflection(source, super::ReflectionMode::Full);
+        assert!(output.contains(r#"{ name = "boxes", type = "[Box<n>]", _flags = 1 },"#));
+        assert!(output
+            .contains(r#"__TypeInfo[1] = { kind = "class", name = "Box", typeParams = { "T" } }"#));
+        assert!(
+            output.contains(r#"__TypeRegistry["Box<n>"] = 3"#),
+            "{}",
+            output
+        );
+        assert!(output.contains(r#"__TypeIdToClass[3] = Box"#));
+        assert!(output.contains(
+            r#"__TypeInfo[3] = { kind = "generic", name = "Box<n>", base = "Box", args = { "n" } }"#
+        ));
+        assert!(output.contains(r#"{ kind = "alias", name = "Id", type = "s|n" }"#));
+    }
+
+    #[test]
+    fn test_reflection_registry_selective_without_import() {
+        let source = r#"
+            enum Color { Red }
+            interface Named { name: string }
+            type Id = string
+        "#;
+        let output = generate_code_with_reflection(source, super::ReflectionMode::Selective);
+        assert!(!output.contains("__TypeInfo"));
+    }
+
     // ========================================================================
     // Lua 5.5 strategy tests
     // ========================================================================
@@ -1987,14 +2113,14 @@ end
     fn test_lua55_bitwise_codegen() {
         let source = "const x = a & b";
         let output = generate_code_with_target(source, LuaTarget::Lua55);
-        assert!(output.contains("local x = (a & b)"));
+        assert!(output.contains("local x <const> = (a & b)"));
     }
 
     #[test]
     fn test_lua55_integer_divide_codegen() {
         let source = "const x = a // b";
         let output = generate_code_with_target(source, LuaTarget::Lua55);
-        assert!(output.contains("local x = (a // b)"));
+        assert!(output.contains("local x <const> = (a // b)"));
     }
 
     #[test]
@@ -2169,7 +2295,7 @@ end
         let source = "const x = a + b";
 
         let output_55 = generate_code_with_target(source, LuaTarget::Lua55);
-        assert!(output_55.contains("local x = (a + b)"));
+        assert!(output_55.contains("local x <const> = (a + b)"));
 
         let output_jit = generate_code_with_target(source, LuaTarget::LuaJIT);
         assert!(output_jit.contains("local x = (a + b)"));
@@ -2184,4 +2310,23 @@ end
         assert!(strategy_51.emit_preamble().is_some());
         assert!(strategy_jit.emit_preamble().is_none());
     }
+
+    // ========================================================================
+    // Local attribute tests
+    // ========================================================================
+
+    #[test]
+    fn test_const_attribute_only_on_lua54_and_later() {
+        let source = "const x = 1\nlet y = 2";
+
+        for target in [LuaTarget::Lua54, LuaTarget::Lua55] {
+            let output = generate_code_with_target(source, target);
+            assert!(output.contains("local x <const> = 1"), "{}", output);
+            assert!(output.contains("local y = 2"), "{}", output);
+        }
+        for target in [LuaTarget::Lua53, LuaTarget::LuaJIT] {
+            let output = generate_code_with_target(source, target);
+            assert!(!output.contains("<const>"), "{}", output);
+        }
+    }
 }
@@ -27,15 +27,19 @@ impl CodeGenerator {
             }
         };
 
+        // @std/json binds to the runtime embedded at the top of the module
+        let loader = if import.source == "@std/json" {
+            "__LuaNextJSON".to_string()
+        } else {
+            format!("{}(\"{}\")", require_fn, module_path)
+        };
+
         match &import.clause {
             luanext_parser::ast::statement::ImportClause::TypeOnly(_) => {}
             luanext_parser::ast::statement::ImportClause::Named(specs) => {
                 self.write_indent();
                 self.write("local _mod = ");
-                self.write(require_fn);
-                self.write("(\"");
-                self.write(&module_path);
-                self.writeln("\")");
+                self.writeln(&loader);
 
                 self.write_indent();
                 self.write("local ");
@@ -64,10 +68,7 @@ impl CodeGenerator {
                 let ident_str = self.resolve(ident.node);
                 self.write(&ident_str);
                 self.write(" = ");
-                self.write(require_fn);
-                self.write("(\"");
-                self.write(&module_path);
-                self.writeln("\")");
+                self.writeln(&loader);
             }
             luanext_parser::ast::statement::ImportClause::Namespace(ident) => {
                 self.write_indent();
@@ -75,19 +76,13 @@ impl CodeGenerator {
                 let ident_str = self.resolve(ident.node);
                 self.write(&ident_str);
                 self.write(" = ");
-                self.write(require_fn);
-                self.write("(\"");
-                self.write(&module_path);
-                self.writeln("\")");
+                self.writeln(&loader);
             }
             luanext_parser::ast::statement::ImportClause::Mixed { default, named } => {
                 // Load module once
                 self.write_indent();
                 self.write("local _mod = ");
-                self.write(require_fn);
-                self.write("(\"");
-                self.write(&module_path);
-                self.writeln("\")");
+                self.writeln(&loader);
 
                 // Assign default export to the default identifier
                 self.write_indent();
@@ -123,6 +118,41 @@ impl CodeGenerator {
         }
     }
 
+    /// Generate a dynamic `import("...")` expression.
+    ///
+    /// Evaluates to the module's export table. In split bundles the module's
+    /// chunk is loaded on first use through `__import`.
+    pub fn generate_dynamic_import(&mut self, source: &str) {
+        let (require_fn, module_path) = match &self.mode {
+            CodeGenMode::Bundle { .. } => {
+                let resolved_id = self
+                    .import_map
+                    .get(source)
+                    .cloned()
+                    .unwrap_or_else(|| source.to_string());
+                let loader = if self.code_splitting_enabled {
+                    "__import"
+                } else {
+                    "__require"
+                };
+                (loader, resolved_id)
+            }
+            CodeGenMode::Require => {
+                let resolved = self
+                    .alias_require_map
+                    .get(source)
+                    .cloned()
+                    .unwrap_or_else(|| source.to_string());
+                ("require", resolved)
+            }
+        };
+
+        self.write(require_fn);
+        self.write("(\"");
+        self.write(&module_path);
+        self.write("\")");
+    }
+
     pub fn generate_export(&mut self, export: &luanext_parser::ast::statement::ExportDeclaration) {
         match &export.kind {
             luanext_parser::ast::statement::ExportKind::Declaration(stmt) => {
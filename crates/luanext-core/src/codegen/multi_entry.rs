@@ -0,0 +1,338 @@
+//! Multiple entry points per bundle build.
+//!
+//! A project can declare several entries in the `bundle` section of
+//! `luanext.config.yaml`:
+//!
+//! ```yaml
+//! bundle:
+//!   entries:
+//!     - name: client
+//!       entry: src/client.luax
+//!       outFile: dist/client.lua
+//!     - name: server
+//!       entry: src/server.luax
+//!       outFile: dist/server.lua
+//!   sharedFile: dist/shared.lua
+//! ```
+//!
+//! All entries share one [`ModuleGraph`]; each entry gets a bundle with its
+//! import closure. Modules reached through a dynamic `import()` are part of the
+//! closure and bundled eagerly; they are not split into chunks. With `sharedFile` set, modules used by two or more
+//! entries are emitted once into that file, and every entry bundle loads it
+//! with `require("<file stem>")` before running.
+
+use super::code_splitting::{
+    chunk_file, collect_dynamic_imports, BundleModule, Chunk, ChunkEmitOptions,
+};
+use super::{CodeGenerator, LuaTarget};
+use crate::assets::AssetModules;
+use crate::optimizer::analysis::module_graph::{resolve_relative_source, ModuleGraph};
+use luanext_parser::ast::statement::{ExportKind, ImportClause, Statement};
+use luanext_parser::ast::Program;
+use luanext_parser::string_interner::StringInterner;
+use luanext_runtime::module;
+use rustc_hash::FxHashMap as HashMap;
+use rustc_hash::FxHashSet as HashSet;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One entry of a multi-entry bundle build
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleEntry {
+    /// Display name of the bundle
+    pub name: String,
+    /// Entry module path
+    pub entry: PathBuf,
+    /// Output file for this entry's bundle
+    pub out_file: PathBuf,
+}
+
+/// The `bundle` section of `luanext.config.yaml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiEntryConfig {
+    /// Entries to bundle, one output file each
+    #[serde(default)]
+    pub entries: Vec<BundleEntry>,
+    /// Output file for modules shared between entries; when unset, shared
+    /// modules are duplicated into every bundle that needs them
+    #[serde(default)]
+    pub shared_file: Option<PathBuf>,
+}
+
+impl MultiEntryConfig {
+    /// Read the `bundle` section from the contents of a config file.
+    ///
+    /// Returns `Ok(None)` when the section is absent.
+    pub fn from_config_str(contents: &str) -> Result<Option<Self>, serde_yaml::Error> {
+        let root: serde_yaml::Value = serde_yaml::from_str(contents)?;
+        match root.get("bundle") {
+            Some(section) => Ok(Some(serde_yaml::from_value(section.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the `bundle` section from a config file on disk.
+    pub fn from_config_file(path: &Path) -> Result<Option<Self>, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_config_str(&contents)
+            .map_err(|e| format!("Invalid bundle section in {}: {}", path.display(), e))
+    }
+
+    /// Name the shared file is loaded by (its file stem), if configured
+    pub fn shared_require_name(&self) -> Option<String> {
+        self.shared_file.as_ref().and_then(|file| {
+            file.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+        })
+    }
+}
+
+/// Map every import source of a module to the bundle module ID it resolves to.
+///
+/// Module IDs are module paths as strings, matching the IDs passed to
+/// [`CodeGenerator::generate_multi_entry_bundles`]. Unresolvable sources are left out.
+pub fn bundle_import_map(
+    module_path: &Path,
+    program: &Program,
+    known_modules: &[PathBuf],
+    interner: &StringInterner,
+) -> std::collections::HashMap<String, String> {
+    let mut sources: Vec<String> = Vec::new();
+    for stmt in program.statements.iter() {
+        match stmt {
+            Statement::Import(import) if !matches!(import.clause, ImportClause::TypeOnly(_)) => {
+                sources.push(import.source.clone());
+            }
+            Statement::Export(export) => match &export.kind {
+                ExportKind::Named {
+                    source: Some(source),
+                    ..
+                }
+                | ExportKind::All { source, .. } => sources.push(source.clone()),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    sources.extend(collect_dynamic_imports(program.statements, interner));
+
+    let from_dir = module_path.parent().unwrap_or(module_path);
+    sources
+        .into_iter()
+        .filter_map(|source| {
+            resolve_relative_source(from_dir, &source, known_modules)
+                .map(|resolved| (source, resolved.to_string_lossy().to_string()))
+        })
+        .collect()
+}
+
+/// Modules bundled for a single entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryModules {
+    pub entry: PathBuf,
+    /// Modules emitted into this entry's bundle, entry first
+    pub modules: Vec<PathBuf>,
+}
+
+/// Assignment of modules to entry bundles and the shared file
+#[derive(Debug, Clone, Default)]
+pub struct MultiEntryPlan {
+    pub entries: Vec<EntryModules>,
+    /// Modules reachable from two or more entries (empty unless extraction is on)
+    pub shared: Vec<PathBuf>,
+}
+
+impl MultiEntryPlan {
+    /// Compute every entry's closure from one module graph.
+    ///
+    /// With `extract_shared`, modules reachable from more than one entry are
+    /// moved out of the entry bundles into [`MultiEntryPlan::shared`].
+    pub fn build(graph: &ModuleGraph, entries: &[PathBuf], extract_shared: bool) -> Self {
+        let dependencies = Self::dependency_lists(graph);
+
+        let closures: Vec<(PathBuf, Vec<PathBuf>)> = entries
+            .iter()
+            .map(|entry| (entry.clone(), Self::closure(entry, &dependencies)))
+            .collect();
+
+        let mut shared = Vec::new();
+        if extract_shared && closures.len() > 1 {
+            let mut usage: HashMap<&Path, usize> = HashMap::default();
+            for (_, closure) in &closures {
+                for module in closure {
+                    *usage.entry(module.as_path()).or_default() += 1;
+                }
+            }
+            // Keep first-seen order so the shared file is stable across builds
+            let mut seen: HashSet<&Path> = HashSet::default();
+            for (_, closure) in &closures {
+                for module in closure {
+                    if usage[&module.as_path()] > 1 && seen.insert(module.as_path()) {
+                        shared.push(module.clone());
+                    }
+                }
+            }
+        }
+
+        let shared_set: HashSet<&Path> = shared.iter().map(PathBuf::as_path).collect();
+        let entries = closures
+            .iter()
+            .map(|(entry, closure)| EntryModules {
+                entry: entry.clone(),
+                modules: closure
+                    .iter()
+                    .filter(|module| !shared_set.contains(&module.as_path()))
+                    .cloned()
+                    .collect(),
+            })
+            .collect();
+
+        MultiEntryPlan { entries, shared }
+    }
+
+    /// Runtime dependencies of every module (static and dynamic imports),
+    /// sorted for deterministic output
+    fn dependency_lists(graph: &ModuleGraph) -> HashMap<PathBuf, Vec<PathBuf>> {
+        graph
+            .modules
+            .iter()
+            .map(|(path, node)| {
+                let mut deps: Vec<PathBuf> = node
+                    .imports
+                    .values()
+                    .filter(|import| !import.is_type_only)
+                    .map(|import| import.source_module.clone())
+                    .chain(node.re_exports.iter().map(|re| re.source_module.clone()))
+                    .chain(node.dynamic_imports.iter().cloned())
+                    .filter(|dep| graph.modules.contains_key(dep))
+                    .collect();
+                deps.sort();
+                deps.dedup();
+                (path.clone(), deps)
+            })
+            .collect()
+    }
+
+    fn closure(entry: &Path, dependencies: &HashMap<PathBuf, Vec<PathBuf>>) -> Vec<PathBuf> {
+        let mut result = Vec::new();
+        let mut visited: HashSet<PathBuf> = HashSet::default();
+        let mut stack = vec![entry.to_path_buf()];
+        while let Some(module) = stack.pop() {
+            if !visited.insert(module.clone()) {
+                continue;
+            }
+            if let Some(deps) = dependencies.get(&module) {
+                stack.extend(deps.iter().rev().cloned());
+            }
+            result.push(module);
+        }
+        result
+    }
+}
+
+/// Output of a multi-entry bundle build
+#[derive(Debug, Clone)]
+pub struct MultiEntryOutput {
+    /// (entry module path, bundle code), in plan order
+    pub bundles: Vec<(PathBuf, String)>,
+    /// Code of the shared file, if any modules were extracted
+    pub shared: Option<String>,
+}
+
+impl CodeGenerator {
+    /// Generate one bundle per entry of `plan`.
+    ///
+    /// `modules` are keyed by the module path as a string. When the plan has
+    /// shared modules, each bundle loads them with `require(shared_require_name)`.
+    /// Modules listed in `asset_modules` are emitted as their Lua constructor.
+    pub fn generate_multi_entry_bundles(
+        modules: &[BundleModule<'_, '_>],
+        plan: &MultiEntryPlan,
+        shared_require_name: &str,
+        target: LuaTarget,
+        interner: Arc<StringInterner>,
+        scope_hoisting_enabled: bool,
+        asset_modules: &AssetModules,
+    ) -> MultiEntryOutput {
+        let by_id: HashMap<&str, &BundleModule<'_, '_>> =
+            modules.iter().map(|m| (m.0.as_str(), m)).collect();
+        let entry_ids: Vec<String> = plan
+            .entries
+            .iter()
+            .map(|e| e.entry.to_string_lossy().to_string())
+            .collect();
+        let keep: HashSet<&str> = entry_ids.iter().map(String::as_str).collect();
+
+        let emit_options = ChunkEmitOptions {
+            target,
+            interner: &interner,
+            reachable_set: None,
+            keep: &keep,
+            scope_hoisting_enabled,
+            // Dynamic import targets are in every closure that reaches them, so
+            // `import()` resolves through `__require` without a chunk loader
+            code_splitting_enabled: false,
+            asset_modules: Some(asset_modules),
+        };
+
+        let shared = (!plan.shared.is_empty()).then(|| {
+            let chunk = Chunk {
+                name: shared_require_name.to_string(),
+                modules: plan
+                    .shared
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect(),
+            };
+            let body = Self::emit_chunk_modules(&chunk, "", &by_id, &emit_options);
+            chunk_file("LuaNext Shared Modules", &body)
+        });
+
+        let bundles = plan
+            .entries
+            .iter()
+            .zip(&entry_ids)
+            .map(|(entry, entry_id)| {
+                let chunk = Chunk {
+                    name: entry_id.clone(),
+                    modules: entry
+                        .modules
+                        .iter()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .collect(),
+                };
+
+                let mut code = String::new();
+                code.push_str("-- LuaNext Bundle\n");
+                code.push_str("-- Generated by LuaNext compiler\n");
+                code.push('\n');
+                code.push_str(module::MODULE_PRELUDE);
+                code.push('\n');
+                if shared.is_some() {
+                    code.push_str("-- Shared modules\n");
+                    code.push_str(&format!(
+                        "require(\"{}\")(__modules, __require)\n",
+                        shared_require_name
+                    ));
+                    code.push('\n');
+                }
+                code.push_str(&Self::emit_chunk_modules(
+                    &chunk,
+                    entry_id,
+                    &by_id,
+                    &emit_options,
+                ));
+                code.push_str("-- Execute entry point\n");
+                code.push_str(&format!("__require(\"{}\")\n", entry_id));
+
+                (entry.entry.clone(), code)
+            })
+            .collect();
+
+        MultiEntryOutput { bundles, shared }
+    }
+}
@@ -9,6 +9,12 @@ use luanext_parser::prelude::Block;
 use luanext_parser::prelude::Expression;
 
 impl CodeGenerator {
+    pub fn statement_to_string(&mut self, stmt: &Statement) -> String {
+        let original_output = std::mem::take(self.emitter.output_mut());
+        self.generate_statement(stmt);
+        std::mem::replace(self.emitter.output_mut(), original_output)
+    }
+
     pub fn generate_statement(&mut self, stmt: &Statement) {
         match stmt {
             Statement::Variable(decl) => self.generate_variable_declaration(decl),
@@ -34,7 +40,10 @@ impl CodeGenerator {
             }
             Statement::Block(block) => self.generate_block(block),
             Statement::Interface(iface_decl) => self.generate_interface_declaration(iface_decl),
-            Statement::TypeAlias(_) => {}
+            Statement::TypeAlias(alias) => {
+                self.annotate_type_alias(alias);
+                self.reflect_type_alias(alias);
+            }
             Statement::Enum(decl) => self.generate_enum_declaration(decl),
             Statement::Class(class_decl) => self.generate_class_declaration(class_decl),
             Statement::Import(import) => self.generate_import(import),
@@ -140,6 +149,12 @@ impl CodeGenerator {
                 } else {
                     self.emit_var_prefix(&decl.kind);
                     self.generate_pattern(&decl.pattern);
+                    // Lua 5.4+ checks that the local is never assigned
+                    if matches!(decl.kind, VariableKind::Const)
+                        && self.strategy.supports_local_attributes()
+                    {
+                        self.write(" <const>");
+                    }
                     self.write(" = ");
                     self.generate_expression(&decl.initializer);
                 }
@@ -445,6 +460,11 @@ impl CodeGenerator {
     }
 
     pub fn generate_function_declaration(&mut self, decl: &FunctionDeclaration) {
+        self.annotate_function(
+            decl.type_parameters,
+            decl.parameters,
+            decl.return_type.as_ref(),
+        );
         self.write_indent();
         self.write("local function ");
         let fn_name = self.resolve(decl.name.node);
@@ -950,7 +970,7 @@ impl CodeGenerator {
         ) && !has_typed_catches;
 
         if use_debug_traceback {
-            self.writeln("debug.traceback)");
+            self.writeln("function(__err) __error = debug.traceback(__err) end)");
         } else {
             self.writeln("function(__err)");
             self.indent();
@@ -966,15 +986,23 @@ impl CodeGenerator {
             self.writeln("end)");
         }
 
-        if use_debug_traceback {
+        // Catch bodies only run after an error; the finally block after both
+        if use_debug_traceback && !stmt.catch_clauses.is_empty() {
             self.write_indent();
-            self.writeln("if __error == nil then return end");
+            self.writeln("if __error ~= nil then");
+            self.indent();
             self.write_indent();
             self.writeln("local e = __error");
-        }
-
-        for catch_clause in stmt.catch_clauses.iter() {
-            self.generate_block(&catch_clause.body);
+            for catch_clause in stmt.catch_clauses.iter() {
+                self.generate_block(&catch_clause.body);
+            }
+            self.dedent();
+            self.write_indent();
+            self.writeln("end");
+        } else {
+            for catch_clause in stmt.catch_clauses.iter() {
+                self.generate_block(&catch_clause.body);
+            }
         }
 
         if let Some(finally_block) = &stmt.finally_block {
@@ -6,7 +6,7 @@ use luanext_parser::string_interner::StringId;
 /// - Native bitwise operators (& | ~ << >>)
 /// - Supports goto/labels
 /// - Native integer division
-/// - Const expressions (generated as-is)
+/// - `<const>` and `<close>` local attributes
 pub struct Lua54Strategy;
 
 impl CodeGenStrategy for Lua54Strategy {
@@ -54,4 +54,8 @@ impl CodeGenStrategy for Lua54Strategy {
     fn supports_goto(&self) -> bool {
         true
     }
+
+    fn supports_local_attributes(&self) -> bool {
+        true
+    }
 }
@@ -8,6 +8,7 @@ use luanext_parser::string_interner::StringId;
 /// - Native integer division
 /// - Native continue statement (no goto hack needed)
 /// - Native global declaration keyword
+/// - `<const>` and `<close>` local attributes
 pub struct Lua55Strategy;
 
 impl CodeGenStrategy for Lua55Strategy {
@@ -56,6 +57,10 @@ impl CodeGenStrategy for Lua55Strategy {
         true
     }
 
+    fn supports_local_attributes(&self) -> bool {
+        true
+    }
+
     fn supports_native_continue(&self) -> bool {
         true
     }
@@ -44,6 +44,12 @@ pub trait CodeGenStrategy {
     /// Check if this strategy supports integer division
     fn supports_native_integer_divide(&self) -> bool;
 
+    /// Check if numbers have an integer subtype (Lua 5.3+). Targets without
+    /// one get a `math.tointeger` polyfill when the program uses it.
+    fn supports_native_integers(&self) -> bool {
+        self.supports_native_integer_divide()
+    }
+
     /// Check if this strategy supports goto/labels (Lua 5.2+)
     fn supports_goto(&self) -> bool;
 
@@ -53,6 +59,12 @@ pub trait CodeGenStrategy {
         false
     }
 
+    /// Check if this strategy supports the `<const>` and `<close>` local
+    /// attributes (Lua 5.4+)
+    fn supports_local_attributes(&self) -> bool {
+        false
+    }
+
     /// How to emit global variable declarations.
     /// Returns `GlobalStyle::NativeKeyword` for Lua 5.5 (emits `global name = value`),
     /// `GlobalStyle::Rawset` for all other targets (emits `rawset(_G, "name", value)`).
@@ -0,0 +1,314 @@
+//! Reflection type registry.
+//!
+//! Classes, enums, interfaces, type aliases and generic instantiations are
+//! recorded while their declarations are generated, then emitted together at
+//! the end of the module as `__TypeRegistry` (name → id), `__TypeIdToClass`
+//! (id → runtime value) and `__TypeInfo` (id → descriptor table).
+
+use super::CodeGenerator;
+use luanext_parser::ast::statement::{
+    ClassDeclaration, EnumDeclaration, InterfaceDeclaration, InterfaceMember, TypeAliasDeclaration,
+    TypeParameter,
+};
+use luanext_parser::ast::types::TypeKind;
+use luanext_runtime::reflection;
+
+/// What a `__TypeRegistry` entry describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReflectedKind {
+    Class,
+    Enum,
+    Interface,
+    Alias,
+    /// A generic type applied to type arguments, e.g. `Box<number>`
+    Generic,
+}
+
+impl ReflectedKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ReflectedKind::Class => "class",
+            ReflectedKind::Enum => "enum",
+            ReflectedKind::Interface => "interface",
+            ReflectedKind::Alias => "alias",
+            ReflectedKind::Generic => "generic",
+        }
+    }
+}
+
+/// A type recorded for the reflection registry
+#[derive(Debug, Clone)]
+pub struct RegisteredType {
+    pub id: u32,
+    pub kind: ReflectedKind,
+    /// Kind-specific `__TypeInfo` entries, e.g. `variants = { "A", "B" }`
+    pub info: Vec<String>,
+}
+
+impl CodeGenerator {
+    fn register_type(&mut self, name: &str, id: u32, kind: ReflectedKind, info: Vec<String>) {
+        self.registered_types
+            .insert(name.to_string(), RegisteredType { id, kind, info });
+    }
+
+    fn allocate_type_id(&mut self) -> u32 {
+        let id = self.next_type_id;
+        self.next_type_id += 1;
+        id
+    }
+
+    /// `typeParams = { "T", "U" }` for generic declarations
+    fn type_params_entry(&self, type_parameters: Option<&[TypeParameter]>) -> Option<String> {
+        let params = type_parameters.filter(|params| !params.is_empty())?;
+        let names = params.iter().map(|p| self.resolve(p.name.node));
+        Some(format!("typeParams = {}", lua_string_list(names)))
+    }
+
+    /// Record a class under the type ID already assigned to its `__typeId`
+    pub(super) fn register_class_type(
+        &mut self,
+        class_decl: &ClassDeclaration,
+        class_name: &str,
+        type_id: u32,
+    ) {
+        let info = self
+            .type_params_entry(class_decl.type_parameters)
+            .into_iter()
+            .collect();
+        self.register_type(class_name, type_id, ReflectedKind::Class, info);
+    }
+
+    /// Record an enum's variants, and for rich enums their fields. Rich enum
+    /// values also get the class infrastructure so `Reflect.typeof` and
+    /// `Reflect.isInstance` work on them.
+    pub(super) fn reflect_enum(&mut self, enum_decl: &EnumDeclaration, enum_name: &str) {
+        if !self.should_emit_reflection() {
+            return;
+        }
+        let type_id = self.allocate_type_id();
+
+        let variants = enum_decl.members.iter().map(|m| self.resolve(m.name.node));
+        let mut info = vec![format!("variants = {}", lua_string_list(variants))];
+
+        if !enum_decl.fields.is_empty() {
+            let fields: Vec<String> = enum_decl
+                .fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        "{{ name = \"{}\", type = \"{}\" }}",
+                        self.resolve(field.name.node),
+                        self.encode_type_code(&field.type_annotation)
+                    )
+                })
+                .collect();
+            info.push(format!("fields = {}", lua_table(&fields)));
+        }
+
+        let is_rich = !enum_decl.fields.is_empty()
+            || enum_decl.constructor.is_some()
+            || !enum_decl.methods.is_empty();
+        if is_rich {
+            self.writeln("");
+            self.write_indent();
+            self.writeln(&format!("{}.__typeName = \"{}\"", enum_name, enum_name));
+            self.write_indent();
+            self.writeln(&format!("{}.__typeId = {}", enum_name, type_id));
+            self.write_indent();
+            self.writeln(&format!(
+                "{}.__ancestors = {{ [{}] = true }}",
+                enum_name, type_id
+            ));
+        }
+
+        self.register_type(enum_name, type_id, ReflectedKind::Enum, info);
+    }
+
+    /// Record an interface's property and method signatures
+    pub(super) fn reflect_interface(&mut self, iface_decl: &InterfaceDeclaration) {
+        if !self.should_emit_reflection() {
+            return;
+        }
+        let interface_name = self.resolve(iface_decl.name.node);
+        let type_id = self.allocate_type_id();
+
+        let mut info: Vec<String> = self
+            .type_params_entry(iface_decl.type_parameters)
+            .into_iter()
+            .collect();
+
+        let extends: Vec<String> = iface_decl
+            .extends
+            .iter()
+            .map(|ty| match &ty.kind {
+                TypeKind::Reference(type_ref) => self.resolve(type_ref.name.node),
+                _ => self.encode_type_code(ty),
+            })
+            .collect();
+        info.push(format!("extends = {}", lua_string_list(extends)));
+
+        let mut members = Vec::new();
+        let mut methods = Vec::new();
+        for member in iface_decl.members.iter() {
+            match member {
+                InterfaceMember::Property(prop) => members.push(format!(
+                    "{{ name = \"{}\", type = \"{}\", optional = {}, readonly = {} }}",
+                    self.resolve(prop.name.node),
+                    self.encode_type_code(&prop.type_annotation),
+                    prop.is_optional,
+                    prop.is_readonly
+                )),
+                InterfaceMember::Method(method) => {
+                    let params: String = method
+                        .parameters
+                        .iter()
+                        .map(|p| {
+                            p.type_annotation
+                                .as_ref()
+                                .map(|ty| self.encode_type_code(ty))
+                                .unwrap_or_else(|| "o".to_string())
+                        })
+                        .collect();
+                    methods.push(format!(
+                        "{{ name = \"{}\", params = \"{}\", ret = \"{}\" }}",
+                        self.resolve(method.name.node),
+                        params,
+                        self.encode_type_code(&method.return_type)
+                    ));
+                }
+                InterfaceMember::Index(_) => {}
+            }
+        }
+        info.push(format!("members = {}", lua_table(&members)));
+        info.push(format!("methods = {}", lua_table(&methods)));
+
+        self.register_type(&interface_name, type_id, ReflectedKind::Interface, info);
+    }
+
+    /// Record a type alias and the type code it stands for
+    pub(super) fn reflect_type_alias(&mut self, alias: &TypeAliasDeclaration) {
+        if !self.should_emit_reflection() {
+            return;
+        }
+        let alias_name = self.resolve(alias.name.node);
+        let type_id = self.allocate_type_id();
+
+        let mut info: Vec<String> = self
+            .type_params_entry(alias.type_parameters)
+            .into_iter()
+            .collect();
+        info.push(format!(
+            "type = \"{}\"",
+            self.encode_type_code(&alias.type_annotation)
+        ));
+
+        self.register_type(&alias_name, type_id, ReflectedKind::Alias, info);
+    }
+
+    /// Record a generic instantiation such as `Box<n>` the first time its
+    /// type code is encoded
+    pub(super) fn register_generic_instantiation(
+        &mut self,
+        code: &str,
+        base: &str,
+        arguments: &[String],
+    ) {
+        if !self.should_emit_reflection() || self.registered_types.contains_key(code) {
+            return;
+        }
+        let type_id = self.allocate_type_id();
+        let info = vec![
+            format!("base = \"{}\"", base),
+            format!("args = {}", lua_string_list(arguments.iter().cloned())),
+        ];
+        self.register_type(code, type_id, ReflectedKind::Generic, info);
+    }
+
+    /// Emit `__TypeRegistry`, `__TypeIdToClass`, `__TypeInfo` and the
+    /// `Reflect` module for everything registered in this module
+    pub(super) fn generate_type_registry(&mut self) {
+        self.writeln("");
+        self.writeln("-- ============================================================");
+        self.writeln("-- Type Registry for Reflection");
+        self.writeln("-- ============================================================");
+
+        // Use `local` to avoid polluting global scope (strict mode compatibility)
+        self.writeln("local __TypeRegistry = {}");
+        self.writeln("local __TypeIdToClass = {}");
+        self.writeln("local __TypeInfo = {}");
+        self.writeln("");
+
+        let mut entries: Vec<(String, RegisteredType)> = self
+            .registered_types
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.clone()))
+            .collect();
+        entries.sort_by_key(|(_, entry)| entry.id);
+
+        // Populate __TypeRegistry (name -> id mapping)
+        for (type_name, entry) in &entries {
+            self.writeln(&format!("__TypeRegistry[\"{}\"] = {}", type_name, entry.id));
+        }
+        self.writeln("");
+
+        // Populate __TypeIdToClass (id -> runtime value); instantiations share their base
+        for (type_name, entry) in &entries {
+            let value = match entry.kind {
+                ReflectedKind::Class | ReflectedKind::Enum => Some(type_name.as_str()),
+                ReflectedKind::Generic => {
+                    let base = generic_base(type_name);
+                    self.registered_types
+                        .get(base)
+                        .filter(|b| matches!(b.kind, ReflectedKind::Class | ReflectedKind::Enum))
+                        .map(|_| base)
+                }
+                ReflectedKind::Interface | ReflectedKind::Alias => None,
+            };
+            if let Some(value) = value {
+                self.writeln(&format!("__TypeIdToClass[{}] = {}", entry.id, value));
+            }
+        }
+        self.writeln("");
+
+        // Populate __TypeInfo (id -> descriptor)
+        for (type_name, entry) in &entries {
+            let mut fields = vec![
+                format!("kind = \"{}\"", entry.kind.as_str()),
+                format!("name = \"{}\"", type_name),
+            ];
+            fields.extend(entry.info.iter().cloned());
+            self.writeln(&format!(
+                "__TypeInfo[{}] = {{ {} }}",
+                entry.id,
+                fields.join(", ")
+            ));
+        }
+        self.writeln("");
+
+        // Generate Reflect module from runtime
+        self.writeln(reflection::REFLECTION_MODULE);
+    }
+}
+
+/// Base type name of a generic instantiation code (`Box<n>` → `Box`)
+fn generic_base(code: &str) -> &str {
+    code.split('<').next().unwrap_or(code)
+}
+
+/// `{ "a", "b" }`
+fn lua_string_list(items: impl IntoIterator<Item = String>) -> String {
+    let quoted: Vec<String> = items
+        .into_iter()
+        .map(|item| format!("\"{}\"", item))
+        .collect();
+    lua_table(&quoted)
+}
+
+/// `{ a, b }`, or `{}` when empty
+fn lua_table(items: &[String]) -> String {
+    if items.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{ {} }}", items.join(", "))
+    }
+}
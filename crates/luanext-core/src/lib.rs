@@ -1,10 +1,13 @@
 // Keep core-specific modules
 pub mod arena;
+pub mod assets;
 pub mod cache;
 pub mod codegen;
 pub mod di;
+pub mod migrate;
 pub mod optimizer;
 pub mod type_checker;
+pub mod typings;
 
 // Re-export arena for convenience
 pub use arena::Arena;
@@ -0,0 +1,356 @@
+//! `setmetatable`-based classes to `class` declarations.
+//!
+//! Recognized shape:
+//!
+//! ```lua
+//! local Point = {}
+//! Point.__index = Point
+//!
+//! function Point.new(x, y)
+//!     local self = setmetatable({}, Point)
+//!     self.x = x
+//!     return self
+//! end
+//!
+//! function Point:len() ... end
+//! ```
+//!
+//! An optional `setmetatable(Point, { __index = Base })` becomes `extends Base`,
+//! in which case the constructor must start with `local self = Base.new(...)`
+//! followed by `setmetatable(self, Point)`.
+
+use super::scanner::{self, code_text, map_code, LineInfo};
+use super::{leading_whitespace, split_params, Migration};
+use std::collections::HashSet;
+
+const MEMBER_INDENT: &str = "    ";
+const BODY_INDENT: &str = "        ";
+
+/// Kind of class member, from the function header form
+enum MemberKind {
+    Constructor,
+    Method,
+    Static,
+}
+
+struct Member {
+    kind: MemberKind,
+    name: String,
+    /// First and last line of the function block
+    start: usize,
+    end: usize,
+}
+
+pub(super) fn convert(migration: &mut Migration) {
+    let mut skipped: HashSet<String> = HashSet::new();
+    while let Some(name) = next_candidate(migration, &skipped) {
+        if !convert_class(migration, &name) {
+            skipped.insert(name);
+        }
+        migration.flush_notes();
+    }
+}
+
+/// Next table declared with `local X = {}` and marked with `X.__index = X`
+fn next_candidate(migration: &Migration, skipped: &HashSet<String>) -> Option<String> {
+    let info = migration.scan();
+    let top_level: Vec<&str> = migration
+        .lines
+        .iter()
+        .zip(&info)
+        .filter(|(_, li)| li.depth_before == 0)
+        .map(|(line, li)| code_text(line, li))
+        .collect();
+    top_level.iter().find_map(|code| {
+        let name = code.strip_prefix("local ")?.strip_suffix("= {}")?.trim();
+        let marked = top_level.contains(&format!("{}.__index = {}", name, name).as_str());
+        (scanner::is_identifier(name) && marked && !skipped.contains(name))
+            .then(|| name.to_string())
+    })
+}
+
+/// Convert class `name`; returns false (with a note) when the shape is not recognized
+fn convert_class(migration: &mut Migration, name: &str) -> bool {
+    let info = migration.scan();
+    let lines = migration.lines.clone();
+    let code = |i: usize| code_text(&lines[i], &info[i]);
+
+    let decl = match (0..lines.len())
+        .find(|&i| info[i].depth_before == 0 && code(i) == format!("local {} = {{}}", name))
+    {
+        Some(decl) => decl,
+        None => return false,
+    };
+
+    let mut remove: HashSet<usize> = HashSet::new();
+    let mut static_fields: Vec<usize> = Vec::new();
+    let mut members: Vec<Member> = Vec::new();
+    let mut base: Option<String> = None;
+
+    let method_prefix = format!("function {}:", name);
+    let static_prefix = format!("function {}.", name);
+    let mut i = 0;
+    while i < lines.len() {
+        if info[i].depth_before != 0 {
+            i += 1;
+            continue;
+        }
+        let text = code(i);
+        if text == format!("{}.__index = {}", name, name) {
+            remove.insert(i);
+        } else if let Some(b) = parse_base(text, name) {
+            base = Some(b);
+            remove.insert(i);
+        } else if let Some(rest) = text
+            .strip_prefix(&method_prefix)
+            .or_else(|| text.strip_prefix(&static_prefix))
+        {
+            let member_name = rest.split('(').next().unwrap_or("").trim().to_string();
+            let kind = if text.starts_with(&method_prefix) {
+                MemberKind::Method
+            } else if member_name == "new" {
+                MemberKind::Constructor
+            } else {
+                MemberKind::Static
+            };
+            let end = scanner::block_end(&info, i);
+            members.push(Member {
+                kind,
+                name: member_name,
+                start: i,
+                end,
+            });
+            i = end + 1;
+            continue;
+        } else if text.starts_with(&format!("{}.", name)) && text.contains('=') {
+            static_fields.push(i);
+        }
+        i += 1;
+    }
+
+    let mut class_lines = vec![match &base {
+        Some(base) => format!("class {} extends {} {{", name, base),
+        None => format!("class {} {{", name),
+    }];
+
+    for (n, member) in members.iter().enumerate() {
+        let header = code(member.start);
+        let (params, inline_rest) = match split_params(header) {
+            Some(parts) => parts,
+            None => {
+                migration.note(member.start, "could not parse function header");
+                return false;
+            }
+        };
+        let mut body = member_body(&lines, &info, member, inline_rest);
+
+        let signature = match member.kind {
+            MemberKind::Constructor => {
+                if !rewrite_constructor(&mut body, name, base.as_deref()) {
+                    let expected = match &base {
+                        Some(base) => format!(
+                            "`local self = {}.new(...)` and `setmetatable(self, {})`",
+                            base, name
+                        ),
+                        None => format!("`local self = setmetatable({{}}, {})`", name),
+                    };
+                    migration.note(
+                        member.start,
+                        &format!(
+                            "constructor does not follow the {} pattern; class `{}` not converted",
+                            expected, name
+                        ),
+                    );
+                    return false;
+                }
+                format!("constructor({})", params)
+            }
+            MemberKind::Method => format!("{}({})", member.name, params),
+            MemberKind::Static => format!("static {}({})", member.name, params),
+        };
+
+        if n > 0 {
+            class_lines.push(String::new());
+        }
+        class_lines.push(format!("{}{} {{", MEMBER_INDENT, signature));
+        class_lines.extend(body);
+        class_lines.push(format!("{}}}", MEMBER_INDENT));
+        remove.extend(member.start..=member.end);
+    }
+    class_lines.push("}".to_string());
+
+    // Rebuild the file with the class at the declaration
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    let mut static_notes: Vec<usize> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i == decl {
+            out.extend(class_lines.iter().cloned());
+        } else if !remove.contains(&i) {
+            super::push_line(&mut out, line.clone());
+            if static_fields.contains(&i) {
+                static_notes.push(out.len() - 1);
+            }
+        }
+    }
+    super::trim_trailing_blank_lines(&mut out);
+    migration.lines = out;
+    migration.pending.clear();
+    for index in static_notes {
+        migration.note(
+            index,
+            &format!("static field of `{}` left outside the class", name),
+        );
+    }
+
+    // `X.new(...)` calls become `new X(...)`, and `return X` a default export
+    let info = migration.scan();
+    let last_code = (0..migration.lines.len())
+        .rev()
+        .find(|&i| !code_text(&migration.lines[i], &info[i]).is_empty());
+    for (i, (line, line_info)) in migration.lines.iter_mut().zip(&info).enumerate() {
+        if Some(i) == last_code && code_text(line, line_info) == format!("return {}", name) {
+            *line = format!("export default {}", name);
+        } else {
+            *line = map_code(line, line_info, |code| rewrite_new_calls(code, name));
+        }
+    }
+    true
+}
+
+/// Base class from `setmetatable(X, { __index = Base })`
+fn parse_base(code: &str, name: &str) -> Option<String> {
+    let base = code
+        .strip_prefix("setmetatable(")?
+        .strip_suffix(')')?
+        .strip_prefix(name)?
+        .trim_start()
+        .strip_prefix(',')?
+        .trim()
+        .strip_prefix('{')?
+        .strip_suffix('}')?
+        .trim()
+        .strip_prefix("__index")?
+        .trim_start()
+        .strip_prefix('=')?
+        .trim();
+    scanner::is_identifier(base).then(|| base.to_string())
+}
+
+/// Rewrite `X.new(...)` calls in a code fragment to `new X(...)`
+pub(super) fn rewrite_new_calls(code: &str, name: &str) -> String {
+    let call = format!("{}.new(", name);
+    let mut out = String::with_capacity(code.len());
+    let mut rest = code;
+    while let Some(at) = rest.find(&call) {
+        let boundary = at == 0 || {
+            let prev = rest.as_bytes()[at - 1];
+            !scanner::is_ident_byte(prev) && prev != b'.' && prev != b':'
+        };
+        out.push_str(&rest[..at]);
+        if boundary {
+            out.push_str(&format!("new {}(", name));
+        } else {
+            out.push_str(&call);
+        }
+        rest = &rest[at + call.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Body lines of a member, re-indented for the class
+fn member_body(
+    lines: &[String],
+    info: &[LineInfo],
+    member: &Member,
+    inline_rest: &str,
+) -> Vec<String> {
+    if member.start == member.end {
+        // One-line function: `function X:f() return 1 end`
+        let body = inline_rest
+            .trim()
+            .strip_suffix("end")
+            .unwrap_or(inline_rest)
+            .trim();
+        return if body.is_empty() {
+            Vec::new()
+        } else {
+            vec![format!("{}{}", BODY_INDENT, body)]
+        };
+    }
+
+    let mut body: Vec<&str> = Vec::new();
+    let rest = inline_rest.trim();
+    if !rest.is_empty() {
+        body.push(rest);
+    }
+    body.extend(
+        lines[member.start + 1..member.end]
+            .iter()
+            .map(String::as_str),
+    );
+    let closing = code_text(&lines[member.end], &info[member.end]);
+    let before_end = closing.strip_suffix("end").unwrap_or("").trim();
+    if !before_end.is_empty() {
+        body.push(before_end);
+    }
+
+    let common = body
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| leading_whitespace(line).len())
+        .min()
+        .unwrap_or(0);
+    body.iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                let stripped = line.get(common..).unwrap_or(line.trim_start());
+                format!("{}{}", BODY_INDENT, stripped)
+            }
+        })
+        .collect()
+}
+
+/// Remove the instance creation and `return self` from a constructor body
+fn rewrite_constructor(body: &mut Vec<String>, name: &str, base: Option<&str>) -> bool {
+    let first = match body.iter().position(|line| !line.trim().is_empty()) {
+        Some(first) => first,
+        None => return false,
+    };
+    match base {
+        None => {
+            let create = format!("local self = setmetatable({{}}, {})", name);
+            if body[first].trim() != create {
+                return false;
+            }
+            body.remove(first);
+        }
+        Some(base) => {
+            let call = body[first].trim();
+            let args = match call
+                .strip_prefix(&format!("local self = {}.new(", base))
+                .and_then(|rest| rest.strip_suffix(')'))
+            {
+                Some(args) => args.to_string(),
+                None => return false,
+            };
+            let attach = format!("setmetatable(self, {})", name);
+            let attach_at = match body.iter().position(|line| line.trim() == attach) {
+                Some(at) => at,
+                None => return false,
+            };
+            body.remove(attach_at);
+            body[first] = format!("{}super({})", BODY_INDENT, args);
+        }
+    }
+
+    match body.iter().rposition(|line| !line.trim().is_empty()) {
+        Some(last) if body[last].trim() == "return self" => {
+            body.remove(last);
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,98 @@
+//! `local x = require("a.b")` to `import` declarations.
+//!
+//! Requires of migrated project modules become relative imports: a namespace
+//! import for modules with named exports, a default import for modules ending
+//! in `export default`. `X.new(...)` calls on an imported class become
+//! `new X(...)`. Requires of modules outside the project stay as they
+//! are, since their types come from declaration files.
+
+use super::classes::rewrite_new_calls;
+use super::scanner::{self, code_text, contains_word, map_code};
+use super::{Migration, ModuleShape};
+use crate::optimizer::analysis::module_graph::compute_relative_require_path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub(super) fn convert(
+    migration: &mut Migration,
+    path: &Path,
+    index: &HashMap<String, (PathBuf, ModuleShape)>,
+) {
+    let info = migration.scan();
+    let mut classes: Vec<String> = Vec::new();
+    for (i, line_info) in info.iter().enumerate() {
+        let line = &migration.lines[i];
+        let calls_require = line_info
+            .code
+            .iter()
+            .any(|range| contains_word(&line[range.clone()], "require"));
+        if !calls_require {
+            continue;
+        }
+        let code = code_text(line, line_info).to_string();
+
+        let parsed = parse_require(&code);
+        let (name, module) = match parsed {
+            Some(parsed) if line_info.depth_before == 0 => parsed,
+            Some(_) => {
+                migration.note(i, "`require` inside a block not converted to an import");
+                continue;
+            }
+            None => {
+                migration.note(i, "`require` not in `local x = require(\"...\")` form");
+                continue;
+            }
+        };
+
+        let (target, shape) = match index.get(module) {
+            Some(entry) => entry,
+            // External module: keep the require so it resolves as before
+            None => continue,
+        };
+        // Anchor both at a common root so sibling files get a `./` path
+        let root = Path::new("/");
+        let source = compute_relative_require_path(&root.join(path), &root.join(target));
+        let indent = super::leading_whitespace(&migration.lines[i]).to_string();
+        migration.lines[i] = match shape {
+            ModuleShape::Namespace => {
+                format!("{}import * as {} from \"{}\"", indent, name, source)
+            }
+            ModuleShape::Default | ModuleShape::Class => {
+                format!("{}import {} from \"{}\"", indent, name, source)
+            }
+        };
+        if *shape == ModuleShape::Class {
+            classes.push(name.to_string());
+        }
+    }
+    migration.flush_notes();
+
+    // `X.new(...)` on an imported class becomes `new X(...)`
+    if !classes.is_empty() {
+        let info = migration.scan();
+        for (line, line_info) in migration.lines.iter_mut().zip(&info) {
+            *line = map_code(line, line_info, |code| {
+                classes.iter().fold(code.to_string(), |code, name| {
+                    rewrite_new_calls(&code, name)
+                })
+            });
+        }
+    }
+}
+
+/// `(name, module)` of `local name = require("module")` or `require "module"`
+fn parse_require(code: &str) -> Option<(&str, &str)> {
+    let (name, call) = code.strip_prefix("local ")?.split_once('=')?;
+    let name = name.trim();
+    if !scanner::is_identifier(name) {
+        return None;
+    }
+    let arg = call.trim().strip_prefix("require")?.trim();
+    let arg = match arg.strip_prefix('(') {
+        Some(inner) => inner.strip_suffix(')')?.trim(),
+        None => arg,
+    };
+    let quote = arg.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let module = arg.strip_prefix(quote)?.strip_suffix(quote)?;
+    (!module.contains(quote)).then_some((name, module))
+}
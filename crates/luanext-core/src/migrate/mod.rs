@@ -0,0 +1,316 @@
+//! Migration of plain Lua sources to LuaNext.
+//!
+//! LuaNext accepts plain Lua, so a migrated file is the original source with
+//! recognizable idioms rewritten:
+//!
+//! - `setmetatable`-based classes (`X.__index = X` with `X.new` and `X:method`)
+//!   become `class` declarations
+//! - module tables (`local M = {}` ... `return M`) become `export`s
+//! - `local x = require("a.b")` becomes an `import` of the migrated module
+//! - locals initialized with a literal get a type annotation when every
+//!   assignment agrees on the type
+//!
+//! Anything that looks like one of these idioms but cannot be converted safely
+//! is left as is and marked with a [`NOTE_MARKER`] comment. The markers are
+//! collected into the [`MigrationReport`].
+
+mod classes;
+mod imports;
+mod modules;
+pub(crate) mod scanner;
+mod types;
+
+use scanner::LineInfo;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Comment prefix marking code that needs manual attention
+pub const NOTE_MARKER: &str = "-- luanext-migrate:";
+
+/// How a migrated module exposes its value to importers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleShape {
+    /// Named exports, imported with `import * as x from`
+    Namespace,
+    /// A default export, imported with `import x from`
+    Default,
+    /// A class as the default export; importers' `X.new(...)` calls become `new X(...)`
+    Class,
+}
+
+/// A Lua source file to migrate
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// Path relative to the project root, used to resolve `require` names
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// A point in a migrated file that needs manual attention
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationNote {
+    /// 1-based line in the migrated file
+    pub line: usize,
+    pub message: String,
+}
+
+/// Result of migrating one file
+#[derive(Debug, Clone)]
+pub struct MigratedFile {
+    /// Original path with a `.luax` extension
+    pub path: PathBuf,
+    pub source: String,
+    pub notes: Vec<MigrationNote>,
+}
+
+/// Result of migrating a project
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    pub files: Vec<MigratedFile>,
+}
+
+impl MigrationReport {
+    /// Total number of notes across all files
+    pub fn note_count(&self) -> usize {
+        self.files.iter().map(|f| f.notes.len()).sum()
+    }
+
+    /// Render the report as Markdown
+    pub fn render(&self) -> String {
+        let mut out = String::from("# LuaNext migration report\n\n");
+        out.push_str(&format!(
+            "Migrated {} file(s); {} item(s) need manual attention.\n",
+            self.files.len(),
+            self.note_count()
+        ));
+        for file in self.files.iter().filter(|f| !f.notes.is_empty()) {
+            out.push_str(&format!("\n## {}\n\n", file.path.display()));
+            for note in &file.notes {
+                out.push_str(&format!("- line {}: {}\n", note.line, note.message));
+            }
+        }
+        out
+    }
+}
+
+/// Migrate a set of Lua files that `require` each other.
+pub fn migrate_project(files: &[SourceFile]) -> MigrationReport {
+    // Structural passes first: their result decides how each module is imported
+    let mut migrations: Vec<Migration> = files
+        .iter()
+        .map(|file| {
+            let mut migration = Migration::new(&file.source);
+            classes::convert(&mut migration);
+            modules::convert(&mut migration);
+            migration
+        })
+        .collect();
+
+    let index: HashMap<String, (PathBuf, ModuleShape)> = files
+        .iter()
+        .zip(&migrations)
+        .flat_map(|(file, migration)| {
+            let shape = migration.shape();
+            module_names(&file.path)
+                .into_iter()
+                .map(move |name| (name, (file.path.clone(), shape)))
+        })
+        .collect();
+
+    let migrated = files
+        .iter()
+        .zip(migrations.iter_mut())
+        .map(|(file, migration)| {
+            imports::convert(migration, &file.path, &index);
+            check_unsupported(migration);
+            types::annotate(migration);
+            migration.finish(file.path.with_extension("luax"))
+        })
+        .collect();
+
+    MigrationReport { files: migrated }
+}
+
+/// Migrate a single file with no other project modules to resolve against
+pub fn migrate_source(path: &Path, source: &str) -> MigratedFile {
+    let file = SourceFile {
+        path: path.to_path_buf(),
+        source: source.to_string(),
+    };
+    migrate_project(&[file])
+        .files
+        .pop()
+        .expect("one file in, one file out")
+}
+
+/// `require` names a file can be loaded by: `a/b.lua` is `a.b`, `a/init.lua` is `a`
+fn module_names(path: &Path) -> Vec<String> {
+    let without_ext = path.with_extension("");
+    let parts: Vec<String> = without_ext
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    let mut names = vec![parts.join(".")];
+    if parts.last().map(String::as_str) == Some("init") && parts.len() > 1 {
+        names.push(parts[..parts.len() - 1].join("."));
+    }
+    names
+}
+
+/// Flag constructs that have no LuaNext counterpart
+fn check_unsupported(migration: &mut Migration) {
+    let info = migration.scan();
+    for (i, line) in migration.lines.clone().iter().enumerate() {
+        let code = scanner::code_text(line, &info[i]);
+        if code.starts_with("module(") || code.starts_with("module ") {
+            migration.note(i, "`module()` is not supported; convert to exports by hand");
+        }
+        for keyword in RESERVED {
+            let declares = code.starts_with(&format!("local {} ", keyword))
+                || code.starts_with(&format!("local function {}(", keyword))
+                || code.starts_with(&format!("function {}(", keyword));
+            if declares {
+                migration.note(
+                    i,
+                    &format!(
+                        "`{}` is a LuaNext keyword; rename this declaration",
+                        keyword
+                    ),
+                );
+            }
+        }
+    }
+    migration.flush_notes();
+}
+
+/// Identifiers that are valid Lua names but LuaNext keywords
+const RESERVED: &[&str] = &[
+    "class",
+    "interface",
+    "enum",
+    "import",
+    "export",
+    "const",
+    "new",
+    "extends",
+    "implements",
+    "namespace",
+    "declare",
+    "match",
+    "throw",
+    "try",
+    "catch",
+];
+
+/// Lines of a file being migrated, with notes pending insertion
+struct Migration {
+    lines: Vec<String>,
+    /// (line index, message), inserted as marker comments by `flush_notes`
+    pending: Vec<(usize, String)>,
+}
+
+impl Migration {
+    fn new(source: &str) -> Self {
+        Migration {
+            lines: source.lines().map(String::from).collect(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn scan(&self) -> Vec<LineInfo> {
+        scanner::scan_lines(&self.lines)
+    }
+
+    /// Queue a note above line `index` of the current text
+    fn note(&mut self, index: usize, message: &str) {
+        self.pending.push((index, message.to_string()));
+    }
+
+    /// Insert queued notes as marker comments; call before lines move
+    fn flush_notes(&mut self) {
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+        for (index, message) in pending {
+            let indent = self
+                .lines
+                .get(index)
+                .map(|line| leading_whitespace(line).to_string())
+                .unwrap_or_default();
+            let marker = format!("{}{} {}", indent, NOTE_MARKER, message);
+            if !self.lines.contains(&marker) {
+                self.lines.insert(index.min(self.lines.len()), marker);
+            }
+        }
+    }
+
+    /// How importers see the module, from its default export if any
+    fn shape(&self) -> ModuleShape {
+        let default = self
+            .lines
+            .iter()
+            .find_map(|line| line.strip_prefix("export default "));
+        match default {
+            Some(name) => {
+                let class = format!("class {} ", name.trim());
+                if self.lines.iter().any(|line| line.starts_with(&class)) {
+                    ModuleShape::Class
+                } else {
+                    ModuleShape::Default
+                }
+            }
+            None => ModuleShape::Namespace,
+        }
+    }
+
+    fn finish(&mut self, path: PathBuf) -> MigratedFile {
+        self.flush_notes();
+        let notes = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                line.trim_start()
+                    .strip_prefix(NOTE_MARKER)
+                    .map(|message| MigrationNote {
+                        line: i + 1,
+                        message: message.trim().to_string(),
+                    })
+            })
+            .collect();
+        let mut source = self.lines.join("\n");
+        source.push('\n');
+        MigratedFile {
+            path,
+            source,
+            notes,
+        }
+    }
+}
+
+/// Append a line, dropping blank lines left behind by removed code
+fn push_line(out: &mut Vec<String>, line: String) {
+    let blank = line.trim().is_empty();
+    if !(blank && out.last().is_none_or(|prev| prev.trim().is_empty())) {
+        out.push(line);
+    }
+}
+
+fn trim_trailing_blank_lines(out: &mut Vec<String>) {
+    while out.last().is_some_and(|line| line.trim().is_empty()) {
+        out.pop();
+    }
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Text between the parentheses of the first `(` in `text`, and the rest after `)`
+fn split_params(text: &str) -> Option<(&str, &str)> {
+    let open = text.find('(')?;
+    let close = open + text[open..].find(')')?;
+    Some((&text[open + 1..close], &text[close + 1..]))
+}
@@ -0,0 +1,261 @@
+//! Module tables and trailing `return` statements to exports.
+//!
+//! ```lua
+//! local M = {}
+//! M.version = "1.0"
+//! function M.greet(name) return "hi " .. name end
+//! return M
+//! ```
+//!
+//! becomes `export const version = "1.0"` and `export function greet(name)`,
+//! with `M.x` references rewritten to `x`. A trailing `return { a = a }`
+//! becomes `export { a }`, and `return X` becomes `export default X`.
+
+use super::scanner::{self, code_text, contains_word, map_code, replace_qualified, LineInfo};
+use super::Migration;
+use std::collections::HashMap;
+
+pub(super) fn convert(migration: &mut Migration) {
+    let info = migration.scan();
+    let last = match last_code_line(&migration.lines, &info) {
+        Some(last) => last,
+        None => return,
+    };
+    let start = match return_start(&migration.lines, &info, last) {
+        Some(start) => start,
+        None => return,
+    };
+    let returned = code_text(&migration.lines[start], &info[start]).to_string();
+
+    let name = returned.strip_prefix("return ").map(str::trim);
+    match name {
+        Some(name) if start == last && scanner::is_identifier(name) => {
+            let declared = (0..start).find(|&i| {
+                info[i].depth_before == 0
+                    && code_text(&migration.lines[i], &info[i]) == format!("local {} = {{}}", name)
+            });
+            // A table marked with `X.__index = X` is a class the class pass left as is
+            let class_marker = format!("{}.__index = {}", name, name);
+            let is_class =
+                (0..start).any(|i| code_text(&migration.lines[i], &info[i]) == class_marker);
+            match declared {
+                Some(decl) if !is_class => convert_module_table(migration, name, decl, start),
+                _ => migration.lines[start] = format!("export default {}", name),
+            }
+        }
+        _ if returned.starts_with("return {") || returned.starts_with("return{") => {
+            convert_returned_table(migration, start, last)
+        }
+        _ => migration.note(start, "module return value not converted to exports"),
+    }
+    migration.flush_notes();
+}
+
+fn last_code_line(lines: &[String], info: &[LineInfo]) -> Option<usize> {
+    (0..lines.len())
+        .rev()
+        .find(|&i| !code_text(&lines[i], &info[i]).is_empty())
+}
+
+/// First line of the top-level `return` statement that ends on line `last`.
+///
+/// Only a returned table literal spans lines; its start is searched back to
+/// the `return {` line.
+fn return_start(lines: &[String], info: &[LineInfo], last: usize) -> Option<usize> {
+    let is_return = |i: usize| {
+        let code = code_text(&lines[i], &info[i]);
+        info[i].depth_before == 0
+            && (code == "return" || code.starts_with("return ") || code.starts_with("return{"))
+    };
+    if is_return(last) {
+        return Some(last);
+    }
+    if !code_text(&lines[last], &info[last]).ends_with('}') {
+        return None;
+    }
+    let start = (0..last).rev().find(|&i| is_return(i))?;
+    let code = code_text(&lines[start], &info[start]);
+    (code.starts_with("return {") || code.starts_with("return{")).then_some(start)
+}
+
+/// Convert `local M = {}` ... `return M`
+fn convert_module_table(migration: &mut Migration, name: &str, decl: usize, ret: usize) {
+    let info = migration.scan();
+    let lines = migration.lines.clone();
+    let code = |i: usize| code_text(&lines[i], &info[i]);
+
+    // Top-level `M.x = ...` and `function M.x(` declare exports, once each
+    let mut declared: HashMap<String, usize> = HashMap::new();
+    let mut blocked = false;
+    let function_prefix = format!("function {}.", name);
+    for i in 0..lines.len() {
+        if i == decl || i == ret {
+            continue;
+        }
+        let text = code(i);
+        if info[i].depth_before == 0 {
+            if text.starts_with(&format!("function {}:", name)) {
+                migration.note(
+                    i,
+                    &format!(
+                        "method-style function on module table `{}`; module not converted",
+                        name
+                    ),
+                );
+                blocked = true;
+                continue;
+            }
+            let field = text
+                .strip_prefix(&function_prefix)
+                .and_then(|rest| rest.split('(').next())
+                .or_else(|| assigned_field(text, name));
+            if let Some(field) = field {
+                let field = field.trim().to_string();
+                if declared.insert(field.clone(), i).is_some() {
+                    migration.note(
+                        i,
+                        &format!(
+                            "`{}.{}` is assigned more than once; module not converted",
+                            name, field
+                        ),
+                    );
+                    blocked = true;
+                }
+                continue;
+            }
+        } else if let Some(field) = assigned_field(text, name) {
+            migration.note(
+                i,
+                &format!(
+                    "`{}.{}` is assigned inside a block; module not converted",
+                    name, field
+                ),
+            );
+            blocked = true;
+            continue;
+        }
+
+        // Any use of the table other than `M.field` needs the table itself
+        let without_fields = info[i]
+            .code
+            .iter()
+            .map(|range| replace_qualified(&lines[i][range.clone()], name, |_| Some(String::new())))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if contains_word(&without_fields, name) {
+            migration.note(
+                i,
+                &format!(
+                    "module table `{}` is used as a value; module not converted",
+                    name
+                ),
+            );
+            blocked = true;
+        }
+    }
+    if blocked {
+        return;
+    }
+
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    let mut forward_refs: Vec<(usize, String)> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i == decl || i == ret {
+            continue;
+        }
+        let text = code(i);
+        let mut rewritten = if info[i].depth_before == 0 && text.starts_with(&function_prefix) {
+            line.replacen(&function_prefix, "export function ", 1)
+        } else if let Some(field) = (info[i].depth_before == 0)
+            .then(|| assigned_field(text, name))
+            .flatten()
+        {
+            line.replacen(
+                &format!("{}.{}", name, field),
+                &format!("export const {}", field),
+                1,
+            )
+        } else {
+            line.clone()
+        };
+
+        let rewritten_info = scanner::scan_lines(std::slice::from_ref(&rewritten));
+        rewritten = map_code(&rewritten, &rewritten_info[0], |fragment| {
+            replace_qualified(fragment, name, |field| {
+                if declared.get(field).is_some_and(|&at| at > i) {
+                    forward_refs.push((out.len(), field.to_string()));
+                }
+                Some(field.to_string())
+            })
+        });
+        super::push_line(&mut out, rewritten);
+    }
+    super::trim_trailing_blank_lines(&mut out);
+
+    migration.lines = out;
+    forward_refs.dedup();
+    for (index, field) in forward_refs {
+        migration.note(
+            index,
+            &format!(
+                "`{}` is used before its declaration; move the declaration up",
+                field
+            ),
+        );
+    }
+}
+
+/// `field` of a `M.field = value` statement
+fn assigned_field<'a>(code: &'a str, table: &str) -> Option<&'a str> {
+    let rest = code.strip_prefix(table)?.strip_prefix('.')?;
+    let (field, value) = rest.split_once('=')?;
+    let field = field.trim();
+    (scanner::is_identifier(field) && !value.starts_with('=')).then_some(field)
+}
+
+/// Convert a trailing `return { a = a, b = other }` into `export { a, other as b }`
+fn convert_returned_table(migration: &mut Migration, start: usize, last: usize) {
+    let info = migration.scan();
+    let text: String = (start..=last)
+        .map(|i| code_text(&migration.lines[i], &info[i]))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let fields = text
+        .strip_prefix("return")
+        .map(str::trim)
+        .and_then(|t| t.strip_prefix('{'))
+        .and_then(|t| t.strip_suffix('}'));
+
+    let specifiers: Option<Vec<String>> = fields.and_then(|fields| {
+        fields
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (key, value) = entry.split_once('=')?;
+                let (key, value) = (key.trim(), value.trim());
+                if !scanner::is_identifier(key) || !scanner::is_identifier(value) {
+                    return None;
+                }
+                Some(if key == value {
+                    key.to_string()
+                } else {
+                    format!("{} as {}", value, key)
+                })
+            })
+            .collect()
+    });
+
+    match specifiers {
+        Some(specifiers) => {
+            migration.lines.drain(start..=last);
+            migration
+                .lines
+                .insert(start, format!("export {{ {} }}", specifiers.join(", ")));
+        }
+        None => migration.note(
+            start,
+            "returned table has non-identifier fields; not converted to exports",
+        ),
+    }
+}
@@ -0,0 +1,295 @@
+//! Line-oriented Lua scanner used by the migration passes and `typings`.
+//!
+//! The passes rewrite plain Lua line by line, so they need to know per line
+//! the block depth and which byte ranges are code rather than strings or comments.
+
+use std::ops::Range;
+
+/// Scan result for one source line
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LineInfo {
+    /// Block depth before the first token of the line
+    pub depth_before: usize,
+    /// Block depth after the last token of the line
+    pub depth_after: usize,
+    /// Byte ranges holding code (outside strings and comments)
+    pub code: Vec<Range<usize>>,
+    /// End of the statement text: the start of a trailing comment, the line
+    /// length, or 0 for lines entirely inside a long string or comment
+    pub text_end: usize,
+}
+
+#[derive(Clone, Copy)]
+enum LongState {
+    None,
+    String(usize),
+    Comment(usize),
+}
+
+/// Scan every line of `source`, tracking block depth across lines.
+pub(crate) fn scan_lines(lines: &[String]) -> Vec<LineInfo> {
+    let mut depth = 0usize;
+    let mut long = LongState::None;
+    let mut result = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        let bytes = line.as_bytes();
+        let depth_before = depth;
+        let mut code = Vec::new();
+        let mut i = 0;
+        let mut code_start = 0;
+        let mut text_end = bytes.len();
+
+        // Continue a long string or comment from the previous line
+        if let LongState::String(level) | LongState::Comment(level) = long {
+            match find_long_close(bytes, 0, level) {
+                Some(end) => {
+                    long = LongState::None;
+                    i = end;
+                    code_start = end;
+                }
+                None => {
+                    result.push(LineInfo {
+                        depth_before,
+                        depth_after: depth,
+                        code,
+                        text_end: 0,
+                    });
+                    continue;
+                }
+            }
+        }
+
+        while i < bytes.len() {
+            let c = bytes[i];
+            if c == b'-' && bytes.get(i + 1) == Some(&b'-') {
+                push_range(&mut code, code_start, i);
+                code_start = bytes.len();
+                text_end = text_end.min(i);
+                if let Some(level) = long_open_level(bytes, i + 2) {
+                    let body = i + 2 + level + 2;
+                    match find_long_close(bytes, body, level) {
+                        Some(end) => {
+                            i = end;
+                            code_start = end;
+                            continue;
+                        }
+                        None => long = LongState::Comment(level),
+                    }
+                }
+                break;
+            } else if c == b'"' || c == b'\'' {
+                push_range(&mut code, code_start, i);
+                i = skip_short_string(bytes, i);
+                code_start = i;
+            } else if let Some(level) = long_open_level(bytes, i) {
+                push_range(&mut code, code_start, i);
+                match find_long_close(bytes, i + level + 2, level) {
+                    Some(end) => {
+                        i = end;
+                        code_start = end;
+                    }
+                    None => {
+                        long = LongState::String(level);
+                        code_start = bytes.len();
+                        break;
+                    }
+                }
+            } else if c.is_ascii_alphabetic() || c == b'_' {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                // Field names after `.` or `:` are never keywords in valid Lua
+                let is_field = start > 0 && matches!(bytes[start - 1], b'.' | b':');
+                if !is_field {
+                    match &line[start..i] {
+                        "function" | "if" | "do" | "repeat" => depth += 1,
+                        "end" | "until" => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                }
+            } else {
+                i += 1;
+            }
+        }
+        push_range(&mut code, code_start, bytes.len());
+
+        result.push(LineInfo {
+            depth_before,
+            depth_after: depth,
+            code,
+            text_end,
+        });
+    }
+
+    result
+}
+
+fn push_range(code: &mut Vec<Range<usize>>, start: usize, end: usize) {
+    if start < end {
+        code.push(start..end);
+    }
+}
+
+/// Level of a long bracket opening at `i` (`[[` is 0, `[==[` is 2)
+fn long_open_level(bytes: &[u8], i: usize) -> Option<usize> {
+    if bytes.get(i) != Some(&b'[') {
+        return None;
+    }
+    let mut level = 0;
+    while bytes.get(i + 1 + level) == Some(&b'=') {
+        level += 1;
+    }
+    (bytes.get(i + 1 + level) == Some(&b'[')).then_some(level)
+}
+
+/// Byte offset just past the long bracket close of `level`, searching from `from`
+fn find_long_close(bytes: &[u8], from: usize, level: usize) -> Option<usize> {
+    let mut i = from;
+    while i < bytes.len() {
+        if bytes[i] == b']' {
+            let mut j = i + 1;
+            let mut eq = 0;
+            while bytes.get(j) == Some(&b'=') {
+                eq += 1;
+                j += 1;
+            }
+            if eq == level && bytes.get(j) == Some(&b']') {
+                return Some(j + 1);
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Byte offset just past a quoted string starting at `start`
+fn skip_short_string(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Index of the line closing the block opened on line `start`
+pub(crate) fn block_end(info: &[LineInfo], start: usize) -> usize {
+    let depth = info[start].depth_before;
+    (start..info.len())
+        .find(|&i| info[i].depth_after <= depth)
+        .unwrap_or(info.len() - 1)
+}
+
+/// Line text with strings kept but comments removed, trimmed
+pub(crate) fn code_text<'a>(line: &'a str, info: &LineInfo) -> &'a str {
+    line[..info.text_end].trim()
+}
+
+/// Rewrite only the code ranges of a line
+pub(crate) fn map_code(line: &str, info: &LineInfo, mut f: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for range in &info.code {
+        out.push_str(&line[last..range.start]);
+        out.push_str(&f(&line[range.clone()]));
+        last = range.end;
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+/// Replace `table.field` accesses in a code fragment.
+///
+/// `replace` receives the field name and returns the replacement for the
+/// whole `table.field` expression, or `None` to keep it.
+pub(crate) fn replace_qualified(
+    code: &str,
+    table: &str,
+    mut replace: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let bytes = code.as_bytes();
+    let mut out = String::with_capacity(code.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let at_boundary = i == 0 || (!is_ident_byte(bytes[i - 1]) && bytes[i - 1] != b'.');
+        if at_boundary && code[i..].starts_with(table) && bytes.get(i + table.len()) == Some(&b'.')
+        {
+            let field_start = i + table.len() + 1;
+            let mut field_end = field_start;
+            while field_end < bytes.len() && is_ident_byte(bytes[field_end]) {
+                field_end += 1;
+            }
+            if field_end > field_start {
+                if let Some(replacement) = replace(&code[field_start..field_end]) {
+                    out.push_str(&replacement);
+                    i = field_end;
+                    continue;
+                }
+            }
+        }
+        let ch = code[i..].chars().next().unwrap_or(' ');
+        out.push(ch);
+        i += ch.len_utf8();
+    }
+    out
+}
+
+/// Whether `word` appears in a code fragment as a whole identifier
+pub(crate) fn contains_word(code: &str, word: &str) -> bool {
+    let bytes = code.as_bytes();
+    code.match_indices(word).any(|(i, _)| {
+        let before = i == 0 || !is_ident_byte(bytes[i - 1]);
+        let after = bytes.get(i + word.len()).is_none_or(|&b| !is_ident_byte(b));
+        before && after
+    })
+}
+
+pub(crate) fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut bytes = s.bytes();
+    matches!(bytes.next(), Some(b) if b.is_ascii_alphabetic() || b == b'_')
+        && bytes.all(is_ident_byte)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(source: &str) -> Vec<String> {
+        source.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_depth_tracking() {
+        let src = lines("function f()\n  if x then\n    return 1\n  end\nend\nlocal y = 2");
+        let info = scan_lines(&src);
+        let depths: Vec<usize> = info.iter().map(|l| l.depth_after).collect();
+        assert_eq!(depths, vec![1, 2, 2, 1, 0, 0]);
+        assert_eq!(block_end(&info, 0), 4);
+    }
+
+    #[test]
+    fn test_keywords_in_strings_and_comments_ignored() {
+        let src = lines("local s = \"end\" -- function\nlocal t = [[\nfunction\n]]\nx = 1");
+        let info = scan_lines(&src);
+        assert!(info.iter().all(|l| l.depth_after == 0));
+        assert_eq!(code_text(&src[0], &info[0]), "local s = \"end\"");
+        assert_eq!(code_text(&src[2], &info[2]), "");
+        assert!(info[2].code.is_empty());
+    }
+
+    #[test]
+    fn test_replace_qualified() {
+        let out = replace_qualified("M.add(M.x, N.M.y)", "M", |f| Some(f.to_string()));
+        assert_eq!(out, "add(x, N.M.y)");
+    }
+}
@@ -0,0 +1,103 @@
+//! Type annotations inferred from literals.
+//!
+//! `local x = 0` becomes `local x: number = 0` when every later `x = ...`
+//! assignment in the file also assigns a number literal or an expression of
+//! unknown type. An assignment of `nil` or a literal of another type leaves
+//! the local unannotated, since the declared type would reject it.
+
+use super::scanner::{self, code_text, LineInfo};
+use super::Migration;
+
+pub(super) fn annotate(migration: &mut Migration) {
+    let info = migration.scan();
+    for i in 0..migration.lines.len() {
+        let code = code_text(&migration.lines[i], &info[i]);
+        let (name, value) = match code
+            .strip_prefix("local ")
+            .and_then(|rest| rest.split_once('='))
+        {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => continue,
+        };
+        if !scanner::is_identifier(name) || value.starts_with('=') {
+            continue;
+        }
+        let ty = match literal_type(value) {
+            Some(ty) => ty,
+            None => continue,
+        };
+        if !assignments_agree(&migration.lines, &info, i, name, ty) {
+            continue;
+        }
+
+        // The code starts with `local name`, right after the indentation
+        let (name_len, annotation) = (name.len(), format!(": {}", ty));
+        let line = &mut migration.lines[i];
+        let at = super::leading_whitespace(line).len() + "local ".len() + name_len;
+        line.insert_str(at, &annotation);
+    }
+}
+
+/// Type of a number, string or boolean literal
+fn literal_type(value: &str) -> Option<&'static str> {
+    match value {
+        "true" | "false" => return Some("boolean"),
+        "nil" => return None,
+        _ => {}
+    }
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted && !value[1..value.len() - 1].contains(&value[..1]) {
+        return Some("string");
+    }
+    let number = value.strip_prefix('-').unwrap_or(value);
+    let is_number = number.parse::<f64>().is_ok()
+        || number
+            .strip_prefix("0x")
+            .or_else(|| number.strip_prefix("0X"))
+            .is_some_and(|hex| !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()));
+    (is_number && !number.starts_with(['+', 'i', 'I', 'n', 'N'])).then_some("number")
+}
+
+/// Whether every later `name = value` assignment is compatible with `ty`
+fn assignments_agree(
+    lines: &[String],
+    info: &[LineInfo],
+    decl: usize,
+    name: &str,
+    ty: &str,
+) -> bool {
+    let prefix = format!("{} =", name);
+    let end = if info[decl].depth_before == 0 {
+        lines.len()
+    } else {
+        // A block-local ends with its enclosing block
+        (decl..lines.len())
+            .find(|&i| info[i].depth_after < info[decl].depth_before)
+            .unwrap_or(lines.len())
+    };
+    for i in decl + 1..end {
+        let code = code_text(&lines[i], &info[i]);
+        if code.starts_with(&format!("local {} ", name))
+            || code.starts_with(&format!("local {}=", name))
+        {
+            // Shadowed: later assignments belong to the new local
+            break;
+        }
+        let value = code
+            .strip_prefix(&prefix)
+            .or_else(|| code.strip_prefix(&format!("{}=", name)))
+            .filter(|value| !value.starts_with('='))
+            .map(str::trim);
+        let compatible = match value {
+            Some("nil") => false,
+            Some(value) => literal_type(value).is_none_or(|other| other == ty),
+            None => true,
+        };
+        if !compatible {
+            return false;
+        }
+    }
+    true
+}
@@ -2,7 +2,8 @@ use crate::config::OptimizationLevel;
 use crate::MutableProgram;
 use bumpalo::Bump;
 
-use crate::optimizer::{StmtVisitor, WholeProgramPass};
+use crate::optimizer::hints::FreeFunctionHints;
+use crate::optimizer::{remarks, StmtVisitor, WholeProgramPass};
 use luanext_parser::ast::expression::{
     Argument, ArrowBody, Expression, ExpressionKind, MatchArmBody,
 };
@@ -19,6 +20,8 @@ use std::sync::Arc;
 
 use luanext_parser::ast::expression::ArrayElement;
 
+const PASS: &str = "aggressive-inlining";
+
 enum InlineResult<'arena> {
     Direct(Box<Expression<'arena>>),
     Replaced {
@@ -35,6 +38,7 @@ pub struct AggressiveInliningPass {
     next_temp_id: usize,
     interner: Option<Arc<StringInterner>>,
     hot_paths: HashSet<StringId>,
+    hints: Arc<FreeFunctionHints>,
 }
 
 impl Default for AggressiveInliningPass {
@@ -47,6 +51,7 @@ impl Default for AggressiveInliningPass {
             next_temp_id: 0,
             interner: None,
             hot_paths: HashSet::new(),
+            hints: Arc::default(),
         }
     }
 }
@@ -58,6 +63,13 @@ impl AggressiveInliningPass {
             ..Default::default()
         }
     }
+
+    /// Never inline `@noinline` functions; ignore the size threshold for
+    /// `@inline` ones
+    pub fn with_hints(mut self, hints: Arc<FreeFunctionHints>) -> Self {
+        self.hints = hints;
+        self
+    }
 }
 
 impl<'arena> StmtVisitor<'arena> for AggressiveInliningPass {
@@ -70,7 +82,7 @@ impl<'arena> StmtVisitor<'arena> for AggressiveInliningPass {
 
 impl<'arena> WholeProgramPass<'arena> for AggressiveInliningPass {
     fn name(&self) -> &'static str {
-        "aggressive-inlining"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -824,15 +836,30 @@ impl AggressiveInliningPass {
         if let ExpressionKind::Call(func, args, _) = &expr.kind.clone() {
             if let ExpressionKind::Identifier(func_name) = &func.kind {
                 if let Some(func_decl) = functions.get(func_name) {
-                    if self.is_aggressively_inlinable(func_decl, *func_name) {
+                    if let Some(reason) = self.inline_blocker(func_decl, *func_name) {
+                        remarks::missed(PASS, expr.span, || {
+                            format!("`{}` not inlined: {}", self.name_of(*func_name), reason)
+                        });
+                    } else {
                         let original_size = func_decl.body.statements.len();
                         let result = self.inline_call(func_decl.clone(), args, arena);
                         if let InlineResult::Replaced { ref stmts, .. } = result {
                             let inlined_size = stmts.len();
                             if self.would_exceed_bloat_guard(original_size, inlined_size) {
+                                remarks::missed(PASS, expr.span, || {
+                                    format!(
+                                        "`{}` not inlined: {} statements would exceed the {}x code bloat limit",
+                                        self.name_of(*func_name),
+                                        inlined_size,
+                                        self.max_code_bloat_ratio
+                                    )
+                                });
                                 return None;
                             }
                         }
+                        remarks::applied(PASS, expr.span, || {
+                            format!("inlined call to `{}`", self.name_of(*func_name))
+                        });
                         match &result {
                             InlineResult::Direct(inlined_expr) => {
                                 *expr = *inlined_expr.clone();
@@ -857,28 +884,47 @@ impl AggressiveInliningPass {
         ratio > self.max_code_bloat_ratio
     }
 
-    fn is_aggressively_inlinable<'arena>(
+    /// Why `func` cannot be inlined, or `None` if it can
+    fn inline_blocker<'arena>(
         &self,
         func: &FunctionDeclaration<'arena>,
         func_name: StringId,
-    ) -> bool {
+    ) -> Option<String> {
         if func.type_parameters.is_some() {
-            return false;
+            return Some("generic functions are left to generic specialization".to_string());
         }
-        if func.body.statements.len() > self.threshold {
-            return false;
+        let hints = self.hints.get(&self.name_of(func_name));
+        if let Some(reason) = hints.inline_blocker() {
+            return Some(reason.to_string());
+        }
+        if func.body.statements.len() > self.threshold && !hints.forces_inlining() {
+            return Some(format!(
+                "body has {} statements, threshold is {}",
+                func.body.statements.len(),
+                self.threshold
+            ));
         }
         if self.has_complex_control_flow(&func.body) {
-            return false;
+            return Some("body has loops or multiple returns".to_string());
         }
         let closure_size = self.count_closure_statements(&func.body);
         if closure_size > self.max_total_closure_size {
-            return false;
+            return Some(format!(
+                "closures in the body have {} statements, limit is {}",
+                closure_size, self.max_total_closure_size
+            ));
         }
         if self.is_recursive(func, func_name) && !self.hot_paths.contains(&func_name) {
-            return false;
+            return Some("function is recursive and not on a hot path".to_string());
         }
-        true
+        None
+    }
+
+    fn name_of(&self, id: StringId) -> String {
+        self.interner
+            .as_ref()
+            .map(|interner| interner.resolve(id).to_string())
+            .unwrap_or_default()
     }
 
     fn is_recursive<'arena>(
@@ -165,6 +165,14 @@ impl AliasAnalyzer {
         }
     }
 
+    /// Track `names` as reference values, as table-typed variables are, so
+    /// that returning, passing or copying them is recorded. Used for locals
+    /// holding closures.
+    pub fn with_reference_variables(mut self, names: impl IntoIterator<Item = StringId>) -> Self {
+        self.table_variables.extend(names);
+        self
+    }
+
     /// Analyze alias information from a statement list.
     pub fn analyze(mut self, statements: &[Statement<'_>]) -> AliasInfo {
         self.collect_locals(statements);
@@ -1063,7 +1063,7 @@ fn terminator_targets(term: &Terminator) -> Vec<BlockId> {
 }
 
 /// Extract the span from any statement variant.
-fn statement_span(stmt: &Statement<'_>) -> Span {
+pub(crate) fn statement_span(stmt: &Statement<'_>) -> Span {
     match stmt {
         Statement::Variable(d) => d.span,
         Statement::Function(d) => d.span,
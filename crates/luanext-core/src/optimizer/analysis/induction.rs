@@ -0,0 +1,360 @@
+//! Induction variable analysis for numeric `for` loops.
+//!
+//! An induction variable changes by the same amount on every iteration of a
+//! loop. The variable of `for i = start, limit, step` is one when `start` and
+//! `step` are integers, and so is any counter the body steps once per
+//! iteration:
+//!
+//! ```lua
+//! local k = 0
+//! for i = 1, n do
+//!     t[k] = i
+//!     k = k + 1
+//! end
+//! ```
+//!
+//! Counters are found on the SSA form of the loop body. A counter has a single
+//! definition in the body, `k = k + c` or `k += c` with an integer `c`, which
+//! reads the version of `k` coming from the previous iteration and whose block
+//! dominates the end of the body, so that every iteration that goes on to the
+//! next one runs it. Bodies with `continue`, `goto` or labels have no
+//! counters, as these may skip the update.
+//!
+//! Like `SsaForm`, the analysis only versions the top-level statements of the
+//! body. Callers must check that nested blocks and closures never assign a
+//! counter.
+
+use super::cfg::{BlockId, CfgBuilder};
+use super::dominance::DominatorTree;
+use super::ssa::{SsaForm, SsaVar};
+use luanext_parser::ast::expression::{
+    AssignmentOp, BinaryOp, Expression, ExpressionKind, Literal, UnaryOp,
+};
+use luanext_parser::ast::statement::{ForNumeric, ForStatement, Statement};
+use luanext_parser::string_interner::StringId;
+use rustc_hash::FxHashMap;
+
+/// A variable the loop body steps by a constant on every iteration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counter {
+    /// The variable.
+    pub name: StringId,
+    /// How much each iteration adds to it.
+    pub step: i64,
+    /// Index of the top-level body statement that updates it.
+    pub update: usize,
+}
+
+/// The induction variables of a numeric `for` loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopInduction {
+    /// The loop variable.
+    pub variable: StringId,
+    /// The value of the loop variable on the first iteration.
+    pub start: i64,
+    /// How much each iteration adds to the loop variable.
+    pub step: i64,
+    /// Counters stepped by the body, in the order of their updates.
+    pub counters: Vec<Counter>,
+}
+
+impl LoopInduction {
+    /// Analyze `for_num`, or `None` unless its start and step are integer
+    /// literals
+    pub fn analyze(for_num: &ForNumeric<'_>) -> Option<Self> {
+        let start = integer_literal(&for_num.start)?;
+        let step = match &for_num.step {
+            Some(step) => integer_literal(step)?,
+            None => 1,
+        };
+        if step == 0 {
+            return None;
+        }
+        let statements = for_num.body.statements;
+        let counters = if statements.iter().any(has_jumps) {
+            Vec::new()
+        } else {
+            find_counters(statements)
+        };
+        Some(LoopInduction {
+            variable: for_num.variable.node,
+            start,
+            step,
+            counters,
+        })
+    }
+
+    /// The counter named `name`, if the body steps one
+    pub fn counter(&self, name: StringId) -> Option<&Counter> {
+        self.counters.iter().find(|counter| counter.name == name)
+    }
+}
+
+/// The value of an integer literal, possibly negated
+pub fn integer_literal(expr: &Expression<'_>) -> Option<i64> {
+    match &expr.kind {
+        ExpressionKind::Literal(Literal::Integer(value)) => Some(*value),
+        ExpressionKind::Unary(UnaryOp::Negate, operand) => match &operand.kind {
+            ExpressionKind::Literal(Literal::Integer(value)) => value.checked_neg(),
+            _ => None,
+        },
+        ExpressionKind::Parenthesized(inner) => integer_literal(inner),
+        _ => None,
+    }
+}
+
+fn find_counters(statements: &[Statement<'_>]) -> Vec<Counter> {
+    let cfg = CfgBuilder::build(statements);
+    let dominators = DominatorTree::build(&cfg);
+    let ssa = SsaForm::build(&cfg, &dominators, statements);
+
+    let mut definitions: FxHashMap<StringId, Vec<usize>> = FxHashMap::default();
+    for (&index, defs) in &ssa.definitions {
+        for def in defs {
+            definitions.entry(def.name).or_default().push(index);
+        }
+    }
+
+    let mut counters: Vec<Counter> = definitions
+        .into_iter()
+        .filter_map(|(name, indices)| {
+            let [update] = indices[..] else {
+                return None;
+            };
+            let step = increment(&statements[update], name)?;
+            // Version 0 is the value the body starts with, which the previous
+            // iteration left behind
+            let incoming = SsaVar { name, version: 0 };
+            if !ssa.uses_at(update).contains(&incoming) {
+                return None;
+            }
+            let block = *cfg.stmt_to_block.get(&update)?;
+            dominators
+                .dominates(block, BlockId::EXIT)
+                .then_some(Counter { name, step, update })
+        })
+        .collect();
+    counters.sort_by_key(|counter| counter.update);
+    counters
+}
+
+/// How much `stmt` adds to `name`, when it is `name = name + c`,
+/// `name = c + name`, `name = name - c`, `name += c` or `name -= c`
+fn increment(stmt: &Statement<'_>, name: StringId) -> Option<i64> {
+    let Statement::Expression(expr) = stmt else {
+        return None;
+    };
+    let ExpressionKind::Assignment(target, op, value) = &expr.kind else {
+        return None;
+    };
+    if !is_identifier(target, name) {
+        return None;
+    }
+    match op {
+        AssignmentOp::AddAssign => integer_literal(value),
+        AssignmentOp::SubtractAssign => integer_literal(value)?.checked_neg(),
+        AssignmentOp::Assign => match &value.kind {
+            ExpressionKind::Binary(BinaryOp::Add, left, right) => {
+                if is_identifier(left, name) {
+                    integer_literal(right)
+                } else if is_identifier(right, name) {
+                    integer_literal(left)
+                } else {
+                    None
+                }
+            }
+            ExpressionKind::Binary(BinaryOp::Subtract, left, right)
+                if is_identifier(left, name) =>
+            {
+                integer_literal(right)?.checked_neg()
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_identifier(expr: &Expression<'_>, name: StringId) -> bool {
+    matches!(expr.kind, ExpressionKind::Identifier(id) if id == name)
+}
+
+/// Whether `stmt` has `continue`, `goto` or a label outside closures
+fn has_jumps(stmt: &Statement<'_>) -> bool {
+    match stmt {
+        Statement::Continue(_) | Statement::Goto(_) | Statement::Label(_) => true,
+        Statement::If(if_stmt) => {
+            if_stmt.then_block.statements.iter().any(has_jumps)
+                || if_stmt
+                    .else_ifs
+                    .iter()
+                    .any(|else_if| else_if.block.statements.iter().any(has_jumps))
+                || if_stmt
+                    .else_block
+                    .as_ref()
+                    .is_some_and(|block| block.statements.iter().any(has_jumps))
+        }
+        Statement::While(while_stmt) => while_stmt.body.statements.iter().any(has_jumps),
+        Statement::Repeat(repeat_stmt) => repeat_stmt.body.statements.iter().any(has_jumps),
+        Statement::For(for_stmt) => match &**for_stmt {
+            ForStatement::Numeric(for_num) => for_num.body.statements.iter().any(has_jumps),
+            ForStatement::Generic(for_gen) => for_gen.body.statements.iter().any(has_jumps),
+        },
+        Statement::Block(block) => block.statements.iter().any(has_jumps),
+        Statement::Try(try_stmt) => {
+            try_stmt.try_block.statements.iter().any(has_jumps)
+                || try_stmt
+                    .catch_clauses
+                    .iter()
+                    .any(|catch| catch.body.statements.iter().any(has_jumps))
+                || try_stmt
+                    .finally_block
+                    .as_ref()
+                    .is_some_and(|block| block.statements.iter().any(has_jumps))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bumpalo::Bump;
+    use luanext_parser::ast::pattern::Pattern;
+    use luanext_parser::ast::statement::{Block, VariableDeclaration, VariableKind};
+    use luanext_parser::ast::Ident;
+    use luanext_parser::span::Span;
+    use luanext_parser::string_interner::StringInterner;
+
+    fn ident(interner: &StringInterner, name: &str) -> Ident {
+        Ident {
+            node: interner.get_or_intern(name),
+            span: Span::dummy(),
+        }
+    }
+
+    fn int<'a>(value: i64) -> Expression<'a> {
+        Expression::new(
+            ExpressionKind::Literal(Literal::Integer(value)),
+            Span::dummy(),
+        )
+    }
+
+    fn var<'a>(interner: &StringInterner, name: &str) -> Expression<'a> {
+        Expression::new(
+            ExpressionKind::Identifier(interner.get_or_intern(name)),
+            Span::dummy(),
+        )
+    }
+
+    /// `name = name + by`
+    fn bump<'a>(arena: &'a Bump, interner: &StringInterner, name: &str, by: i64) -> Statement<'a> {
+        let sum = Expression::new(
+            ExpressionKind::Binary(
+                BinaryOp::Add,
+                arena.alloc(var(interner, name)),
+                arena.alloc(int(by)),
+            ),
+            Span::dummy(),
+        );
+        Statement::Expression(Expression::new(
+            ExpressionKind::Assignment(
+                arena.alloc(var(interner, name)),
+                AssignmentOp::Assign,
+                arena.alloc(sum),
+            ),
+            Span::dummy(),
+        ))
+    }
+
+    fn declare<'a>(interner: &StringInterner, name: &str) -> Statement<'a> {
+        Statement::Variable(VariableDeclaration {
+            kind: VariableKind::Local,
+            pattern: Pattern::Identifier(ident(interner, name)),
+            type_annotation: None,
+            initializer: int(0),
+            span: Span::dummy(),
+        })
+    }
+
+    fn for_loop<'a>(
+        arena: &'a Bump,
+        interner: &StringInterner,
+        start: i64,
+        step: Option<i64>,
+        body: &[Statement<'a>],
+    ) -> ForNumeric<'a> {
+        ForNumeric {
+            variable: ident(interner, "i"),
+            start: int(start),
+            end: int(10),
+            step: step.map(int),
+            body: Block {
+                statements: arena.alloc_slice_clone(body),
+                span: Span::dummy(),
+            },
+            span: Span::dummy(),
+        }
+    }
+
+    #[test]
+    fn test_loop_variable_with_default_step() {
+        let arena = Bump::new();
+        let interner = StringInterner::new();
+        let for_num = for_loop(&arena, &interner, 1, None, &[]);
+        let induction = LoopInduction::analyze(&for_num).unwrap();
+        assert_eq!(induction.start, 1);
+        assert_eq!(induction.step, 1);
+        assert!(induction.counters.is_empty());
+    }
+
+    #[test]
+    fn test_zero_step_is_not_an_induction() {
+        let arena = Bump::new();
+        let interner = StringInterner::new();
+        let for_num = for_loop(&arena, &interner, 1, Some(0), &[]);
+        assert!(LoopInduction::analyze(&for_num).is_none());
+    }
+
+    #[test]
+    fn test_counter_stepped_every_iteration() {
+        let arena = Bump::new();
+        let interner = StringInterner::new();
+        let body = [declare(&interner, "x"), bump(&arena, &interner, "k", 2)];
+        let for_num = for_loop(&arena, &interner, 0, Some(2), &body);
+        let induction = LoopInduction::analyze(&for_num).unwrap();
+        assert_eq!(
+            induction.counters,
+            vec![Counter {
+                name: interner.get_or_intern("k"),
+                step: 2,
+                update: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_counter_stepped_twice_is_not_a_counter() {
+        let arena = Bump::new();
+        let interner = StringInterner::new();
+        let body = [
+            bump(&arena, &interner, "k", 1),
+            bump(&arena, &interner, "k", 1),
+        ];
+        let for_num = for_loop(&arena, &interner, 1, None, &body);
+        let induction = LoopInduction::analyze(&for_num).unwrap();
+        assert!(induction.counters.is_empty());
+    }
+
+    #[test]
+    fn test_counter_skipped_by_continue_is_not_a_counter() {
+        let arena = Bump::new();
+        let interner = StringInterner::new();
+        let body = [
+            Statement::Continue(Span::dummy()),
+            bump(&arena, &interner, "k", 1),
+        ];
+        let for_num = for_loop(&arena, &interner, 1, None, &body);
+        let induction = LoopInduction::analyze(&for_num).unwrap();
+        assert!(induction.counters.is_empty());
+    }
+}
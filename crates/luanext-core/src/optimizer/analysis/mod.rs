@@ -8,6 +8,7 @@
 //! CFG (needs only AST)
 //!  └─> Dominance (needs CFG)
 //!       └─> SSA (needs CFG + Dominance)
+//!            └─> Induction Variables (needs SSA, per numeric `for` loop)
 //!
 //! Alias Analysis (needs only AST, independent)
 //! Side-Effect Analysis (needs only AST, independent)
@@ -20,6 +21,7 @@
 pub mod alias;
 pub mod cfg;
 pub mod dominance;
+pub mod induction;
 pub mod module_graph;
 pub mod side_effect;
 pub mod ssa;
@@ -27,6 +29,7 @@ pub mod ssa;
 pub use alias::{AliasAnalyzer, AliasInfo, AliasResult, MemoryLocation};
 pub use cfg::{BasicBlock, BlockId, CfgBuilder, ControlFlowGraph, Terminator};
 pub use dominance::DominatorTree;
+pub use induction::{Counter, LoopInduction};
 pub use module_graph::{
     ExportInfo, ImportInfo, ModuleGraph, ModuleNode, ReExportInfo, ReExportKind,
 };
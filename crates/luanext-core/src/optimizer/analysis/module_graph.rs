@@ -1,3 +1,4 @@
+use crate::codegen::code_splitting::collect_dynamic_imports;
 use luanext_parser::ast::statement::{
     ExportDeclaration, ExportKind, ImportClause, ImportDeclaration, Statement,
 };
@@ -30,6 +31,9 @@ pub struct ModuleNode {
     /// Re-exports (export * from, export { x } from)
     pub re_exports: Vec<ReExportInfo>,
 
+    /// Modules loaded lazily with a dynamic `import("...")` expression
+    pub dynamic_imports: Vec<PathBuf>,
+
     /// Whether this module is reachable from any entry point
     pub is_reachable: bool,
 }
@@ -109,6 +113,10 @@ impl ModuleGraph {
             exports: FxHashMap::default(),
             imports: FxHashMap::default(),
             re_exports: Vec::new(),
+            dynamic_imports: collect_dynamic_imports(statements, interner)
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
             is_reachable: false,
         };
 
@@ -375,6 +383,7 @@ impl ModuleGraph {
         // Collect all resolutions first to avoid borrow issues
         let mut import_resolutions: Vec<(PathBuf, String, PathBuf)> = Vec::new();
         let mut reexport_resolutions: Vec<(PathBuf, usize, PathBuf)> = Vec::new();
+        let mut dynamic_resolutions: Vec<(PathBuf, usize, PathBuf)> = Vec::new();
 
         for (module_path, node) in &self.modules {
             let parent = module_path
@@ -401,6 +410,16 @@ impl ModuleGraph {
                     reexport_resolutions.push((module_path.clone(), idx, resolved));
                 }
             }
+
+            // Resolve dynamic import source paths
+            for (idx, source) in node.dynamic_imports.iter().enumerate() {
+                let source_str = source.to_string_lossy();
+                if let Some(resolved) =
+                    resolve_relative_source(&parent, &source_str, &known_modules)
+                {
+                    dynamic_resolutions.push((module_path.clone(), idx, resolved));
+                }
+            }
         }
 
         // Apply import resolutions
@@ -420,6 +439,15 @@ impl ModuleGraph {
                 }
             }
         }
+
+        // Apply dynamic import resolutions
+        for (module_path, idx, resolved) in dynamic_resolutions {
+            if let Some(node) = self.modules.get_mut(&module_path) {
+                if let Some(source) = node.dynamic_imports.get_mut(idx) {
+                    *source = resolved;
+                }
+            }
+        }
     }
 
     /// DFS from entry points to mark reachable modules
@@ -454,6 +482,9 @@ impl ModuleGraph {
                 deps.push(re.source_module.clone());
             }
 
+            // Dynamically imported modules are loaded at runtime too
+            deps.extend(node.dynamic_imports.iter().cloned());
+
             deps
         } else {
             Vec::new()
@@ -482,11 +513,19 @@ impl ModuleGraph {
             }
         }
 
+        // A dynamic import returns the whole export table, so every export counts
+        let dynamically_imported: FxHashSet<PathBuf> = self
+            .modules
+            .values()
+            .flat_map(|node| node.dynamic_imports.iter().cloned())
+            .collect();
+
         // Mark exports as used if they're imported
         for node in self.modules.values_mut() {
+            let whole_module = dynamically_imported.contains(&node.path);
             for export in node.exports.values_mut() {
                 let key = (node.path.clone(), export.name.clone());
-                if export_usage.contains_key(&key) {
+                if whole_module || export_usage.contains_key(&key) {
                     export.is_used = true;
                 }
             }
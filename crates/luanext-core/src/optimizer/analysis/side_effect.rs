@@ -11,8 +11,9 @@
 //! - Exception throwing
 //! - Environment access (_ENV, getfenv, setfenv)
 
-use luanext_parser::ast::expression::ExpressionKind;
-use luanext_parser::ast::statement::{Block, ForStatement, Statement};
+use crate::optimizer::hints::FunctionHints;
+use luanext_parser::ast::expression::{ExpressionKind, ReceiverClassInfo};
+use luanext_parser::ast::statement::{Block, ClassMember, ExportKind, ForStatement, Statement};
 use luanext_parser::string_interner::{StringId, StringInterner};
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::sync::Arc;
@@ -94,6 +95,8 @@ pub struct SideEffectInfo {
     pub read_only_functions: FxHashSet<StringId>,
     /// Known pure builtins (math.*, string.*, etc.).
     pub known_pure_builtins: FxHashSet<StringId>,
+    /// Class methods marked `@pure`, keyed by (class, method).
+    pub pure_methods: FxHashSet<(StringId, StringId)>,
 }
 
 impl SideEffectInfo {
@@ -107,6 +110,11 @@ impl SideEffectInfo {
         self.read_only_functions.contains(&name) || self.known_pure_builtins.contains(&name)
     }
 
+    /// Query whether a class method is declared `@pure`.
+    pub fn is_pure_method(&self, class: StringId, method: StringId) -> bool {
+        self.pure_methods.contains(&(class, method))
+    }
+
     /// Get the side effects of a function, if analyzed.
     pub fn effects(&self, name: StringId) -> Option<&SideEffects> {
         self.function_effects.get(&name)
@@ -128,6 +136,8 @@ pub struct SideEffectAnalyzer {
     known_env: FxHashSet<StringId>,
     /// Known error function names.
     known_error: FxHashSet<StringId>,
+    /// Class methods marked `@pure`, keyed by (class, method).
+    pure_methods: FxHashSet<(StringId, StringId)>,
 }
 
 impl SideEffectAnalyzer {
@@ -240,6 +250,7 @@ impl SideEffectAnalyzer {
             known_io,
             known_env,
             known_error,
+            pure_methods: FxHashSet::default(),
         }
     }
 
@@ -247,6 +258,12 @@ impl SideEffectAnalyzer {
     pub fn analyze(mut self, statements: &[Statement<'_>]) -> SideEffectInfo {
         let mut function_effects: FxHashMap<StringId, SideEffects> = FxHashMap::default();
 
+        // Methods declared `@pure` are trusted without looking at their bodies,
+        // so collect them before any call site is classified
+        for stmt in statements {
+            self.collect_pure_methods(stmt);
+        }
+
         // First pass: collect function names and analyze each function body
         for stmt in statements {
             if let Statement::Function(func) = stmt {
@@ -300,6 +317,27 @@ impl SideEffectAnalyzer {
             pure_functions,
             read_only_functions,
             known_pure_builtins: self.known_pure,
+            pure_methods: self.pure_methods,
+        }
+    }
+
+    /// Record the methods of a class that are marked `@pure`.
+    fn collect_pure_methods(&mut self, stmt: &Statement<'_>) {
+        let class = match stmt {
+            Statement::Class(class) => class,
+            Statement::Export(export) => match &export.kind {
+                ExportKind::Declaration(Statement::Class(class)) => class,
+                _ => return,
+            },
+            _ => return,
+        };
+        for member in class.members.iter() {
+            if let ClassMember::Method(method) = member {
+                if FunctionHints::from_decorators(method.decorators, &self.interner).pure {
+                    self.pure_methods
+                        .insert((class.name.node, method.name.node));
+                }
+            }
         }
     }
 
@@ -466,7 +504,12 @@ impl SideEffectAnalyzer {
                     effects.merge(&self.analyze_expression(&arg.value));
                 }
                 // Method calls on unknown objects are side-effectful
-                self.classify_method_call(obj, method.node, &mut effects);
+                self.classify_method_call(
+                    obj,
+                    method.node,
+                    expr.receiver_class.as_ref(),
+                    &mut effects,
+                );
             }
 
             ExpressionKind::Binary(_, left, right) => {
@@ -582,7 +625,12 @@ impl SideEffectAnalyzer {
                 for arg in args.iter() {
                     effects.merge(&self.analyze_expression(&arg.value));
                 }
-                self.classify_method_call(obj, method.node, &mut effects);
+                self.classify_method_call(
+                    obj,
+                    method.node,
+                    expr.receiver_class.as_ref(),
+                    &mut effects,
+                );
             }
 
             ExpressionKind::Template(template) => {
@@ -629,7 +677,11 @@ impl SideEffectAnalyzer {
             }
             ExpressionKind::Member(obj, member) => {
                 // Handle module.function pattern (e.g., math.sin, io.write)
-                if let ExpressionKind::Identifier(_) = &obj.kind {
+                if let ExpressionKind::Identifier(class_name) = &obj.kind {
+                    if self.pure_methods.contains(&(*class_name, member.node)) {
+                        // `Class.method(...)` on a method declared @pure
+                        return;
+                    }
                     let qualified = self
                         .interner
                         .get_or_intern(&format!("{}.{}", "?", member.node));
@@ -653,9 +705,16 @@ impl SideEffectAnalyzer {
     fn classify_method_call(
         &self,
         _obj: &luanext_parser::ast::expression::Expression<'_>,
-        _method: StringId,
+        method: StringId,
+        receiver: Option<&ReceiverClassInfo>,
         effects: &mut SideEffects,
     ) {
+        // Calls on a receiver of known class to a method declared @pure
+        // have no effects beyond evaluating their arguments
+        if receiver.is_some_and(|info| self.pure_methods.contains(&(info.class_name, method))) {
+            return;
+        }
+
         // Method calls are conservatively side-effectful
         // (the method may modify self or have other effects)
         effects.calls_unknown = true;
@@ -0,0 +1,230 @@
+//! Miscompile bisection
+//!
+//! Finds the optimizer transformation that makes a program misbehave. The
+//! caller supplies a check that runs the generated Lua; bisection then
+//! 1. enables the passes one at a time in pipeline order until the check
+//!    fails, which names the first failing pass, and
+//! 2. with that pass prefix enabled, binary-searches the number of
+//!    transformations the optimizer may apply until the smallest failing
+//!    count is found.
+//!
+//! A transformation is one pass run changing one top-level statement, counted
+//! in execution order across passes and fixed-point iterations. Under a
+//! limit, changes past the limit are reverted statement by statement, like
+//! LLVM's `-opt-bisect-limit`. A pass run that adds or removes top-level
+//! statements counts as a single transformation.
+
+use super::pipeline::{OptimizationGoal, PassPipelineConfig};
+use super::{remarks, Optimizer};
+use crate::codegen::{CodeGenerator, LuaTarget};
+use crate::config::{OptimizationLevel, OutputFormat};
+use crate::diagnostics::DiagnosticHandler;
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::StringInterner;
+use std::sync::Arc;
+
+/// One transformation the optimizer applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transformation {
+    /// 1-based position in execution order
+    pub index: usize,
+    /// The passes of the run that made it; several when they share a traversal
+    pub passes: Vec<&'static str>,
+    /// Fixed-point iteration, starting at 1
+    pub iteration: usize,
+    /// Span of the top-level statement it changed
+    pub span: Span,
+}
+
+/// Caps how many transformations `Optimizer::optimize` applies
+pub(crate) struct TransformationBudget {
+    remaining: usize,
+    applied: usize,
+    last: Option<Transformation>,
+    generator: CodeGenerator,
+}
+
+impl TransformationBudget {
+    pub(crate) fn new(limit: usize, interner: Arc<StringInterner>) -> Self {
+        Self {
+            remaining: limit,
+            applied: 0,
+            last: None,
+            generator: CodeGenerator::new(interner).with_output_format(OutputFormat::Minified),
+        }
+    }
+
+    pub(crate) fn exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+
+    pub(crate) fn applied(&self) -> usize {
+        self.applied
+    }
+
+    pub(crate) fn last(&self) -> Option<&Transformation> {
+        self.last.as_ref()
+    }
+
+    /// Keep as many of the changes `passes` made to `before` as the budget
+    /// allows and revert the rest of `program`
+    pub(crate) fn charge<'arena>(
+        &mut self,
+        passes: &[&'static str],
+        iteration: usize,
+        before: MutableProgram<'arena>,
+        program: &mut MutableProgram<'arena>,
+    ) {
+        if before.statements.len() != program.statements.len() {
+            if self.exhausted() {
+                *program = before;
+                return;
+            }
+            let span = program
+                .statements
+                .first()
+                .map_or(program.span, remarks::statement_span);
+            self.record(passes, iteration, span);
+            return;
+        }
+
+        let changed: Vec<usize> = (0..program.statements.len())
+            .filter(|&i| {
+                self.generator.statement_to_string(&before.statements[i])
+                    != self.generator.statement_to_string(&program.statements[i])
+            })
+            .collect();
+
+        let kept = changed.len().min(self.remaining);
+        for &i in &changed[kept..] {
+            program.statements[i] = before.statements[i].clone();
+        }
+        for &i in &changed[..kept] {
+            self.record(
+                passes,
+                iteration,
+                remarks::statement_span(&program.statements[i]),
+            );
+        }
+    }
+
+    fn record(&mut self, passes: &[&'static str], iteration: usize, span: Span) {
+        self.remaining -= 1;
+        self.applied += 1;
+        self.last = Some(Transformation {
+            index: self.applied,
+            passes: passes.to_vec(),
+            iteration,
+            span,
+        });
+    }
+}
+
+/// Outcome of a bisection
+#[derive(Debug, Clone)]
+pub struct BisectResult {
+    /// Passes enabled when the check first fails, the culprit last
+    pub passes: Vec<&'static str>,
+    /// Transformations those passes apply without a limit
+    pub total_transformations: usize,
+    /// Smallest number of transformations that fails the check
+    pub failing_transformations: usize,
+    /// The last of those transformations, which flips the check
+    pub culprit: Option<Transformation>,
+    /// Generated Lua with exactly `failing_transformations` applied
+    pub failing_lua: String,
+}
+
+impl BisectResult {
+    /// The first pass whose addition fails the check
+    pub fn culprit_pass(&self) -> &'static str {
+        self.passes.last().copied().unwrap_or_default()
+    }
+}
+
+/// Bisect the optimizer pipeline for the transformation that fails `check`.
+///
+/// `program` itself is left untouched. `emit` turns an optimized copy into
+/// Lua and `check` runs it, returning whether it behaves correctly. Fails
+/// when the unoptimized program already fails the check or the fully
+/// optimized one passes it.
+#[allow(clippy::too_many_arguments)]
+pub fn bisect<'arena>(
+    program: &MutableProgram<'arena>,
+    arena: &'arena Bump,
+    level: OptimizationLevel,
+    goal: OptimizationGoal,
+    target: LuaTarget,
+    config: &PassPipelineConfig,
+    handler: Arc<dyn DiagnosticHandler>,
+    interner: Arc<StringInterner>,
+    emit: &mut dyn FnMut(&MutableProgram<'arena>) -> String,
+    check: &mut dyn FnMut(&str) -> bool,
+) -> Result<BisectResult, String> {
+    if !check(&emit(program)) {
+        return Err("the check already fails without optimization".to_string());
+    }
+
+    let mut full = Optimizer::new(level, handler.clone(), interner.clone());
+    full.set_goal(goal);
+    full.set_target(target);
+    full.set_pass_pipeline(config.clone());
+    let pipeline: Vec<&'static str> = full.pass_pipeline().iter().map(|p| p.name).collect();
+
+    // Runs the first `pass_count` passes, applying at most `limit`
+    // transformations, and returns the optimizer and the generated Lua
+    let mut run =
+        |pass_count: usize, limit: usize| -> Result<(Optimizer<'arena>, String), String> {
+            let mut step_config = config.clone();
+            step_config
+                .disable
+                .extend(pipeline[pass_count..].iter().map(|name| name.to_string()));
+
+            let mut optimizer = Optimizer::new(level, handler.clone(), interner.clone());
+            optimizer.set_goal(goal);
+            optimizer.set_target(target);
+            optimizer.set_pass_pipeline(step_config);
+            optimizer.set_transformation_limit(limit);
+
+            let mut optimized = program.clone();
+            optimizer.optimize(&mut optimized, arena)?;
+            let lua = emit(&optimized);
+            Ok((optimizer, lua))
+        };
+
+    let mut failing_prefix = None;
+    for pass_count in 1..=pipeline.len() {
+        let (optimizer, lua) = run(pass_count, usize::MAX)?;
+        if !check(&lua) {
+            failing_prefix = Some((pass_count, optimizer.transformations_applied()));
+            break;
+        }
+    }
+    let Some((pass_count, total_transformations)) = failing_prefix else {
+        return Err("the check passes with every optimizer pass enabled".to_string());
+    };
+
+    // Zero transformations is the unoptimized program, which passes
+    let mut passing = 0;
+    let mut failing = total_transformations;
+    while failing - passing > 1 {
+        let mid = passing + (failing - passing) / 2;
+        let (_, lua) = run(pass_count, mid)?;
+        if check(&lua) {
+            passing = mid;
+        } else {
+            failing = mid;
+        }
+    }
+
+    let (optimizer, failing_lua) = run(pass_count, failing)?;
+    Ok(BisectResult {
+        passes: pipeline[..pass_count].to_vec(),
+        total_transformations,
+        failing_transformations: failing,
+        culprit: optimizer.last_transformation().cloned(),
+        failing_lua,
+    })
+}
@@ -536,7 +536,9 @@ use bumpalo::Bump;
 use luanext_parser::string_interner::StringInterner;
 use std::sync::Arc;
 
-use super::{AstFeatures, WholeProgramPass};
+use super::{remarks, AstFeatures, WholeProgramPass};
+
+const PASS: &str = "devirtualization";
 
 /// Devirtualization optimization pass (O3).
 ///
@@ -544,12 +546,14 @@ use super::{AstFeatures, WholeProgramPass};
 /// allows safe devirtualization. Currently a stub during arena migration.
 pub struct DevirtualizationPass {
     class_hierarchy: Option<ClassHierarchy>,
+    interner: Arc<StringInterner>,
 }
 
 impl DevirtualizationPass {
-    pub fn new(_interner: Arc<StringInterner>) -> Self {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
         Self {
             class_hierarchy: None,
+            interner,
         }
     }
 
@@ -724,9 +728,26 @@ impl DevirtualizationPass {
                         let (can_devirt, target_class) =
                             hierarchy.can_devirtualize_with_rta(class_id, method_id);
 
-                        if can_devirt {
+                        if !can_devirt {
+                            remarks::missed(PASS, expr.span, || {
+                                format!(
+                                    "`{}:{}` not devirtualized: a subclass may override it",
+                                    self.interner.resolve(class_id),
+                                    self.interner.resolve(method_id)
+                                )
+                            });
+                        } else {
                             // Determine which class to use
                             let effective_class = target_class.unwrap_or(class_id);
+                            remarks::applied(PASS, expr.span, || {
+                                format!(
+                                    "devirtualized `{}:{}` to a direct call to `{}.{}`",
+                                    self.interner.resolve(class_id),
+                                    self.interner.resolve(method_id),
+                                    self.interner.resolve(effective_class),
+                                    self.interner.resolve(method_id)
+                                )
+                            });
 
                             // Build the devirtualized call: ClassName.methodName(obj, ...)
                             let class_expr = Expression {
@@ -1075,7 +1096,7 @@ impl DevirtualizationPass {
 
 impl<'arena> WholeProgramPass<'arena> for DevirtualizationPass {
     fn name(&self) -> &'static str {
-        "devirtualization"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -0,0 +1,234 @@
+//! Per-function optimization hints
+//!
+//! The built-in decorators `@inline`, `@noinline`, `@optimize("none" | "size" |
+//! "speed")` and `@pure` let authors override the optimizer's heuristics for a
+//! single method or operator. They carry no runtime behavior: codegen strips
+//! them from the output, and passes read them through [`FunctionHints`].
+//!
+//! The grammar only allows decorators on classes and class members, so free
+//! functions get the same hints by name from the `optimizer.hints` section of
+//! `luanext.config.yaml` ([`FreeFunctionHints`]):
+//!
+//! ```yaml
+//! optimizer:
+//!   hints:
+//!     onDamage: [noinline]
+//!     lerp: [inline, pure]
+//!     buildTables: ['optimize("none")']
+//!     squares: [comptime]
+//! ```
+//!
+//! `comptime` exists only there: it opts a top-level function into
+//! compile-time evaluation (see `passes/comptime_evaluation.rs`), which never
+//! looks at methods.
+
+use luanext_parser::ast::expression::{ExpressionKind, Literal};
+use luanext_parser::ast::statement::{Decorator, DecoratorExpression};
+use luanext_parser::string_interner::StringInterner;
+use rustc_hash::FxHashMap;
+use std::path::Path;
+
+/// Whether `name` is one of the optimization hint decorators
+pub fn is_hint_decorator(name: &str) -> bool {
+    matches!(name, "inline" | "noinline" | "optimize" | "pure")
+}
+
+/// The hint a decorator names, accepting both `@inline` and `@LuaNext.inline`
+pub fn hint_name(expr: &DecoratorExpression, interner: &StringInterner) -> Option<String> {
+    let name = match expr {
+        DecoratorExpression::Identifier(name) => interner.resolve(name.node).to_string(),
+        DecoratorExpression::Call { callee, .. } => return hint_name(callee, interner),
+        DecoratorExpression::Member {
+            object, property, ..
+        } => {
+            let DecoratorExpression::Identifier(object) = object else {
+                return None;
+            };
+            if interner.resolve(object.node) != "LuaNext" {
+                return None;
+            }
+            interner.resolve(property.node).to_string()
+        }
+    };
+    is_hint_decorator(&name).then_some(name)
+}
+
+/// Inlining preference requested by `@inline` / `@noinline`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InlineHint {
+    /// Let the pass's heuristics decide
+    #[default]
+    Default,
+    /// Inline at every eligible call site, ignoring size limits
+    Always,
+    /// Never inline, e.g. because the function is patched at runtime
+    Never,
+}
+
+/// Optimization goal requested by `@optimize(...)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OptimizeHint {
+    /// Use the global optimization level
+    #[default]
+    Default,
+    /// Leave the function and its call sites untouched
+    None,
+    /// Avoid transformations that duplicate code
+    Size,
+    /// Favor speed over code size
+    Speed,
+}
+
+/// Optimization hints attached to a single function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FunctionHints {
+    pub inline: InlineHint,
+    pub optimize: OptimizeHint,
+    /// The function has no side effects and its result depends only on its
+    /// arguments
+    pub pure: bool,
+    /// Calls with constant arguments may be evaluated at compile time
+    pub comptime: bool,
+}
+
+impl FunctionHints {
+    /// Read the hint decorators out of a decorator list. Unknown decorators
+    /// and malformed `@optimize` arguments are ignored.
+    pub fn from_decorators(decorators: &[Decorator], interner: &StringInterner) -> Self {
+        let mut hints = FunctionHints::default();
+        for decorator in decorators {
+            let Some(name) = hint_name(&decorator.expression, interner) else {
+                continue;
+            };
+            match (name.as_str(), &decorator.expression) {
+                ("inline", _) => hints.inline = InlineHint::Always,
+                ("noinline", _) => hints.inline = InlineHint::Never,
+                ("pure", _) => hints.pure = true,
+                ("optimize", DecoratorExpression::Call { arguments, .. }) => {
+                    if let Some(ExpressionKind::Literal(Literal::String(mode))) =
+                        arguments.first().map(|arg| &arg.kind)
+                    {
+                        hints.optimize = match mode.as_str() {
+                            "none" => OptimizeHint::None,
+                            "size" => OptimizeHint::Size,
+                            "speed" => OptimizeHint::Speed,
+                            _ => hints.optimize,
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+        hints
+    }
+
+    /// Whether call sites of this function may be replaced by its body
+    pub fn allows_inlining(&self) -> bool {
+        self.inline != InlineHint::Never && self.optimize != OptimizeHint::None
+    }
+
+    /// Whether size limits should be ignored when inlining this function
+    pub fn forces_inlining(&self) -> bool {
+        self.allows_inlining()
+            && (self.inline == InlineHint::Always || self.optimize == OptimizeHint::Speed)
+    }
+
+    /// Why inlining is blocked, for optimization remarks
+    pub fn inline_blocker(&self) -> Option<&'static str> {
+        if self.inline == InlineHint::Never {
+            Some("marked @noinline")
+        } else if self.optimize == OptimizeHint::None {
+            Some("marked @optimize(\"none\")")
+        } else {
+            None
+        }
+    }
+
+    /// Why specializing copies of the function is blocked, for optimization
+    /// remarks. Clones bypass runtime patches just like inlining does, and
+    /// they duplicate code.
+    pub fn clone_blocker(&self) -> Option<&'static str> {
+        self.inline_blocker().or_else(|| {
+            (self.optimize == OptimizeHint::Size).then_some("marked @optimize(\"size\")")
+        })
+    }
+
+    /// Apply one hint written as its decorator name without `@`, e.g.
+    /// `noinline` or `optimize("size")`
+    fn apply(&mut self, hint: &str) -> Result<(), String> {
+        match hint.trim() {
+            "inline" => self.inline = InlineHint::Always,
+            "noinline" => self.inline = InlineHint::Never,
+            "pure" => self.pure = true,
+            "comptime" => self.comptime = true,
+            other => {
+                let mode = other
+                    .strip_prefix("optimize(")
+                    .and_then(|rest| rest.strip_suffix(')'))
+                    .map(|mode| mode.trim().trim_matches('"'));
+                self.optimize = match mode {
+                    Some("none") => OptimizeHint::None,
+                    Some("size") => OptimizeHint::Size,
+                    Some("speed") => OptimizeHint::Speed,
+                    _ => return Err(format!("unknown hint `{}`", other)),
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hints for free functions, keyed by function name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FreeFunctionHints {
+    by_name: FxHashMap<String, FunctionHints>,
+}
+
+impl FreeFunctionHints {
+    /// Read `optimizer.hints` from the contents of a config file.
+    ///
+    /// Returns no hints when the section is absent.
+    pub fn from_config_str(contents: &str) -> Result<Self, String> {
+        let root: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+        let Some(section) = root
+            .get("optimizer")
+            .and_then(|section| section.get("hints"))
+        else {
+            return Ok(FreeFunctionHints::default());
+        };
+        let names: FxHashMap<String, Vec<String>> = serde_yaml::from_value(section.clone())
+            .map_err(|_| "optimizer.hints must map function names to lists of hints".to_string())?;
+
+        let mut hints = FreeFunctionHints::default();
+        for (function, list) in names {
+            let mut function_hints = FunctionHints::default();
+            for hint in &list {
+                function_hints
+                    .apply(hint)
+                    .map_err(|e| format!("optimizer.hints.{}: {}", function, e))?;
+            }
+            hints.insert(function, function_hints);
+        }
+        Ok(hints)
+    }
+
+    /// Read `optimizer.hints` from a config file on disk.
+    pub fn from_config_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_config_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn insert(&mut self, function: impl Into<String>, hints: FunctionHints) {
+        self.by_name.insert(function.into(), hints);
+    }
+
+    /// Hints for the free function `name`; the defaults when it has none
+    pub fn get(&self, name: &str) -> FunctionHints {
+        self.by_name.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
@@ -21,7 +21,8 @@ use crate::config::OptimizationLevel;
 use crate::MutableProgram;
 use bumpalo::Bump;
 
-use crate::optimizer::{StmtVisitor, WholeProgramPass};
+use crate::optimizer::hints::FunctionHints;
+use crate::optimizer::{remarks, StmtVisitor, WholeProgramPass};
 use luanext_parser::ast::expression::{AssignmentOp, Expression, ExpressionKind};
 use luanext_parser::ast::pattern::Pattern;
 use luanext_parser::ast::statement::{
@@ -36,6 +37,8 @@ use std::sync::Arc;
 
 const MAX_INLINABLE_STATEMENTS: usize = 10;
 
+const PASS: &str = "interface-method-inlining";
+
 #[derive(Debug, Default)]
 struct InterfaceImplementationMap<'arena> {
     interface_to_classes: FxHashMap<StringId, Vec<StringId>>,
@@ -43,12 +46,13 @@ struct InterfaceImplementationMap<'arena> {
     class_is_final: FxHashMap<StringId, bool>,
     method_body: FxHashMap<(StringId, StringId), Block<'arena>>,
     method_signature: FxHashMap<(StringId, StringId), Spanned<StringId>>,
+    method_hints: FxHashMap<(StringId, StringId), FunctionHints>,
     known_classes: FxHashMap<StringId, bool>,
     known_interfaces: FxHashMap<StringId, bool>,
 }
 
 impl<'arena> InterfaceImplementationMap<'arena> {
-    pub fn build(program: &MutableProgram<'arena>) -> Self {
+    pub fn build(program: &MutableProgram<'arena>, interner: &StringInterner) -> Self {
         let mut map = InterfaceImplementationMap::default();
 
         for stmt in &program.statements {
@@ -64,6 +68,10 @@ impl<'arena> InterfaceImplementationMap<'arena> {
                             if let Some(body) = &method.body {
                                 map.method_body.insert((class_id, method_id), body.clone());
                             }
+                            map.method_hints.insert(
+                                (class_id, method_id),
+                                FunctionHints::from_decorators(method.decorators, interner),
+                            );
                         }
                     }
 
@@ -126,6 +134,13 @@ impl<'arena> InterfaceImplementationMap<'arena> {
         self.method_body.get(&(class_id, method_id))
     }
 
+    pub fn get_method_hints(&self, class_id: StringId, method_id: StringId) -> FunctionHints {
+        self.method_hints
+            .get(&(class_id, method_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
     pub fn count_statement_depth(&self, block: &Block<'arena>) -> usize {
         block.statements.len()
     }
@@ -296,11 +311,13 @@ impl<'arena> InterfaceImplementationMap<'arena> {
     }
 }
 
-pub struct InterfaceMethodInliningPass;
+pub struct InterfaceMethodInliningPass {
+    interner: Arc<StringInterner>,
+}
 
 impl InterfaceMethodInliningPass {
-    pub fn new(_interner: Arc<StringInterner>) -> Self {
-        Self
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self { interner }
     }
 
     fn process_statement<'arena>(
@@ -493,23 +510,48 @@ impl InterfaceMethodInliningPass {
                                 if let Some(method_body) =
                                     impl_map.get_method_body(implementing_class, method_id)
                                 {
-                                    if impl_map.count_statement_depth(method_body)
-                                        <= MAX_INLINABLE_STATEMENTS
-                                        && !impl_map.mutates_self(method_body, implementing_class)
+                                    let hints =
+                                        impl_map.get_method_hints(implementing_class, method_id);
+                                    let size = impl_map.count_statement_depth(method_body);
+                                    if let Some(reason) = hints.inline_blocker() {
+                                        remarks::missed(PASS, expr.span, || {
+                                            format!("method not inlined: {}", reason)
+                                        });
+                                    } else if size > MAX_INLINABLE_STATEMENTS
+                                        && !hints.forces_inlining()
+                                    {
+                                        remarks::missed(PASS, expr.span, || {
+                                            format!(
+                                                "method not inlined: body has {} statements, limit is {}",
+                                                size, MAX_INLINABLE_STATEMENTS
+                                            )
+                                        });
+                                    } else if impl_map.mutates_self(method_body, implementing_class)
                                     {
-                                        if let Some(inlined) = self.inline_interface_method(
-                                            &new_obj,
-                                            implementing_class,
-                                            method_body,
-                                            &new_args,
-                                            expr.span,
-                                            arena,
-                                        ) {
-                                            expr.kind = inlined;
-                                            changed = true;
-                                        }
+                                        remarks::missed(PASS, expr.span, || {
+                                            "method not inlined: it mutates `self`".to_string()
+                                        });
+                                    } else if let Some(inlined) = self.inline_interface_method(
+                                        &new_obj,
+                                        implementing_class,
+                                        method_body,
+                                        &new_args,
+                                        expr.span,
+                                        arena,
+                                    ) {
+                                        remarks::applied(PASS, expr.span, || {
+                                            "inlined interface method from its only implementing class"
+                                                .to_string()
+                                        });
+                                        expr.kind = inlined;
+                                        changed = true;
                                     }
                                 }
+                            } else {
+                                remarks::missed(PASS, expr.span, || {
+                                    "method not inlined: the interface has no single implementation"
+                                        .to_string()
+                                });
                             }
                         }
                     }
@@ -1026,7 +1068,7 @@ impl<'arena> StmtVisitor<'arena> for InterfaceMethodInliningPass {
 
 impl<'arena> WholeProgramPass<'arena> for InterfaceMethodInliningPass {
     fn name(&self) -> &'static str {
-        "interface-method-inlining"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -1038,7 +1080,7 @@ impl<'arena> WholeProgramPass<'arena> for InterfaceMethodInliningPass {
         program: &mut MutableProgram<'arena>,
         arena: &'arena Bump,
     ) -> Result<bool, String> {
-        let impl_map = InterfaceImplementationMap::build(program);
+        let impl_map = InterfaceImplementationMap::build(program, &self.interner);
 
         let mut changed = false;
         for stmt in &mut program.statements {
@@ -1055,7 +1097,7 @@ impl<'arena> WholeProgramPass<'arena> for InterfaceMethodInliningPass {
 
 impl Default for InterfaceMethodInliningPass {
     fn default() -> Self {
-        Self
+        Self::new(Arc::new(StringInterner::new()))
     }
 }
 
@@ -1076,7 +1118,7 @@ mod tests {
             statements: vec![],
             span: Span::dummy(),
         };
-        let map = InterfaceImplementationMap::build(&program);
+        let map = InterfaceImplementationMap::build(&program, &StringInterner::new());
 
         assert!(map.known_classes.is_empty());
         assert!(map.known_interfaces.is_empty());
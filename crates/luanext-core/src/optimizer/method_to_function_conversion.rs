@@ -2,13 +2,15 @@ use crate::config::OptimizationLevel;
 use crate::MutableProgram;
 use bumpalo::Bump;
 
-use crate::optimizer::{StmtVisitor, WholeProgramPass};
+use crate::optimizer::{remarks, StmtVisitor, WholeProgramPass};
 use luanext_parser::ast::expression::{Expression, ExpressionKind, ReceiverClassInfo};
 use luanext_parser::ast::statement::Statement;
 use luanext_parser::span::Span;
 use luanext_parser::string_interner::StringInterner;
 use std::sync::Arc;
 
+const PASS: &str = "method-to-function-conversion";
+
 pub struct MethodToFunctionConversionPass {
     interner: Arc<StringInterner>,
 }
@@ -190,10 +192,24 @@ impl MethodToFunctionConversionPass {
                         expr.span,
                         arena,
                     ) {
+                        remarks::applied(PASS, expr.span, || {
+                            format!(
+                                "converted method call to a direct call to `{}.{}`",
+                                self.interner.resolve(receiver_info.class_name),
+                                self.interner.resolve(method_name.node)
+                            )
+                        });
                         expr.kind = converted;
                         expr.receiver_class = None;
                         changed = true;
                     }
+                } else {
+                    remarks::missed(PASS, expr.span, || {
+                        format!(
+                            "`{}` call kept as dynamic dispatch: the receiver's class is not statically known",
+                            self.interner.resolve(method_name.node)
+                        )
+                    });
                 }
 
                 changed
@@ -498,7 +514,7 @@ impl<'arena> StmtVisitor<'arena> for MethodToFunctionConversionPass {
 
 impl<'arena> WholeProgramPass<'arena> for MethodToFunctionConversionPass {
     fn name(&self) -> &'static str {
-        "method-to-function-conversion"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -6,7 +6,15 @@
 //! clone sub-expressions to owned values, mutate, then allocate back into the arena.
 
 pub mod analysis;
-
+pub mod bisect;
+pub mod hints;
+pub mod pipeline;
+pub mod profile;
+pub mod remarks;
+pub mod size_report;
+pub mod verifier;
+
+use crate::codegen::LuaTarget;
 use crate::config::OptimizationLevel;
 use crate::diagnostics::DiagnosticHandler;
 use crate::MutableProgram;
@@ -309,115 +317,11 @@ impl<'arena> ExpressionCompositePass<'arena> {
     ) -> Result<bool, String> {
         let mut changed = false;
         for stmt in &mut program.statements {
-            changed |= self.visit_stmt(stmt, arena);
+            changed |= visit_stmt_expressions(stmt, arena, &mut |e, a| self.visit_expr(e, a));
         }
         Ok(changed)
     }
 
-    fn visit_stmt(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) -> bool {
-        let mut changed = false;
-
-        match stmt {
-            Statement::Variable(decl) => {
-                changed |= self.visit_expr(&mut decl.initializer, arena);
-            }
-            Statement::Expression(expr) => {
-                changed |= self.visit_expr(expr, arena);
-            }
-            Statement::If(if_stmt) => {
-                changed |= self.visit_expr(&mut if_stmt.condition, arena);
-                changed |= self.visit_block(&mut if_stmt.then_block, arena);
-                let mut new_else_ifs: Vec<_> = if_stmt.else_ifs.to_vec();
-                let mut eic = false;
-                for else_if in &mut new_else_ifs {
-                    eic |= self.visit_expr(&mut else_if.condition, arena);
-                    eic |= self.visit_block(&mut else_if.block, arena);
-                }
-                if eic {
-                    if_stmt.else_ifs = arena.alloc_slice_clone(&new_else_ifs);
-                    changed = true;
-                }
-                if let Some(else_block) = &mut if_stmt.else_block {
-                    changed |= self.visit_block(else_block, arena);
-                }
-            }
-            Statement::While(while_stmt) => {
-                changed |= self.visit_expr(&mut while_stmt.condition, arena);
-                changed |= self.visit_block(&mut while_stmt.body, arena);
-            }
-            Statement::For(for_stmt) => match &**for_stmt {
-                ForStatement::Numeric(for_num_ref) => {
-                    let mut new_num = (**for_num_ref).clone();
-                    let mut fc = false;
-                    fc |= self.visit_expr(&mut new_num.start, arena);
-                    fc |= self.visit_expr(&mut new_num.end, arena);
-                    if let Some(step) = &mut new_num.step {
-                        fc |= self.visit_expr(step, arena);
-                    }
-                    fc |= self.visit_block(&mut new_num.body, arena);
-                    if fc {
-                        *stmt = Statement::For(
-                            arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
-                        );
-                        changed = true;
-                    }
-                }
-                ForStatement::Generic(for_gen_ref) => {
-                    let mut new_gen = for_gen_ref.clone();
-                    let mut fc = false;
-                    let mut new_iters: Vec<_> = new_gen.iterators.to_vec();
-                    for expr in &mut new_iters {
-                        fc |= self.visit_expr(expr, arena);
-                    }
-                    if fc {
-                        new_gen.iterators = arena.alloc_slice_clone(&new_iters);
-                    }
-                    fc |= self.visit_block(&mut new_gen.body, arena);
-                    if fc {
-                        *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
-                        changed = true;
-                    }
-                }
-            },
-            Statement::Repeat(repeat_stmt) => {
-                changed |= self.visit_expr(&mut repeat_stmt.until, arena);
-                changed |= self.visit_block(&mut repeat_stmt.body, arena);
-            }
-            Statement::Return(ret_stmt) => {
-                let mut values: Vec<Expression<'arena>> = ret_stmt.values.to_vec();
-                let mut ret_changed = false;
-                for expr in &mut values {
-                    ret_changed |= self.visit_expr(expr, arena);
-                }
-                if ret_changed {
-                    ret_stmt.values = arena.alloc_slice_clone(&values);
-                    changed = true;
-                }
-            }
-            Statement::Function(func) => {
-                changed |= self.visit_block(&mut func.body, arena);
-            }
-            Statement::Block(block) => {
-                changed |= self.visit_block(block, arena);
-            }
-            _ => {}
-        }
-
-        changed
-    }
-
-    fn visit_block(&mut self, block: &mut Block<'arena>, arena: &'arena Bump) -> bool {
-        let mut stmts: Vec<Statement<'arena>> = block.statements.to_vec();
-        let mut changed = false;
-        for stmt in &mut stmts {
-            changed |= self.visit_stmt(stmt, arena);
-        }
-        if changed {
-            block.statements = arena.alloc_slice_clone(&stmts);
-        }
-        changed
-    }
-
     fn visit_expr(&mut self, expr: &mut Expression<'arena>, arena: &'arena Bump) -> bool {
         let mut changed = false;
 
@@ -725,9 +629,122 @@ impl<'arena> AnalysisCompositePass<'arena> {
 }
 
 // =============================================================================
-// Shared expression child visitor — clone-and-rebuild pattern
+// Shared statement and expression visitors — clone-and-rebuild pattern
 // =============================================================================
 
+/// Visit the top-level expressions of a statement and of the statements nested
+/// in its blocks. `visit_fn` is responsible for recursing into sub-expressions.
+fn visit_stmt_expressions<'arena>(
+    stmt: &mut Statement<'arena>,
+    arena: &'arena Bump,
+    visit_fn: &mut dyn FnMut(&mut Expression<'arena>, &'arena Bump) -> bool,
+) -> bool {
+    let mut changed = false;
+
+    match stmt {
+        Statement::Variable(decl) => {
+            changed |= visit_fn(&mut decl.initializer, arena);
+        }
+        Statement::Expression(expr) => {
+            changed |= visit_fn(expr, arena);
+        }
+        Statement::If(if_stmt) => {
+            changed |= visit_fn(&mut if_stmt.condition, arena);
+            changed |= visit_block_expressions(&mut if_stmt.then_block, arena, visit_fn);
+            let mut new_else_ifs: Vec<_> = if_stmt.else_ifs.to_vec();
+            let mut eic = false;
+            for else_if in &mut new_else_ifs {
+                eic |= visit_fn(&mut else_if.condition, arena);
+                eic |= visit_block_expressions(&mut else_if.block, arena, visit_fn);
+            }
+            if eic {
+                if_stmt.else_ifs = arena.alloc_slice_clone(&new_else_ifs);
+                changed = true;
+            }
+            if let Some(else_block) = &mut if_stmt.else_block {
+                changed |= visit_block_expressions(else_block, arena, visit_fn);
+            }
+        }
+        Statement::While(while_stmt) => {
+            changed |= visit_fn(&mut while_stmt.condition, arena);
+            changed |= visit_block_expressions(&mut while_stmt.body, arena, visit_fn);
+        }
+        Statement::For(for_stmt) => match &**for_stmt {
+            ForStatement::Numeric(for_num_ref) => {
+                let mut new_num = (**for_num_ref).clone();
+                let mut fc = false;
+                fc |= visit_fn(&mut new_num.start, arena);
+                fc |= visit_fn(&mut new_num.end, arena);
+                if let Some(step) = &mut new_num.step {
+                    fc |= visit_fn(step, arena);
+                }
+                fc |= visit_block_expressions(&mut new_num.body, arena, visit_fn);
+                if fc {
+                    *stmt =
+                        Statement::For(arena.alloc(ForStatement::Numeric(arena.alloc(new_num))));
+                    changed = true;
+                }
+            }
+            ForStatement::Generic(for_gen_ref) => {
+                let mut new_gen = for_gen_ref.clone();
+                let mut fc = false;
+                let mut new_iters: Vec<_> = new_gen.iterators.to_vec();
+                for expr in &mut new_iters {
+                    fc |= visit_fn(expr, arena);
+                }
+                if fc {
+                    new_gen.iterators = arena.alloc_slice_clone(&new_iters);
+                }
+                fc |= visit_block_expressions(&mut new_gen.body, arena, visit_fn);
+                if fc {
+                    *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    changed = true;
+                }
+            }
+        },
+        Statement::Repeat(repeat_stmt) => {
+            changed |= visit_fn(&mut repeat_stmt.until, arena);
+            changed |= visit_block_expressions(&mut repeat_stmt.body, arena, visit_fn);
+        }
+        Statement::Return(ret_stmt) => {
+            let mut values: Vec<Expression<'arena>> = ret_stmt.values.to_vec();
+            let mut ret_changed = false;
+            for expr in &mut values {
+                ret_changed |= visit_fn(expr, arena);
+            }
+            if ret_changed {
+                ret_stmt.values = arena.alloc_slice_clone(&values);
+                changed = true;
+            }
+        }
+        Statement::Function(func) => {
+            changed |= visit_block_expressions(&mut func.body, arena, visit_fn);
+        }
+        Statement::Block(block) => {
+            changed |= visit_block_expressions(block, arena, visit_fn);
+        }
+        _ => {}
+    }
+
+    changed
+}
+
+fn visit_block_expressions<'arena>(
+    block: &mut Block<'arena>,
+    arena: &'arena Bump,
+    visit_fn: &mut dyn FnMut(&mut Expression<'arena>, &'arena Bump) -> bool,
+) -> bool {
+    let mut stmts: Vec<Statement<'arena>> = block.statements.to_vec();
+    let mut changed = false;
+    for stmt in &mut stmts {
+        changed |= visit_stmt_expressions(stmt, arena, visit_fn);
+    }
+    if changed {
+        block.statements = arena.alloc_slice_clone(&stmts);
+    }
+    changed
+}
+
 /// Visit children of an expression using the clone-and-rebuild pattern.
 /// The `visit_fn` closure handles recursive visitation.
 fn visit_expr_children<'arena>(
@@ -1023,6 +1040,34 @@ fn visit_expr_children<'arena>(
     changed
 }
 
+/// Verify one pass run and charge it to the transformation budget.
+///
+/// `before` is the program the run started from, if the verifier or the
+/// budget needs it. Returns whether further passes may still run.
+fn finish_pass_run<'arena>(
+    verifier: Option<&verifier::PassVerifier>,
+    budget: Option<&mut bisect::TransformationBudget>,
+    passes: &[&'static str],
+    iteration: usize,
+    before: Option<MutableProgram<'arena>>,
+    program: &mut MutableProgram<'arena>,
+    arena: &'arena Bump,
+) -> Result<bool, String> {
+    let Some(before) = before else {
+        return Ok(true);
+    };
+    if let Some(verifier) = verifier {
+        verifier.check(passes, &before, program, arena)?;
+    }
+    match budget {
+        Some(budget) => {
+            budget.charge(passes, iteration, before, program);
+            Ok(!budget.exhausted())
+        }
+        None => Ok(true),
+    }
+}
+
 // =============================================================================
 // Optimizer - Orchestrates all passes
 // =============================================================================
@@ -1033,6 +1078,7 @@ fn visit_expr_children<'arena>(
 /// Passes are organized into composite groups to minimize AST traversals.
 pub struct Optimizer<'arena> {
     level: OptimizationLevel,
+    handler: Arc<dyn DiagnosticHandler>,
     interner: Arc<StringInterner>,
 
     // Composite passes (merged traversals)
@@ -1053,17 +1099,39 @@ pub struct Optimizer<'arena> {
     // Module graph for link-time optimizations (LTO)
     module_graph: Option<Arc<analysis::module_graph::ModuleGraph>>,
     current_module_path: Option<std::path::PathBuf>,
+
+    // Optimization remarks reported by passes (only collected when enabled)
+    collect_remarks: bool,
+    remarks: Vec<remarks::Remark>,
+
+    // Pass selection and iteration limit from `optimizer.passes`
+    pipeline_config: pipeline::PassPipelineConfig,
+    // Whether code-growing or size-reducing passes run
+    goal: pipeline::OptimizationGoal,
+    // Registered passes, in execution order within each group
+    pipeline: Vec<&'static pipeline::PassInfo>,
+    // Check AST invariants after every pass (debugging aid, off by default)
+    verify: bool,
+    // Cap on applied transformations, for miscompile bisection
+    transformation_budget: Option<bisect::TransformationBudget>,
+    // Runtime counts from `--profile-use`, for the profile-guided heuristics
+    profile: Option<Arc<profile::ModuleProfile>>,
+    // `optimizer.hints` for free functions, which cannot carry decorators
+    function_hints: Arc<hints::FreeFunctionHints>,
+    // Lua version the output runs on, for target-dependent folding
+    target: LuaTarget,
 }
 
 impl<'arena> Optimizer<'arena> {
     /// Create a new optimizer with the given optimization level
     pub fn new(
         level: OptimizationLevel,
-        _handler: Arc<dyn DiagnosticHandler>,
+        handler: Arc<dyn DiagnosticHandler>,
         interner: Arc<StringInterner>,
     ) -> Self {
         let mut optimizer = Self {
             level,
+            handler,
             interner,
             expr_pass: None,
             elim_pass: None,
@@ -1074,12 +1142,80 @@ impl<'arena> Optimizer<'arena> {
             analysis_context: None,
             module_graph: None,
             current_module_path: None,
+            collect_remarks: false,
+            remarks: Vec::new(),
+            pipeline_config: pipeline::PassPipelineConfig::default(),
+            goal: pipeline::OptimizationGoal::default(),
+            pipeline: Vec::new(),
+            verify: false,
+            transformation_budget: None,
+            profile: None,
+            function_hints: Arc::default(),
+            target: LuaTarget::default(),
         };
 
         optimizer.register_passes();
         optimizer
     }
 
+    /// Replace the default pass selection with a configured pipeline.
+    ///
+    /// Re-registers every pass, so call it before `set_whole_program_analysis`.
+    pub fn set_pass_pipeline(&mut self, config: pipeline::PassPipelineConfig) {
+        self.pipeline_config = config;
+        self.reregister_passes();
+    }
+
+    /// Optimize for speed (the default) or for size.
+    ///
+    /// Re-registers every pass, so call it before `set_whole_program_analysis`.
+    pub fn set_goal(&mut self, goal: pipeline::OptimizationGoal) {
+        self.goal = goal;
+        self.reregister_passes();
+    }
+
+    pub fn goal(&self) -> pipeline::OptimizationGoal {
+        self.goal
+    }
+
+    /// Guide inlining, cloning, loop unrolling and branch layout with the
+    /// runtime counts of this module. See [`profile`].
+    ///
+    /// Re-registers every pass, so call it before `set_whole_program_analysis`.
+    pub fn set_profile(&mut self, profile: profile::ModuleProfile) {
+        self.profile = Some(Arc::new(profile));
+        self.reregister_passes();
+    }
+
+    /// Apply `@inline`/`@noinline`/`@optimize` hints to free functions in
+    /// function inlining, aggressive inlining and function cloning, and
+    /// evaluate `comptime` ones at compile time. See [`hints`].
+    ///
+    /// Re-registers every pass, so call it before `set_whole_program_analysis`.
+    pub fn set_function_hints(&mut self, function_hints: hints::FreeFunctionHints) {
+        self.function_hints = Arc::new(function_hints);
+        self.reregister_passes();
+    }
+
+    /// Fold constants the way `target` computes them: integer arithmetic
+    /// wraps around on Lua 5.3+ and stays within ±2^53 elsewhere.
+    ///
+    /// Re-registers every pass, so call it before `set_whole_program_analysis`.
+    pub fn set_target(&mut self, target: LuaTarget) {
+        self.target = target;
+        self.reregister_passes();
+    }
+
+    fn reregister_passes(&mut self) {
+        self.expr_pass = None;
+        self.elim_pass = None;
+        self.func_pass = None;
+        self.data_pass = None;
+        self.standalone_passes.clear();
+        self.pipeline.clear();
+        self.register_passes();
+    }
+
     /// Set module graph for link-time optimizations (LTO)
     pub fn set_module_graph(&mut self, graph: Arc<analysis::module_graph::ModuleGraph>) {
         self.module_graph = Some(graph);
@@ -1090,6 +1226,57 @@ impl<'arena> Optimizer<'arena> {
         self.current_module_path = Some(path);
     }
 
+    /// Record optimization remarks reported by passes during `optimize`
+    pub fn enable_remarks(&mut self) {
+        self.collect_remarks = true;
+    }
+
+    /// Take the remarks recorded so far
+    pub fn take_remarks(&mut self) -> Vec<remarks::Remark> {
+        std::mem::take(&mut self.remarks)
+    }
+
+    /// Verify AST invariants after every pass.
+    ///
+    /// `optimize` fails with the name of the first pass that leaves an
+    /// unresolved identifier, a duplicate local, a `break` or `goto` without
+    /// a target or an inverted span. See [`verifier`].
+    ///
+    /// Off by default in every build; the CLI turns it on with
+    /// `--verify-optimizer` and the optimizer test harness always does.
+    pub fn enable_verifier(&mut self) {
+        self.verify = true;
+    }
+
+    /// Whether `optimize` checks AST invariants after every pass
+    pub fn verifies(&self) -> bool {
+        self.verify
+    }
+
+    /// Apply at most `limit` transformations, reverting the rest.
+    ///
+    /// See [`bisect`] for what counts as a transformation.
+    pub fn set_transformation_limit(&mut self, limit: usize) {
+        self.transformation_budget = Some(bisect::TransformationBudget::new(
+            limit,
+            self.interner.clone(),
+        ));
+    }
+
+    /// Transformations applied under the limit set by `set_transformation_limit`
+    pub fn transformations_applied(&self) -> usize {
+        self.transformation_budget
+            .as_ref()
+            .map_or(0, |budget| budget.applied())
+    }
+
+    /// The last transformation applied under a transformation limit
+    pub fn last_transformation(&self) -> Option<&bisect::Transformation> {
+        self.transformation_budget
+            .as_ref()
+            .and_then(|budget| budget.last())
+    }
+
     /// Set whole-program analysis results for cross-module optimizations
     pub fn set_whole_program_analysis(&mut self, analysis: WholeProgramAnalysis) {
         self.whole_program_analysis = Some(analysis.clone());
@@ -1101,97 +1288,190 @@ impl<'arena> Optimizer<'arena> {
         }
     }
 
-    /// Register optimization passes based on the optimization level
+    /// Register the passes the pipeline configuration enables at this level
     fn register_passes(&mut self) {
         let interner = self.interner.clone();
-        let level = self.level;
-
-        // O1 passes - Expression transformations
-        if level >= OptimizationLevel::Minimal {
-            let mut expr_pass = ExpressionCompositePass::new("expression-transforms");
-            expr_pass.add_visitor(Box::new(ConstantFoldingPass::new()));
-            expr_pass.add_visitor(Box::new(AlgebraicSimplificationPass::new()));
-
-            // O2 addition: Peephole optimizations
-            if level >= OptimizationLevel::Moderate {
-                expr_pass.add_visitor(Box::new(PeepholeOptimizationPass::new()));
-            }
-
-            self.expr_pass = Some(expr_pass);
-        }
-
-        // O1 passes - Dead code elimination (block-level: truncates after return)
-        if level >= OptimizationLevel::Minimal {
-            let mut elim_pass = StatementCompositePass::new("elimination-transforms");
-            elim_pass.add_block_visitor(Box::new(DeadCodeEliminationPass::new()));
-            self.elim_pass = Some(elim_pass);
-        }
-
-        // O2 passes - Elimination and data structure transforms
-        if level >= OptimizationLevel::Moderate {
-            // Dead store elimination (block-level: reverse liveness analysis)
-            // Copy propagation (block-level: SSA-based value propagation)
-            // Common subexpression elimination (block-level: value numbering)
-            if let Some(ref mut elim_pass) = self.elim_pass {
-                elim_pass.add_block_visitor(Box::new(SccpPass::new()));
-                elim_pass.add_block_visitor(Box::new(JumpThreadingPass::new()));
-                elim_pass.add_block_visitor(Box::new(CopyPropagationPass::new()));
-                elim_pass.add_block_visitor(Box::new(CommonSubexpressionEliminationPass::new()));
-                elim_pass.add_block_visitor(Box::new(DeadStoreEliminationPass::new()));
-            }
-
-            let mut data_pass = ExpressionCompositePass::new("data-structure-transforms");
-            data_pass.add_visitor(Box::new(TablePreallocationPass::new()));
-            data_pass.add_visitor(Box::new(StringConcatOptimizationPass::new(
-                interner.clone(),
-            )));
-            self.data_pass = Some(data_pass);
-
-            let mut func_pass = AnalysisCompositePass::new("function-transforms");
-            func_pass.add_pre_analyzer(Box::new(FunctionInliningPass::new(interner.clone())));
-            func_pass.add_visitor(Box::new(FunctionInliningPass::new(interner.clone())));
-            func_pass.add_visitor(Box::new(TailCallOptimizationPass::new()));
-            func_pass.add_visitor(Box::new(MethodToFunctionConversionPass::new(
-                interner.clone(),
-            )));
-            self.func_pass = Some(func_pass);
+        let profile = self.profile.clone();
+        let target = self.target;
+        let function_hints = self.function_hints.clone();
+
+        for info in self.pipeline_config.resolve(self.level, self.goal) {
+            match info.name {
+                // Expression transformations
+                "constant-folding" => self
+                    .expr_pass()
+                    .add_visitor(Box::new(ConstantFoldingPass::new().with_target(target))),
+                "algebraic-simplification" => self
+                    .expr_pass()
+                    .add_visitor(Box::new(AlgebraicSimplificationPass::new())),
+                "peephole-optimization" => self
+                    .expr_pass()
+                    .add_visitor(Box::new(PeepholeOptimizationPass::new())),
+                "operator-inlining" => self
+                    .expr_pass()
+                    .add_visitor(Box::new(OperatorInliningPass::new(interner.clone()))),
+
+                // Block-level elimination: dead code (truncate after return),
+                // SCCP, jump threading, copy propagation (SSA-based value
+                // propagation), CSE (value numbering), dead stores (reverse
+                // liveness analysis)
+                "dead-code-elimination" => self
+                    .elim_pass()
+                    .add_block_visitor(Box::new(DeadCodeEliminationPass::new())),
+                "sccp" => self
+                    .elim_pass()
+                    .add_block_visitor(Box::new(SccpPass::new())),
+                "jump-threading" => self
+                    .elim_pass()
+                    .add_block_visitor(Box::new(JumpThreadingPass::new())),
+                "copy-propagation" => self
+                    .elim_pass()
+                    .add_block_visitor(Box::new(CopyPropagationPass::new())),
+                "common-subexpression-elimination" => self
+                    .elim_pass()
+                    .add_block_visitor(Box::new(CommonSubexpressionEliminationPass::new())),
+                "dead-store-elimination" => self
+                    .elim_pass()
+                    .add_block_visitor(Box::new(DeadStoreEliminationPass::new())),
+                "code-outlining" => self
+                    .elim_pass()
+                    .add_block_visitor(Box::new(CodeOutliningPass::new(interner.clone()))),
+
+                // Function transforms
+                "function-inlining" => {
+                    let func_pass = self.func_pass();
+                    func_pass.add_pre_analyzer(Box::new(
+                        FunctionInliningPass::new(interner.clone())
+                            .with_profile(profile.clone())
+                            .with_hints(function_hints.clone()),
+                    ));
+                    func_pass.add_visitor(Box::new(
+                        FunctionInliningPass::new(interner.clone())
+                            .with_profile(profile.clone())
+                            .with_hints(function_hints.clone()),
+                    ));
+                }
+                "tail-call-optimization" => self
+                    .func_pass()
+                    .add_visitor(Box::new(TailCallOptimizationPass::new())),
+                "method-to-function-conversion" => {
+                    self.func_pass()
+                        .add_visitor(Box::new(MethodToFunctionConversionPass::new(
+                            interner.clone(),
+                        )))
+                }
+                "aggressive-inlining" => self.func_pass().add_visitor(Box::new(
+                    AggressiveInliningPass::new(interner.clone())
+                        .with_hints(function_hints.clone()),
+                )),
+                "interface-method-inlining" => self
+                    .func_pass()
+                    .add_visitor(Box::new(InterfaceMethodInliningPass::new(interner.clone()))),
+
+                // Data structure transforms
+                "table-preallocation" => self
+                    .data_pass()
+                    .add_visitor(Box::new(TablePreallocationPass::new())),
+                "string-concat-optimization" => {
+                    self.data_pass()
+                        .add_visitor(Box::new(StringConcatOptimizationPass::new(
+                            interner.clone(),
+                        )))
+                }
 
-            self.standalone_passes
-                .push(Box::new(LoopOptimizationPass::new()));
-            self.standalone_passes
-                .push(Box::new(RichEnumOptimizationPass::new()));
+                // Standalone passes (whole-program analysis)
+                "comptime-evaluation" => self.standalone_passes.push(Box::new(
+                    ComptimeEvaluationPass::new(interner.clone())
+                        .with_hints(function_hints.clone()),
+                )),
+                "array-iteration" => self
+                    .standalone_passes
+                    .push(Box::new(ArrayIterationPass::new(interner.clone()))),
+                "integer-division" => self
+                    .standalone_passes
+                    .push(Box::new(IntegerDivisionPass::new(interner.clone()))),
+                "loop-optimization" => self
+                    .standalone_passes
+                    .push(Box::new(LoopOptimizationPass::new(interner.clone()))),
+                "rich-enum-optimization" => self
+                    .standalone_passes
+                    .push(Box::new(RichEnumOptimizationPass::new())),
+                "devirtualization" => self
+                    .standalone_passes
+                    .push(Box::new(DevirtualizationPass::new(interner.clone()))),
+                "generic-specialization" => self
+                    .standalone_passes
+                    .push(Box::new(GenericSpecializationPass::new(interner.clone()))),
+                "loop-unrolling" => self.standalone_passes.push(Box::new(
+                    LoopUnrollingPass::new().with_profile(profile.clone()),
+                )),
+                "induction-variables" => self
+                    .standalone_passes
+                    .push(Box::new(InductionVariablePass::new(interner.clone()))),
+                "function-cloning" => self.standalone_passes.push(Box::new(
+                    FunctionCloningPass::new(interner.clone())
+                        .with_profile(profile.clone())
+                        .with_hints(function_hints.clone()),
+                )),
+                "interprocedural-const-prop" => {
+                    self.standalone_passes
+                        .push(Box::new(InterproceduralConstPropPass::new(
+                            interner.clone(),
+                        )))
+                }
+                "scalar-replacement" => self
+                    .standalone_passes
+                    .push(Box::new(ScalarReplacementPass::new(interner.clone()))),
+                "global-value-numbering" => self
+                    .standalone_passes
+                    .push(Box::new(GlobalValueNumberingPass::new(interner.clone()))),
+                "lambda-lifting" => self
+                    .standalone_passes
+                    .push(Box::new(LambdaLiftingPass::new(interner.clone()))),
+                "constant-pooling" => self
+                    .standalone_passes
+                    .push(Box::new(ConstantPoolingPass::new(interner.clone()))),
+                "branch-layout" => self.standalone_passes.push(Box::new(
+                    BranchLayoutPass::new().with_profile(profile.clone()),
+                )),
+                "global-localization" => self
+                    .standalone_passes
+                    .push(Box::new(GlobalLocalizationPass::new(interner.clone()))),
+                "string-constant-sharing" => self
+                    .standalone_passes
+                    .push(Box::new(StringConstantSharingPass::new(interner.clone()))),
+                "identifier-minification" => self
+                    .standalone_passes
+                    .push(Box::new(IdentifierMinificationPass::new(interner.clone()))),
+
+                other => unreachable!("pass `{}` is listed in PASSES but never registered", other),
+            }
+            self.pipeline.push(info);
         }
+    }
 
-        // O3 passes - Aggressive optimizations
-        if level >= OptimizationLevel::Aggressive {
-            if let Some(ref mut expr_pass) = self.expr_pass {
-                expr_pass.add_visitor(Box::new(OperatorInliningPass::new(interner.clone())));
-            }
+    fn expr_pass(&mut self) -> &mut ExpressionCompositePass<'arena> {
+        self.expr_pass.get_or_insert_with(|| {
+            ExpressionCompositePass::new(pipeline::PassGroup::Expression.as_str())
+        })
+    }
 
-            if let Some(ref mut func_pass) = self.func_pass {
-                func_pass.add_visitor(Box::new(AggressiveInliningPass::new(interner.clone())));
-                func_pass.add_visitor(Box::new(InterfaceMethodInliningPass::new(interner.clone())));
-            }
+    fn elim_pass(&mut self) -> &mut StatementCompositePass<'arena> {
+        self.elim_pass.get_or_insert_with(|| {
+            StatementCompositePass::new(pipeline::PassGroup::Elimination.as_str())
+        })
+    }
 
-            self.standalone_passes
-                .push(Box::new(DevirtualizationPass::new(interner.clone())));
-            self.standalone_passes
-                .push(Box::new(GenericSpecializationPass::new(interner.clone())));
-            self.standalone_passes
-                .push(Box::new(LoopUnrollingPass::new()));
-            self.standalone_passes
-                .push(Box::new(FunctionCloningPass::new(interner.clone())));
-            self.standalone_passes
-                .push(Box::new(InterproceduralConstPropPass::new(
-                    interner.clone(),
-                )));
-            self.standalone_passes
-                .push(Box::new(ScalarReplacementPass::new(interner.clone())));
-        }
+    fn func_pass(&mut self) -> &mut AnalysisCompositePass<'arena> {
+        self.func_pass.get_or_insert_with(|| {
+            AnalysisCompositePass::new(pipeline::PassGroup::Function.as_str())
+        })
+    }
 
-        // Global localization runs at all optimization levels
-        self.standalone_passes
-            .push(Box::new(GlobalLocalizationPass::new(interner.clone())));
+    fn data_pass(&mut self) -> &mut ExpressionCompositePass<'arena> {
+        self.data_pass.get_or_insert_with(|| {
+            ExpressionCompositePass::new(pipeline::PassGroup::DataStructure.as_str())
+        })
     }
 
     /// Returns the number of registered passes (counting individual visitors within composites)
@@ -1223,68 +1503,32 @@ impl<'arena> Optimizer<'arena> {
 
     /// Returns the names of all registered passes
     pub fn pass_names(&self) -> Vec<&'static str> {
-        let mut names = Vec::new();
-
-        // expr_pass contains: constant-folding, algebraic-simplification, [operator-inlining at O3]
-        if let Some(ref ep) = self.expr_pass {
-            // O1 base passes
-            if ep.visitor_count() >= 1 {
-                names.push("constant-folding");
-            }
-            if ep.visitor_count() >= 2 {
-                names.push("algebraic-simplification");
-            }
-            // O3 adds operator-inlining
-            if ep.visitor_count() >= 3 {
-                names.push("operator-inlining");
-            }
-        }
-
-        // elim_pass contains: dead-code-elimination, dead-store-elimination (block visitors)
-        if let Some(ref elp) = self.elim_pass {
-            if elp.visitor_count() >= 1 {
-                names.push("dead-code-elimination");
-            }
-            if elp.visitor_count() >= 2 {
-                names.push("dead-store-elimination");
-            }
-        }
-
-        // data_pass contains: table-preallocation, string-concat-optimization
-        if let Some(ref dp) = self.data_pass {
-            if dp.visitor_count() >= 1 {
-                names.push("table-preallocation");
-            }
-            if dp.visitor_count() >= 2 {
-                names.push("string-concat-optimization");
-            }
-        }
-
-        // func_pass contains: function-inlining, tail-call-optimization, method-to-function-conversion
-        //   [O3 adds: aggressive-inlining, interface-method-inlining]
-        if let Some(ref fp) = self.func_pass {
-            if fp.visitor_count() >= 1 {
-                names.push("function-inlining");
-            }
-            if fp.visitor_count() >= 2 {
-                names.push("tail-call-optimization");
-            }
-            if fp.visitor_count() >= 3 {
-                names.push("method-to-function-conversion");
-            }
-            if fp.visitor_count() >= 4 {
-                names.push("aggressive-inlining");
-            }
-            if fp.visitor_count() >= 5 {
-                names.push("interface-method-inlining");
-            }
-        }
+        self.pipeline.iter().map(|info| info.name).collect()
+    }
 
-        for pass in &self.standalone_passes {
-            names.push(pass.name());
-        }
+    /// Returns the registered passes in execution order, grouped by traversal
+    pub fn pass_pipeline(&self) -> Vec<&'static pipeline::PassInfo> {
+        let groups = [
+            pipeline::PassGroup::Expression,
+            pipeline::PassGroup::Elimination,
+            pipeline::PassGroup::Function,
+            pipeline::PassGroup::DataStructure,
+            pipeline::PassGroup::WholeProgram,
+        ];
+        groups
+            .iter()
+            .flat_map(|group| {
+                self.pipeline
+                    .iter()
+                    .filter(move |info| info.group == *group)
+            })
+            .copied()
+            .collect()
+    }
 
-        names
+    /// Upper bound on fixed-point iterations
+    pub fn max_iterations(&self) -> usize {
+        self.pipeline_config.max_iterations()
     }
 
     /// Optimize the program AST.
@@ -1293,6 +1537,20 @@ impl<'arena> Optimizer<'arena> {
         &mut self,
         program: &mut MutableProgram<'arena>,
         arena: &'arena Bump,
+    ) -> Result<(), String> {
+        if !self.collect_remarks {
+            return self.run_passes(program, arena);
+        }
+
+        let (result, collected) = remarks::collect(|| self.run_passes(program, arena));
+        self.remarks.extend(collected);
+        result
+    }
+
+    fn run_passes(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
     ) -> Result<(), String> {
         use std::time::Instant;
 
@@ -1318,96 +1576,165 @@ impl<'arena> Optimizer<'arena> {
             }
         }
 
+        // With the verifier on, each pass group is checked against a copy
+        // of the program from before it ran
+        let verifier = self.verify.then(|| verifier::PassVerifier::new(program, self));
+        let group_passes = |group: pipeline::PassGroup| -> Vec<&'static str> {
+            self.pipeline
+                .iter()
+                .filter(|info| info.group == group)
+                .map(|info| info.name)
+                .collect()
+        };
+        let expr_passes = group_passes(pipeline::PassGroup::Expression);
+        let elim_passes = group_passes(pipeline::PassGroup::Elimination);
+        let func_passes = group_passes(pipeline::PassGroup::Function);
+        let data_passes = group_passes(pipeline::PassGroup::DataStructure);
+        let mut budget = self.transformation_budget.take();
+
         let mut iteration = 0;
-        let max_iterations = 10;
+        let max_iterations = self.pipeline_config.max_iterations();
 
-        loop {
+        'fixed_point: loop {
             let mut changed = false;
             iteration += 1;
 
-            if iteration > max_iterations {
+            if iteration > max_iterations || budget.as_ref().is_some_and(|b| b.exhausted()) {
                 break;
             }
 
+            // Registration already applied the level and the pipeline
+            // configuration, so every registered pass runs
+
             if let Some(ref mut pass) = self.expr_pass {
-                if effective_level >= OptimizationLevel::Minimal {
-                    let required = pass.required_features();
-                    if required.is_empty() || features.contains(required) {
-                        let start = Instant::now();
-                        let pass_changed = pass.run(program, arena)?;
-                        let elapsed = start.elapsed();
-                        debug!(
-                            "  [Iter {}] ExpressionCompositePass: {:?} (changed: {})",
-                            iteration, elapsed, pass_changed
-                        );
-                        changed |= pass_changed;
+                let required = pass.required_features();
+                if required.is_empty() || features.contains(required) {
+                    let before = (verifier.is_some() || budget.is_some()).then(|| program.clone());
+                    let start = Instant::now();
+                    let pass_changed = pass.run(program, arena)?;
+                    let elapsed = start.elapsed();
+                    debug!(
+                        "  [Iter {}] ExpressionCompositePass: {:?} (changed: {})",
+                        iteration, elapsed, pass_changed
+                    );
+                    changed |= pass_changed;
+                    if !finish_pass_run(
+                        verifier.as_ref(),
+                        budget.as_mut(),
+                        &expr_passes,
+                        iteration,
+                        before,
+                        program,
+                        arena,
+                    )? {
+                        break 'fixed_point;
                     }
                 }
             }
 
             if let Some(ref mut pass) = self.elim_pass {
-                if effective_level >= OptimizationLevel::Minimal {
-                    let required = pass.required_features();
-                    if required.is_empty() || features.contains(required) {
-                        let start = Instant::now();
-                        let pass_changed = pass.run(program, arena)?;
-                        let elapsed = start.elapsed();
-                        debug!(
-                            "  [Iter {}] EliminationCompositePass: {:?} (changed: {})",
-                            iteration, elapsed, pass_changed
-                        );
-                        changed |= pass_changed;
+                let required = pass.required_features();
+                if required.is_empty() || features.contains(required) {
+                    let before = (verifier.is_some() || budget.is_some()).then(|| program.clone());
+                    let start = Instant::now();
+                    let pass_changed = pass.run(program, arena)?;
+                    let elapsed = start.elapsed();
+                    debug!(
+                        "  [Iter {}] EliminationCompositePass: {:?} (changed: {})",
+                        iteration, elapsed, pass_changed
+                    );
+                    changed |= pass_changed;
+                    if !finish_pass_run(
+                        verifier.as_ref(),
+                        budget.as_mut(),
+                        &elim_passes,
+                        iteration,
+                        before,
+                        program,
+                        arena,
+                    )? {
+                        break 'fixed_point;
                     }
                 }
             }
 
             if let Some(ref mut pass) = self.func_pass {
-                if effective_level >= OptimizationLevel::Moderate {
-                    let required = pass.required_features();
-                    if required.is_empty() || features.contains(required) {
-                        let start = Instant::now();
-                        let pass_changed = pass.run(program, arena)?;
-                        let elapsed = start.elapsed();
-                        debug!(
-                            "  [Iter {}] FunctionCompositePass: {:?} (changed: {})",
-                            iteration, elapsed, pass_changed
-                        );
-                        changed |= pass_changed;
+                let required = pass.required_features();
+                if required.is_empty() || features.contains(required) {
+                    let before = (verifier.is_some() || budget.is_some()).then(|| program.clone());
+                    let start = Instant::now();
+                    let pass_changed = pass.run(program, arena)?;
+                    let elapsed = start.elapsed();
+                    debug!(
+                        "  [Iter {}] FunctionCompositePass: {:?} (changed: {})",
+                        iteration, elapsed, pass_changed
+                    );
+                    changed |= pass_changed;
+                    if !finish_pass_run(
+                        verifier.as_ref(),
+                        budget.as_mut(),
+                        &func_passes,
+                        iteration,
+                        before,
+                        program,
+                        arena,
+                    )? {
+                        break 'fixed_point;
                     }
                 }
             }
 
             if let Some(ref mut pass) = self.data_pass {
-                if effective_level >= OptimizationLevel::Moderate {
-                    let required = pass.required_features();
-                    if required.is_empty() || features.contains(required) {
-                        let start = Instant::now();
-                        let pass_changed = pass.run(program, arena)?;
-                        let elapsed = start.elapsed();
-                        debug!(
-                            "  [Iter {}] DataStructureCompositePass: {:?} (changed: {})",
-                            iteration, elapsed, pass_changed
-                        );
-                        changed |= pass_changed;
+                let required = pass.required_features();
+                if required.is_empty() || features.contains(required) {
+                    let before = (verifier.is_some() || budget.is_some()).then(|| program.clone());
+                    let start = Instant::now();
+                    let pass_changed = pass.run(program, arena)?;
+                    let elapsed = start.elapsed();
+                    debug!(
+                        "  [Iter {}] DataStructureCompositePass: {:?} (changed: {})",
+                        iteration, elapsed, pass_changed
+                    );
+                    changed |= pass_changed;
+                    if !finish_pass_run(
+                        verifier.as_ref(),
+                        budget.as_mut(),
+                        &data_passes,
+                        iteration,
+                        before,
+                        program,
+                        arena,
+                    )? {
+                        break 'fixed_point;
                     }
                 }
             }
 
             for pass in &mut self.standalone_passes {
-                if pass.min_level() <= effective_level {
-                    let required = pass.required_features();
-                    if required.is_empty() || features.contains(required) {
-                        let start = Instant::now();
-                        let pass_changed = pass.run(program, arena)?;
-                        let elapsed = start.elapsed();
-                        debug!(
-                            "  [Iter {}] {}: {:?} (changed: {})",
-                            iteration,
-                            pass.name(),
-                            elapsed,
-                            pass_changed
-                        );
-                        changed |= pass_changed;
+                let required = pass.required_features();
+                if required.is_empty() || features.contains(required) {
+                    let before = (verifier.is_some() || budget.is_some()).then(|| program.clone());
+                    let start = Instant::now();
+                    let pass_changed = pass.run(program, arena)?;
+                    let elapsed = start.elapsed();
+                    debug!(
+                        "  [Iter {}] {}: {:?} (changed: {})",
+                        iteration,
+                        pass.name(),
+                        elapsed,
+                        pass_changed
+                    );
+                    changed |= pass_changed;
+                    if !finish_pass_run(
+                        verifier.as_ref(),
+                        budget.as_mut(),
+                        &[pass.name()],
+                        iteration,
+                        before,
+                        program,
+                        arena,
+                    )? {
+                        break 'fixed_point;
                     }
                 }
             }
@@ -1417,6 +1744,8 @@ impl<'arena> Optimizer<'arena> {
             }
         }
 
+        self.transformation_budget = budget;
+
         let total_elapsed = start_total.elapsed();
         info!(
             "Optimization complete: {} iterations, {:?} total",
@@ -11,7 +11,8 @@
 use crate::config::OptimizationLevel;
 use crate::MutableProgram;
 
-use crate::optimizer::{ExprVisitor, PreAnalysisPass, WholeProgramPass};
+use crate::optimizer::hints::FunctionHints;
+use crate::optimizer::{remarks, ExprVisitor, PreAnalysisPass, WholeProgramPass};
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, UnaryOp};
 use luanext_parser::ast::statement::{Block, ClassMember, ForStatement, Statement};
@@ -24,11 +25,14 @@ use std::sync::Arc;
 const MAX_INLINE_STATEMENTS: usize = 5;
 const MIN_CALL_FREQUENCY: usize = 3;
 
+const PASS: &str = "operator-inlining";
+
 #[derive(Debug, Clone)]
 struct OperatorInfo {
     statement_count: usize,
     has_side_effects: bool,
     call_count: usize,
+    hints: FunctionHints,
 }
 
 pub struct OperatorInliningPass {
@@ -64,6 +68,7 @@ impl OperatorInliningPass {
                             statement_count,
                             has_side_effects,
                             call_count: 0,
+                            hints: FunctionHints::from_decorators(op.decorators, &self.interner),
                         };
 
                         self.operator_catalog
@@ -321,17 +326,28 @@ impl OperatorInliningPass {
         }
     }
 
-    fn can_inline(&self, info: &OperatorInfo) -> bool {
-        if info.statement_count > MAX_INLINE_STATEMENTS {
-            return false;
+    /// Why an operator cannot be converted, or `None` if it can
+    fn inline_blocker(&self, info: &OperatorInfo) -> Option<String> {
+        if let Some(reason) = info.hints.inline_blocker() {
+            return Some(reason.to_string());
+        }
+        let forced = info.hints.forces_inlining();
+        if info.statement_count > MAX_INLINE_STATEMENTS && !forced {
+            return Some(format!(
+                "body has {} statements, limit is {}",
+                info.statement_count, MAX_INLINE_STATEMENTS
+            ));
         }
-        if info.has_side_effects {
-            return false;
+        if info.has_side_effects && !info.hints.pure {
+            return Some("body has side effects".to_string());
         }
-        if info.call_count < MIN_CALL_FREQUENCY {
-            return false;
+        if info.call_count < MIN_CALL_FREQUENCY && !forced {
+            return Some(format!(
+                "called {} time(s), needs at least {}",
+                info.call_count, MIN_CALL_FREQUENCY
+            ));
         }
-        true
+        None
     }
 
     fn convert_operator_call<'arena>(
@@ -345,13 +361,25 @@ impl OperatorInliningPass {
         let operator_kind = binary_op_to_operator_kind(op)?;
         let class_id = get_class_from_type(left.annotated_type.as_ref()?)?;
         let info = self.operator_catalog.get(&(class_id, operator_kind))?;
-        if !self.can_inline(info) {
+        let class_name_str = self.interner.resolve(class_id);
+        let metamethod_name = operator_kind_to_metamethod_name(operator_kind);
+        if let Some(reason) = self.inline_blocker(info) {
+            remarks::missed(PASS, span, || {
+                format!(
+                    "`{}.{}` not converted to a direct call: {}",
+                    class_name_str, metamethod_name, reason
+                )
+            });
             return None;
         }
+        remarks::applied(PASS, span, || {
+            format!(
+                "converted operator to a direct call to `{}.{}`",
+                class_name_str, metamethod_name
+            )
+        });
 
-        let class_name_str = self.interner.resolve(class_id);
         let class_ident_id = self.interner.get_or_intern(&class_name_str);
-        let metamethod_name = operator_kind_to_metamethod_name(operator_kind);
         let method_ident_id = self.interner.get_or_intern(&metamethod_name);
 
         let func_expr = Expression {
@@ -412,7 +440,7 @@ impl<'arena> PreAnalysisPass<'arena> for OperatorInliningPass {
 
 impl<'arena> WholeProgramPass<'arena> for OperatorInliningPass {
     fn name(&self) -> &'static str {
-        "operator-inlining"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
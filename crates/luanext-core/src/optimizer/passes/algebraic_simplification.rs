@@ -1,8 +1,11 @@
 use crate::config::OptimizationLevel;
-use crate::optimizer::{ExprVisitor, WholeProgramPass};
+use crate::optimizer::{remarks, ExprVisitor, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, Literal, UnaryOp};
+use luanext_parser::span::Span;
+
+const PASS: &str = "algebraic-simplification";
 
 pub struct AlgebraicSimplificationPass;
 
@@ -20,7 +23,7 @@ impl<'arena> ExprVisitor<'arena> for AlgebraicSimplificationPass {
 
 impl<'arena> WholeProgramPass<'arena> for AlgebraicSimplificationPass {
     fn name(&self) -> &'static str {
-        "algebraic-simplification"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -152,6 +155,7 @@ impl AlgebraicSimplificationPass {
         match &expr.kind {
             ExpressionKind::Binary(op, left, right) => {
                 let op = *op;
+                let span = expr.span;
                 let mut new_left = (**left).clone();
                 let mut new_right = (**right).clone();
                 let mut changed = self.simplify_expression(&mut new_left, arena);
@@ -163,41 +167,41 @@ impl AlgebraicSimplificationPass {
                     BinaryOp::Add => {
                         if is_zero(&new_right.kind) {
                             *expr = new_left;
-                            return true;
+                            return self.simplified(span, "x + 0 = x");
                         }
                         if is_zero(&new_left.kind) {
                             *expr = new_right;
-                            return true;
+                            return self.simplified(span, "0 + x = x");
                         }
                     }
                     // x - 0 = x
                     BinaryOp::Subtract => {
                         if is_zero(&new_right.kind) {
                             *expr = new_left;
-                            return true;
+                            return self.simplified(span, "x - 0 = x");
                         }
                     }
                     // x * 0 = 0 or 0 * x = 0
                     BinaryOp::Multiply => {
                         if is_zero(&new_right.kind) || is_zero(&new_left.kind) {
                             expr.kind = ExpressionKind::Literal(Literal::Number(0.0));
-                            return true;
+                            return self.simplified(span, "x * 0 = 0");
                         }
                         // x * 1 = x or 1 * x = x
                         if is_one(&new_right.kind) {
                             *expr = new_left;
-                            return true;
+                            return self.simplified(span, "x * 1 = x");
                         }
                         if is_one(&new_left.kind) {
                             *expr = new_right;
-                            return true;
+                            return self.simplified(span, "1 * x = x");
                         }
                     }
                     // x / 1 = x
                     BinaryOp::Divide => {
                         if is_one(&new_right.kind) {
                             *expr = new_left;
-                            return true;
+                            return self.simplified(span, "x / 1 = x");
                         }
                     }
                     // true && x = x, false && x = false
@@ -208,7 +212,7 @@ impl AlgebraicSimplificationPass {
                             } else {
                                 expr.kind = ExpressionKind::Literal(Literal::Boolean(false));
                             }
-                            return true;
+                            return self.simplified(span, "true and x = x, false and x = false");
                         }
                         if let ExpressionKind::Literal(Literal::Boolean(b)) = &new_right.kind {
                             if *b {
@@ -216,7 +220,7 @@ impl AlgebraicSimplificationPass {
                             } else {
                                 expr.kind = ExpressionKind::Literal(Literal::Boolean(false));
                             }
-                            return true;
+                            return self.simplified(span, "x and true = x, x and false = false");
                         }
                     }
                     // true || x = true, false || x = x
@@ -227,7 +231,7 @@ impl AlgebraicSimplificationPass {
                             } else {
                                 *expr = new_right;
                             }
-                            return true;
+                            return self.simplified(span, "true or x = true, false or x = x");
                         }
                         if let ExpressionKind::Literal(Literal::Boolean(b)) = &new_right.kind {
                             if *b {
@@ -235,7 +239,7 @@ impl AlgebraicSimplificationPass {
                             } else {
                                 *expr = new_left;
                             }
-                            return true;
+                            return self.simplified(span, "x or true = true, x or false = x");
                         }
                     }
                     _ => {}
@@ -249,6 +253,7 @@ impl AlgebraicSimplificationPass {
             }
             ExpressionKind::Unary(op, operand) => {
                 let op = *op;
+                let span = expr.span;
                 let mut new_operand = (**operand).clone();
                 let changed = self.simplify_expression(&mut new_operand, arena);
 
@@ -256,7 +261,7 @@ impl AlgebraicSimplificationPass {
                 if let UnaryOp::Not = op {
                     if let ExpressionKind::Unary(UnaryOp::Not, inner) = &new_operand.kind {
                         *expr = (**inner).clone();
-                        return true;
+                        return self.simplified(span, "not not x = x");
                     }
                 }
 
@@ -296,6 +301,12 @@ impl AlgebraicSimplificationPass {
             _ => false,
         }
     }
+
+    /// Report an applied simplification rule; always returns `true`
+    fn simplified(&self, span: Span, rule: &'static str) -> bool {
+        remarks::applied(PASS, span, || format!("simplified {}", rule));
+        true
+    }
 }
 
 // Helper functions
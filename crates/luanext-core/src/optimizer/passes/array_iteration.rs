@@ -0,0 +1,594 @@
+//! Array iteration rewriting
+//!
+//! Turns `ipairs` and `pairs` loops, and destructuring `for [a, b] in xs`
+//! loops, over a local or parameter declared as an array `T[]` into numeric
+//! `for` loops, which PUC Lua runs without a call per iteration and LuaJIT
+//! compiles more readily:
+//!
+//! ```lua
+//! -- Before:
+//! for i, body in ipairs(bodies) do
+//!     body.y = body.y + body.vy
+//! end
+//!
+//! -- After:
+//! for i = 1, #bodies do
+//!     local body = bodies[i]
+//!     body.y = body.y + body.vy
+//! end
+//! ```
+//!
+//! `ipairs` stops before the first nil element, while `#xs` may be any border
+//! of the table, and a table with holes has borders past its first nil. When
+//! the element type admits nil, reading the element and breaking on nil
+//! visits the same elements as `ipairs` whichever border `#xs` picks. When it
+//! excludes nil, the array is assumed to have no holes, so `#xs` is its
+//! length and the check is left out; a local whose initializer visibly holds
+//! a `nil` is not rewritten at all. `pairs` loops are only rewritten when the
+//! element type excludes nil, as `pairs` would also visit the elements after
+//! a hole.
+//!
+//! `#xs` is taken once, so the loop body must not grow the array: it may not
+//! assign the variable, store into an index of a table that may be the array,
+//! or call a function that may store into a table. Metamethods such as
+//! `__index` and `__len` are assumed absent, as they are on arrays.
+
+use super::memory_effects::{pattern_names, variable_name, LocalTables, PureCalls, Scan, Store};
+use crate::codegen::statements::block_contains_continue;
+use crate::config::OptimizationLevel;
+use crate::optimizer::{remarks, AstFeatures, WholeProgramPass};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{
+    ArrayElement, BinaryOp, Expression, ExpressionKind, Literal, UnaryOp,
+};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{
+    Block, ClassMember, ForGeneric, ForNumeric, ForStatement, IfStatement, Parameter, Statement,
+    VariableDeclaration, VariableKind,
+};
+use luanext_parser::ast::types::{PrimitiveType, Type, TypeKind};
+use luanext_parser::ast::Spanned;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::FxHashMap;
+use std::sync::Arc;
+
+const PASS: &str = "array-iteration";
+
+/// Whether the elements of an array type may be nil
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Elements {
+    NonNil,
+    MaybeNil,
+}
+
+/// How a generic `for` walks its array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Iteration {
+    Ipairs,
+    Pairs,
+    Destructuring,
+}
+
+impl Iteration {
+    fn as_str(self) -> &'static str {
+        match self {
+            Iteration::Ipairs => "`ipairs`",
+            Iteration::Pairs => "`pairs`",
+            Iteration::Destructuring => "destructuring",
+        }
+    }
+}
+
+/// The arrays of the function around a loop
+struct Frame {
+    /// Locals and parameters declared once, with an array type
+    arrays: FxHashMap<StringId, Elements>,
+    tables: LocalTables,
+}
+
+impl Frame {
+    fn new(
+        parameters: &[Parameter<'_>],
+        statements: &[Statement<'_>],
+        pure_calls: &PureCalls,
+    ) -> Self {
+        let mut scan = Scan::new(Some(pure_calls));
+        scan.statements(statements);
+        let tables = LocalTables::new(statements, &scan);
+        let mut arrays = FxHashMap::default();
+        if scan.opaque {
+            return Self { arrays, tables };
+        }
+
+        for param in parameters {
+            if let (Pattern::Identifier(ident), Some(ty)) = (&param.pattern, &param.type_annotation)
+            {
+                if let Some(elements) = array_elements(ty) {
+                    if !param.is_rest && !scan.declared.contains_key(&ident.node) {
+                        arrays.insert(ident.node, elements);
+                    }
+                }
+            }
+        }
+        let mut declarations = Vec::new();
+        typed_locals(statements, &mut declarations);
+        for (name, ty, initializer) in declarations {
+            if let Some(elements) = array_elements(ty) {
+                if elements == Elements::NonNil && has_nil_element(initializer) {
+                    continue;
+                }
+                let shadows_param = parameters.iter().any(|param| {
+                    let mut names = Vec::new();
+                    pattern_names(&param.pattern, &mut names);
+                    names.contains(&name)
+                });
+                if scan.declared.get(&name) == Some(&1) && !shadows_param {
+                    arrays.insert(name, elements);
+                }
+            }
+        }
+        Self { arrays, tables }
+    }
+}
+
+/// Rewrites generic `for` loops over arrays into numeric `for` loops
+pub struct ArrayIterationPass {
+    interner: Arc<StringInterner>,
+    next_temp_id: usize,
+    pure_calls: PureCalls,
+    /// `ipairs` and `pairs`, when the program does not rebind them
+    ipairs: Option<StringId>,
+    pairs: Option<StringId>,
+    frames: Vec<Frame>,
+}
+
+impl ArrayIterationPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            pure_calls: PureCalls::new(&[], interner.clone()),
+            interner,
+            next_temp_id: 0,
+            ipairs: None,
+            pairs: None,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for ArrayIterationPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn min_level(&self) -> OptimizationLevel {
+        OptimizationLevel::Moderate
+    }
+
+    fn required_features(&self) -> AstFeatures {
+        AstFeatures::HAS_LOOPS
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let mut scan = Scan::new(None);
+        scan.statements(&program.statements);
+        let builtin = |name: &str| {
+            let id = self.interner.get_or_intern(name);
+            (!scan.declared.contains_key(&id) && !scan.written.contains(&id)).then_some(id)
+        };
+        self.ipairs = builtin("ipairs");
+        self.pairs = builtin("pairs");
+        self.pure_calls = PureCalls::new(&program.statements, self.interner.clone());
+        self.frames = vec![Frame::new(&[], &program.statements, &self.pure_calls)];
+
+        let mut changed = false;
+        for stmt in program.statements.iter_mut() {
+            changed |= self.statement(stmt, arena);
+        }
+
+        self.frames.clear();
+        Ok(changed)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl ArrayIterationPass {
+    fn block<'arena>(&mut self, block: &mut Block<'arena>, arena: &'arena Bump) -> bool {
+        let mut statements = block.statements.to_vec();
+        let mut changed = false;
+        for stmt in &mut statements {
+            changed |= self.statement(stmt, arena);
+        }
+        if changed {
+            block.statements = arena.alloc_slice_clone(&statements);
+        }
+        changed
+    }
+
+    fn function<'arena>(
+        &mut self,
+        parameters: &[Parameter<'arena>],
+        body: &mut Block<'arena>,
+        arena: &'arena Bump,
+    ) -> bool {
+        self.frames
+            .push(Frame::new(parameters, body.statements, &self.pure_calls));
+        let changed = self.block(body, arena);
+        self.frames.pop();
+        changed
+    }
+
+    fn statement<'arena>(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) -> bool {
+        match stmt {
+            Statement::For(for_stmt) => match *for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    let mut new_num = (**for_num).clone();
+                    if !self.block(&mut new_num.body, arena) {
+                        return false;
+                    }
+                    *stmt =
+                        Statement::For(arena.alloc(ForStatement::Numeric(arena.alloc(new_num))));
+                    true
+                }
+                ForStatement::Generic(for_gen) => {
+                    let mut new_gen = for_gen.clone();
+                    let changed = self.block(&mut new_gen.body, arena);
+                    if let Some(new_num) = self.rewrite(&new_gen, arena) {
+                        *stmt = Statement::For(
+                            arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
+                        );
+                        return true;
+                    }
+                    if changed {
+                        *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    }
+                    changed
+                }
+            },
+            Statement::While(while_stmt) => self.block(&mut while_stmt.body, arena),
+            Statement::Repeat(repeat_stmt) => self.block(&mut repeat_stmt.body, arena),
+            Statement::Block(block) => self.block(block, arena),
+            Statement::If(if_stmt) => {
+                let mut changed = self.block(&mut if_stmt.then_block, arena);
+                let mut else_ifs = if_stmt.else_ifs.to_vec();
+                let mut else_ifs_changed = false;
+                for else_if in &mut else_ifs {
+                    else_ifs_changed |= self.block(&mut else_if.block, arena);
+                }
+                if else_ifs_changed {
+                    if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+                    changed = true;
+                }
+                if let Some(else_block) = &mut if_stmt.else_block {
+                    changed |= self.block(else_block, arena);
+                }
+                changed
+            }
+            Statement::Function(func) => self.function(func.parameters, &mut func.body, arena),
+            Statement::Class(class) => {
+                let mut members = class.members.to_vec();
+                let mut changed = false;
+                for member in &mut members {
+                    match member {
+                        ClassMember::Method(method) => {
+                            if let Some(body) = &mut method.body {
+                                changed |= self.function(method.parameters, body, arena);
+                            }
+                        }
+                        ClassMember::Constructor(ctor) => {
+                            changed |= self.function(ctor.parameters, &mut ctor.body, arena);
+                        }
+                        _ => {}
+                    }
+                }
+                if changed {
+                    class.members = arena.alloc_slice_clone(&members);
+                }
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    /// The numeric `for` that visits the same elements as `for_gen`, if it
+    /// walks an array its body cannot grow
+    fn rewrite<'arena>(
+        &mut self,
+        for_gen: &ForGeneric<'arena>,
+        arena: &'arena Bump,
+    ) -> Option<ForNumeric<'arena>> {
+        let (iteration, array, index, value) = self.iteration(for_gen)?;
+        let frame = self.frames.last()?;
+        let elements = *frame.arrays.get(&array)?;
+        let name = self.interner.resolve(array);
+
+        if iteration == Iteration::Pairs && elements == Elements::MaybeNil {
+            remarks::missed(PASS, for_gen.span, || {
+                format!(
+                    "elements of `{}` may be nil, so `pairs` may visit indices past a hole",
+                    name
+                )
+            });
+            return None;
+        }
+        let mut loop_names: Vec<StringId> = for_gen.variables.iter().map(|var| var.node).collect();
+        if let Some(pattern) = &for_gen.pattern {
+            pattern_names(pattern, &mut loop_names);
+        }
+        if loop_names.contains(&array) {
+            return None;
+        }
+
+        let mut scan = Scan::new(Some(&self.pure_calls));
+        scan.block(&for_gen.body);
+        let read = Store {
+            table: Some(array),
+            field: None,
+        };
+        let reason = if scan.opaque || scan.hidden_stores {
+            Some("loop body may call a function that changes the array".to_string())
+        } else if scan.written.contains(&array) {
+            Some(format!("loop body assigns `{}`", name))
+        } else if index
+            .as_ref()
+            .is_some_and(|index| scan.written.contains(&index.node))
+        {
+            Some("loop body assigns the index variable".to_string())
+        } else if scan
+            .stores
+            .iter()
+            .any(|store| store.field.is_none() && frame.tables.may_alias(read, *store))
+        {
+            Some(format!("loop body may store into `{}`", name))
+        } else if elements == Elements::MaybeNil && block_contains_continue(&for_gen.body) {
+            Some("the nil check would need `break` in a loop with `continue`".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            remarks::missed(PASS, for_gen.span, || reason);
+            return None;
+        }
+
+        let index = index.unwrap_or_else(|| {
+            let id = self.next_temp_id;
+            self.next_temp_id += 1;
+            let temp = self.interner.get_or_intern(&format!("__iter_{}", id));
+            Spanned::new(temp, for_gen.span)
+        });
+        remarks::applied(PASS, for_gen.span, || {
+            format!(
+                "rewrote {} loop over `{}` into a numeric `for`",
+                iteration.as_str(),
+                name
+            )
+        });
+
+        let span = for_gen.span;
+        let element = Expression::new(
+            ExpressionKind::Index(
+                arena.alloc(Expression::new(ExpressionKind::Identifier(array), span)),
+                arena.alloc(Expression::new(
+                    ExpressionKind::Identifier(index.node),
+                    span,
+                )),
+            ),
+            span,
+        );
+        let mut statements = Vec::with_capacity(for_gen.body.statements.len() + 2);
+        match value {
+            Some(Pattern::Identifier(value)) => {
+                let read = Expression::new(ExpressionKind::Identifier(value.node), span);
+                statements.push(declare(Pattern::Identifier(value), element));
+                if elements == Elements::MaybeNil {
+                    statements.push(break_if_nil(read, arena));
+                }
+            }
+            Some(pattern) => {
+                if elements == Elements::MaybeNil {
+                    statements.push(break_if_nil(element.clone(), arena));
+                }
+                statements.push(declare(pattern, element));
+            }
+            None => {
+                if elements == Elements::MaybeNil {
+                    statements.push(break_if_nil(element, arena));
+                }
+            }
+        }
+        statements.extend(for_gen.body.statements.iter().cloned());
+
+        Some(ForNumeric {
+            variable: index,
+            start: Expression::new(ExpressionKind::Literal(Literal::Integer(1)), span),
+            end: Expression::new(
+                ExpressionKind::Unary(
+                    UnaryOp::Length,
+                    arena.alloc(Expression::new(ExpressionKind::Identifier(array), span)),
+                ),
+                span,
+            ),
+            step: None,
+            body: Block {
+                statements: arena.alloc_slice_clone(&statements),
+                span: for_gen.body.span,
+            },
+            span,
+        })
+    }
+
+    /// How `for_gen` walks a named table: the iteration, the table, the index
+    /// variable and the pattern bound to each element
+    #[allow(clippy::type_complexity)]
+    fn iteration<'arena>(
+        &self,
+        for_gen: &ForGeneric<'arena>,
+    ) -> Option<(
+        Iteration,
+        StringId,
+        Option<Spanned<StringId>>,
+        Option<Pattern<'arena>>,
+    )> {
+        let [iterator] = for_gen.iterators else {
+            return None;
+        };
+        if let Some(pattern) = &for_gen.pattern {
+            let array = variable_name(iterator)?;
+            return Some((Iteration::Destructuring, array, None, Some(pattern.clone())));
+        }
+
+        let ExpressionKind::Call(callee, arguments, _) = &iterator.kind else {
+            return None;
+        };
+        let ExpressionKind::Identifier(function) = &callee.kind else {
+            return None;
+        };
+        let iteration = if Some(*function) == self.ipairs {
+            Iteration::Ipairs
+        } else if Some(*function) == self.pairs {
+            Iteration::Pairs
+        } else {
+            return None;
+        };
+        let [argument] = arguments else {
+            return None;
+        };
+        if argument.is_spread {
+            return None;
+        }
+        let array = variable_name(&argument.value)?;
+        match for_gen.variables {
+            [index] => Some((iteration, array, Some(index.clone()), None)),
+            [index, value] => Some((
+                iteration,
+                array,
+                Some(index.clone()),
+                Some(Pattern::Identifier(value.clone())),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The elements of `ty` if it is an array type
+fn array_elements(ty: &Type<'_>) -> Option<Elements> {
+    match &ty.kind {
+        TypeKind::Array(element) if may_be_nil(element) => Some(Elements::MaybeNil),
+        TypeKind::Array(_) => Some(Elements::NonNil),
+        TypeKind::Parenthesized(inner) => array_elements(inner),
+        _ => None,
+    }
+}
+
+/// Whether `expr` is a table literal with a `nil` element, which leaves a
+/// hole that the element type does not show
+fn has_nil_element(expr: &Expression<'_>) -> bool {
+    match &expr.kind {
+        ExpressionKind::Array(elements) => elements.iter().any(|element| {
+            matches!(
+                element,
+                ArrayElement::Expression(Expression {
+                    kind: ExpressionKind::Literal(Literal::Nil),
+                    ..
+                })
+            )
+        }),
+        ExpressionKind::Parenthesized(inner) => has_nil_element(inner),
+        _ => false,
+    }
+}
+
+/// Whether a value of type `ty` may be nil. Type references may name an
+/// alias or a type parameter that admits nil.
+fn may_be_nil(ty: &Type<'_>) -> bool {
+    match &ty.kind {
+        TypeKind::Primitive(primitive) => matches!(
+            primitive,
+            PrimitiveType::Nil | PrimitiveType::Void | PrimitiveType::Unknown
+        ),
+        TypeKind::Literal(literal) => matches!(literal, Literal::Nil),
+        TypeKind::Parenthesized(inner) => may_be_nil(inner),
+        TypeKind::Union(members) => members.iter().any(may_be_nil),
+        TypeKind::Array(_) | TypeKind::Tuple(_) | TypeKind::Object(_) | TypeKind::Function(_) => {
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Locals declared with a type annotation in `statements`, outside closures,
+/// with their initializers
+pub(super) fn typed_locals<'a, 'arena>(
+    statements: &'a [Statement<'arena>],
+    out: &mut Vec<(StringId, &'a Type<'arena>, &'a Expression<'arena>)>,
+) {
+    for stmt in statements {
+        match stmt {
+            Statement::Variable(decl) => {
+                if let (
+                    VariableKind::Local | VariableKind::Const,
+                    Pattern::Identifier(ident),
+                    Some(ty),
+                ) = (&decl.kind, &decl.pattern, &decl.type_annotation)
+                {
+                    out.push((ident.node, ty, &decl.initializer));
+                }
+            }
+            Statement::If(if_stmt) => {
+                typed_locals(if_stmt.then_block.statements, out);
+                for else_if in if_stmt.else_ifs.iter() {
+                    typed_locals(else_if.block.statements, out);
+                }
+                if let Some(else_block) = &if_stmt.else_block {
+                    typed_locals(else_block.statements, out);
+                }
+            }
+            Statement::While(while_stmt) => typed_locals(while_stmt.body.statements, out),
+            Statement::Repeat(repeat_stmt) => typed_locals(repeat_stmt.body.statements, out),
+            Statement::For(for_stmt) => match &**for_stmt {
+                ForStatement::Numeric(for_num) => typed_locals(for_num.body.statements, out),
+                ForStatement::Generic(for_gen) => typed_locals(for_gen.body.statements, out),
+            },
+            Statement::Block(block) => typed_locals(block.statements, out),
+            _ => {}
+        }
+    }
+}
+
+/// `local pattern = value`
+fn declare<'arena>(pattern: Pattern<'arena>, value: Expression<'arena>) -> Statement<'arena> {
+    let span = value.span;
+    Statement::Variable(VariableDeclaration {
+        kind: VariableKind::Local,
+        pattern,
+        type_annotation: None,
+        initializer: value,
+        span,
+    })
+}
+
+/// `if value == nil then break end`
+fn break_if_nil<'arena>(value: Expression<'arena>, arena: &'arena Bump) -> Statement<'arena> {
+    let span = value.span;
+    let nil = Expression::new(ExpressionKind::Literal(Literal::Nil), span);
+    Statement::If(IfStatement {
+        condition: Expression::new(
+            ExpressionKind::Binary(BinaryOp::Equal, arena.alloc(value), arena.alloc(nil)),
+            span,
+        ),
+        then_block: Block {
+            statements: arena.alloc_slice_clone(&[Statement::Break(span)]),
+            span,
+        },
+        else_ifs: &[],
+        else_block: None,
+        span,
+    })
+}
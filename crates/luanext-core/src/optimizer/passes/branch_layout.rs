@@ -0,0 +1,351 @@
+//! Branch Layout Pass
+//!
+//! Orders the arms of `if` statements by how often they ran in the profile of
+//! a `--profile-use` build, so the hot arm is tested first and falls through
+//! without a jump. Without a profile the pass does nothing.
+//!
+//! An `if`/`else` whose `else` arm ran more often is inverted:
+//!
+//! ```lua
+//! -- Before:
+//! if cached == nil then
+//!     cached = compute(key)
+//! else
+//!     hits = hits + 1
+//! end
+//!
+//! -- After:
+//! if cached ~= nil then
+//!     hits = hits + 1
+//! else
+//!     cached = compute(key)
+//! end
+//! ```
+//!
+//! An `if`/`elseif` chain is reordered hottest arm first when every condition
+//! compares the same variable with a different literal (`op == "add"`,
+//! `op == "sub"`, ...). Those arms are mutually exclusive and their conditions
+//! have no side effects, so the order they are tested in does not matter.
+
+use crate::config::OptimizationLevel;
+use crate::optimizer::profile::ModuleProfile;
+use crate::optimizer::{remarks, WholeProgramPass};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, Literal, UnaryOp};
+use luanext_parser::ast::statement::{
+    Block, ElseIf, ExportKind, ForStatement, IfStatement, Statement,
+};
+use luanext_parser::string_interner::StringId;
+use rustc_hash::FxHashSet;
+use std::sync::Arc;
+
+const PASS: &str = "branch-layout";
+
+pub struct BranchLayoutPass {
+    profile: Option<Arc<ModuleProfile>>,
+    /// Positions of the `if` statements already laid out. Their arms no
+    /// longer match the arm numbers of the profile.
+    laid_out: FxHashSet<(usize, usize)>,
+}
+
+impl BranchLayoutPass {
+    pub fn new() -> Self {
+        Self {
+            profile: None,
+            laid_out: FxHashSet::default(),
+        }
+    }
+
+    /// Lay out branches by the counts in `profile`
+    pub fn with_profile(mut self, profile: Option<Arc<ModuleProfile>>) -> Self {
+        self.profile = profile;
+        self
+    }
+}
+
+impl Default for BranchLayoutPass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for BranchLayoutPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn min_level(&self) -> OptimizationLevel {
+        OptimizationLevel::Moderate
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let Some(profile) = self.profile.clone() else {
+            return Ok(false);
+        };
+
+        let mut changed = false;
+        for stmt in &mut program.statements {
+            changed |= self.visit_stmt(stmt, &profile, arena);
+        }
+        Ok(changed)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl BranchLayoutPass {
+    fn visit_block<'arena>(
+        &mut self,
+        block: &mut Block<'arena>,
+        profile: &ModuleProfile,
+        arena: &'arena Bump,
+    ) -> bool {
+        let mut stmts = block.statements.to_vec();
+        let mut changed = false;
+        for stmt in &mut stmts {
+            changed |= self.visit_stmt(stmt, profile, arena);
+        }
+        if changed {
+            block.statements = arena.alloc_slice_clone(&stmts);
+        }
+        changed
+    }
+
+    fn visit_stmt<'arena>(
+        &mut self,
+        stmt: &mut Statement<'arena>,
+        profile: &ModuleProfile,
+        arena: &'arena Bump,
+    ) -> bool {
+        match stmt {
+            Statement::If(if_stmt) => {
+                let mut changed = self.lay_out(if_stmt, profile, arena);
+                changed |= self.visit_block(&mut if_stmt.then_block, profile, arena);
+                let mut else_ifs = if_stmt.else_ifs.to_vec();
+                let mut else_ifs_changed = false;
+                for else_if in &mut else_ifs {
+                    else_ifs_changed |= self.visit_block(&mut else_if.block, profile, arena);
+                }
+                if else_ifs_changed {
+                    if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+                    changed = true;
+                }
+                if let Some(else_block) = &mut if_stmt.else_block {
+                    changed |= self.visit_block(else_block, profile, arena);
+                }
+                changed
+            }
+            Statement::Function(func) => self.visit_block(&mut func.body, profile, arena),
+            Statement::While(while_stmt) => self.visit_block(&mut while_stmt.body, profile, arena),
+            Statement::Repeat(repeat_stmt) => {
+                self.visit_block(&mut repeat_stmt.body, profile, arena)
+            }
+            Statement::For(for_stmt) => match &**for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    let mut new_num = (**for_num).clone();
+                    if !self.visit_block(&mut new_num.body, profile, arena) {
+                        return false;
+                    }
+                    *stmt =
+                        Statement::For(arena.alloc(ForStatement::Numeric(arena.alloc(new_num))));
+                    true
+                }
+                ForStatement::Generic(for_gen) => {
+                    let mut new_gen = for_gen.clone();
+                    if !self.visit_block(&mut new_gen.body, profile, arena) {
+                        return false;
+                    }
+                    *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    true
+                }
+            },
+            Statement::Block(block) => self.visit_block(block, profile, arena),
+            Statement::Try(try_stmt) => {
+                let mut changed = self.visit_block(&mut try_stmt.try_block, profile, arena);
+                let mut catch_clauses = try_stmt.catch_clauses.to_vec();
+                let mut catch_changed = false;
+                for catch in &mut catch_clauses {
+                    catch_changed |= self.visit_block(&mut catch.body, profile, arena);
+                }
+                if catch_changed {
+                    try_stmt.catch_clauses = arena.alloc_slice_clone(&catch_clauses);
+                    changed = true;
+                }
+                if let Some(finally_block) = &mut try_stmt.finally_block {
+                    changed |= self.visit_block(finally_block, profile, arena);
+                }
+                changed
+            }
+            Statement::Export(export) => {
+                if let ExportKind::Declaration(inner) = export.kind {
+                    let mut new_inner = inner.clone();
+                    if self.visit_stmt(&mut new_inner, profile, arena) {
+                        export.kind = ExportKind::Declaration(arena.alloc(new_inner));
+                        return true;
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Put the most taken arm of `if_stmt` first, if that is safe
+    fn lay_out<'arena>(
+        &mut self,
+        if_stmt: &mut IfStatement<'arena>,
+        profile: &ModuleProfile,
+        arena: &'arena Bump,
+    ) -> bool {
+        let position = (if_stmt.span.line as usize, if_stmt.span.column as usize);
+        if self.laid_out.contains(&position) {
+            return false;
+        }
+
+        let changed = if if_stmt.else_ifs.is_empty() {
+            Self::invert(if_stmt, profile, arena)
+        } else {
+            Self::reorder(if_stmt, profile, arena)
+        };
+        if changed {
+            self.laid_out.insert(position);
+        }
+        changed
+    }
+
+    /// Swap the arms of an `if`/`else` whose `else` arm ran more often
+    fn invert<'arena>(
+        if_stmt: &mut IfStatement<'arena>,
+        profile: &ModuleProfile,
+        arena: &'arena Bump,
+    ) -> bool {
+        let Some(else_block) = if_stmt.else_block.clone() else {
+            return false;
+        };
+        let then_count = profile.branch_count(if_stmt.span, 0);
+        let else_count = profile.branch_count(if_stmt.span, 1);
+        if else_count <= then_count {
+            return false;
+        }
+
+        remarks::applied(PASS, if_stmt.span, || {
+            format!(
+                "inverted the branch: the `else` arm ran {} times, the `then` arm {}",
+                else_count, then_count
+            )
+        });
+        if_stmt.condition = negate(&if_stmt.condition, arena);
+        if_stmt.else_block = Some(std::mem::replace(&mut if_stmt.then_block, else_block));
+        true
+    }
+
+    /// Sort an `if`/`elseif` chain over distinct literals by arm count
+    fn reorder<'arena>(
+        if_stmt: &mut IfStatement<'arena>,
+        profile: &ModuleProfile,
+        arena: &'arena Bump,
+    ) -> bool {
+        let mut arms = vec![(
+            if_stmt.condition.clone(),
+            if_stmt.then_block.clone(),
+            if_stmt.span,
+        )];
+        arms.extend(if_stmt.else_ifs.iter().map(|else_if| {
+            (
+                else_if.condition.clone(),
+                else_if.block.clone(),
+                else_if.span,
+            )
+        }));
+
+        // Every condition must be `<same variable> == <distinct literal>`
+        let mut variable = None;
+        let mut literals = FxHashSet::default();
+        for (condition, _, _) in &arms {
+            let Some((name, literal)) = literal_comparison(condition) else {
+                return false;
+            };
+            if *variable.get_or_insert(name) != name || !literals.insert(literal) {
+                return false;
+            }
+        }
+
+        let counts: Vec<u64> = (0..arms.len())
+            .map(|arm| profile.branch_count(if_stmt.span, arm))
+            .collect();
+        let mut order: Vec<usize> = (0..arms.len()).collect();
+        order.sort_by(|&a, &b| counts[b].cmp(&counts[a]));
+        if order
+            .iter()
+            .enumerate()
+            .all(|(position, &arm)| position == arm)
+        {
+            return false;
+        }
+
+        remarks::applied(PASS, if_stmt.span, || {
+            format!(
+                "reordered {} arms by profile count, hottest ran {} times",
+                arms.len(),
+                counts[order[0]]
+            )
+        });
+        let mut sorted = order.into_iter().map(|arm| arms[arm].clone());
+        let (condition, then_block, _) = sorted.next().expect("if has a first arm");
+        let else_ifs: Vec<ElseIf<'arena>> = sorted
+            .map(|(condition, block, span)| ElseIf {
+                condition,
+                block,
+                span,
+            })
+            .collect();
+        if_stmt.condition = condition;
+        if_stmt.then_block = then_block;
+        if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+        true
+    }
+}
+
+/// The logical negation of a branch condition
+fn negate<'arena>(condition: &Expression<'arena>, arena: &'arena Bump) -> Expression<'arena> {
+    let kind = match &condition.kind {
+        ExpressionKind::Binary(BinaryOp::Equal, left, right) => {
+            ExpressionKind::Binary(BinaryOp::NotEqual, left, right)
+        }
+        ExpressionKind::Binary(BinaryOp::NotEqual, left, right) => {
+            ExpressionKind::Binary(BinaryOp::Equal, left, right)
+        }
+        // `not x` and `x` differ in value but not in truthiness
+        ExpressionKind::Unary(UnaryOp::Not, operand) => return (**operand).clone(),
+        _ => ExpressionKind::Unary(UnaryOp::Not, arena.alloc(condition.clone())),
+    };
+    Expression::new(kind, condition.span)
+}
+
+/// `(x, literal)` for `x == literal` or `literal == x`. Numbers compare by
+/// value, so `1` and `1.0` are the same literal.
+fn literal_comparison(condition: &Expression<'_>) -> Option<(StringId, String)> {
+    let ExpressionKind::Binary(BinaryOp::Equal, left, right) = &condition.kind else {
+        return None;
+    };
+    let (name, literal) = match (&left.kind, &right.kind) {
+        (ExpressionKind::Identifier(name), ExpressionKind::Literal(literal))
+        | (ExpressionKind::Literal(literal), ExpressionKind::Identifier(name)) => (*name, literal),
+        _ => return None,
+    };
+    let key = match literal {
+        Literal::Nil => "nil".to_string(),
+        Literal::Boolean(value) => format!("boolean {}", value),
+        Literal::Number(value) => format!("number {}", value),
+        Literal::Integer(value) => format!("number {}", *value as f64),
+        Literal::String(value) => format!("string {}", value),
+    };
+    Some((name, key))
+}
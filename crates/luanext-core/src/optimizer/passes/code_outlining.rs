@@ -0,0 +1,229 @@
+//! Code outlining (size goal)
+//!
+//! Moves a run of statements that repeats within a block into a local
+//! function and replaces every occurrence with a call:
+//!
+//! ```lua
+//! -- Before
+//! log("start") flush()
+//! work()
+//! log("start") flush()
+//!
+//! -- After
+//! local function a()
+//!     log("start") flush()
+//! end
+//! a()
+//! work()
+//! a()
+//! ```
+//!
+//! Occurrences are matched by their generated Lua, so two runs only match when
+//! they would emit the same code. A run is outlined when:
+//! - it has at least two statements, none of which declares a block-level name
+//! - it contains no `return`, `break`, `goto`, label or `...`, whose meaning
+//!   would change inside a function
+//! - no statement between the first and the last occurrence declares a name,
+//!   so every identifier resolves to the same binding inside the function
+//! - the calls and the function together are shorter than the copies
+
+use super::short_names::NameGenerator;
+use crate::codegen::CodeGenerator;
+use crate::config::OutputFormat;
+use crate::optimizer::{remarks, BlockVisitor};
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{Expression, ExpressionKind};
+use luanext_parser::ast::statement::{Block, FunctionDeclaration, Statement};
+use luanext_parser::ast::Spanned;
+use luanext_parser::string_interner::StringInterner;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::Arc;
+
+const PASS: &str = "code-outlining";
+
+/// Longest run of statements considered for outlining
+const MAX_SEQUENCE_LEN: usize = 32;
+
+/// Generated code that must not move into a function body
+const FORBIDDEN_TOKENS: &[&str] = &["return", "break", "goto", "::", "..."];
+
+/// A repeated run of statements and where it occurs
+struct Candidate {
+    len: usize,
+    starts: Vec<usize>,
+    savings: usize,
+}
+
+pub struct CodeOutliningPass {
+    interner: Arc<StringInterner>,
+}
+
+impl CodeOutliningPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self { interner }
+    }
+
+    /// Minified Lua of each statement, or `None` if it can't be outlined
+    fn statement_keys(&self, stmts: &[Statement]) -> Vec<Option<String>> {
+        let mut generator =
+            CodeGenerator::new(self.interner.clone()).with_output_format(OutputFormat::Minified);
+        stmts
+            .iter()
+            .map(|stmt| {
+                if !is_non_binding(stmt) || matches!(stmt, Statement::Return(_)) {
+                    return None;
+                }
+                let code = generator.statement_to_string(stmt);
+                let movable = !FORBIDDEN_TOKENS.iter().any(|token| code.contains(token));
+                movable.then_some(code)
+            })
+            .collect()
+    }
+
+    /// The repeated run whose outlining saves the most bytes
+    fn best_candidate(&self, stmts: &[Statement], name_len: usize) -> Option<Candidate> {
+        let keys = self.statement_keys(stmts);
+        let max_len = (stmts.len() / 2).min(MAX_SEQUENCE_LEN);
+        let mut best: Option<Candidate> = None;
+
+        for len in 2..=max_len {
+            let mut occurrences: FxHashMap<String, Vec<usize>> = FxHashMap::default();
+            for start in 0..=stmts.len() - len {
+                let Some(run) = keys[start..start + len]
+                    .iter()
+                    .map(|key| key.as_deref())
+                    .collect::<Option<Vec<&str>>>()
+                else {
+                    continue;
+                };
+                occurrences.entry(run.concat()).or_default().push(start);
+            }
+
+            for (code, starts) in occurrences {
+                // Keep non-overlapping occurrences, left to right
+                let mut kept: Vec<usize> = Vec::new();
+                for start in starts {
+                    if kept.last().is_none_or(|&last| start >= last + len) {
+                        kept.push(start);
+                    }
+                }
+                if kept.len() < 2 {
+                    continue;
+                }
+                let first = kept[0];
+                let end = kept[kept.len() - 1] + len;
+                if !stmts[first..end].iter().all(is_non_binding) {
+                    continue;
+                }
+
+                let Some(savings) = outlining_savings(code.len(), kept.len(), name_len) else {
+                    continue;
+                };
+                if best.as_ref().is_none_or(|b| savings > b.savings) {
+                    best = Some(Candidate {
+                        len,
+                        starts: kept,
+                        savings,
+                    });
+                }
+            }
+        }
+
+        best
+    }
+
+    fn outline<'arena>(
+        &self,
+        stmts: &mut Vec<Statement<'arena>>,
+        candidate: &Candidate,
+        name: &str,
+        arena: &'arena Bump,
+    ) {
+        let name_id = self.interner.get_or_intern(name);
+
+        let first = candidate.starts[0];
+        let body = &stmts[first..first + candidate.len];
+        let span = remarks::statement_span(&body[0]);
+        let function = Statement::Function(FunctionDeclaration {
+            name: Spanned::new(name_id, span),
+            type_parameters: None,
+            parameters: &[],
+            return_type: None,
+            throws: None,
+            body: Block {
+                statements: arena.alloc_slice_clone(body),
+                span,
+            },
+            span,
+        });
+
+        // Replace from the back so earlier indices stay valid
+        for &start in candidate.starts.iter().rev() {
+            let span = remarks::statement_span(&stmts[start]);
+            let callee = Expression::new(ExpressionKind::Identifier(name_id), span);
+            let call = Expression::new(ExpressionKind::Call(arena.alloc(callee), &[], None), span);
+            stmts.splice(
+                start..start + candidate.len,
+                std::iter::once(Statement::Expression(call)),
+            );
+        }
+        stmts.insert(first, function);
+
+        remarks::applied(PASS, span, || {
+            format!(
+                "outlined {} repeated statements into `{}` ({} occurrences, {} bytes saved)",
+                candidate.len,
+                name,
+                candidate.starts.len(),
+                candidate.savings
+            )
+        });
+    }
+}
+
+impl<'arena> BlockVisitor<'arena> for CodeOutliningPass {
+    fn visit_block_stmts(
+        &mut self,
+        stmts: &mut Vec<Statement<'arena>>,
+        arena: &'arena Bump,
+    ) -> bool {
+        let mut changed = false;
+        loop {
+            let taken: FxHashSet<String> = self.interner.to_strings().into_iter().collect();
+            let name = NameGenerator::new(taken).next_name();
+            let Some(candidate) = self.best_candidate(stmts, name.len()) else {
+                return changed;
+            };
+            self.outline(stmts, &candidate, &name, arena);
+            changed = true;
+        }
+    }
+}
+
+/// Whether a statement leaves the names visible in its block unchanged
+fn is_non_binding(stmt: &Statement) -> bool {
+    matches!(
+        stmt,
+        Statement::Expression(_)
+            | Statement::If(_)
+            | Statement::While(_)
+            | Statement::For(_)
+            | Statement::Repeat(_)
+            | Statement::Block(_)
+            | Statement::Return(_)
+            | Statement::Break(_)
+            | Statement::Continue(_)
+    )
+}
+
+/// Bytes saved by replacing `count` copies of `code_len` bytes with calls to
+/// a local function, or `None` if outlining doesn't pay off
+fn outlining_savings(code_len: usize, count: usize, name_len: usize) -> Option<usize> {
+    // `local function a()` ... `end`, plus separators
+    let definition = "local function ()".len() + name_len + "end".len() + 2;
+    // `a()` plus a separator
+    let call = name_len + 3;
+    let before = count * code_len;
+    let after = code_len + definition + count * call;
+    before.checked_sub(after).filter(|&saved| saved > 0)
+}
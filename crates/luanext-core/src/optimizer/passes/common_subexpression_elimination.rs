@@ -34,7 +34,7 @@
 //! local y = x         -- eliminated duplicate
 //! ```
 
-use crate::optimizer::BlockVisitor;
+use crate::optimizer::{remarks, BlockVisitor};
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, Literal, UnaryOp};
 use luanext_parser::ast::pattern::Pattern;
@@ -44,6 +44,8 @@ use rustc_hash::FxHashMap;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
+const PASS: &str = "common-subexpression-elimination";
+
 /// Common Subexpression Elimination pass.
 ///
 /// Uses local value numbering to detect and eliminate duplicate computations.
@@ -249,6 +251,9 @@ impl CommonSubexpressionEliminationPass {
 
         // Check for CSE opportunity
         if let Some(existing_var) = self.find_cse_opportunity(&decl.initializer) {
+            remarks::applied(PASS, decl.initializer.span, || {
+                "reused the value of an identical earlier expression".to_string()
+            });
             // Replace with existing variable
             decl.initializer = Expression {
                 kind: ExpressionKind::Identifier(existing_var),
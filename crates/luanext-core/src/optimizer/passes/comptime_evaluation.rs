@@ -0,0 +1,475 @@
+//! Compile-time evaluation of const functions
+//!
+//! A const function is a top-level function, declared with `function` or
+//! bound to a `const`, that the `optimizer.hints` section of the config
+//! marks `comptime`:
+//!
+//! ```yaml
+//! optimizer:
+//!   hints:
+//!     squares: [comptime]
+//! ```
+//!
+//! Nothing else is evaluated, however constant it looks. Each call to a
+//! const function whose arguments are constants is run at compile time, and
+//! replaced by a literal of its result:
+//!
+//! ```lua
+//! -- Before:
+//! const squares = function(n: integer): integer[]
+//!     local out = {}
+//!     for i = 1, n do
+//!         out[i] = i * i
+//!     end
+//!     return out
+//! end
+//! local lookup = squares(5)
+//!
+//! -- After:
+//! local lookup = { 1, 4, 9, 16, 25 }
+//! ```
+//!
+//! Calls are run by the interpreter in `comptime_interpreter.rs`, which
+//! follows calls into other top-level functions declared before the caller,
+//! so `function` declarations serve as helpers and may recurse. Top-level
+//! `const` declarations of numbers, strings and booleans are readable.
+//! Every name involved must be declared once in the program and never
+//! assigned.
+//!
+//! A call is left alone, with a missed remark giving the reason, when
+//! evaluation reaches something outside the interpreter's pure subset, runs
+//! out of fuel, or returns a value with no literal: a float of integral
+//! value, whose literal Lua 5.3 would read as an integer, a table that
+//! appears twice in the result, or a result of more than 4096 table entries.
+//! Calls whose arguments are not constants are skipped silently.
+
+use super::comptime_interpreter::{
+    FunctionBody, Global, GlobalKind, Interpreter, Key, Table, Value,
+};
+use super::memory_effects::Scan;
+use super::short_names::LUA_KEYWORDS;
+use crate::config::OptimizationLevel;
+use crate::optimizer::hints::FreeFunctionHints;
+use crate::optimizer::{
+    remarks, visit_block_expressions, visit_expr_children, visit_stmt_expressions, AstFeatures,
+    WholeProgramPass,
+};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{
+    Argument, ArrayElement, ArrowBody, Expression, ExpressionKind, Literal, ObjectProperty,
+};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{ExportKind, Statement, VariableKind};
+use luanext_parser::ast::Spanned;
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+const PASS: &str = "comptime-evaluation";
+
+/// Most table entries a result may have
+const MAX_RESULT_ENTRIES: usize = 4096;
+
+/// Evaluates calls to const functions with constant arguments at compile
+/// time
+pub struct ComptimeEvaluationPass {
+    interner: Arc<StringInterner>,
+    hints: Arc<FreeFunctionHints>,
+}
+
+impl ComptimeEvaluationPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            interner,
+            hints: Arc::default(),
+        }
+    }
+
+    /// Evaluate calls to the functions these hints mark `comptime`
+    pub fn with_hints(mut self, hints: Arc<FreeFunctionHints>) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    fn is_marked(&self, name: StringId) -> bool {
+        self.hints.get(&self.interner.resolve(name)).comptime
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for ComptimeEvaluationPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn min_level(&self) -> OptimizationLevel {
+        OptimizationLevel::Minimal
+    }
+
+    fn required_features(&self) -> AstFeatures {
+        AstFeatures::HAS_FUNCTIONS
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let mut scan = Scan::new(None);
+        scan.statements(&program.statements);
+        let mut bound: FxHashSet<StringId> = scan.declared.keys().copied().collect();
+        bound.extend(scan.written.iter().chain(&scan.stored));
+
+        let mut globals = FxHashMap::default();
+        let mut const_functions = FxHashSet::default();
+        for (position, stmt) in program.statements.iter().enumerate() {
+            let stmt = match stmt {
+                Statement::Export(export) => match export.kind {
+                    ExportKind::Declaration(inner) => inner,
+                    _ => continue,
+                },
+                stmt => stmt,
+            };
+            let Some((name, global)) =
+                self.global(stmt, position, &globals, &bound, &mut const_functions)
+            else {
+                continue;
+            };
+            if scan.declared.get(&name) == Some(&1) && !scan.written.contains(&name) {
+                globals.insert(name, global);
+            } else {
+                const_functions.remove(&name);
+            }
+        }
+        if const_functions.is_empty() {
+            return Ok(false);
+        }
+
+        let mut evaluator = Evaluator {
+            interner: &self.interner,
+            globals: &globals,
+            bound: &bound,
+            const_functions: &const_functions,
+            position: 0,
+        };
+        let mut changed = false;
+        for (position, stmt) in program.statements.iter_mut().enumerate() {
+            evaluator.position = position;
+            changed |= evaluator.statement(stmt, arena);
+        }
+        Ok(changed)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl ComptimeEvaluationPass {
+    /// The name `stmt` binds for the interpreter, when it declares a function
+    /// or a `const` scalar
+    fn global<'arena>(
+        &self,
+        stmt: &Statement<'arena>,
+        position: usize,
+        globals: &FxHashMap<StringId, Global<'arena>>,
+        bound: &FxHashSet<StringId>,
+        const_functions: &mut FxHashSet<StringId>,
+    ) -> Option<(StringId, Global<'arena>)> {
+        match stmt {
+            Statement::Function(func) => {
+                if self.is_marked(func.name.node) {
+                    const_functions.insert(func.name.node);
+                }
+                Some((
+                    func.name.node,
+                    Global {
+                        position,
+                        visible_from: position,
+                        kind: GlobalKind::Function {
+                            parameters: func.parameters,
+                            body: FunctionBody::Block(func.body.statements),
+                        },
+                    },
+                ))
+            }
+            Statement::Variable(decl) if matches!(decl.kind, VariableKind::Const) => {
+                let Pattern::Identifier(name) = &decl.pattern else {
+                    return None;
+                };
+                let kind = match &decl.initializer.kind {
+                    ExpressionKind::Function(func) => GlobalKind::Function {
+                        parameters: func.parameters,
+                        body: FunctionBody::Block(func.body.statements),
+                    },
+                    ExpressionKind::Arrow(arrow) => GlobalKind::Function {
+                        parameters: arrow.parameters,
+                        body: match &arrow.body {
+                            ArrowBody::Expression(body) => FunctionBody::Expression(*body),
+                            ArrowBody::Block(block) => FunctionBody::Block(block.statements),
+                        },
+                    },
+                    _ => {
+                        let mut interpreter =
+                            Interpreter::new(&self.interner, globals, bound, position);
+                        match interpreter.evaluate(&decl.initializer) {
+                            Ok(Value::Table(_)) | Err(_) => return None,
+                            Ok(value) => GlobalKind::Constant(value),
+                        }
+                    }
+                };
+                if matches!(kind, GlobalKind::Function { .. }) && self.is_marked(name.node) {
+                    const_functions.insert(name.node);
+                }
+                Some((
+                    name.node,
+                    Global {
+                        position,
+                        visible_from: position + 1,
+                        kind,
+                    },
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Replaces calls to const functions in one run
+struct Evaluator<'e, 'arena> {
+    interner: &'e StringInterner,
+    globals: &'e FxHashMap<StringId, Global<'arena>>,
+    bound: &'e FxHashSet<StringId>,
+    const_functions: &'e FxHashSet<StringId>,
+    /// Top-level statement being rewritten
+    position: usize,
+}
+
+impl<'arena> Evaluator<'_, 'arena> {
+    fn statement(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) -> bool {
+        if let Statement::Export(export) = stmt {
+            let ExportKind::Declaration(inner) = export.kind else {
+                return false;
+            };
+            let mut new_inner = inner.clone();
+            let changed = self.statement(&mut new_inner, arena);
+            if changed {
+                export.kind = ExportKind::Declaration(arena.alloc(new_inner));
+            }
+            return changed;
+        }
+        visit_stmt_expressions(stmt, arena, &mut |e, a| self.expression(e, a))
+    }
+
+    fn expression(&mut self, expr: &mut Expression<'arena>, arena: &'arena Bump) -> bool {
+        match &expr.kind {
+            ExpressionKind::Call(callee, arguments, _) => {
+                let result = match callee.kind {
+                    ExpressionKind::Identifier(name) => {
+                        self.evaluate(name, arguments, expr.span, arena)
+                    }
+                    _ => None,
+                };
+                if let Some(result) = result {
+                    *expr = result;
+                    return true;
+                }
+                visit_expr_children(expr, arena, &mut |e, a| self.expression(e, a))
+            }
+            ExpressionKind::Function(func) => {
+                let mut new_func = func.clone();
+                let changed = visit_block_expressions(&mut new_func.body, arena, &mut |e, a| {
+                    self.expression(e, a)
+                });
+                if changed {
+                    expr.kind = ExpressionKind::Function(new_func);
+                }
+                changed
+            }
+            ExpressionKind::Arrow(arrow) => {
+                let mut new_arrow = arrow.clone();
+                let changed = match &mut new_arrow.body {
+                    ArrowBody::Expression(body) => {
+                        let mut new_body = (**body).clone();
+                        let changed = self.expression(&mut new_body, arena);
+                        *body = arena.alloc(new_body);
+                        changed
+                    }
+                    ArrowBody::Block(block) => {
+                        visit_block_expressions(block, arena, &mut |e, a| self.expression(e, a))
+                    }
+                };
+                if changed {
+                    expr.kind = ExpressionKind::Arrow(new_arrow);
+                }
+                changed
+            }
+            _ => visit_expr_children(expr, arena, &mut |e, a| self.expression(e, a)),
+        }
+    }
+
+    /// The literal a call to `name` evaluates to, if it is a const function
+    /// visible here and its arguments are constants
+    fn evaluate(
+        &self,
+        name: StringId,
+        arguments: &[Argument<'arena>],
+        span: Span,
+        arena: &'arena Bump,
+    ) -> Option<Expression<'arena>> {
+        if !self.const_functions.contains(&name) {
+            return None;
+        }
+        if !self
+            .globals
+            .get(&name)
+            .is_some_and(|global| global.visible_from <= self.position)
+        {
+            return None;
+        }
+
+        let mut interpreter =
+            Interpreter::new(self.interner, self.globals, self.bound, self.position);
+        let mut values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            if argument.is_spread {
+                return None;
+            }
+            values.push(interpreter.evaluate(&argument.value).ok()?);
+        }
+
+        let result = interpreter.call(name, values).and_then(|value| {
+            let mut converter = Converter {
+                interner: self.interner,
+                seen: FxHashSet::default(),
+                entries: 0,
+                span,
+                arena,
+            };
+            converter.literal(&value)
+        });
+        let function = self.interner.resolve(name);
+        match result {
+            Ok(literal) => {
+                remarks::applied(PASS, span, || {
+                    format!(
+                        "evaluated call to `{}` at compile time in {} steps",
+                        function,
+                        interpreter.steps()
+                    )
+                });
+                Some(literal)
+            }
+            Err(reason) => {
+                remarks::missed(PASS, span, || {
+                    format!(
+                        "call to `{}` not evaluated at compile time: {}",
+                        function, reason
+                    )
+                });
+                None
+            }
+        }
+    }
+}
+
+/// Turns a value into a literal expression
+struct Converter<'c, 'arena> {
+    interner: &'c StringInterner,
+    /// Tables converted so far
+    seen: FxHashSet<*const RefCell<Table>>,
+    /// Table entries converted so far
+    entries: usize,
+    span: Span,
+    arena: &'arena Bump,
+}
+
+impl<'arena> Converter<'_, 'arena> {
+    fn literal(&mut self, value: &Value) -> Result<Expression<'arena>, String> {
+        let kind = match value {
+            Value::Nil => ExpressionKind::Literal(Literal::Nil),
+            Value::Boolean(value) => ExpressionKind::Literal(Literal::Boolean(*value)),
+            Value::Integer(value) => ExpressionKind::Literal(Literal::Integer(*value)),
+            Value::Number(value) if !value.is_finite() => {
+                return Err("it returns a float that is not finite".to_string())
+            }
+            Value::Number(value) if value.fract() == 0.0 => {
+                return Err(format!(
+                    "it returns the float {}, whose literal Lua 5.3 reads as an integer",
+                    value
+                ))
+            }
+            Value::Number(value) => ExpressionKind::Literal(Literal::Number(*value)),
+            Value::String(value) if value.chars().any(char::is_control) => {
+                return Err("it returns a string with control characters".to_string())
+            }
+            Value::String(value) => ExpressionKind::Literal(Literal::String(value.clone())),
+            Value::Table(table) => {
+                if !self.seen.insert(Rc::as_ptr(table)) {
+                    return Err("it returns a table that appears twice in the result".to_string());
+                }
+                let table = table.borrow();
+                self.entries += table.array.len() + table.hash.len();
+                if self.entries > MAX_RESULT_ENTRIES {
+                    return Err(format!(
+                        "its result has more than {} table entries",
+                        MAX_RESULT_ENTRIES
+                    ));
+                }
+                if table.hash.is_empty() {
+                    let mut elements = Vec::with_capacity(table.array.len());
+                    for value in &table.array {
+                        elements.push(ArrayElement::Expression(self.literal(value)?));
+                    }
+                    ExpressionKind::Array(self.arena.alloc_slice_clone(&elements))
+                } else {
+                    let mut properties = Vec::with_capacity(table.array.len() + table.hash.len());
+                    for (index, value) in table.array.iter().enumerate() {
+                        let key = Key::Integer(index as i64 + 1);
+                        properties.push(self.property(&key, value)?);
+                    }
+                    for (key, value) in &table.hash {
+                        properties.push(self.property(key, value)?);
+                    }
+                    ExpressionKind::Object(self.arena.alloc_slice_clone(&properties))
+                }
+            }
+        };
+        Ok(Expression::new(kind, self.span))
+    }
+
+    fn property(&mut self, key: &Key, value: &Value) -> Result<ObjectProperty<'arena>, String> {
+        let value = self.arena.alloc(self.literal(value)?);
+        let key = match key {
+            Key::String(name) if is_identifier(name) => {
+                return Ok(ObjectProperty::Property {
+                    key: Spanned::new(self.interner.get_or_intern(name), self.span),
+                    value,
+                    span: self.span,
+                });
+            }
+            Key::String(name) => self.literal(&Value::String(name.clone()))?,
+            Key::Boolean(key) => self.literal(&Value::Boolean(*key))?,
+            Key::Integer(key) => self.literal(&Value::Integer(*key))?,
+            Key::Number(bits) => self.literal(&Value::Number(f64::from_bits(*bits)))?,
+        };
+        Ok(ObjectProperty::Computed {
+            key: self.arena.alloc(key),
+            value,
+            span: self.span,
+        })
+    }
+}
+
+/// Whether `name` can be written as a bare field name
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !LUA_KEYWORDS.contains(&name)
+}
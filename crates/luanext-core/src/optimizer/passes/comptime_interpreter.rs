@@ -0,0 +1,1214 @@
+//! Interpreter for compile-time evaluation
+//!
+//! Runs calls to const functions over the LuaNext AST, for
+//! `ComptimeEvaluationPass`. It covers a pure subset of the language:
+//! arithmetic, comparisons, strings, tables, `if`, loops, and calls to other
+//! top-level functions, including recursive ones, and to a few builtins that
+//! only compute values. Anything outside that subset, or whose result depends
+//! on the Lua version the program targets, stops evaluation with the reason,
+//! which the pass reports as a missed remark.
+//!
+//! Every statement and expression evaluated spends one unit of fuel, so
+//! runaway loops and recursion end.
+
+use luanext_parser::ast::expression::{
+    ArrayElement, AssignmentOp, BinaryOp, Expression, ExpressionKind, Literal,
+    ObjectProperty, UnaryOp,
+};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{ForStatement, Parameter, Statement, VariableKind};
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+/// Steps one evaluation may take
+pub(super) const FUEL: usize = 100_000;
+
+/// Calls one evaluation may nest
+const MAX_DEPTH: usize = 200;
+
+/// Longest string one evaluation may build
+const MAX_STRING_LEN: usize = 64 * 1024;
+
+/// Integers beyond this lose precision as Lua 5.1 numbers
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+pub(super) type Eval<T> = Result<T, String>;
+
+/// A value at compile time
+#[derive(Debug, Clone)]
+pub(super) enum Value {
+    Nil,
+    Boolean(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+    Table(Rc<RefCell<Table>>),
+}
+
+/// A table key, with floats of integral value normalized to integers as Lua
+/// does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Key {
+    Boolean(bool),
+    Integer(i64),
+    /// Bits of a float with a fraction
+    Number(u64),
+    String(String),
+}
+
+/// A table at compile time
+#[derive(Debug, Default)]
+pub(super) struct Table {
+    /// Values at keys `1..=array.len()`, none of them nil
+    pub(super) array: Vec<Value>,
+    /// The other entries, in insertion order
+    pub(super) hash: Vec<(Key, Value)>,
+}
+
+impl Table {
+    fn get(&self, key: &Key) -> Value {
+        if let Key::Integer(index) = key {
+            if *index >= 1 && *index <= self.array.len() as i64 {
+                return self.array[*index as usize - 1].clone();
+            }
+        }
+        self.hash
+            .iter()
+            .find(|(k, _)| k == key)
+            .map_or(Value::Nil, |(_, value)| value.clone())
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        let is_nil = matches!(value, Value::Nil);
+        if let Key::Integer(index) = key {
+            let len = self.array.len() as i64;
+            if index >= 1 && index <= len {
+                if is_nil {
+                    // Entries after the hole move to the hash part
+                    let tail = self.array.split_off(index as usize - 1);
+                    for (offset, value) in tail.into_iter().enumerate().skip(1) {
+                        self.hash.push((Key::Integer(index + offset as i64), value));
+                    }
+                } else {
+                    self.array[index as usize - 1] = value;
+                }
+                return;
+            }
+            if index == len + 1 && !is_nil {
+                self.array.push(value);
+                // Entries that now follow the array part join it
+                while let Some(value) = self.take(&Key::Integer(self.array.len() as i64 + 1)) {
+                    self.array.push(value);
+                }
+                return;
+            }
+        }
+        match self.hash.iter().position(|(k, _)| *k == key) {
+            Some(position) if is_nil => {
+                self.hash.remove(position);
+            }
+            Some(position) => self.hash[position].1 = value,
+            None if is_nil => {}
+            None => self.hash.push((key, value)),
+        }
+    }
+
+    fn take(&mut self, key: &Key) -> Option<Value> {
+        let position = self.hash.iter().position(|(k, _)| k == key)?;
+        Some(self.hash.remove(position).1)
+    }
+
+    /// `#t`, when every border gives the same length
+    fn len(&self) -> Eval<usize> {
+        if self
+            .hash
+            .iter()
+            .any(|(key, _)| matches!(key, Key::Integer(index) if *index > 0))
+        {
+            return Err("it takes the length of a table with holes".to_string());
+        }
+        Ok(self.array.len())
+    }
+}
+
+/// The body of a top-level function
+#[derive(Clone, Copy)]
+pub(super) enum FunctionBody<'arena> {
+    Block(&'arena [Statement<'arena>]),
+    Expression(&'arena Expression<'arena>),
+}
+
+/// What a top-level name is bound to
+pub(super) enum GlobalKind<'arena> {
+    Function {
+        parameters: &'arena [Parameter<'arena>],
+        body: FunctionBody<'arena>,
+    },
+    Constant(Value),
+}
+
+/// A top-level name the interpreter can use
+pub(super) struct Global<'arena> {
+    /// Index of the top-level statement declaring it
+    pub(super) position: usize,
+    /// First top-level statement that sees it: a `local function` sees
+    /// itself, a `local` only the statements after it
+    pub(super) visible_from: usize,
+    pub(super) kind: GlobalKind<'arena>,
+}
+
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+/// Evaluates expressions and calls for one call site
+pub(super) struct Interpreter<'g, 'arena> {
+    interner: &'g StringInterner,
+    globals: &'g FxHashMap<StringId, Global<'arena>>,
+    /// Names the program declares or assigns anywhere, which are not the
+    /// builtins they may share a name with
+    bound: &'g FxHashSet<StringId>,
+    fuel: usize,
+    depth: usize,
+    /// Local scopes of the running call, innermost last
+    scopes: Vec<FxHashMap<StringId, Value>>,
+    /// Top-level statement the running code belongs to
+    position: usize,
+}
+
+impl<'g, 'arena> Interpreter<'g, 'arena> {
+    /// An interpreter for code in the top-level statement at `position`
+    pub(super) fn new(
+        interner: &'g StringInterner,
+        globals: &'g FxHashMap<StringId, Global<'arena>>,
+        bound: &'g FxHashSet<StringId>,
+        position: usize,
+    ) -> Self {
+        Self {
+            interner,
+            globals,
+            bound,
+            fuel: FUEL,
+            depth: 0,
+            scopes: vec![FxHashMap::default()],
+            position,
+        }
+    }
+
+    /// Steps taken so far
+    pub(super) fn steps(&self) -> usize {
+        FUEL - self.fuel
+    }
+
+    /// Call the top-level function `name` with `arguments`
+    pub(super) fn call(&mut self, name: StringId, arguments: Vec<Value>) -> Eval<Value> {
+        let global = match self.globals.get(&name) {
+            Some(global) if global.visible_from <= self.position => global,
+            _ => {
+                return Err(format!(
+                    "it calls `{}`, which is not known at compile time",
+                    self.name(name)
+                ))
+            }
+        };
+        let GlobalKind::Function { parameters, body } = &global.kind else {
+            return Err(format!(
+                "it calls `{}`, which is not a function",
+                self.name(name)
+            ));
+        };
+        if self.depth == MAX_DEPTH {
+            return Err(format!("it nests more than {} calls", MAX_DEPTH));
+        }
+
+        let scopes = std::mem::replace(&mut self.scopes, vec![FxHashMap::default()]);
+        let position = std::mem::replace(&mut self.position, global.position);
+        self.depth += 1;
+        let result = self.run(parameters, *body, arguments);
+        self.depth -= 1;
+        self.position = position;
+        self.scopes = scopes;
+        result
+    }
+
+    fn run(
+        &mut self,
+        parameters: &[Parameter<'arena>],
+        body: FunctionBody<'arena>,
+        arguments: Vec<Value>,
+    ) -> Eval<Value> {
+        let mut arguments = arguments.into_iter();
+        for parameter in parameters {
+            if parameter.is_rest {
+                return Err("it calls a function with rest parameters".to_string());
+            }
+            let Pattern::Identifier(name) = &parameter.pattern else {
+                return Err("it calls a function that destructures its parameters".to_string());
+            };
+            let mut value = arguments.next().unwrap_or(Value::Nil);
+            if let (Value::Nil, Some(default)) = (&value, &parameter.default) {
+                value = self.evaluate(default)?;
+            }
+            self.declare(name.node, value);
+        }
+        match body {
+            FunctionBody::Block(statements) => match self.statements(statements)? {
+                Flow::Return(value) => Ok(value),
+                _ => Ok(Value::Nil),
+            },
+            FunctionBody::Expression(expr) => self.evaluate(expr),
+        }
+    }
+
+    fn tick(&mut self) -> Eval<()> {
+        if self.fuel == 0 {
+            return Err(format!("it takes more than {} steps", FUEL));
+        }
+        self.fuel -= 1;
+        Ok(())
+    }
+
+    fn name(&self, name: StringId) -> String {
+        self.interner.resolve(name).to_string()
+    }
+
+    fn declare(&mut self, name: StringId, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, value);
+        }
+    }
+
+    fn local(&self, name: StringId) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name))
+    }
+
+    /// The builtin `name` refers to, unless the program binds it
+    fn builtin_name(&self, name: StringId) -> Option<String> {
+        (self.local(name).is_none() && !self.bound.contains(&name)).then(|| self.name(name))
+    }
+
+    fn lookup(&self, name: StringId) -> Eval<Value> {
+        if let Some(value) = self.local(name) {
+            return Ok(value.clone());
+        }
+        match self.globals.get(&name) {
+            Some(global) if global.visible_from <= self.position => match &global.kind {
+                GlobalKind::Constant(value) => Ok(value.clone()),
+                GlobalKind::Function { .. } => {
+                    Err(format!("it uses function `{}` as a value", self.name(name)))
+                }
+            },
+            _ => Err(format!(
+                "it reads `{}`, which is not known at compile time",
+                self.name(name)
+            )),
+        }
+    }
+
+    fn assign_local(&mut self, name: StringId, value: Value) -> Eval<()> {
+        match self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(&name))
+        {
+            Some(slot) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(format!(
+                "it assigns `{}`, which is not one of its locals",
+                self.name(name)
+            )),
+        }
+    }
+
+    fn block(&mut self, statements: &[Statement<'arena>]) -> Eval<Flow> {
+        self.scopes.push(FxHashMap::default());
+        let flow = self.statements(statements);
+        self.scopes.pop();
+        flow
+    }
+
+    fn statements(&mut self, statements: &[Statement<'arena>]) -> Eval<Flow> {
+        for stmt in statements {
+            let flow = self.statement(stmt)?;
+            if !matches!(flow, Flow::Normal) {
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn statement(&mut self, stmt: &Statement<'arena>) -> Eval<Flow> {
+        self.tick()?;
+        match stmt {
+            Statement::Variable(decl) => {
+                if matches!(decl.kind, VariableKind::Global) {
+                    return Err("it declares a global".to_string());
+                }
+                let Pattern::Identifier(name) = &decl.pattern else {
+                    return Err("it destructures a declaration".to_string());
+                };
+                let value = self.evaluate(&decl.initializer)?;
+                self.declare(name.node, value);
+            }
+            Statement::Expression(expr) => match &expr.kind {
+                ExpressionKind::Assignment(target, op, value) => self.assign(target, *op, value)?,
+                _ => {
+                    self.evaluate(expr)?;
+                }
+            },
+            Statement::If(if_stmt) => {
+                if truthy(&self.evaluate(&if_stmt.condition)?) {
+                    return self.block(if_stmt.then_block.statements);
+                }
+                for else_if in if_stmt.else_ifs.iter() {
+                    if truthy(&self.evaluate(&else_if.condition)?) {
+                        return self.block(else_if.block.statements);
+                    }
+                }
+                if let Some(else_block) = &if_stmt.else_block {
+                    return self.block(else_block.statements);
+                }
+            }
+            Statement::While(while_stmt) => {
+                while truthy(&self.evaluate(&while_stmt.condition)?) {
+                    match self.block(while_stmt.body.statements)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                }
+            }
+            Statement::Repeat(repeat_stmt) => loop {
+                // `until` sees the locals of the body
+                self.scopes.push(FxHashMap::default());
+                let flow = self.statements(repeat_stmt.body.statements);
+                let done = match flow {
+                    Ok(Flow::Break) => Ok(true),
+                    Ok(Flow::Return(value)) => {
+                        self.scopes.pop();
+                        return Ok(Flow::Return(value));
+                    }
+                    Ok(Flow::Normal | Flow::Continue) => self
+                        .evaluate(&repeat_stmt.until)
+                        .map(|value| truthy(&value)),
+                    Err(reason) => Err(reason),
+                };
+                self.scopes.pop();
+                if done? {
+                    break;
+                }
+            },
+            Statement::For(for_stmt) => return self.for_loop(for_stmt),
+            Statement::Return(ret) => {
+                let value = match ret.values {
+                    [] => Value::Nil,
+                    [value] => self.evaluate(value)?,
+                    _ => return Err("it returns several values".to_string()),
+                };
+                return Ok(Flow::Return(value));
+            }
+            Statement::Break(_) => return Ok(Flow::Break),
+            Statement::Continue(_) => return Ok(Flow::Continue),
+            Statement::Block(block) => return self.block(block.statements),
+            _ => return Err("it uses a statement the interpreter does not run".to_string()),
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn for_loop(&mut self, for_stmt: &ForStatement<'arena>) -> Eval<Flow> {
+        match for_stmt {
+            ForStatement::Numeric(for_num) => {
+                let start = self.evaluate(&for_num.start)?;
+                let limit = self.evaluate(&for_num.end)?;
+                let step = match &for_num.step {
+                    Some(step) => self.evaluate(step)?,
+                    None => Value::Integer(1),
+                };
+                let variable = for_num.variable.node;
+                let body = for_num.body.statements;
+                match (start, limit, step) {
+                    (_, _, Value::Integer(0)) => Err("its loop step is zero".to_string()),
+                    (Value::Integer(start), limit, Value::Integer(step)) => {
+                        let limit = match limit {
+                            Value::Integer(limit) => limit,
+                            Value::Number(limit) => {
+                                let limit = if step > 0 {
+                                    limit.floor()
+                                } else {
+                                    limit.ceil()
+                                };
+                                exact_integer_of(limit)?
+                            }
+                            _ => return Err("its loop limit is not a number".to_string()),
+                        };
+                        let mut i = start;
+                        while (step > 0 && i <= limit) || (step < 0 && i >= limit) {
+                            match self.iteration(variable, Value::Integer(i), body)? {
+                                Flow::Break => break,
+                                Flow::Return(value) => return Ok(Flow::Return(value)),
+                                Flow::Normal | Flow::Continue => {}
+                            }
+                            let Some(next) = i.checked_add(step) else {
+                                break;
+                            };
+                            i = next;
+                        }
+                        Ok(Flow::Normal)
+                    }
+                    (start, limit, step) => {
+                        let (Some(start), Some(limit), Some(step)) =
+                            (number(&start), number(&limit), number(&step))
+                        else {
+                            return Err("its loop bounds are not numbers".to_string());
+                        };
+                        if step == 0.0 {
+                            return Err("its loop step is zero".to_string());
+                        }
+                        let mut i = start;
+                        while (step > 0.0 && i <= limit) || (step < 0.0 && i >= limit) {
+                            match self.iteration(variable, Value::Number(i), body)? {
+                                Flow::Break => break,
+                                Flow::Return(value) => return Ok(Flow::Return(value)),
+                                Flow::Normal | Flow::Continue => {}
+                            }
+                            i += step;
+                        }
+                        Ok(Flow::Normal)
+                    }
+                }
+            }
+            ForStatement::Generic(for_gen) => {
+                let table = match (for_gen.iterators, &for_gen.pattern) {
+                    ([iterator], None) => self.ipairs_table(iterator)?,
+                    _ => return Err("it iterates with something other than `ipairs`".to_string()),
+                };
+                let (index, value) = match for_gen.variables {
+                    [index] => (index.node, None),
+                    [index, value] => (index.node, Some(value.node)),
+                    _ => return Err("it iterates with more than two variables".to_string()),
+                };
+                let body = for_gen.body.statements;
+                let mut i = 1;
+                loop {
+                    self.tick()?;
+                    // Like `ipairs`, read each element when its turn comes
+                    let element = table.borrow().get(&Key::Integer(i));
+                    if matches!(element, Value::Nil) {
+                        break;
+                    }
+                    self.scopes.push(FxHashMap::default());
+                    self.declare(index, Value::Integer(i));
+                    if let Some(value) = value {
+                        self.declare(value, element);
+                    }
+                    let flow = self.statements(body);
+                    self.scopes.pop();
+                    match flow? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                    i += 1;
+                }
+                Ok(Flow::Normal)
+            }
+        }
+    }
+
+    /// The table of `ipairs(t)`
+    fn ipairs_table(&mut self, iterator: &Expression<'arena>) -> Eval<Rc<RefCell<Table>>> {
+        if let ExpressionKind::Call(callee, arguments, _) = &iterator.kind {
+            if let ExpressionKind::Identifier(function) = &callee.kind {
+                match self.builtin_name(*function).as_deref() {
+                    Some("ipairs") => {
+                        if let [argument] = arguments {
+                            if !argument.is_spread {
+                                return match self.evaluate(&argument.value)? {
+                                    Value::Table(table) => Ok(table),
+                                    _ => {
+                                        Err("it iterates over something other than a table"
+                                            .to_string())
+                                    }
+                                };
+                            }
+                        }
+                    }
+                    Some("pairs") => {
+                        return Err(
+                            "it iterates with `pairs`, whose order is only known at runtime"
+                                .to_string(),
+                        )
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Err("it iterates with something other than `ipairs`".to_string())
+    }
+
+    fn iteration(
+        &mut self,
+        variable: StringId,
+        value: Value,
+        body: &[Statement<'arena>],
+    ) -> Eval<Flow> {
+        self.tick()?;
+        self.scopes.push(FxHashMap::default());
+        self.declare(variable, value);
+        let flow = self.statements(body);
+        self.scopes.pop();
+        flow
+    }
+
+    fn assign(
+        &mut self,
+        target: &Expression<'arena>,
+        op: AssignmentOp,
+        value: &Expression<'arena>,
+    ) -> Eval<()> {
+        let op = match op {
+            AssignmentOp::Assign => None,
+            AssignmentOp::AddAssign => Some(BinaryOp::Add),
+            AssignmentOp::SubtractAssign => Some(BinaryOp::Subtract),
+            AssignmentOp::MultiplyAssign => Some(BinaryOp::Multiply),
+            AssignmentOp::DivideAssign => Some(BinaryOp::Divide),
+            AssignmentOp::ModuloAssign => Some(BinaryOp::Modulo),
+            AssignmentOp::PowerAssign => Some(BinaryOp::Power),
+            AssignmentOp::ConcatenateAssign => Some(BinaryOp::Concatenate),
+            AssignmentOp::FloorDivideAssign => Some(BinaryOp::IntegerDivide),
+            _ => return Err("it uses a bitwise assignment".to_string()),
+        };
+        match &target.kind {
+            ExpressionKind::Identifier(name) => {
+                let mut value = self.evaluate(value)?;
+                if let Some(op) = op {
+                    value = arithmetic(op, self.lookup(*name)?, value)?;
+                }
+                self.assign_local(*name, value)
+            }
+            ExpressionKind::Index(object, key) => {
+                let table = self.table(object)?;
+                let key = table_key(self.evaluate(key)?)?
+                    .ok_or_else(|| "it stores at a nil key".to_string())?;
+                self.store(table, key, op, value)
+            }
+            ExpressionKind::Member(object, field) => {
+                let table = self.table(object)?;
+                let key = Key::String(self.name(field.node));
+                self.store(table, key, op, value)
+            }
+            _ => Err("it assigns to something other than a local or a table field".to_string()),
+        }
+    }
+
+    fn store(
+        &mut self,
+        table: Rc<RefCell<Table>>,
+        key: Key,
+        op: Option<BinaryOp>,
+        value: &Expression<'arena>,
+    ) -> Eval<()> {
+        let mut value = self.evaluate(value)?;
+        if let Some(op) = op {
+            let current = table.borrow().get(&key);
+            value = arithmetic(op, current, value)?;
+        }
+        table.borrow_mut().set(key, value);
+        Ok(())
+    }
+
+    fn table(&mut self, expr: &Expression<'arena>) -> Eval<Rc<RefCell<Table>>> {
+        match self.evaluate(expr)? {
+            Value::Table(table) => Ok(table),
+            _ => Err("it indexes something other than a table".to_string()),
+        }
+    }
+
+    /// Evaluate `expr` to a single value
+    pub(super) fn evaluate(&mut self, expr: &Expression<'arena>) -> Eval<Value> {
+        self.tick()?;
+        match &expr.kind {
+            ExpressionKind::Literal(literal) => match literal {
+                Literal::Nil => Ok(Value::Nil),
+                Literal::Boolean(value) => Ok(Value::Boolean(*value)),
+                Literal::Integer(value) => exact_integer(*value),
+                Literal::Number(value) => Ok(Value::Number(*value)),
+                Literal::String(value) if value.contains('\\') => {
+                    Err("it uses a string with escape sequences".to_string())
+                }
+                Literal::String(value) => Ok(Value::String(value.clone())),
+            },
+            ExpressionKind::Identifier(name) => self.lookup(*name),
+            ExpressionKind::Parenthesized(inner) => self.evaluate(inner),
+            ExpressionKind::Binary(op, left, right) => match op {
+                BinaryOp::And => {
+                    let left = self.evaluate(left)?;
+                    if truthy(&left) {
+                        self.evaluate(right)
+                    } else {
+                        Ok(left)
+                    }
+                }
+                BinaryOp::Or => {
+                    let left = self.evaluate(left)?;
+                    if truthy(&left) {
+                        Ok(left)
+                    } else {
+                        self.evaluate(right)
+                    }
+                }
+                BinaryOp::NullCoalesce => match self.evaluate(left)? {
+                    Value::Nil => self.evaluate(right),
+                    left => Ok(left),
+                },
+                _ => {
+                    let left = self.evaluate(left)?;
+                    let right = self.evaluate(right)?;
+                    arithmetic(*op, left, right)
+                }
+            },
+            ExpressionKind::Unary(op, operand) => {
+                let operand = self.evaluate(operand)?;
+                match (op, operand) {
+                    (UnaryOp::Not, operand) => Ok(Value::Boolean(!truthy(&operand))),
+                    (UnaryOp::Negate, Value::Integer(value)) => {
+                        exact_integer(value.checked_neg().ok_or_else(overflow)?)
+                    }
+                    (UnaryOp::Negate, Value::Number(value)) => Ok(Value::Number(-value)),
+                    (UnaryOp::Length, Value::String(value)) => {
+                        Ok(Value::Integer(value.len() as i64))
+                    }
+                    (UnaryOp::Length, Value::Table(table)) => {
+                        Ok(Value::Integer(table.borrow().len()? as i64))
+                    }
+                    _ => {
+                        Err("it applies an operator to a value that does not support it"
+                            .to_string())
+                    }
+                }
+            }
+            ExpressionKind::Conditional(condition, then_expr, else_expr) => {
+                if truthy(&self.evaluate(condition)?) {
+                    self.evaluate(then_expr)
+                } else {
+                    self.evaluate(else_expr)
+                }
+            }
+            ExpressionKind::Index(object, key) => {
+                let table = self.table(object)?;
+                let value = match table_key(self.evaluate(key)?)? {
+                    Some(key) => table.borrow().get(&key),
+                    None => Value::Nil,
+                };
+                Ok(value)
+            }
+            ExpressionKind::Member(object, field) => {
+                let table = self.table(object)?;
+                let value = table.borrow().get(&Key::String(self.name(field.node)));
+                Ok(value)
+            }
+            ExpressionKind::Array(elements) => {
+                let mut table = Table::default();
+                for (i, element) in elements.iter().enumerate() {
+                    let ArrayElement::Expression(element) = element else {
+                        return Err("it spreads into a table".to_string());
+                    };
+                    let value = self.evaluate(element)?;
+                    table.set(Key::Integer(i as i64 + 1), value);
+                }
+                Ok(Value::Table(Rc::new(RefCell::new(table))))
+            }
+            ExpressionKind::Object(properties) => {
+                let mut table = Table::default();
+                for property in properties.iter() {
+                    let (key, value) = match property {
+                        ObjectProperty::Property { key, value, .. } => {
+                            (Key::String(self.name(key.node)), self.evaluate(value)?)
+                        }
+                        ObjectProperty::Computed { key, value, .. } => {
+                            let key = table_key(self.evaluate(key)?)?
+                                .ok_or_else(|| "it builds a table with a nil key".to_string())?;
+                            (key, self.evaluate(value)?)
+                        }
+                        ObjectProperty::Spread { .. } => {
+                            return Err("it spreads into a table".to_string())
+                        }
+                    };
+                    table.set(key, value);
+                }
+                Ok(Value::Table(Rc::new(RefCell::new(table))))
+            }
+            ExpressionKind::Call(callee, arguments, _) => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments.iter() {
+                    if argument.is_spread {
+                        return Err("it spreads call arguments".to_string());
+                    }
+                    values.push(self.evaluate(&argument.value)?);
+                }
+                match &callee.kind {
+                    ExpressionKind::Identifier(name) if self.local(*name).is_none() => {
+                        if self.globals.contains_key(name) {
+                            self.call(*name, values)
+                        } else {
+                            match self.builtin_name(*name) {
+                                Some(builtin) => self.builtin(&builtin, values),
+                                None => self.call(*name, values),
+                            }
+                        }
+                    }
+                    ExpressionKind::Member(library, function) => {
+                        let builtin = match &library.kind {
+                            ExpressionKind::Identifier(library) => self.builtin_name(*library),
+                            _ => None,
+                        };
+                        match builtin {
+                            Some(library) => {
+                                let name = format!("{}.{}", library, self.name(function.node));
+                                self.builtin(&name, values)
+                            }
+                            None => Err("it calls a function stored in a table".to_string()),
+                        }
+                    }
+                    _ => Err("it calls a function that is not known at compile time".to_string()),
+                }
+            }
+            ExpressionKind::MethodCall(object, method, arguments, _) => {
+                let mut values = vec![self.evaluate(object)?];
+                if !matches!(values[0], Value::String(_)) {
+                    return Err("it calls a method".to_string());
+                }
+                for argument in arguments.iter() {
+                    if argument.is_spread {
+                        return Err("it spreads call arguments".to_string());
+                    }
+                    values.push(self.evaluate(&argument.value)?);
+                }
+                let name = format!("string.{}", self.name(method.node));
+                self.builtin(&name, values)
+            }
+            ExpressionKind::Function(_) | ExpressionKind::Arrow(_) => {
+                Err("it creates a closure".to_string())
+            }
+            _ => Err("it uses an expression the interpreter does not evaluate".to_string()),
+        }
+    }
+
+    fn builtin(&mut self, name: &str, arguments: Vec<Value>) -> Eval<Value> {
+        let wrong_arguments = || format!("it calls `{}` with unexpected arguments", name);
+        let first = arguments.first().cloned().unwrap_or(Value::Nil);
+        match name {
+            "type" => Ok(Value::String(
+                match first {
+                    Value::Nil => "nil",
+                    Value::Boolean(_) => "boolean",
+                    Value::Integer(_) | Value::Number(_) => "number",
+                    Value::String(_) => "string",
+                    Value::Table(_) => "table",
+                }
+                .to_string(),
+            )),
+            "tostring" => Ok(Value::String(match first {
+                Value::Nil => "nil".to_string(),
+                Value::Boolean(value) => value.to_string(),
+                value => to_lua_string(&value)?,
+            })),
+            "tonumber" => match first {
+                Value::Integer(_) | Value::Number(_) => Ok(first),
+                Value::String(text) => parse_number(&text),
+                _ => Err(wrong_arguments()),
+            },
+            "math.floor" | "math.ceil" => match first {
+                Value::Integer(_) => Ok(first),
+                Value::Number(value) => exact_integer_of(if name == "math.floor" {
+                    value.floor()
+                } else {
+                    value.ceil()
+                })
+                .map(Value::Integer),
+                _ => Err(wrong_arguments()),
+            },
+            "math.abs" => match first {
+                Value::Integer(value) => exact_integer(value.checked_abs().ok_or_else(overflow)?),
+                Value::Number(value) => Ok(Value::Number(value.abs())),
+                _ => Err(wrong_arguments()),
+            },
+            "math.sqrt" => number(&first)
+                .map(|value| Value::Number(value.sqrt()))
+                .ok_or_else(wrong_arguments),
+            "math.max" | "math.min" => {
+                let wanted = if name == "math.max" {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                };
+                let mut arguments = arguments.into_iter();
+                let mut best = arguments.next().ok_or_else(wrong_arguments)?;
+                number(&best).ok_or_else(wrong_arguments)?;
+                for argument in arguments {
+                    number(&argument).ok_or_else(wrong_arguments)?;
+                    if compare(&argument, &best)? == Some(wanted) {
+                        best = argument;
+                    }
+                }
+                Ok(best)
+            }
+            "string.len" => match first {
+                Value::String(text) => Ok(Value::Integer(text.len() as i64)),
+                _ => Err(wrong_arguments()),
+            },
+            "string.upper" | "string.lower" => match first {
+                Value::String(text) => Ok(Value::String(if name == "string.upper" {
+                    text.to_ascii_uppercase()
+                } else {
+                    text.to_ascii_lowercase()
+                })),
+                _ => Err(wrong_arguments()),
+            },
+            "string.rep" => match (&first, arguments.get(1), arguments.get(2)) {
+                (Value::String(text), Some(Value::Integer(count)), separator) => {
+                    let separator = match separator {
+                        None => String::new(),
+                        Some(Value::String(separator)) => separator.clone(),
+                        Some(_) => return Err(wrong_arguments()),
+                    };
+                    let count = (*count).max(0) as usize;
+                    let len = (text.len() + separator.len()).saturating_mul(count);
+                    if len > MAX_STRING_LEN {
+                        return Err(too_long());
+                    }
+                    Ok(Value::String(vec![text.as_str(); count].join(&separator)))
+                }
+                _ => Err(wrong_arguments()),
+            },
+            "string.sub" => match (&first, arguments.get(1), arguments.get(2)) {
+                (Value::String(text), Some(Value::Integer(start)), end) => {
+                    let end = match end {
+                        None => -1,
+                        Some(Value::Integer(end)) => *end,
+                        Some(_) => return Err(wrong_arguments()),
+                    };
+                    let len = text.len() as i64;
+                    let start = match *start {
+                        start if start < 0 => (len + start + 1).max(1),
+                        0 => 1,
+                        start => start,
+                    };
+                    let end = match end {
+                        end if end < 0 => len + end + 1,
+                        end => end.min(len),
+                    };
+                    if start > end {
+                        return Ok(Value::String(String::new()));
+                    }
+                    let bytes = &text.as_bytes()[start as usize - 1..end as usize];
+                    String::from_utf8(bytes.to_vec())
+                        .map(Value::String)
+                        .map_err(|_| "it splits a multi-byte character".to_string())
+                }
+                _ => Err(wrong_arguments()),
+            },
+            "string.byte" => match (&first, arguments.get(1)) {
+                (Value::String(text), index) => {
+                    let index = match index {
+                        None => 1,
+                        Some(Value::Integer(index)) => *index,
+                        Some(_) => return Err(wrong_arguments()),
+                    };
+                    let len = text.len() as i64;
+                    let index = if index < 0 { len + index + 1 } else { index };
+                    Ok(if index >= 1 && index <= len {
+                        Value::Integer(text.as_bytes()[index as usize - 1] as i64)
+                    } else {
+                        Value::Nil
+                    })
+                }
+                _ => Err(wrong_arguments()),
+            },
+            "string.char" => {
+                let mut bytes = Vec::with_capacity(arguments.len());
+                for argument in &arguments {
+                    match argument {
+                        Value::Integer(byte @ 0..=255) => bytes.push(*byte as u8),
+                        _ => return Err(wrong_arguments()),
+                    }
+                }
+                String::from_utf8(bytes)
+                    .map(Value::String)
+                    .map_err(|_| "it builds a string that is not valid UTF-8".to_string())
+            }
+            "table.insert" => {
+                let Value::Table(table) = first else {
+                    return Err(wrong_arguments());
+                };
+                let mut table = table.borrow_mut();
+                let len = table.len()?;
+                match &arguments[1..] {
+                    [value] => table.set(Key::Integer(len as i64 + 1), value.clone()),
+                    [Value::Integer(position), value]
+                        if *position >= 1 && *position <= len as i64 + 1 =>
+                    {
+                        if matches!(value, Value::Nil) {
+                            return Err(wrong_arguments());
+                        }
+                        table.array.insert(*position as usize - 1, value.clone());
+                    }
+                    _ => return Err(wrong_arguments()),
+                }
+                Ok(Value::Nil)
+            }
+            "table.concat" => {
+                let Value::Table(table) = first else {
+                    return Err(wrong_arguments());
+                };
+                let separator = match arguments.get(1) {
+                    None => String::new(),
+                    Some(Value::String(separator)) => separator.clone(),
+                    Some(_) => return Err(wrong_arguments()),
+                };
+                let table = table.borrow();
+                table.len()?;
+                let mut parts = Vec::with_capacity(table.array.len());
+                for value in &table.array {
+                    match value {
+                        Value::String(_) | Value::Integer(_) => parts.push(to_lua_string(value)?),
+                        _ => return Err(wrong_arguments()),
+                    }
+                }
+                let joined = parts.join(&separator);
+                if joined.len() > MAX_STRING_LEN {
+                    return Err(too_long());
+                }
+                Ok(Value::String(joined))
+            }
+            _ => Err(format!(
+                "it calls `{}`, which is not evaluated at compile time",
+                name
+            )),
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Nil | Value::Boolean(false))
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(value) => Some(*value as f64),
+        Value::Number(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn overflow() -> String {
+    "an integer outgrows 2^53, which Lua 5.1 cannot represent exactly".to_string()
+}
+
+fn too_long() -> String {
+    format!("it builds a string longer than {} bytes", MAX_STRING_LEN)
+}
+
+/// An integer every Lua version represents exactly
+fn exact_integer(value: i64) -> Eval<Value> {
+    if value.unsigned_abs() > MAX_EXACT_INTEGER {
+        return Err(overflow());
+    }
+    Ok(Value::Integer(value))
+}
+
+/// The integer a float of integral value holds
+fn exact_integer_of(value: f64) -> Eval<i64> {
+    if value.is_nan() || value.abs() > MAX_EXACT_INTEGER as f64 {
+        return Err(overflow());
+    }
+    Ok(value as i64)
+}
+
+/// The key a value indexes a table with; `None` for nil and NaN, which
+/// index nothing
+fn table_key(value: Value) -> Eval<Option<Key>> {
+    Ok(match value {
+        Value::Nil => None,
+        Value::Boolean(value) => Some(Key::Boolean(value)),
+        Value::Integer(value) => Some(Key::Integer(value)),
+        Value::Number(value) if value.is_nan() => None,
+        Value::Number(value) if value.fract() == 0.0 && value.abs() <= MAX_EXACT_INTEGER as f64 => {
+            Some(Key::Integer(value as i64))
+        }
+        Value::Number(value) => Some(Key::Number(value.to_bits())),
+        Value::String(value) => Some(Key::String(value)),
+        Value::Table(_) => return Err("it uses a table as a key".to_string()),
+    })
+}
+
+/// How `..` and `tostring` write a value; floats are written differently by
+/// Lua 5.1 and 5.3, so they are not evaluated
+fn to_lua_string(value: &Value) -> Eval<String> {
+    match value {
+        Value::Integer(value) => Ok(value.to_string()),
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(_) => {
+            Err("it turns a float into a string, which Lua versions write differently".to_string())
+        }
+        _ => Err("it turns a value that is not a number into a string".to_string()),
+    }
+}
+
+/// `tonumber` of a decimal string
+fn parse_number(text: &str) -> Eval<Value> {
+    let text = text.trim();
+    if let Ok(value) = text.parse::<i64>() {
+        return exact_integer(value);
+    }
+    let is_decimal = !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+    if text.starts_with("0x") || text.starts_with("0X") {
+        return Err("it converts a hexadecimal string to a number".to_string());
+    }
+    Ok(match text.parse::<f64>() {
+        Ok(value) if is_decimal => Value::Number(value),
+        _ => Value::Nil,
+    })
+}
+
+/// How two values compare with `<`; `None` when they are unordered, as NaN is
+fn compare(left: &Value, right: &Value) -> Eval<Option<Ordering>> {
+    match (left, right) {
+        (Value::Integer(left), Value::Integer(right)) => Ok(Some(left.cmp(right))),
+        (Value::String(left), Value::String(right)) => {
+            Ok(Some(left.as_bytes().cmp(right.as_bytes())))
+        }
+        _ => match (number(left), number(right)) {
+            (Some(left), Some(right)) => Ok(left.partial_cmp(&right)),
+            _ => Err("it compares values that cannot be ordered".to_string()),
+        },
+    }
+}
+
+fn equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Nil, Value::Nil) => true,
+        (Value::Boolean(left), Value::Boolean(right)) => left == right,
+        (Value::Integer(left), Value::Integer(right)) => left == right,
+        (Value::String(left), Value::String(right)) => left == right,
+        (Value::Table(left), Value::Table(right)) => Rc::ptr_eq(left, right),
+        _ => match (number(left), number(right)) {
+            (Some(left), Some(right)) => left == right,
+            _ => false,
+        },
+    }
+}
+
+/// Apply a binary operator that evaluates both operands
+fn arithmetic(op: BinaryOp, left: Value, right: Value) -> Eval<Value> {
+    let ints = match (&left, &right) {
+        (Value::Integer(left), Value::Integer(right)) => Some((*left, *right)),
+        _ => None,
+    };
+    let floats = || match (number(&left), number(&right)) {
+        (Some(left), Some(right)) => Ok((left, right)),
+        _ => Err("it does arithmetic on a value that is not a number".to_string()),
+    };
+    match op {
+        BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply => {
+            if let Some((left, right)) = ints {
+                let result = match op {
+                    BinaryOp::Add => left.checked_add(right),
+                    BinaryOp::Subtract => left.checked_sub(right),
+                    _ => left.checked_mul(right),
+                };
+                return exact_integer(result.ok_or_else(overflow)?);
+            }
+            let (left, right) = floats()?;
+            Ok(Value::Number(match op {
+                BinaryOp::Add => left + right,
+                BinaryOp::Subtract => left - right,
+                _ => left * right,
+            }))
+        }
+        BinaryOp::Divide => {
+            let (left, right) = floats()?;
+            Ok(Value::Number(left / right))
+        }
+        BinaryOp::Power => {
+            let (left, right) = floats()?;
+            Ok(Value::Number(left.powf(right)))
+        }
+        BinaryOp::IntegerDivide => {
+            if let Some((left, right)) = ints {
+                if right == 0 {
+                    return Err("it divides an integer by zero".to_string());
+                }
+                let quotient = left.checked_div(right).ok_or_else(overflow)?;
+                let floor = if left % right != 0 && (left < 0) != (right < 0) {
+                    quotient - 1
+                } else {
+                    quotient
+                };
+                return exact_integer(floor);
+            }
+            let (left, right) = floats()?;
+            Ok(Value::Number((left / right).floor()))
+        }
+        BinaryOp::Modulo => {
+            if let Some((left, right)) = ints {
+                if right == 0 {
+                    return Err("it takes an integer modulo zero".to_string());
+                }
+                let remainder = left.checked_rem(right).ok_or_else(overflow)?;
+                return Ok(Value::Integer(
+                    if remainder != 0 && (remainder < 0) != (right < 0) {
+                        remainder + right
+                    } else {
+                        remainder
+                    },
+                ));
+            }
+            let (left, right) = floats()?;
+            let remainder = left % right;
+            Ok(Value::Number(
+                if remainder != 0.0 && (remainder < 0.0) != (right < 0.0) {
+                    remainder + right
+                } else {
+                    remainder
+                },
+            ))
+        }
+        BinaryOp::Concatenate => {
+            let joined = to_lua_string(&left)? + &to_lua_string(&right)?;
+            if joined.len() > MAX_STRING_LEN {
+                return Err(too_long());
+            }
+            Ok(Value::String(joined))
+        }
+        BinaryOp::Equal => Ok(Value::Boolean(equal(&left, &right))),
+        BinaryOp::NotEqual => Ok(Value::Boolean(!equal(&left, &right))),
+        BinaryOp::LessThan => Ok(Value::Boolean(
+            compare(&left, &right)? == Some(Ordering::Less),
+        )),
+        BinaryOp::LessThanOrEqual => Ok(Value::Boolean(matches!(
+            compare(&left, &right)?,
+            Some(Ordering::Less | Ordering::Equal)
+        ))),
+        BinaryOp::GreaterThan => Ok(Value::Boolean(
+            compare(&left, &right)? == Some(Ordering::Greater),
+        )),
+        BinaryOp::GreaterThanOrEqual => Ok(Value::Boolean(matches!(
+            compare(&left, &right)?,
+            Some(Ordering::Greater | Ordering::Equal)
+        ))),
+        _ => Err("it uses an operator the interpreter does not evaluate".to_string()),
+    }
+}
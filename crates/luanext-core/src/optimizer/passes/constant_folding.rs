@@ -1,14 +1,39 @@
+use crate::codegen::LuaTarget;
 use crate::config::OptimizationLevel;
-use crate::optimizer::{ExprVisitor, WholeProgramPass};
+use crate::optimizer::{remarks, ExprVisitor, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, Literal, UnaryOp};
 
-pub struct ConstantFoldingPass;
+const PASS: &str = "constant-folding";
+
+/// Integers up to this magnitude are all exact doubles
+const MAX_EXACT_DOUBLE: u64 = 1 << 53;
+
+pub struct ConstantFoldingPass {
+    target: LuaTarget,
+}
 
 impl ConstantFoldingPass {
     pub fn new() -> Self {
-        Self
+        Self {
+            target: LuaTarget::default(),
+        }
+    }
+
+    /// Fold integer arithmetic the way `target` computes it
+    pub fn with_target(mut self, target: LuaTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Lua 5.3+ have 64-bit integers that wrap around; Lua 5.1, 5.2 and
+    /// LuaJIT compute in doubles
+    fn integers_wrap(&self) -> bool {
+        matches!(
+            self.target,
+            LuaTarget::Lua53 | LuaTarget::Lua54 | LuaTarget::Lua55
+        )
     }
 }
 
@@ -20,7 +45,7 @@ impl<'arena> ExprVisitor<'arena> for ConstantFoldingPass {
 
 impl<'arena> WholeProgramPass<'arena> for ConstantFoldingPass {
     fn name(&self) -> &'static str {
-        "constant-folding"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -159,16 +184,56 @@ impl ConstantFoldingPass {
                 let left_changed = self.fold_expression(&mut new_left, arena);
                 let right_changed = self.fold_expression(&mut new_right, arena);
 
-                // Try to fold if both operands are literals
+                // Integers wrap around on Lua 5.3+ and are only folded while
+                // exact on targets that compute in doubles; `/` and `^`
+                // produce floats
                 if let (
-                    ExpressionKind::Literal(Literal::Number(l)),
-                    ExpressionKind::Literal(Literal::Number(r)),
+                    ExpressionKind::Literal(Literal::Integer(l)),
+                    ExpressionKind::Literal(Literal::Integer(r)),
                 ) = (&new_left.kind, &new_right.kind)
                 {
-                    if let Some(result) = self.fold_numeric_binary_op(op, *l, *r) {
+                    if let Some(result) = self.fold_integer_binary_op(op, *l, *r) {
+                        remarks::applied(PASS, expr.span, || {
+                            format!("folded {} {:?} {} to {}", l, op, r, literal_text(&result))
+                        });
+                        expr.kind = ExpressionKind::Literal(result);
+                        return true;
+                    }
+                    if *r == 0 && is_division(op) {
+                        remarks::missed(PASS, expr.span, || {
+                            format!("{:?} by zero is left to runtime", op)
+                        });
+                    } else if matches!(op, BinaryOp::Divide | BinaryOp::Power) {
+                        remarks::missed(PASS, expr.span, || {
+                            format!(
+                                "{} {:?} {} is a float with an integral value, which would be written as an integer",
+                                l, op, r
+                            )
+                        });
+                    } else if !self.integers_wrap() && is_integer_op(op) {
+                        remarks::missed(PASS, expr.span, || {
+                            format!(
+                                "{} {:?} {} leaves the ±2^53 range {:?} numbers hold exactly",
+                                l, op, r, self.target
+                            )
+                        });
+                    }
+                } else if let (Some(l), Some(r)) =
+                    (float_operand(&new_left), float_operand(&new_right))
+                {
+                    // Fold numeric literals when at least one is a float
+                    if let Some(result) = self.fold_numeric_binary_op(op, l, r) {
+                        remarks::applied(PASS, expr.span, || {
+                            format!("folded {} {:?} {} to {}", l, op, r, result)
+                        });
                         expr.kind = ExpressionKind::Literal(Literal::Number(result));
                         return true;
                     }
+                    if r == 0.0 && is_division(op) {
+                        remarks::missed(PASS, expr.span, || {
+                            format!("{:?} by zero is left to runtime", op)
+                        });
+                    }
                 }
 
                 // Try to fold boolean operations
@@ -178,6 +243,9 @@ impl ConstantFoldingPass {
                 ) = (&new_left.kind, &new_right.kind)
                 {
                     if let Some(result) = self.fold_boolean_binary_op(op, *l, *r) {
+                        remarks::applied(PASS, expr.span, || {
+                            format!("folded {} {:?} {} to {}", l, op, r, result)
+                        });
                         expr.kind = ExpressionKind::Literal(Literal::Boolean(result));
                         return true;
                     }
@@ -197,10 +265,22 @@ impl ConstantFoldingPass {
                 // Try to fold unary operations
                 match (&new_operand.kind, op) {
                     (ExpressionKind::Literal(Literal::Number(n)), UnaryOp::Negate) => {
+                        remarks::applied(PASS, expr.span, || format!("folded -{} to {}", n, -n));
                         expr.kind = ExpressionKind::Literal(Literal::Number(-n));
                         return true;
                     }
+                    (ExpressionKind::Literal(Literal::Integer(n)), UnaryOp::Negate)
+                        if self.integers_wrap() || is_exact_double(*n) =>
+                    {
+                        let negated = n.wrapping_neg();
+                        remarks::applied(PASS, expr.span, || {
+                            format!("folded -{} to {}", n, negated)
+                        });
+                        expr.kind = ExpressionKind::Literal(Literal::Integer(negated));
+                        return true;
+                    }
                     (ExpressionKind::Literal(Literal::Boolean(b)), UnaryOp::Not) => {
+                        remarks::applied(PASS, expr.span, || format!("folded not {} to {}", b, !b));
                         expr.kind = ExpressionKind::Literal(Literal::Boolean(!b));
                         return true;
                     }
@@ -317,9 +397,22 @@ impl ConstantFoldingPass {
                     None // Don't fold division by zero
                 }
             }
+            BinaryOp::IntegerDivide => {
+                if r != 0.0 {
+                    Some((l / r).floor())
+                } else {
+                    None
+                }
+            }
             BinaryOp::Modulo => {
                 if r != 0.0 {
-                    Some(l % r)
+                    // Lua's remainder takes the sign of the divisor
+                    let m = l % r;
+                    Some(if m != 0.0 && (m < 0.0) != (r < 0.0) {
+                        m + r
+                    } else {
+                        m
+                    })
                 } else {
                     None
                 }
@@ -329,6 +422,27 @@ impl ConstantFoldingPass {
         }
     }
 
+    /// Fold an operation on two integers. Float results are only folded when
+    /// they have a fraction, since codegen writes integral floats as integers.
+    /// Without wrapping integers, operands and result must be exact doubles.
+    fn fold_integer_binary_op(&self, op: BinaryOp, left: i64, right: i64) -> Option<Literal> {
+        if matches!(op, BinaryOp::Divide | BinaryOp::Power) {
+            return self
+                .fold_numeric_binary_op(op, left as f64, right as f64)
+                .filter(|result| result.is_finite() && result.fract() != 0.0)
+                .map(Literal::Number);
+        }
+
+        let result = if self.integers_wrap() {
+            wrapping_integer_op(op, left, right)
+        } else if is_exact_double(left) && is_exact_double(right) {
+            checked_integer_op(op, left, right).filter(|&result| is_exact_double(result))
+        } else {
+            None
+        };
+        result.map(Literal::Integer)
+    }
+
     fn fold_boolean_binary_op(&self, op: BinaryOp, left: bool, right: bool) -> Option<bool> {
         match op {
             BinaryOp::And => Some(left && right),
@@ -340,6 +454,96 @@ impl ConstantFoldingPass {
     }
 }
 
+/// The value of a numeric literal as a float. An integer operand of a float
+/// operation is converted, as Lua does.
+fn float_operand(expr: &Expression<'_>) -> Option<f64> {
+    match &expr.kind {
+        ExpressionKind::Literal(Literal::Number(n)) => Some(*n),
+        ExpressionKind::Literal(Literal::Integer(n)) => Some(*n as f64),
+        _ => None,
+    }
+}
+
+/// `left op right` with Lua 5.3+ integer semantics: wrapping arithmetic,
+/// floor division and a remainder with the sign of the divisor
+fn wrapping_integer_op(op: BinaryOp, left: i64, right: i64) -> Option<i64> {
+    let (l, r) = (left, right);
+    match op {
+        BinaryOp::Add => Some(l.wrapping_add(r)),
+        BinaryOp::Subtract => Some(l.wrapping_sub(r)),
+        BinaryOp::Multiply => Some(l.wrapping_mul(r)),
+        BinaryOp::IntegerDivide if r != 0 => {
+            Some(floor_quotient(l.wrapping_div(r), l.wrapping_rem(r), r))
+        }
+        BinaryOp::Modulo if r != 0 => Some(floor_remainder(l.wrapping_rem(r), r)),
+        _ => None,
+    }
+}
+
+/// `left op right` as exact integers, `None` on overflow
+fn checked_integer_op(op: BinaryOp, left: i64, right: i64) -> Option<i64> {
+    let (l, r) = (left, right);
+    match op {
+        BinaryOp::Add => l.checked_add(r),
+        BinaryOp::Subtract => l.checked_sub(r),
+        BinaryOp::Multiply => l.checked_mul(r),
+        BinaryOp::IntegerDivide if r != 0 => {
+            Some(floor_quotient(l.checked_div(r)?, l.checked_rem(r)?, r))
+        }
+        BinaryOp::Modulo if r != 0 => Some(floor_remainder(l.checked_rem(r)?, r)),
+        _ => None,
+    }
+}
+
+/// Round a truncated quotient with remainder `rem` towards negative infinity
+fn floor_quotient(quotient: i64, rem: i64, right: i64) -> i64 {
+    if rem != 0 && (rem < 0) != (right < 0) {
+        quotient - 1
+    } else {
+        quotient
+    }
+}
+
+/// Give a truncated remainder the sign of the divisor, as Lua does
+fn floor_remainder(rem: i64, right: i64) -> i64 {
+    if rem != 0 && (rem < 0) != (right < 0) {
+        rem + right
+    } else {
+        rem
+    }
+}
+
+/// Whether a double holds `value` exactly
+fn is_exact_double(value: i64) -> bool {
+    value.unsigned_abs() <= MAX_EXACT_DOUBLE
+}
+
+fn is_integer_op(op: BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Add
+            | BinaryOp::Subtract
+            | BinaryOp::Multiply
+            | BinaryOp::IntegerDivide
+            | BinaryOp::Modulo
+    )
+}
+
+fn is_division(op: BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Divide | BinaryOp::IntegerDivide | BinaryOp::Modulo
+    )
+}
+
+fn literal_text(literal: &Literal) -> String {
+    match literal {
+        Literal::Integer(n) => n.to_string(),
+        Literal::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
 impl Default for ConstantFoldingPass {
     fn default() -> Self {
         Self::new()
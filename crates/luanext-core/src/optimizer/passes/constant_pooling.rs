@@ -0,0 +1,636 @@
+//! Constant table and string pooling
+//!
+//! A table constructor builds a new table each time it runs, so a lookup
+//! table declared inside a function is rebuilt on every call. This pass
+//! moves such tables into module-level locals, built once, that the function
+//! reads as upvalues:
+//!
+//! ```lua
+//! -- Before:
+//! local function weekday(n)
+//!     local names = { "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun" }
+//!     return names[n]
+//! end
+//!
+//! -- After:
+//! local __pool_0 = { "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun" }
+//! local function weekday(n)
+//!     local names = __pool_0
+//!     return names[n]
+//! end
+//! ```
+//!
+//! Only `const` declarations inside functions are pooled, and only tables
+//! whose entries are all literals. A pooled table is shared by every call, so
+//! the function must never change it: it must not store into the table, and
+//! `AliasAnalyzer` must see it neither returned, passed to a call nor copied
+//! to another local. Every use of the local must read a field, take its
+//! length or pass it to a builtin that only reads it, such as `ipairs`.
+//!
+//! Each function prototype keeps its own copy of its string constants, so
+//! long strings bound to `const` locals are pooled the same way. Identical
+//! tables and strings share one pooled local across the module.
+//!
+//! The pass reports each pooled constant with its size as a remark, and a
+//! summary of the bytes shared by deduplication as an analysis remark.
+
+use super::memory_effects::Scan;
+use crate::config::OptimizationLevel;
+use crate::optimizer::analysis::{AliasAnalyzer, MemoryLocation};
+use crate::optimizer::{
+    remarks, visit_block_expressions, visit_expr_children, visit_stmt_expressions, AstFeatures,
+    WholeProgramPass,
+};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{
+    ArrayElement, ArrowBody, Expression, ExpressionKind, Literal, ObjectProperty, UnaryOp,
+};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{
+    Block, ExportKind, ForStatement, Statement, VariableDeclaration, VariableKind,
+};
+use luanext_parser::ast::Spanned;
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::Arc;
+
+const PASS: &str = "constant-pooling";
+
+/// Pooled constants become locals of the main chunk, which Lua caps at 200
+const MAX_POOLED_CONSTANTS: usize = 32;
+
+/// Shorter strings are not worth a module-level local
+const MIN_POOLED_STRING_LEN: usize = 32;
+
+/// Builtins that read a table argument without changing it
+const READ_ONLY_BUILTINS: &[&str] = &[
+    "ipairs",
+    "pairs",
+    "next",
+    "rawget",
+    "rawlen",
+    "type",
+    "unpack",
+    "table.unpack",
+    "table.concat",
+];
+
+/// Hoists immutable literal tables and long strings out of functions into
+/// shared module-level locals
+pub struct ConstantPoolingPass {
+    interner: Arc<StringInterner>,
+    /// Pooled locals by the Lua source of their value, kept across runs so
+    /// later runs reuse them
+    pooled: FxHashMap<String, StringId>,
+    next_pool_id: usize,
+}
+
+impl ConstantPoolingPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            interner,
+            pooled: FxHashMap::default(),
+            next_pool_id: 0,
+        }
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for ConstantPoolingPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn min_level(&self) -> OptimizationLevel {
+        OptimizationLevel::Moderate
+    }
+
+    fn required_features(&self) -> AstFeatures {
+        AstFeatures::HAS_FUNCTIONS
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let mut scan = Scan::new(None);
+        scan.statements(&program.statements);
+        let mut bound: FxHashSet<StringId> = scan.declared.keys().copied().collect();
+        bound.extend(scan.written.iter().chain(&scan.stored));
+        let declared = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::Variable(decl) => match &decl.pattern {
+                    Pattern::Identifier(ident) => Some(ident.node),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        let mut pooler = Pooler {
+            interner: &self.interner,
+            pooled: &mut self.pooled,
+            next_pool_id: &mut self.next_pool_id,
+            bound,
+            declared,
+            declarations: Vec::new(),
+            replaced: 0,
+            shared_bytes: 0,
+        };
+        if !pooler.statements(&mut program.statements, false, arena) {
+            return Ok(false);
+        }
+
+        let (replaced, shared_bytes) = (pooler.replaced, pooler.shared_bytes);
+        let declarations = std::mem::take(&mut pooler.declarations);
+        remarks::analysis(PASS, program.span, || {
+            format!(
+                "pooled {} constants into {} module-level locals, sharing {} bytes of duplicate literals",
+                replaced,
+                declarations.len(),
+                shared_bytes
+            )
+        });
+        // Pooled values are literals, so they may go before everything else
+        program.statements.splice(0..0, declarations);
+        Ok(true)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Pools the constants of one run
+struct Pooler<'p, 'arena> {
+    interner: &'p StringInterner,
+    pooled: &'p mut FxHashMap<String, StringId>,
+    next_pool_id: &'p mut usize,
+    /// Names the program declares or assigns anywhere, which are not the
+    /// builtins they may share a name with
+    bound: FxHashSet<StringId>,
+    /// Locals the top level declares, including pooled ones
+    declared: FxHashSet<StringId>,
+    /// Declarations of the pooled locals, in order
+    declarations: Vec<Statement<'arena>>,
+    /// Declarations whose initializer was replaced by a pooled local
+    replaced: usize,
+    /// Bytes of literals that reused a pooled local instead of declaring one
+    shared_bytes: usize,
+}
+
+impl<'arena> Pooler<'_, 'arena> {
+    fn block(&mut self, block: &mut Block<'arena>, in_function: bool, arena: &'arena Bump) -> bool {
+        let mut statements = block.statements.to_vec();
+        let changed = self.statements(&mut statements, in_function, arena);
+        if changed {
+            block.statements = arena.alloc_slice_clone(&statements);
+        }
+        changed
+    }
+
+    fn statements(
+        &mut self,
+        statements: &mut [Statement<'arena>],
+        in_function: bool,
+        arena: &'arena Bump,
+    ) -> bool {
+        let mut changed = false;
+        for i in 0..statements.len() {
+            changed |= self.statement(&mut statements[i], in_function, arena);
+            if in_function {
+                changed |= self.pool(statements, i, arena);
+            }
+        }
+        changed
+    }
+
+    fn statement(
+        &mut self,
+        stmt: &mut Statement<'arena>,
+        in_function: bool,
+        arena: &'arena Bump,
+    ) -> bool {
+        match stmt {
+            Statement::Variable(decl) => self.expression(&mut decl.initializer, arena),
+            Statement::Expression(expr) => self.expression(expr, arena),
+            Statement::Return(ret) => {
+                let mut values = ret.values.to_vec();
+                let mut changed = false;
+                for value in &mut values {
+                    changed |= self.expression(value, arena);
+                }
+                if changed {
+                    ret.values = arena.alloc_slice_clone(&values);
+                }
+                changed
+            }
+            Statement::If(if_stmt) => {
+                let mut changed = self.expression(&mut if_stmt.condition, arena);
+                changed |= self.block(&mut if_stmt.then_block, in_function, arena);
+                let mut else_ifs = if_stmt.else_ifs.to_vec();
+                let mut else_ifs_changed = false;
+                for else_if in &mut else_ifs {
+                    else_ifs_changed |= self.expression(&mut else_if.condition, arena);
+                    else_ifs_changed |= self.block(&mut else_if.block, in_function, arena);
+                }
+                if else_ifs_changed {
+                    if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+                    changed = true;
+                }
+                if let Some(else_block) = &mut if_stmt.else_block {
+                    changed |= self.block(else_block, in_function, arena);
+                }
+                changed
+            }
+            Statement::While(while_stmt) => {
+                let changed = self.expression(&mut while_stmt.condition, arena);
+                self.block(&mut while_stmt.body, in_function, arena) || changed
+            }
+            Statement::Repeat(repeat_stmt) => {
+                let changed = self.block(&mut repeat_stmt.body, in_function, arena);
+                self.expression(&mut repeat_stmt.until, arena) || changed
+            }
+            Statement::For(for_stmt) => match *for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    let mut new_num = (**for_num).clone();
+                    let mut changed = self.expression(&mut new_num.start, arena);
+                    changed |= self.expression(&mut new_num.end, arena);
+                    if let Some(step) = &mut new_num.step {
+                        changed |= self.expression(step, arena);
+                    }
+                    changed |= self.block(&mut new_num.body, in_function, arena);
+                    if changed {
+                        *stmt = Statement::For(
+                            arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
+                        );
+                    }
+                    changed
+                }
+                ForStatement::Generic(for_gen) => {
+                    let mut new_gen = for_gen.clone();
+                    let mut iterators = new_gen.iterators.to_vec();
+                    let mut changed = false;
+                    for iterator in &mut iterators {
+                        changed |= self.expression(iterator, arena);
+                    }
+                    if changed {
+                        new_gen.iterators = arena.alloc_slice_clone(&iterators);
+                    }
+                    changed |= self.block(&mut new_gen.body, in_function, arena);
+                    if changed {
+                        *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    }
+                    changed
+                }
+            },
+            Statement::Block(block) => self.block(block, in_function, arena),
+            Statement::Function(func) => self.block(&mut func.body, true, arena),
+            Statement::Export(export) => {
+                let ExportKind::Declaration(inner) = export.kind else {
+                    return false;
+                };
+                let mut new_inner = inner.clone();
+                let changed = self.statement(&mut new_inner, in_function, arena);
+                if changed {
+                    export.kind = ExportKind::Declaration(arena.alloc(new_inner));
+                }
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    /// Visit the function bodies in `expr`
+    fn expression(&mut self, expr: &mut Expression<'arena>, arena: &'arena Bump) -> bool {
+        match &expr.kind {
+            ExpressionKind::Function(func) => {
+                let mut new_func = func.clone();
+                let changed = self.block(&mut new_func.body, true, arena);
+                if changed {
+                    expr.kind = ExpressionKind::Function(new_func);
+                }
+                changed
+            }
+            ExpressionKind::Arrow(arrow) => {
+                let mut new_arrow = arrow.clone();
+                let changed = match &mut new_arrow.body {
+                    ArrowBody::Expression(body) => {
+                        let mut new_body = (**body).clone();
+                        let changed = self.expression(&mut new_body, arena);
+                        *body = arena.alloc(new_body);
+                        changed
+                    }
+                    ArrowBody::Block(block) => self.block(block, true, arena),
+                };
+                if changed {
+                    expr.kind = ExpressionKind::Arrow(new_arrow);
+                }
+                changed
+            }
+            _ => visit_expr_children(expr, arena, &mut |e, a| self.expression(e, a)),
+        }
+    }
+
+    /// Replace the initializer of `statements[i]` with a pooled local when
+    /// it binds a `const` to a constant the rest of the block never changes
+    fn pool(
+        &mut self,
+        statements: &mut [Statement<'arena>],
+        i: usize,
+        arena: &'arena Bump,
+    ) -> bool {
+        let Statement::Variable(decl) = &statements[i] else {
+            return false;
+        };
+        let Pattern::Identifier(binding) = &decl.pattern else {
+            return false;
+        };
+        if !matches!(decl.kind, VariableKind::Const) {
+            return false;
+        }
+        let Some(source) = constant_source(&decl.initializer, self.interner) else {
+            return false;
+        };
+        let local = binding.node;
+        let span = decl.span;
+        let kind = match &decl.initializer.kind {
+            ExpressionKind::Literal(Literal::String(value)) => {
+                if value.len() < MIN_POOLED_STRING_LEN {
+                    return false;
+                }
+                "string"
+            }
+            ExpressionKind::Literal(_) => return false,
+            _ => {
+                if !self.is_read_only(statements, i, local, span, arena) {
+                    return false;
+                }
+                "table"
+            }
+        };
+
+        let initializer = decl.initializer.clone();
+        let (pool, shared) = match self.pooled.get(&source) {
+            Some(&pool) => (pool, true),
+            None => {
+                if self.pooled.len() >= MAX_POOLED_CONSTANTS {
+                    remarks::missed(PASS, span, || {
+                        format!(
+                            "{} `{}` kept: {} constants are pooled already",
+                            kind,
+                            self.interner.resolve(local),
+                            MAX_POOLED_CONSTANTS
+                        )
+                    });
+                    return false;
+                }
+                let pool = self.fresh_name();
+                self.pooled.insert(source.clone(), pool);
+                (pool, false)
+            }
+        };
+        // Pooled locals of earlier runs may since have been removed
+        if self.declared.insert(pool) {
+            self.declarations
+                .push(Statement::Variable(VariableDeclaration {
+                    kind: VariableKind::Const,
+                    pattern: Pattern::Identifier(Spanned::new(pool, initializer.span)),
+                    type_annotation: None,
+                    initializer: initializer.clone(),
+                    span: initializer.span,
+                }));
+        }
+
+        remarks::applied(PASS, span, || {
+            format!(
+                "pooled {} `{}` ({} bytes) as `{}`{}",
+                kind,
+                self.interner.resolve(local),
+                source.len(),
+                self.interner.resolve(pool),
+                if shared {
+                    ", shared with an identical one"
+                } else {
+                    ""
+                }
+            )
+        });
+        if shared {
+            self.shared_bytes += source.len();
+        }
+        self.replaced += 1;
+        if let Statement::Variable(decl) = &mut statements[i] {
+            decl.initializer = Expression::new(ExpressionKind::Identifier(pool), initializer.span);
+        }
+        true
+    }
+
+    /// Whether the table `statements[i]` binds to `local` is only ever read
+    /// by the rest of the block
+    fn is_read_only(
+        &self,
+        statements: &[Statement<'arena>],
+        i: usize,
+        local: StringId,
+        span: Span,
+        arena: &'arena Bump,
+    ) -> bool {
+        let name = self.interner.resolve(local);
+        let mut rest = Scan::new(None);
+        rest.statements(&statements[i + 1..]);
+        if rest.opaque || rest.declared.contains_key(&local) {
+            return false;
+        }
+        if rest.stored.contains(&local) {
+            remarks::missed(PASS, span, || {
+                format!(
+                    "table `{}` is stored into, so each call needs its own",
+                    name
+                )
+            });
+            return false;
+        }
+
+        let alias = AliasAnalyzer::new().analyze(&statements[i..]);
+        if alias.has_escaped(local) || !alias.aliases_of(&MemoryLocation::Local(local)).is_empty() {
+            remarks::missed(PASS, span, || {
+                format!("table `{}` escapes, so each call needs its own", name)
+            });
+            return false;
+        }
+
+        let uses = rest.referenced.get(&local).copied().unwrap_or(0);
+        let mut reads = 0;
+        for stmt in &mut statements[i + 1..].to_vec() {
+            visit_stmt_expressions(stmt, arena, &mut |e, a| {
+                self.count_reads(e, local, &mut reads, a)
+            });
+        }
+        if reads != uses {
+            remarks::missed(PASS, span, || {
+                format!("table `{}` is used other than by reading it", name)
+            });
+            return false;
+        }
+        true
+    }
+
+    /// Count in `reads` the uses of `local` in `expr` that only read the
+    /// table it holds
+    fn count_reads(
+        &self,
+        expr: &mut Expression<'arena>,
+        local: StringId,
+        reads: &mut usize,
+        arena: &'arena Bump,
+    ) -> bool {
+        match &expr.kind {
+            ExpressionKind::Index(table, key) if is_identifier(table, local) => {
+                *reads += 1;
+                let mut key = (**key).clone();
+                self.count_reads(&mut key, local, reads, arena);
+            }
+            ExpressionKind::Member(table, _) | ExpressionKind::Unary(UnaryOp::Length, table)
+                if is_identifier(table, local) =>
+            {
+                *reads += 1;
+            }
+            ExpressionKind::Call(callee, arguments, _) if self.is_read_only_builtin(callee) => {
+                for argument in arguments.iter() {
+                    if is_identifier(&argument.value, local) && !argument.is_spread {
+                        *reads += 1;
+                    } else {
+                        let mut value = argument.value.clone();
+                        self.count_reads(&mut value, local, reads, arena);
+                    }
+                }
+            }
+            ExpressionKind::Function(func) => {
+                let mut body = func.body.clone();
+                visit_block_expressions(&mut body, arena, &mut |e, a| {
+                    self.count_reads(e, local, reads, a)
+                });
+            }
+            ExpressionKind::Arrow(arrow) => match &arrow.body {
+                ArrowBody::Expression(body) => {
+                    let mut body = (**body).clone();
+                    self.count_reads(&mut body, local, reads, arena);
+                }
+                ArrowBody::Block(block) => {
+                    let mut block = block.clone();
+                    visit_block_expressions(&mut block, arena, &mut |e, a| {
+                        self.count_reads(e, local, reads, a)
+                    });
+                }
+            },
+            _ => {
+                visit_expr_children(expr, arena, &mut |e, a| {
+                    self.count_reads(e, local, reads, a)
+                });
+            }
+        }
+        false
+    }
+
+    /// Whether `callee` is one of `READ_ONLY_BUILTINS`, not rebound by the
+    /// program
+    fn is_read_only_builtin(&self, callee: &Expression<'_>) -> bool {
+        let name = match &callee.kind {
+            ExpressionKind::Identifier(name) if !self.bound.contains(name) => {
+                self.interner.resolve(*name).to_string()
+            }
+            ExpressionKind::Member(object, member) => match &object.kind {
+                ExpressionKind::Identifier(module) if !self.bound.contains(module) => format!(
+                    "{}.{}",
+                    self.interner.resolve(*module),
+                    self.interner.resolve(member.node)
+                ),
+                _ => return false,
+            },
+            _ => return false,
+        };
+        READ_ONLY_BUILTINS.contains(&name.as_str())
+    }
+
+    /// A fresh `__pool_N` name the program does not use
+    fn fresh_name(&mut self) -> StringId {
+        loop {
+            let id = *self.next_pool_id;
+            *self.next_pool_id += 1;
+            let name = self.interner.get_or_intern(&format!("__pool_{}", id));
+            if !self.bound.contains(&name) && !self.declared.contains(&name) {
+                return name;
+            }
+        }
+    }
+}
+
+/// The Lua source of `expr` when it is a string or a table whose entries are
+/// all literals; identical sources are identical constants
+fn constant_source(expr: &Expression<'_>, interner: &StringInterner) -> Option<String> {
+    match &expr.kind {
+        ExpressionKind::Literal(literal) => Some(literal_source(literal)),
+        ExpressionKind::Array(elements) => {
+            let mut entries = Vec::with_capacity(elements.len());
+            for element in elements.iter() {
+                let ArrayElement::Expression(value) = element else {
+                    return None;
+                };
+                entries.push(scalar_source(value)?);
+            }
+            Some(format!("{{{}}}", entries.join(", ")))
+        }
+        ExpressionKind::Object(properties) => {
+            let mut entries = Vec::with_capacity(properties.len());
+            for property in properties.iter() {
+                let entry = match property {
+                    ObjectProperty::Property { key, value, .. } => {
+                        format!("{} = {}", interner.resolve(key.node), scalar_source(value)?)
+                    }
+                    ObjectProperty::Computed { key, value, .. } => {
+                        // `nil` and NaN keys fail when the table is built
+                        match &key.kind {
+                            ExpressionKind::Literal(Literal::Nil) => return None,
+                            ExpressionKind::Literal(Literal::Number(n)) if n.is_nan() => {
+                                return None
+                            }
+                            _ => {}
+                        }
+                        format!("[{}] = {}", scalar_source(key)?, scalar_source(value)?)
+                    }
+                    ObjectProperty::Spread { .. } => return None,
+                };
+                entries.push(entry);
+            }
+            Some(format!("{{{}}}", entries.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+fn is_identifier(expr: &Expression<'_>, name: StringId) -> bool {
+    matches!(expr.kind, ExpressionKind::Identifier(id) if id == name)
+}
+
+fn scalar_source(expr: &Expression<'_>) -> Option<String> {
+    match &expr.kind {
+        ExpressionKind::Literal(literal) => Some(literal_source(literal)),
+        _ => None,
+    }
+}
+
+/// Floats keep their decimal point so `1.0` and `1` stay apart
+fn literal_source(literal: &Literal) -> String {
+    match literal {
+        Literal::Nil => "nil".to_string(),
+        Literal::Boolean(b) => b.to_string(),
+        Literal::Number(n) => format!("{:?}", n),
+        Literal::Integer(i) => i.to_string(),
+        Literal::String(s) => format!("{:?}", s),
+    }
+}
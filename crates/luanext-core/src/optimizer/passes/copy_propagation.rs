@@ -35,7 +35,7 @@
 //! - Control flow merges (phi functions with multiple incoming values)
 //! - Side effects (function calls may change state)
 
-use crate::optimizer::BlockVisitor;
+use crate::optimizer::{remarks, BlockVisitor};
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{Expression, ExpressionKind, Literal};
 use luanext_parser::ast::pattern::Pattern;
@@ -44,6 +44,8 @@ use luanext_parser::ast::Spanned;
 use luanext_parser::string_interner::StringId;
 use rustc_hash::FxHashMap;
 
+const PASS: &str = "copy-propagation";
+
 /// Represents a value that can be safely propagated to replace a variable use.
 #[derive(Debug, Clone)]
 enum PropagationValue {
@@ -181,6 +183,14 @@ impl CopyPropagationPass {
         match &mut expr.kind {
             ExpressionKind::Identifier(var) => {
                 if let Some(new_expr) = self.try_propagate(*var, arena) {
+                    remarks::applied(PASS, expr.span, || {
+                        let source = match new_expr.kind {
+                            ExpressionKind::Literal(_) => "its constant value",
+                            ExpressionKind::Identifier(_) => "the variable it copies",
+                            _ => "the member access it copies",
+                        };
+                        format!("replaced variable use with {}", source)
+                    });
                     // Preserve type annotation if present
                     let old_type = expr.annotated_type.clone();
                     *expr = new_expr;
@@ -1,3 +1,4 @@
+use crate::optimizer::remarks::{self, statement_span};
 use crate::optimizer::BlockVisitor;
 use bumpalo::Bump;
 use luanext_parser::ast::statement::{Block, ForStatement, Statement};
@@ -38,6 +39,12 @@ impl DeadCodeEliminationPass {
             if is_terminal {
                 let new_len = i + 1;
                 if stmts.len() > new_len {
+                    let removed = stmts.len() - new_len;
+                    remarks::applied(
+                        "dead-code-elimination",
+                        statement_span(&stmts[new_len]),
+                        || format!("removed {} unreachable statement(s)", removed),
+                    );
                     stmts.truncate(new_len);
                     changed = true;
                 }
@@ -1,9 +1,12 @@
 use crate::optimizer::analysis::module_graph::ModuleGraph;
+use crate::optimizer::remarks;
 use luanext_parser::ast::statement::{ExportKind, Statement};
 use luanext_parser::string_interner::StringInterner;
 use std::path::Path;
 use std::sync::Arc;
 
+const PASS: &str = "dead-export-elimination";
+
 /// Dead Export Elimination Pass
 ///
 /// Removes export statements for symbols that are never imported by any other module.
@@ -81,6 +84,16 @@ impl DeadExportEliminationPass {
                                         result.push(stmt.clone());
                                     } else {
                                         // Export is dead, unwrap to just the declaration
+                                        remarks::applied(
+                                            PASS,
+                                            remarks::statement_span(stmt),
+                                            || {
+                                                format!(
+                                                    "removed export of `{}`; no module imports it",
+                                                    name_str
+                                                )
+                                            },
+                                        );
                                         result.push((*inner_stmt).clone());
                                     }
                                 } else {
@@ -113,8 +126,13 @@ impl DeadExportEliminationPass {
                                     // Note: We can't modify arena-allocated slices easily,
                                     // so we keep all specifiers if any are used
                                     result.push(stmt.clone());
+                                } else {
+                                    // If all dead, drop the entire export statement
+                                    remarks::applied(PASS, remarks::statement_span(stmt), || {
+                                        "removed export list; no module imports its names"
+                                            .to_string()
+                                    });
                                 }
-                                // If all dead, drop the entire export statement
                             } else {
                                 // Re-export: export { x } from './other'
                                 // Keep re-exports for now (handled by ReExportFlatteningPass)
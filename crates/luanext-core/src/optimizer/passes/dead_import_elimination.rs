@@ -1,10 +1,13 @@
 use crate::optimizer::analysis::module_graph::ModuleGraph;
+use crate::optimizer::remarks;
 use luanext_parser::ast::statement::ImportClause;
 use luanext_parser::ast::statement::Statement;
 use luanext_parser::string_interner::StringInterner;
 use std::path::Path;
 use std::sync::Arc;
 
+const PASS: &str = "dead-import-elimination";
+
 /// Dead Import Elimination Pass
 ///
 /// Removes import statements for symbols that are never referenced in the module's code.
@@ -69,6 +72,7 @@ impl DeadImportEliminationPass {
         for stmt in statements {
             match stmt {
                 Statement::Import(import_decl) => {
+                    let kept = result.len();
                     match &import_decl.clause {
                         ImportClause::Named(specifiers) => {
                             // Check if any import specifier is referenced
@@ -149,6 +153,12 @@ impl DeadImportEliminationPass {
                             // Else drop the import
                         }
                     }
+                    let is_type_only = matches!(import_decl.clause, ImportClause::TypeOnly(_));
+                    if result.len() == kept && !is_type_only {
+                        remarks::applied(PASS, import_decl.span, || {
+                            "removed import; none of its bindings are referenced".to_string()
+                        });
+                    }
                 }
                 _ => {
                     // Non-import statement, keep it
@@ -2,6 +2,7 @@
 // O2: Dead Store Elimination Pass
 // =============================================================================
 
+use crate::optimizer::remarks::{self, statement_span};
 use crate::optimizer::BlockVisitor;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind};
@@ -10,6 +11,8 @@ use luanext_parser::ast::statement::{Block, ForStatement, Statement};
 use luanext_parser::string_interner::StringId;
 use std::collections::HashSet;
 
+const PASS: &str = "dead-store-elimination";
+
 /// Dead store elimination pass
 /// Removes assignments to variables that are never read
 pub struct DeadStoreEliminationPass;
@@ -343,6 +346,22 @@ impl DeadStoreEliminationPass {
                     .all(|name| !captured.contains(name) && !current_live_vars.contains(name))
             };
 
+            let never_read =
+                !names.is_empty() && names.iter().all(|name| !current_live_vars.contains(name));
+            if is_dead {
+                remarks::applied(PASS, statement_span(stmt), || {
+                    "removed store to a variable that is never read".to_string()
+                });
+            } else if never_read {
+                remarks::missed(PASS, statement_span(stmt), || {
+                    if has_side_effects {
+                        "store is never read but its initializer has side effects".to_string()
+                    } else {
+                        "store is never read but the variable is captured by a closure".to_string()
+                    }
+                });
+            }
+
             if !is_dead {
                 let mut stmt_clone = stmt.clone();
                 changed |= self.eliminate_dead_stores_in_statement(&mut stmt_clone, arena);
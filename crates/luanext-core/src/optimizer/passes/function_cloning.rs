@@ -10,8 +10,13 @@
 // - Only clones small functions (≤8 statements) to limit code size growth
 // - Only specializes for literal constant arguments (numbers, strings, booleans)
 // - Maximum 4 clones per function to prevent combinatorial explosion
+// - With a profile, hot functions allow ≤16 statements and 8 clones, and
+//   functions that never ran are not cloned
 // - Does NOT clone functions with varargs, rest parameters, or closures
 //   over mutable upvalues (conservative, safe for Lua semantics)
+// - `optimizer.hints`: `noinline`, `optimize("none")` and `optimize("size")`
+//   functions are never cloned; `inline` and `optimize("speed")` ones get the
+//   hot limits
 //
 // Example transformation:
 //   function greet(name: string, loud: boolean): string
@@ -33,7 +38,9 @@
 //   greet__clone1()
 
 use crate::config::OptimizationLevel;
-use crate::optimizer::{AstFeatures, WholeProgramPass};
+use crate::optimizer::hints::{FreeFunctionHints, FunctionHints};
+use crate::optimizer::profile::{Hotness, ModuleProfile};
+use crate::optimizer::{remarks, AstFeatures, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{
@@ -46,15 +53,25 @@ use luanext_parser::string_interner::{StringId, StringInterner};
 use rustc_hash::FxHashMap;
 use std::sync::Arc;
 
+const PASS: &str = "function-cloning";
+
 /// Maximum body size (statements) for a function to be eligible for cloning
 const MAX_CLONE_BODY_SIZE: usize = 8;
 
 /// Maximum number of clones per original function
 const MAX_CLONES_PER_FUNCTION: usize = 4;
 
+/// Body size limit for functions the profile marks hot
+const HOT_MAX_CLONE_BODY_SIZE: usize = 16;
+
+/// Clone limit for functions the profile marks hot
+const HOT_MAX_CLONES_PER_FUNCTION: usize = 8;
+
 pub struct FunctionCloningPass {
     interner: Arc<StringInterner>,
     next_clone_id: usize,
+    profile: Option<Arc<ModuleProfile>>,
+    hints: Arc<FreeFunctionHints>,
 }
 
 impl FunctionCloningPass {
@@ -62,6 +79,40 @@ impl FunctionCloningPass {
         Self {
             interner,
             next_clone_id: 0,
+            profile: None,
+            hints: Arc::default(),
+        }
+    }
+
+    /// Raise the limits for hot functions and skip cold ones
+    pub fn with_profile(mut self, profile: Option<Arc<ModuleProfile>>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Skip functions whose hints block cloning; use the hot limits for
+    /// functions hinted `inline` or `optimize("speed")`
+    pub fn with_hints(mut self, hints: Arc<FreeFunctionHints>) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    /// Body size and clone limits for `func`, `None` if it never ran
+    fn limits(
+        &self,
+        func: &FunctionDeclaration<'_>,
+        hints: FunctionHints,
+    ) -> Option<(usize, usize)> {
+        let hotness = self.profile.as_ref().map_or(Hotness::Normal, |profile| {
+            profile.function_hotness(func.span)
+        });
+        match hotness {
+            _ if hints.forces_inlining() => {
+                Some((HOT_MAX_CLONE_BODY_SIZE, HOT_MAX_CLONES_PER_FUNCTION))
+            }
+            Hotness::Hot => Some((HOT_MAX_CLONE_BODY_SIZE, HOT_MAX_CLONES_PER_FUNCTION)),
+            Hotness::Normal => Some((MAX_CLONE_BODY_SIZE, MAX_CLONES_PER_FUNCTION)),
+            Hotness::Cold => None,
         }
     }
 }
@@ -71,6 +122,8 @@ impl Default for FunctionCloningPass {
         Self {
             interner: Arc::new(StringInterner::new()),
             next_clone_id: 0,
+            profile: None,
+            hints: Arc::default(),
         }
     }
 }
@@ -87,6 +140,10 @@ struct CloningContext<'arena> {
     functions: FxHashMap<StringId, FunctionDeclaration<'arena>>,
     /// How many clones have been created for each function
     clone_counts: FxHashMap<StringId, usize>,
+    /// How many clones each function may have
+    clone_limits: FxHashMap<StringId, usize>,
+    /// Functions whose hints forbid cloning, with the reason for remarks
+    blocked: FxHashMap<StringId, &'static str>,
     /// Maps (func_name, args_key) -> cloned_func_name for deduplication
     clone_cache: FxHashMap<(StringId, String), StringId>,
     /// New cloned functions to insert
@@ -98,6 +155,8 @@ impl<'arena> CloningContext<'arena> {
         Self {
             functions: FxHashMap::default(),
             clone_counts: FxHashMap::default(),
+            clone_limits: FxHashMap::default(),
+            blocked: FxHashMap::default(),
             clone_cache: FxHashMap::default(),
             new_functions: Vec::new(),
         }
@@ -106,7 +165,7 @@ impl<'arena> CloningContext<'arena> {
 
 impl<'arena> WholeProgramPass<'arena> for FunctionCloningPass {
     fn name(&self) -> &'static str {
-        "function-cloning"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -129,13 +188,22 @@ impl<'arena> WholeProgramPass<'arena> for FunctionCloningPass {
         // Phase 1: Collect eligible functions
         for stmt in &program.statements {
             if let Statement::Function(func) = stmt {
-                if is_eligible_for_cloning(func) {
+                let hints = self.hints.get(&self.interner.resolve(func.name.node));
+                if let Some(reason) = hints.clone_blocker() {
+                    ctx.blocked.insert(func.name.node, reason);
+                    continue;
+                }
+                let Some((max_body_size, max_clones)) = self.limits(func, hints) else {
+                    continue;
+                };
+                if is_eligible_for_cloning(func, max_body_size) {
                     ctx.functions.insert(func.name.node, func.clone());
+                    ctx.clone_limits.insert(func.name.node, max_clones);
                 }
             }
         }
 
-        if ctx.functions.is_empty() {
+        if ctx.functions.is_empty() && ctx.blocked.is_empty() {
             return Ok(false);
         }
 
@@ -170,14 +238,14 @@ impl<'arena> WholeProgramPass<'arena> for FunctionCloningPass {
 }
 
 /// Check if a function is eligible for cloning
-fn is_eligible_for_cloning(func: &FunctionDeclaration<'_>) -> bool {
+fn is_eligible_for_cloning(func: &FunctionDeclaration<'_>, max_body_size: usize) -> bool {
     // Must have parameters
     if func.parameters.is_empty() {
         return false;
     }
 
     // Body must be small
-    if count_statements(&func.body) > MAX_CLONE_BODY_SIZE {
+    if count_statements(&func.body) > max_body_size {
         return false;
     }
 
@@ -356,6 +424,7 @@ impl FunctionCloningPass {
         ctx: &mut CloningContext<'arena>,
     ) -> bool {
         let mut changed = false;
+        let span = expr.span;
 
         match &expr.kind {
             ExpressionKind::Call(callee, args, type_args) => {
@@ -379,10 +448,18 @@ impl FunctionCloningPass {
                             if !info.const_args.is_empty() {
                                 // Check clone limit
                                 let count = ctx.clone_counts.get(&func_name).copied().unwrap_or(0);
-                                if count < MAX_CLONES_PER_FUNCTION {
+                                let max_clones = ctx.clone_limits[&func_name];
+                                if count < max_clones {
                                     // Check cache for identical specialization
                                     let cache_key = (func_name, args_cache_key(&info.const_args));
                                     if let Some(&cloned_name) = ctx.clone_cache.get(&cache_key) {
+                                        remarks::applied(PASS, span, || {
+                                            format!(
+                                                "call to `{}` reuses clone `{}`",
+                                                self.interner.resolve(func_name),
+                                                self.interner.resolve(cloned_name)
+                                            )
+                                        });
                                         // Reuse existing clone
                                         new_callee.kind = ExpressionKind::Identifier(cloned_name);
                                         // Remove constant args from call
@@ -400,6 +477,14 @@ impl FunctionCloningPass {
                                     if let Some(cloned_name) =
                                         self.create_clone(&func, &info.const_args, arena, ctx)
                                     {
+                                        remarks::applied(PASS, span, || {
+                                            format!(
+                                                "cloned `{}` as `{}` for {} constant argument(s)",
+                                                self.interner.resolve(func_name),
+                                                self.interner.resolve(cloned_name),
+                                                info.const_args.len()
+                                            )
+                                        });
                                         new_callee.kind = ExpressionKind::Identifier(cloned_name);
                                         let remaining_args = self
                                             .remove_specialized_args(&new_args, &info.const_args);
@@ -410,9 +495,30 @@ impl FunctionCloningPass {
                                         );
                                         return true;
                                     }
+                                } else {
+                                    remarks::missed(PASS, span, || {
+                                        format!(
+                                            "`{}` not cloned: it already has {} clones",
+                                            self.interner.resolve(func_name),
+                                            max_clones
+                                        )
+                                    });
                                 }
                             }
                         }
+                    } else if let Some(reason) = ctx.blocked.get(&func_name) {
+                        let has_const_args = self
+                            .analyze_call_site(&new_args)
+                            .is_some_and(|info| !info.const_args.is_empty());
+                        if has_const_args {
+                            remarks::missed(PASS, span, || {
+                                format!(
+                                    "`{}` not cloned: {}",
+                                    self.interner.resolve(func_name),
+                                    reason
+                                )
+                            });
+                        }
                     }
                 }
 
@@ -1,7 +1,9 @@
 // O2: Function Inlining Pass
 // =============================================================================
 
-use crate::optimizer::{PreAnalysisPass, StmtVisitor};
+use crate::optimizer::hints::FreeFunctionHints;
+use crate::optimizer::profile::{Hotness, ModuleProfile};
+use crate::optimizer::{remarks, PreAnalysisPass, StmtVisitor};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{ArrowBody, Expression, ExpressionKind};
@@ -15,6 +17,11 @@ use luanext_parser::string_interner::{StringId, StringInterner};
 use rustc_hash::FxHashMap as HashMap;
 use std::sync::Arc;
 
+const PASS: &str = "function-inlining";
+
+/// Statement threshold for functions the profile marks hot
+const HOT_THRESHOLD: usize = 12;
+
 enum InlineResult<'arena> {
     /// Direct expression substitution - for simple single-return functions
     /// The expression can be directly substituted for the call
@@ -33,6 +40,8 @@ pub struct FunctionInliningPass<'arena> {
     next_temp_id: usize,
     functions: HashMap<StringId, FunctionDeclaration<'arena>>,
     interner: Option<Arc<StringInterner>>,
+    profile: Option<Arc<ModuleProfile>>,
+    hints: Arc<FreeFunctionHints>,
 }
 
 impl Default for FunctionInliningPass<'_> {
@@ -42,6 +51,8 @@ impl Default for FunctionInliningPass<'_> {
             next_temp_id: 0,
             functions: HashMap::default(),
             interner: None,
+            profile: None,
+            hints: Arc::default(),
         }
     }
 }
@@ -53,8 +64,23 @@ impl<'arena> FunctionInliningPass<'arena> {
             next_temp_id: 0,
             functions: HashMap::default(),
             interner: Some(interner),
+            profile: None,
+            hints: Arc::default(),
         }
     }
+
+    /// Raise the threshold for hot functions and skip cold ones
+    pub fn with_profile(mut self, profile: Option<Arc<ModuleProfile>>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Never inline `@noinline` functions; ignore the size threshold and the
+    /// profile for `@inline` ones
+    pub fn with_hints(mut self, hints: Arc<FreeFunctionHints>) -> Self {
+        self.hints = hints;
+        self
+    }
 }
 
 impl<'arena> PreAnalysisPass<'arena> for FunctionInliningPass<'arena> {
@@ -592,7 +618,14 @@ impl<'arena> FunctionInliningPass<'arena> {
         if let ExpressionKind::Call(func, args, _) = &expr.kind.clone() {
             if let ExpressionKind::Identifier(func_name) = &func.kind {
                 if let Some(func_decl) = self.find_function_definition(expr, *func_name) {
-                    if self.is_inlinable(func_decl) {
+                    if let Some(reason) = self.inline_blocker(func_decl) {
+                        remarks::missed(PASS, expr.span, || {
+                            format!("`{}` not inlined: {}", self.name_of(*func_name), reason)
+                        });
+                    } else {
+                        remarks::applied(PASS, expr.span, || {
+                            format!("inlined call to `{}`", self.name_of(*func_name))
+                        });
                         let args_vec: Vec<_> = args.to_vec();
                         let result = self.inline_call(func_decl.clone(), &args_vec, arena);
                         // Replace the call expression based on the inline result
@@ -622,24 +655,49 @@ impl<'arena> FunctionInliningPass<'arena> {
         self.functions.get(&name)
     }
 
-    fn is_inlinable(&self, func: &FunctionDeclaration<'arena>) -> bool {
+    /// Why `func` cannot be inlined, or `None` if it can
+    fn inline_blocker(&self, func: &FunctionDeclaration<'arena>) -> Option<String> {
         // Skip generic functions - let GenericSpecializationPass handle them first
         if func.type_parameters.is_some() {
-            return false;
+            return Some("generic functions are left to generic specialization".to_string());
+        }
+        let hints = self.hints.get(&self.name_of(func.name.node));
+        if let Some(reason) = hints.inline_blocker() {
+            return Some(reason.to_string());
         }
-        if func.body.statements.len() > self.threshold {
-            return false;
+        let hotness = self.profile.as_ref().map_or(Hotness::Normal, |profile| {
+            profile.function_hotness(func.span)
+        });
+        let threshold = match hotness {
+            _ if hints.forces_inlining() => usize::MAX,
+            Hotness::Hot => HOT_THRESHOLD,
+            Hotness::Normal => self.threshold,
+            Hotness::Cold => return Some("function never ran in the profile".to_string()),
+        };
+        if func.body.statements.len() > threshold {
+            return Some(format!(
+                "body has {} statements, threshold is {}",
+                func.body.statements.len(),
+                threshold
+            ));
         }
         if self.is_recursive(func) {
-            return false;
+            return Some("function is recursive".to_string());
         }
         if self.has_complex_control_flow(&func.body) {
-            return false;
+            return Some("body has loops or multiple returns".to_string());
         }
         if self.has_closures(&func.body) {
-            return false;
+            return Some("body creates closures".to_string());
         }
-        true
+        None
+    }
+
+    fn name_of(&self, id: StringId) -> String {
+        self.interner
+            .as_ref()
+            .map(|interner| interner.resolve(id).to_string())
+            .unwrap_or_default()
     }
 
     fn is_recursive(&self, func: &FunctionDeclaration<'arena>) -> bool {
@@ -3,7 +3,7 @@
 // =============================================================================
 
 use crate::config::OptimizationLevel;
-use crate::optimizer::WholeProgramPass;
+use crate::optimizer::{remarks, WholeProgramPass};
 use crate::MutableProgram;
 use crate::{build_substitutions, instantiate_function_declaration};
 use bumpalo::Bump;
@@ -16,6 +16,8 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
+const PASS: &str = "generic-specialization";
+
 /// Computes a hash of type arguments for caching specialized functions
 fn hash_type_args(type_args: &[Type<'_>]) -> u64 {
     let mut hasher = DefaultHasher::new();
@@ -81,6 +83,14 @@ impl<'arena, 'pass> SpecializationContext<'arena, 'pass> {
         }
     }
 
+    fn resolve(&self, id: StringId) -> String {
+        self.pass
+            .interner
+            .as_ref()
+            .map(|interner| interner.resolve(id).to_string())
+            .unwrap_or_default()
+    }
+
     /// Collects all generic function declarations from the program
     fn collect_generic_functions(&mut self, program: &MutableProgram<'arena>) {
         for stmt in &program.statements {
@@ -370,6 +380,7 @@ impl<'arena, 'pass> SpecializationContext<'arena, 'pass> {
         arena: &'arena Bump,
     ) -> bool {
         let mut changed = false;
+        let span = expr.span;
 
         match &expr.kind {
             ExpressionKind::Call(callee, args, type_args) => {
@@ -399,11 +410,25 @@ impl<'arena, 'pass> SpecializationContext<'arena, 'pass> {
                                 if let Some(specialized_name) =
                                     self.specialize_function(arena, &func, ta)
                                 {
+                                    remarks::applied(PASS, span, || {
+                                        format!(
+                                            "specialized call to `{}` as `{}`",
+                                            self.resolve(*func_name),
+                                            self.resolve(specialized_name)
+                                        )
+                                    });
                                     // Replace callee with specialized function name
                                     new_callee.kind = ExpressionKind::Identifier(specialized_name);
                                     // Clear type arguments since the function is now monomorphic
                                     type_args_val = None;
                                     sub_changed = true;
+                                } else {
+                                    remarks::missed(PASS, span, || {
+                                        format!(
+                                            "`{}` not specialized: type arguments do not match its type parameters",
+                                            self.resolve(*func_name)
+                                        )
+                                    });
                                 }
                             }
                         }
@@ -721,7 +746,7 @@ impl<'arena, 'pass> SpecializationContext<'arena, 'pass> {
 
 impl<'arena> WholeProgramPass<'arena> for GenericSpecializationPass {
     fn name(&self) -> &'static str {
-        "generic-specialization"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -1,5 +1,5 @@
 use crate::config::OptimizationLevel;
-use crate::optimizer::WholeProgramPass;
+use crate::optimizer::{remarks, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{Expression, ExpressionKind};
@@ -11,6 +11,8 @@ use luanext_parser::string_interner::StringInterner;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+const PASS: &str = "global-localization";
+
 pub struct GlobalLocalizationPass {
     interner: Arc<StringInterner>,
 }
@@ -24,7 +26,7 @@ impl GlobalLocalizationPass {
 
 impl<'arena> WholeProgramPass<'arena> for GlobalLocalizationPass {
     fn name(&self) -> &'static str {
-        "global-localization"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -168,6 +170,13 @@ impl GlobalLocalizationPass {
                 new_statements.push(Statement::Variable(var_decl));
                 declared_locals.insert(local_name_id);
                 changed = true;
+                remarks::applied(PASS, program.span, || {
+                    format!(
+                        "localized global `{}` ({} uses) as a local alias",
+                        self.interner.resolve(*name),
+                        count
+                    )
+                });
             }
         }
 
@@ -0,0 +1,1155 @@
+//! Global Value Numbering (GVN) Pass
+//!
+//! Replaces a computation with the local that already holds its value when
+//! that local was computed on every path to it, across `if` arms and loop
+//! bodies rather than within one statement list like
+//! `common_subexpression_elimination.rs`:
+//!
+//! ```lua
+//! -- Before:
+//! local scaled = width * factor
+//! if wide then
+//!     print(width * factor)
+//! end
+//! local area = width * factor
+//!
+//! -- After:
+//! local scaled = width * factor
+//! if wide then
+//!     print(scaled)
+//! end
+//! local area = scaled
+//! ```
+//!
+//! # Algorithm
+//!
+//! Values are numbered over the structured AST in dominator order. Without
+//! `goto`, a statement dominates the rest of its block and every block nested
+//! there, so the value table is copied on entering an `if` arm or a loop body
+//! and dropped on leaving it. Variables are renamed SSA-style while numbering,
+//! each assignment creating a new version. A variable assigned in an arm gets
+//! a new version after the `if` (the phi at the join), and one assigned in a
+//! loop gets a new version on entry (the phi at the header). `SsaForm` only
+//! versions the top-level statements of a function, so the pass renames
+//! nested blocks itself.
+//!
+//! Numbered values are:
+//! - operators over numbered operands
+//! - table reads (`t.x`, `t[k]`), until a store that may write the same field.
+//!   A store to another local table keeps the read only when `AliasAnalyzer`
+//!   proves the two tables distinct
+//! - calls to functions `SideEffectAnalyzer` marks pure that declare a
+//!   primitive return type, and to deterministic builtins such as
+//!   `math.floor`, until the next store
+//!
+//! Any other call may write anything, so it ends every table read and call
+//! value. Locals that a closure uses are numbered like globals, since the
+//! closure may run during any call. Metamethods are assumed to have no side
+//! effects, as in common subexpression elimination. Functions containing
+//! `goto` or labels are left alone.
+//!
+//! # Partial redundancy elimination
+//!
+//! A declaration right after an `if` whose value some arms already hold is
+//! made fully redundant by computing the value in the other arms:
+//!
+//! ```lua
+//! -- Before:
+//! if fast then
+//!     local d = x * y
+//!     emit(d)
+//! end
+//! local product = x * y
+//!
+//! -- After:
+//! local __gvn_0 = nil
+//! if fast then
+//!     local d = x * y
+//!     emit(d)
+//!     __gvn_0 = d
+//! else
+//!     __gvn_0 = x * y
+//! end
+//! local product = __gvn_0
+//! ```
+//!
+//! Every path still computes the value once, but paths through arms that
+//! held it no longer compute it twice.
+
+use super::memory_effects::{pattern_names, variable_name, LocalTables, PureCalls, Scan, Store};
+use crate::config::OptimizationLevel;
+use crate::optimizer::analysis::SsaVar;
+use crate::optimizer::{remarks, WholeProgramPass};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{
+    Argument, AssignmentOp, BinaryOp, Expression, ExpressionKind, Literal, UnaryOp,
+};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{
+    Block, ExportKind, ForStatement, IfStatement, Parameter, Statement, VariableDeclaration,
+    VariableKind,
+};
+use luanext_parser::ast::Spanned;
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::mem::{discriminant, Discriminant};
+use std::sync::Arc;
+
+const PASS: &str = "global-value-numbering";
+
+pub struct GlobalValueNumberingPass {
+    interner: Arc<StringInterner>,
+    next_temp_id: usize,
+}
+
+impl GlobalValueNumberingPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            interner,
+            next_temp_id: 0,
+        }
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for GlobalValueNumberingPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn min_level(&self) -> OptimizationLevel {
+        OptimizationLevel::Aggressive
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let pure_calls = PureCalls::new(&program.statements, self.interner.clone());
+        let Some(mut numbering) = Numbering::new(
+            &pure_calls,
+            &program.statements,
+            &self.interner,
+            &mut self.next_temp_id,
+        ) else {
+            return Ok(false);
+        };
+
+        let mut state = State::default();
+        numbering.statements(&mut state, &mut program.statements, arena);
+        Ok(numbering.changes > 0)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+type ValueNumber = u32;
+
+/// What a value is computed from; equal keys are equal values
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ValueKey {
+    Literal(String),
+    /// A global, or a local a closure uses
+    Variable(StringId),
+    Unary(Discriminant<UnaryOp>, ValueNumber),
+    Binary(Discriminant<BinaryOp>, ValueNumber, ValueNumber),
+    Member(ValueNumber, StringId),
+    Index(ValueNumber, ValueNumber),
+    Call(StringId, Vec<ValueNumber>),
+}
+
+/// The memory a value was read from, which ends the value when written
+#[derive(Debug, Clone, Copy)]
+enum Dependence {
+    /// A global, or a local a closure uses
+    Variable(StringId),
+    /// A table field, described like the stores that may write it
+    Field(Store),
+    /// A pure call, which may read any global or table
+    Call,
+}
+
+/// The values known at one point of a function body
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// Current version of each local in scope
+    versions: FxHashMap<StringId, u32>,
+    /// Value number of each key computed so far
+    values: FxHashMap<ValueKey, ValueNumber>,
+    /// Value number of each local version
+    var_values: FxHashMap<SsaVar, ValueNumber>,
+    /// The local version holding each value
+    leaders: FxHashMap<ValueNumber, SsaVar>,
+    /// Keys of the values read from memory
+    memory: Vec<(ValueKey, Dependence)>,
+}
+
+impl State {
+    /// Forget the values read from memory that `stale` says may have changed
+    fn forget_memory(&mut self, mut stale: impl FnMut(Dependence) -> bool) {
+        let values = &mut self.values;
+        self.memory.retain(|(key, dependence)| {
+            let keep = !stale(*dependence);
+            if !keep {
+                values.remove(key);
+            }
+            keep
+        });
+    }
+}
+
+/// Value numbering of one function body
+struct Numbering<'p> {
+    pure_calls: &'p PureCalls,
+    interner: &'p StringInterner,
+    next_temp_id: &'p mut usize,
+    tables: LocalTables,
+    /// Locals a closure uses
+    captured: FxHashSet<StringId>,
+    /// Whether the body has constructs that may hide closures
+    all_captured: bool,
+    next_value: ValueNumber,
+    next_version: FxHashMap<StringId, u32>,
+    /// Probing numbers an expression without rewriting it
+    probing: bool,
+    changes: usize,
+}
+
+impl<'p> Numbering<'p> {
+    /// Numbering for the function body `statements`, or `None` if it jumps
+    fn new(
+        pure_calls: &'p PureCalls,
+        statements: &[Statement<'_>],
+        interner: &'p StringInterner,
+        next_temp_id: &'p mut usize,
+    ) -> Option<Self> {
+        let mut scan = Scan::new(Some(pure_calls));
+        scan.statements(statements);
+        if scan.jumps {
+            return None;
+        }
+
+        Some(Self {
+            pure_calls,
+            interner,
+            next_temp_id,
+            tables: LocalTables::new(statements, &scan),
+            captured: scan.captured,
+            all_captured: scan.opaque,
+            next_value: 0,
+            next_version: FxHashMap::default(),
+            probing: false,
+            changes: 0,
+        })
+    }
+
+    fn is_captured(&self, name: StringId) -> bool {
+        self.all_captured || self.captured.contains(&name)
+    }
+
+    fn fresh_value(&mut self) -> ValueNumber {
+        self.next_value += 1;
+        self.next_value
+    }
+
+    fn new_version(&mut self, st: &mut State, name: StringId) -> SsaVar {
+        let version = self.next_version.entry(name).or_insert(0);
+        *version += 1;
+        st.versions.insert(name, *version);
+        SsaVar {
+            name,
+            version: *version,
+        }
+    }
+
+    /// Declare a new local `name`
+    fn declare(&mut self, st: &mut State, name: StringId) -> SsaVar {
+        if self.is_captured(name) {
+            st.forget_memory(
+                |dependence| matches!(dependence, Dependence::Variable(n) if n == name),
+            );
+        }
+        self.new_version(st, name)
+    }
+
+    /// Assign to `name`, returning the new version if it is a local that only
+    /// this body writes
+    fn assign(&mut self, st: &mut State, name: StringId) -> Option<SsaVar> {
+        if st.versions.contains_key(&name) && !self.is_captured(name) {
+            return Some(self.new_version(st, name));
+        }
+        // Pure calls may read globals and the locals of enclosing functions
+        st.forget_memory(|dependence| match dependence {
+            Dependence::Variable(n) => n == name,
+            Dependence::Call => true,
+            Dependence::Field(_) => false,
+        });
+        None
+    }
+
+    /// Record that `var` holds `value`
+    fn bind(&mut self, st: &mut State, var: SsaVar, value: Option<ValueNumber>) {
+        let Some(value) = value else {
+            return;
+        };
+        if self.is_captured(var.name) {
+            return;
+        }
+        st.var_values.insert(var, value);
+        if self.leader(st, value).is_none() {
+            st.leaders.insert(value, var);
+        }
+    }
+
+    /// The local in scope that holds `value`
+    fn leader(&self, st: &State, value: ValueNumber) -> Option<StringId> {
+        let var = st.leaders.get(&value)?;
+        (st.versions.get(&var.name) == Some(&var.version)).then_some(var.name)
+    }
+
+    fn value(
+        &mut self,
+        st: &mut State,
+        key: ValueKey,
+        dependence: Option<Dependence>,
+    ) -> ValueNumber {
+        if let Some(&value) = st.values.get(&key) {
+            return value;
+        }
+        let value = self.fresh_value();
+        if let Some(dependence) = dependence {
+            st.memory.push((key.clone(), dependence));
+        }
+        st.values.insert(key, value);
+        value
+    }
+
+    fn variable(&mut self, st: &mut State, name: StringId) -> ValueNumber {
+        match st.versions.get(&name) {
+            Some(&version) if !self.is_captured(name) => {
+                let var = SsaVar { name, version };
+                if let Some(&value) = st.var_values.get(&var) {
+                    return value;
+                }
+                let value = self.fresh_value();
+                st.var_values.insert(var, value);
+                value
+            }
+            _ => self.value(
+                st,
+                ValueKey::Variable(name),
+                Some(Dependence::Variable(name)),
+            ),
+        }
+    }
+
+    /// Forget the reads `store` may overwrite
+    fn store(&self, st: &mut State, store: Store) {
+        st.forget_memory(|dependence| match dependence {
+            Dependence::Variable(_) => false,
+            Dependence::Call => true,
+            Dependence::Field(read) => self.tables.may_alias(read, store),
+        });
+    }
+
+    /// Give every local a new version and forget all memory
+    fn invalidate(&mut self, st: &mut State) {
+        let names: Vec<StringId> = st.versions.keys().copied().collect();
+        for name in names {
+            self.new_version(st, name);
+        }
+        st.forget_memory(|_| true);
+    }
+
+    /// Apply the effects of code that was not numbered
+    fn apply(&mut self, st: &mut State, scan: &Scan) {
+        if scan.opaque {
+            self.invalidate(st);
+            return;
+        }
+        for &name in &scan.assigned {
+            self.assign(st, name);
+        }
+        if scan.clobbers {
+            st.forget_memory(|_| true);
+        } else {
+            for &store in &scan.stores {
+                self.store(st, store);
+            }
+        }
+    }
+
+    fn scan(&self) -> Scan<'p> {
+        Scan::new(Some(self.pure_calls))
+    }
+
+    fn function<'arena>(
+        &mut self,
+        parameters: &[Parameter<'arena>],
+        body: &mut Block<'arena>,
+        arena: &'arena Bump,
+    ) {
+        let Some(mut numbering) = Numbering::new(
+            self.pure_calls,
+            body.statements,
+            self.interner,
+            &mut *self.next_temp_id,
+        ) else {
+            return;
+        };
+
+        let mut state = State::default();
+        for param in parameters {
+            let mut names = Vec::new();
+            pattern_names(&param.pattern, &mut names);
+            for name in names {
+                numbering.declare(&mut state, name);
+            }
+        }
+        numbering.block(&mut state, body, arena);
+        self.changes += numbering.changes;
+    }
+
+    fn block<'arena>(&mut self, st: &mut State, block: &mut Block<'arena>, arena: &'arena Bump) {
+        let mut statements = block.statements.to_vec();
+        let before = self.changes;
+        self.statements(st, &mut statements, arena);
+        if self.changes != before {
+            block.statements = arena.alloc_slice_clone(&statements);
+        }
+    }
+
+    fn statements<'arena>(
+        &mut self,
+        st: &mut State,
+        statements: &mut Vec<Statement<'arena>>,
+        arena: &'arena Bump,
+    ) {
+        let mut i = 0;
+        while i < statements.len() {
+            let Statement::If(if_stmt) = &mut statements[i] else {
+                self.statement(st, &mut statements[i], arena);
+                i += 1;
+                continue;
+            };
+            let ends = self.if_statement(st, if_stmt, arena);
+
+            if let Some(Statement::Variable(next)) = statements.get(i + 1) {
+                let next = next.clone();
+                if let Statement::If(if_stmt) = &mut statements[i] {
+                    if let Some(temp) =
+                        self.eliminate_partial_redundancy(st, if_stmt, ends, &next, arena)
+                    {
+                        statements.insert(i, temp);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+        }
+    }
+
+    fn statement<'arena>(
+        &mut self,
+        st: &mut State,
+        stmt: &mut Statement<'arena>,
+        arena: &'arena Bump,
+    ) {
+        match stmt {
+            Statement::Variable(decl) => self.declaration(st, decl, arena),
+            Statement::Expression(expr) => {
+                if let ExpressionKind::Assignment(target, op, value) = &mut expr.kind {
+                    self.assignment(st, target, *op, value, arena);
+                } else {
+                    // A statement call can't be replaced by a variable
+                    self.expression(st, expr, false, arena);
+                }
+            }
+            Statement::Function(func) => {
+                self.declare(st, func.name.node);
+                self.function(func.parameters, &mut func.body, arena);
+            }
+            Statement::If(if_stmt) => {
+                self.if_statement(st, if_stmt, arena);
+            }
+            Statement::While(while_stmt) => {
+                let mut scan = self.scan();
+                scan.expression(&while_stmt.condition);
+                scan.block(&while_stmt.body);
+                self.apply(st, &scan);
+
+                self.expression(st, &mut while_stmt.condition, true, arena);
+                let mut body = st.clone();
+                self.block(&mut body, &mut while_stmt.body, arena);
+            }
+            Statement::Repeat(repeat_stmt) => {
+                let mut scan = self.scan();
+                scan.block(&repeat_stmt.body);
+                scan.expression(&repeat_stmt.until);
+                self.apply(st, &scan);
+
+                // `until` sees the locals of the body
+                let mut body = st.clone();
+                self.block(&mut body, &mut repeat_stmt.body, arena);
+                self.expression(&mut body, &mut repeat_stmt.until, true, arena);
+            }
+            Statement::For(for_stmt) => match &**for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    let mut new_num = (**for_num).clone();
+                    let before = self.changes;
+                    self.expression(st, &mut new_num.start, true, arena);
+                    self.expression(st, &mut new_num.end, true, arena);
+                    if let Some(step) = &mut new_num.step {
+                        self.expression(st, step, true, arena);
+                    }
+
+                    let mut scan = self.scan();
+                    scan.block(&new_num.body);
+                    self.apply(st, &scan);
+
+                    let mut body = st.clone();
+                    self.declare(&mut body, new_num.variable.node);
+                    self.block(&mut body, &mut new_num.body, arena);
+                    if self.changes != before {
+                        *stmt = Statement::For(
+                            arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
+                        );
+                    }
+                }
+                ForStatement::Generic(for_gen) => {
+                    let mut new_gen = for_gen.clone();
+                    let before = self.changes;
+                    let mut iterators = new_gen.iterators.to_vec();
+                    self.expressions(st, &mut iterators, arena);
+                    if self.changes != before {
+                        new_gen.iterators = arena.alloc_slice_clone(&iterators);
+                    }
+
+                    let mut scan = self.scan();
+                    scan.block(&new_gen.body);
+                    self.apply(st, &scan);
+
+                    let mut body = st.clone();
+                    for var in new_gen.variables.iter() {
+                        self.declare(&mut body, var.node);
+                    }
+                    self.block(&mut body, &mut new_gen.body, arena);
+                    if self.changes != before {
+                        *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    }
+                }
+            },
+            Statement::Block(block) => {
+                let mut inner = st.clone();
+                self.block(&mut inner, block, arena);
+                let mut scan = self.scan();
+                scan.block(block);
+                self.apply(st, &scan);
+            }
+            Statement::Return(ret) => {
+                let mut values = ret.values.to_vec();
+                let before = self.changes;
+                self.expressions(st, &mut values, arena);
+                if self.changes != before {
+                    ret.values = arena.alloc_slice_clone(&values);
+                }
+            }
+            Statement::Throw(throw) => {
+                self.expression(st, &mut throw.expression, true, arena);
+            }
+            Statement::Export(export) => {
+                if let ExportKind::Declaration(inner) = export.kind {
+                    let mut new_inner = inner.clone();
+                    let before = self.changes;
+                    self.statement(st, &mut new_inner, arena);
+                    if self.changes != before {
+                        export.kind = ExportKind::Declaration(arena.alloc(new_inner));
+                    }
+                } else {
+                    let mut scan = self.scan();
+                    scan.statement(stmt);
+                    self.apply(st, &scan);
+                }
+            }
+            Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::Rethrow(_)
+            | Statement::Interface(_)
+            | Statement::TypeAlias(_)
+            | Statement::DeclareFunction(_)
+            | Statement::DeclareNamespace(_)
+            | Statement::DeclareType(_)
+            | Statement::DeclareInterface(_)
+            | Statement::DeclareConst(_) => {}
+            _ => {
+                let mut scan = self.scan();
+                scan.statement(stmt);
+                self.apply(st, &scan);
+            }
+        }
+    }
+
+    fn declaration<'arena>(
+        &mut self,
+        st: &mut State,
+        decl: &mut VariableDeclaration<'arena>,
+        arena: &'arena Bump,
+    ) {
+        let name = match &decl.pattern {
+            Pattern::Identifier(ident) => Some(ident.node),
+            _ => None,
+        };
+        let global = matches!(decl.kind, VariableKind::Global);
+        let value = self.expression(st, &mut decl.initializer, name.is_some() && !global, arena);
+
+        let mut names = Vec::new();
+        pattern_names(&decl.pattern, &mut names);
+        if global {
+            for name in names {
+                self.assign(st, name);
+            }
+            return;
+        }
+        match name {
+            Some(name) => {
+                let var = self.declare(st, name);
+                self.bind(st, var, value);
+            }
+            None => {
+                for name in names {
+                    self.declare(st, name);
+                }
+                // Patterns may bind names `pattern_names` does not list
+                self.invalidate(st);
+            }
+        }
+    }
+
+    fn assignment<'arena>(
+        &mut self,
+        st: &mut State,
+        target: &mut &'arena Expression<'arena>,
+        op: AssignmentOp,
+        value: &mut &'arena Expression<'arena>,
+        arena: &'arena Bump,
+    ) {
+        let plain = matches!(op, AssignmentOp::Assign);
+        if let ExpressionKind::Identifier(name) = target.kind {
+            let value = self.operand(st, value, true, arena);
+            if let Some(var) = self.assign(st, name) {
+                self.bind(st, var, value.filter(|_| plain));
+            }
+            return;
+        }
+
+        // The table and key are evaluated before the value, the store last
+        let mut new_target = (**target).clone();
+        let before = self.changes;
+        let store = match &mut new_target.kind {
+            ExpressionKind::Member(object, field) => {
+                let table = variable_name(object);
+                self.operand(st, object, true, arena);
+                Some(Store {
+                    table,
+                    field: Some(field.node),
+                })
+            }
+            ExpressionKind::Index(object, index) => {
+                let table = variable_name(object);
+                self.operand(st, object, true, arena);
+                self.operand(st, index, true, arena);
+                Some(Store { table, field: None })
+            }
+            _ => None,
+        };
+        if self.changes != before {
+            *target = arena.alloc(new_target);
+        }
+        self.operand(st, value, true, arena);
+        match store {
+            Some(store) => self.store(st, store),
+            None => st.forget_memory(|_| true),
+        }
+    }
+
+    /// Number a list of expressions whose last one may produce several values
+    fn expressions<'arena>(
+        &mut self,
+        st: &mut State,
+        expressions: &mut [Expression<'arena>],
+        arena: &'arena Bump,
+    ) {
+        let last = expressions.len().saturating_sub(1);
+        for (i, expr) in expressions.iter_mut().enumerate() {
+            let reuse = i != last || !produces_many(expr);
+            self.expression(st, expr, reuse, arena);
+        }
+    }
+
+    fn arguments<'arena>(
+        &mut self,
+        st: &mut State,
+        arguments: &mut &'arena [Argument<'arena>],
+        arena: &'arena Bump,
+    ) -> Option<Vec<ValueNumber>> {
+        let mut new_arguments = arguments.to_vec();
+        let before = self.changes;
+        let last = new_arguments.len().saturating_sub(1);
+        let mut values = Some(Vec::new());
+        for (i, argument) in new_arguments.iter_mut().enumerate() {
+            // A call in the last argument passes on all of its results
+            let reuse = i != last || !produces_many(&argument.value);
+            let value = self.expression(st, &mut argument.value, reuse, arena);
+            match (value, &mut values) {
+                (Some(value), Some(values)) if !argument.is_spread => values.push(value),
+                _ => values = None,
+            }
+        }
+        if self.changes != before {
+            *arguments = arena.alloc_slice_clone(&new_arguments);
+        }
+        values
+    }
+
+    /// Number a sub-expression, reallocating it if it was rewritten
+    fn operand<'arena>(
+        &mut self,
+        st: &mut State,
+        operand: &mut &'arena Expression<'arena>,
+        reuse: bool,
+        arena: &'arena Bump,
+    ) -> Option<ValueNumber> {
+        let mut new_operand = (**operand).clone();
+        let before = self.changes;
+        let value = self.expression(st, &mut new_operand, reuse, arena);
+        if self.changes != before {
+            *operand = arena.alloc(new_operand);
+        }
+        value
+    }
+
+    /// Number `expr` and replace it with the local holding its value, unless
+    /// `reuse` is false because the expression may produce several values
+    fn expression<'arena>(
+        &mut self,
+        st: &mut State,
+        expr: &mut Expression<'arena>,
+        reuse: bool,
+        arena: &'arena Bump,
+    ) -> Option<ValueNumber> {
+        if !is_numbered(&expr.kind) {
+            let mut scan = self.scan();
+            scan.expression(expr);
+            self.apply(st, &scan);
+            return None;
+        }
+
+        let (key, dependence) = match &mut expr.kind {
+            ExpressionKind::Literal(literal) => {
+                return Some(self.value(st, ValueKey::Literal(literal_key(literal)), None));
+            }
+            ExpressionKind::Identifier(name) => return Some(self.variable(st, *name)),
+            ExpressionKind::Parenthesized(inner) | ExpressionKind::TypeAssertion(inner, _) => {
+                return self.operand(st, inner, true, arena);
+            }
+            ExpressionKind::Unary(op, operand) => {
+                // The length of a table changes with its contents
+                let dependence = matches!(op, UnaryOp::Length).then(|| {
+                    Dependence::Field(Store {
+                        table: variable_name(operand),
+                        field: None,
+                    })
+                });
+                let operand = self.operand(st, operand, true, arena)?;
+                (ValueKey::Unary(discriminant(&*op), operand), dependence)
+            }
+            ExpressionKind::Binary(op, left, right) => {
+                let left = self.operand(st, left, true, arena);
+                let right = self.operand(st, right, true, arena);
+                (ValueKey::Binary(discriminant(&*op), left?, right?), None)
+            }
+            ExpressionKind::Member(object, field) => {
+                let table = variable_name(object);
+                let object = self.operand(st, object, true, arena)?;
+                let field = field.node;
+                let read = Store {
+                    table,
+                    field: Some(field),
+                };
+                (
+                    ValueKey::Member(object, field),
+                    Some(Dependence::Field(read)),
+                )
+            }
+            ExpressionKind::Index(object, index) => {
+                let table = variable_name(object);
+                let object = self.operand(st, object, true, arena);
+                let index = self.operand(st, index, true, arena);
+                let read = Store { table, field: None };
+                (
+                    ValueKey::Index(object?, index?),
+                    Some(Dependence::Field(read)),
+                )
+            }
+            ExpressionKind::Call(callee, arguments, _) => {
+                let function = self.pure_calls.callee(callee);
+                if function.is_none() {
+                    self.operand(st, callee, true, arena);
+                }
+                let arguments = self.arguments(st, arguments, arena);
+                let Some(function) = function else {
+                    st.forget_memory(|_| true);
+                    return None;
+                };
+                (ValueKey::Call(function, arguments?), Some(Dependence::Call))
+            }
+            ExpressionKind::MethodCall(object, _, arguments, _)
+            | ExpressionKind::OptionalMethodCall(object, _, arguments, _)
+            | ExpressionKind::OptionalCall(object, arguments, _)
+            | ExpressionKind::New(object, arguments, _) => {
+                self.operand(st, object, true, arena);
+                self.arguments(st, arguments, arena);
+                st.forget_memory(|_| true);
+                return None;
+            }
+            ExpressionKind::Conditional(condition, then_expr, else_expr) => {
+                self.operand(st, condition, true, arena);
+                self.operand(st, then_expr, true, arena);
+                self.operand(st, else_expr, true, arena);
+                return None;
+            }
+            // Creating a closure runs none of its body
+            _ => return None,
+        };
+
+        let value = self.value(st, key, dependence);
+        if reuse && !self.probing {
+            if let Some(leader) = self.leader(st, value) {
+                let interner = self.interner;
+                remarks::applied(PASS, expr.span, || {
+                    format!(
+                        "reused `{}`, which already holds this value",
+                        interner.resolve(leader)
+                    )
+                });
+                *expr = Expression {
+                    kind: ExpressionKind::Identifier(leader),
+                    span: expr.span,
+                    annotated_type: expr.annotated_type.clone(),
+                    receiver_class: None,
+                };
+                self.changes += 1;
+            }
+        }
+        Some(value)
+    }
+
+    /// Number `expr` without rewriting it
+    fn probe<'arena>(
+        &mut self,
+        st: &mut State,
+        expr: &Expression<'arena>,
+        arena: &'arena Bump,
+    ) -> Option<ValueNumber> {
+        let mut copy = expr.clone();
+        self.probing = true;
+        let value = self.expression(st, &mut copy, false, arena);
+        self.probing = false;
+        value
+    }
+
+    /// Number an `if`, leaving `st` at the join. Returns the state at the end
+    /// of each arm that falls through to the join, the last entry being the
+    /// `else` arm or, without one, the state when every condition was false.
+    fn if_statement<'arena>(
+        &mut self,
+        st: &mut State,
+        if_stmt: &mut IfStatement<'arena>,
+        arena: &'arena Bump,
+    ) -> Vec<Option<State>> {
+        self.expression(st, &mut if_stmt.condition, true, arena);
+        let mut ends = vec![self.arm(st, &mut if_stmt.then_block, arena)];
+
+        if !if_stmt.else_ifs.is_empty() {
+            let mut else_ifs = if_stmt.else_ifs.to_vec();
+            let before = self.changes;
+            for else_if in &mut else_ifs {
+                self.expression(st, &mut else_if.condition, true, arena);
+                ends.push(self.arm(st, &mut else_if.block, arena));
+            }
+            if self.changes != before {
+                if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+            }
+        }
+
+        match &mut if_stmt.else_block {
+            Some(else_block) => ends.push(self.arm(st, else_block, arena)),
+            None => ends.push(Some(st.clone())),
+        }
+
+        // The phis at the join: whatever an arm wrote has a new value
+        let mut scan = self.scan();
+        scan.block(&if_stmt.then_block);
+        for else_if in if_stmt.else_ifs.iter() {
+            scan.block(&else_if.block);
+        }
+        if let Some(else_block) = &if_stmt.else_block {
+            scan.block(else_block);
+        }
+        self.apply(st, &scan);
+        ends
+    }
+
+    /// Number an arm from a copy of `st`, returning its end state if control
+    /// falls through to the join
+    fn arm<'arena>(
+        &mut self,
+        st: &State,
+        block: &mut Block<'arena>,
+        arena: &'arena Bump,
+    ) -> Option<State> {
+        let mut arm = st.clone();
+        self.block(&mut arm, block, arena);
+        (!exits(block)).then_some(arm)
+    }
+
+    /// Make the initializer of `next`, the declaration after `if_stmt`, fully
+    /// redundant when some arms already hold its value. `st` is the state at
+    /// the join and `ends` the states at the ends of the arms. Returns the
+    /// declaration of the temporary to insert before the `if`.
+    fn eliminate_partial_redundancy<'arena>(
+        &mut self,
+        st: &mut State,
+        if_stmt: &mut IfStatement<'arena>,
+        ends: Vec<Option<State>>,
+        next: &VariableDeclaration<'arena>,
+        arena: &'arena Bump,
+    ) -> Option<Statement<'arena>> {
+        if !matches!(next.pattern, Pattern::Identifier(_))
+            || matches!(next.kind, VariableKind::Global)
+        {
+            return None;
+        }
+        let expr = &next.initializer;
+        let mut names = Vec::new();
+        if !matches!(
+            expr.kind,
+            ExpressionKind::Unary(..)
+                | ExpressionKind::Binary(..)
+                | ExpressionKind::Member(..)
+                | ExpressionKind::Index(..)
+        ) || !operand_names(expr, &mut names)
+        {
+            return None;
+        }
+
+        // Copies of the expression in the arms must see the same variables
+        let mut blocks = vec![&if_stmt.then_block];
+        blocks.extend(if_stmt.else_ifs.iter().map(|else_if| &else_if.block));
+        blocks.extend(if_stmt.else_block.as_ref());
+        if blocks.iter().any(|block| declares_any(block, &names)) {
+            return None;
+        }
+
+        let value = self.probe(st, expr, arena)?;
+        if self.leader(st, value).is_some() {
+            return None;
+        }
+
+        // For each arm reaching the join, the local holding the value there
+        let mut holders = Vec::new();
+        for end in ends {
+            holders.push(end.map(|mut arm| {
+                let value = self.probe(&mut arm, expr, arena)?;
+                self.leader(&arm, value)
+            }));
+        }
+        let available = holders
+            .iter()
+            .filter(|h| matches!(h, Some(Some(_))))
+            .count();
+        if available == 0 {
+            return None;
+        }
+        let missing = holders.iter().filter(|h| matches!(h, Some(None))).count();
+
+        remarks::applied(PASS, expr.span, || match missing {
+            0 => "every arm of the `if` holds the value, so it is reused after it".to_string(),
+            _ => format!(
+                "computed the value in {} arm(s) that lacked it, so it is reused after the `if`",
+                missing
+            ),
+        });
+        let temp = self.temp_name();
+        let mut holders = holders.into_iter();
+        let assign_in = |block: &mut Block<'arena>, holder: Option<Option<StringId>>| {
+            if let Some(holder) = holder {
+                assign_temp(block, temp, holder, expr, arena);
+            }
+        };
+
+        assign_in(&mut if_stmt.then_block, holders.next().flatten());
+        if !if_stmt.else_ifs.is_empty() {
+            let mut else_ifs = if_stmt.else_ifs.to_vec();
+            for else_if in &mut else_ifs {
+                assign_in(&mut else_if.block, holders.next().flatten());
+            }
+            if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+        }
+        let last = holders.next().flatten();
+        match &mut if_stmt.else_block {
+            Some(else_block) => assign_in(else_block, last),
+            None => {
+                let mut else_block = Block {
+                    statements: &[],
+                    span: if_stmt.span,
+                };
+                assign_in(&mut else_block, last);
+                if_stmt.else_block = Some(else_block);
+            }
+        }
+        self.changes += 1;
+
+        let var = self.declare(st, temp);
+        self.bind(st, var, Some(value));
+        Some(Statement::Variable(VariableDeclaration {
+            kind: VariableKind::Local,
+            pattern: Pattern::Identifier(Spanned::new(temp, Span::dummy())),
+            type_annotation: None,
+            initializer: Expression::new(ExpressionKind::Literal(Literal::Nil), if_stmt.span),
+            span: if_stmt.span,
+        }))
+    }
+
+    fn temp_name(&mut self) -> StringId {
+        let id = *self.next_temp_id;
+        *self.next_temp_id += 1;
+        self.interner.get_or_intern(&format!("__gvn_{}", id))
+    }
+}
+
+/// Append `temp = <holder or expr>` to `block`
+fn assign_temp<'arena>(
+    block: &mut Block<'arena>,
+    temp: StringId,
+    holder: Option<StringId>,
+    expr: &Expression<'arena>,
+    arena: &'arena Bump,
+) {
+    let value = match holder {
+        Some(name) => Expression::new(ExpressionKind::Identifier(name), expr.span),
+        None => expr.clone(),
+    };
+    let target = Expression::new(ExpressionKind::Identifier(temp), expr.span);
+    let assignment = Expression::new(
+        ExpressionKind::Assignment(
+            arena.alloc(target),
+            AssignmentOp::Assign,
+            arena.alloc(value),
+        ),
+        expr.span,
+    );
+    let mut statements = block.statements.to_vec();
+    statements.push(Statement::Expression(assignment));
+    block.statements = arena.alloc_slice_clone(&statements);
+}
+
+/// Whether `Numbering::expression` numbers or descends into `kind`; the
+/// effects of anything else are taken from a scan
+fn is_numbered(kind: &ExpressionKind<'_>) -> bool {
+    matches!(
+        kind,
+        ExpressionKind::Literal(_)
+            | ExpressionKind::Identifier(_)
+            | ExpressionKind::Parenthesized(_)
+            | ExpressionKind::TypeAssertion(..)
+            | ExpressionKind::Unary(..)
+            | ExpressionKind::Binary(..)
+            | ExpressionKind::Member(..)
+            | ExpressionKind::Index(..)
+            | ExpressionKind::Call(..)
+            | ExpressionKind::MethodCall(..)
+            | ExpressionKind::OptionalMethodCall(..)
+            | ExpressionKind::OptionalCall(..)
+            | ExpressionKind::New(..)
+            | ExpressionKind::Conditional(..)
+            | ExpressionKind::Function(_)
+            | ExpressionKind::Arrow(_)
+    )
+}
+
+/// Whether `expr` may produce more than one value
+fn produces_many(expr: &Expression<'_>) -> bool {
+    matches!(
+        expr.kind,
+        ExpressionKind::Call(..)
+            | ExpressionKind::MethodCall(..)
+            | ExpressionKind::OptionalCall(..)
+            | ExpressionKind::OptionalMethodCall(..)
+    )
+}
+
+/// Whether the last statement of `block` leaves it
+fn exits(block: &Block<'_>) -> bool {
+    matches!(
+        block.statements.last(),
+        Some(
+            Statement::Return(_)
+                | Statement::Break(_)
+                | Statement::Continue(_)
+                | Statement::Goto(_)
+                | Statement::Throw(_)
+                | Statement::Rethrow(_)
+        )
+    )
+}
+
+/// Numbers compare by value but integers and floats stay apart, as
+/// `math.type` tells them apart
+fn literal_key(literal: &Literal) -> String {
+    match literal {
+        Literal::Nil => "nil".to_string(),
+        Literal::Boolean(value) => format!("boolean {}", value),
+        Literal::Number(value) => format!("number {}", value),
+        Literal::Integer(value) => format!("integer {}", value),
+        Literal::String(value) => format!("string {}", value),
+    }
+}
+
+/// Collect the variables of an expression built from operators, table reads,
+/// variables and literals. Returns false for anything else.
+fn operand_names(expr: &Expression<'_>, names: &mut Vec<StringId>) -> bool {
+    match &expr.kind {
+        ExpressionKind::Identifier(name) => {
+            names.push(*name);
+            true
+        }
+        ExpressionKind::Literal(_) => true,
+        ExpressionKind::Parenthesized(operand)
+        | ExpressionKind::Unary(_, operand)
+        | ExpressionKind::Member(operand, _) => operand_names(operand, names),
+        ExpressionKind::Binary(_, left, right) | ExpressionKind::Index(left, right) => {
+            operand_names(left, names) && operand_names(right, names)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the top level of `block` declares one of `names`
+fn declares_any(block: &Block<'_>, names: &[StringId]) -> bool {
+    block.statements.iter().any(|stmt| {
+        let mut declared = Vec::new();
+        match stmt {
+            Statement::Variable(decl) => pattern_names(&decl.pattern, &mut declared),
+            Statement::Function(func) => declared.push(func.name.node),
+            // Other declarations are not modeled
+            Statement::Class(_) | Statement::Enum(_) | Statement::Import(_) => return true,
+            _ => {}
+        }
+        declared.iter().any(|name| names.contains(name))
+    })
+}
@@ -0,0 +1,514 @@
+//! Identifier minification (size goal)
+//!
+//! Renames parameters and locals to the shortest names that are free where
+//! they are declared:
+//!
+//! ```lua
+//! -- Before
+//! local function distance(first, second)
+//!     local deltaX = second.x - first.x
+//!     return deltaX * deltaX
+//! end
+//!
+//! -- After
+//! local function distance(a, b)
+//!     local c = b.x - a.x
+//!     return c * c
+//! end
+//! ```
+//!
+//! Module-level bindings and function names keep their names, since other
+//! modules, the export table and forward references use them. Each top-level
+//! statement is renamed on its own; generated names avoid every name the
+//! statement mentions, so no reference is captured. A statement containing a
+//! construct the renamer does not model (classes, `try`, `match`, template
+//! strings, destructuring, labels, ...) is left untouched.
+
+use super::short_names::NameGenerator;
+use crate::optimizer::{remarks, visit_expr_children, WholeProgramPass};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{ArrowBody, Expression, ExpressionKind};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{
+    Block, ExportKind, ForStatement, Parameter, Statement, VariableKind,
+};
+use luanext_parser::ast::Spanned;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::Arc;
+
+const PASS: &str = "identifier-minification";
+
+pub struct IdentifierMinificationPass {
+    interner: Arc<StringInterner>,
+    /// Names this pass generated; later iterations keep them as they are
+    produced: FxHashSet<StringId>,
+}
+
+impl IdentifierMinificationPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            interner,
+            produced: FxHashSet::default(),
+        }
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for IdentifierMinificationPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let mut changed = false;
+
+        for stmt in &mut program.statements {
+            // First pass: collect every name the statement mentions
+            let mut collector = Renamer::new(&self.interner, &self.produced, None);
+            collector.visit_top_level(&mut stmt.clone(), arena);
+            if !collector.supported {
+                continue;
+            }
+            let taken: FxHashSet<String> = collector
+                .seen
+                .iter()
+                .map(|name| self.interner.resolve(*name).to_string())
+                .collect();
+
+            let mut renamer = Renamer::new(
+                &self.interner,
+                &self.produced,
+                Some(NameGenerator::new(taken)),
+            );
+            let mut renamed = stmt.clone();
+            renamer.visit_top_level(&mut renamed, arena);
+            if !renamer.supported || renamer.renamed == 0 {
+                continue;
+            }
+
+            remarks::applied(PASS, remarks::statement_span(stmt), || {
+                format!("shortened {} local names", renamer.renamed)
+            });
+            let generated = renamer.generated;
+            self.produced.extend(generated);
+            *stmt = renamed;
+            changed = true;
+        }
+
+        Ok(changed)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Bindings of one lexical scope
+struct Scope {
+    bindings: FxHashMap<StringId, StringId>,
+    /// Index of the next generated name declared in this scope
+    next_slot: usize,
+}
+
+/// Renames the locals of one top-level statement.
+///
+/// Names are assigned by slot: the n-th local visible at a point gets the
+/// n-th generated name, so sibling scopes reuse names while nested scopes
+/// never shadow a renamed binding they can see.
+struct Renamer<'p> {
+    interner: &'p StringInterner,
+    produced: &'p FxHashSet<StringId>,
+    /// `None` while only collecting names
+    names: Option<NameGenerator>,
+    slots: Vec<StringId>,
+    scopes: Vec<Scope>,
+    /// Every name declared or referenced
+    seen: FxHashSet<StringId>,
+    generated: Vec<StringId>,
+    renamed: usize,
+    supported: bool,
+}
+
+impl<'p> Renamer<'p> {
+    fn new(
+        interner: &'p StringInterner,
+        produced: &'p FxHashSet<StringId>,
+        names: Option<NameGenerator>,
+    ) -> Self {
+        Self {
+            interner,
+            produced,
+            names,
+            slots: Vec::new(),
+            // The module scope, whose bindings keep their names
+            scopes: vec![Scope {
+                bindings: FxHashMap::default(),
+                next_slot: 0,
+            }],
+            seen: FxHashSet::default(),
+            generated: Vec::new(),
+            renamed: 0,
+            supported: true,
+        }
+    }
+
+    fn push_scope(&mut self) {
+        let next_slot = self.scopes.last().map_or(0, |scope| scope.next_slot);
+        self.scopes.push(Scope {
+            bindings: FxHashMap::default(),
+            next_slot,
+        });
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("module scope is never popped")
+    }
+
+    fn slot_name(&mut self, slot: usize) -> Option<StringId> {
+        let names = self.names.as_mut()?;
+        while self.slots.len() <= slot {
+            let name = self.interner.get_or_intern(&names.next_name());
+            self.generated.push(name);
+            self.slots.push(name);
+        }
+        Some(self.slots[slot])
+    }
+
+    /// Declare `name` in the current scope under a name that is never renamed
+    fn declare_fixed(&mut self, name: StringId) {
+        self.seen.insert(name);
+        self.scope().bindings.insert(name, name);
+    }
+
+    /// Declare `name` in the current scope and return its new name
+    fn declare(&mut self, name: StringId) -> StringId {
+        if self.scopes.len() == 1 || self.produced.contains(&name) {
+            self.declare_fixed(name);
+            return name;
+        }
+        self.seen.insert(name);
+
+        let slot = self.scope().next_slot;
+        let Some(new_name) = self.slot_name(slot) else {
+            self.scope().bindings.insert(name, name);
+            return name;
+        };
+        let scope = self.scope();
+        scope.next_slot += 1;
+        scope.bindings.insert(name, new_name);
+        if new_name != name {
+            self.renamed += 1;
+        }
+        new_name
+    }
+
+    fn resolve(&mut self, name: StringId) -> StringId {
+        self.seen.insert(name);
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.bindings.get(&name).copied())
+            .unwrap_or(name)
+    }
+
+    fn visit_top_level<'arena>(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) {
+        match stmt {
+            Statement::Export(export) => match export.kind {
+                ExportKind::Declaration(inner)
+                    if matches!(inner, Statement::Function(_) | Statement::Variable(_)) =>
+                {
+                    let mut new_inner = inner.clone();
+                    if self.visit_stmt(&mut new_inner, arena) {
+                        export.kind = ExportKind::Declaration(arena.alloc(new_inner));
+                    }
+                }
+                _ => self.supported = false,
+            },
+            _ => {
+                self.visit_stmt(stmt, arena);
+            }
+        }
+    }
+
+    fn visit_stmt<'arena>(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) -> bool {
+        if !self.supported {
+            return false;
+        }
+
+        match stmt {
+            Statement::Variable(decl) => {
+                let Pattern::Identifier(ident) = &decl.pattern else {
+                    self.supported = false;
+                    return false;
+                };
+                if matches!(decl.kind, VariableKind::Global) && self.scopes.len() > 1 {
+                    self.supported = false;
+                    return false;
+                }
+                let ident = ident.clone();
+                // The initializer can't see the binding it initializes
+                let mut changed = self.visit_expr(&mut decl.initializer, arena);
+                let new_name = self.declare(ident.node);
+                if new_name != ident.node {
+                    decl.pattern = Pattern::Identifier(Spanned::new(new_name, ident.span));
+                    changed = true;
+                }
+                changed
+            }
+            Statement::Function(func) => {
+                // Declared before the body so recursive calls resolve to it
+                self.declare_fixed(func.name.node);
+                self.visit_function(&mut func.parameters, &mut func.body.statements, None, arena)
+            }
+            Statement::Expression(expr) => self.visit_expr(expr, arena),
+            Statement::If(if_stmt) => {
+                let mut changed = self.visit_expr(&mut if_stmt.condition, arena);
+                changed |= self.visit_block(&mut if_stmt.then_block, arena);
+                let mut new_else_ifs: Vec<_> = if_stmt.else_ifs.to_vec();
+                let mut eic = false;
+                for else_if in &mut new_else_ifs {
+                    eic |= self.visit_expr(&mut else_if.condition, arena);
+                    eic |= self.visit_block(&mut else_if.block, arena);
+                }
+                if eic {
+                    if_stmt.else_ifs = arena.alloc_slice_clone(&new_else_ifs);
+                    changed = true;
+                }
+                if let Some(else_block) = &mut if_stmt.else_block {
+                    changed |= self.visit_block(else_block, arena);
+                }
+                changed
+            }
+            Statement::While(while_stmt) => {
+                let changed = self.visit_expr(&mut while_stmt.condition, arena);
+                changed | self.visit_block(&mut while_stmt.body, arena)
+            }
+            Statement::For(for_stmt) => match &**for_stmt {
+                ForStatement::Numeric(for_num_ref) => {
+                    let mut new_num = (**for_num_ref).clone();
+                    let mut fc = self.visit_expr(&mut new_num.start, arena);
+                    fc |= self.visit_expr(&mut new_num.end, arena);
+                    if let Some(step) = &mut new_num.step {
+                        fc |= self.visit_expr(step, arena);
+                    }
+                    self.push_scope();
+                    let variable = new_num.variable.node;
+                    let new_variable = self.declare(variable);
+                    if new_variable != variable {
+                        new_num.variable = Spanned::new(new_variable, new_num.variable.span);
+                        fc = true;
+                    }
+                    fc |= self.visit_block(&mut new_num.body, arena);
+                    self.pop_scope();
+                    if fc {
+                        *stmt = Statement::For(
+                            arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
+                        );
+                    }
+                    fc
+                }
+                ForStatement::Generic(for_gen_ref) => {
+                    let mut new_gen = for_gen_ref.clone();
+                    let mut fc = false;
+                    let mut new_iters: Vec<_> = new_gen.iterators.to_vec();
+                    for expr in &mut new_iters {
+                        fc |= self.visit_expr(expr, arena);
+                    }
+                    if fc {
+                        new_gen.iterators = arena.alloc_slice_clone(&new_iters);
+                    }
+                    self.push_scope();
+                    let mut new_vars: Vec<_> = new_gen.variables.to_vec();
+                    let mut vc = false;
+                    for var in &mut new_vars {
+                        let new_name = self.declare(var.node);
+                        if new_name != var.node {
+                            *var = Spanned::new(new_name, var.span);
+                            vc = true;
+                        }
+                    }
+                    if vc {
+                        new_gen.variables = arena.alloc_slice_clone(&new_vars);
+                        fc = true;
+                    }
+                    fc |= self.visit_block(&mut new_gen.body, arena);
+                    self.pop_scope();
+                    if fc {
+                        *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    }
+                    fc
+                }
+            },
+            Statement::Repeat(repeat_stmt) => {
+                // `until` sees the locals of the body
+                self.push_scope();
+                let mut changed = self.visit_statements(&mut repeat_stmt.body.statements, arena);
+                changed |= self.visit_expr(&mut repeat_stmt.until, arena);
+                self.pop_scope();
+                changed
+            }
+            Statement::Block(block) => self.visit_block(block, arena),
+            Statement::Return(ret_stmt) => {
+                let mut values: Vec<Expression<'arena>> = ret_stmt.values.to_vec();
+                let mut changed = false;
+                for expr in &mut values {
+                    changed |= self.visit_expr(expr, arena);
+                }
+                if changed {
+                    ret_stmt.values = arena.alloc_slice_clone(&values);
+                }
+                changed
+            }
+            Statement::Break(_) | Statement::Continue(_) => false,
+            _ => {
+                self.supported = false;
+                false
+            }
+        }
+    }
+
+    fn visit_block<'arena>(&mut self, block: &mut Block<'arena>, arena: &'arena Bump) -> bool {
+        self.push_scope();
+        let changed = self.visit_statements(&mut block.statements, arena);
+        self.pop_scope();
+        changed
+    }
+
+    fn visit_statements<'arena>(
+        &mut self,
+        statements: &mut &'arena [Statement<'arena>],
+        arena: &'arena Bump,
+    ) -> bool {
+        let mut stmts: Vec<Statement<'arena>> = statements.to_vec();
+        let mut changed = false;
+        for stmt in &mut stmts {
+            changed |= self.visit_stmt(stmt, arena);
+        }
+        if changed {
+            *statements = arena.alloc_slice_clone(&stmts);
+        }
+        changed
+    }
+
+    /// Visit a function's parameters and body in a new scope. The body is
+    /// either a statement list or, for expression-bodied arrows, `expr_body`.
+    fn visit_function<'arena>(
+        &mut self,
+        parameters: &mut &'arena [Parameter<'arena>],
+        statements: &mut &'arena [Statement<'arena>],
+        expr_body: Option<&mut Expression<'arena>>,
+        arena: &'arena Bump,
+    ) -> bool {
+        self.push_scope();
+
+        let mut new_params: Vec<Parameter<'arena>> = parameters.to_vec();
+        let mut changed = false;
+        for param in &mut new_params {
+            let Pattern::Identifier(ident) = &param.pattern else {
+                self.supported = false;
+                continue;
+            };
+            let ident = ident.clone();
+            let new_name = self.declare(ident.node);
+            if new_name != ident.node {
+                param.pattern = Pattern::Identifier(Spanned::new(new_name, ident.span));
+                changed = true;
+            }
+        }
+        // Defaults are assigned in the body, where every parameter is visible
+        for param in &mut new_params {
+            if let Some(default) = &mut param.default {
+                changed |= self.visit_expr(default, arena);
+            }
+        }
+        if changed {
+            *parameters = arena.alloc_slice_clone(&new_params);
+        }
+
+        changed |= self.visit_statements(statements, arena);
+        if let Some(expr) = expr_body {
+            changed |= self.visit_expr(expr, arena);
+        }
+
+        self.pop_scope();
+        changed
+    }
+
+    fn visit_expr<'arena>(&mut self, expr: &mut Expression<'arena>, arena: &'arena Bump) -> bool {
+        if !self.supported {
+            return false;
+        }
+
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => {
+                let name = *name;
+                let new_name = self.resolve(name);
+                if new_name == name {
+                    return false;
+                }
+                expr.kind = ExpressionKind::Identifier(new_name);
+                true
+            }
+            ExpressionKind::Literal(_)
+            | ExpressionKind::SelfKeyword
+            | ExpressionKind::SuperKeyword => false,
+            ExpressionKind::Arrow(arrow) => {
+                let mut new_arrow = arrow.clone();
+                let changed = match &mut new_arrow.body {
+                    ArrowBody::Expression(body_expr) => {
+                        let mut new_body = (**body_expr).clone();
+                        let changed = self.visit_function(
+                            &mut new_arrow.parameters,
+                            &mut &[][..],
+                            Some(&mut new_body),
+                            arena,
+                        );
+                        if changed {
+                            new_arrow.body = ArrowBody::Expression(arena.alloc(new_body));
+                        }
+                        changed
+                    }
+                    ArrowBody::Block(block) => self.visit_function(
+                        &mut new_arrow.parameters,
+                        &mut block.statements,
+                        None,
+                        arena,
+                    ),
+                };
+                if changed {
+                    expr.kind = ExpressionKind::Arrow(new_arrow);
+                }
+                changed
+            }
+            ExpressionKind::Function(func_expr) => {
+                let mut new_func = func_expr.clone();
+                let changed = self.visit_function(
+                    &mut new_func.parameters,
+                    &mut new_func.body.statements,
+                    None,
+                    arena,
+                );
+                if changed {
+                    expr.kind = ExpressionKind::Function(new_func);
+                }
+                changed
+            }
+            ExpressionKind::Match(_) | ExpressionKind::Try(_) | ExpressionKind::Template(_) => {
+                self.supported = false;
+                false
+            }
+            _ => visit_expr_children(expr, arena, &mut |e, a| self.visit_expr(e, a)),
+        }
+    }
+}
@@ -0,0 +1,758 @@
+//! Induction variable strength reduction and bounds simplification
+//!
+//! Loops over flat arrays of records index them with arithmetic on the loop
+//! variable, such as `data[i * 4 + 1]`, which costs a multiplication on every
+//! iteration. Using the induction variables `LoopInduction` finds in numeric
+//! `for` loops with integer start and step, this pass:
+//!
+//! 1. Removes a counter declared right before the loop that steps with the
+//!    loop variable, using the loop variable plus a constant instead
+//! 2. Strength-reduces `i * c + d` into a counter that the body steps by
+//!    `step * c` at its start, declared before the loop
+//! 3. Rebases the loop variable when the body only uses it as `i + d`,
+//!    shifting both bounds by `d` and folding the shift into the limit
+//!
+//! ```lua
+//! -- Before:
+//! local k = 1
+//! for i = 0, n - 1 do
+//!     out[k] = data[i * 4 + 1]
+//!     k = k + 1
+//! end
+//!
+//! -- After:
+//! local __iv_0 = -3
+//! for i = 1, n do
+//!     __iv_0 = __iv_0 + 4
+//!     out[i] = data[__iv_0]
+//! end
+//! ```
+//!
+//! A loop is left alone when its body assigns or redeclares the loop
+//! variable. Counters are only removed when the rest of the block never uses
+//! them and no closure captures them, and products are only reduced when no
+//! closure captures the loop variable, since a closure may run after its
+//! iteration has ended.
+
+use super::memory_effects::Scan;
+use crate::config::OptimizationLevel;
+use crate::optimizer::analysis::induction::integer_literal;
+use crate::optimizer::analysis::LoopInduction;
+use crate::optimizer::{
+    remarks, visit_block_expressions, visit_expr_children, visit_stmt_expressions, AstFeatures,
+    WholeProgramPass,
+};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{
+    ArrowBody, AssignmentOp, BinaryOp, Expression, ExpressionKind, Literal,
+};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{
+    Block, ExportKind, ForNumeric, ForStatement, Statement, VariableDeclaration, VariableKind,
+};
+use luanext_parser::ast::Spanned;
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::FxHashSet;
+use std::sync::Arc;
+
+const PASS: &str = "induction-variables";
+
+/// Integers beyond this lose precision as Lua 5.1 numbers
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+/// Strength-reduces and removes induction variables of numeric `for` loops
+pub struct InductionVariablePass {
+    interner: Arc<StringInterner>,
+    next_iv_id: usize,
+}
+
+impl InductionVariablePass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            interner,
+            next_iv_id: 0,
+        }
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for InductionVariablePass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn min_level(&self) -> OptimizationLevel {
+        OptimizationLevel::Aggressive
+    }
+
+    fn required_features(&self) -> AstFeatures {
+        AstFeatures::HAS_LOOPS
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let mut scan = Scan::new(None);
+        scan.statements(&program.statements);
+        let mut bound: FxHashSet<StringId> = scan.declared.keys().copied().collect();
+        bound.extend(scan.written.iter().copied());
+
+        let mut reducer = Reducer {
+            interner: &self.interner,
+            next_iv_id: &mut self.next_iv_id,
+            bound,
+        };
+        Ok(reducer.statements(&mut program.statements, arena))
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// A counter replacing `variable * factor + offset`
+struct Reduced {
+    name: StringId,
+    factor: i64,
+    offset: i64,
+    initial: i64,
+    increment: i64,
+}
+
+/// Optimizes the loops of one run
+struct Reducer<'p> {
+    interner: &'p StringInterner,
+    next_iv_id: &'p mut usize,
+    /// Names the program declares or assigns anywhere
+    bound: FxHashSet<StringId>,
+}
+
+impl<'arena> Reducer<'_> {
+    fn block(&mut self, block: &mut Block<'arena>, arena: &'arena Bump) -> bool {
+        let mut statements = block.statements.to_vec();
+        let changed = self.statements(&mut statements, arena);
+        if changed {
+            block.statements = arena.alloc_slice_clone(&statements);
+        }
+        changed
+    }
+
+    fn statements(&mut self, statements: &mut Vec<Statement<'arena>>, arena: &'arena Bump) -> bool {
+        let mut changed = false;
+        let mut i = 0;
+        while i < statements.len() {
+            // Inner loops first, so outer loops see their counters
+            changed |= self.statement(&mut statements[i], arena);
+            if let Some(index) = self.optimize_loop(statements, i, arena) {
+                i = index;
+                changed = true;
+            }
+            i += 1;
+        }
+        changed
+    }
+
+    fn statement(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) -> bool {
+        match stmt {
+            Statement::Variable(decl) => self.expression(&mut decl.initializer, arena),
+            Statement::Expression(expr) => self.expression(expr, arena),
+            Statement::Return(ret) => {
+                let mut values = ret.values.to_vec();
+                let mut changed = false;
+                for value in &mut values {
+                    changed |= self.expression(value, arena);
+                }
+                if changed {
+                    ret.values = arena.alloc_slice_clone(&values);
+                }
+                changed
+            }
+            Statement::If(if_stmt) => {
+                let mut changed = self.expression(&mut if_stmt.condition, arena);
+                changed |= self.block(&mut if_stmt.then_block, arena);
+                let mut else_ifs = if_stmt.else_ifs.to_vec();
+                let mut else_ifs_changed = false;
+                for else_if in &mut else_ifs {
+                    else_ifs_changed |= self.expression(&mut else_if.condition, arena);
+                    else_ifs_changed |= self.block(&mut else_if.block, arena);
+                }
+                if else_ifs_changed {
+                    if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+                    changed = true;
+                }
+                if let Some(else_block) = &mut if_stmt.else_block {
+                    changed |= self.block(else_block, arena);
+                }
+                changed
+            }
+            Statement::While(while_stmt) => {
+                let changed = self.expression(&mut while_stmt.condition, arena);
+                self.block(&mut while_stmt.body, arena) || changed
+            }
+            Statement::Repeat(repeat_stmt) => {
+                let changed = self.block(&mut repeat_stmt.body, arena);
+                self.expression(&mut repeat_stmt.until, arena) || changed
+            }
+            Statement::For(for_stmt) => match *for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    let mut new_num = (**for_num).clone();
+                    let mut changed = self.expression(&mut new_num.start, arena);
+                    changed |= self.expression(&mut new_num.end, arena);
+                    if let Some(step) = &mut new_num.step {
+                        changed |= self.expression(step, arena);
+                    }
+                    changed |= self.block(&mut new_num.body, arena);
+                    if changed {
+                        *stmt = Statement::For(
+                            arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
+                        );
+                    }
+                    changed
+                }
+                ForStatement::Generic(for_gen) => {
+                    let mut new_gen = for_gen.clone();
+                    let mut iterators = new_gen.iterators.to_vec();
+                    let mut changed = false;
+                    for iterator in &mut iterators {
+                        changed |= self.expression(iterator, arena);
+                    }
+                    if changed {
+                        new_gen.iterators = arena.alloc_slice_clone(&iterators);
+                    }
+                    changed |= self.block(&mut new_gen.body, arena);
+                    if changed {
+                        *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    }
+                    changed
+                }
+            },
+            Statement::Block(block) => self.block(block, arena),
+            Statement::Function(func) => self.block(&mut func.body, arena),
+            Statement::Export(export) => {
+                let ExportKind::Declaration(inner) = export.kind else {
+                    return false;
+                };
+                let mut new_inner = inner.clone();
+                let changed = self.statement(&mut new_inner, arena);
+                if changed {
+                    export.kind = ExportKind::Declaration(arena.alloc(new_inner));
+                }
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    /// Visit the function bodies in `expr`
+    fn expression(&mut self, expr: &mut Expression<'arena>, arena: &'arena Bump) -> bool {
+        match &expr.kind {
+            ExpressionKind::Function(func) => {
+                let mut new_func = func.clone();
+                let changed = self.block(&mut new_func.body, arena);
+                if changed {
+                    expr.kind = ExpressionKind::Function(new_func);
+                }
+                changed
+            }
+            ExpressionKind::Arrow(arrow) => {
+                let mut new_arrow = arrow.clone();
+                let changed = match &mut new_arrow.body {
+                    ArrowBody::Expression(body) => {
+                        let mut new_body = (**body).clone();
+                        let changed = self.expression(&mut new_body, arena);
+                        *body = arena.alloc(new_body);
+                        changed
+                    }
+                    ArrowBody::Block(block) => self.block(block, arena),
+                };
+                if changed {
+                    expr.kind = ExpressionKind::Arrow(new_arrow);
+                }
+                changed
+            }
+            _ => visit_expr_children(expr, arena, &mut |e, a| self.expression(e, a)),
+        }
+    }
+
+    /// Optimize the induction variables of `statements[index]` when it is a
+    /// numeric `for` loop, returning the loop's new index
+    fn optimize_loop(
+        &mut self,
+        statements: &mut Vec<Statement<'arena>>,
+        index: usize,
+        arena: &'arena Bump,
+    ) -> Option<usize> {
+        let Statement::For(for_stmt) = &statements[index] else {
+            return None;
+        };
+        let ForStatement::Numeric(for_num) = **for_stmt else {
+            return None;
+        };
+        let induction = LoopInduction::analyze(for_num)?;
+        let mut body = Scan::new(None);
+        body.block(&for_num.body);
+        let variable = induction.variable;
+        if body.opaque || body.declared.contains_key(&variable) || body.written.contains(&variable)
+        {
+            return None;
+        }
+
+        let mut new_num = for_num.clone();
+        let mut index = index;
+        let mut changed = false;
+        if index > 0
+            && self.eliminate_counter(statements, index, &induction, &body, &mut new_num, arena)
+        {
+            statements.remove(index - 1);
+            index -= 1;
+            changed = true;
+        }
+        let declarations = if body.captured.contains(&variable) {
+            Vec::new()
+        } else {
+            self.strength_reduce(&induction, &mut new_num, arena)
+        };
+        changed |= !declarations.is_empty();
+        changed |= self.rebase(&induction, &mut new_num, arena);
+        if !changed {
+            return None;
+        }
+
+        let count = declarations.len();
+        statements[index] =
+            Statement::For(arena.alloc(ForStatement::Numeric(arena.alloc(new_num))));
+        statements.splice(index..index, declarations);
+        Some(index + count)
+    }
+
+    /// Replace the counter declared by `statements[index - 1]` with the loop
+    /// variable plus a constant, when it steps with the loop variable
+    fn eliminate_counter(
+        &self,
+        statements: &[Statement<'arena>],
+        index: usize,
+        induction: &LoopInduction,
+        body: &Scan<'_>,
+        new_num: &mut ForNumeric<'arena>,
+        arena: &'arena Bump,
+    ) -> bool {
+        let Statement::Variable(decl) = &statements[index - 1] else {
+            return false;
+        };
+        let Pattern::Identifier(binding) = &decl.pattern else {
+            return false;
+        };
+        if !matches!(decl.kind, VariableKind::Local) {
+            return false;
+        }
+        let counter_name = binding.node;
+        let Some(initial) = integer_literal(&decl.initializer) else {
+            return false;
+        };
+        let Some(counter) = induction.counter(counter_name) else {
+            return false;
+        };
+        if counter.step != induction.step || body.declared.contains_key(&counter_name) {
+            return false;
+        }
+        let name = self.interner.resolve(counter_name);
+        if body.captured.contains(&counter_name) {
+            remarks::missed(PASS, decl.span, || {
+                format!("counter `{}` is used by a closure", name)
+            });
+            return false;
+        }
+
+        // `SsaForm` does not see assignments in nested blocks
+        let mut others = Scan::new(None);
+        for (i, stmt) in new_num.body.statements.iter().enumerate() {
+            if i != counter.update {
+                others.statement(stmt);
+            }
+        }
+        if others.written.contains(&counter_name) {
+            return false;
+        }
+        let mut rest = Scan::new(None);
+        rest.expression(&new_num.end);
+        rest.statements(&statements[index + 1..]);
+        if rest.referenced.contains_key(&counter_name) || rest.written.contains(&counter_name) {
+            remarks::missed(PASS, decl.span, || {
+                format!("counter `{}` is used after the loop", name)
+            });
+            return false;
+        }
+
+        // The counter holds `i + before` until its update and `i + after` after it
+        let Some(before) = initial.checked_sub(induction.start) else {
+            return false;
+        };
+        let Some(after) = before.checked_add(induction.step) else {
+            return false;
+        };
+        let variable = induction.variable;
+        let span = new_num.variable.span;
+        let mut statements = new_num.body.statements.to_vec();
+        let mut replaced = 0;
+        for (i, stmt) in statements.iter_mut().enumerate() {
+            if i == counter.update {
+                continue;
+            }
+            let offset = if i < counter.update { before } else { after };
+            let replacement = offset_by(identifier(variable, span), offset, arena);
+            visit_stmt_expressions(stmt, arena, &mut |expr, arena| {
+                replace_identifier(expr, counter_name, &replacement, &mut replaced, arena)
+            });
+        }
+        if replaced != others.referenced.get(&counter_name).copied().unwrap_or(0) {
+            return false;
+        }
+        statements.remove(counter.update);
+        new_num.body.statements = arena.alloc_slice_clone(&statements);
+
+        remarks::applied(PASS, decl.span, || {
+            format!(
+                "replaced counter `{}` with loop variable `{}`",
+                name,
+                self.interner.resolve(variable)
+            )
+        });
+        true
+    }
+
+    /// Replace `i * c + d` in the body of `new_num` with counters the body
+    /// steps at its start, returning their declarations
+    fn strength_reduce(
+        &mut self,
+        induction: &LoopInduction,
+        new_num: &mut ForNumeric<'arena>,
+        arena: &'arena Bump,
+    ) -> Vec<Statement<'arena>> {
+        let mut reduced = Vec::new();
+        let mut statements = new_num.body.statements.to_vec();
+        for stmt in &mut statements {
+            visit_stmt_expressions(stmt, arena, &mut |expr, arena| {
+                self.reduce(expr, induction, &mut reduced, arena)
+            });
+        }
+        if reduced.is_empty() {
+            return Vec::new();
+        }
+
+        let variable = self.interner.resolve(induction.variable);
+        let span = new_num.span;
+        let mut declarations = Vec::with_capacity(reduced.len());
+        let mut updates = Vec::with_capacity(reduced.len());
+        for counter in &reduced {
+            declarations.push(Statement::Variable(VariableDeclaration {
+                kind: VariableKind::Local,
+                pattern: Pattern::Identifier(Spanned::new(counter.name, span)),
+                type_annotation: None,
+                initializer: integer(counter.initial, span),
+                span,
+            }));
+            let sum = offset_by(identifier(counter.name, span), counter.increment, arena);
+            updates.push(Statement::Expression(Expression::new(
+                ExpressionKind::Assignment(
+                    arena.alloc(identifier(counter.name, span)),
+                    AssignmentOp::Assign,
+                    arena.alloc(sum),
+                ),
+                span,
+            )));
+            remarks::applied(PASS, span, || {
+                format!(
+                    "strength-reduced `{}` to counter `{}`, stepped by {}",
+                    affine_source(&variable, counter.factor, counter.offset),
+                    self.interner.resolve(counter.name),
+                    counter.increment
+                )
+            });
+        }
+        updates.extend(statements);
+        new_num.body.statements = arena.alloc_slice_clone(&updates);
+        declarations
+    }
+
+    /// Replace `expr` or its subexpressions of the form `i * c + d` with
+    /// counters
+    fn reduce(
+        &mut self,
+        expr: &mut Expression<'arena>,
+        induction: &LoopInduction,
+        reduced: &mut Vec<Reduced>,
+        arena: &'arena Bump,
+    ) -> bool {
+        if let Some((factor, offset)) = affine(expr, induction.variable) {
+            let Some(name) = self.counter(factor, offset, induction, reduced) else {
+                return false;
+            };
+            expr.kind = ExpressionKind::Identifier(name);
+            return true;
+        }
+        visit_expr_children(expr, arena, &mut |e, a| {
+            self.reduce(e, induction, reduced, a)
+        })
+    }
+
+    /// The counter holding `i * factor + offset`, declaring one if needed
+    fn counter(
+        &mut self,
+        factor: i64,
+        offset: i64,
+        induction: &LoopInduction,
+        reduced: &mut Vec<Reduced>,
+    ) -> Option<StringId> {
+        if let Some(counter) = reduced
+            .iter()
+            .find(|counter| counter.factor == factor && counter.offset == offset)
+        {
+            return Some(counter.name);
+        }
+        // The counter starts one step early, as the body steps it first
+        let increment = induction.step.checked_mul(factor)?;
+        let initial = induction
+            .start
+            .checked_mul(factor)?
+            .checked_add(offset)?
+            .checked_sub(increment)?;
+        if initial.unsigned_abs() > MAX_EXACT_INTEGER
+            || increment.unsigned_abs() > MAX_EXACT_INTEGER
+        {
+            return None;
+        }
+        let name = self.fresh_name();
+        reduced.push(Reduced {
+            name,
+            factor,
+            offset,
+            initial,
+            increment,
+        });
+        Some(name)
+    }
+
+    /// Make the body use `i` where it used `i + d`, shifting the bounds by `d`
+    fn rebase(
+        &self,
+        induction: &LoopInduction,
+        new_num: &mut ForNumeric<'arena>,
+        arena: &'arena Bump,
+    ) -> bool {
+        let variable = induction.variable;
+        let mut scan = Scan::new(None);
+        scan.block(&new_num.body);
+        let Some(&uses) = scan.referenced.get(&variable) else {
+            return false;
+        };
+
+        let mut offsets = Vec::new();
+        let mut body = new_num.body.clone();
+        visit_block_expressions(&mut body, arena, &mut |expr, arena| {
+            strip_offsets(expr, variable, &mut offsets, arena)
+        });
+        let Some(&offset) = offsets.first() else {
+            return false;
+        };
+        if offsets.len() != uses || offsets.iter().any(|&d| d != offset) {
+            return false;
+        }
+        let Some(start) = induction.start.checked_add(offset) else {
+            return false;
+        };
+
+        new_num.start = integer(start, new_num.start.span);
+        new_num.end = shifted(&new_num.end, offset, arena);
+        new_num.body = body;
+
+        let name = self.interner.resolve(variable);
+        remarks::applied(PASS, new_num.span, || {
+            format!(
+                "rebased loop variable `{}` to start at {}, replacing `{}` with `{}`",
+                name,
+                start,
+                affine_source(&name, 1, offset),
+                name
+            )
+        });
+        true
+    }
+
+    fn fresh_name(&mut self) -> StringId {
+        loop {
+            let id = *self.next_iv_id;
+            *self.next_iv_id += 1;
+            let name = self.interner.get_or_intern(&format!("__iv_{}", id));
+            if self.bound.insert(name) {
+                return name;
+            }
+        }
+    }
+}
+
+/// `(c, d)` when `expr` is `i * c + d`, `c * i + d`, `d + i * c` or
+/// `i * c - d` with integers `c` other than 0 and 1 and `d`, which may be 0
+fn affine(expr: &Expression<'_>, variable: StringId) -> Option<(i64, i64)> {
+    match &expr.kind {
+        ExpressionKind::Binary(BinaryOp::Add, left, right) => {
+            match (product(left, variable), integer_literal(right)) {
+                (Some(factor), Some(offset)) => Some((factor, offset)),
+                _ => Some((product(right, variable)?, integer_literal(left)?)),
+            }
+        }
+        ExpressionKind::Binary(BinaryOp::Subtract, left, right) => Some((
+            product(left, variable)?,
+            integer_literal(right)?.checked_neg()?,
+        )),
+        _ => Some((product(expr, variable)?, 0)),
+    }
+}
+
+/// `c` when `expr` is `i * c` or `c * i` with an integer `c` other than 0 and 1
+fn product(expr: &Expression<'_>, variable: StringId) -> Option<i64> {
+    let ExpressionKind::Binary(BinaryOp::Multiply, left, right) = &expr.kind else {
+        return None;
+    };
+    let factor = if is_identifier(left, variable) {
+        integer_literal(right)?
+    } else if is_identifier(right, variable) {
+        integer_literal(left)?
+    } else {
+        return None;
+    };
+    (factor != 0 && factor != 1).then_some(factor)
+}
+
+/// Replace `i + d`, `d + i` and `i - d` in `expr` with `i`, collecting each `d`
+fn strip_offsets<'arena>(
+    expr: &mut Expression<'arena>,
+    variable: StringId,
+    offsets: &mut Vec<i64>,
+    arena: &'arena Bump,
+) -> bool {
+    let offset = match &expr.kind {
+        ExpressionKind::Binary(BinaryOp::Add, left, right) if is_identifier(left, variable) => {
+            integer_literal(right)
+        }
+        ExpressionKind::Binary(BinaryOp::Add, left, right) if is_identifier(right, variable) => {
+            integer_literal(left)
+        }
+        ExpressionKind::Binary(BinaryOp::Subtract, left, right)
+            if is_identifier(left, variable) =>
+        {
+            integer_literal(right).and_then(i64::checked_neg)
+        }
+        _ => None,
+    };
+    match offset {
+        Some(offset) if offset != 0 => {
+            offsets.push(offset);
+            expr.kind = ExpressionKind::Identifier(variable);
+            true
+        }
+        _ => visit_expr_children(expr, arena, &mut |e, a| {
+            strip_offsets(e, variable, offsets, a)
+        }),
+    }
+}
+
+/// Replace `name` in `expr` with `replacement`, outside closures
+fn replace_identifier<'arena>(
+    expr: &mut Expression<'arena>,
+    name: StringId,
+    replacement: &Expression<'arena>,
+    replaced: &mut usize,
+    arena: &'arena Bump,
+) -> bool {
+    if is_identifier(expr, name) {
+        *expr = replacement.clone();
+        *replaced += 1;
+        return true;
+    }
+    visit_expr_children(expr, arena, &mut |e, a| {
+        replace_identifier(e, name, replacement, replaced, a)
+    })
+}
+
+/// The loop limit `bound` shifted by `offset`, folding it into a literal or
+/// an outer `+`/`-` when there is one
+fn shifted<'arena>(
+    bound: &Expression<'arena>,
+    offset: i64,
+    arena: &'arena Bump,
+) -> Expression<'arena> {
+    match &bound.kind {
+        ExpressionKind::Literal(Literal::Integer(value)) => {
+            if let Some(value) = value.checked_add(offset) {
+                return integer(value, bound.span);
+            }
+        }
+        ExpressionKind::Literal(Literal::Number(value)) => {
+            return Expression::new(
+                ExpressionKind::Literal(Literal::Number(value + offset as f64)),
+                bound.span,
+            );
+        }
+        ExpressionKind::Binary(BinaryOp::Add, left, right) => {
+            if let Some(total) = integer_literal(right).and_then(|d| d.checked_add(offset)) {
+                return offset_by((**left).clone(), total, arena);
+            }
+        }
+        ExpressionKind::Binary(BinaryOp::Subtract, left, right) => {
+            if let Some(total) = integer_literal(right).and_then(|d| offset.checked_sub(d)) {
+                return offset_by((**left).clone(), total, arena);
+            }
+        }
+        _ => {}
+    }
+    offset_by(bound.clone(), offset, arena)
+}
+
+/// `expr + offset`, written with `-` for negative offsets
+fn offset_by<'arena>(
+    expr: Expression<'arena>,
+    offset: i64,
+    arena: &'arena Bump,
+) -> Expression<'arena> {
+    if offset == 0 {
+        return expr;
+    }
+    let span = expr.span;
+    let (op, amount) = match offset.checked_neg() {
+        Some(negated) if offset < 0 => (BinaryOp::Subtract, negated),
+        _ => (BinaryOp::Add, offset),
+    };
+    Expression::new(
+        ExpressionKind::Binary(op, arena.alloc(expr), arena.alloc(integer(amount, span))),
+        span,
+    )
+}
+
+/// How remarks show `name * factor + offset`
+fn affine_source(name: &str, factor: i64, offset: i64) -> String {
+    let product = if factor == 1 {
+        name.to_string()
+    } else {
+        format!("{} * {}", name, factor)
+    };
+    match offset {
+        0 => product,
+        d if d < 0 => format!("{} - {}", product, d.unsigned_abs()),
+        d => format!("{} + {}", product, d),
+    }
+}
+
+fn identifier<'arena>(name: StringId, span: Span) -> Expression<'arena> {
+    Expression::new(ExpressionKind::Identifier(name), span)
+}
+
+fn integer<'arena>(value: i64, span: Span) -> Expression<'arena> {
+    Expression::new(ExpressionKind::Literal(Literal::Integer(value)), span)
+}
+
+fn is_identifier(expr: &Expression<'_>, name: StringId) -> bool {
+    matches!(expr.kind, ExpressionKind::Identifier(id) if id == name)
+}
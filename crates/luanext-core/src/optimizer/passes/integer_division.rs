@@ -0,0 +1,402 @@
+//! Integer division
+//!
+//! Rewrites `math.floor(a / b)` into `a // b` when both operands are integers,
+//! so Lua 5.3 and later divide natively without converting to float and
+//! calling `math.floor`:
+//!
+//! ```lua
+//! -- Before:
+//! local mid = math.floor((lo + hi) / 2)
+//!
+//! -- After:
+//! local mid = (lo + hi) // 2
+//! ```
+//!
+//! Codegen writes `//` back as `math.floor(a / b)` for Lua 5.1, 5.2 and
+//! LuaJIT, so their output is unchanged.
+//!
+//! An operand is an integer when it is an integer literal, a local or
+//! parameter declared `integer`, the variable of a numeric `for` whose start
+//! and step are integers, a length `#t` (assuming no `__len` metamethod), or
+//! `+`, `-`, `*`, `//` or `%` of integers. The divisor must also be a nonzero
+//! constant: `//` raises an error on an integer zero, where
+//! `math.floor(a / 0)` returns an infinity. Beyond 2^53 the float division
+//! rounds, so `//` gives the exact quotient where `math.floor` may be off by
+//! one.
+
+use super::array_iteration::typed_locals;
+use super::memory_effects::{pattern_names, PureCalls, Scan};
+use crate::config::OptimizationLevel;
+use crate::optimizer::{remarks, visit_expr_children, WholeProgramPass};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, Literal, UnaryOp};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{Block, ClassMember, ForStatement, Parameter, Statement};
+use luanext_parser::ast::types::{PrimitiveType, Type, TypeKind};
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::FxHashSet;
+use std::sync::Arc;
+
+const PASS: &str = "integer-division";
+
+/// Floats up to this magnitude convert to integers exactly
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// The integer variables of the function around an expression
+struct Frame {
+    /// Locals and parameters declared once with type `integer`, and the
+    /// variables of the enclosing integer `for` loops
+    integers: FxHashSet<StringId>,
+    /// Names declared once and never assigned, which a numeric `for` keeps
+    /// an integer throughout its body
+    loop_candidates: FxHashSet<StringId>,
+}
+
+impl Frame {
+    fn new(parameters: &[Parameter<'_>], statements: &[Statement<'_>]) -> Self {
+        let mut scan = Scan::new(None);
+        scan.statements(statements);
+        let mut integers = FxHashSet::default();
+
+        for param in parameters {
+            if let (Pattern::Identifier(ident), Some(ty)) = (&param.pattern, &param.type_annotation)
+            {
+                if is_integer_type(ty) && !param.is_rest && !scan.declared.contains_key(&ident.node)
+                {
+                    integers.insert(ident.node);
+                }
+            }
+        }
+        let mut declarations = Vec::new();
+        typed_locals(statements, &mut declarations);
+        for (name, ty, _) in declarations {
+            let shadows_param = parameters.iter().any(|param| {
+                let mut names = Vec::new();
+                pattern_names(&param.pattern, &mut names);
+                names.contains(&name)
+            });
+            if is_integer_type(ty) && scan.declared.get(&name) == Some(&1) && !shadows_param {
+                integers.insert(name);
+            }
+        }
+
+        let loop_candidates = scan
+            .declared
+            .iter()
+            .filter(|&(name, &count)| count == 1 && !scan.written.contains(name))
+            .map(|(name, _)| *name)
+            .collect();
+        Self {
+            integers,
+            loop_candidates,
+        }
+    }
+}
+
+/// Rewrites `math.floor` of an integer division into `//`
+pub struct IntegerDivisionPass {
+    interner: Arc<StringInterner>,
+    pure_calls: PureCalls,
+    math_floor: StringId,
+    frames: Vec<Frame>,
+}
+
+impl IntegerDivisionPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            pure_calls: PureCalls::new(&[], interner.clone()),
+            math_floor: interner.get_or_intern("math.floor"),
+            interner,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for IntegerDivisionPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn min_level(&self) -> OptimizationLevel {
+        OptimizationLevel::Moderate
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        self.pure_calls = PureCalls::new(&program.statements, self.interner.clone());
+        self.frames = vec![Frame::new(&[], &program.statements)];
+
+        let mut changed = false;
+        for stmt in program.statements.iter_mut() {
+            changed |= self.statement(stmt, arena);
+        }
+
+        self.frames.clear();
+        Ok(changed)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl IntegerDivisionPass {
+    fn block<'arena>(&mut self, block: &mut Block<'arena>, arena: &'arena Bump) -> bool {
+        let mut statements = block.statements.to_vec();
+        let mut changed = false;
+        for stmt in &mut statements {
+            changed |= self.statement(stmt, arena);
+        }
+        if changed {
+            block.statements = arena.alloc_slice_clone(&statements);
+        }
+        changed
+    }
+
+    fn function<'arena>(
+        &mut self,
+        parameters: &[Parameter<'arena>],
+        body: &mut Block<'arena>,
+        arena: &'arena Bump,
+    ) -> bool {
+        self.frames.push(Frame::new(parameters, body.statements));
+        let changed = self.block(body, arena);
+        self.frames.pop();
+        changed
+    }
+
+    fn statement<'arena>(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) -> bool {
+        match stmt {
+            Statement::Variable(decl) => self.expression(&mut decl.initializer, arena),
+            Statement::Expression(expr) => self.expression(expr, arena),
+            Statement::Return(ret) => {
+                let mut values = ret.values.to_vec();
+                let mut changed = false;
+                for value in &mut values {
+                    changed |= self.expression(value, arena);
+                }
+                if changed {
+                    ret.values = arena.alloc_slice_clone(&values);
+                }
+                changed
+            }
+            Statement::If(if_stmt) => {
+                let mut changed = self.expression(&mut if_stmt.condition, arena);
+                changed |= self.block(&mut if_stmt.then_block, arena);
+                let mut else_ifs = if_stmt.else_ifs.to_vec();
+                let mut else_ifs_changed = false;
+                for else_if in &mut else_ifs {
+                    else_ifs_changed |= self.expression(&mut else_if.condition, arena);
+                    else_ifs_changed |= self.block(&mut else_if.block, arena);
+                }
+                if else_ifs_changed {
+                    if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+                    changed = true;
+                }
+                if let Some(else_block) = &mut if_stmt.else_block {
+                    changed |= self.block(else_block, arena);
+                }
+                changed
+            }
+            Statement::While(while_stmt) => {
+                let changed = self.expression(&mut while_stmt.condition, arena);
+                self.block(&mut while_stmt.body, arena) || changed
+            }
+            Statement::Repeat(repeat_stmt) => {
+                let changed = self.block(&mut repeat_stmt.body, arena);
+                self.expression(&mut repeat_stmt.until, arena) || changed
+            }
+            Statement::For(for_stmt) => match *for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    let mut new_num = (**for_num).clone();
+                    let mut changed = self.expression(&mut new_num.start, arena);
+                    changed |= self.expression(&mut new_num.end, arena);
+                    if let Some(step) = &mut new_num.step {
+                        changed |= self.expression(step, arena);
+                    }
+
+                    let variable = new_num.variable.node;
+                    let integer_loop = self.frames.last().is_some_and(|frame| {
+                        frame.loop_candidates.contains(&variable)
+                            && self.is_integer(&new_num.start)
+                            && new_num
+                                .step
+                                .as_ref()
+                                .is_none_or(|step| self.is_integer(step))
+                    });
+                    if integer_loop {
+                        if let Some(frame) = self.frames.last_mut() {
+                            frame.integers.insert(variable);
+                        }
+                    }
+                    changed |= self.block(&mut new_num.body, arena);
+                    if integer_loop {
+                        if let Some(frame) = self.frames.last_mut() {
+                            frame.integers.remove(&variable);
+                        }
+                    }
+
+                    if changed {
+                        *stmt = Statement::For(
+                            arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
+                        );
+                    }
+                    changed
+                }
+                ForStatement::Generic(for_gen) => {
+                    let mut new_gen = for_gen.clone();
+                    let mut iterators = new_gen.iterators.to_vec();
+                    let mut changed = false;
+                    for iterator in &mut iterators {
+                        changed |= self.expression(iterator, arena);
+                    }
+                    if changed {
+                        new_gen.iterators = arena.alloc_slice_clone(&iterators);
+                    }
+                    changed |= self.block(&mut new_gen.body, arena);
+                    if changed {
+                        *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    }
+                    changed
+                }
+            },
+            Statement::Block(block) => self.block(block, arena),
+            Statement::Function(func) => self.function(func.parameters, &mut func.body, arena),
+            Statement::Class(class) => {
+                let mut members = class.members.to_vec();
+                let mut changed = false;
+                for member in &mut members {
+                    match member {
+                        ClassMember::Method(method) => {
+                            if let Some(body) = &mut method.body {
+                                changed |= self.function(method.parameters, body, arena);
+                            }
+                        }
+                        ClassMember::Constructor(ctor) => {
+                            changed |= self.function(ctor.parameters, &mut ctor.body, arena);
+                        }
+                        _ => {}
+                    }
+                }
+                if changed {
+                    class.members = arena.alloc_slice_clone(&members);
+                }
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    fn expression<'arena>(&mut self, expr: &mut Expression<'arena>, arena: &'arena Bump) -> bool {
+        let mut changed = visit_expr_children(expr, arena, &mut |e, a| self.expression(e, a));
+        if let Some(division) = self.rewrite(expr, arena) {
+            expr.kind = division;
+            changed = true;
+        }
+        changed
+    }
+
+    /// `a // b` if `expr` is `math.floor(a / b)` with integer operands and a
+    /// nonzero constant divisor
+    fn rewrite<'arena>(
+        &self,
+        expr: &Expression<'arena>,
+        arena: &'arena Bump,
+    ) -> Option<ExpressionKind<'arena>> {
+        let ExpressionKind::Call(callee, arguments, _) = &expr.kind else {
+            return None;
+        };
+        if self.pure_calls.callee(callee) != Some(self.math_floor) {
+            return None;
+        }
+        let [argument] = arguments else {
+            return None;
+        };
+        if argument.is_spread {
+            return None;
+        }
+        let ExpressionKind::Binary(BinaryOp::Divide, dividend, divisor) =
+            &strip_parens(&argument.value).kind
+        else {
+            return None;
+        };
+        if !self.is_integer(dividend) || !self.is_integer(divisor) {
+            return None;
+        }
+        if !is_nonzero_constant(divisor) {
+            remarks::missed(PASS, expr.span, || {
+                "`math.floor` of an integer division kept: the divisor may be zero, \
+                 where `//` raises an error"
+                    .to_string()
+            });
+            return None;
+        }
+
+        remarks::applied(PASS, expr.span, || {
+            "rewrote `math.floor` of an integer division into `//`".to_string()
+        });
+        Some(ExpressionKind::Binary(
+            BinaryOp::IntegerDivide,
+            arena.alloc((**dividend).clone()),
+            arena.alloc((**divisor).clone()),
+        ))
+    }
+
+    /// Whether `expr` always evaluates to an integer
+    fn is_integer(&self, expr: &Expression<'_>) -> bool {
+        match &expr.kind {
+            ExpressionKind::Literal(Literal::Integer(_)) => true,
+            // Codegen writes integral floats without a fraction, so Lua
+            // reads them back as integers
+            ExpressionKind::Literal(Literal::Number(n)) => {
+                n.fract() == 0.0 && n.abs() <= MAX_EXACT_INTEGER
+            }
+            ExpressionKind::Identifier(name) => self
+                .frames
+                .last()
+                .is_some_and(|frame| frame.integers.contains(name)),
+            ExpressionKind::Parenthesized(inner) => self.is_integer(inner),
+            ExpressionKind::Unary(UnaryOp::Negate, operand) => self.is_integer(operand),
+            ExpressionKind::Unary(UnaryOp::Length, _) => true,
+            ExpressionKind::Binary(
+                BinaryOp::Add
+                | BinaryOp::Subtract
+                | BinaryOp::Multiply
+                | BinaryOp::IntegerDivide
+                | BinaryOp::Modulo,
+                left,
+                right,
+            ) => self.is_integer(left) && self.is_integer(right),
+            _ => false,
+        }
+    }
+}
+
+/// Whether `ty` is `integer`
+fn is_integer_type(ty: &Type<'_>) -> bool {
+    match &ty.kind {
+        TypeKind::Primitive(PrimitiveType::Integer) => true,
+        TypeKind::Parenthesized(inner) => is_integer_type(inner),
+        _ => false,
+    }
+}
+
+fn is_nonzero_constant(expr: &Expression<'_>) -> bool {
+    match &strip_parens(expr).kind {
+        ExpressionKind::Literal(Literal::Integer(n)) => *n != 0,
+        ExpressionKind::Literal(Literal::Number(n)) => *n != 0.0,
+        ExpressionKind::Unary(UnaryOp::Negate, operand) => is_nonzero_constant(operand),
+        _ => false,
+    }
+}
+
+fn strip_parens<'a, 'arena>(expr: &'a Expression<'arena>) -> &'a Expression<'arena> {
+    match &expr.kind {
+        ExpressionKind::Parenthesized(inner) => strip_parens(inner),
+        _ => expr,
+    }
+}
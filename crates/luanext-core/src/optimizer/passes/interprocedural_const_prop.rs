@@ -38,7 +38,7 @@
 //   multiply(30)
 
 use crate::config::OptimizationLevel;
-use crate::optimizer::{AstFeatures, WholeProgramPass};
+use crate::optimizer::{remarks, AstFeatures, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{
@@ -50,25 +50,25 @@ use luanext_parser::string_interner::{StringId, StringInterner};
 use rustc_hash::FxHashMap;
 use std::sync::Arc;
 
+const PASS: &str = "interprocedural-const-prop";
+
 /// Maximum fixed-point iterations
 const MAX_ITERATIONS: usize = 3;
 
 pub struct InterproceduralConstPropPass {
-    _interner: Arc<StringInterner>,
+    interner: Arc<StringInterner>,
 }
 
 impl InterproceduralConstPropPass {
     pub fn new(interner: Arc<StringInterner>) -> Self {
-        Self {
-            _interner: interner,
-        }
+        Self { interner }
     }
 }
 
 impl Default for InterproceduralConstPropPass {
     fn default() -> Self {
         Self {
-            _interner: Arc::new(StringInterner::new()),
+            interner: Arc::new(StringInterner::new()),
         }
     }
 }
@@ -150,7 +150,7 @@ impl PropagationContext {
 
 impl<'arena> WholeProgramPass<'arena> for InterproceduralConstPropPass {
     fn name(&self) -> &'static str {
-        "interprocedural-const-prop"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -216,17 +216,27 @@ impl<'arena> WholeProgramPass<'arena> for InterproceduralConstPropPass {
                         // Build substitution map
                         let mut subs: FxHashMap<StringId, ConstValue> = FxHashMap::default();
                         let mut remove_indices: Vec<usize> = Vec::new();
+                        let mut param_names: Vec<String> = Vec::new();
 
                         for (idx, cv) in targets {
                             if *idx < func.parameters.len() {
                                 if let Pattern::Identifier(ident) = &func.parameters[*idx].pattern {
                                     subs.insert(ident.node, cv.clone());
                                     remove_indices.push(*idx);
+                                    param_names
+                                        .push(format!("`{}`", self.interner.resolve(ident.node)));
                                 }
                             }
                         }
 
                         if !subs.is_empty() {
+                            remarks::applied(PASS, func.span, || {
+                                format!(
+                                    "every call to `{}` passes the same constant for {}; removed the parameter(s)",
+                                    self.interner.resolve(func.name.node),
+                                    param_names.join(", ")
+                                )
+                            });
                             // Substitute in body
                             let new_body = substitute_consts_in_block(&func.body, &subs, arena);
                             func.body = new_body;
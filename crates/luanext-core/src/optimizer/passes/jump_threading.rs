@@ -28,11 +28,13 @@
 //! (statement removed entirely)
 //! ```
 
-use crate::optimizer::BlockVisitor;
+use crate::optimizer::{remarks, BlockVisitor};
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{ExpressionKind, Literal};
 use luanext_parser::ast::statement::{Block, ForStatement, IfStatement, Statement};
 
+const PASS: &str = "jump-threading";
+
 /// Jump threading optimization pass.
 ///
 /// Replaces `if` statements with constant conditions by inlining
@@ -76,6 +78,9 @@ impl JumpThreadingPass {
                 Statement::If(if_stmt) => {
                     if let Some(truthy) = Self::is_constant_truthy(&if_stmt.condition.kind) {
                         if truthy {
+                            remarks::applied(PASS, if_stmt.span, || {
+                                "condition is always truthy; kept the then-branch".to_string()
+                            });
                             // Condition is always true: replace with then_block statements
                             let body_stmts: Vec<_> = if_stmt.then_block.statements.to_vec();
                             stmts.splice(i..=i, body_stmts.into_iter());
@@ -84,6 +89,9 @@ impl JumpThreadingPass {
                             continue;
                         } else {
                             // Condition is always false
+                            remarks::applied(PASS, if_stmt.span, || {
+                                "condition is always falsy; removed the then-branch".to_string()
+                            });
                             if !if_stmt.else_ifs.is_empty() {
                                 // Promote first else-if to become the if condition
                                 let first_else_if = &if_stmt.else_ifs[0];
@@ -117,6 +125,9 @@ impl JumpThreadingPass {
                 Statement::While(while_stmt) => {
                     if let Some(false) = Self::is_constant_truthy(&while_stmt.condition.kind) {
                         // while false do ... end → remove entirely
+                        remarks::applied(PASS, while_stmt.span, || {
+                            "loop condition is always falsy; removed the loop".to_string()
+                        });
                         stmts.remove(i);
                         changed = true;
                         continue;
@@ -0,0 +1,742 @@
+//! Lambda lifting
+//!
+//! A closure is created each time the expression defining it runs, so a
+//! local closure inside a function allocates a new closure on every call.
+//! This pass moves the local closures of top-level functions out into
+//! module-level constants, created once:
+//!
+//! ```lua
+//! -- Before:
+//! local function total(a, b)
+//!     local price = function(item) return item.price * item.count end
+//!     return price(a) + price(b)
+//! end
+//!
+//! -- After:
+//! local __lambda_0 = function(item) return item.price * item.count end
+//! local function total(a, b)
+//!     return __lambda_0(a) + __lambda_0(b)
+//! end
+//! ```
+//!
+//! Only closures bound to a local that never escapes are lifted:
+//! `AliasAnalyzer`, tracking the local as it tracks tables, must see it
+//! neither returned, passed to a call nor copied, and each of its uses must
+//! be a direct call the pass rewrites. Lua 5.1 and 5.4 create a distinct
+//! closure every time, so a closure that escapes could be compared or used
+//! as a table key, and sharing it across calls would change the result.
+//!
+//! Every name the closure uses must be its own, a global, a module-level
+//! constant, or a local of the function that is never reassigned. The
+//! module-level constants are those scope hoisting's `EscapeAnalysis` may
+//! move to bundle scope, as their initializers depend on no other module
+//! local. Captured locals become leading parameters, and the calls pass
+//! them:
+//!
+//! ```lua
+//! -- Before:
+//! local function scale(xs, k)
+//!     local by = function(x) return x * k end
+//!     return by(xs[1]), by(xs[2])
+//! end
+//!
+//! -- After:
+//! local __lambda_1 = function(k, x) return x * k end
+//! local function scale(xs, k)
+//!     return __lambda_1(k, xs[1]), __lambda_1(k, xs[2])
+//! end
+//! ```
+//!
+//! The lifted constant goes right before the function, so that names
+//! resolve as before.
+
+use super::memory_effects::{pattern_names, Scan};
+use crate::codegen::scope_hoisting::EscapeAnalysis;
+use crate::config::OptimizationLevel;
+use crate::optimizer::analysis::{AliasAnalyzer, MemoryLocation};
+use crate::optimizer::{
+    remarks, visit_block_expressions, visit_expr_children, visit_stmt_expressions, AstFeatures,
+    WholeProgramPass,
+};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{Argument, ArrowBody, Expression, ExpressionKind};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{
+    Block, ExportKind, ForStatement, ImportClause, Parameter, Statement, VariableDeclaration,
+    VariableKind,
+};
+use luanext_parser::ast::{Program, Spanned};
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::FxHashSet;
+use std::sync::Arc;
+
+const PASS: &str = "lambda-lifting";
+
+/// Lua allows a function 200 locals; lifting leaves the main chunk room
+/// below that
+const MAX_MODULE_LOCALS: usize = 180;
+
+/// The module-level names lifted closures are placed among
+struct Module {
+    /// Names declared at the top level
+    names: FxHashSet<StringId>,
+    /// Module-level constants a lifted closure may use, including the
+    /// closures lifted so far
+    constants: FxHashSet<StringId>,
+    /// Locals the main chunk declares, including lifted closures
+    locals: usize,
+}
+
+/// Lifts closures out of top-level functions into module-level constants
+pub struct LambdaLiftingPass {
+    interner: Arc<StringInterner>,
+    next_lambda_id: usize,
+}
+
+impl LambdaLiftingPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            interner,
+            next_lambda_id: 0,
+        }
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for LambdaLiftingPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn min_level(&self) -> OptimizationLevel {
+        OptimizationLevel::Moderate
+    }
+
+    fn required_features(&self) -> AstFeatures {
+        AstFeatures::HAS_FUNCTIONS
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let view = Program::new(arena.alloc_slice_clone(&program.statements), program.span);
+        let hoistable = EscapeAnalysis::analyze(&view, &self.interner);
+        let names = module_names(&program.statements);
+        let mut module = Module {
+            locals: names.len(),
+            constants: hoistable
+                .variables
+                .iter()
+                .map(|name| self.interner.get_or_intern(name))
+                .collect(),
+            names,
+        };
+
+        let mut changed = false;
+        let mut statements = Vec::with_capacity(program.statements.len());
+        for mut stmt in std::mem::take(&mut program.statements) {
+            let lifted = self.top_level(&mut stmt, &mut module, arena);
+            changed |= !lifted.is_empty();
+            statements.extend(lifted);
+            statements.push(stmt);
+        }
+        program.statements = statements;
+        Ok(changed)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl LambdaLiftingPass {
+    /// Lift the closures of `stmt` when it declares a function, returning
+    /// the declarations that go before it
+    fn top_level<'arena>(
+        &mut self,
+        stmt: &mut Statement<'arena>,
+        module: &mut Module,
+        arena: &'arena Bump,
+    ) -> Vec<Statement<'arena>> {
+        match stmt {
+            Statement::Function(_) => {
+                let mut function = Scan::new(None);
+                function.statement(stmt);
+                let Statement::Function(func) = stmt else {
+                    unreachable!()
+                };
+                if function.opaque {
+                    return Vec::new();
+                }
+                let mut visible = Vec::new();
+                for param in func.parameters.iter() {
+                    pattern_names(&param.pattern, &mut visible);
+                }
+                let mut lifter = Lifter {
+                    interner: &self.interner,
+                    next_lambda_id: &mut self.next_lambda_id,
+                    module,
+                    function,
+                    visible,
+                    lifted: Vec::new(),
+                };
+                lifter.block(&mut func.body, arena);
+                lifter.lifted
+            }
+            Statement::Export(export) => {
+                let ExportKind::Declaration(inner) = export.kind else {
+                    return Vec::new();
+                };
+                let mut new_inner = inner.clone();
+                let lifted = self.top_level(&mut new_inner, module, arena);
+                if !lifted.is_empty() {
+                    export.kind = ExportKind::Declaration(arena.alloc(new_inner));
+                }
+                lifted
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Lifts the closures of one top-level function
+struct Lifter<'p, 'arena> {
+    interner: &'p StringInterner,
+    next_lambda_id: &'p mut usize,
+    module: &'p mut Module,
+    /// The function as it was before lifting
+    function: Scan<'static>,
+    /// Locals in scope at the statement being visited
+    visible: Vec<StringId>,
+    /// Declarations of the lifted closures, in order
+    lifted: Vec<Statement<'arena>>,
+}
+
+/// A closure bound to a local, lifted with its captures as parameters
+struct Lifted {
+    local: StringId,
+    lambda: StringId,
+    captures: Vec<StringId>,
+}
+
+impl<'arena> Lifter<'_, 'arena> {
+    fn block(&mut self, block: &mut Block<'arena>, arena: &'arena Bump) -> bool {
+        let depth = self.visible.len();
+        let mut statements = block.statements.to_vec();
+        let mut changed = false;
+        let mut i = 0;
+        while i < statements.len() {
+            changed |= self.statement(&mut statements[i], arena);
+            if self.lift_local(&mut statements, i, arena) {
+                changed = true;
+                continue;
+            }
+            match &statements[i] {
+                Statement::Variable(decl) => pattern_names(&decl.pattern, &mut self.visible),
+                Statement::Function(func) => self.visible.push(func.name.node),
+                _ => {}
+            }
+            i += 1;
+        }
+        self.visible.truncate(depth);
+        if changed {
+            block.statements = arena.alloc_slice_clone(&statements);
+        }
+        changed
+    }
+
+    /// Visit a function body with `parameters` in scope
+    fn body(
+        &mut self,
+        parameters: &[Parameter<'arena>],
+        body: &mut Block<'arena>,
+        arena: &'arena Bump,
+    ) -> bool {
+        let depth = self.visible.len();
+        for param in parameters {
+            pattern_names(&param.pattern, &mut self.visible);
+        }
+        let changed = self.block(body, arena);
+        self.visible.truncate(depth);
+        changed
+    }
+
+    fn statement(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) -> bool {
+        match stmt {
+            Statement::Variable(decl) => self.expression(&mut decl.initializer, arena),
+            Statement::Expression(expr) => self.expression(expr, arena),
+            Statement::Return(ret) => {
+                let mut values = ret.values.to_vec();
+                let mut changed = false;
+                for value in &mut values {
+                    changed |= self.expression(value, arena);
+                }
+                if changed {
+                    ret.values = arena.alloc_slice_clone(&values);
+                }
+                changed
+            }
+            Statement::If(if_stmt) => {
+                let mut changed = self.expression(&mut if_stmt.condition, arena);
+                changed |= self.block(&mut if_stmt.then_block, arena);
+                let mut else_ifs = if_stmt.else_ifs.to_vec();
+                let mut else_ifs_changed = false;
+                for else_if in &mut else_ifs {
+                    else_ifs_changed |= self.expression(&mut else_if.condition, arena);
+                    else_ifs_changed |= self.block(&mut else_if.block, arena);
+                }
+                if else_ifs_changed {
+                    if_stmt.else_ifs = arena.alloc_slice_clone(&else_ifs);
+                    changed = true;
+                }
+                if let Some(else_block) = &mut if_stmt.else_block {
+                    changed |= self.block(else_block, arena);
+                }
+                changed
+            }
+            Statement::While(while_stmt) => {
+                let changed = self.expression(&mut while_stmt.condition, arena);
+                self.block(&mut while_stmt.body, arena) || changed
+            }
+            Statement::Repeat(repeat_stmt) => {
+                let changed = self.block(&mut repeat_stmt.body, arena);
+                self.expression(&mut repeat_stmt.until, arena) || changed
+            }
+            Statement::For(for_stmt) => match *for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    let mut new_num = (**for_num).clone();
+                    let mut changed = self.expression(&mut new_num.start, arena);
+                    changed |= self.expression(&mut new_num.end, arena);
+                    if let Some(step) = &mut new_num.step {
+                        changed |= self.expression(step, arena);
+                    }
+                    let depth = self.visible.len();
+                    self.visible.push(new_num.variable.node);
+                    changed |= self.block(&mut new_num.body, arena);
+                    self.visible.truncate(depth);
+                    if changed {
+                        *stmt = Statement::For(
+                            arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
+                        );
+                    }
+                    changed
+                }
+                ForStatement::Generic(for_gen) => {
+                    let mut new_gen = for_gen.clone();
+                    let mut iterators = new_gen.iterators.to_vec();
+                    let mut changed = false;
+                    for iterator in &mut iterators {
+                        changed |= self.expression(iterator, arena);
+                    }
+                    if changed {
+                        new_gen.iterators = arena.alloc_slice_clone(&iterators);
+                    }
+                    let depth = self.visible.len();
+                    self.visible
+                        .extend(new_gen.variables.iter().map(|var| var.node));
+                    if let Some(pattern) = &new_gen.pattern {
+                        pattern_names(pattern, &mut self.visible);
+                    }
+                    changed |= self.block(&mut new_gen.body, arena);
+                    self.visible.truncate(depth);
+                    if changed {
+                        *stmt = Statement::For(arena.alloc(ForStatement::Generic(new_gen)));
+                    }
+                    changed
+                }
+            },
+            Statement::Block(block) => self.block(block, arena),
+            Statement::Function(func) => {
+                let depth = self.visible.len();
+                self.visible.push(func.name.node);
+                let changed = self.body(func.parameters, &mut func.body, arena);
+                self.visible.truncate(depth);
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    /// Visit the bodies of the closures in `expr`, lifting the local
+    /// closures they declare
+    fn expression(&mut self, expr: &mut Expression<'arena>, arena: &'arena Bump) -> bool {
+        match &expr.kind {
+            ExpressionKind::Function(func) => {
+                let mut new_func = func.clone();
+                let changed = self.body(func.parameters, &mut new_func.body, arena);
+                if changed {
+                    expr.kind = ExpressionKind::Function(new_func);
+                }
+                changed
+            }
+            ExpressionKind::Arrow(arrow) => match &arrow.body {
+                ArrowBody::Expression(body) => {
+                    let depth = self.visible.len();
+                    for param in arrow.parameters.iter() {
+                        pattern_names(&param.pattern, &mut self.visible);
+                    }
+                    let mut new_body = (**body).clone();
+                    let changed = self.expression(&mut new_body, arena);
+                    self.visible.truncate(depth);
+                    if changed {
+                        let mut new_arrow = arrow.clone();
+                        new_arrow.body = ArrowBody::Expression(arena.alloc(new_body));
+                        expr.kind = ExpressionKind::Arrow(new_arrow);
+                    }
+                    changed
+                }
+                ArrowBody::Block(block) => {
+                    let mut new_block = block.clone();
+                    let changed = self.body(arrow.parameters, &mut new_block, arena);
+                    if changed {
+                        let mut new_arrow = arrow.clone();
+                        new_arrow.body = ArrowBody::Block(new_block);
+                        expr.kind = ExpressionKind::Arrow(new_arrow);
+                    }
+                    changed
+                }
+            },
+            _ => visit_expr_children(expr, arena, &mut |e, a| self.expression(e, a)),
+        }
+    }
+
+    /// The locals of the function that the closure scanned into `closure`
+    /// captures, sorted by name, or why it cannot be lifted
+    fn captures(&self, closure: &Scan<'_>) -> Result<Vec<StringId>, String> {
+        if closure.opaque || closure.uses_self {
+            return Err("uses constructs lambda lifting does not model".to_string());
+        }
+        let mut captures = Vec::new();
+        for &name in &closure.captured {
+            let inside = closure.declared.get(&name).copied().unwrap_or(0);
+            let everywhere = self.function.declared.get(&name).copied().unwrap_or(0);
+            if everywhere > inside {
+                captures.push(name);
+            } else if inside == 0
+                && self.module.names.contains(&name)
+                && !self.module.constants.contains(&name)
+            {
+                return Err(format!(
+                    "uses `{}`, which is not a module-level constant",
+                    self.interner.resolve(name)
+                ));
+            }
+        }
+        captures.sort_by_key(|&name| self.interner.resolve(name));
+        Ok(captures)
+    }
+
+    /// A fresh `__lambda_N` name, unless the main chunk has no room for
+    /// another local
+    fn lambda(&mut self, span: Span) -> Option<StringId> {
+        if self.module.locals >= MAX_MODULE_LOCALS {
+            remarks::missed(PASS, span, || {
+                format!(
+                    "closure kept: the module already declares {} locals",
+                    self.module.locals
+                )
+            });
+            return None;
+        }
+        loop {
+            let id = *self.next_lambda_id;
+            *self.next_lambda_id += 1;
+            let name = self.interner.get_or_intern(&format!("__lambda_{}", id));
+            if !self.module.names.contains(&name) {
+                return Some(name);
+            }
+        }
+    }
+
+    /// Declare `closure` as the module-level constant `lambda`
+    fn declare(&mut self, lambda: StringId, closure: Expression<'arena>) {
+        let span = closure.span;
+        self.lifted.push(Statement::Variable(VariableDeclaration {
+            kind: VariableKind::Const,
+            pattern: Pattern::Identifier(Spanned::new(lambda, span)),
+            type_annotation: None,
+            initializer: closure,
+            span,
+        }));
+        self.module.constants.insert(lambda);
+        self.module.locals += 1;
+    }
+
+    /// Lift the closure `statements[i]` binds to a local when the local does
+    /// not escape and the closure captures only locals that are never
+    /// reassigned, passing them at its calls in the rest of the block.
+    /// Removes the declaration when it does.
+    fn lift_local(
+        &mut self,
+        statements: &mut Vec<Statement<'arena>>,
+        i: usize,
+        arena: &'arena Bump,
+    ) -> bool {
+        let Statement::Variable(decl) = &statements[i] else {
+            return false;
+        };
+        let Pattern::Identifier(binding) = &decl.pattern else {
+            return false;
+        };
+        if !matches!(decl.kind, VariableKind::Local | VariableKind::Const)
+            || !matches!(
+                decl.initializer.kind,
+                ExpressionKind::Function(_) | ExpressionKind::Arrow(_)
+            )
+        {
+            return false;
+        }
+        let local = binding.node;
+        let span = decl.span;
+        let name = self.interner.resolve(local);
+
+        let mut closure = Scan::new(None);
+        closure.expression(&decl.initializer);
+        let captures = match self.captures(&closure) {
+            Ok(captures) => captures,
+            Err(reason) => {
+                remarks::missed(PASS, span, || format!("closure `{}` {}", name, reason));
+                return false;
+            }
+        };
+        if captures.contains(&local)
+            || self.function.declared.get(&local) != Some(&1)
+            || self.function.written.contains(&local)
+        {
+            return false;
+        }
+        for &capture in &captures {
+            let reason = if self.function.written.contains(&capture) {
+                "is reassigned"
+            } else if self.function.declared.get(&capture) != Some(&1)
+                || !self.visible.contains(&capture)
+            {
+                "is not declared once before the closure"
+            } else {
+                continue;
+            };
+            remarks::missed(PASS, span, || {
+                format!(
+                    "closure `{}` captures `{}`, which {}",
+                    name,
+                    self.interner.resolve(capture),
+                    reason
+                )
+            });
+            return false;
+        }
+
+        let alias = AliasAnalyzer::new()
+            .with_reference_variables([local])
+            .analyze(&statements[i..]);
+        let copied = alias
+            .aliases_of(&MemoryLocation::Local(local))
+            .iter()
+            .any(|loc| **loc != MemoryLocation::Local(local));
+        if alias.has_escaped(local) || copied {
+            remarks::missed(PASS, span, || {
+                format!(
+                    "closure `{}` escapes, so its calls cannot be rewritten",
+                    name
+                )
+            });
+            return false;
+        }
+
+        let mut rest = Scan::new(None);
+        rest.statements(&statements[i + 1..]);
+        let uses = rest.referenced.get(&local).copied().unwrap_or(0);
+        if uses == 0 {
+            return false;
+        }
+        let Some(lambda) = self.lambda(span) else {
+            return false;
+        };
+        let lifted = Lifted {
+            local,
+            lambda,
+            captures,
+        };
+        let mut rewritten = statements[i + 1..].to_vec();
+        let mut calls = 0;
+        for stmt in &mut rewritten {
+            visit_stmt_expressions(stmt, arena, &mut |e, a| {
+                rewrite_calls(e, &lifted, &mut calls, a)
+            });
+        }
+        if calls != uses {
+            remarks::missed(PASS, span, || {
+                format!("closure `{}` is used other than by direct calls", name)
+            });
+            return false;
+        }
+
+        let mut initializer = decl.initializer.clone();
+        let parameters: Vec<Parameter<'arena>> = lifted
+            .captures
+            .iter()
+            .map(|&capture| Parameter {
+                pattern: Pattern::Identifier(Spanned::new(capture, span)),
+                type_annotation: None,
+                default: None,
+                is_rest: false,
+                is_optional: false,
+                span,
+            })
+            .chain(closure_parameters(&initializer).iter().cloned())
+            .collect();
+        let parameters = arena.alloc_slice_clone(&parameters);
+        match &mut initializer.kind {
+            ExpressionKind::Function(func) => func.parameters = parameters,
+            ExpressionKind::Arrow(arrow) => arrow.parameters = parameters,
+            _ => unreachable!(),
+        }
+        self.declare(lambda, initializer);
+        remarks::applied(PASS, span, || {
+            let captures: Vec<String> = lifted
+                .captures
+                .iter()
+                .map(|&capture| format!("`{}`", self.interner.resolve(capture)))
+                .collect();
+            let lifted = format!(
+                "lifted closure `{}` into `{}`",
+                name,
+                self.interner.resolve(lambda)
+            );
+            if captures.is_empty() {
+                lifted
+            } else {
+                format!("{}, passing {} at its calls", lifted, captures.join(", "))
+            }
+        });
+        statements.truncate(i);
+        statements.extend(rewritten);
+        true
+    }
+}
+
+/// Rewrite the direct calls of `lifted.local` in `expr` into calls of the
+/// lifted closure, counting them in `calls`
+fn rewrite_calls<'arena>(
+    expr: &mut Expression<'arena>,
+    lifted: &Lifted,
+    calls: &mut usize,
+    arena: &'arena Bump,
+) -> bool {
+    match &expr.kind {
+        ExpressionKind::Call(callee, arguments, type_args) if matches!(callee.kind, ExpressionKind::Identifier(name) if name == lifted.local) =>
+        {
+            let type_args = *type_args;
+            let span = callee.span;
+            let mut new_arguments: Vec<Argument<'arena>> = lifted
+                .captures
+                .iter()
+                .map(|&capture| Argument {
+                    value: Expression::new(ExpressionKind::Identifier(capture), span),
+                    is_spread: false,
+                    span,
+                })
+                .collect();
+            for argument in arguments.iter() {
+                let mut argument = argument.clone();
+                rewrite_calls(&mut argument.value, lifted, calls, arena);
+                new_arguments.push(argument);
+            }
+            expr.kind = ExpressionKind::Call(
+                arena.alloc(Expression::new(
+                    ExpressionKind::Identifier(lifted.lambda),
+                    span,
+                )),
+                arena.alloc_slice_clone(&new_arguments),
+                type_args,
+            );
+            *calls += 1;
+            true
+        }
+        // The piped value becomes the first argument, ahead of the captures
+        ExpressionKind::Pipe(..) => false,
+        ExpressionKind::Function(func) => {
+            let mut new_func = func.clone();
+            let changed = visit_block_expressions(&mut new_func.body, arena, &mut |e, a| {
+                rewrite_calls(e, lifted, calls, a)
+            });
+            if changed {
+                expr.kind = ExpressionKind::Function(new_func);
+            }
+            changed
+        }
+        ExpressionKind::Arrow(arrow) => {
+            let mut new_arrow = arrow.clone();
+            let changed = match &mut new_arrow.body {
+                ArrowBody::Expression(body) => {
+                    let mut new_body = (**body).clone();
+                    let changed = rewrite_calls(&mut new_body, lifted, calls, arena);
+                    *body = arena.alloc(new_body);
+                    changed
+                }
+                ArrowBody::Block(block) => visit_block_expressions(block, arena, &mut |e, a| {
+                    rewrite_calls(e, lifted, calls, a)
+                }),
+            };
+            if changed {
+                expr.kind = ExpressionKind::Arrow(new_arrow);
+            }
+            changed
+        }
+        _ => visit_expr_children(expr, arena, &mut |e, a| rewrite_calls(e, lifted, calls, a)),
+    }
+}
+
+fn closure_parameters<'e, 'arena>(closure: &'e Expression<'arena>) -> &'e [Parameter<'arena>] {
+    match &closure.kind {
+        ExpressionKind::Function(func) => func.parameters,
+        ExpressionKind::Arrow(arrow) => arrow.parameters,
+        _ => &[],
+    }
+}
+
+/// Names the top-level statements declare
+fn module_names(statements: &[Statement<'_>]) -> FxHashSet<StringId> {
+    let mut names = Vec::new();
+    for stmt in statements {
+        declared_names(stmt, &mut names);
+    }
+    names.into_iter().collect()
+}
+
+fn declared_names(stmt: &Statement<'_>, names: &mut Vec<StringId>) {
+    match stmt {
+        Statement::Variable(decl) if !matches!(decl.kind, VariableKind::Global) => {
+            pattern_names(&decl.pattern, names)
+        }
+        Statement::Function(func) => names.push(func.name.node),
+        Statement::Class(class_decl) => names.push(class_decl.name.node),
+        Statement::Enum(enum_decl) => names.push(enum_decl.name.node),
+        Statement::Import(import) => match &import.clause {
+            ImportClause::Named(specifiers) => names.extend(
+                specifiers
+                    .iter()
+                    .map(|spec| spec.local.as_ref().unwrap_or(&spec.imported).node),
+            ),
+            ImportClause::Default(ident) | ImportClause::Namespace(ident) => names.push(ident.node),
+            ImportClause::Mixed { default, named } => {
+                names.push(default.node);
+                names.extend(
+                    named
+                        .iter()
+                        .map(|spec| spec.local.as_ref().unwrap_or(&spec.imported).node),
+                );
+            }
+            ImportClause::TypeOnly(_) => {}
+        },
+        Statement::Export(export) => {
+            if let ExportKind::Declaration(inner) = export.kind {
+                declared_names(inner, names);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,549 @@
+//! Expression-level loop-invariant code motion for `LoopOptimizationPass`
+//!
+//! Hoists the invariant sub-expressions of a loop body, such as arithmetic on
+//! values the loop never changes and table reads the loop never writes, into
+//! fresh locals computed once before the loop:
+//!
+//! ```lua
+//! -- Before:
+//! for i = 1, #self.bodies do
+//!     local body = self.bodies[i]
+//!     body.vy = body.vy + self.config.gravity * dt
+//! end
+//!
+//! -- After:
+//! local __licm_0 = nil
+//! if 1 <= #self.bodies then
+//!     __licm_0 = self.config.gravity * dt
+//! end
+//! for i = 1, #self.bodies do
+//!     local body = self.bodies[i]
+//!     body.vy = body.vy + __licm_0
+//! end
+//! ```
+//!
+//! An expression is invariant when no variable it uses is declared or
+//! assigned in the loop, and:
+//! - for a table read (`t.x`, `t[k]`, `#t`), the loop makes no call that may
+//!   write memory and no store that may write the same field. A store to
+//!   another local table does not count when `AliasAnalyzer` proves the two
+//!   tables distinct
+//! - for a call, `SideEffectAnalyzer` marks the callee pure and the loop
+//!   stores nothing. A call to a user function also needs the loop to assign
+//!   no variable the function could read
+//!
+//! A variable other than an unassigned parameter also needs the loop to make
+//! no call that may write memory, as such a call may assign a global or a
+//! captured local.
+//!
+//! Only expressions every iteration evaluates are hoisted: those of a `while`
+//! condition and of the statements at the start of the body, up to the first
+//! branch, loop or call statement, leaving out the right side of `and`/`or`
+//! and the arms of conditional expressions. They are computed before the loop
+//! only if its body runs at least once, which is tested by repeating the loop
+//! bounds or condition when these have no side effects. Generic `for` loops
+//! are left alone, as nothing tells whether they iterate.
+//!
+//! An error a hoisted expression raises, such as indexing `nil`, is raised
+//! before the loop instead of in its first iteration. Metamethods are assumed
+//! to have no side effects, as in global value numbering.
+
+use super::memory_effects::{pattern_names, variable_name, LocalTables, PureCalls, Scan, Store};
+use crate::optimizer::remarks;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{
+    Argument, AssignmentOp, BinaryOp, Expression, ExpressionKind, Literal, UnaryOp,
+};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{
+    Block, IfStatement, Parameter, Statement, VariableDeclaration, VariableKind,
+};
+use luanext_parser::ast::Spanned;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::FxHashSet;
+
+const PASS: &str = "loop-optimization";
+
+/// What the function around a loop tells about its names and tables
+pub(super) struct Frame {
+    /// Parameters the function never assigns or shadows
+    parameters: FxHashSet<StringId>,
+    tables: LocalTables,
+}
+
+impl Frame {
+    pub(super) fn new(
+        parameters: &[Parameter<'_>],
+        statements: &[Statement<'_>],
+        pure_calls: &PureCalls,
+    ) -> Self {
+        let mut scan = Scan::new(Some(pure_calls));
+        scan.statements(statements);
+
+        let mut names = Vec::new();
+        if !scan.opaque {
+            for param in parameters {
+                pattern_names(&param.pattern, &mut names);
+            }
+        }
+        let parameters = names
+            .into_iter()
+            .filter(|name| !scan.written.contains(name) && !scan.declared.contains_key(name))
+            .collect();
+        Self {
+            parameters,
+            tables: LocalTables::new(statements, &scan),
+        }
+    }
+}
+
+/// Whether the body of a loop runs at least once
+pub(super) enum Entry<'arena> {
+    Always,
+    /// When the expression, built from the loop bounds, is true
+    When(Expression<'arena>),
+    Unknown,
+}
+
+/// What a loop evaluates besides its body
+pub(super) enum Header<'h, 'arena> {
+    /// `for variable = start, end, step`
+    Numeric {
+        variable: StringId,
+        entry: Entry<'arena>,
+    },
+    /// The condition of a `while`, evaluated before every iteration
+    While(&'h mut Expression<'arena>),
+    /// The condition of a `repeat`, evaluated after every iteration
+    Repeat(&'h Expression<'arena>),
+}
+
+/// Expression motion for the loops of one function
+pub(super) struct Motion<'p> {
+    pub(super) pure_calls: &'p PureCalls,
+    pub(super) frame: &'p Frame,
+    pub(super) interner: &'p StringInterner,
+    pub(super) next_temp_id: &'p mut usize,
+}
+
+impl<'p> Motion<'p> {
+    /// Hoist the invariant expressions of a loop, rewriting its condition and
+    /// `body` to use them. Returns the statements computing them, which go
+    /// right before the loop.
+    pub(super) fn hoist<'arena>(
+        self,
+        header: Header<'_, 'arena>,
+        body: &mut Block<'arena>,
+        arena: &'arena Bump,
+    ) -> Vec<Statement<'arena>> {
+        let mut scan = Scan::new(Some(self.pure_calls));
+        match &header {
+            Header::While(condition) => scan.expression(condition),
+            Header::Repeat(until) => scan.expression(until),
+            Header::Numeric { .. } => {}
+        }
+        scan.block(body);
+        if scan.opaque || scan.jumps {
+            return Vec::new();
+        }
+
+        let mut variant: FxHashSet<StringId> = scan
+            .written
+            .iter()
+            .chain(scan.declared.keys())
+            .copied()
+            .collect();
+        if let Header::Numeric { variable, .. } = &header {
+            variant.insert(*variable);
+        }
+        let mut invariants = Invariants {
+            motion: self,
+            scan,
+            variant,
+            hoisted: Vec::new(),
+            changes: 0,
+        };
+
+        // Whether the body is hoisted from, and the test guarding it
+        let guard = match header {
+            Header::Numeric { entry, .. } => match entry {
+                Entry::Always => Some(None),
+                Entry::When(test) => invariants.has_no_effects(&test).then_some(Some(test)),
+                Entry::Unknown => None,
+            },
+            Header::While(condition) => {
+                invariants.expression(condition, false, arena);
+                match &condition.kind {
+                    ExpressionKind::Literal(Literal::Boolean(true)) => Some(None),
+                    _ if invariants.has_no_effects(condition) => Some(Some(condition.clone())),
+                    _ => None,
+                }
+            }
+            Header::Repeat(_) => Some(None),
+        };
+        let unguarded = invariants.hoisted.len();
+
+        if guard.is_some() {
+            let mut statements = body.statements.to_vec();
+            let before = invariants.changes;
+            for stmt in &mut statements {
+                if !invariants.statement(stmt, arena) {
+                    break;
+                }
+            }
+            if invariants.changes != before {
+                body.statements = arena.alloc_slice_clone(&statements);
+            }
+        }
+
+        let mut hoisted = invariants.hoisted;
+        let in_body = hoisted.split_off(unguarded);
+        let mut statements: Vec<Statement<'arena>> = hoisted
+            .into_iter()
+            .map(|(_, temp, value)| declare(temp, value))
+            .collect();
+        match guard {
+            Some(Some(test)) if !in_body.is_empty() => {
+                let mut assignments = Vec::new();
+                for (_, temp, value) in in_body {
+                    let nil = Expression::new(ExpressionKind::Literal(Literal::Nil), value.span);
+                    statements.push(declare(temp, nil));
+                    assignments.push(assign(temp, value, arena));
+                }
+                statements.push(Statement::If(IfStatement {
+                    span: test.span,
+                    then_block: Block {
+                        statements: arena.alloc_slice_clone(&assignments),
+                        span: test.span,
+                    },
+                    condition: test,
+                    else_ifs: &[],
+                    else_block: None,
+                }));
+            }
+            Some(None) => {
+                statements.extend(
+                    in_body
+                        .into_iter()
+                        .map(|(_, temp, value)| declare(temp, value)),
+                );
+            }
+            _ => {}
+        }
+        statements
+    }
+}
+
+/// The invariant expressions of one loop
+struct Invariants<'p, 'arena> {
+    motion: Motion<'p>,
+    /// Effects of the loop
+    scan: Scan<'p>,
+    /// Names the loop declares or assigns
+    variant: FxHashSet<StringId>,
+    /// Each hoisted expression by key, with the local holding its value
+    hoisted: Vec<(String, StringId, Expression<'arena>)>,
+    changes: usize,
+}
+
+impl<'p, 'arena> Invariants<'p, 'arena> {
+    /// Hoist from a statement at the start of the body. Returns false when
+    /// the statements after it may not run.
+    fn statement(&mut self, stmt: &mut Statement<'arena>, arena: &'arena Bump) -> bool {
+        match stmt {
+            Statement::Variable(decl) => {
+                // A call in a destructuring declaration passes on all of its
+                // results
+                let many = !matches!(decl.pattern, Pattern::Identifier(_));
+                self.expression(&mut decl.initializer, many, arena);
+                true
+            }
+            Statement::Expression(expr) => match &mut expr.kind {
+                ExpressionKind::Assignment(target, _, value) => {
+                    self.target(target, arena);
+                    self.operand(value, false, arena);
+                    true
+                }
+                // The call may raise an error or never return
+                ExpressionKind::Call(callee, arguments, _)
+                | ExpressionKind::MethodCall(callee, _, arguments, _) => {
+                    self.operand(callee, false, arena);
+                    self.arguments(arguments, arena);
+                    false
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Hoist from the table and key an assignment stores into
+    fn target(&mut self, target: &mut &'arena Expression<'arena>, arena: &'arena Bump) {
+        let mut new_target = (**target).clone();
+        let before = self.changes;
+        match &mut new_target.kind {
+            ExpressionKind::Member(object, _) => self.operand(object, false, arena),
+            ExpressionKind::Index(object, index) => {
+                self.operand(object, false, arena);
+                self.operand(index, false, arena);
+            }
+            _ => {}
+        }
+        if self.changes != before {
+            *target = arena.alloc(new_target);
+        }
+    }
+
+    fn operand(
+        &mut self,
+        operand: &mut &'arena Expression<'arena>,
+        many: bool,
+        arena: &'arena Bump,
+    ) {
+        let mut new_operand = (**operand).clone();
+        let before = self.changes;
+        self.expression(&mut new_operand, many, arena);
+        if self.changes != before {
+            *operand = arena.alloc(new_operand);
+        }
+    }
+
+    fn arguments(&mut self, arguments: &mut &'arena [Argument<'arena>], arena: &'arena Bump) {
+        let mut new_arguments = arguments.to_vec();
+        let before = self.changes;
+        let last = new_arguments.len().saturating_sub(1);
+        for (i, argument) in new_arguments.iter_mut().enumerate() {
+            self.expression(&mut argument.value, i == last, arena);
+        }
+        if self.changes != before {
+            *arguments = arena.alloc_slice_clone(&new_arguments);
+        }
+    }
+
+    /// Replace the largest invariant parts of `expr` that every evaluation of
+    /// it evaluates with locals. `many` is set where a call passes on all of
+    /// its results, so it must not be replaced.
+    fn expression(&mut self, expr: &mut Expression<'arena>, many: bool, arena: &'arena Bump) {
+        let call = matches!(expr.kind, ExpressionKind::Call(..));
+        if self.is_worth_hoisting(expr) && !(many && call) {
+            let temp = self.temp_for(expr);
+            *expr = Expression {
+                kind: ExpressionKind::Identifier(temp),
+                span: expr.span,
+                annotated_type: expr.annotated_type.clone(),
+                receiver_class: None,
+            };
+            self.changes += 1;
+            return;
+        }
+
+        match &mut expr.kind {
+            // The right side is evaluated only for some values of the left
+            ExpressionKind::Binary(
+                BinaryOp::And | BinaryOp::Or | BinaryOp::NullCoalesce,
+                left,
+                _,
+            )
+            | ExpressionKind::Conditional(left, _, _) => self.operand(left, false, arena),
+            ExpressionKind::Binary(_, left, right) | ExpressionKind::Index(left, right) => {
+                self.operand(left, false, arena);
+                self.operand(right, false, arena);
+            }
+            ExpressionKind::TypeAssertion(operand, _) => self.operand(operand, many, arena),
+            ExpressionKind::Unary(_, operand)
+            | ExpressionKind::Parenthesized(operand)
+            | ExpressionKind::Member(operand, _) => self.operand(operand, false, arena),
+            ExpressionKind::Call(callee, arguments, _)
+            | ExpressionKind::MethodCall(callee, _, arguments, _) => {
+                self.operand(callee, false, arena);
+                self.arguments(arguments, arena);
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether `expr` is invariant and computes something worth a local
+    fn is_worth_hoisting(&self, expr: &Expression<'_>) -> bool {
+        match &expr.kind {
+            ExpressionKind::Literal(_)
+            | ExpressionKind::Identifier(_)
+            | ExpressionKind::SelfKeyword
+            | ExpressionKind::Parenthesized(_)
+            | ExpressionKind::TypeAssertion(..) => false,
+            _ => !is_constant(expr) && self.is_invariant(expr),
+        }
+    }
+
+    fn is_invariant(&self, expr: &Expression<'_>) -> bool {
+        match &expr.kind {
+            ExpressionKind::Literal(_) => true,
+            ExpressionKind::Identifier(name) => {
+                !self.variant.contains(name)
+                    && (!self.scan.clobbers || self.motion.frame.parameters.contains(name))
+            }
+            ExpressionKind::SelfKeyword => !self.scan.clobbers,
+            ExpressionKind::Parenthesized(operand) | ExpressionKind::TypeAssertion(operand, _) => {
+                self.is_invariant(operand)
+            }
+            ExpressionKind::Unary(UnaryOp::Length, operand) => {
+                self.is_invariant(operand)
+                    && self.is_unwritten(Store {
+                        table: variable_name(operand),
+                        field: None,
+                    })
+            }
+            ExpressionKind::Unary(_, operand) => self.is_invariant(operand),
+            ExpressionKind::Binary(_, left, right) => {
+                self.is_invariant(left) && self.is_invariant(right)
+            }
+            ExpressionKind::Member(object, field) => {
+                self.is_invariant(object)
+                    && self.is_unwritten(Store {
+                        table: variable_name(object),
+                        field: Some(field.node),
+                    })
+            }
+            ExpressionKind::Index(object, index) => {
+                self.is_invariant(object)
+                    && self.is_invariant(index)
+                    && self.is_unwritten(Store {
+                        table: variable_name(object),
+                        field: None,
+                    })
+            }
+            ExpressionKind::Call(callee, arguments, _) => {
+                let Some(function) = self.motion.pure_calls.callee(callee) else {
+                    return false;
+                };
+                // A user function may read the globals and upvalues the loop
+                // assigns, a builtin reads none
+                let reads_assigned = !self.motion.pure_calls.is_builtin(function)
+                    && self.scan.written.iter().any(|name| {
+                        !self.scan.declared.contains_key(name)
+                            && !self.motion.frame.parameters.contains(name)
+                    });
+                !self.scan.clobbers
+                    && self.scan.stores.is_empty()
+                    && !reads_assigned
+                    && arguments
+                        .iter()
+                        .all(|argument| !argument.is_spread && self.is_invariant(&argument.value))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether nothing in the loop may write the field `read` reads
+    fn is_unwritten(&self, read: Store) -> bool {
+        !self.scan.clobbers
+            && self
+                .scan
+                .stores
+                .iter()
+                .all(|&store| !self.motion.frame.tables.may_alias(read, store))
+    }
+
+    /// Whether evaluating `expr` once more changes nothing
+    fn has_no_effects(&self, expr: &Expression<'_>) -> bool {
+        let mut scan = Scan::new(Some(self.motion.pure_calls));
+        scan.expression(expr);
+        !scan.clobbers && !scan.opaque && scan.stores.is_empty() && scan.written.is_empty()
+    }
+
+    /// The local holding the value of `expr`, hoisting it the first time
+    fn temp_for(&mut self, expr: &Expression<'arena>) -> StringId {
+        let key = expression_key(expr);
+        if let Some((_, temp, _)) = self.hoisted.iter().find(|(k, _, _)| *k == key) {
+            return *temp;
+        }
+
+        let id = *self.motion.next_temp_id;
+        *self.motion.next_temp_id += 1;
+        let temp = self
+            .motion
+            .interner
+            .get_or_intern(&format!("__licm_{}", id));
+        remarks::applied(PASS, expr.span, || {
+            format!("hoisted loop-invariant expression into `__licm_{}`", id)
+        });
+        self.hoisted.push((key, temp, expr.clone()));
+        temp
+    }
+}
+
+/// Whether `expr` is built from literals alone, which is left to constant
+/// folding
+fn is_constant(expr: &Expression<'_>) -> bool {
+    match &expr.kind {
+        ExpressionKind::Literal(_) => true,
+        ExpressionKind::Parenthesized(operand)
+        | ExpressionKind::TypeAssertion(operand, _)
+        | ExpressionKind::Unary(_, operand) => is_constant(operand),
+        ExpressionKind::Binary(_, left, right) => is_constant(left) && is_constant(right),
+        _ => false,
+    }
+}
+
+/// A key equal for invariant expressions that compute the same value
+fn expression_key(expr: &Expression<'_>) -> String {
+    match &expr.kind {
+        ExpressionKind::Literal(literal) => format!("{:?}", literal),
+        ExpressionKind::Identifier(name) => format!("{:?}", name),
+        ExpressionKind::SelfKeyword => "self".to_string(),
+        ExpressionKind::Parenthesized(operand) | ExpressionKind::TypeAssertion(operand, _) => {
+            expression_key(operand)
+        }
+        ExpressionKind::Unary(op, operand) => format!("({:?} {})", op, expression_key(operand)),
+        ExpressionKind::Binary(op, left, right) => format!(
+            "({} {:?} {})",
+            expression_key(left),
+            op,
+            expression_key(right)
+        ),
+        ExpressionKind::Member(object, field) => {
+            format!("{}.{:?}", expression_key(object), field.node)
+        }
+        ExpressionKind::Index(object, index) => {
+            format!("{}[{}]", expression_key(object), expression_key(index))
+        }
+        ExpressionKind::Call(callee, arguments, _) => {
+            let arguments: Vec<String> = arguments
+                .iter()
+                .map(|argument| expression_key(&argument.value))
+                .collect();
+            format!("{}({})", expression_key(callee), arguments.join(", "))
+        }
+        // Not invariant, so never hoisted
+        other => format!("{:?}", other),
+    }
+}
+
+/// `local temp = value`
+fn declare(temp: StringId, value: Expression<'_>) -> Statement<'_> {
+    let span = value.span;
+    Statement::Variable(VariableDeclaration {
+        kind: VariableKind::Local,
+        pattern: Pattern::Identifier(Spanned::new(temp, span)),
+        type_annotation: None,
+        initializer: value,
+        span,
+    })
+}
+
+/// `temp = value`
+fn assign<'arena>(
+    temp: StringId,
+    value: Expression<'arena>,
+    arena: &'arena Bump,
+) -> Statement<'arena> {
+    let span = value.span;
+    let target = Expression::new(ExpressionKind::Identifier(temp), span);
+    Statement::Expression(Expression::new(
+        ExpressionKind::Assignment(
+            arena.alloc(target),
+            AssignmentOp::Assign,
+            arena.alloc(value),
+        ),
+        span,
+    ))
+}
@@ -2,33 +2,54 @@
 // O2: Loop Optimization Pass
 // =============================================================================
 
+use super::loop_invariant_motion::{Entry, Frame, Header, Motion};
+use super::memory_effects::{pattern_names, PureCalls, Scan};
 use crate::config::OptimizationLevel;
-use crate::optimizer::{AstFeatures, WholeProgramPass};
+use crate::optimizer::{remarks, AstFeatures, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{
     ArrayElement, BinaryOp, Expression, ExpressionKind, Literal, UnaryOp,
 };
 use luanext_parser::ast::pattern::Pattern;
-use luanext_parser::ast::statement::{Block, ForNumeric, ForStatement, Statement};
-use luanext_parser::string_interner::StringId;
+use luanext_parser::ast::statement::{
+    Block, ClassMember, ForNumeric, ForStatement, Parameter, Statement,
+};
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
 use std::collections::HashSet;
+use std::sync::Arc;
+
+const PASS: &str = "loop-optimization";
 
 /// Loop optimization pass
 /// 1. Hoists loop-invariant local variable declarations
-/// 2. Removes dead loops (while false, zero-iteration for, repeat until true)
-/// 3. Handles all loop types including repeat...until
-pub struct LoopOptimizationPass;
+/// 2. Hoists loop-invariant expressions and table reads into fresh locals
+///    (see `loop_invariant_motion.rs`)
+/// 3. Removes dead loops (while false, zero-iteration for, repeat until true)
+/// 4. Handles all loop types including repeat...until
+pub struct LoopOptimizationPass {
+    interner: Arc<StringInterner>,
+    next_temp_id: usize,
+    pure_calls: PureCalls,
+    /// The functions around the statement being optimized, innermost last
+    frames: Vec<Frame>,
+}
 
 impl LoopOptimizationPass {
-    pub fn new() -> Self {
-        Self
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            pure_calls: PureCalls::new(&[], interner.clone()),
+            interner,
+            next_temp_id: 0,
+            frames: Vec::new(),
+        }
     }
 }
 
 impl<'arena> WholeProgramPass<'arena> for LoopOptimizationPass {
     fn name(&self) -> &'static str {
-        "loop-optimization"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -44,6 +65,9 @@ impl<'arena> WholeProgramPass<'arena> for LoopOptimizationPass {
         program: &mut MutableProgram<'arena>,
         arena: &'arena Bump,
     ) -> Result<bool, String> {
+        self.pure_calls = PureCalls::new(&program.statements, self.interner.clone());
+        self.frames = vec![Frame::new(&[], &program.statements, &self.pure_calls)];
+
         let mut changed = false;
         let mut i = 0;
 
@@ -65,6 +89,7 @@ impl<'arena> WholeProgramPass<'arena> for LoopOptimizationPass {
             i += 1;
         }
 
+        self.frames.clear();
         Ok(changed)
     }
 
@@ -95,10 +120,31 @@ impl LoopOptimizationPass {
             | Statement::Rethrow(_)
             | Statement::Throw(_) => (Vec::new(), false),
             Statement::Block(block) => (Vec::new(), self.optimize_block(block, arena)),
-            Statement::Class(_)
-            | Statement::Interface(_)
-            | Statement::Enum(_)
-            | Statement::TypeAlias(_) => (Vec::new(), false),
+            Statement::Class(class) => {
+                let mut members = class.members.to_vec();
+                let mut changed = false;
+                for member in &mut members {
+                    match member {
+                        ClassMember::Method(method) => {
+                            if let Some(body) = &mut method.body {
+                                changed |= self.optimize_function(method.parameters, body, arena);
+                            }
+                        }
+                        ClassMember::Constructor(ctor) => {
+                            changed |=
+                                self.optimize_function(ctor.parameters, &mut ctor.body, arena);
+                        }
+                        _ => {}
+                    }
+                }
+                if changed {
+                    class.members = arena.alloc_slice_clone(&members);
+                }
+                (Vec::new(), changed)
+            }
+            Statement::Interface(_) | Statement::Enum(_) | Statement::TypeAlias(_) => {
+                (Vec::new(), false)
+            }
             Statement::Import(_) | Statement::Export(_) => (Vec::new(), false),
             Statement::Namespace(_)
             | Statement::DeclareFunction(_)
@@ -108,7 +154,10 @@ impl LoopOptimizationPass {
             | Statement::DeclareConst(_)
             | Statement::Label(_)
             | Statement::Goto(_) => (Vec::new(), false),
-            Statement::Function(func) => (Vec::new(), self.optimize_block(&mut func.body, arena)),
+            Statement::Function(func) => (
+                Vec::new(),
+                self.optimize_function(func.parameters, &mut func.body, arena),
+            ),
             Statement::If(if_stmt) => {
                 let mut changed = self.optimize_block(&mut if_stmt.then_block, arena);
                 let mut new_else_ifs: Vec<_> = if_stmt.else_ifs.to_vec();
@@ -144,7 +193,8 @@ impl LoopOptimizationPass {
         match for_stmt_ref {
             ForStatement::Generic(for_gen_ref) => {
                 let mut new_gen = for_gen_ref.clone();
-                let modified_vars = self.collect_modified_variables(&new_gen.body);
+                let mut modified_vars = self.collect_modified_variables(&new_gen.body);
+                modified_vars.extend(new_gen.variables.iter().map(|var| var.node));
                 let (hoisted, new_body) =
                     self.hoist_invariants_simple(&new_gen.body, &modified_vars, arena);
                 new_gen.body = new_body;
@@ -159,6 +209,7 @@ impl LoopOptimizationPass {
                 let mut new_num = (**for_num_ref).clone();
                 if let Some((start, end, step)) = self.evaluate_numeric_bounds(&new_num) {
                     if self.has_zero_iterations(start, end, step) {
+                        report_dead_loop(&new_num.body, new_num.span, "loop runs zero times");
                         new_num.body.statements = arena.alloc_slice_clone(&[]);
                         *stmt = Statement::For(
                             arena.alloc(ForStatement::Numeric(arena.alloc(new_num))),
@@ -166,10 +217,19 @@ impl LoopOptimizationPass {
                         return (Vec::new(), true);
                     }
                 }
-                let modified_vars = self.collect_modified_variables(&new_num.body);
-                let (hoisted, new_body) =
+                let mut modified_vars = self.collect_modified_variables(&new_num.body);
+                modified_vars.insert(new_num.variable.node);
+                let (mut hoisted, new_body) =
                     self.hoist_invariants_simple(&new_num.body, &modified_vars, arena);
                 new_num.body = new_body;
+                let entry = self.numeric_entry(&new_num, arena);
+                if let Some(motion) = self.motion() {
+                    let header = Header::Numeric {
+                        variable: new_num.variable.node,
+                        entry,
+                    };
+                    hoisted.extend(motion.hoist(header, &mut new_num.body, arena));
+                }
                 let block_changed = self.optimize_block(&mut new_num.body, arena);
                 let changed = !hoisted.is_empty() || block_changed;
                 if changed {
@@ -187,13 +247,22 @@ impl LoopOptimizationPass {
         arena: &'arena Bump,
     ) -> (Vec<Statement<'arena>>, bool) {
         if let ExpressionKind::Literal(Literal::Boolean(false)) = &while_stmt.condition.kind {
+            report_dead_loop(
+                &while_stmt.body,
+                while_stmt.span,
+                "condition is always false",
+            );
             while_stmt.body.statements = arena.alloc_slice_clone(&[]);
             return (Vec::new(), true);
         }
         let modified_vars = self.collect_modified_variables(&while_stmt.body);
-        let (hoisted, new_body) =
+        let (mut hoisted, new_body) =
             self.hoist_invariants_simple(&while_stmt.body, &modified_vars, arena);
         while_stmt.body = new_body;
+        if let Some(motion) = self.motion() {
+            let header = Header::While(&mut while_stmt.condition);
+            hoisted.extend(motion.hoist(header, &mut while_stmt.body, arena));
+        }
         let block_changed = self.optimize_block(&mut while_stmt.body, arena);
         let changed = !hoisted.is_empty() || block_changed;
         (hoisted, changed)
@@ -205,18 +274,84 @@ impl LoopOptimizationPass {
         arena: &'arena Bump,
     ) -> (Vec<Statement<'arena>>, bool) {
         if let ExpressionKind::Literal(Literal::Boolean(true)) = &repeat_stmt.until.kind {
+            report_dead_loop(
+                &repeat_stmt.body,
+                repeat_stmt.span,
+                "until condition is always true",
+            );
             repeat_stmt.body.statements = arena.alloc_slice_clone(&[]);
             return (Vec::new(), true);
         }
         let modified_vars = self.collect_modified_variables(&repeat_stmt.body);
-        let (hoisted, new_body) =
+        let (mut hoisted, new_body) =
             self.hoist_invariants_simple(&repeat_stmt.body, &modified_vars, arena);
         repeat_stmt.body = new_body;
+        if let Some(motion) = self.motion() {
+            let header = Header::Repeat(&repeat_stmt.until);
+            hoisted.extend(motion.hoist(header, &mut repeat_stmt.body, arena));
+        }
         let block_changed = self.optimize_block(&mut repeat_stmt.body, arena);
         let changed = !hoisted.is_empty() || block_changed;
         (hoisted, changed)
     }
 
+    /// Optimize the loops of a function body, whose parameters `motion`
+    /// relies on
+    fn optimize_function<'arena>(
+        &mut self,
+        parameters: &[Parameter<'arena>],
+        body: &mut Block<'arena>,
+        arena: &'arena Bump,
+    ) -> bool {
+        self.frames
+            .push(Frame::new(parameters, body.statements, &self.pure_calls));
+        let changed = self.optimize_block(body, arena);
+        self.frames.pop();
+        changed
+    }
+
+    /// Expression motion for a loop of the innermost function
+    fn motion(&mut self) -> Option<Motion<'_>> {
+        Some(Motion {
+            pure_calls: &self.pure_calls,
+            frame: self.frames.last()?,
+            interner: &self.interner,
+            next_temp_id: &mut self.next_temp_id,
+        })
+    }
+
+    /// Whether the body of a numeric `for` runs at least once
+    fn numeric_entry<'arena>(
+        &self,
+        for_num: &ForNumeric<'arena>,
+        arena: &'arena Bump,
+    ) -> Entry<'arena> {
+        if let Some((start, end, step)) = self.evaluate_numeric_bounds(for_num) {
+            if step.abs() < 1e-10 || self.has_zero_iterations(start, end, step) {
+                return Entry::Unknown;
+            }
+            return Entry::Always;
+        }
+        let step = for_num
+            .step
+            .as_ref()
+            .map(|step| self.evaluate_constant_f64(step));
+        let op = match step {
+            None => BinaryOp::LessThanOrEqual,
+            Some(Some(step)) if step > 0.0 => BinaryOp::LessThanOrEqual,
+            Some(Some(step)) if step < 0.0 => BinaryOp::GreaterThanOrEqual,
+            _ => return Entry::Unknown,
+        };
+        Entry::When(Expression::new(
+            ExpressionKind::Binary(
+                op,
+                arena.alloc(for_num.start.clone()),
+                arena.alloc(for_num.end.clone()),
+            ),
+            for_num.span,
+        ))
+    }
+
     fn optimize_block<'arena>(&mut self, block: &mut Block<'arena>, arena: &'arena Bump) -> bool {
         let mut stmts: Vec<Statement<'arena>> = block.statements.to_vec();
         let mut changed = false;
@@ -602,13 +737,26 @@ impl LoopOptimizationPass {
     ) -> (Vec<Statement<'arena>>, Block<'arena>) {
         let mut hoisted = Vec::new();
         let mut new_statements = Vec::new();
+        let mut scan = Scan::new(None);
+        scan.block(block);
 
         for stmt in block.statements.iter() {
             match stmt {
                 Statement::Variable(decl) => {
-                    if self.is_invariant_expression(&decl.initializer, loop_vars) {
+                    // A local the loop assigns must start over each iteration
+                    let mut names = Vec::new();
+                    pattern_names(&decl.pattern, &mut names);
+                    let reassigned = names.iter().any(|name| scan.written.contains(name));
+                    if !reassigned && self.is_invariant_expression(&decl.initializer, loop_vars) {
+                        remarks::applied(PASS, decl.span, || {
+                            "hoisted loop-invariant declaration out of the loop".to_string()
+                        });
                         hoisted.push(stmt.clone());
                     } else {
+                        remarks::missed(PASS, decl.span, || {
+                            "not hoisted: initializer depends on loop state or has side effects"
+                                .to_string()
+                        });
                         new_statements.push(stmt.clone());
                     }
                 }
@@ -638,75 +786,11 @@ impl LoopOptimizationPass {
                     && self.is_invariant_expression(right, loop_vars)
             }
             ExpressionKind::Unary(_, operand) => self.is_invariant_expression(operand, loop_vars),
-            ExpressionKind::Call(func, args, _) => {
-                let func_invariant = match &func.kind {
-                    ExpressionKind::Identifier(id) => !loop_vars.contains(id),
-                    _ => false,
-                };
-                func_invariant
-                    && args
-                        .iter()
-                        .all(|arg| self.is_invariant_expression(&arg.value, loop_vars))
-            }
-            ExpressionKind::MethodCall(obj, _, args, _) => {
-                self.is_invariant_expression(obj, loop_vars)
-                    && args
-                        .iter()
-                        .all(|arg| self.is_invariant_expression(&arg.value, loop_vars))
-            }
-            ExpressionKind::Member(obj, _) => self.is_invariant_expression(obj, loop_vars),
-            ExpressionKind::Index(obj, index) => {
-                self.is_invariant_expression(obj, loop_vars)
-                    && self.is_invariant_expression(index, loop_vars)
-            }
-            ExpressionKind::Array(elements) => elements.iter().all(|elem| match elem {
-                ArrayElement::Expression(e) => self.is_invariant_expression(e, loop_vars),
-                ArrayElement::Spread(e) => self.is_invariant_expression(e, loop_vars),
-            }),
-            ExpressionKind::Object(properties) => properties.iter().all(|prop| match prop {
-                luanext_parser::ast::expression::ObjectProperty::Property {
-                    key: _,
-                    value,
-                    span: _,
-                } => self.is_invariant_expression(value, loop_vars),
-                luanext_parser::ast::expression::ObjectProperty::Computed {
-                    key,
-                    value,
-                    span: _,
-                } => {
-                    self.is_invariant_expression(key, loop_vars)
-                        && self.is_invariant_expression(value, loop_vars)
-                }
-                luanext_parser::ast::expression::ObjectProperty::Spread { value, span: _ } => {
-                    self.is_invariant_expression(value, loop_vars)
-                }
-            }),
-            ExpressionKind::Function(_) => true,
-            ExpressionKind::Arrow(arrow) => {
-                let body_invariant = match &arrow.body {
-                    luanext_parser::ast::expression::ArrowBody::Expression(expr) => {
-                        self.is_invariant_expression(expr, loop_vars)
-                    }
-                    luanext_parser::ast::expression::ArrowBody::Block(block) => {
-                        block.statements.iter().all(|s| match s {
-                            Statement::Variable(decl) => {
-                                self.is_invariant_expression(&decl.initializer, loop_vars)
-                            }
-                            _ => false,
-                        })
-                    }
-                };
-                body_invariant
-            }
             ExpressionKind::Conditional(cond, then_expr, else_expr) => {
                 self.is_invariant_expression(cond, loop_vars)
                     && self.is_invariant_expression(then_expr, loop_vars)
                     && self.is_invariant_expression(else_expr, loop_vars)
             }
-            ExpressionKind::Pipe(left, right) => {
-                self.is_invariant_expression(left, loop_vars)
-                    && self.is_invariant_expression(right, loop_vars)
-            }
             ExpressionKind::Match(match_expr) => {
                 self.is_invariant_expression(match_expr.value, loop_vars)
                     && match_expr.arms.iter().all(|arm| {
@@ -734,29 +818,6 @@ impl LoopOptimizationPass {
             }),
             ExpressionKind::Parenthesized(expr) => self.is_invariant_expression(expr, loop_vars),
             ExpressionKind::TypeAssertion(expr, _) => self.is_invariant_expression(expr, loop_vars),
-            ExpressionKind::New(expr, args, _) => {
-                self.is_invariant_expression(expr, loop_vars)
-                    && args
-                        .iter()
-                        .all(|arg| self.is_invariant_expression(&arg.value, loop_vars))
-            }
-            ExpressionKind::OptionalMember(obj, _) => self.is_invariant_expression(obj, loop_vars),
-            ExpressionKind::OptionalIndex(obj, index) => {
-                self.is_invariant_expression(obj, loop_vars)
-                    && self.is_invariant_expression(index, loop_vars)
-            }
-            ExpressionKind::OptionalCall(obj, args, _) => {
-                self.is_invariant_expression(obj, loop_vars)
-                    && args
-                        .iter()
-                        .all(|arg| self.is_invariant_expression(&arg.value, loop_vars))
-            }
-            ExpressionKind::OptionalMethodCall(obj, _, args, _) => {
-                self.is_invariant_expression(obj, loop_vars)
-                    && args
-                        .iter()
-                        .all(|arg| self.is_invariant_expression(&arg.value, loop_vars))
-            }
             ExpressionKind::Try(try_expr) => {
                 self.is_invariant_expression(try_expr.expression, loop_vars)
                     && self.is_invariant_expression(try_expr.catch_expression, loop_vars)
@@ -767,6 +828,24 @@ impl LoopOptimizationPass {
                     && self.is_invariant_expression(right, loop_vars)
             }
             ExpressionKind::SelfKeyword | ExpressionKind::SuperKeyword => true,
+            // Calls may have side effects and table reads may see the stores of
+            // the loop; `loop_invariant_motion.rs` hoists those it proves
+            // invariant
+            ExpressionKind::Call(..)
+            | ExpressionKind::MethodCall(..)
+            | ExpressionKind::OptionalCall(..)
+            | ExpressionKind::OptionalMethodCall(..)
+            | ExpressionKind::New(..)
+            | ExpressionKind::Pipe(..)
+            | ExpressionKind::Member(..)
+            | ExpressionKind::Index(..)
+            | ExpressionKind::OptionalMember(..)
+            | ExpressionKind::OptionalIndex(..) => false,
+            // Each iteration must get a new table or closure
+            ExpressionKind::Array(_)
+            | ExpressionKind::Object(_)
+            | ExpressionKind::Function(_)
+            | ExpressionKind::Arrow(_) => false,
         }
     }
 
@@ -832,8 +911,9 @@ impl LoopOptimizationPass {
     }
 }
 
-impl Default for LoopOptimizationPass {
-    fn default() -> Self {
-        Self::new()
+/// Report a loop body removed because of a constant loop condition
+fn report_dead_loop(body: &Block<'_>, span: Span, reason: &'static str) {
+    if !body.statements.is_empty() {
+        remarks::applied(PASS, span, || format!("removed loop body: {}", reason));
     }
 }
@@ -8,6 +8,8 @@
 // - Constant bounds (start, end, step must be compile-time constants)
 // - Small trip count (≤4 iterations to avoid code bloat)
 // - No break/continue/return statements (unsafe to unroll)
+// - With a profile, hot loops unroll up to 8 iterations and loops that never
+//   ran are left alone
 //
 // Example transformation:
 //   for i = 1, 3 do
@@ -19,27 +21,42 @@
 //   print(3)
 
 use crate::config::OptimizationLevel;
-use crate::optimizer::{AstFeatures, WholeProgramPass};
+use crate::optimizer::profile::{Hotness, ModuleProfile};
+use crate::optimizer::{remarks, AstFeatures, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{Expression, ExpressionKind, Literal, UnaryOp};
 use luanext_parser::ast::statement::{Block, ForNumeric, ForStatement, Statement};
 use luanext_parser::string_interner::StringId;
+use std::sync::Arc;
+
+const PASS: &str = "loop-unrolling";
 
 /// Maximum number of iterations to unroll (conservative to avoid code bloat)
 const MAX_UNROLL_COUNT: usize = 4;
 
-pub struct LoopUnrollingPass;
+/// Iteration limit for loops the profile marks hot
+const HOT_MAX_UNROLL_COUNT: usize = 8;
+
+pub struct LoopUnrollingPass {
+    profile: Option<Arc<ModuleProfile>>,
+}
 
 impl LoopUnrollingPass {
     pub fn new() -> Self {
-        Self
+        Self { profile: None }
+    }
+
+    /// Raise the limit for hot loops and skip cold ones
+    pub fn with_profile(mut self, profile: Option<Arc<ModuleProfile>>) -> Self {
+        self.profile = profile;
+        self
     }
 }
 
 impl<'arena> WholeProgramPass<'arena> for LoopUnrollingPass {
     fn name(&self) -> &'static str {
-        "loop-unrolling"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -105,27 +122,59 @@ impl LoopUnrollingPass {
         for_num: &ForNumeric<'arena>,
         arena: &'arena Bump,
     ) -> Option<Vec<Statement<'arena>>> {
+        let hotness = self.profile.as_ref().map_or(Hotness::Normal, |profile| {
+            profile.loop_hotness(for_num.span)
+        });
+        let max_unroll = match hotness {
+            Hotness::Hot => HOT_MAX_UNROLL_COUNT,
+            Hotness::Normal => MAX_UNROLL_COUNT,
+            Hotness::Cold => {
+                remarks::missed(PASS, for_num.span, || {
+                    "loop never ran in the profile".to_string()
+                });
+                return None;
+            }
+        };
+
         // Safety check: loop must not contain break/continue/return
         if self.contains_control_flow(&for_num.body) {
+            remarks::missed(PASS, for_num.span, || {
+                "loop body contains break, continue or return".to_string()
+            });
             return None;
         }
 
         // Evaluate loop bounds
-        let (start, end, step) = self.evaluate_numeric_bounds(for_num)?;
+        let Some((start, end, step)) = self.evaluate_numeric_bounds(for_num) else {
+            remarks::missed(PASS, for_num.span, || {
+                "loop bounds are not compile-time constants".to_string()
+            });
+            return None;
+        };
 
         // Safety check: step must not be zero
         if step == 0.0 {
+            remarks::missed(PASS, for_num.span, || "loop step is zero".to_string());
             return None;
         }
 
         // Calculate trip count
-        let trip_count = self.calculate_trip_count(start, end, step)?;
+        let Some(trip_count) = self.calculate_trip_count(start, end, step, max_unroll) else {
+            remarks::missed(PASS, for_num.span, || {
+                format!("trip count exceeds the unroll limit of {}", max_unroll)
+            });
+            return None;
+        };
 
         // Profitability check: only unroll small loops
-        if trip_count == 0 || trip_count > MAX_UNROLL_COUNT {
+        if trip_count == 0 || trip_count > max_unroll {
             return None;
         }
 
+        remarks::applied(PASS, for_num.span, || {
+            format!("unrolled loop with {} iteration(s)", trip_count)
+        });
+
         // Unroll the loop
         Some(self.generate_unrolled_statements(for_num, start, step, trip_count, arena))
     }
@@ -205,13 +254,19 @@ impl LoopUnrollingPass {
     }
 
     /// Calculate trip count for a numeric loop
-    fn calculate_trip_count(&self, start: f64, end: f64, step: f64) -> Option<usize> {
+    fn calculate_trip_count(
+        &self,
+        start: f64,
+        end: f64,
+        step: f64,
+        max_unroll: usize,
+    ) -> Option<usize> {
         if step > 0.0 {
             if start > end {
                 return Some(0);
             }
             let count = ((end - start) / step).floor() + 1.0;
-            if count < 0.0 || count > MAX_UNROLL_COUNT as f64 {
+            if count < 0.0 || count > max_unroll as f64 {
                 return None;
             }
             Some(count as usize)
@@ -220,7 +275,7 @@ impl LoopUnrollingPass {
                 return Some(0);
             }
             let count = ((end - start) / step).floor() + 1.0;
-            if count < 0.0 || count > MAX_UNROLL_COUNT as f64 {
+            if count < 0.0 || count > max_unroll as f64 {
                 return None;
             }
             Some(count as usize)
@@ -0,0 +1,645 @@
+//! Syntactic memory effects shared by the passes that move or reuse values
+//!
+//! `Scan` walks code without evaluating it and records the names it assigns,
+//! the tables it stores into and whether it makes calls that may write any
+//! memory. `PureCalls` decides which calls those are not, and which of the
+//! others still store into no table.
+
+use crate::optimizer::analysis::{
+    AliasAnalyzer, AliasInfo, AliasResult, MemoryLocation, SideEffectAnalyzer,
+};
+use luanext_parser::ast::expression::{
+    ArrayElement, ArrowBody, Expression, ExpressionKind, MatchArmBody, ObjectProperty, TemplatePart,
+};
+use luanext_parser::ast::pattern::{ArrayPatternElement, Pattern};
+use luanext_parser::ast::statement::{
+    Block, CatchPattern, ExportKind, ForStatement, FunctionDeclaration, Parameter, Statement,
+    VariableKind,
+};
+use luanext_parser::ast::types::{PrimitiveType, TypeKind};
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::Arc;
+
+/// Builtins `SideEffectAnalyzer` lists as pure whose results may differ
+/// between two calls with the same arguments, or that call back into Lua
+const NONDETERMINISTIC_BUILTINS: &[&str] = &[
+    "math.random",
+    "pcall",
+    "xpcall",
+    "string.gmatch",
+    "string.gsub",
+    "table.move",
+];
+
+/// Builtins that only write output, so they store into no table
+const OUTPUT_BUILTINS: &[&str] = &["print", "io.write"];
+
+/// A table store: the table when it is a named variable, and the field when
+/// it is static
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Store {
+    pub(super) table: Option<StringId>,
+    pub(super) field: Option<StringId>,
+}
+
+/// The calls whose value depends only on their arguments and memory
+pub(super) struct PureCalls {
+    interner: Arc<StringInterner>,
+    /// Pure functions the program declares at the top level
+    functions: FxHashSet<StringId>,
+    /// Deterministic builtins, by qualified name
+    builtins: FxHashSet<StringId>,
+    /// `OUTPUT_BUILTINS`, by qualified name
+    outputs: FxHashSet<StringId>,
+    /// How often each name is declared anywhere in the program
+    declarations: FxHashMap<StringId, usize>,
+    /// Names the program assigns or stores fields into
+    rebound: FxHashSet<StringId>,
+}
+
+impl PureCalls {
+    pub(super) fn new(statements: &[Statement<'_>], interner: Arc<StringInterner>) -> Self {
+        let effects = SideEffectAnalyzer::new(interner.clone()).analyze(statements);
+        let mut program = Scan::new(None);
+        program.statements(statements);
+
+        let nondeterministic: FxHashSet<StringId> = NONDETERMINISTIC_BUILTINS
+            .iter()
+            .map(|name| interner.get_or_intern(name))
+            .collect();
+        let builtins = effects
+            .known_pure_builtins
+            .iter()
+            .filter(|name| !nondeterministic.contains(*name))
+            .copied()
+            .collect();
+
+        let mut functions = FxHashSet::default();
+        for stmt in statements {
+            let Statement::Function(func) = stmt else {
+                continue;
+            };
+            let name = func.name.node;
+            if !effects.pure_functions.contains(&name)
+                || !returns_primitive(func)
+                || program.written.contains(&name)
+            {
+                continue;
+            }
+            let mut body = Scan::new(None);
+            body.block(&func.body);
+            if body.called.is_disjoint(&nondeterministic) {
+                functions.insert(name);
+            }
+        }
+
+        let outputs = OUTPUT_BUILTINS
+            .iter()
+            .map(|name| interner.get_or_intern(name))
+            .collect();
+        let rebound = program.written.union(&program.stored).copied().collect();
+        Self {
+            interner,
+            functions,
+            builtins,
+            outputs,
+            declarations: program.declared,
+            rebound,
+        }
+    }
+
+    /// The name of the pure function `callee` refers to
+    pub(super) fn callee(&self, callee: &Expression<'_>) -> Option<StringId> {
+        if let ExpressionKind::Identifier(name) = &callee.kind {
+            if self.declarations.get(name) == Some(&1) && self.functions.contains(name) {
+                return Some(*name);
+            }
+        }
+        self.global(callee)
+            .filter(|name| self.builtins.contains(name))
+    }
+
+    /// Whether `callee` is a builtin that only writes output
+    pub(super) fn is_output(&self, callee: &Expression<'_>) -> bool {
+        self.global(callee)
+            .is_some_and(|name| self.outputs.contains(&name))
+    }
+
+    /// The qualified name of the global or module member `callee` refers to,
+    /// when the program never declares or rebinds it
+    fn global(&self, callee: &Expression<'_>) -> Option<StringId> {
+        match &callee.kind {
+            ExpressionKind::Identifier(name) => {
+                let bound = self.declarations.contains_key(name) || self.rebound.contains(name);
+                (!bound).then_some(*name)
+            }
+            ExpressionKind::Member(object, member) => {
+                let ExpressionKind::Identifier(module) = &object.kind else {
+                    return None;
+                };
+                if self.declarations.contains_key(module) || self.rebound.contains(module) {
+                    return None;
+                }
+                Some(self.interner.get_or_intern(&format!(
+                    "{}.{}",
+                    self.interner.resolve(*module),
+                    self.interner.resolve(member.node)
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `name`, returned by `callee`, is a builtin rather than a
+    /// function of the program
+    pub(super) fn is_builtin(&self, name: StringId) -> bool {
+        self.builtins.contains(&name)
+    }
+}
+
+/// The local tables of a function body that `AliasAnalyzer` tells apart
+pub(super) struct LocalTables {
+    alias: AliasInfo,
+    /// Locals initialized from a table constructor that are declared once
+    /// and never reassigned or used by a closure
+    tracked: FxHashSet<StringId>,
+}
+
+impl LocalTables {
+    /// The tables of the function body `statements`, given its `scan`
+    pub(super) fn new(statements: &[Statement<'_>], scan: &Scan<'_>) -> Self {
+        let alias = AliasAnalyzer::new().analyze(statements);
+        let tracked = if scan.opaque {
+            FxHashSet::default()
+        } else {
+            alias
+                .table_variables
+                .iter()
+                .filter(|name| {
+                    !scan.written.contains(*name)
+                        && !scan.captured.contains(*name)
+                        && scan.declared.get(*name) == Some(&1)
+                })
+                .copied()
+                .collect()
+        };
+        Self { alias, tracked }
+    }
+
+    /// Whether `store` may write the field `read` was read from
+    pub(super) fn may_alias(&self, read: Store, store: Store) -> bool {
+        if let (Some(a), Some(b)) = (read.field, store.field) {
+            if a != b {
+                return false;
+            }
+        }
+        match (read.table, store.table) {
+            (Some(a), Some(b)) => !self.distinct(a, b),
+            _ => true,
+        }
+    }
+
+    /// Whether locals `a` and `b` always hold different tables: both are
+    /// tracked and `AliasAnalyzer` put them in no common alias class
+    fn distinct(&self, a: StringId, b: StringId) -> bool {
+        if a == b || !self.tracked.contains(&a) || !self.tracked.contains(&b) {
+            return false;
+        }
+        let (a, b) = (MemoryLocation::Local(a), MemoryLocation::Local(b));
+        match self.alias.query(&a, &b) {
+            AliasResult::NoAlias => true,
+            AliasResult::MayAlias => !self.alias.aliases_of(&a).contains(&&b),
+            AliasResult::MustAlias => false,
+        }
+    }
+}
+
+/// Syntactic effects of a piece of code, with the facts about its names a
+/// pass needs before rewriting it
+pub(super) struct Scan<'p> {
+    pure_calls: Option<&'p PureCalls>,
+    /// Closure nesting depth
+    depth: usize,
+    /// Names assigned outside closures
+    pub(super) assigned: FxHashSet<StringId>,
+    /// Names assigned anywhere
+    pub(super) written: FxHashSet<StringId>,
+    /// Stores to tables outside closures
+    pub(super) stores: Vec<Store>,
+    /// Named tables stored into anywhere
+    pub(super) stored: FxHashSet<StringId>,
+    /// Whether a call outside closures may write any memory
+    pub(super) clobbers: bool,
+    /// Whether a call outside closures may store into a table
+    pub(super) hidden_stores: bool,
+    /// Whether the code has constructs the scan does not model
+    pub(super) opaque: bool,
+    /// Whether the code has `goto` or labels outside closures
+    pub(super) jumps: bool,
+    /// How often each name is declared
+    pub(super) declared: FxHashMap<StringId, usize>,
+    /// Names used inside closures
+    pub(super) captured: FxHashSet<StringId>,
+    /// Names called directly
+    pub(super) called: FxHashSet<StringId>,
+    /// How often each name is read
+    pub(super) referenced: FxHashMap<StringId, usize>,
+    /// Whether the code uses `self` or `super`
+    pub(super) uses_self: bool,
+}
+
+impl<'p> Scan<'p> {
+    pub(super) fn new(pure_calls: Option<&'p PureCalls>) -> Self {
+        Self {
+            pure_calls,
+            depth: 0,
+            assigned: FxHashSet::default(),
+            written: FxHashSet::default(),
+            stores: Vec::new(),
+            stored: FxHashSet::default(),
+            clobbers: false,
+            hidden_stores: false,
+            opaque: false,
+            jumps: false,
+            declared: FxHashMap::default(),
+            captured: FxHashSet::default(),
+            called: FxHashSet::default(),
+            referenced: FxHashMap::default(),
+            uses_self: false,
+        }
+    }
+
+    fn declare(&mut self, name: StringId) {
+        *self.declared.entry(name).or_insert(0) += 1;
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern<'_>) {
+        let mut names = Vec::new();
+        pattern_names(pattern, &mut names);
+        for name in names {
+            self.declare(name);
+        }
+    }
+
+    fn assign(&mut self, name: StringId) {
+        self.written.insert(name);
+        if self.depth == 0 {
+            self.assigned.insert(name);
+        } else {
+            self.captured.insert(name);
+        }
+    }
+
+    fn store(&mut self, store: Store) {
+        self.stored.extend(store.table);
+        if self.depth == 0 {
+            self.stores.push(store);
+        }
+    }
+
+    fn clobber(&mut self) {
+        if self.depth == 0 {
+            self.clobbers = true;
+            self.hidden_stores = true;
+        }
+    }
+
+    pub(super) fn statements(&mut self, statements: &[Statement<'_>]) {
+        for stmt in statements {
+            self.statement(stmt);
+        }
+    }
+
+    pub(super) fn block(&mut self, block: &Block<'_>) {
+        self.statements(block.statements);
+    }
+
+    pub(super) fn statement(&mut self, stmt: &Statement<'_>) {
+        match stmt {
+            Statement::Variable(decl) => {
+                self.expression(&decl.initializer);
+                if matches!(decl.kind, VariableKind::Global) {
+                    let mut names = Vec::new();
+                    pattern_names(&decl.pattern, &mut names);
+                    for name in names {
+                        self.assign(name);
+                    }
+                } else {
+                    self.declare_pattern(&decl.pattern);
+                }
+            }
+            Statement::Function(func) => {
+                self.declare(func.name.node);
+                self.function(func.parameters, func.body.statements, None);
+            }
+            Statement::Expression(expr) => self.expression(expr),
+            Statement::MultiAssignment(multi) => {
+                for value in multi.values.iter() {
+                    self.expression(value);
+                }
+                for target in multi.targets.iter() {
+                    self.target(target);
+                }
+            }
+            Statement::If(if_stmt) => {
+                self.expression(&if_stmt.condition);
+                self.block(&if_stmt.then_block);
+                for else_if in if_stmt.else_ifs.iter() {
+                    self.expression(&else_if.condition);
+                    self.block(&else_if.block);
+                }
+                if let Some(else_block) = &if_stmt.else_block {
+                    self.block(else_block);
+                }
+            }
+            Statement::While(while_stmt) => {
+                self.expression(&while_stmt.condition);
+                self.block(&while_stmt.body);
+            }
+            Statement::For(for_stmt) => match &**for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    self.expression(&for_num.start);
+                    self.expression(&for_num.end);
+                    if let Some(step) = &for_num.step {
+                        self.expression(step);
+                    }
+                    self.declare(for_num.variable.node);
+                    self.block(&for_num.body);
+                }
+                ForStatement::Generic(for_gen) => {
+                    for iterator in for_gen.iterators.iter() {
+                        self.expression(iterator);
+                    }
+                    for var in for_gen.variables.iter() {
+                        self.declare(var.node);
+                    }
+                    if let Some(pattern) = &for_gen.pattern {
+                        self.declare_pattern(pattern);
+                    }
+                    self.block(&for_gen.body);
+                }
+            },
+            Statement::Repeat(repeat_stmt) => {
+                self.block(&repeat_stmt.body);
+                self.expression(&repeat_stmt.until);
+            }
+            Statement::Return(ret) => {
+                for value in ret.values.iter() {
+                    self.expression(value);
+                }
+            }
+            Statement::Block(block) => self.block(block),
+            Statement::Try(try_stmt) => {
+                self.block(&try_stmt.try_block);
+                for catch in try_stmt.catch_clauses.iter() {
+                    let variable = match &catch.pattern {
+                        CatchPattern::Typed { variable, .. }
+                        | CatchPattern::MultiTyped { variable, .. }
+                        | CatchPattern::Untyped { variable, .. } => variable,
+                    };
+                    self.declare(variable.node);
+                    self.block(&catch.body);
+                }
+                if let Some(finally_block) = &try_stmt.finally_block {
+                    self.block(finally_block);
+                }
+            }
+            Statement::Throw(throw) => self.expression(&throw.expression),
+            Statement::Export(export) => match &export.kind {
+                ExportKind::Declaration(inner) => self.statement(inner),
+                ExportKind::Default(expr) => self.expression(expr),
+                ExportKind::Named { .. } | ExportKind::All { .. } => {}
+            },
+            Statement::Label(_) | Statement::Goto(_) => {
+                if self.depth == 0 {
+                    self.jumps = true;
+                }
+            }
+            Statement::Class(class_decl) => {
+                // Methods may use the enclosing locals
+                self.declare(class_decl.name.node);
+                self.opaque = true;
+            }
+            Statement::Enum(enum_decl) => {
+                self.declare(enum_decl.name.node);
+                self.opaque = true;
+            }
+            Statement::Import(_) | Statement::Namespace(_) => self.opaque = true,
+            Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::Rethrow(_)
+            | Statement::Interface(_)
+            | Statement::TypeAlias(_)
+            | Statement::DeclareFunction(_)
+            | Statement::DeclareNamespace(_)
+            | Statement::DeclareType(_)
+            | Statement::DeclareInterface(_)
+            | Statement::DeclareConst(_) => {}
+        }
+    }
+
+    fn function(
+        &mut self,
+        parameters: &[Parameter<'_>],
+        statements: &[Statement<'_>],
+        expr_body: Option<&Expression<'_>>,
+    ) {
+        self.depth += 1;
+        for param in parameters {
+            self.declare_pattern(&param.pattern);
+            if let Some(default) = &param.default {
+                self.expression(default);
+            }
+        }
+        self.statements(statements);
+        if let Some(expr) = expr_body {
+            self.expression(expr);
+        }
+        self.depth -= 1;
+    }
+
+    fn target(&mut self, target: &Expression<'_>) {
+        match &target.kind {
+            ExpressionKind::Identifier(name) => self.assign(*name),
+            ExpressionKind::Member(object, field) => {
+                self.expression(object);
+                self.store(Store {
+                    table: variable_name(object),
+                    field: Some(field.node),
+                });
+            }
+            ExpressionKind::Index(object, index) => {
+                self.expression(object);
+                self.expression(index);
+                self.store(Store {
+                    table: variable_name(object),
+                    field: None,
+                });
+            }
+            _ => {
+                self.expression(target);
+                self.clobber();
+            }
+        }
+    }
+
+    pub(super) fn expression(&mut self, expr: &Expression<'_>) {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => {
+                *self.referenced.entry(*name).or_insert(0) += 1;
+                if self.depth > 0 {
+                    self.captured.insert(*name);
+                }
+            }
+            ExpressionKind::Literal(_) => {}
+            ExpressionKind::SelfKeyword | ExpressionKind::SuperKeyword => self.uses_self = true,
+            ExpressionKind::Binary(_, left, right)
+            | ExpressionKind::Index(left, right)
+            | ExpressionKind::OptionalIndex(left, right) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            ExpressionKind::Pipe(left, right) | ExpressionKind::ErrorChain(left, right) => {
+                self.expression(left);
+                self.expression(right);
+                self.clobber();
+            }
+            ExpressionKind::Unary(_, operand)
+            | ExpressionKind::Parenthesized(operand)
+            | ExpressionKind::TypeAssertion(operand, _)
+            | ExpressionKind::Member(operand, _)
+            | ExpressionKind::OptionalMember(operand, _) => self.expression(operand),
+            ExpressionKind::Assignment(target, _, value) => {
+                self.expression(value);
+                self.target(target);
+            }
+            ExpressionKind::Call(callee, arguments, _) => {
+                if let ExpressionKind::Identifier(name) = &callee.kind {
+                    self.called.insert(*name);
+                }
+                self.expression(callee);
+                for argument in arguments.iter() {
+                    self.expression(&argument.value);
+                }
+                match self.pure_calls {
+                    Some(pure_calls) if pure_calls.callee(callee).is_some() => {}
+                    Some(pure_calls) if pure_calls.is_output(callee) => {
+                        if self.depth == 0 {
+                            self.clobbers = true;
+                        }
+                    }
+                    _ => self.clobber(),
+                }
+            }
+            ExpressionKind::OptionalCall(callee, arguments, _)
+            | ExpressionKind::New(callee, arguments, _)
+            | ExpressionKind::MethodCall(callee, _, arguments, _)
+            | ExpressionKind::OptionalMethodCall(callee, _, arguments, _) => {
+                self.expression(callee);
+                for argument in arguments.iter() {
+                    self.expression(&argument.value);
+                }
+                self.clobber();
+            }
+            ExpressionKind::Conditional(condition, then_expr, else_expr) => {
+                self.expression(condition);
+                self.expression(then_expr);
+                self.expression(else_expr);
+            }
+            ExpressionKind::Array(elements) => {
+                for element in elements.iter() {
+                    match element {
+                        ArrayElement::Expression(e) | ArrayElement::Spread(e) => self.expression(e),
+                    }
+                }
+            }
+            ExpressionKind::Object(properties) => {
+                for property in properties.iter() {
+                    match property {
+                        ObjectProperty::Property { value, .. }
+                        | ObjectProperty::Spread { value, .. } => self.expression(value),
+                        ObjectProperty::Computed { key, value, .. } => {
+                            self.expression(key);
+                            self.expression(value);
+                        }
+                    }
+                }
+            }
+            ExpressionKind::Function(func) => {
+                self.function(func.parameters, func.body.statements, None)
+            }
+            ExpressionKind::Arrow(arrow) => match &arrow.body {
+                ArrowBody::Expression(body) => self.function(arrow.parameters, &[], Some(&**body)),
+                ArrowBody::Block(block) => self.function(arrow.parameters, block.statements, None),
+            },
+            ExpressionKind::Match(match_expr) => {
+                // Arm patterns bind names the scan does not collect
+                self.opaque = true;
+                self.expression(match_expr.value);
+                for arm in match_expr.arms.iter() {
+                    match &arm.body {
+                        MatchArmBody::Expression(e) => self.expression(e),
+                        MatchArmBody::Block(block) => self.statements(block.statements),
+                    }
+                }
+            }
+            ExpressionKind::Try(try_expr) => {
+                self.expression(try_expr.expression);
+                self.expression(try_expr.catch_expression);
+            }
+            ExpressionKind::Template(template) => {
+                for part in template.parts.iter() {
+                    if let TemplatePart::Expression(e) = part {
+                        self.expression(e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(super) fn variable_name(expr: &Expression<'_>) -> Option<StringId> {
+    match expr.kind {
+        ExpressionKind::Identifier(name) => Some(name),
+        _ => None,
+    }
+}
+
+/// A pure function returning a table would hand each caller a new one
+fn returns_primitive(func: &FunctionDeclaration<'_>) -> bool {
+    matches!(
+        func.return_type.as_ref().map(|ty| &ty.kind),
+        Some(TypeKind::Primitive(
+            PrimitiveType::Number
+                | PrimitiveType::Integer
+                | PrimitiveType::String
+                | PrimitiveType::Boolean
+        ))
+    )
+}
+
+/// Names bound by a declaration pattern
+pub(super) fn pattern_names(pattern: &Pattern<'_>, names: &mut Vec<StringId>) {
+    match pattern {
+        Pattern::Identifier(ident) => names.push(ident.node),
+        Pattern::Array(arr_pat) => {
+            for elem in arr_pat.elements.iter() {
+                match elem {
+                    ArrayPatternElement::Pattern(pwd) => pattern_names(&pwd.pattern, names),
+                    ArrayPatternElement::Rest(ident) => names.push(ident.node),
+                    ArrayPatternElement::Hole => {}
+                }
+            }
+        }
+        Pattern::Object(obj_pat) => {
+            for prop in obj_pat.properties.iter() {
+                match &prop.value {
+                    Some(pat) => pattern_names(pat, names),
+                    None => names.push(prop.key.node),
+                }
+            }
+        }
+        Pattern::Wildcard(_) | Pattern::Literal(_, _) | Pattern::Or(_) | Pattern::Template(_) => {}
+    }
+}
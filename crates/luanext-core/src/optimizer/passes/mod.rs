@@ -19,6 +19,8 @@ pub use function_inlining::FunctionInliningPass;
 mod loop_optimization;
 pub use loop_optimization::LoopOptimizationPass;
 
+mod loop_invariant_motion;
+
 mod string_concat_optimization;
 pub use string_concat_optimization::StringConcatOptimizationPass;
 
@@ -49,6 +51,9 @@ pub use sccp::SccpPass;
 mod loop_unrolling;
 pub use loop_unrolling::LoopUnrollingPass;
 
+mod induction_variables;
+pub use induction_variables::InductionVariablePass;
+
 mod function_cloning;
 pub use function_cloning::FunctionCloningPass;
 
@@ -58,6 +63,43 @@ pub use interprocedural_const_prop::InterproceduralConstPropPass;
 mod scalar_replacement;
 pub use scalar_replacement::ScalarReplacementPass;
 
+mod global_value_numbering;
+pub use global_value_numbering::GlobalValueNumberingPass;
+
+mod memory_effects;
+
+mod branch_layout;
+pub use branch_layout::BranchLayoutPass;
+
+// Size passes (optimizer.goal: size)
+mod short_names;
+
+mod code_outlining;
+pub use code_outlining::CodeOutliningPass;
+
+mod string_constant_sharing;
+pub use string_constant_sharing::StringConstantSharingPass;
+
+mod identifier_minification;
+pub use identifier_minification::IdentifierMinificationPass;
+
+mod comptime_evaluation;
+pub use comptime_evaluation::ComptimeEvaluationPass;
+
+mod comptime_interpreter;
+
+mod array_iteration;
+pub use array_iteration::ArrayIterationPass;
+
+mod integer_division;
+pub use integer_division::IntegerDivisionPass;
+
+mod lambda_lifting;
+pub use lambda_lifting::LambdaLiftingPass;
+
+mod constant_pooling;
+pub use constant_pooling::ConstantPoolingPass;
+
 // Link-Time Optimization (LTO) passes
 mod dead_export_elimination;
 pub use dead_export_elimination::DeadExportEliminationPass;
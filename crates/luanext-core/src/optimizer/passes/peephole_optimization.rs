@@ -27,10 +27,12 @@
 //! x and x  →  x
 //! ```
 
-use crate::optimizer::ExprVisitor;
+use crate::optimizer::{remarks, ExprVisitor};
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, Literal, UnaryOp};
 
+const PASS: &str = "peephole-optimization";
+
 /// Peephole optimization pass.
 ///
 /// Applies local pattern-based optimizations to expressions.
@@ -208,6 +210,7 @@ impl<'arena> ExprVisitor<'arena> for PeepholeOptimizationPass {
             // Double negation: not (not x) → x
             ExpressionKind::Unary(UnaryOp::Not, operand) => {
                 if let ExpressionKind::Unary(UnaryOp::Not, inner) = &operand.kind {
+                    remarks::applied(PASS, expr.span, || "removed double negation".to_string());
                     // Preserve type annotation if present
                     let old_type = expr.annotated_type.clone();
                     *expr = (**inner).clone();
@@ -222,6 +225,9 @@ impl<'arena> ExprVisitor<'arena> for PeepholeOptimizationPass {
             // Binary operation optimizations
             ExpressionKind::Binary(op, left, right) => {
                 if let Some(optimized) = self.optimize_binary_op(*op, left, right, arena) {
+                    remarks::applied(PASS, expr.span, || {
+                        format!("rewrote {:?} with an identity or absorbing operand", op)
+                    });
                     // Preserve type annotation if present
                     let old_type = expr.annotated_type.clone();
                     *expr = optimized;
@@ -237,6 +243,9 @@ impl<'arena> ExprVisitor<'arena> for PeepholeOptimizationPass {
             ExpressionKind::Conditional(cond, then_expr, else_expr) => match &cond.kind {
                 ExpressionKind::Literal(Literal::Boolean(true)) => {
                     // if true then A else B → A
+                    remarks::applied(PASS, expr.span, || {
+                        "selected the then-branch of a constant `true` conditional".to_string()
+                    });
                     let old_type = expr.annotated_type.clone();
                     *expr = (**then_expr).clone();
                     if old_type.is_some() {
@@ -246,6 +255,9 @@ impl<'arena> ExprVisitor<'arena> for PeepholeOptimizationPass {
                 }
                 ExpressionKind::Literal(Literal::Boolean(false)) => {
                     // if false then A else B → B
+                    remarks::applied(PASS, expr.span, || {
+                        "selected the else-branch of a constant `false` conditional".to_string()
+                    });
                     let old_type = expr.annotated_type.clone();
                     *expr = (**else_expr).clone();
                     if old_type.is_some() {
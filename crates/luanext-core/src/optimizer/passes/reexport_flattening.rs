@@ -1,11 +1,14 @@
 use crate::optimizer::analysis::module_graph::{
     compute_relative_require_path, resolve_relative_source, ModuleGraph,
 };
+use crate::optimizer::remarks;
 use luanext_parser::ast::statement::{ExportKind, ExportSpecifier, Statement};
 use luanext_parser::string_interner::StringInterner;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+const PASS: &str = "reexport-flattening";
+
 /// Re-export Flattening Pass
 ///
 /// Optimizes re-export chains by flattening them to reduce runtime `require()` overhead.
@@ -85,6 +88,12 @@ impl ReExportFlatteningPass {
                         if let Some(new_source) =
                             self.try_flatten_named(module_path, specifiers, source)
                         {
+                            remarks::applied(PASS, export_decl.span, || {
+                                format!(
+                                    "re-export now requires '{}' directly instead of '{}'",
+                                    new_source, source
+                                )
+                            });
                             let mut new_export = export_decl.clone();
                             new_export.kind = ExportKind::Named {
                                 specifiers,
@@ -101,6 +110,12 @@ impl ReExportFlatteningPass {
                         is_type_only,
                     } => {
                         if let Some(new_source) = self.try_flatten_all(module_path, source) {
+                            remarks::applied(PASS, export_decl.span, || {
+                                format!(
+                                    "re-export now requires '{}' directly instead of '{}'",
+                                    new_source, source
+                                )
+                            });
                             let mut new_export = export_decl.clone();
                             new_export.kind = ExportKind::All {
                                 source: new_source,
@@ -228,6 +243,7 @@ mod tests {
             exports: FxHashMap::default(),
             imports: FxHashMap::default(),
             re_exports: Vec::new(),
+            dynamic_imports: Vec::new(),
             is_reachable: true,
         }
     }
@@ -20,8 +20,8 @@
 //   const dx = point.x + 10
 //   const dy = point.y + 20
 // →
-//   const point__x = 1
-//   const point__y = 2
+//   local point__x = 1
+//   local point__y = 2
 //   const dx = point__x + 10
 //   const dy = point__y + 20
 //
@@ -29,7 +29,7 @@
 // with direct local variable accesses (which map to Lua VM registers).
 
 use crate::config::OptimizationLevel;
-use crate::optimizer::{AstFeatures, WholeProgramPass};
+use crate::optimizer::{remarks, AstFeatures, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{ArrayElement, Expression, ExpressionKind, ObjectProperty};
@@ -43,6 +43,8 @@ use luanext_parser::string_interner::{StringId, StringInterner};
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::sync::Arc;
 
+const PASS: &str = "scalar-replacement";
+
 /// Maximum number of fields in an object eligible for SRA
 const MAX_FIELDS: usize = 8;
 
@@ -61,7 +63,7 @@ impl ScalarReplacementPass {
 
 impl<'arena> WholeProgramPass<'arena> for ScalarReplacementPass {
     fn name(&self) -> &'static str {
-        "scalar-replacement"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -110,10 +112,30 @@ impl ScalarReplacementPass {
                 self.try_extract_candidate(&stmts[i], arena)
             {
                 // Check if the table escapes in the remaining statements
+                let span = remarks::statement_span(&stmts[i]);
                 let remaining = &stmts[i + 1..];
-                if !table_escapes(var_name, remaining)
-                    && all_accesses_are_fields(var_name, remaining)
-                {
+                if table_escapes(var_name, remaining) {
+                    remarks::missed(PASS, span, || {
+                        format!(
+                            "`{}` not replaced: the table escapes",
+                            self.interner.resolve(var_name)
+                        )
+                    });
+                } else if !all_accesses_are_fields(var_name, remaining) {
+                    remarks::missed(PASS, span, || {
+                        format!(
+                            "`{}` not replaced: it is accessed other than by static field",
+                            self.interner.resolve(var_name)
+                        )
+                    });
+                } else {
+                    remarks::applied(PASS, span, || {
+                        format!(
+                            "replaced table `{}` with {} local(s)",
+                            self.interner.resolve(var_name),
+                            fields.len()
+                        )
+                    });
                     // Perform scalar replacement
                     let scalar_vars =
                         self.create_scalar_variables(&fields, &field_exprs, var_name, arena);
@@ -274,8 +296,9 @@ impl ScalarReplacementPass {
             .enumerate()
             .map(|(i, (field_name, _))| {
                 let scalar_name = self.make_scalar_name(var_name, *field_name);
+                // Field stores become assignments, so the scalars are not const
                 Statement::Variable(VariableDeclaration {
-                    kind: VariableKind::Const,
+                    kind: VariableKind::Local,
                     pattern: Pattern::Identifier(Spanned::new(scalar_name, Span::default())),
                     type_annotation: None,
                     initializer: field_exprs[i].clone(),
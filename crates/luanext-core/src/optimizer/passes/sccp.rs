@@ -28,7 +28,7 @@
 //! end
 //! ```
 
-use crate::optimizer::BlockVisitor;
+use crate::optimizer::{remarks, BlockVisitor};
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, Literal, UnaryOp};
 use luanext_parser::ast::pattern::Pattern;
@@ -36,6 +36,8 @@ use luanext_parser::ast::statement::{Block, ForStatement, Statement};
 use luanext_parser::string_interner::StringId;
 use rustc_hash::FxHashMap;
 
+const PASS: &str = "sccp";
+
 /// Lattice value for SCCP analysis.
 /// Represents the known state of a variable.
 #[derive(Debug, Clone)]
@@ -285,6 +287,7 @@ impl SccpPass {
                 // Try to resolve the main condition
                 if let Some(lit) = self.try_evaluate(&if_stmt.condition) {
                     let is_truthy = self.is_truthy(&lit);
+                    report_resolved(&if_stmt.condition, is_truthy);
                     if_stmt.condition = Expression {
                         kind: ExpressionKind::Literal(Literal::Boolean(is_truthy)),
                         span: if_stmt.condition.span,
@@ -307,6 +310,7 @@ impl SccpPass {
                     self.lattice = saved.clone();
                     if let Some(lit) = self.try_evaluate(&else_if.condition) {
                         let is_truthy = self.is_truthy(&lit);
+                        report_resolved(&else_if.condition, is_truthy);
                         else_if.condition = Expression {
                             kind: ExpressionKind::Literal(Literal::Boolean(is_truthy)),
                             span: else_if.condition.span,
@@ -339,6 +343,7 @@ impl SccpPass {
                 // Try to resolve the condition
                 if let Some(lit) = self.try_evaluate(&while_stmt.condition) {
                     let is_truthy = self.is_truthy(&lit);
+                    report_resolved(&while_stmt.condition, is_truthy);
                     while_stmt.condition = Expression {
                         kind: ExpressionKind::Literal(Literal::Boolean(is_truthy)),
                         span: while_stmt.condition.span,
@@ -385,6 +390,7 @@ impl SccpPass {
                 // Try to resolve until condition
                 if let Some(lit) = self.try_evaluate(&repeat_stmt.until) {
                     let is_truthy = self.is_truthy(&lit);
+                    report_resolved(&repeat_stmt.until, is_truthy);
                     repeat_stmt.until = Expression {
                         kind: ExpressionKind::Literal(Literal::Boolean(is_truthy)),
                         span: repeat_stmt.until.span,
@@ -425,6 +431,18 @@ impl SccpPass {
     }
 }
 
+/// Report a condition resolved to a constant, unless it already was one
+fn report_resolved(condition: &Expression, is_truthy: bool) {
+    if !matches!(condition.kind, ExpressionKind::Literal(Literal::Boolean(_))) {
+        remarks::applied(PASS, condition.span, || {
+            format!(
+                "condition is always {}",
+                if is_truthy { "truthy" } else { "falsy" }
+            )
+        });
+    }
+}
+
 impl Default for SccpPass {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,96 @@
+//! Short identifier names for the size passes
+//!
+//! Names are handed out shortest first: `a`..`z`, `A`..`Z`, then `aa`, `ab`,
+//! ... with digits allowed after the first character.
+
+use rustc_hash::FxHashSet;
+
+const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+pub(super) const LUA_KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Names codegen emits inside function bodies (`M` for module exports, `e`
+/// for caught errors) and the standard globals short enough to be generated
+const RESERVED: &[&str] = &["M", "e", "io", "os"];
+
+/// The `index`-th name in shortest-first order
+fn short_name(mut index: usize) -> String {
+    let mut len = 1;
+    let mut count = FIRST.len();
+    while index >= count {
+        index -= count;
+        len += 1;
+        count *= REST.len();
+    }
+
+    let mut tail = Vec::with_capacity(len - 1);
+    for _ in 1..len {
+        tail.push(REST[index % REST.len()] as char);
+        index /= REST.len();
+    }
+    let mut name = String::with_capacity(len);
+    name.push(FIRST[index] as char);
+    name.extend(tail.into_iter().rev());
+    name
+}
+
+/// Hands out the shortest names that are not Lua keywords, reserved by
+/// codegen, or already `taken`
+pub(super) struct NameGenerator {
+    next: usize,
+    taken: FxHashSet<String>,
+}
+
+impl NameGenerator {
+    pub(super) fn new(taken: FxHashSet<String>) -> Self {
+        NameGenerator { next: 0, taken }
+    }
+
+    pub(super) fn next_name(&mut self) -> String {
+        loop {
+            let name = short_name(self.next);
+            self.next += 1;
+            if !LUA_KEYWORDS.contains(&name.as_str())
+                && !RESERVED.contains(&name.as_str())
+                && !self.taken.contains(&name)
+            {
+                return name;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_names_in_order() {
+        assert_eq!(short_name(0), "a");
+        assert_eq!(short_name(51), "Z");
+        assert_eq!(short_name(52), "aa");
+        assert_eq!(short_name(52 + 61), "a9");
+        assert_eq!(short_name(52 + 62), "ba");
+        assert_eq!(short_name(52 + 52 * 62), "aaa");
+    }
+
+    #[test]
+    fn test_generator_skips_keywords_and_taken() {
+        let taken: FxHashSet<String> = ["a", "c"].iter().map(|s| s.to_string()).collect();
+        let mut names = NameGenerator::new(taken);
+        assert_eq!(names.next_name(), "b");
+        assert_eq!(names.next_name(), "d");
+        // `e` is reserved for caught errors
+        assert_eq!(names.next_name(), "f");
+
+        let mut names = NameGenerator::new(FxHashSet::default());
+        let generated: Vec<String> = (0..200).map(|_| names.next_name()).collect();
+        assert!(!generated
+            .iter()
+            .any(|n| n == "do" || n == "if" || n == "or"));
+    }
+}
@@ -3,6 +3,7 @@
 // =============================================================================
 
 use crate::config::OptimizationLevel;
+use crate::optimizer::remarks::{self, statement_span};
 use crate::optimizer::{ExprVisitor, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
@@ -18,6 +19,8 @@ use luanext_parser::span::Span;
 use luanext_parser::string_interner::{StringId, StringInterner};
 use std::sync::Arc;
 
+const PASS: &str = "string-concat-optimization";
+
 const MIN_CONCAT_PARTS_FOR_OPTIMIZATION: usize = 3;
 
 pub struct StringConcatOptimizationPass {
@@ -50,7 +53,7 @@ impl<'arena> ExprVisitor<'arena> for StringConcatOptimizationPass {
 
 impl<'arena> WholeProgramPass<'arena> for StringConcatOptimizationPass {
     fn name(&self) -> &'static str {
-        "string-concat-optimization"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -275,6 +278,12 @@ impl StringConcatOptimizationPass {
                 if let Some(new_stmts) =
                     self.transform_loop_string_concat(statements, i, loop_idx, concat_var, arena)
                 {
+                    remarks::applied(PASS, statement_span(&statements[loop_idx]), || {
+                        format!(
+                            "loop appends to `{}` through a table buffer joined once with table.concat",
+                            self.interner.resolve(concat_var)
+                        )
+                    });
                     // Replace the original statements with transformed ones
                     statements.splice(i..=loop_idx, new_stmts);
                     changed = true;
@@ -733,6 +742,13 @@ impl StringConcatOptimizationPass {
         parts: &[Expression<'arena>],
         arena: &'arena Bump,
     ) {
+        remarks::applied(PASS, expr.span, || {
+            format!(
+                "joined {} concatenated parts with table.concat",
+                parts.len()
+            )
+        });
+
         let elements: Vec<ArrayElement<'arena>> = parts
             .iter()
             .map(|p| ArrayElement::Expression(p.clone()))
@@ -0,0 +1,253 @@
+//! String constant sharing (size goal)
+//!
+//! Hoists string literals that appear several times into locals declared at
+//! the top of the chunk:
+//!
+//! ```lua
+//! -- Before
+//! log("connection refused")
+//! error("connection refused")
+//!
+//! -- After
+//! local a = "connection refused"
+//! log(a)
+//! error(a)
+//! ```
+//!
+//! A literal is only shared when the declaration costs fewer bytes than the
+//! copies it replaces. Arguments of `require(...)` and `import(...)` stay
+//! literal because module resolution reads them.
+
+use super::short_names::NameGenerator;
+use crate::optimizer::{remarks, visit_expr_children, visit_stmt_expressions, WholeProgramPass};
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{Expression, ExpressionKind, Literal};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{Statement, VariableDeclaration, VariableKind};
+use luanext_parser::ast::Spanned;
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::Arc;
+
+const PASS: &str = "string-constant-sharing";
+
+/// Shared strings become locals of the main chunk, which Lua caps at 200
+const MAX_SHARED_CONSTANTS: usize = 32;
+
+pub struct StringConstantSharingPass {
+    interner: Arc<StringInterner>,
+    /// Strings shared by earlier runs, so later iterations reuse their names
+    shared: FxHashMap<String, StringId>,
+}
+
+impl StringConstantSharingPass {
+    pub fn new(interner: Arc<StringInterner>) -> Self {
+        Self {
+            interner,
+            shared: FxHashMap::default(),
+        }
+    }
+}
+
+impl<'arena> WholeProgramPass<'arena> for StringConstantSharingPass {
+    fn name(&self) -> &'static str {
+        PASS
+    }
+
+    fn run(
+        &mut self,
+        program: &mut MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<bool, String> {
+        let mut counts: FxHashMap<String, usize> = FxHashMap::default();
+        for stmt in &mut program.statements {
+            if self.is_shared_declaration(stmt) {
+                continue;
+            }
+            visit_stmt_expressions(stmt, arena, &mut |expr, arena| {
+                count_strings(expr, arena, &self.interner, &mut counts);
+                false
+            });
+        }
+
+        let constants = self.choose_constants(counts, program.span);
+        if constants.is_empty() {
+            return Ok(false);
+        }
+
+        for stmt in &mut program.statements {
+            if self.is_shared_declaration(stmt) {
+                continue;
+            }
+            visit_stmt_expressions(stmt, arena, &mut |expr, arena| {
+                replace_strings(expr, arena, &self.interner, &constants)
+            });
+        }
+
+        // Declarations go first so every use sees them
+        let declared = declared_names(&program.statements);
+        let mut declarations: Vec<(&String, &StringId)> = constants
+            .iter()
+            .filter(|(_, name)| !declared.contains(*name))
+            .collect();
+        declarations.sort_by_key(|(_, name)| self.interner.resolve(**name).to_string());
+        let span = program.span;
+        let declarations = declarations.into_iter().map(|(value, name)| {
+            Statement::Variable(VariableDeclaration {
+                kind: VariableKind::Local,
+                pattern: Pattern::Identifier(Spanned::new(*name, span)),
+                type_annotation: None,
+                initializer: Expression::new(
+                    ExpressionKind::Literal(Literal::String(value.clone())),
+                    span,
+                ),
+                span,
+            })
+        });
+        program.statements.splice(0..0, declarations);
+
+        Ok(true)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl StringConstantSharingPass {
+    /// Whether `stmt` is a `local <name> = "<value>"` this pass declared
+    fn is_shared_declaration(&self, stmt: &Statement) -> bool {
+        let Statement::Variable(decl) = stmt else {
+            return false;
+        };
+        let (Pattern::Identifier(ident), ExpressionKind::Literal(Literal::String(value))) =
+            (&decl.pattern, &decl.initializer.kind)
+        else {
+            return false;
+        };
+        self.shared.get(value) == Some(&ident.node)
+    }
+
+    /// Pick the strings worth sharing and name them, biggest savings first
+    fn choose_constants(
+        &mut self,
+        counts: FxHashMap<String, usize>,
+        span: Span,
+    ) -> FxHashMap<String, StringId> {
+        let mut candidates: Vec<(String, usize)> = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= 2)
+            .collect();
+        // Rank by the savings of a one-letter name; ties by value for stable output
+        candidates.sort_by(|(a, a_count), (b, b_count)| {
+            sharing_savings(b, *b_count, 1)
+                .cmp(&sharing_savings(a, *a_count, 1))
+                .then_with(|| a.cmp(b))
+        });
+
+        let taken: FxHashSet<String> = self.interner.to_strings().into_iter().collect();
+        let mut names = NameGenerator::new(taken);
+        let mut constants = FxHashMap::default();
+        for (value, count) in candidates.into_iter().take(MAX_SHARED_CONSTANTS) {
+            let name = match self.shared.get(&value) {
+                Some(&name) => name,
+                None => {
+                    let name = names.next_name();
+                    if sharing_savings(&value, count, name.len()) <= 0 {
+                        continue;
+                    }
+                    let name = self.interner.get_or_intern(&name);
+                    self.shared.insert(value.clone(), name);
+                    name
+                }
+            };
+            remarks::applied(PASS, span, || {
+                format!(
+                    "shared {} uses of {:?} as local `{}`",
+                    count,
+                    value,
+                    self.interner.resolve(name)
+                )
+            });
+            constants.insert(value, name);
+        }
+        constants
+    }
+}
+
+/// Bytes saved by declaring `local <name> = "<value>"` and replacing `count`
+/// copies of the literal with `name`
+fn sharing_savings(value: &str, count: usize, name_len: usize) -> i64 {
+    let quoted = format!("{:?}", value).len() as i64;
+    let count = count as i64;
+    let name_len = name_len as i64;
+    let declaration = "local ".len() as i64 + name_len + "=".len() as i64 + quoted + 1;
+    count * quoted - (declaration + count * name_len)
+}
+
+/// Whether `expr` is `require(...)` or `import(...)`, whose arguments are
+/// module specifiers
+fn is_module_specifier_call(expr: &Expression, interner: &StringInterner) -> bool {
+    let ExpressionKind::Call(callee, _, _) = &expr.kind else {
+        return false;
+    };
+    let ExpressionKind::Identifier(name) = &callee.kind else {
+        return false;
+    };
+    matches!(&*interner.resolve(*name), "require" | "import")
+}
+
+fn count_strings<'arena>(
+    expr: &mut Expression<'arena>,
+    arena: &'arena Bump,
+    interner: &StringInterner,
+    counts: &mut FxHashMap<String, usize>,
+) -> bool {
+    if is_module_specifier_call(expr, interner) {
+        return false;
+    }
+    if let ExpressionKind::Literal(Literal::String(value)) = &expr.kind {
+        *counts.entry(value.clone()).or_default() += 1;
+        return false;
+    }
+    visit_expr_children(expr, arena, &mut |e, a| {
+        count_strings(e, a, interner, counts)
+    })
+}
+
+fn replace_strings<'arena>(
+    expr: &mut Expression<'arena>,
+    arena: &'arena Bump,
+    interner: &StringInterner,
+    constants: &FxHashMap<String, StringId>,
+) -> bool {
+    if is_module_specifier_call(expr, interner) {
+        return false;
+    }
+    if let ExpressionKind::Literal(Literal::String(value)) = &expr.kind {
+        let Some(&name) = constants.get(value) else {
+            return false;
+        };
+        expr.kind = ExpressionKind::Identifier(name);
+        return true;
+    }
+    visit_expr_children(expr, arena, &mut |e, a| {
+        replace_strings(e, a, interner, constants)
+    })
+}
+
+/// Names declared by top-level `local` statements
+fn declared_names(statements: &[Statement]) -> FxHashSet<StringId> {
+    statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Statement::Variable(decl) => match &decl.pattern {
+                Pattern::Identifier(ident) => Some(ident.node),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
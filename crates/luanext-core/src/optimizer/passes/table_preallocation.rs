@@ -51,12 +51,14 @@
 // =============================================================================
 
 use crate::config::OptimizationLevel;
-use crate::optimizer::{ExprVisitor, WholeProgramPass};
+use crate::optimizer::{remarks, ExprVisitor, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
-use luanext_parser::ast::expression::Expression;
+use luanext_parser::ast::expression::{ArrayElement, Expression, ExpressionKind, ObjectProperty};
 use luanext_parser::ast::statement::Statement;
 
+const PASS: &str = "table-preallocation";
+
 /// Table preallocation pass (analysis + codegen optimization)
 ///
 /// This pass analyzes table construction patterns. The actual preallocation
@@ -73,10 +75,13 @@ impl TablePreallocationPass {
 }
 
 impl<'arena> ExprVisitor<'arena> for TablePreallocationPass {
-    fn visit_expr(&mut self, _expr: &mut Expression<'arena>, _arena: &'arena Bump) -> bool {
+    fn visit_expr(&mut self, expr: &mut Expression<'arena>, _arena: &'arena Bump) -> bool {
         // This pass is analysis-only at the optimizer level.
         // Actual preallocation optimizations are implemented directly in codegen
         // (see codegen/expressions.rs for array and object preallocation logic).
+        if remarks::is_enabled() {
+            self.report_preallocation(expr);
+        }
         false
     }
 }
@@ -121,6 +126,45 @@ impl<'arena> WholeProgramPass<'arena> for TablePreallocationPass {
 }
 
 impl TablePreallocationPass {
+    /// Explain what codegen will preallocate for a table constructor with spreads
+    fn report_preallocation(&self, expr: &Expression<'_>) {
+        let (known, kind) = match &expr.kind {
+            ExpressionKind::Array(elements)
+                if elements
+                    .iter()
+                    .any(|elem| matches!(elem, ArrayElement::Spread(_))) =>
+            {
+                let known = elements
+                    .iter()
+                    .filter(|elem| matches!(elem, ArrayElement::Expression(_)))
+                    .count();
+                (known, "array slot(s)")
+            }
+            ExpressionKind::Object(props)
+                if props
+                    .iter()
+                    .any(|prop| matches!(prop, ObjectProperty::Spread { .. })) =>
+            {
+                let known = props
+                    .iter()
+                    .filter(|prop| matches!(prop, ObjectProperty::Property { .. }))
+                    .count();
+                (known, "hash key(s)")
+            }
+            _ => return,
+        };
+
+        if known > 0 {
+            remarks::analysis(PASS, expr.span, || {
+                format!("codegen preallocates {} {} before spreading", known, kind)
+            });
+        } else {
+            remarks::missed(PASS, expr.span, || {
+                "table is built only from spreads; its size is unknown".to_string()
+            });
+        }
+    }
+
     fn count_tables_in_statement<'arena>(&self, stmt: &Statement<'arena>) -> usize {
         match stmt {
             Statement::Variable(decl) => self.count_tables_in_expression(&decl.initializer),
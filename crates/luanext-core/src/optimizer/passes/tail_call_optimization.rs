@@ -38,13 +38,15 @@
 // =============================================================================
 
 use crate::config::OptimizationLevel;
-use crate::optimizer::{StmtVisitor, WholeProgramPass};
+use crate::optimizer::{remarks, StmtVisitor, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::Expression;
 use luanext_parser::ast::expression::ExpressionKind;
 use luanext_parser::ast::statement::{ForStatement, Statement};
 
+const PASS: &str = "tail-call-optimization";
+
 /// Tail call optimization pass (analysis only)
 ///
 /// This pass analyzes tail call patterns and ensures other optimizations don't break
@@ -61,10 +63,15 @@ impl TailCallOptimizationPass {
 }
 
 impl<'arena> StmtVisitor<'arena> for TailCallOptimizationPass {
-    fn visit_stmt(&mut self, _stmt: &mut Statement<'arena>, _arena: &'arena Bump) -> bool {
+    fn visit_stmt(&mut self, stmt: &mut Statement<'arena>, _arena: &'arena Bump) -> bool {
         // This pass is analysis-only and never modifies the AST.
         // Lua's VM provides guaranteed tail call elimination, so we preserve
         // tail calls as-is to let the runtime optimize them.
+        if let Statement::Return(ret) = stmt {
+            if let [value] = ret.values {
+                self.report_return_value(value);
+            }
+        }
         false
     }
 }
@@ -108,6 +115,35 @@ impl<'arena> WholeProgramPass<'arena> for TailCallOptimizationPass {
 }
 
 impl TailCallOptimizationPass {
+    /// Report whether a single returned value is a call the Lua VM turns into a tail call
+    fn report_return_value(&self, value: &Expression<'_>) {
+        let is_call = |expr: &Expression<'_>| {
+            matches!(
+                expr.kind,
+                ExpressionKind::Call(..) | ExpressionKind::MethodCall(..)
+            )
+        };
+        match &value.kind {
+            _ if is_call(value) => {
+                remarks::analysis(PASS, value.span, || {
+                    "tail call; the Lua VM reuses the current stack frame".to_string()
+                });
+            }
+            ExpressionKind::Parenthesized(inner) if is_call(inner) => {
+                remarks::missed(PASS, value.span, || {
+                    "parentheses truncate the call to one result, so it is not a tail call"
+                        .to_string()
+                });
+            }
+            ExpressionKind::Binary(op, left, right) if is_call(left) || is_call(right) => {
+                remarks::missed(PASS, value.span, || {
+                    format!("call result is used by {:?}, so it is not a tail call", op)
+                });
+            }
+            _ => {}
+        }
+    }
+
     /// Analyzes a statement for tail call patterns and returns the count.
     ///
     /// A tail call is a function call that appears in tail position - the last operation
@@ -144,6 +144,7 @@ mod tests {
             exports: FxHashMap::default(),
             imports: FxHashMap::default(),
             re_exports: Vec::new(),
+            dynamic_imports: Vec::new(),
             is_reachable: false, // Even if marked unreachable
         };
         graph.modules.insert(entry_path.clone(), node);
@@ -167,6 +168,7 @@ mod tests {
             exports: FxHashMap::default(),
             imports: FxHashMap::default(),
             re_exports: Vec::new(),
+            dynamic_imports: Vec::new(),
             is_reachable: true,
         };
         graph.modules.insert(reachable_path.clone(), node);
@@ -189,6 +191,7 @@ mod tests {
             exports: FxHashMap::default(),
             imports: FxHashMap::default(),
             re_exports: Vec::new(),
+            dynamic_imports: Vec::new(),
             is_reachable: false,
         };
         graph.modules.insert(unreachable_path.clone(), node);
@@ -226,6 +229,7 @@ mod tests {
                 exports: FxHashMap::default(),
                 imports: FxHashMap::default(),
                 re_exports: Vec::new(),
+                dynamic_imports: Vec::new(),
                 is_reachable: true,
             };
             graph.modules.insert(path, node);
@@ -239,6 +243,7 @@ mod tests {
                 exports: FxHashMap::default(),
                 imports: FxHashMap::default(),
                 re_exports: Vec::new(),
+                dynamic_imports: Vec::new(),
                 is_reachable: false,
             };
             graph.modules.insert(path, node);
@@ -270,6 +275,7 @@ mod tests {
                 exports: FxHashMap::default(),
                 imports: FxHashMap::default(),
                 re_exports: Vec::new(),
+                dynamic_imports: Vec::new(),
                 is_reachable: true,
             };
             graph.modules.insert((*path).clone(), node);
@@ -280,6 +286,7 @@ mod tests {
             exports: FxHashMap::default(),
             imports: FxHashMap::default(),
             re_exports: Vec::new(),
+            dynamic_imports: Vec::new(),
             is_reachable: false,
         };
         graph.modules.insert(unreachable.clone(), node);
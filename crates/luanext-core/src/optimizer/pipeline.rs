@@ -0,0 +1,450 @@
+//! Configurable optimizer pass pipeline
+//!
+//! Every pass the [`Optimizer`](super::Optimizer) can run is listed in
+//! [`PASSES`] with the composite group it runs in and the lowest level that
+//! enables it by default. The `optimizer.passes` section of
+//! `luanext.config.yaml` adjusts that default:
+//!
+//! ```yaml
+//! optimizer:
+//!   passes:
+//!     disable: [loop-unrolling, function-cloning]
+//!     enable: [scalar-replacement]
+//!     order: [copy-propagation, sccp]
+//!     maxIterations: 4
+//! ```
+//!
+//! `enable` adds passes above the current level (the optimizer still runs
+//! nothing at O0), `disable` removes passes, and `order` moves the listed
+//! passes to the front of their group in the given order. Passes in
+//! different groups always run group by group, so `order` only reorders
+//! passes that share a traversal.
+//!
+//! Opt-in passes (see [`PassInfo::opt_in`]) never run by default; they run
+//! at O1 and above only when listed under `enable`.
+//!
+//! The [`OptimizationGoal`] picks between passes that trade size for speed
+//! and passes that shrink the output. `optimizer.goal: size` (or
+//! `--optimize-size`) skips the code-growing passes and runs the size passes.
+
+use crate::config::OptimizationLevel;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Iteration limit of the fixed-point loop when none is configured
+pub const DEFAULT_MAX_ITERATIONS: usize = 10;
+
+/// Traversal a pass runs in. Groups run in declaration order on every
+/// fixed-point iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PassGroup {
+    /// Expression visitors, one traversal
+    Expression,
+    /// Block visitors, one traversal
+    Elimination,
+    /// Statement visitors with pre-analysis, one traversal
+    Function,
+    /// Expression visitors for data structures, one traversal
+    DataStructure,
+    /// Whole-program passes, one traversal each
+    WholeProgram,
+    /// Cross-module passes the CLI applies before codegen
+    LinkTime,
+}
+
+impl PassGroup {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PassGroup::Expression => "expression-transforms",
+            PassGroup::Elimination => "elimination-transforms",
+            PassGroup::Function => "function-transforms",
+            PassGroup::DataStructure => "data-structure-transforms",
+            PassGroup::WholeProgram => "whole-program",
+            PassGroup::LinkTime => "link-time",
+        }
+    }
+}
+
+/// What the optimizer trades off when a pass makes code faster but larger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptimizationGoal {
+    /// Favor runtime speed (the default for every optimization level)
+    #[default]
+    Speed,
+    /// Favor small output, e.g. for embedded targets
+    Size,
+}
+
+impl OptimizationGoal {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OptimizationGoal::Speed => "speed",
+            OptimizationGoal::Size => "size",
+        }
+    }
+
+    /// Read `optimizer.goal` from the contents of a config file.
+    ///
+    /// Returns [`OptimizationGoal::Speed`] when the key is absent.
+    pub fn from_config_str(contents: &str) -> Result<Self, String> {
+        let root: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+        match root.get("optimizer").and_then(|section| section.get("goal")) {
+            Some(goal) => serde_yaml::from_value(goal.clone())
+                .map_err(|_| "optimizer.goal must be \"speed\" or \"size\"".to_string()),
+            None => Ok(OptimizationGoal::Speed),
+        }
+    }
+
+    /// Read `optimizer.goal` from a config file on disk.
+    pub fn from_config_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_config_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// A pass known to the optimizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PassInfo {
+    /// Name used in configuration, remarks and `--print-pass-pipeline`
+    pub name: &'static str,
+    pub group: PassGroup,
+    /// Lowest level that runs the pass unless the configuration says otherwise
+    pub default_level: OptimizationLevel,
+    /// Goal the pass serves; `None` for passes that help both
+    pub goal: Option<OptimizationGoal>,
+    /// Runs only when `optimizer.passes.enable` lists it
+    pub opt_in: bool,
+}
+
+const fn pass(name: &'static str, group: PassGroup, default_level: OptimizationLevel) -> PassInfo {
+    PassInfo {
+        name,
+        group,
+        default_level,
+        goal: None,
+        opt_in: false,
+    }
+}
+
+/// A pass that grows the output to make it faster
+const fn speed_pass(
+    name: &'static str,
+    group: PassGroup,
+    default_level: OptimizationLevel,
+) -> PassInfo {
+    PassInfo {
+        goal: Some(OptimizationGoal::Speed),
+        ..pass(name, group, default_level)
+    }
+}
+
+/// A pass that only runs when optimizing for size
+const fn size_pass(name: &'static str, group: PassGroup) -> PassInfo {
+    PassInfo {
+        goal: Some(OptimizationGoal::Size),
+        ..pass(name, group, OptimizationLevel::Minimal)
+    }
+}
+
+/// A pass that no level enables until the configuration asks for it
+const fn opt_in_pass(name: &'static str, group: PassGroup) -> PassInfo {
+    PassInfo {
+        opt_in: true,
+        ..pass(name, group, OptimizationLevel::Minimal)
+    }
+}
+
+/// Every optimizer pass, in default execution order within each group
+pub const PASSES: &[PassInfo] = &[
+    pass(
+        "constant-folding",
+        PassGroup::Expression,
+        OptimizationLevel::Minimal,
+    ),
+    pass(
+        "algebraic-simplification",
+        PassGroup::Expression,
+        OptimizationLevel::Minimal,
+    ),
+    pass(
+        "peephole-optimization",
+        PassGroup::Expression,
+        OptimizationLevel::Moderate,
+    ),
+    speed_pass(
+        "operator-inlining",
+        PassGroup::Expression,
+        OptimizationLevel::Aggressive,
+    ),
+    pass(
+        "dead-code-elimination",
+        PassGroup::Elimination,
+        OptimizationLevel::Minimal,
+    ),
+    pass("sccp", PassGroup::Elimination, OptimizationLevel::Moderate),
+    pass(
+        "jump-threading",
+        PassGroup::Elimination,
+        OptimizationLevel::Moderate,
+    ),
+    pass(
+        "copy-propagation",
+        PassGroup::Elimination,
+        OptimizationLevel::Moderate,
+    ),
+    pass(
+        "common-subexpression-elimination",
+        PassGroup::Elimination,
+        OptimizationLevel::Moderate,
+    ),
+    pass(
+        "dead-store-elimination",
+        PassGroup::Elimination,
+        OptimizationLevel::Moderate,
+    ),
+    size_pass("code-outlining", PassGroup::Elimination),
+    speed_pass(
+        "function-inlining",
+        PassGroup::Function,
+        OptimizationLevel::Moderate,
+    ),
+    pass(
+        "tail-call-optimization",
+        PassGroup::Function,
+        OptimizationLevel::Moderate,
+    ),
+    speed_pass(
+        "method-to-function-conversion",
+        PassGroup::Function,
+        OptimizationLevel::Moderate,
+    ),
+    speed_pass(
+        "aggressive-inlining",
+        PassGroup::Function,
+        OptimizationLevel::Aggressive,
+    ),
+    speed_pass(
+        "interface-method-inlining",
+        PassGroup::Function,
+        OptimizationLevel::Aggressive,
+    ),
+    pass(
+        "table-preallocation",
+        PassGroup::DataStructure,
+        OptimizationLevel::Moderate,
+    ),
+    speed_pass(
+        "string-concat-optimization",
+        PassGroup::DataStructure,
+        OptimizationLevel::Moderate,
+    ),
+    opt_in_pass("comptime-evaluation", PassGroup::WholeProgram),
+    opt_in_pass("array-iteration", PassGroup::WholeProgram),
+    opt_in_pass("integer-division", PassGroup::WholeProgram),
+    pass(
+        "loop-optimization",
+        PassGroup::WholeProgram,
+        OptimizationLevel::Moderate,
+    ),
+    pass(
+        "rich-enum-optimization",
+        PassGroup::WholeProgram,
+        OptimizationLevel::Moderate,
+    ),
+    pass(
+        "devirtualization",
+        PassGroup::WholeProgram,
+        OptimizationLevel::Aggressive,
+    ),
+    speed_pass(
+        "generic-specialization",
+        PassGroup::WholeProgram,
+        OptimizationLevel::Aggressive,
+    ),
+    speed_pass(
+        "loop-unrolling",
+        PassGroup::WholeProgram,
+        OptimizationLevel::Aggressive,
+    ),
+    opt_in_pass("induction-variables", PassGroup::WholeProgram),
+    speed_pass(
+        "function-cloning",
+        PassGroup::WholeProgram,
+        OptimizationLevel::Aggressive,
+    ),
+    pass(
+        "interprocedural-const-prop",
+        PassGroup::WholeProgram,
+        OptimizationLevel::Aggressive,
+    ),
+    pass(
+        "scalar-replacement",
+        PassGroup::WholeProgram,
+        OptimizationLevel::Aggressive,
+    ),
+    opt_in_pass("global-value-numbering", PassGroup::WholeProgram),
+    opt_in_pass("lambda-lifting", PassGroup::WholeProgram),
+    opt_in_pass("constant-pooling", PassGroup::WholeProgram),
+    opt_in_pass("branch-layout", PassGroup::WholeProgram),
+    // Registered at every level; run_passes still does nothing at O0
+    speed_pass(
+        "global-localization",
+        PassGroup::WholeProgram,
+        OptimizationLevel::None,
+    ),
+    size_pass("string-constant-sharing", PassGroup::WholeProgram),
+    size_pass("identifier-minification", PassGroup::WholeProgram),
+];
+
+/// Link-time passes the CLI applies per module before codegen. They honor
+/// `disable` but are not part of the fixed-point pipeline.
+pub const LTO_PASSES: &[PassInfo] = &[
+    pass(
+        "unused-module-elimination",
+        PassGroup::LinkTime,
+        OptimizationLevel::Moderate,
+    ),
+    pass(
+        "reexport-flattening",
+        PassGroup::LinkTime,
+        OptimizationLevel::Aggressive,
+    ),
+    pass(
+        "dead-import-elimination",
+        PassGroup::LinkTime,
+        OptimizationLevel::Moderate,
+    ),
+    pass(
+        "dead-export-elimination",
+        PassGroup::LinkTime,
+        OptimizationLevel::Moderate,
+    ),
+];
+
+/// Look up a pass by name
+pub fn find_pass(name: &str) -> Option<&'static PassInfo> {
+    PASSES.iter().find(|info| info.name == name)
+}
+
+/// The `optimizer.passes` section of `luanext.config.yaml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PassPipelineConfig {
+    /// Passes to run even though the level would not enable them
+    #[serde(default)]
+    pub enable: Vec<String>,
+    /// Passes to skip even though the level enables them
+    #[serde(default)]
+    pub disable: Vec<String>,
+    /// Passes to run first within their group, in this order
+    #[serde(default)]
+    pub order: Vec<String>,
+    /// Upper bound on fixed-point iterations
+    #[serde(default)]
+    pub max_iterations: Option<usize>,
+}
+
+impl PassPipelineConfig {
+    /// Read `optimizer.passes` from the contents of a config file.
+    ///
+    /// Returns the default pipeline when the section is absent.
+    pub fn from_config_str(contents: &str) -> Result<Self, String> {
+        let root: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+        let config = match root
+            .get("optimizer")
+            .and_then(|section| section.get("passes"))
+        {
+            Some(section) => serde_yaml::from_value(section.clone()).map_err(|e| e.to_string())?,
+            None => PassPipelineConfig::default(),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read `optimizer.passes` from a config file on disk.
+    pub fn from_config_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_config_str(&contents).map_err(|e| {
+            format!(
+                "Invalid optimizer.passes section in {}: {}",
+                path.display(),
+                e
+            )
+        })
+    }
+
+    /// Reject unknown pass names and a zero iteration limit
+    pub fn validate(&self) -> Result<(), String> {
+        for name in self.enable.iter().chain(&self.order) {
+            if find_pass(name).is_none() {
+                return Err(format!("unknown optimizer pass `{}`", name));
+            }
+        }
+        for name in &self.disable {
+            if find_pass(name).is_none() && !LTO_PASSES.iter().any(|info| info.name == name) {
+                return Err(format!("unknown optimizer pass `{}`", name));
+            }
+        }
+        if self.max_iterations == Some(0) {
+            return Err("maxIterations must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether `name` is listed under `disable`
+    pub fn is_disabled(&self, name: &str) -> bool {
+        self.disable.iter().any(|n| n == name)
+    }
+
+    /// Whether a pass runs at `level` and `goal` under this configuration
+    pub fn is_enabled(
+        &self,
+        info: &PassInfo,
+        level: OptimizationLevel,
+        goal: OptimizationGoal,
+    ) -> bool {
+        if self.is_disabled(info.name) {
+            return false;
+        }
+        if self.enable.iter().any(|n| n == info.name) {
+            return true;
+        }
+        !info.opt_in && level >= info.default_level && info.goal.is_none_or(|g| g == goal)
+    }
+
+    /// The link-time passes that run at `level`
+    pub fn link_time_passes(&self, level: OptimizationLevel) -> Vec<&'static PassInfo> {
+        LTO_PASSES
+            .iter()
+            .filter(|info| level >= info.default_level && !self.is_disabled(info.name))
+            .collect()
+    }
+
+    pub fn max_iterations(&self) -> usize {
+        self.max_iterations.unwrap_or(DEFAULT_MAX_ITERATIONS)
+    }
+
+    /// The passes that run at `level` and `goal`, in execution order within
+    /// each group
+    pub fn resolve(
+        &self,
+        level: OptimizationLevel,
+        goal: OptimizationGoal,
+    ) -> Vec<&'static PassInfo> {
+        let mut passes: Vec<(usize, &'static PassInfo)> = PASSES
+            .iter()
+            .enumerate()
+            .filter(|(_, info)| self.is_enabled(info, level, goal))
+            .collect();
+        passes.sort_by_key(
+            |(index, info)| match self.order.iter().position(|n| n == info.name) {
+                Some(position) => (0, position),
+                None => (1, *index),
+            },
+        );
+        passes.into_iter().map(|(_, info)| info).collect()
+    }
+}
@@ -0,0 +1,392 @@
+//! Profile-guided optimization
+//!
+//! A `--profile-generate` build runs [`instrument`] on every module. It puts a
+//! counter at the start of every function declaration body, every loop body
+//! and every arm of an `if`, adding an empty `else` arm so both directions of
+//! a branch are counted. Counters are keyed by what they count and where the
+//! construct starts, so a later build of the same sources finds them again.
+//! The instrumented program writes the counts when it exits:
+//!
+//! ```text
+//! # luanext profile v1
+//! module	src/main.luax
+//! branch 9:9 0	47990
+//! branch 9:9 1	10
+//! call 3:1	1200
+//! loop 7:5	48000
+//! ```
+//!
+//! A `--profile-use` build reads the file with [`Profile::from_file`] and
+//! hands each module's counts to the optimizer through
+//! [`Optimizer::set_profile`](super::Optimizer::set_profile). A function or
+//! loop is hot when its count is among the highest counts that together make
+//! up 90% of all counts of its kind, and cold when its module ran but it never
+//! did. Hot functions get higher inlining and cloning limits and hot loops a
+//! higher unroll limit. Cold functions and loops are never inlined, cloned or
+//! unrolled. `branch-layout` puts the most taken arm of an `if` first.
+
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{Argument, Expression, ExpressionKind, Literal};
+use luanext_parser::ast::statement::{Block, ExportKind, ForStatement, Statement};
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::FxHashMap;
+use std::path::Path;
+
+/// Local that the module preamble of an instrumented build binds to the
+/// module's counter function
+pub const HIT_FUNCTION: &str = "__luanext_hit";
+
+/// Share of all counts of a kind that the hot counters make up, in percent
+const HOT_COUNT_PERCENT: u128 = 90;
+
+/// What a counter counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CounterKind {
+    /// Entries into a function body
+    Call,
+    /// Iterations of a loop body
+    Loop,
+    /// Runs of an `if` arm: 0 is `then`, then the `elseif`s, then `else`
+    Branch(usize),
+}
+
+/// A profile counter: what it counts and where the construct starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Counter {
+    pub kind: CounterKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Counter {
+    pub fn new(kind: CounterKind, span: Span) -> Self {
+        Self {
+            kind,
+            line: span.line as usize,
+            column: span.column as usize,
+        }
+    }
+
+    /// Key used by the instrumented program and the profile file, e.g.
+    /// `loop 7:5` or `branch 9:9 1`
+    pub fn key(&self) -> String {
+        match self.kind {
+            CounterKind::Call => format!("call {}:{}", self.line, self.column),
+            CounterKind::Loop => format!("loop {}:{}", self.line, self.column),
+            CounterKind::Branch(arm) => format!("branch {}:{} {}", self.line, self.column, arm),
+        }
+    }
+
+    /// Parse a key written by [`Counter::key`]
+    pub fn parse(key: &str) -> Option<Self> {
+        let mut parts = key.split(' ');
+        let kind = parts.next()?;
+        let (line, column) = parts.next()?.split_once(':')?;
+        let kind = match (kind, parts.next()) {
+            ("call", None) => CounterKind::Call,
+            ("loop", None) => CounterKind::Loop,
+            ("branch", Some(arm)) => CounterKind::Branch(arm.parse().ok()?),
+            _ => return None,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            kind,
+            line: line.parse().ok()?,
+            column: column.parse().ok()?,
+        })
+    }
+}
+
+/// How often a function or loop ran compared to the rest of the program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotness {
+    Hot,
+    /// Neither hot nor cold
+    Normal,
+    /// Never ran, although its module did
+    Cold,
+}
+
+/// Counts read from a profile file
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    modules: FxHashMap<String, FxHashMap<Counter, u64>>,
+    hot_call_count: u64,
+    hot_loop_count: u64,
+}
+
+impl Profile {
+    /// Parse the contents of a profile file. Counts of repeated counters add up.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modules: FxHashMap<String, FxHashMap<Counter, u64>> = FxHashMap::default();
+        let mut current: Option<String> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || format!("line {}: invalid profile entry `{}`", index + 1, line);
+            let (key, value) = line.split_once('\t').ok_or_else(invalid)?;
+            if key == "module" {
+                modules.entry(value.to_string()).or_default();
+                current = Some(value.to_string());
+                continue;
+            }
+
+            let Some(module) = &current else {
+                return Err(format!(
+                    "line {}: counter before the first `module` line",
+                    index + 1
+                ));
+            };
+            let counter = Counter::parse(key).ok_or_else(invalid)?;
+            let count: u64 = value.parse().map_err(|_| invalid())?;
+            *modules
+                .entry(module.clone())
+                .or_default()
+                .entry(counter)
+                .or_default() += count;
+        }
+
+        let counts_of = |kind: CounterKind| {
+            modules
+                .values()
+                .flat_map(|counts| counts.iter())
+                .filter(move |(counter, _)| counter.kind == kind)
+                .map(|(_, &count)| count)
+                .collect::<Vec<_>>()
+        };
+        let hot_call_count = hot_threshold(counts_of(CounterKind::Call));
+        let hot_loop_count = hot_threshold(counts_of(CounterKind::Loop));
+
+        Ok(Self {
+            modules,
+            hot_call_count,
+            hot_loop_count,
+        })
+    }
+
+    /// Read a profile file from disk
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Counts of one module, `None` if it never ran while profiling
+    pub fn module(&self, module: &str) -> Option<ModuleProfile> {
+        self.modules.get(module).map(|counts| ModuleProfile {
+            counts: counts.clone(),
+            hot_call_count: self.hot_call_count,
+            hot_loop_count: self.hot_loop_count,
+        })
+    }
+}
+
+/// Smallest count among the highest counts that make up `HOT_COUNT_PERCENT`
+/// of the total, or `u64::MAX` when nothing ran
+fn hot_threshold(mut counts: Vec<u64>) -> u64 {
+    counts.retain(|&count| count > 0);
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    let total: u128 = counts.iter().map(|&count| count as u128).sum();
+
+    let mut covered = 0u128;
+    for count in counts {
+        covered += count as u128;
+        if covered * 100 >= total * HOT_COUNT_PERCENT {
+            return count;
+        }
+    }
+    u64::MAX
+}
+
+/// The counts of one module, as the optimizer passes see them
+#[derive(Debug, Clone, Default)]
+pub struct ModuleProfile {
+    counts: FxHashMap<Counter, u64>,
+    hot_call_count: u64,
+    hot_loop_count: u64,
+}
+
+impl ModuleProfile {
+    /// How often `counter` ran; counters that never ran are not in the file
+    pub fn count(&self, counter: Counter) -> u64 {
+        self.counts.get(&counter).copied().unwrap_or(0)
+    }
+
+    /// Hotness of the function declared at `span`
+    pub fn function_hotness(&self, span: Span) -> Hotness {
+        Self::hotness(
+            self.count(Counter::new(CounterKind::Call, span)),
+            self.hot_call_count,
+        )
+    }
+
+    /// Hotness of the loop at `span`
+    pub fn loop_hotness(&self, span: Span) -> Hotness {
+        Self::hotness(
+            self.count(Counter::new(CounterKind::Loop, span)),
+            self.hot_loop_count,
+        )
+    }
+
+    /// How often arm `arm` of the `if` at `span` ran
+    pub fn branch_count(&self, span: Span, arm: usize) -> u64 {
+        self.count(Counter::new(CounterKind::Branch(arm), span))
+    }
+
+    fn hotness(count: u64, hot_count: u64) -> Hotness {
+        if count == 0 {
+            Hotness::Cold
+        } else if count >= hot_count {
+            Hotness::Hot
+        } else {
+            Hotness::Normal
+        }
+    }
+}
+
+/// Insert the counters of a `--profile-generate` build into `program`.
+///
+/// The counters call [`HIT_FUNCTION`] with their [`Counter::key`]; codegen
+/// binds it in the module preamble.
+pub fn instrument<'arena>(
+    program: &mut MutableProgram<'arena>,
+    arena: &'arena Bump,
+    interner: &StringInterner,
+) {
+    let instrumenter = Instrumenter {
+        hit: interner.get_or_intern(HIT_FUNCTION),
+        arena,
+    };
+    for stmt in &mut program.statements {
+        instrumenter.visit_stmt(stmt);
+    }
+}
+
+struct Instrumenter<'arena> {
+    hit: StringId,
+    arena: &'arena Bump,
+}
+
+impl<'arena> Instrumenter<'arena> {
+    /// `__luanext_hit("<key>")`
+    fn hit_stmt(&self, counter: Counter, span: Span) -> Statement<'arena> {
+        let callee = Expression::new(ExpressionKind::Identifier(self.hit), span);
+        let key = Expression::new(
+            ExpressionKind::Literal(Literal::String(counter.key())),
+            span,
+        );
+        let arguments = self.arena.alloc_slice_clone(&[Argument {
+            value: key,
+            is_spread: false,
+            span,
+        }]);
+        Statement::Expression(Expression::new(
+            ExpressionKind::Call(self.arena.alloc(callee), arguments, None),
+            span,
+        ))
+    }
+
+    /// Instrument `block` and count its runs with `counter`
+    fn visit_counted_block(&self, block: &mut Block<'arena>, counter: Counter) {
+        let mut stmts = Vec::with_capacity(block.statements.len() + 1);
+        stmts.push(self.hit_stmt(counter, block.span));
+        stmts.extend(block.statements.iter().cloned());
+        for stmt in &mut stmts[1..] {
+            self.visit_stmt(stmt);
+        }
+        block.statements = self.arena.alloc_slice_clone(&stmts);
+    }
+
+    fn visit_block(&self, block: &mut Block<'arena>) {
+        let mut stmts = block.statements.to_vec();
+        for stmt in &mut stmts {
+            self.visit_stmt(stmt);
+        }
+        block.statements = self.arena.alloc_slice_clone(&stmts);
+    }
+
+    fn visit_stmt(&self, stmt: &mut Statement<'arena>) {
+        match stmt {
+            Statement::Function(func) => {
+                let counter = Counter::new(CounterKind::Call, func.span);
+                self.visit_counted_block(&mut func.body, counter);
+            }
+            Statement::If(if_stmt) => {
+                let span = if_stmt.span;
+                self.visit_counted_block(
+                    &mut if_stmt.then_block,
+                    Counter::new(CounterKind::Branch(0), span),
+                );
+                let mut else_ifs = if_stmt.else_ifs.to_vec();
+                for (index, else_if) in else_ifs.iter_mut().enumerate() {
+                    self.visit_counted_block(
+                        &mut else_if.block,
+                        Counter::new(CounterKind::Branch(index + 1), span),
+                    );
+                }
+                let mut else_block = if_stmt.else_block.clone().unwrap_or(Block {
+                    statements: &[],
+                    span,
+                });
+                self.visit_counted_block(
+                    &mut else_block,
+                    Counter::new(CounterKind::Branch(else_ifs.len() + 1), span),
+                );
+                if_stmt.else_ifs = self.arena.alloc_slice_clone(&else_ifs);
+                if_stmt.else_block = Some(else_block);
+            }
+            Statement::While(while_stmt) => {
+                let counter = Counter::new(CounterKind::Loop, while_stmt.span);
+                self.visit_counted_block(&mut while_stmt.body, counter);
+            }
+            Statement::Repeat(repeat_stmt) => {
+                let counter = Counter::new(CounterKind::Loop, repeat_stmt.span);
+                self.visit_counted_block(&mut repeat_stmt.body, counter);
+            }
+            Statement::For(for_stmt) => match &**for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    let mut new_num = (**for_num).clone();
+                    let counter = Counter::new(CounterKind::Loop, new_num.span);
+                    self.visit_counted_block(&mut new_num.body, counter);
+                    *stmt = Statement::For(
+                        self.arena
+                            .alloc(ForStatement::Numeric(self.arena.alloc(new_num))),
+                    );
+                }
+                ForStatement::Generic(for_gen) => {
+                    let mut new_gen = for_gen.clone();
+                    let counter = Counter::new(CounterKind::Loop, new_gen.span);
+                    self.visit_counted_block(&mut new_gen.body, counter);
+                    *stmt = Statement::For(self.arena.alloc(ForStatement::Generic(new_gen)));
+                }
+            },
+            Statement::Block(block) => self.visit_block(block),
+            Statement::Try(try_stmt) => {
+                self.visit_block(&mut try_stmt.try_block);
+                let mut catch_clauses = try_stmt.catch_clauses.to_vec();
+                for catch in &mut catch_clauses {
+                    self.visit_block(&mut catch.body);
+                }
+                try_stmt.catch_clauses = self.arena.alloc_slice_clone(&catch_clauses);
+                if let Some(finally_block) = &mut try_stmt.finally_block {
+                    self.visit_block(finally_block);
+                }
+            }
+            Statement::Export(export) => {
+                if let ExportKind::Declaration(inner) = export.kind {
+                    let mut new_inner = inner.clone();
+                    self.visit_stmt(&mut new_inner);
+                    export.kind = ExportKind::Declaration(self.arena.alloc(new_inner));
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Optimization remarks
+//!
+//! Passes report the transformations they applied and the opportunities they
+//! had to give up on, each tagged with the source span and a reason, similar
+//! to LLVM's `-Rpass` / `-Rpass-missed`. Remarks are only recorded while a
+//! [`collect`] call is active on the current thread, so reporting is a cheap
+//! no-op in normal builds and messages are only formatted when requested.
+
+use luanext_parser::span::Span;
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+pub(crate) use super::analysis::cfg::statement_span;
+
+/// Whether a remark describes a transformation or a missed opportunity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RemarkKind {
+    /// The pass transformed the code at this location
+    Applied,
+    /// The pass considered this location but did not transform it
+    Missed,
+    /// Information computed by the pass that explains later decisions
+    Analysis,
+}
+
+impl RemarkKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RemarkKind::Applied => "applied",
+            RemarkKind::Missed => "missed",
+            RemarkKind::Analysis => "analysis",
+        }
+    }
+}
+
+/// A single optimization remark
+#[derive(Debug, Clone, PartialEq)]
+pub struct Remark {
+    /// Name of the reporting pass, as returned by `Optimizer::pass_names`
+    pub pass: &'static str,
+    pub kind: RemarkKind,
+    pub span: Span,
+    pub message: String,
+}
+
+thread_local! {
+    static SINK: RefCell<Option<Vec<Remark>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, collecting every remark reported on this thread while it runs.
+///
+/// Remarks repeated by the optimizer's fixed-point iterations are reported
+/// once, in the order they were first seen.
+pub fn collect<R>(f: impl FnOnce() -> R) -> (R, Vec<Remark>) {
+    let outer = SINK.with(|sink| sink.replace(Some(Vec::new())));
+    let result = f();
+    let collected = SINK.with(|sink| sink.replace(outer)).unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    let remarks = collected
+        .into_iter()
+        .filter(|r| seen.insert((r.pass, r.kind, r.span.start, r.span.end, r.message.clone())))
+        .collect();
+    (result, remarks)
+}
+
+/// Whether remarks are being collected on this thread
+pub fn is_enabled() -> bool {
+    SINK.with(|sink| sink.borrow().is_some())
+}
+
+/// Report a remark; `message` is only evaluated while collecting
+pub fn report(pass: &'static str, kind: RemarkKind, span: Span, message: impl FnOnce() -> String) {
+    SINK.with(|sink| {
+        if let Some(remarks) = sink.borrow_mut().as_mut() {
+            remarks.push(Remark {
+                pass,
+                kind,
+                span,
+                message: message(),
+            });
+        }
+    });
+}
+
+/// Report a transformation `pass` applied at `span`
+pub fn applied(pass: &'static str, span: Span, message: impl FnOnce() -> String) {
+    report(pass, RemarkKind::Applied, span, message);
+}
+
+/// Report a transformation `pass` considered at `span` but did not apply
+pub fn missed(pass: &'static str, span: Span, message: impl FnOnce() -> String) {
+    report(pass, RemarkKind::Missed, span, message);
+}
+
+/// Report an analysis result of `pass` at `span`
+pub fn analysis(pass: &'static str, span: Span, message: impl FnOnce() -> String) {
+    report(pass, RemarkKind::Analysis, span, message);
+}
@@ -1,5 +1,5 @@
 use crate::config::OptimizationLevel;
-use crate::optimizer::{AstFeatures, WholeProgramPass};
+use crate::optimizer::{remarks, AstFeatures, WholeProgramPass};
 use crate::MutableProgram;
 use bumpalo::Bump;
 use luanext_parser::ast::expression::{Expression, ExpressionKind};
@@ -7,6 +7,8 @@ use luanext_parser::ast::statement::{Block, EnumDeclaration, Statement};
 use luanext_parser::string_interner::StringId;
 use rustc_hash::{FxHashMap, FxHashSet};
 
+const PASS: &str = "rich-enum-optimization";
+
 pub struct RichEnumOptimizationPass {
     /// Maps enum name to its fields
     enum_fields: FxHashMap<StringId, FxHashSet<StringId>>,
@@ -46,6 +48,14 @@ impl RichEnumOptimizationPass {
                             simple_methods.insert(method.name.node);
                         }
                     }
+                    remarks::analysis(PASS, enum_decl.span, || {
+                        format!(
+                            "rich enum with {} field(s) and {} of {} method(s) simple enough to inline",
+                            enum_decl.fields.len(),
+                            simple_methods.len(),
+                            enum_decl.methods.len()
+                        )
+                    });
                     if !simple_methods.is_empty() {
                         self.enum_simple_methods.insert(enum_name, simple_methods);
                     }
@@ -76,7 +86,7 @@ impl RichEnumOptimizationPass {
 
 impl<'arena> WholeProgramPass<'arena> for RichEnumOptimizationPass {
     fn name(&self) -> &'static str {
-        "rich-enum-optimization"
+        PASS
     }
 
     fn min_level(&self) -> OptimizationLevel {
@@ -0,0 +1,93 @@
+//! Per-pass output size report
+//!
+//! Measures how many bytes of generated Lua each optimizer pass saves. Passes
+//! are switched on one at a time in pipeline order and the program is
+//! re-optimized and re-emitted after each step, so a pass is credited with the
+//! bytes it removes on top of the passes before it. Passes that grow the
+//! output show up with a negative saving.
+
+use super::pipeline::{OptimizationGoal, PassPipelineConfig};
+use super::Optimizer;
+use crate::codegen::LuaTarget;
+use crate::config::OptimizationLevel;
+use crate::diagnostics::DiagnosticHandler;
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::string_interner::StringInterner;
+use std::sync::Arc;
+
+/// Bytes a single pass removed from the output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassSizeSaving {
+    pub pass: &'static str,
+    pub bytes_saved: i64,
+}
+
+/// Output size before and after optimization, broken down by pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeReport {
+    pub original_bytes: usize,
+    pub optimized_bytes: usize,
+    pub passes: Vec<PassSizeSaving>,
+}
+
+impl SizeReport {
+    pub fn bytes_saved(&self) -> i64 {
+        self.original_bytes as i64 - self.optimized_bytes as i64
+    }
+}
+
+/// Optimize `program` once per enabled pass and report each pass's saving.
+///
+/// `program` itself is left untouched. `emit` turns an optimized copy into
+/// the Lua whose length is measured.
+#[allow(clippy::too_many_arguments)]
+pub fn measure<'arena>(
+    program: &MutableProgram<'arena>,
+    arena: &'arena Bump,
+    level: OptimizationLevel,
+    goal: OptimizationGoal,
+    target: LuaTarget,
+    config: &PassPipelineConfig,
+    handler: Arc<dyn DiagnosticHandler>,
+    interner: Arc<StringInterner>,
+    emit: &mut dyn FnMut(&MutableProgram<'arena>) -> String,
+) -> Result<SizeReport, String> {
+    let original_bytes = emit(program).len();
+
+    let mut full = Optimizer::new(level, handler.clone(), interner.clone());
+    full.set_goal(goal);
+    full.set_target(target);
+    full.set_pass_pipeline(config.clone());
+    let pipeline: Vec<&'static str> = full.pass_pipeline().iter().map(|p| p.name).collect();
+
+    let mut passes = Vec::with_capacity(pipeline.len());
+    let mut previous_bytes = original_bytes;
+    for (i, &pass) in pipeline.iter().enumerate() {
+        let mut step_config = config.clone();
+        step_config
+            .disable
+            .extend(pipeline[i + 1..].iter().map(|name| name.to_string()));
+
+        let mut optimizer = Optimizer::new(level, handler.clone(), interner.clone());
+        optimizer.set_goal(goal);
+        optimizer.set_target(target);
+        optimizer.set_pass_pipeline(step_config);
+
+        let mut optimized = program.clone();
+        optimizer.optimize(&mut optimized, arena)?;
+        let bytes = emit(&optimized).len();
+
+        passes.push(PassSizeSaving {
+            pass,
+            bytes_saved: previous_bytes as i64 - bytes as i64,
+        });
+        previous_bytes = bytes;
+    }
+
+    Ok(SizeReport {
+        original_bytes,
+        optimized_bytes: previous_bytes,
+        passes,
+    })
+}
@@ -0,0 +1,693 @@
+//! AST invariant verifier
+//!
+//! Passes rewrite `MutableProgram` by cloning and rebuilding nodes, so a pass
+//! that drops a declaration or moves a `break` out of its loop only shows up
+//! later as broken Lua. With `Optimizer::enable_verifier` the optimizer
+//! checks these invariants after every pass and names the pass that broke
+//! one:
+//! - every identifier resolves to a parameter, a local declared before it, a
+//!   Lua standard global or a global the original program already used
+//! - no scope declares the same local twice
+//! - `break` and `continue` appear inside a loop of the same function, and
+//!   every `goto` has a visible label
+//! - no span ends before it starts
+//!
+//! Only violations the original program did not already have are reported,
+//! so code the optimizer received as-is never fails verification.
+//!
+//! Constructs the verifier does not model (classes, `match`, `try`
+//! expressions, template strings, imports, namespaces) are not descended
+//! into; names they declare count as globals of the original program.
+
+use super::analysis::module_graph::ModuleGraph;
+use super::hints::FreeFunctionHints;
+use super::pipeline::{OptimizationGoal, PassPipelineConfig};
+use super::profile::ModuleProfile;
+use super::{remarks, Optimizer, WholeProgramAnalysis};
+use crate::codegen::LuaTarget;
+use crate::config::OptimizationLevel;
+use crate::diagnostics::DiagnosticHandler;
+use crate::MutableProgram;
+use bumpalo::Bump;
+use luanext_parser::ast::expression::{
+    ArrayElement, ArrowBody, Expression, ExpressionKind, ObjectProperty,
+};
+use luanext_parser::ast::pattern::{ArrayPatternElement, Pattern};
+use luanext_parser::ast::statement::{
+    Block, CatchPattern, ExportKind, ForStatement, Parameter, Statement, VariableKind,
+};
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::{StringId, StringInterner};
+use rustc_hash::FxHashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Globals of the Lua standard library, which passes may reference freely
+const LUA_GLOBALS: &[&str] = &[
+    "_G",
+    "_VERSION",
+    "_ENV",
+    "assert",
+    "bit32",
+    "collectgarbage",
+    "coroutine",
+    "debug",
+    "dofile",
+    "error",
+    "getmetatable",
+    "io",
+    "ipairs",
+    "load",
+    "loadfile",
+    "loadstring",
+    "math",
+    "next",
+    "os",
+    "package",
+    "pairs",
+    "pcall",
+    "print",
+    "rawequal",
+    "rawget",
+    "rawlen",
+    "rawset",
+    "require",
+    "select",
+    "setmetatable",
+    "string",
+    "table",
+    "tonumber",
+    "tostring",
+    "type",
+    "unpack",
+    "utf8",
+    "xpcall",
+];
+
+/// Which invariant a violation breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    /// An identifier that no declaration in scope or known global provides
+    UnresolvedIdentifier,
+    /// A local declared twice in the same scope
+    DuplicateLocal,
+    /// `break` or `continue` outside a loop
+    JumpOutsideLoop,
+    /// `goto` without a visible label
+    UndefinedLabel,
+    /// A span whose end is before its start
+    InvalidSpan,
+}
+
+impl ViolationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ViolationKind::UnresolvedIdentifier => "unresolved-identifier",
+            ViolationKind::DuplicateLocal => "duplicate-local",
+            ViolationKind::JumpOutsideLoop => "jump-outside-loop",
+            ViolationKind::UndefinedLabel => "undefined-label",
+            ViolationKind::InvalidSpan => "invalid-span",
+        }
+    }
+}
+
+/// A broken AST invariant
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub span: Span,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} [{}]",
+            self.span.line,
+            self.span.column,
+            self.message,
+            self.kind.as_str()
+        )
+    }
+}
+
+/// Checks optimized programs against the program the optimizer started from
+pub struct Verifier {
+    interner: Arc<StringInterner>,
+    /// Names that resolve without a declaration
+    globals: FxHashSet<StringId>,
+    /// Violations the original program already had, by kind and message
+    baseline: FxHashSet<(ViolationKind, String)>,
+}
+
+impl Verifier {
+    /// Record the globals and pre-existing violations of `program`
+    pub fn new(program: &MutableProgram<'_>, interner: Arc<StringInterner>) -> Self {
+        let globals: FxHashSet<StringId> = LUA_GLOBALS
+            .iter()
+            .map(|name| interner.get_or_intern(name))
+            .collect();
+
+        let mut walker = Walker::new(&interner, &globals);
+        walker.visit_program(program);
+        let unresolved = std::mem::take(&mut walker.unresolved);
+        let baseline = walker
+            .violations
+            .into_iter()
+            .filter(|v| v.kind != ViolationKind::UnresolvedIdentifier)
+            .map(|v| (v.kind, v.message))
+            .collect();
+
+        let mut globals = globals;
+        globals.extend(unresolved);
+        Self {
+            interner,
+            globals,
+            baseline,
+        }
+    }
+
+    /// Violations in `program` that the original program did not have
+    pub fn check(&self, program: &MutableProgram<'_>) -> Vec<Violation> {
+        let mut walker = Walker::new(&self.interner, &self.globals);
+        walker.visit_program(program);
+        walker
+            .violations
+            .into_iter()
+            .filter(|v| !self.baseline.contains(&(v.kind, v.message.clone())))
+            .collect()
+    }
+}
+
+/// Verifies the program after each optimizer pass group and attributes new
+/// violations to a single pass
+pub(crate) struct PassVerifier {
+    verifier: Verifier,
+    level: OptimizationLevel,
+    goal: OptimizationGoal,
+    target: LuaTarget,
+    config: PassPipelineConfig,
+    handler: Arc<dyn DiagnosticHandler>,
+    /// Every registered pass, so a replay can switch off all but one
+    pipeline: Vec<&'static str>,
+    profile: Option<Arc<ModuleProfile>>,
+    function_hints: Arc<FreeFunctionHints>,
+    whole_program_analysis: Option<WholeProgramAnalysis>,
+    module_graph: Option<Arc<ModuleGraph>>,
+    current_module_path: Option<PathBuf>,
+}
+
+impl PassVerifier {
+    /// Verify against `program`, replaying passes with the settings of
+    /// `optimizer`
+    pub(crate) fn new(program: &MutableProgram<'_>, optimizer: &Optimizer<'_>) -> Self {
+        Self {
+            verifier: Verifier::new(program, optimizer.interner.clone()),
+            level: optimizer.level,
+            goal: optimizer.goal,
+            target: optimizer.target,
+            config: optimizer.pipeline_config.clone(),
+            handler: optimizer.handler.clone(),
+            pipeline: optimizer.pass_names(),
+            profile: optimizer.profile.clone(),
+            function_hints: optimizer.function_hints.clone(),
+            whole_program_analysis: optimizer.whole_program_analysis.clone(),
+            module_graph: optimizer.module_graph.clone(),
+            current_module_path: optimizer.current_module_path.clone(),
+        }
+    }
+
+    /// Check `after`, the result of running `passes` on `before`.
+    ///
+    /// When several passes ran in one traversal, each is replayed alone on
+    /// `before` to find the one that breaks an invariant.
+    pub(crate) fn check<'arena>(
+        &self,
+        passes: &[&'static str],
+        before: &MutableProgram<'arena>,
+        after: &MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Result<(), String> {
+        let violations = self.verifier.check(after);
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        let culprit = match passes {
+            [pass] => Some(*pass),
+            _ => passes.iter().copied().find(|&pass| {
+                self.replay(pass, before, arena)
+                    .is_some_and(|violations| !violations.is_empty())
+            }),
+        };
+        let description = match culprit {
+            Some(pass) => format!("pass `{}`", pass),
+            None => format!("one of the passes {}", passes.join(", ")),
+        };
+
+        let mut message = format!(
+            "AST verification failed: {} introduced {} violation(s)",
+            description,
+            violations.len()
+        );
+        for violation in &violations {
+            message.push_str(&format!("\n  {}", violation));
+        }
+        Err(message)
+    }
+
+    /// Run `pass` alone for one iteration on a copy of `before`, or `None`
+    /// if the pass fails on its own
+    fn replay<'arena>(
+        &self,
+        pass: &'static str,
+        before: &MutableProgram<'arena>,
+        arena: &'arena Bump,
+    ) -> Option<Vec<Violation>> {
+        let mut config = self.config.clone();
+        config.disable.extend(
+            self.pipeline
+                .iter()
+                .filter(|&&other| other != pass)
+                .map(|name| name.to_string()),
+        );
+        config.max_iterations = Some(1);
+
+        let mut optimizer = Optimizer::new(
+            self.level,
+            self.handler.clone(),
+            self.verifier.interner.clone(),
+        );
+        optimizer.goal = self.goal;
+        optimizer.target = self.target;
+        optimizer.profile = self.profile.clone();
+        optimizer.function_hints = self.function_hints.clone();
+        optimizer.module_graph = self.module_graph.clone();
+        optimizer.current_module_path = self.current_module_path.clone();
+        optimizer.set_pass_pipeline(config);
+        if let Some(analysis) = &self.whole_program_analysis {
+            optimizer.set_whole_program_analysis(analysis.clone());
+        }
+
+        let mut program = before.clone();
+        optimizer.optimize(&mut program, arena).ok()?;
+        Some(self.verifier.check(&program))
+    }
+}
+
+/// Bindings visible inside one function body
+struct Frame {
+    /// Innermost scope last
+    scopes: Vec<FxHashSet<StringId>>,
+    /// Labels of the enclosing blocks
+    labels: Vec<FxHashSet<StringId>>,
+    loop_depth: usize,
+}
+
+impl Frame {
+    fn new() -> Self {
+        Self {
+            scopes: vec![FxHashSet::default()],
+            labels: Vec::new(),
+            loop_depth: 0,
+        }
+    }
+}
+
+struct Walker<'v> {
+    interner: &'v StringInterner,
+    globals: &'v FxHashSet<StringId>,
+    /// Innermost function last; the first frame is the main chunk
+    frames: Vec<Frame>,
+    violations: Vec<Violation>,
+    unresolved: FxHashSet<StringId>,
+}
+
+impl<'v> Walker<'v> {
+    fn new(interner: &'v StringInterner, globals: &'v FxHashSet<StringId>) -> Self {
+        Self {
+            interner,
+            globals,
+            frames: vec![Frame::new()],
+            violations: Vec::new(),
+            unresolved: FxHashSet::default(),
+        }
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("main chunk frame is never popped")
+    }
+
+    fn report(&mut self, kind: ViolationKind, span: Span, message: String) {
+        self.violations.push(Violation {
+            kind,
+            span,
+            message,
+        });
+    }
+
+    fn check_span(&mut self, span: Span, what: &str) {
+        if span.start > span.end {
+            self.report(
+                ViolationKind::InvalidSpan,
+                span,
+                format!(
+                    "{} span ends at {} before it starts at {}",
+                    what, span.end, span.start
+                ),
+            );
+        }
+    }
+
+    fn declare(&mut self, name: StringId, span: Span, check_duplicate: bool) {
+        let scope = self
+            .frame()
+            .scopes
+            .last_mut()
+            .expect("a frame always has a scope");
+        if !scope.insert(name) && check_duplicate {
+            let message = format!(
+                "local `{}` is declared twice in the same scope",
+                self.interner.resolve(name)
+            );
+            self.report(ViolationKind::DuplicateLocal, span, message);
+        }
+    }
+
+    fn resolve(&mut self, name: StringId, span: Span) {
+        let declared = self
+            .frames
+            .iter()
+            .flat_map(|frame| frame.scopes.iter())
+            .any(|scope| scope.contains(&name));
+        if declared || self.globals.contains(&name) {
+            return;
+        }
+
+        // Passes reference library members such as `math.abs` as one name
+        let resolved = self.interner.resolve(name).to_string();
+        if let Some((base, _)) = resolved.split_once('.') {
+            let base = self.interner.get_or_intern(base);
+            if self.globals.contains(&base) {
+                return;
+            }
+        }
+
+        self.unresolved.insert(name);
+        let message = format!("`{}` does not resolve to any declaration", resolved);
+        self.report(ViolationKind::UnresolvedIdentifier, span, message);
+    }
+
+    fn visit_program(&mut self, program: &MutableProgram<'_>) {
+        self.check_span(program.span, "program");
+        self.visit_statements(&program.statements);
+    }
+
+    /// Visit a statement list in the current scope
+    fn visit_statements(&mut self, statements: &[Statement<'_>]) {
+        let labels = statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::Label(label) => Some(label.name.node),
+                _ => None,
+            })
+            .collect();
+        self.frame().labels.push(labels);
+        for stmt in statements {
+            self.visit_stmt(stmt);
+        }
+        self.frame().labels.pop();
+    }
+
+    fn visit_block(&mut self, block: &Block<'_>) {
+        self.check_span(block.span, "block");
+        self.frame().scopes.push(FxHashSet::default());
+        self.visit_statements(block.statements);
+        self.frame().scopes.pop();
+    }
+
+    fn visit_loop_body(&mut self, block: &Block<'_>) {
+        self.frame().loop_depth += 1;
+        self.visit_block(block);
+        self.frame().loop_depth -= 1;
+    }
+
+    fn visit_stmt(&mut self, stmt: &Statement<'_>) {
+        self.check_span(remarks::statement_span(stmt), "statement");
+
+        match stmt {
+            Statement::Variable(decl) => {
+                // The initializer can't see the binding it initializes
+                self.visit_expr(&decl.initializer);
+                let mut names = Vec::new();
+                collect_pattern_names(&decl.pattern, &mut names);
+                let check_duplicate = !matches!(decl.kind, VariableKind::Global);
+                for (name, span) in names {
+                    self.declare(name, span, check_duplicate);
+                }
+            }
+            Statement::Function(func) => {
+                // Declared before the body so recursive calls resolve to it
+                self.declare(func.name.node, func.name.span, true);
+                self.visit_function(func.parameters, func.body.statements, None);
+            }
+            Statement::Class(decl) => self.declare(decl.name.node, decl.name.span, false),
+            Statement::Enum(decl) => self.declare(decl.name.node, decl.name.span, false),
+            Statement::Expression(expr) => self.visit_expr(expr),
+            Statement::If(if_stmt) => {
+                self.visit_expr(&if_stmt.condition);
+                self.visit_block(&if_stmt.then_block);
+                for else_if in if_stmt.else_ifs.iter() {
+                    self.visit_expr(&else_if.condition);
+                    self.visit_block(&else_if.block);
+                }
+                if let Some(else_block) = &if_stmt.else_block {
+                    self.visit_block(else_block);
+                }
+            }
+            Statement::While(while_stmt) => {
+                self.visit_expr(&while_stmt.condition);
+                self.visit_loop_body(&while_stmt.body);
+            }
+            Statement::For(for_stmt) => match &**for_stmt {
+                ForStatement::Numeric(for_num) => {
+                    self.visit_expr(&for_num.start);
+                    self.visit_expr(&for_num.end);
+                    if let Some(step) = &for_num.step {
+                        self.visit_expr(step);
+                    }
+                    self.frame().scopes.push(FxHashSet::default());
+                    self.declare(for_num.variable.node, for_num.variable.span, true);
+                    self.visit_loop_body(&for_num.body);
+                    self.frame().scopes.pop();
+                }
+                ForStatement::Generic(for_gen) => {
+                    for expr in for_gen.iterators.iter() {
+                        self.visit_expr(expr);
+                    }
+                    self.frame().scopes.push(FxHashSet::default());
+                    for var in for_gen.variables.iter() {
+                        self.declare(var.node, var.span, true);
+                    }
+                    self.visit_loop_body(&for_gen.body);
+                    self.frame().scopes.pop();
+                }
+            },
+            Statement::Repeat(repeat_stmt) => {
+                // `until` sees the locals of the body
+                self.check_span(repeat_stmt.body.span, "block");
+                self.frame().scopes.push(FxHashSet::default());
+                self.frame().loop_depth += 1;
+                self.visit_statements(repeat_stmt.body.statements);
+                self.frame().loop_depth -= 1;
+                self.visit_expr(&repeat_stmt.until);
+                self.frame().scopes.pop();
+            }
+            Statement::Block(block) => self.visit_block(block),
+            Statement::Return(ret) => {
+                for expr in ret.values.iter() {
+                    self.visit_expr(expr);
+                }
+            }
+            Statement::Break(_) | Statement::Continue(_) => {
+                if self.frame().loop_depth == 0 {
+                    let keyword = match stmt {
+                        Statement::Break(_) => "break",
+                        _ => "continue",
+                    };
+                    self.report(
+                        ViolationKind::JumpOutsideLoop,
+                        remarks::statement_span(stmt),
+                        format!("`{}` outside a loop", keyword),
+                    );
+                }
+            }
+            Statement::Goto(goto) => {
+                let target = goto.target.node;
+                if !self
+                    .frame()
+                    .labels
+                    .iter()
+                    .any(|labels| labels.contains(&target))
+                {
+                    let message = format!(
+                        "`goto {}` has no visible label",
+                        self.interner.resolve(target)
+                    );
+                    self.report(ViolationKind::UndefinedLabel, goto.target.span, message);
+                }
+            }
+            Statement::Try(try_stmt) => {
+                self.visit_block(&try_stmt.try_block);
+                for catch in try_stmt.catch_clauses.iter() {
+                    let variable = match &catch.pattern {
+                        CatchPattern::Typed { variable, .. }
+                        | CatchPattern::MultiTyped { variable, .. }
+                        | CatchPattern::Untyped { variable, .. } => variable,
+                    };
+                    self.frame().scopes.push(FxHashSet::default());
+                    self.declare(variable.node, variable.span, true);
+                    self.visit_block(&catch.body);
+                    self.frame().scopes.pop();
+                }
+                if let Some(finally_block) = &try_stmt.finally_block {
+                    self.visit_block(finally_block);
+                }
+            }
+            Statement::Export(export) => {
+                if let ExportKind::Declaration(inner) = &export.kind {
+                    self.visit_stmt(inner);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Visit a function's parameters and body in a new frame. The body is
+    /// either a statement list or, for expression-bodied arrows, `expr_body`.
+    fn visit_function(
+        &mut self,
+        parameters: &[Parameter<'_>],
+        statements: &[Statement<'_>],
+        expr_body: Option<&Expression<'_>>,
+    ) {
+        self.frames.push(Frame::new());
+
+        for param in parameters {
+            let mut names = Vec::new();
+            collect_pattern_names(&param.pattern, &mut names);
+            for (name, span) in names {
+                self.declare(name, span, true);
+            }
+        }
+        // Defaults are assigned in the body, where every parameter is visible
+        for param in parameters {
+            if let Some(default) = &param.default {
+                self.visit_expr(default);
+            }
+        }
+
+        self.visit_statements(statements);
+        if let Some(expr) = expr_body {
+            self.visit_expr(expr);
+        }
+
+        self.frames.pop();
+    }
+
+    fn visit_expr(&mut self, expr: &Expression<'_>) {
+        self.check_span(expr.span, "expression");
+
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.resolve(*name, expr.span),
+            ExpressionKind::Arrow(arrow) => match &arrow.body {
+                ArrowBody::Expression(body) => {
+                    self.visit_function(arrow.parameters, &[], Some(&**body))
+                }
+                ArrowBody::Block(block) => {
+                    self.visit_function(arrow.parameters, block.statements, None)
+                }
+            },
+            ExpressionKind::Function(func) => {
+                self.visit_function(func.parameters, func.body.statements, None)
+            }
+            ExpressionKind::Binary(_, left, right)
+            | ExpressionKind::Index(left, right)
+            | ExpressionKind::OptionalIndex(left, right)
+            | ExpressionKind::Pipe(left, right)
+            | ExpressionKind::ErrorChain(left, right)
+            | ExpressionKind::Assignment(left, _, right) => {
+                self.visit_expr(left);
+                self.visit_expr(right);
+            }
+            ExpressionKind::Unary(_, inner)
+            | ExpressionKind::Member(inner, _)
+            | ExpressionKind::OptionalMember(inner, _)
+            | ExpressionKind::TypeAssertion(inner, _)
+            | ExpressionKind::Parenthesized(inner) => self.visit_expr(inner),
+            ExpressionKind::Call(callee, args, _)
+            | ExpressionKind::OptionalCall(callee, args, _)
+            | ExpressionKind::New(callee, args, _)
+            | ExpressionKind::MethodCall(callee, _, args, _)
+            | ExpressionKind::OptionalMethodCall(callee, _, args, _) => {
+                self.visit_expr(callee);
+                for arg in args.iter() {
+                    self.visit_expr(&arg.value);
+                }
+            }
+            ExpressionKind::Conditional(cond, then_expr, else_expr) => {
+                self.visit_expr(cond);
+                self.visit_expr(then_expr);
+                self.visit_expr(else_expr);
+            }
+            ExpressionKind::Array(elements) => {
+                for element in elements.iter() {
+                    match element {
+                        ArrayElement::Expression(e) | ArrayElement::Spread(e) => self.visit_expr(e),
+                    }
+                }
+            }
+            ExpressionKind::Object(props) => {
+                for prop in props.iter() {
+                    match prop {
+                        ObjectProperty::Property { value, .. }
+                        | ObjectProperty::Spread { value, .. } => self.visit_expr(value),
+                        ObjectProperty::Computed { key, value, .. } => {
+                            self.visit_expr(key);
+                            self.visit_expr(value);
+                        }
+                    }
+                }
+            }
+            // Leaves, and constructs the verifier does not model
+            _ => {}
+        }
+    }
+}
+
+/// Names bound by a declaration pattern, with their spans
+fn collect_pattern_names(pattern: &Pattern<'_>, names: &mut Vec<(StringId, Span)>) {
+    match pattern {
+        Pattern::Identifier(ident) => names.push((ident.node, ident.span)),
+        Pattern::Array(arr_pat) => {
+            for elem in arr_pat.elements.iter() {
+                if let ArrayPatternElement::Pattern(pwd) = elem {
+                    collect_pattern_names(&pwd.pattern, names);
+                }
+            }
+        }
+        Pattern::Object(obj_pat) => {
+            for prop in obj_pat.properties.iter() {
+                match &prop.value {
+                    Some(pat) => collect_pattern_names(pat, names),
+                    None => names.push((prop.key.node, prop.key.span)),
+                }
+            }
+        }
+        Pattern::Wildcard(_) | Pattern::Literal(_, _) | Pattern::Or(_) | Pattern::Template(_) => {}
+    }
+}
@@ -0,0 +1,865 @@
+//! `.d.luax` declaration files from LuaLS/EmmyLua annotations.
+//!
+//! Third-party Lua libraries often document their API with `---@class`,
+//! `---@field`, `---@param` and `---@return` comments. This module turns those
+//! annotations into `declare` statements:
+//!
+//! - `---@class X` becomes `declare interface X`, with its `---@field`s and the
+//!   `function X:m()` methods of the table it annotates
+//! - `function X.f()` on a class table becomes a member of `declare namespace X`
+//! - `---@alias` becomes `declare type`, `---@enum` a `declare type` of the
+//!   values plus a `declare const` for the table
+//! - global functions become `declare function`, global tables namespaces
+//!
+//! When the file ends in `return M`, the members of `M` and all types are
+//! exported instead. Locals that never leave the file are skipped.
+//! Annotations that cannot be translated produce a [`TypingsWarning`] and fall
+//! back to `any`.
+
+mod tags;
+mod types;
+
+use crate::migrate::scanner::{self, code_text, LineInfo};
+use std::collections::HashMap;
+use tags::{FieldName, Tag};
+
+/// Something in the annotations that could not be translated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypingsWarning {
+    /// 1-based line in the Lua source
+    pub line: usize,
+    pub message: String,
+}
+
+/// A generated declaration file
+#[derive(Debug, Clone)]
+pub struct Typings {
+    pub source: String,
+    pub warnings: Vec<TypingsWarning>,
+}
+
+/// Generate a `.d.luax` declaration file from an annotated Lua source
+pub fn generate(source: &str) -> Typings {
+    let mut collector = Collector::new(source);
+    collector.collect();
+    collector.emit()
+}
+
+struct ClassDecl {
+    /// Name with generic parameters, e.g. `List<T>`
+    name: String,
+    parents: Vec<String>,
+    /// Table annotated by the class, if any
+    table: Option<String>,
+    members: Vec<String>,
+}
+
+struct EnumDecl {
+    name: String,
+    table: String,
+    keys: Vec<String>,
+    values: Vec<String>,
+}
+
+/// A function or value attached to a table, or global when `owner` is `None`
+struct Member {
+    owner: Option<String>,
+    kind: MemberKind,
+}
+
+enum MemberKind {
+    /// `name<T>(params): R` and overload signatures of the same form
+    Function {
+        signature: String,
+        overloads: Vec<String>,
+    },
+    /// `function X:name()`, a method of the class annotating `X`
+    Method {
+        signature: String,
+    },
+    Value {
+        name: String,
+        ty: String,
+    },
+    /// `owner.name = table` for a table declared in the file
+    TableRef {
+        name: String,
+        table: String,
+    },
+}
+
+struct FunctionHeader {
+    /// Dotted path before the name, e.g. `M` in `function M.f()`
+    owner: Option<String>,
+    name: String,
+    method: bool,
+    local: bool,
+    params: Vec<String>,
+}
+
+struct Collector {
+    lines: Vec<String>,
+    info: Vec<LineInfo>,
+    aliases: Vec<(String, String)>,
+    enums: Vec<EnumDecl>,
+    classes: Vec<ClassDecl>,
+    members: Vec<Member>,
+    /// Top-level tables: name → declared `local`
+    tables: Vec<(String, bool)>,
+    /// Table name → class name (without generics) it is annotated with
+    class_tables: HashMap<String, String>,
+    /// Table returned at the end of the file
+    module: Option<String>,
+    warnings: Vec<TypingsWarning>,
+}
+
+impl Collector {
+    fn new(source: &str) -> Self {
+        let lines: Vec<String> = source.lines().map(String::from).collect();
+        let info = scanner::scan_lines(&lines);
+        Collector {
+            lines,
+            info,
+            aliases: Vec::new(),
+            enums: Vec::new(),
+            classes: Vec::new(),
+            members: Vec::new(),
+            tables: Vec::new(),
+            class_tables: HashMap::new(),
+            module: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn warn(&mut self, index: usize, message: impl Into<String>) {
+        self.warnings.push(TypingsWarning {
+            line: index + 1,
+            message: message.into(),
+        });
+    }
+
+    fn code(&self, index: usize) -> &str {
+        code_text(&self.lines[index], &self.info[index])
+    }
+
+    /// Walk the top level, attaching each annotation block to the statement after it
+    fn collect(&mut self) {
+        let mut block: Vec<(usize, Tag)> = Vec::new();
+        // Open brackets before each line: lines inside a multi-line table
+        // constructor or call are not statements
+        let mut open = 0i32;
+        for i in 0..self.lines.len() {
+            let continued = open > 0;
+            for range in &self.info[i].code {
+                for b in self.lines[i][range.clone()].bytes() {
+                    match b {
+                        b'{' | b'(' | b'[' => open += 1,
+                        b'}' | b')' | b']' => open -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            let trimmed = self.lines[i].trim_start();
+            if self.info[i].depth_before > 0 || continued {
+                continue;
+            }
+            if let Some(comment) = trimmed.strip_prefix("---") {
+                match tags::parse(comment) {
+                    Some(Ok(tag)) => block.push((i, tag)),
+                    Some(Err(message)) => self.warn(i, message),
+                    None => {}
+                }
+                continue;
+            }
+            if self.code(i).is_empty() {
+                // A blank line detaches annotations from the code below
+                if trimmed.is_empty() && !block.is_empty() {
+                    self.apply(std::mem::take(&mut block), None);
+                }
+                continue;
+            }
+            self.apply(std::mem::take(&mut block), Some(i));
+        }
+        if !block.is_empty() {
+            self.apply(block, None);
+        }
+
+        let last = (0..self.lines.len())
+            .rev()
+            .find(|&i| self.info[i].depth_before == 0 && !self.code(i).is_empty());
+        self.module = last.and_then(|i| {
+            let returned = self.code(i).strip_prefix("return ")?.trim();
+            scanner::is_identifier(returned).then(|| returned.to_string())
+        });
+    }
+
+    /// Apply an annotation block to the statement on line `target`
+    fn apply(&mut self, block: Vec<(usize, Tag)>, target: Option<usize>) {
+        let assigned = target.and_then(|t| assigned_name(self.code(t)).map(String::from));
+        let mut rest: Vec<(usize, Tag)> = Vec::new();
+        let mut tags = block.into_iter().peekable();
+
+        while let Some((line, tag)) = tags.next() {
+            match tag {
+                Tag::Class { name, parents } => {
+                    let mut fields = Vec::new();
+                    while let Some((_, Tag::Field { .. })) = tags.peek() {
+                        fields.push(tags.next().expect("peeked"));
+                    }
+                    // Only the last class of a block annotates the statement
+                    let binds = !matches!(tags.peek(), Some((_, Tag::Class { .. })));
+                    self.add_class(name, parents, fields, assigned.clone().filter(|_| binds));
+                }
+                Tag::Field { .. } => self.warn(line, "`@field` outside a `@class`"),
+                Tag::Alias { name, ty } => {
+                    let mut variants: Vec<String> =
+                        Some(ty).filter(|ty| !ty.is_empty()).into_iter().collect();
+                    while let Some((_, Tag::AliasVariant(_))) = tags.peek() {
+                        if let Some((_, Tag::AliasVariant(variant))) = tags.next() {
+                            variants.push(variant);
+                        }
+                    }
+                    if variants.is_empty() {
+                        self.warn(line, format!("alias `{}` has no type", name));
+                        continue;
+                    }
+                    let ty = self.convert(line, &variants.join(" | "), None);
+                    self.aliases.push((name, ty));
+                }
+                Tag::AliasVariant(_) => self.warn(line, "`---|` outside an `@alias`"),
+                Tag::Enum(name) => match target {
+                    Some(target) => self.add_enum(line, name, target),
+                    None => self.warn(line, format!("enum `{}` is not followed by a table", name)),
+                },
+                Tag::Other => {}
+                other => rest.push((line, other)),
+            }
+        }
+
+        if let Some(target) = target {
+            self.add_statement(target, &rest);
+        }
+    }
+
+    fn add_class(
+        &mut self,
+        name: String,
+        parents: Vec<String>,
+        fields: Vec<(usize, Tag)>,
+        table: Option<String>,
+    ) {
+        let base = name.split('<').next().unwrap_or(&name).to_string();
+        let mut members = Vec::new();
+        for (field_line, field) in fields {
+            if let Tag::Field {
+                name: field_name,
+                optional,
+                ty,
+                private,
+            } = field
+            {
+                if private {
+                    continue;
+                }
+                let ty = self.convert(field_line, &ty, Some(&name));
+                members.push(match field_name {
+                    FieldName::Named(field_name) => {
+                        format!("{}{}: {}", field_name, if optional { "?" } else { "" }, ty)
+                    }
+                    FieldName::Index(key) => {
+                        let key = self.convert(field_line, &key, Some(&name));
+                        format!("[key: {}]: {}", key, ty)
+                    }
+                });
+            }
+        }
+        if let Some(table) = &table {
+            self.class_tables.insert(table.clone(), base);
+        }
+        self.classes.push(ClassDecl {
+            name,
+            parents,
+            table,
+            members,
+        });
+    }
+
+    fn add_enum(&mut self, line: usize, name: String, target: usize) {
+        let table = match assigned_name(self.code(target)) {
+            Some(table) => table.to_string(),
+            None => {
+                self.warn(line, format!("enum `{}` is not followed by a table", name));
+                return;
+            }
+        };
+        let text: String = (target..self.lines.len())
+            .map(|i| self.code(i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let entries = text
+            .split_once('{')
+            .and_then(|(_, body)| body.split_once('}'))
+            .map(|(body, _)| body);
+        let entries = match entries {
+            Some(entries) => entries,
+            None => {
+                self.warn(line, format!("enum `{}` is not followed by a table", name));
+                return;
+            }
+        };
+
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        for entry in entries
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+        {
+            let Some((key, value)) = entry.split_once('=') else {
+                self.warn(
+                    line,
+                    format!("enum `{}` entry `{}` has no key", name, entry),
+                );
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let key = key
+                .strip_prefix("[\"")
+                .and_then(|k| k.strip_suffix("\"]"))
+                .unwrap_or(key);
+            keys.push(key.to_string());
+            values.push(match literal_type(value) {
+                Some(_) if value.starts_with('\'') => format!("\"{}\"", &value[1..value.len() - 1]),
+                Some(_) if value != "true" && value != "false" => value.to_string(),
+                Some(ty) => ty.to_string(),
+                None => "any".to_string(),
+            });
+        }
+        values.dedup();
+        self.tables
+            .push((table.clone(), self.code(target).starts_with("local ")));
+        self.enums.push(EnumDecl {
+            name,
+            table,
+            keys,
+            values,
+        });
+    }
+
+    /// Record the function, table or value declared on line `target`
+    fn add_statement(&mut self, target: usize, tags: &[(usize, Tag)]) {
+        let code = self.code(target).to_string();
+        let type_tag = tags.iter().find_map(|(line, tag)| match tag {
+            Tag::Type(ty) => Some((*line, ty.clone())),
+            _ => None,
+        });
+
+        if let Some(header) = parse_function_header(&code) {
+            if header.local {
+                return;
+            }
+            if header
+                .owner
+                .as_deref()
+                .is_some_and(|owner| owner.contains('.'))
+            {
+                let owner = header.owner.unwrap_or_default();
+                self.warn(
+                    target,
+                    format!(
+                        "nested table `{}` not supported; `{}` skipped",
+                        owner, header.name
+                    ),
+                );
+                return;
+            }
+            let class = header
+                .owner
+                .as_ref()
+                .and_then(|o| self.class_tables.get(o))
+                .cloned();
+            let self_type = class.as_deref().map(|base| self.class_type(base));
+            let (signature, overloads) = self.signature(&header, tags, self_type.as_deref());
+            let kind = if header.method {
+                if class.is_none() {
+                    self.warn(
+                        target,
+                        format!(
+                            "method `{}` on a table without `@class` skipped",
+                            header.name
+                        ),
+                    );
+                    return;
+                }
+                MemberKind::Method { signature }
+            } else {
+                MemberKind::Function {
+                    signature,
+                    overloads,
+                }
+            };
+            self.members.push(Member {
+                owner: header.owner,
+                kind,
+            });
+            return;
+        }
+
+        let local = code.starts_with("local ");
+        let Some((name, value)) = code
+            .strip_prefix("local ")
+            .unwrap_or(&code)
+            .split_once('=')
+            .filter(|(_, value)| !value.starts_with('='))
+        else {
+            return;
+        };
+        let (name, value) = (name.trim(), value.trim());
+
+        let is_table = value.starts_with('{') || value.starts_with("setmetatable(");
+        if scanner::is_identifier(name) && is_table && type_tag.is_none() {
+            self.tables.push((name.to_string(), local));
+            return;
+        }
+
+        let (owner, field) = match name.split_once('.') {
+            Some((owner, field))
+                if scanner::is_identifier(owner) && scanner::is_identifier(field) =>
+            {
+                (Some(owner.to_string()), field)
+            }
+            None if scanner::is_identifier(name) && !local => (None, name),
+            None if scanner::is_identifier(name) => {
+                // Annotated locals can still be returned as the module
+                if type_tag.is_some() || is_table {
+                    self.tables.push((name.to_string(), true));
+                }
+                return;
+            }
+            _ => return,
+        };
+        let known_table =
+            self.tables.iter().any(|(t, _)| t == value) || self.class_tables.contains_key(value);
+        if owner.is_some() && known_table && type_tag.is_none() {
+            self.members.push(Member {
+                owner,
+                kind: MemberKind::TableRef {
+                    name: field.to_string(),
+                    table: value.to_string(),
+                },
+            });
+            return;
+        }
+
+        let class = owner
+            .as_ref()
+            .and_then(|o| self.class_tables.get(o))
+            .cloned();
+        let ty = match type_tag {
+            Some((line, ty)) => {
+                let self_type = class.as_deref().map(|base| self.class_type(base));
+                self.convert(line, &ty, self_type.as_deref())
+            }
+            None => literal_type(value).unwrap_or("any").to_string(),
+        };
+        self.members.push(Member {
+            owner,
+            kind: MemberKind::Value {
+                name: field.to_string(),
+                ty,
+            },
+        });
+    }
+
+    /// `name<T>(params): R` for a function header, plus its overloads
+    fn signature(
+        &mut self,
+        header: &FunctionHeader,
+        tags: &[(usize, Tag)],
+        self_type: Option<&str>,
+    ) -> (String, Vec<String>) {
+        let annotated = !tags.is_empty();
+
+        let mut generics = Vec::new();
+        for (_, tag) in tags {
+            if let Tag::Generic(params) = tag {
+                for (name, bound) in params {
+                    generics.push(match bound {
+                        Some(bound) => format!("{} extends {}", name, bound),
+                        None => name.clone(),
+                    });
+                }
+            }
+        }
+        let generics = if generics.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", generics.join(", "))
+        };
+
+        let mut params = Vec::new();
+        for param in &header.params {
+            let tagged = tags.iter().find_map(|(line, tag)| match tag {
+                Tag::Param { name, optional, ty } if name == param => {
+                    Some((*line, *optional, ty.clone()))
+                }
+                Tag::Vararg(ty) if param == "..." => Some((*line, false, ty.clone())),
+                _ => None,
+            });
+            params.push(match tagged {
+                Some((line, optional, ty)) => {
+                    let ty = self.convert(line, &ty, self_type);
+                    format!("{}{}: {}", param, if optional { "?" } else { "" }, ty)
+                }
+                None => format!("{}: any", param),
+            });
+        }
+        for (line, tag) in tags {
+            if let Tag::Param { name, .. } = tag {
+                if !header.params.contains(name) {
+                    self.warn(
+                        *line,
+                        format!(
+                            "`@param {}` does not match a parameter of `{}`",
+                            name, header.name
+                        ),
+                    );
+                }
+            }
+        }
+
+        let mut returns = Vec::new();
+        for (line, tag) in tags {
+            if let Tag::Return(types) = tag {
+                for ty in types {
+                    returns.push(self.convert(*line, ty, self_type));
+                }
+            }
+        }
+        let ret = match (returns.is_empty(), annotated) {
+            (false, _) => returns.join(", "),
+            (true, true) => "void".to_string(),
+            (true, false) => "any".to_string(),
+        };
+
+        let mut overloads = Vec::new();
+        for (line, tag) in tags {
+            if let Tag::Overload(ty) = tag {
+                let converted = self.convert(*line, ty, self_type);
+                match arrow_to_signature(&converted) {
+                    Some(signature) => overloads.push(format!("{}{}", header.name, signature)),
+                    None => self.warn(*line, format!("`@overload {}` is not a function type", ty)),
+                }
+            }
+        }
+
+        (
+            format!(
+                "{}{}({}): {}",
+                header.name,
+                generics,
+                params.join(", "),
+                ret
+            ),
+            overloads,
+        )
+    }
+
+    /// Full class type, with generics, for `self`
+    fn class_type(&self, base: &str) -> String {
+        self.classes
+            .iter()
+            .find(|c| c.name.split('<').next() == Some(base))
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| base.to_string())
+    }
+
+    fn convert(&mut self, line: usize, ty: &str, self_type: Option<&str>) -> String {
+        match types::convert(ty, self_type) {
+            Ok(converted) => converted,
+            Err(message) => {
+                self.warn(line, format!("{}; using `any`", message));
+                "any".to_string()
+            }
+        }
+    }
+
+    fn members_of<'a>(&'a self, table: &'a str) -> impl Iterator<Item = &'a Member> + 'a {
+        self.members
+            .iter()
+            .filter(move |m| m.owner.as_deref() == Some(table))
+    }
+
+    fn emit(self) -> Typings {
+        let module = self.module.as_deref();
+        let export = if module.is_some() { "export " } else { "" };
+        let is_class = |table: &str| self.class_tables.contains_key(table);
+        let is_enum = |table: &str| self.enums.iter().any(|e| e.table == table);
+
+        // Name and export prefix of each table reachable from outside the file:
+        // globals, the module table when it is a class or enum, and tables
+        // stored in fields of the module table
+        let mut exposed: Vec<(&str, &str, &str)> = Vec::new();
+        fn expose<'a>(
+            exposed: &mut Vec<(&'a str, &'a str, &'static str)>,
+            table: &'a str,
+            name: &'a str,
+            prefix: &'static str,
+        ) {
+            if !exposed.iter().any(|(t, _, _)| *t == table) {
+                exposed.push((table, name, prefix));
+            }
+        }
+        if let Some(module) = module {
+            if is_class(module) || is_enum(module) {
+                expose(&mut exposed, module, module, "export ");
+            }
+            for member in self.members_of(module) {
+                if let MemberKind::TableRef { name, table } = &member.kind {
+                    expose(&mut exposed, table, name, "export ");
+                }
+            }
+        }
+        let locals: Vec<&str> = self
+            .tables
+            .iter()
+            .filter(|(_, local)| *local)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        for table in self
+            .members
+            .iter()
+            .filter_map(|m| m.owner.as_deref())
+            .chain(self.class_tables.keys().map(String::as_str))
+            .chain(self.enums.iter().map(|e| e.table.as_str()))
+        {
+            if !locals.contains(&table) && Some(table) != module {
+                expose(&mut exposed, table, table, "");
+            }
+        }
+        let exposed_as = |table: &str| {
+            exposed
+                .iter()
+                .find(|(t, _, _)| *t == table)
+                .map(|(_, name, prefix)| (*name, *prefix))
+        };
+
+        let mut blocks: Vec<String> = Vec::new();
+        for (name, ty) in &self.aliases {
+            blocks.push(format!("{}declare type {} = {}", export, name, ty));
+        }
+
+        for decl in &self.enums {
+            let mut block = format!(
+                "{}declare type {} = {}",
+                export,
+                decl.name,
+                decl.values.join(" | ")
+            );
+            if let Some((name, prefix)) = exposed_as(&decl.table) {
+                let fields: Vec<String> = decl
+                    .keys
+                    .iter()
+                    .map(|k| format!("{}: {}", k, decl.name))
+                    .collect();
+                block.push_str(&format!(
+                    "\n{}declare const {}: {{ {} }}",
+                    prefix,
+                    name,
+                    fields.join(", ")
+                ));
+            }
+            blocks.push(block);
+        }
+
+        for class in &self.classes {
+            let mut members = class.members.clone();
+            let mut statics = Vec::new();
+            if let Some(table) = class.table.as_deref() {
+                for member in self.members_of(table) {
+                    match &member.kind {
+                        MemberKind::Method { signature } => members.push(signature.clone()),
+                        kind => statics.extend(namespace_members(kind)),
+                    }
+                }
+            }
+
+            let extends = if class.parents.is_empty() {
+                String::new()
+            } else {
+                format!(" extends {}", class.parents.join(", "))
+            };
+            let mut block = format!("{}declare interface {}{} {{\n", export, class.name, extends);
+            for member in &members {
+                block.push_str(&format!("    {}\n", member));
+            }
+            block.push('}');
+
+            let exposed_table = class.table.as_deref().and_then(exposed_as);
+            if let Some((name, prefix)) = exposed_table.filter(|_| !statics.is_empty()) {
+                block.push_str("\n\n");
+                block.push_str(&namespace(prefix, name, &statics));
+            }
+            blocks.push(block);
+        }
+
+        // Members of a plain module table become top-level exports
+        if let Some(module) = module.filter(|m| !is_class(m) && !is_enum(m)) {
+            for member in self.members_of(module) {
+                blocks.extend(top_level_declarations(&member.kind, "export "));
+            }
+        }
+
+        for member in self.members.iter().filter(|m| m.owner.is_none()) {
+            blocks.extend(top_level_declarations(&member.kind, ""));
+        }
+
+        // Other reachable tables become namespaces
+        for (table, name, prefix) in &exposed {
+            if is_class(table) || is_enum(table) {
+                continue;
+            }
+            let declarations: Vec<String> = self
+                .members_of(table)
+                .flat_map(|m| namespace_members(&m.kind))
+                .collect();
+            if !declarations.is_empty() {
+                blocks.push(namespace(prefix, name, &declarations));
+            }
+        }
+
+        let mut source = String::from("-- Generated by `luanext typings` from LuaLS annotations\n");
+        for block in blocks {
+            source.push('\n');
+            source.push_str(&block);
+            source.push('\n');
+        }
+        Typings {
+            source,
+            warnings: self.warnings,
+        }
+    }
+}
+
+fn namespace(prefix: &str, name: &str, declarations: &[String]) -> String {
+    let mut block = format!("{}declare namespace {} {{\n", prefix, name);
+    for declaration in declarations {
+        block.push_str(&format!("    {}\n", declaration));
+    }
+    block.push('}');
+    block
+}
+
+/// Declarations of a table member inside a `declare namespace`
+fn namespace_members(kind: &MemberKind) -> Vec<String> {
+    top_level_declarations(kind, "")
+        .into_iter()
+        .map(|d| d.strip_prefix("declare ").unwrap_or(&d).to_string())
+        .collect()
+}
+
+/// `declare function` / `declare const` lines for a function or value
+fn top_level_declarations(kind: &MemberKind, export: &str) -> Vec<String> {
+    match kind {
+        MemberKind::Function {
+            signature,
+            overloads,
+        } => overloads
+            .iter()
+            .chain(std::iter::once(signature))
+            .map(|s| format!("{}declare function {}", export, s))
+            .collect(),
+        MemberKind::Value { name, ty } => vec![format!("{}declare const {}: {}", export, name, ty)],
+        MemberKind::Method { .. } | MemberKind::TableRef { .. } => Vec::new(),
+    }
+}
+
+/// `(a: T) => R` to `(a: T): R`
+fn arrow_to_signature(ty: &str) -> Option<String> {
+    if !ty.starts_with('(') {
+        return None;
+    }
+    let mut depth = 0;
+    for (i, c) in ty.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    let ret = ty[i + 1..].strip_prefix(" => ")?;
+                    return Some(format!("{}: {}", &ty[..=i], ret));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `X` of `local X = ...` or `X = ...`
+fn assigned_name(code: &str) -> Option<&str> {
+    let (name, value) = code
+        .strip_prefix("local ")
+        .unwrap_or(code)
+        .split_once('=')?;
+    let name = name.trim();
+    (scanner::is_identifier(name) && !value.starts_with('=')).then_some(name)
+}
+
+/// Header of `function a.b:c(x, y)`, `local function f()` or `a.b = function(x)`
+fn parse_function_header(code: &str) -> Option<FunctionHeader> {
+    let (local, rest) = match code.strip_prefix("local ") {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, code),
+    };
+    let (path, params) = if let Some(rest) = rest.strip_prefix("function ") {
+        let open = rest.find('(')?;
+        (rest[..open].trim(), &rest[open..])
+    } else {
+        let (path, value) = rest.split_once('=')?;
+        let value = value.trim_start().strip_prefix("function")?.trim_start();
+        value.starts_with('(').then_some((path.trim(), value))?
+    };
+    let close = params.find(')')?;
+    let params: Vec<String> = params[1..close]
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(String::from)
+        .collect();
+
+    let (owner, name, method) = match path.rsplit_once(':') {
+        Some((owner, name)) => (Some(owner.to_string()), name, true),
+        None => match path.rsplit_once('.') {
+            Some((owner, name)) => (Some(owner.to_string()), name, false),
+            None => (None, path, false),
+        },
+    };
+    if !scanner::is_identifier(name) {
+        return None;
+    }
+    Some(FunctionHeader {
+        owner,
+        name: name.to_string(),
+        method,
+        local,
+        params,
+    })
+}
+
+/// Type of a number, string or boolean literal
+fn literal_type(value: &str) -> Option<&'static str> {
+    if value == "true" || value == "false" {
+        return Some("boolean");
+    }
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted {
+        return Some("string");
+    }
+    let number = value.strip_prefix('-').unwrap_or(value);
+    let numeric = number.starts_with(|c: char| c.is_ascii_digit())
+        && (number.parse::<f64>().is_ok() || number.starts_with("0x") || number.starts_with("0X"));
+    numeric.then_some("number")
+}
@@ -0,0 +1,217 @@
+//! Parsing of `---@tag` annotation comments.
+//!
+//! Types are kept as LuaLS text here; `types::convert` translates them once
+//! the enclosing class (for `self`) is known.
+
+use super::types::type_extent;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Tag {
+    /// `---@class Name<T>: Parent`
+    Class { name: String, parents: Vec<String> },
+    /// `---@field [scope] name[?] type`, `---@field [K] V`
+    Field {
+        name: FieldName,
+        optional: bool,
+        ty: String,
+        private: bool,
+    },
+    /// `---@param name[?] type`
+    Param {
+        name: String,
+        optional: bool,
+        ty: String,
+    },
+    /// `---@return type [name]`, or several comma-separated types
+    Return(Vec<String>),
+    /// `---@generic T, K: Base`
+    Generic(Vec<(String, Option<String>)>),
+    /// `---@alias Name type`; the type may follow on `---|` lines
+    Alias { name: String, ty: String },
+    /// `---| value` continuation of an alias
+    AliasVariant(String),
+    /// `---@enum Name`
+    Enum(String),
+    /// `---@type T`
+    Type(String),
+    /// `---@vararg T`
+    Vararg(String),
+    /// `---@overload fun(...): R`
+    Overload(String),
+    /// Any tag with no declaration counterpart (`@deprecated`, `@see`, ...)
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum FieldName {
+    Named(String),
+    /// `[K]` index signature key type
+    Index(String),
+}
+
+/// Parse the text after `---` on an annotation line.
+///
+/// Returns `None` for plain documentation lines, `Err` for malformed tags.
+pub(super) fn parse(comment: &str) -> Option<Result<Tag, String>> {
+    let comment = comment.trim();
+    if let Some(variant) = comment.strip_prefix('|') {
+        let variant = variant.trim().trim_start_matches(['>', '+']).trim();
+        let (ty, _) = split_type(variant);
+        return Some(Ok(Tag::AliasVariant(ty.to_string())));
+    }
+    let body = comment.strip_prefix('@')?;
+    let (tag, rest) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+    let rest = rest.trim();
+    Some(match tag {
+        "class" => parse_class(rest),
+        "field" => parse_field(rest),
+        "param" => parse_param(rest),
+        "return" => parse_return(rest),
+        "generic" => Ok(parse_generic(rest)),
+        "alias" => parse_alias(rest),
+        "enum" => {
+            let rest = skip_attribute(rest);
+            first_word(rest)
+                .map(|name| Tag::Enum(name.to_string()))
+                .ok_or_else(|| "`@enum` without a name".to_string())
+        }
+        "type" => non_empty(split_type(rest).0, "@type").map(Tag::Type),
+        "vararg" => non_empty(split_type(rest).0, "@vararg").map(Tag::Vararg),
+        "overload" => non_empty(split_type(rest).0, "@overload").map(Tag::Overload),
+        _ => Ok(Tag::Other),
+    })
+}
+
+fn parse_class(rest: &str) -> Result<Tag, String> {
+    let rest = skip_attribute(rest);
+    let (head, parents) = match rest.split_once(':') {
+        Some((head, parents)) => (head.trim(), parents),
+        None => (rest, ""),
+    };
+    let name = head
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| "`@class` without a name".to_string())?;
+    let parents = parents
+        .split(',')
+        .filter_map(first_word)
+        .map(String::from)
+        .collect();
+    Ok(Tag::Class {
+        name: name.to_string(),
+        parents,
+    })
+}
+
+fn parse_field(rest: &str) -> Result<Tag, String> {
+    let (scope, rest) = match rest.split_once(char::is_whitespace) {
+        Some((scope @ ("public" | "private" | "protected" | "package"), rest)) => {
+            (scope, rest.trim_start())
+        }
+        _ => ("public", rest),
+    };
+    let (name, rest) = if let Some(index) = rest.strip_prefix('[') {
+        let close = index
+            .find(']')
+            .ok_or_else(|| format!("unterminated index key in `@field {}`", rest))?;
+        (
+            FieldName::Index(index[..close].trim().to_string()),
+            &index[close + 1..],
+        )
+    } else {
+        let (name, rest) = rest
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("`@field {}` has no type", rest))?;
+        (FieldName::Named(name.to_string()), rest)
+    };
+    let (name, optional) = match name {
+        FieldName::Named(n) if n.ends_with('?') => {
+            (FieldName::Named(n.trim_end_matches('?').to_string()), true)
+        }
+        other => (other, false),
+    };
+    let ty = non_empty(split_type(rest.trim()).0, "@field")?;
+    Ok(Tag::Field {
+        name,
+        optional,
+        ty,
+        private: scope != "public",
+    })
+}
+
+fn parse_param(rest: &str) -> Result<Tag, String> {
+    let (name, rest) = rest
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("`@param {}` has no type", rest))?;
+    let optional = name.ends_with('?');
+    Ok(Tag::Param {
+        name: name.trim_end_matches('?').to_string(),
+        optional,
+        ty: non_empty(split_type(rest.trim()).0, "@param")?,
+    })
+}
+
+fn parse_return(rest: &str) -> Result<Tag, String> {
+    let mut types = Vec::new();
+    let mut rest = rest;
+    loop {
+        let (ty, after) = split_type(rest);
+        types.push(non_empty(ty, "@return")?);
+        // `---@return A, B`; anything else after the type is a name or description
+        match after.trim_start().strip_prefix(',') {
+            Some(next) => rest = next.trim_start(),
+            None => return Ok(Tag::Return(types)),
+        }
+    }
+}
+
+fn parse_generic(rest: &str) -> Tag {
+    let params = rest
+        .split(',')
+        .filter_map(|param| {
+            let (name, bound) = match param.split_once(':') {
+                Some((name, bound)) => (name.trim(), Some(bound.trim().to_string())),
+                None => (param.trim(), None),
+            };
+            first_word(name).map(|name| (name.to_string(), bound))
+        })
+        .collect();
+    Tag::Generic(params)
+}
+
+fn parse_alias(rest: &str) -> Result<Tag, String> {
+    let (name, ty) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    if name.is_empty() {
+        return Err("`@alias` without a name".to_string());
+    }
+    Ok(Tag::Alias {
+        name: name.to_string(),
+        ty: split_type(ty.trim()).0.to_string(),
+    })
+}
+
+/// Split a type from the name or description following it
+fn split_type(text: &str) -> (&str, &str) {
+    let end = type_extent(text);
+    (text[..end].trim(), &text[end..])
+}
+
+/// Skip a `(exact)`-style attribute before a name
+fn skip_attribute(text: &str) -> &str {
+    match text.strip_prefix('(').and_then(|t| t.split_once(')')) {
+        Some((_, rest)) => rest.trim_start(),
+        None => text,
+    }
+}
+
+fn first_word(text: &str) -> Option<&str> {
+    text.split_whitespace().next()
+}
+
+fn non_empty(ty: &str, tag: &str) -> Result<String, String> {
+    if ty.is_empty() {
+        Err(format!("`{}` without a type", tag))
+    } else {
+        Ok(ty.to_string())
+    }
+}
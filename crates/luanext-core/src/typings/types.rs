@@ -0,0 +1,334 @@
+//! LuaLS type expressions to LuaNext type syntax.
+//!
+//! | LuaLS                      | LuaNext                    |
+//! |----------------------------|----------------------------|
+//! | `integer`                  | `number`                   |
+//! | `T?`                       | `T?`                       |
+//! | `T[]`                      | `T[]`                      |
+//! | `table<K, V>`              | `{ [key: K]: V }`          |
+//! | `{ x: T, [K]: V }`         | `{ x: T, [key: K]: V }`    |
+//! | `fun(a: T, ...: U): R, S`  | `(a: T, ...: U) => (R, S)` |
+//! | `function`                 | `(...: any) => any`        |
+//! | `userdata`, `lightuserdata`| `any`                      |
+//! | `self`                     | the enclosing class        |
+
+/// Convert a LuaLS type to LuaNext syntax.
+///
+/// `self_type` replaces `self` inside class members.
+pub(super) fn convert(ty: &str, self_type: Option<&str>) -> Result<String, String> {
+    let mut parser = TypeParser {
+        tokens: tokenize(ty)?,
+        pos: 0,
+        self_type,
+    };
+    let converted = parser.union()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(converted),
+        Some(token) => Err(format!("unexpected `{}` in type `{}`", token, ty)),
+    }
+}
+
+/// Length of the type expression at the start of a tag's text.
+///
+/// Types may contain spaces (`fun(a: T): R`, `A | B`), so the type ends at the
+/// first top-level whitespace not next to `|` or `:`.
+pub(super) fn type_extent(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' | b'[' | b'{' | b'<' => depth += 1,
+            b')' | b']' | b'}' | b'>' => depth = depth.saturating_sub(1),
+            quote @ (b'"' | b'\'' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b' ' | b'\t' if depth == 0 => {
+                let before = text[..i].trim_end().bytes().last();
+                let after = text[i..].trim_start().bytes().next();
+                let joined = matches!(before, Some(b'|' | b':' | b','))
+                    || matches!(after, Some(b'|' | b':'));
+                if !joined {
+                    return i;
+                }
+            }
+            // A top-level comma separates `---@return A, B` types
+            b',' if depth == 0 => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn tokenize(ty: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = ty.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' || c == '\'' || c == '`' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(format!("unterminated string in type `{}`", ty));
+            }
+            i += 1;
+            // LuaLS backtick literals are plain string literals here
+            let text: String = chars[start + 1..i - 1].iter().collect();
+            tokens.push(format!("\"{}\"", text));
+        } else if c.is_alphanumeric() || c == '_' || c == '-' {
+            let start = i;
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.' | '*'))
+            {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        } else if c == '.' && chars.get(i + 1) == Some(&'.') && chars.get(i + 2) == Some(&'.') {
+            tokens.push("...".to_string());
+            i += 3;
+        } else if "|?[](){}<>,:".contains(c) {
+            tokens.push(c.to_string());
+            i += 1;
+        } else {
+            return Err(format!("unexpected `{}` in type `{}`", c, ty));
+        }
+    }
+    Ok(tokens)
+}
+
+struct TypeParser<'a> {
+    tokens: Vec<String>,
+    pos: usize,
+    self_type: Option<&'a str>,
+}
+
+impl TypeParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected `{}`, found `{}`",
+                token,
+                self.peek().unwrap_or("end of type")
+            ))
+        }
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "unexpected end of type".to_string())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// `A | B | C`
+    fn union(&mut self) -> Result<String, String> {
+        // LuaLS allows a leading `|` in multi-line aliases
+        self.eat("|");
+        let mut members = vec![self.postfix()?];
+        while self.eat("|") {
+            members.push(self.postfix()?);
+        }
+        Ok(members.join(" | "))
+    }
+
+    /// `T[]` and `T?`
+    fn postfix(&mut self) -> Result<String, String> {
+        let mut ty = self.primary()?;
+        loop {
+            if self.peek() == Some("[")
+                && self.tokens.get(self.pos + 1).map(String::as_str) == Some("]")
+            {
+                self.pos += 2;
+                ty = format!("{}[]", wrap(&ty));
+            } else if self.eat("?") {
+                ty = format!("{}?", wrap(&ty));
+            } else {
+                return Ok(ty);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<String, String> {
+        let token = self.next()?;
+        match token.as_str() {
+            "(" => {
+                let inner = self.union()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            "{" => self.table_literal(),
+            "fun" if self.peek() == Some("(") => self.function(),
+            "table" if self.peek() == Some("<") => {
+                self.pos += 1;
+                let key = self.union()?;
+                self.expect(",")?;
+                let value = self.union()?;
+                self.expect(">")?;
+                Ok(format!("{{ [key: {}]: {} }}", key, value))
+            }
+            _ if token.starts_with('"')
+                || token.starts_with(|c: char| c.is_ascii_digit() || c == '-') =>
+            {
+                Ok(token)
+            }
+            _ => {
+                let mut name = self.named(&token);
+                if self.eat("<") {
+                    let mut args = vec![self.union()?];
+                    while self.eat(",") {
+                        args.push(self.union()?);
+                    }
+                    self.expect(">")?;
+                    name = format!("{}<{}>", name, args.join(", "));
+                }
+                Ok(name)
+            }
+        }
+    }
+
+    fn named(&self, name: &str) -> String {
+        match name {
+            "integer" => "number".to_string(),
+            "function" => "(...: any) => any".to_string(),
+            "userdata" | "lightuserdata" | "cdata" | "ffi.cdata*" => "any".to_string(),
+            "self" => self.self_type.unwrap_or("any").to_string(),
+            other => other.trim_end_matches('*').to_string(),
+        }
+    }
+
+    /// `fun(a: T, b?: U, ...: V): R, S` after `fun`
+    fn function(&mut self) -> Result<String, String> {
+        self.expect("(")?;
+        let mut params = Vec::new();
+        while !self.eat(")") {
+            let name = self.next()?;
+            let optional = self.eat("?");
+            let ty = if self.eat(":") {
+                self.union()?
+            } else {
+                "any".to_string()
+            };
+            params.push(format!(
+                "{}{}: {}",
+                name,
+                if optional { "?" } else { "" },
+                ty
+            ));
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+
+        let mut returns = Vec::new();
+        if self.eat(":") {
+            returns.push(self.postfix_union()?);
+            // Commas belong to an enclosing generic or table otherwise
+            while self.peek() == Some(",") && self.returns_continue() {
+                self.pos += 1;
+                returns.push(self.postfix_union()?);
+            }
+        }
+        let ret = match returns.len() {
+            0 => "void".to_string(),
+            1 => returns.remove(0),
+            _ => format!("({})", returns.join(", ")),
+        };
+        Ok(format!("({}) => {}", params.join(", "), ret))
+    }
+
+    /// A return type: a union, possibly parenthesized when it is a function
+    fn postfix_union(&mut self) -> Result<String, String> {
+        let ty = self.union()?;
+        Ok(if ty.contains("=>") {
+            format!("({})", ty)
+        } else {
+            ty
+        })
+    }
+
+    /// Whether a `,` after a return type starts another return type: only at
+    /// the top level of the whole expression
+    fn returns_continue(&self) -> bool {
+        let mut depth = 0i32;
+        for token in &self.tokens[..self.pos] {
+            match token.as_str() {
+                "(" | "{" | "<" => depth += 1,
+                ")" | "}" | ">" => depth -= 1,
+                _ => {}
+            }
+        }
+        depth == 0
+    }
+
+    /// `{ x: T, [K]: V }` after `{`
+    fn table_literal(&mut self) -> Result<String, String> {
+        let mut fields = Vec::new();
+        while !self.eat("}") {
+            if self.eat("[") {
+                let key = self.union()?;
+                self.expect("]")?;
+                self.expect(":")?;
+                fields.push(format!("[key: {}]: {}", key, self.union()?));
+            } else {
+                let name = self.next()?;
+                let optional = self.eat("?");
+                self.expect(":")?;
+                let ty = self.union()?;
+                fields.push(format!(
+                    "{}{}: {}",
+                    name,
+                    if optional { "?" } else { "" },
+                    ty
+                ));
+            }
+            if !self.eat(",") {
+                self.expect("}")?;
+                break;
+            }
+        }
+        Ok(if fields.is_empty() {
+            "{}".to_string()
+        } else {
+            format!("{{ {} }}", fields.join(", "))
+        })
+    }
+}
+
+/// Parenthesize a union or function type before a postfix operator
+fn wrap(ty: &str) -> String {
+    if ty.contains(" | ") || ty.contains("=>") {
+        format!("({})", ty)
+    } else {
+        ty.to_string()
+    }
+}
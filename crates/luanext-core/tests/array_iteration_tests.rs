@@ -0,0 +1,233 @@
+//! Tests for array iteration rewriting
+//!
+//! These tests verify that:
+//! 1. `ipairs` and `pairs` loops over typed arrays become numeric `for` loops
+//! 2. Arrays whose elements may be nil get a nil check, and are not rewritten
+//!    for `pairs`
+//! 3. Destructuring `for [a, b] in xs` loops are rewritten
+//! 4. Loops whose body may grow the array, untyped tables, and literals with
+//!    a nil hole are left alone
+
+use luanext_core::config::OptimizationLevel;
+use luanext_core::optimizer::pipeline::PassPipelineConfig;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_test_helpers::optimize::{enabling, int, only, optimize, remarks_of};
+
+const PASS: &str = "array-iteration";
+
+/// Run array iteration rewriting alone
+fn rewrite_loops(source: &str) -> (String, Vec<Remark>) {
+    optimize(source, OptimizationLevel::Moderate, only(PASS))
+}
+
+#[test]
+fn test_ipairs_over_typed_parameter() {
+    let source = r#"
+        function total(values: number[]): number
+            local sum = 0
+            for i, v in ipairs(values) do
+                sum = sum + v * i
+            end
+            return sum
+        end
+        result = total({1, 2, 3})
+    "#;
+    let (lua, remarks) = rewrite_loops(source);
+
+    assert!(lua.contains("for i = 1, #values do"), "{}", lua);
+    assert!(lua.contains("local v = values[i]"), "{}", lua);
+    assert!(!lua.contains("ipairs"), "{}", lua);
+    assert!(!lua.contains("== nil"), "{}", lua);
+    assert_eq!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied),
+        vec!["rewrote `ipairs` loop over `values` into a numeric `for`"]
+    );
+    assert_eq!(int(&lua, "result"), 14);
+}
+
+#[test]
+fn test_nilable_elements_checked_for_ipairs_kept_for_pairs() {
+    let source = r#"
+        function until_hole(xs: (number | nil)[]): number
+            local sum = 0
+            for _, v in ipairs(xs) do
+                sum = sum + v
+            end
+            return sum
+        end
+        function every(xs: (number | nil)[]): number
+            local count = 0
+            for k in pairs(xs) do
+                count = count + 1
+            end
+            return count
+        end
+        prefix = until_hole({1, 2, nil, 4})
+        counted = every({1, 2, nil, 4})
+    "#;
+    let (lua, remarks) = rewrite_loops(source);
+
+    assert!(lua.contains("for _ = 1, #xs do"), "{}", lua);
+    assert!(lua.contains("if v == nil then"), "{}", lua);
+    assert!(lua.contains("in pairs(xs)"), "{}", lua);
+    assert_eq!(remarks_of(&remarks, PASS, RemarkKind::Applied).len(), 1);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Missed)
+            .iter()
+            .any(|m| m.contains("may be nil")),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "prefix"), 3);
+    assert_eq!(int(&lua, "counted"), 3);
+}
+
+#[test]
+fn test_pairs_over_typed_local_with_output() {
+    let source = r#"
+        local weights: number[] = {2, 3, 4}
+        weighted = 0
+        for k, w in pairs(weights) do
+            weighted = weighted + k * w
+            print(w)
+        end
+    "#;
+    let (lua, remarks) = rewrite_loops(source);
+
+    assert!(lua.contains("for k = 1, #weights do"), "{}", lua);
+    assert!(lua.contains("local w = weights[k]"), "{}", lua);
+    assert!(!lua.contains("pairs"), "{}", lua);
+    assert_eq!(remarks_of(&remarks, PASS, RemarkKind::Applied).len(), 1);
+    assert_eq!(int(&lua, "weighted"), 20);
+}
+
+#[test]
+fn test_destructuring_loop_rewritten() {
+    let source = r#"
+        local items: number[][] = {{1, 10}, {2, 20}, {3, 30}}
+        sum_first = 0
+        sum_second = 0
+        for [a, b] in items do
+            sum_first = sum_first + a
+            sum_second = sum_second + b
+        end
+    "#;
+    let (lua, remarks) = rewrite_loops(source);
+
+    assert!(lua.contains("for __iter_0 = 1, #items do"), "{}", lua);
+    assert!(!lua.contains("ipairs"), "{}", lua);
+    assert_eq!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied),
+        vec!["rewrote destructuring loop over `items` into a numeric `for`"]
+    );
+    assert_eq!(int(&lua, "sum_first"), 6);
+    assert_eq!(int(&lua, "sum_second"), 60);
+}
+
+#[test]
+fn test_growing_or_untyped_arrays_not_rewritten() {
+    let source = r#"
+        function grow(xs: number[]): number
+            local sum = 0
+            for _, v in ipairs(xs) do
+                if v < 3 then
+                    xs[#xs + 1] = v + 10
+                end
+                sum = sum + v
+            end
+            return sum
+        end
+        function insert(xs: number[]): number
+            local sum = 0
+            for _, v in ipairs(xs) do
+                if v < 3 then
+                    table.insert(xs, v + 10)
+                end
+                sum = sum + v
+            end
+            return sum
+        end
+        function untyped(xs)
+            local sum = 0
+            for _, v in ipairs(xs) do
+                sum = sum + v
+            end
+            return sum
+        end
+        grown = grow({1, 2})
+        inserted = insert({1, 2})
+        plain = untyped({1, 2})
+    "#;
+    let (lua, remarks) = rewrite_loops(source);
+
+    assert_eq!(lua.matches("in ipairs(xs)").count(), 3, "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied).is_empty(),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(remarks_of(&remarks, PASS, RemarkKind::Missed).len(), 2);
+    assert_eq!(int(&lua, "grown"), 26);
+    assert_eq!(int(&lua, "inserted"), 26);
+    assert_eq!(int(&lua, "plain"), 3);
+}
+
+#[test]
+fn test_literal_with_nil_hole_not_rewritten() {
+    let source = r#"
+        local xs: number[] = {1, nil, 3}
+        prefix = 0
+        for _, x in ipairs(xs) do
+            prefix = prefix + x
+        end
+        every = 0
+        for _, x in pairs(xs) do
+            every = every + x
+        end
+    "#;
+    let (lua, remarks) = rewrite_loops(source);
+
+    assert!(lua.contains("in ipairs(xs)"), "{}", lua);
+    assert!(lua.contains("in pairs(xs)"), "{}", lua);
+    assert!(!lua.contains("#xs"), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied).is_empty(),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "prefix"), 1);
+    assert_eq!(int(&lua, "every"), 4);
+}
+
+#[test]
+fn test_o2_matches_unoptimized() {
+    let source = r#"
+        function mix(xs: number[], ys: (number | nil)[]): number
+            local out = 0
+            for i, x in ipairs(xs) do
+                out = out + i * x
+            end
+            for _, y in ipairs(ys) do
+                out = out + y
+            end
+            for k, x in pairs(xs) do
+                out = out - k
+            end
+            return out
+        end
+        a = mix({1, 2, 3}, {4, nil, 6})
+        b = mix({}, {})
+        c = mix({5}, {nil, 1})
+    "#;
+    let (o0, _) = optimize(
+        source,
+        OptimizationLevel::None,
+        PassPipelineConfig::default(),
+    );
+    let (o2, _) = optimize(source, OptimizationLevel::Moderate, enabling(PASS));
+
+    assert!(!o2.contains("ipairs"), "{}", o2);
+    for var in ["a", "b", "c"] {
+        assert_eq!(int(&o2, var), int(&o0, var), "{}: {}", var, o2);
+    }
+}
@@ -0,0 +1,276 @@
+//! Integration tests for JSON, YAML and text asset imports
+//!
+//! These tests verify that:
+//! 1. Asset kinds are detected by extension
+//! 2. Object types are inferred from asset data
+//! 3. Synthesized asset modules type-check
+//! 4. Require mode emits each asset once as a module that every importer requires
+//! 5. Bundles emit each asset once as a module returning its data
+
+use bumpalo::Bump;
+use luanext_core::assets::{is_asset_path, Asset, AssetKind, AssetModules};
+use luanext_core::codegen::multi_entry::MultiEntryPlan;
+use luanext_core::codegen::{CodeGenerator, CodeGeneratorBuilder, LuaTarget};
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::MutableProgram;
+use luanext_parser::ast::Program;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::compile::type_check;
+use luanext_test_helpers::LuaExecutor;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const CONFIG_JSON: &str = r#"{
+    "name": "server",
+    "port": 8080,
+    "debug": false,
+    "tags": ["a", "b"],
+    "limits": { "rate": 1.5 }
+}"#;
+
+fn json(contents: &str) -> Asset {
+    Asset::parse(AssetKind::Json, contents).unwrap()
+}
+
+fn create_program<'arena>(
+    source: &str,
+    interner: &StringInterner,
+    common: &luanext_parser::string_interner::CommonIdentifiers,
+    arena: &'arena Bump,
+) -> Program<'arena> {
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, interner, common, arena);
+    parser.parse().expect("Parsing failed")
+}
+
+fn generate(source: &str) -> String {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let program = create_program(source, &interner, &common, &arena);
+    let mut generator = CodeGeneratorBuilder::new(interner).build();
+    generator.generate(&MutableProgram::from_program(&program))
+}
+
+// ============================================================================
+// Parsing and type inference
+// ============================================================================
+
+#[test]
+fn test_asset_kind_from_extension() {
+    assert_eq!(
+        AssetKind::from_path(Path::new("./config.json")),
+        Some(AssetKind::Json)
+    );
+    assert_eq!(
+        AssetKind::from_path(Path::new("data.YML")),
+        Some(AssetKind::Yaml)
+    );
+    assert_eq!(
+        AssetKind::from_path(Path::new("banner.txt")),
+        Some(AssetKind::Text)
+    );
+    assert!(!is_asset_path("./utils"));
+    assert!(!is_asset_path("main.luax"));
+}
+
+#[test]
+fn test_infer_object_type() {
+    assert_eq!(
+        json(CONFIG_JSON).type_annotation(),
+        "{ debug: boolean, limits: { rate: number }, name: string, port: number, tags: Array<string> }"
+    );
+}
+
+#[test]
+fn test_infer_types_for_mixed_arrays_and_non_identifier_keys() {
+    assert_eq!(
+        json(r#"[1, "x", 2]"#).type_annotation(),
+        "Array<number | string>"
+    );
+    assert_eq!(json("[]").type_annotation(), "Array<unknown>");
+    assert_eq!(
+        json(r#"{ "content-type": "json", "end": 1 }"#).type_annotation(),
+        "{ [key: string]: string | number }"
+    );
+}
+
+#[test]
+fn test_parse_yaml_and_text() {
+    let yaml = Asset::parse(
+        AssetKind::Yaml,
+        "host: localhost\nports:\n  - 80\n  - 443\n",
+    )
+    .unwrap();
+    assert_eq!(
+        yaml.type_annotation(),
+        "{ host: string, ports: Array<number> }"
+    );
+
+    let text = Asset::parse(AssetKind::Text, "line \"one\"\nline two\n").unwrap();
+    assert_eq!(text.type_annotation(), "string");
+    assert_eq!(text.to_lua_expression(), r#""line \"one\"\nline two\n""#);
+}
+
+#[test]
+fn test_invalid_json_is_an_error() {
+    assert!(Asset::parse(AssetKind::Json, "{ name: ").is_err());
+}
+
+#[test]
+fn test_synthesized_module_type_checks() {
+    let module = json(CONFIG_JSON).to_luanext_module();
+    assert!(module.contains("export const port = __asset.port"));
+    assert!(module.contains("export default __asset"));
+    type_check(&module).unwrap();
+
+    let quoted = json(r#"{ "content-type": "json" }"#).to_luanext_module();
+    type_check(&quoted).unwrap();
+}
+
+// ============================================================================
+// Code generation
+// ============================================================================
+
+#[test]
+fn test_lua_expression_evaluates_to_data() {
+    let code = format!(
+        "local cfg = {}\nresult = cfg.name .. \":\" .. cfg.port .. \":\" .. cfg.tags[2] .. \":\" .. cfg.limits.rate",
+        json(CONFIG_JSON).to_lua_expression()
+    );
+    let executor = LuaExecutor::new().unwrap();
+    let result: String = executor.execute_and_get(&code, "result").unwrap();
+    assert_eq!(result, "server:8080:b:1.5");
+}
+
+#[test]
+fn test_require_mode_requires_asset_module() {
+    let code = generate(
+        r#"
+            import config from "./config.json"
+            import { port } from "./config.json"
+            result = config.name .. ":" .. tostring(port)
+        "#,
+    );
+    assert_eq!(code.matches("require(\"./config.json\")").count(), 2);
+    assert!(!code.contains("8080"));
+
+    let executor = LuaExecutor::new().unwrap();
+    executor
+        .execute(&format!(
+            "package.preload[\"./config.json\"] = function() {} end",
+            json(CONFIG_JSON).to_lua_module()
+        ))
+        .unwrap();
+    let result: String = executor.execute_and_get(&code, "result").unwrap();
+    assert_eq!(result, "server:8080");
+}
+
+#[test]
+fn test_require_mode_importers_share_asset_table() {
+    let first = generate(
+        r#"
+            import config from "./config.json"
+            first = config
+        "#,
+    );
+    let second = generate(
+        r#"
+            import config from "./config.json"
+            second = config
+        "#,
+    );
+
+    let executor = LuaExecutor::new().unwrap();
+    executor
+        .execute(&format!(
+            "package.preload[\"./config.json\"] = function() {} end",
+            json(CONFIG_JSON).to_lua_module()
+        ))
+        .unwrap();
+    executor.execute(&first).unwrap();
+    executor.execute(&second).unwrap();
+    let same: bool = executor
+        .execute_with_result("return first == second")
+        .unwrap();
+    assert!(same);
+}
+
+#[test]
+fn test_bundle_emits_asset_module_once() {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+
+    let asset = json(CONFIG_JSON);
+    let entry = create_program(
+        r#"
+            import config from "./config.json"
+            import { label } from "./label"
+            result = label(config.name)
+        "#,
+        &interner,
+        &common,
+        &arena,
+    );
+    let label = create_program(
+        r#"
+            import config from "./config.json"
+            export function label(s: string): string
+                return s .. "@" .. tostring(config.port)
+            end
+        "#,
+        &interner,
+        &common,
+        &arena,
+    );
+    let config = create_program(&asset.to_luanext_module(), &interner, &common, &arena);
+
+    let import_map: HashMap<String, String> = [
+        ("./config.json".to_string(), "config.json".to_string()),
+        ("./label".to_string(), "label.luax".to_string()),
+    ]
+    .into_iter()
+    .collect();
+    let modules = vec![
+        ("main.luax".to_string(), &entry, import_map.clone()),
+        ("label.luax".to_string(), &label, import_map.clone()),
+        ("config.json".to_string(), &config, HashMap::new()),
+    ];
+
+    let plan = MultiEntryPlan {
+        entries: vec![luanext_core::codegen::multi_entry::EntryModules {
+            entry: PathBuf::from("main.luax"),
+            modules: vec![
+                PathBuf::from("main.luax"),
+                PathBuf::from("label.luax"),
+                PathBuf::from("config.json"),
+            ],
+        }],
+        shared: Vec::new(),
+    };
+    let mut assets = AssetModules::new();
+    assets.insert("config.json".to_string(), asset.to_lua_expression());
+
+    let output = CodeGenerator::generate_multi_entry_bundles(
+        &modules,
+        &plan,
+        "shared",
+        LuaTarget::Lua54,
+        interner,
+        false,
+        &assets,
+    );
+    let bundle = &output.bundles[0].1;
+    assert_eq!(bundle.matches("__modules[\"config.json\"]").count(), 1);
+    assert!(bundle.contains("-- Asset: config.json"));
+
+    let executor = LuaExecutor::new().unwrap();
+    let result: String = executor.execute_and_get(bundle, "result").unwrap();
+    assert_eq!(result, "server@8080");
+}
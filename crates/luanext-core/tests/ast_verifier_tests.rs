@@ -0,0 +1,209 @@
+//! Tests for the AST invariant verifier
+//!
+//! These tests verify that:
+//! 1. The verifier reports unresolved identifiers, duplicate locals, jumps
+//!    without a target and inverted spans
+//! 2. Violations the original program already had are not reported
+//! 3. Every pass keeps the invariants on representative programs
+//! 4. Verification is off unless enabled, and the optimizer test harness enables it
+
+use bumpalo::Bump;
+use luanext_core::config::OptimizationLevel;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::optimizer::pipeline::OptimizationGoal;
+use luanext_core::optimizer::verifier::{Verifier, ViolationKind};
+use luanext_core::optimizer::Optimizer;
+use luanext_parser::ast::statement::Statement;
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::optimize::parse;
+use std::sync::Arc;
+
+/// Violation kinds `broken` has that `original` does not
+fn new_violations(original: &str, broken: &str) -> Vec<(ViolationKind, String)> {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let original = parse(original, &interner, &common, &arena);
+    let broken = parse(broken, &interner, &common, &arena);
+
+    Verifier::new(&original, interner)
+        .check(&broken)
+        .into_iter()
+        .map(|v| (v.kind, v.message))
+        .collect()
+}
+
+/// Optimize `source` at O3 with the verifier enabled
+fn optimize_verified(source: &str, goal: OptimizationGoal) -> Result<(), String> {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let mut program = parse(source, &interner, &common, &arena);
+
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut optimizer = Optimizer::new(OptimizationLevel::Aggressive, handler, interner);
+    optimizer.set_goal(goal);
+    optimizer.enable_verifier();
+    optimizer.optimize(&mut program, &arena)
+}
+
+#[test]
+fn test_unresolved_identifier_reported() {
+    let violations = new_violations("local x = 1\nprint(x)", "print(y)");
+
+    assert_eq!(violations.len(), 1, "{:?}", violations);
+    assert_eq!(violations[0].0, ViolationKind::UnresolvedIdentifier);
+    assert!(violations[0].1.contains("`y`"), "{:?}", violations);
+}
+
+#[test]
+fn test_globals_of_original_program_resolve() {
+    let source = "configuredLimit = 10\nprint(configuredLimit, math.max(1, 2))";
+    assert!(new_violations(source, source).is_empty());
+    // Standard library globals resolve even if the original never used them
+    assert!(new_violations(source, "print(table.concat({}))").is_empty());
+}
+
+#[test]
+fn test_local_out_of_scope_reported() {
+    let violations = new_violations(
+        "do\n  local hidden = 1\n  print(hidden)\nend",
+        "do\n  local hidden = 1\nend\nprint(hidden)",
+    );
+
+    assert_eq!(violations.len(), 1, "{:?}", violations);
+    assert_eq!(violations[0].0, ViolationKind::UnresolvedIdentifier);
+}
+
+#[test]
+fn test_duplicate_local_reported() {
+    let violations = new_violations("local a = 1", "local a = 1\nlocal a = 2");
+
+    assert_eq!(violations.len(), 1, "{:?}", violations);
+    assert_eq!(violations[0].0, ViolationKind::DuplicateLocal);
+    assert!(violations[0].1.contains("`a`"), "{:?}", violations);
+
+    // Shadowing in a nested scope is fine
+    let nested = new_violations("local a = 1", "local a = 1\ndo\n  local a = 2\nend");
+    assert!(nested.is_empty(), "{:?}", nested);
+}
+
+#[test]
+fn test_break_outside_loop_reported() {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let original = parse("while true do\n  break\nend", &interner, &common, &arena);
+    let verifier = Verifier::new(&original, interner);
+    assert!(verifier.check(&original).is_empty());
+
+    // Hoist the loop body out of the loop
+    let mut broken = original.clone();
+    let Statement::While(while_stmt) = &original.statements[0] else {
+        panic!("expected a while loop");
+    };
+    broken.statements = while_stmt.body.statements.to_vec();
+
+    let violations = verifier.check(&broken);
+    assert_eq!(violations.len(), 1, "{:?}", violations);
+    assert_eq!(violations[0].kind, ViolationKind::JumpOutsideLoop);
+}
+
+#[test]
+fn test_goto_without_label_reported() {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let original = parse(
+        "local n = 0\n::retry::\nn = n + 1\nif n < 3 then\n  goto retry\nend",
+        &interner,
+        &common,
+        &arena,
+    );
+    let verifier = Verifier::new(&original, interner);
+    assert!(verifier.check(&original).is_empty());
+
+    let mut broken = original.clone();
+    broken
+        .statements
+        .retain(|stmt| !matches!(stmt, Statement::Label(_)));
+
+    let violations = verifier.check(&broken);
+    assert_eq!(violations.len(), 1, "{:?}", violations);
+    assert_eq!(violations[0].kind, ViolationKind::UndefinedLabel);
+    assert!(violations[0].message.contains("retry"), "{:?}", violations);
+}
+
+#[test]
+fn test_inverted_span_reported() {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let original = parse("print(1)", &interner, &common, &arena);
+    let verifier = Verifier::new(&original, interner);
+
+    let mut broken = original.clone();
+    let Statement::Expression(expr) = &mut broken.statements[0] else {
+        panic!("expected an expression statement");
+    };
+    expr.span = Span::new(10, 2, 1, 1);
+
+    let violations = verifier.check(&broken);
+    assert!(
+        violations
+            .iter()
+            .any(|v| v.kind == ViolationKind::InvalidSpan),
+        "{:?}",
+        violations
+    );
+}
+
+#[test]
+fn test_all_passes_keep_invariants() {
+    let source = r#"
+        function clamp(value: number, low: number, high: number): number
+            if value < low then
+                return low
+            elseif value > high then
+                return high
+            end
+            return value
+        end
+
+        function total(values: number[]): number
+            local sum = 0
+            for i = 1, #values do
+                if values[i] < 0 then
+                    break
+                end
+                local doubled = (x) => x * 2
+                sum = sum + clamp(doubled(values[i]), 0, 100)
+            end
+            local i = 0
+            while i < 3 do
+                i = i + 1
+                sum = sum + 2 * 3
+            end
+            return sum
+        end
+
+        local label = "total: " .. "values"
+        print(label, total({1, 2, 3}))
+    "#;
+
+    for goal in [OptimizationGoal::Speed, OptimizationGoal::Size] {
+        if let Err(err) = optimize_verified(source, goal) {
+            panic!("{:?}: {}", goal, err);
+        }
+    }
+}
+
+#[test]
+fn test_verifier_off_by_default() {
+    let interner = Arc::new(StringInterner::new());
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let optimizer = Optimizer::new(OptimizationLevel::Moderate, handler, interner);
+
+    assert!(!optimizer.verifies());
+}
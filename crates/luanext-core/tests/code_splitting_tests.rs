@@ -0,0 +1,433 @@
+//! Integration tests for code splitting in bundle mode
+//!
+//! These tests verify that:
+//! 1. Dynamic `import()` targets are moved into separate chunks
+//! 2. Modules shared between chunks go into the common chunk
+//! 3. Statically imported modules stay in the main chunk
+//! 4. The generated chunk loader runs and loads chunks lazily
+
+use bumpalo::Bump;
+use luanext_core::assets::AssetModules;
+use luanext_core::codegen::code_splitting::{
+    chunk_name, ChunkPlan, SplitBundle, COMMON_CHUNK_NAME,
+};
+use luanext_core::codegen::{CodeGenerator, LuaTarget};
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_parser::ast::Program;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::LuaExecutor;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn create_program<'arena>(
+    source: &str,
+    interner: &StringInterner,
+    common: &luanext_parser::string_interner::CommonIdentifiers,
+    arena: &'arena Bump,
+) -> Program<'arena> {
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, interner, common, arena);
+    parser.parse().expect("Parsing failed")
+}
+
+fn create_modules<'arena>(
+    sources: &[(&str, &str)],
+    arena: &'arena Bump,
+) -> (Vec<(String, Program<'arena>)>, Arc<StringInterner>) {
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let modules = sources
+        .iter()
+        .map(|&(name, source)| {
+            (
+                name.to_string(),
+                create_program(source, &interner, &common, arena),
+            )
+        })
+        .collect();
+    (modules, interner)
+}
+
+fn plan(sources: &[(&str, &str)], entry: &str) -> ChunkPlan {
+    let arena = Bump::new();
+    let (modules, interner) = create_modules(sources, &arena);
+    let module_refs: Vec<(String, &Program, HashMap<String, String>)> = modules
+        .iter()
+        .map(|(id, program)| (id.clone(), program, HashMap::new()))
+        .collect();
+    ChunkPlan::build(&module_refs, entry, &interner)
+}
+
+fn split(sources: &[(&str, &str)], entry: &str, tree_shaking: bool) -> SplitBundle {
+    let arena = Bump::new();
+    let (modules, interner) = create_modules(sources, &arena);
+    let module_refs: Vec<(String, &Program, HashMap<String, String>)> = modules
+        .iter()
+        .map(|(id, program)| (id.clone(), program, HashMap::new()))
+        .collect();
+    CodeGenerator::generate_split_bundle(
+        &module_refs,
+        entry,
+        LuaTarget::Lua54,
+        interner,
+        tree_shaking,
+        false, // no scope hoisting
+        &AssetModules::new(),
+    )
+}
+
+/// Run a split bundle with its chunks registered in `package.preload`
+fn run_split_bundle(bundle: &SplitBundle, result_var: &str) -> f64 {
+    let executor = LuaExecutor::new().unwrap();
+    for chunk in &bundle.chunks {
+        let preload = format!(
+            "package.preload[\"{}\"] = function() return load([==[{}]==])() end",
+            chunk.name, chunk.code
+        );
+        executor.execute(&preload).unwrap();
+    }
+    executor.execute_and_get(&bundle.main, result_var).unwrap()
+}
+
+// ============================================================================
+// Chunk planning
+// ============================================================================
+
+#[test]
+fn test_no_dynamic_imports_single_chunk() {
+    let sources = [
+        (
+            "main.lua",
+            r#"
+                import { add } from "math"
+                const x = add(1, 2)
+            "#,
+        ),
+        (
+            "math.lua",
+            r#"
+                export function add(a: number, b: number): number
+                    return a + b
+                end
+            "#,
+        ),
+    ];
+
+    let plan = plan(&sources, "main.lua");
+    assert_eq!(plan.main.modules, vec!["main.lua", "math.lua"]);
+    assert!(plan.chunks.is_empty());
+    assert!(plan.common.is_none());
+}
+
+#[test]
+fn test_dynamic_import_creates_chunk() {
+    let sources = [
+        (
+            "main.lua",
+            r#"
+                function loadEditor()
+                    return import("editor")
+                end
+            "#,
+        ),
+        (
+            "editor.lua",
+            r#"
+                import { format } from "formatter"
+                export function open(): string
+                    return format("editor")
+                end
+            "#,
+        ),
+        (
+            "formatter.lua",
+            r#"
+                export function format(s: string): string
+                    return "[" .. s .. "]"
+                end
+            "#,
+        ),
+    ];
+
+    let plan = plan(&sources, "main.lua");
+    assert_eq!(plan.main.modules, vec!["main.lua"]);
+    assert_eq!(plan.chunks.len(), 1);
+    assert_eq!(plan.chunks[0].name, chunk_name("editor.lua"));
+    assert_eq!(plan.chunks[0].modules, vec!["editor.lua", "formatter.lua"]);
+    assert_eq!(
+        plan.chunk_map,
+        vec![("editor.lua".to_string(), vec![chunk_name("editor.lua")])]
+    );
+}
+
+#[test]
+fn test_statically_imported_module_stays_in_main() {
+    let sources = [
+        (
+            "main.lua",
+            r#"
+                import { helper } from "utils"
+                const lazy = import("utils")
+                const x = helper()
+            "#,
+        ),
+        (
+            "utils.lua",
+            r#"
+                export function helper(): number
+                    return 1
+                end
+            "#,
+        ),
+    ];
+
+    let plan = plan(&sources, "main.lua");
+    assert_eq!(plan.main.modules, vec!["main.lua", "utils.lua"]);
+    assert!(plan.chunks.is_empty());
+    assert!(plan.chunk_map.is_empty());
+}
+
+#[test]
+fn test_shared_modules_go_to_common_chunk() {
+    let sources = [
+        (
+            "main.lua",
+            r#"
+                const a = import("pageA")
+                const b = import("pageB")
+            "#,
+        ),
+        (
+            "pageA.lua",
+            r#"
+                import { render } from "widgets"
+                export const name = render("A")
+            "#,
+        ),
+        (
+            "pageB.lua",
+            r#"
+                import { render } from "widgets"
+                export const name = render("B")
+            "#,
+        ),
+        (
+            "widgets.lua",
+            r#"
+                export function render(s: string): string
+                    return s
+                end
+            "#,
+        ),
+    ];
+
+    let plan = plan(&sources, "main.lua");
+    let common = plan.common.as_ref().expect("common chunk expected");
+    assert_eq!(common.name, COMMON_CHUNK_NAME);
+    assert_eq!(common.modules, vec!["widgets.lua"]);
+
+    let names: Vec<&str> = plan.chunks.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![chunk_name("pageA.lua"), chunk_name("pageB.lua")]
+    );
+    assert!(plan
+        .chunks
+        .iter()
+        .all(|c| !c.modules.contains(&"widgets.lua".to_string())));
+
+    for (_, required) in &plan.chunk_map {
+        assert_eq!(required[0], COMMON_CHUNK_NAME);
+    }
+}
+
+#[test]
+fn test_nested_dynamic_import() {
+    let sources = [
+        ("main.lua", r#"const settings = import("settings")"#),
+        (
+            "settings.lua",
+            r#"
+                export function advanced()
+                    return import("advanced")
+                end
+            "#,
+        ),
+        ("advanced.lua", r#"export const level = 2"#),
+    ];
+
+    let plan = plan(&sources, "main.lua");
+    let names: Vec<&str> = plan.chunks.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![chunk_name("settings.lua"), chunk_name("advanced.lua")]
+    );
+}
+
+#[test]
+fn test_chunk_names_do_not_collide_after_sanitizing() {
+    assert_ne!(chunk_name("a/b.lua"), chunk_name("a_b.lua"));
+    assert_eq!(chunk_name("a/b.lua"), chunk_name("a/b.lua"));
+
+    let sources = [
+        (
+            "main.lua",
+            r#"
+                const first = import("a/b")
+                const second = import("a_b")
+            "#,
+        ),
+        ("a/b.lua", r#"export const value = 1"#),
+        ("a_b.lua", r#"export const value = 2"#),
+    ];
+
+    let plan = plan(&sources, "main.lua");
+    assert_eq!(plan.chunks.len(), 2);
+    assert_ne!(plan.chunks[0].name, plan.chunks[1].name);
+    assert!(plan.chunks[0].name.starts_with("chunk_a_b_"));
+    assert!(plan.chunks[1].name.starts_with("chunk_a_b_"));
+}
+
+// ============================================================================
+// Code generation
+// ============================================================================
+
+#[test]
+fn test_main_bundle_contains_chunk_loader() {
+    let sources = [
+        ("main.lua", r#"const feature = import("feature")"#),
+        ("feature.lua", r#"export const value = 42"#),
+    ];
+
+    let bundle = split(&sources, "main.lua", false);
+    assert!(bundle.main.contains("local function __load_chunk(name)"));
+    assert!(bundle.main.contains(&format!(
+        "__chunk_map[\"feature.lua\"] = {{ \"{}\" }}",
+        chunk_name("feature.lua")
+    )));
+    assert!(bundle.main.contains("__import(\"feature.lua\")"));
+    assert!(!bundle.main.contains("__modules[\"feature.lua\"]"));
+
+    assert_eq!(bundle.chunks.len(), 1);
+    assert_eq!(
+        bundle.chunks[0].file_name,
+        format!("{}.lua", chunk_name("feature.lua"))
+    );
+    assert!(bundle.chunks[0]
+        .code
+        .contains("return function(__modules, __require, __import)"));
+    assert!(bundle.chunks[0].code.contains("__modules[\"feature.lua\"]"));
+}
+
+#[test]
+fn test_tree_shaking_per_chunk() {
+    let sources = [
+        ("main.lua", r#"const feature = import("feature")"#),
+        (
+            "feature.lua",
+            r#"
+                import { used } from "helpers"
+                export const value = used()
+            "#,
+        ),
+        (
+            "helpers.lua",
+            r#"
+                export function used(): number
+                    return 1
+                end
+            "#,
+        ),
+        (
+            "orphan.lua",
+            r#"
+                export function neverImported(): number
+                    return 0
+                end
+            "#,
+        ),
+    ];
+
+    let bundle = split(&sources, "main.lua", true);
+    let all_code: String = bundle.chunks.iter().map(|c| c.code.as_str()).collect();
+    assert!(all_code.contains("__modules[\"helpers.lua\"]"));
+    assert!(!all_code.contains("orphan.lua"));
+    assert!(!bundle.main.contains("orphan.lua"));
+}
+
+#[test]
+fn test_execute_split_bundle() {
+    let sources = [
+        (
+            "main.lua",
+            r#"
+                const feature = import("feature")
+                result = feature.double(21)
+            "#,
+        ),
+        (
+            "feature.lua",
+            r#"
+                export function double(n: number): number
+                    return n * 2
+                end
+            "#,
+        ),
+    ];
+
+    let bundle = split(&sources, "main.lua", false);
+    assert_eq!(run_split_bundle(&bundle, "result"), 42.0);
+}
+
+#[test]
+fn test_execute_common_chunk_loaded_once() {
+    let sources = [
+        (
+            "main.lua",
+            r#"
+                const a = import("pageA")
+                const b = import("pageB")
+                result = a.value + b.value + a.counted() * 100
+            "#,
+        ),
+        (
+            "pageA.lua",
+            r#"
+                import { tick, count } from "counter"
+                export const value = tick()
+                export function counted(): number
+                    return count()
+                end
+            "#,
+        ),
+        (
+            "pageB.lua",
+            r#"
+                import { tick } from "counter"
+                export const value = tick()
+            "#,
+        ),
+        (
+            "counter.lua",
+            r#"
+                local n = 0
+                export function tick(): number
+                    n = n + 1
+                    return n
+                end
+                export function count(): number
+                    return n
+                end
+            "#,
+        ),
+    ];
+
+    let bundle = split(&sources, "main.lua", false);
+    assert!(bundle.chunks.iter().any(|c| c.name == COMMON_CHUNK_NAME));
+    // counter.lua is initialized once: tick() returns 1 then 2, count() is 2
+    assert_eq!(run_split_bundle(&bundle, "result"), 203.0);
+}
@@ -552,6 +552,7 @@ fn create_module_node(path: PathBuf) -> ModuleNode {
         exports: FxHashMap::default(),
         imports: FxHashMap::default(),
         re_exports: Vec::new(),
+        dynamic_imports: Vec::new(),
         is_reachable: true,
     }
 }
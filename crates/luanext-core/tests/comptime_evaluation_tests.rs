@@ -0,0 +1,235 @@
+//! Tests for compile-time evaluation of const functions
+//!
+//! These tests verify that:
+//! 1. Only functions `optimizer.hints` marks `comptime` are evaluated
+//! 2. Calls to const functions with constant arguments are replaced by a
+//!    literal of their result, including tables
+//! 3. Const functions may call earlier top-level functions, recursively, and
+//!    read top-level constants
+//! 4. Calls that cannot be evaluated are kept, with a missed remark giving
+//!    the reason
+//! 5. Calls with arguments only known at runtime are left alone
+//! 6. Evaluation does not change what the program computes
+
+use luanext_core::codegen::LuaTarget;
+use luanext_core::config::OptimizationLevel;
+use luanext_core::optimizer::hints::FreeFunctionHints;
+use luanext_core::optimizer::pipeline::PassPipelineConfig;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_test_helpers::optimize::{
+    enabling, int, only, optimize, optimize_with, remarks_of, string,
+};
+
+const PASS: &str = "comptime-evaluation";
+
+/// Marks the const functions the tests evaluate
+const HINTS: &str = r#"
+optimizer:
+  hints:
+    squares: [comptime]
+    fibonacci: [comptime]
+    banner: [comptime]
+    config: [comptime]
+    half: [comptime]
+    forever: [comptime]
+    twice: [comptime]
+    cube: [comptime]
+"#;
+
+/// Optimize `source` with the functions in `HINTS` marked `comptime`
+fn optimize_marked(
+    source: &str,
+    level: OptimizationLevel,
+    config: PassPipelineConfig,
+) -> (String, Vec<Remark>) {
+    let hints = FreeFunctionHints::from_config_str(HINTS).expect("Invalid hints");
+    optimize_with(source, level, LuaTarget::default(), |optimizer, _| {
+        optimizer.set_pass_pipeline(config);
+        optimizer.set_function_hints(hints);
+    })
+}
+
+/// Run compile-time evaluation alone at O1
+fn evaluate(source: &str) -> (String, Vec<Remark>) {
+    optimize_marked(source, OptimizationLevel::Minimal, only(PASS))
+}
+
+#[test]
+fn test_only_functions_marked_comptime_are_evaluated() {
+    let source = r#"
+        const plain = (n: integer): integer => n * n
+        function cube(n: integer): integer
+            return n * n * n
+        end
+        p = plain(4)
+        c = cube(3)
+    "#;
+    let (lua, remarks) = evaluate(source);
+
+    assert!(lua.contains("plain(4)"), "{}", lua);
+    assert!(lua.contains("c = 27"), "{}", lua);
+    assert!(
+        !remarks
+            .iter()
+            .any(|r| r.pass == PASS && r.message.contains("`plain`")),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "p"), 16);
+    assert_eq!(int(&lua, "c"), 27);
+
+    let (unmarked, _) = optimize(source, OptimizationLevel::Minimal, only(PASS));
+    assert!(unmarked.contains("cube(3)"), "{}", unmarked);
+}
+
+const COMPTIME: &str = r#"
+    const squares = function(n: integer): integer[]
+        local out = {}
+        for i = 1, n do
+            out[i] = i * i
+        end
+        return out
+    end
+    function fib(n: integer): integer
+        if n < 2 then
+            return n
+        end
+        return fib(n - 1) + fib(n - 2)
+    end
+    const fibonacci = (n: integer): integer => fib(n)
+    const SEPARATOR = "-"
+    const banner = function(word: string, width: integer): string
+        local line = string.rep(SEPARATOR, width)
+        return line .. " " .. string.upper(word) .. " " .. line
+    end
+    const config = () => ({ debug: false, level: 3, name: "app" })
+    const half = (n: number): number => n / 2
+    const forever = function(): integer
+        local n = 0
+        while true do
+            n = n + 1
+        end
+        return n
+    end
+    function dynamic(n: integer): integer
+        return fibonacci(n)
+    end
+    const lookup = squares(5)
+    sq = lookup[4] + #lookup
+    f = fibonacci(15)
+    b = banner("hi", 3)
+    const settings = config()
+    level = settings.level
+    h = half(3) * 2
+    whole = half(4)
+    d = dynamic(10)
+    if false then
+        stuck = forever()
+    end
+"#;
+
+#[test]
+fn test_calls_with_constant_arguments_are_evaluated() {
+    let (lua, remarks) = evaluate(COMPTIME);
+
+    assert!(
+        lua.contains("local lookup <const> = {1, 4, 9, 16, 25}"),
+        "{}",
+        lua
+    );
+    assert!(lua.contains("f = 610"), "{}", lua);
+    assert!(lua.contains("b = \"--- HI ---\""), "{}", lua);
+    assert!(lua.contains("h = 1.5 * 2"), "{}", lua);
+    let applied = remarks_of(&remarks, PASS, RemarkKind::Applied);
+    assert!(
+        applied
+            .iter()
+            .any(|message| message.starts_with("evaluated call to `fibonacci` at compile time")),
+        "{:?}",
+        applied
+    );
+    assert_eq!(int(&lua, "sq"), 21);
+    assert_eq!(int(&lua, "f"), 610);
+    assert_eq!(string(&lua, "b"), "--- HI ---");
+}
+
+#[test]
+fn test_tables_with_fields_become_object_literals() {
+    let (lua, _) = evaluate(COMPTIME);
+
+    assert!(!lua.contains("config()"), "{}", lua);
+    assert!(lua.contains("level = 3"), "{}", lua);
+    assert_eq!(int(&lua, "level"), 3);
+}
+
+#[test]
+fn test_calls_that_cannot_be_evaluated_are_kept() {
+    let (lua, remarks) = evaluate(COMPTIME);
+    let missed = remarks_of(&remarks, PASS, RemarkKind::Missed);
+
+    assert!(
+        missed.contains(
+            &"call to `half` not evaluated at compile time: it returns the float 2, whose literal Lua 5.3 reads as an integer"
+        ),
+        "{:?}",
+        missed
+    );
+    assert!(
+        missed.contains(
+            &"call to `forever` not evaluated at compile time: it takes more than 100000 steps"
+        ),
+        "{:?}",
+        missed
+    );
+    assert!(lua.contains("half(4)"), "{}", lua);
+    assert!(lua.contains("forever()"), "{}", lua);
+}
+
+#[test]
+fn test_runtime_arguments_are_left_alone() {
+    let (lua, remarks) = evaluate(COMPTIME);
+
+    assert!(lua.contains("return fibonacci(n)"), "{}", lua);
+    assert!(!remarks
+        .iter()
+        .any(|r| r.pass == PASS && r.message.contains("`fibonacci` not evaluated")));
+    assert_eq!(int(&lua, "d"), 55);
+}
+
+#[test]
+fn test_names_declared_twice_are_not_evaluated() {
+    let source = r#"
+        const LIMIT = 10
+        const twice = (n: integer): integer => n * LIMIT
+        function shadow(LIMIT: integer): integer
+            return LIMIT
+        end
+        t = twice(2) + shadow(1)
+    "#;
+    let (lua, remarks) = evaluate(source);
+
+    assert!(lua.contains("twice(2)"), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Missed).contains(
+            &"call to `twice` not evaluated at compile time: it reads `LIMIT`, which is not known at compile time"
+        ),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "t"), 21);
+}
+
+#[test]
+fn test_comptime_evaluation_preserves_results_in_full_pipeline() {
+    let (plain, _) = optimize(
+        COMPTIME,
+        OptimizationLevel::None,
+        PassPipelineConfig::default(),
+    );
+    let (optimized, _) = optimize_marked(COMPTIME, OptimizationLevel::Minimal, enabling(PASS));
+
+    for var in ["sq", "f", "level", "d"] {
+        assert_eq!(int(&plain, var), int(&optimized, var), "{}", var);
+    }
+    assert_eq!(string(&plain, "b"), string(&optimized, "b"));
+}
@@ -0,0 +1,185 @@
+//! Tests for constant table and string pooling
+//!
+//! These tests verify that:
+//! 1. `const` literal tables inside functions that are only read become
+//!    module-level locals, shared between identical tables
+//! 2. Long `const` strings are pooled the same way, short ones are not
+//! 3. Tables that are stored into, escape or are used other than by reading
+//!    them are rebuilt on every call, with a missed remark
+//! 4. Pooling does not change what the program computes
+
+use luanext_core::config::OptimizationLevel;
+use luanext_core::optimizer::pipeline::PassPipelineConfig;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_test_helpers::optimize::{boolean, enabling, int, only, optimize, remarks_of, string};
+
+const PASS: &str = "constant-pooling";
+
+/// Run constant pooling alone at O2
+fn pool(source: &str) -> (String, Vec<Remark>) {
+    optimize(source, OptimizationLevel::Moderate, only(PASS))
+}
+
+const CONSTANTS: &str = r#"
+    function weekday(n: integer): string
+        const names = { "Mon", "Tue", "Wed" }
+        return names[n]
+    end
+    function letters(): integer
+        const days = { "Mon", "Tue", "Wed" }
+        local total = 0
+        for _, day in ipairs(days) do
+            total = total + #day
+        end
+        return total + #days
+    end
+    function edited(): integer
+        const edits = { 1, 2, 3 }
+        edits[1] = edits[1] + 10
+        return edits[1]
+    end
+    function pushed(): integer
+        const items = { 1, 2 }
+        table.insert(items, 3)
+        return #items
+    end
+    function leaked()
+        const pair = { 4, 5 }
+        return pair
+    end
+    function summed(): integer
+        const terms = { 6, 7 }
+        return sum(terms) + 1
+    end
+    function sum(t): integer
+        return t[1] + t[2]
+    end
+    function message(): string
+        const text = "this message is longer than thirty-two bytes"
+        const short = "short"
+        return text .. short
+    end
+    first = weekday(2)
+    count = letters()
+    e1 = edited()
+    e2 = edited()
+    p1 = pushed()
+    p2 = pushed()
+    distinct = leaked() ~= leaked()
+    total = summed()
+    m = message()
+"#;
+
+#[test]
+fn test_read_only_tables_are_pooled_and_shared() {
+    let (lua, remarks) = pool(CONSTANTS);
+
+    assert!(lua.contains("local names <const> = __pool_0"), "{}", lua);
+    assert!(lua.contains("local days <const> = __pool_0"), "{}", lua);
+    let pooled = lua.find("__pool_0 =").unwrap();
+    let user = lua.find("function weekday").unwrap();
+    assert!(pooled < user, "{}", lua);
+    let applied = remarks_of(&remarks, PASS, RemarkKind::Applied);
+    assert!(
+        applied.contains(&"pooled table `names` (21 bytes) as `__pool_0`"),
+        "{:?}",
+        applied
+    );
+    assert!(
+        applied.contains(
+            &"pooled table `days` (21 bytes) as `__pool_0`, shared with an identical one"
+        ),
+        "{:?}",
+        applied
+    );
+    assert_eq!(string(&lua, "first"), "Tue");
+    assert_eq!(int(&lua, "count"), 12);
+}
+
+#[test]
+fn test_long_strings_are_pooled() {
+    let (lua, _) = pool(CONSTANTS);
+
+    assert!(lua.contains("local text <const> = __pool_1"), "{}", lua);
+    assert!(!lua.contains("__pool_2"), "{}", lua);
+    assert_eq!(
+        string(&lua, "m"),
+        "this message is longer than thirty-two bytesshort"
+    );
+}
+
+#[test]
+fn test_tables_that_may_change_are_rebuilt() {
+    let (lua, remarks) = pool(CONSTANTS);
+    let missed = remarks_of(&remarks, PASS, RemarkKind::Missed);
+
+    assert!(
+        missed.contains(&"table `edits` is stored into, so each call needs its own"),
+        "{:?}",
+        missed
+    );
+    assert!(
+        missed.contains(&"table `items` escapes, so each call needs its own"),
+        "{:?}",
+        missed
+    );
+    assert!(
+        missed.contains(&"table `pair` escapes, so each call needs its own"),
+        "{:?}",
+        missed
+    );
+    assert!(
+        missed.contains(&"table `terms` is used other than by reading it"),
+        "{:?}",
+        missed
+    );
+    assert!(!lua.contains("__pool_2"), "{}", lua);
+    assert_eq!(int(&lua, "e2"), 11);
+    assert_eq!(int(&lua, "p2"), 3);
+    assert!(boolean(&lua, "distinct"));
+    assert_eq!(int(&lua, "total"), 14);
+}
+
+#[test]
+fn test_pooling_reports_shared_bytes() {
+    let (_, remarks) = pool(CONSTANTS);
+
+    assert_eq!(
+        remarks_of(&remarks, PASS, RemarkKind::Analysis),
+        vec![
+            "pooled 3 constants into 2 module-level locals, sharing 21 bytes of duplicate literals"
+        ]
+    );
+}
+
+#[test]
+fn test_top_level_tables_are_left_alone() {
+    let source = r#"
+        const NAMES = { "a", "b" }
+        function pick(i: integer): string
+            return NAMES[i]
+        end
+        picked = pick(1)
+    "#;
+    let (lua, remarks) = pool(source);
+
+    assert!(!lua.contains("__pool_"), "{}", lua);
+    assert!(remarks_of(&remarks, PASS, RemarkKind::Applied).is_empty());
+    assert_eq!(string(&lua, "picked"), "a");
+}
+
+#[test]
+fn test_pooling_preserves_results_in_full_pipeline() {
+    let (plain, _) = optimize(
+        CONSTANTS,
+        OptimizationLevel::None,
+        PassPipelineConfig::default(),
+    );
+    let (optimized, _) = optimize(CONSTANTS, OptimizationLevel::Moderate, enabling(PASS));
+
+    for var in ["count", "e1", "e2", "p1", "p2", "total"] {
+        assert_eq!(int(&plain, var), int(&optimized, var), "{}", var);
+    }
+    assert_eq!(string(&plain, "first"), string(&optimized, "first"));
+    assert!(boolean(&optimized, "distinct"));
+}
@@ -9,7 +9,8 @@
 //! - Rethrow:    `rethrow` (keyword in catch body)
 //! - Chain:      `expr !! fallback` (error chain operator)
 
-use luanext_test_helpers::compile::compile;
+use luanext_core::config::OptimizationLevel;
+use luanext_test_helpers::compile::{compile, compile_with_optimization};
 use luanext_test_helpers::LuaExecutor;
 
 #[test]
@@ -471,3 +472,53 @@ fn test_try_expression_nested_in_try_expression() {
     let result: i64 = executor.execute_and_get(&lua_code, "result").unwrap();
     assert_eq!(result, 77);
 }
+
+// O2 and above lower untyped catches through the xpcall form with a
+// traceback handler
+
+#[test]
+fn test_try_catch_at_o2_runs_catch_after_error() {
+    let source = r#"
+        caught: boolean = false
+        try {
+            throw "err"
+        } catch (e) {
+            caught = true
+        }
+        after: boolean = true
+    "#;
+
+    let lua_code = compile_with_optimization(source, OptimizationLevel::Moderate).unwrap();
+    let executor = LuaExecutor::new().unwrap();
+
+    let caught: bool = executor.execute_and_get(&lua_code, "caught").unwrap();
+    let after: bool = executor.execute_and_get(&lua_code, "after").unwrap();
+    assert!(caught, "catch block should have run");
+    assert!(after, "code after the try should have run");
+}
+
+#[test]
+fn test_try_catch_finally_at_o2_without_error() {
+    let source = r#"
+        try_ran: boolean = false
+        caught: boolean = false
+        finally_ran: boolean = false
+        try {
+            try_ran = true
+        } catch (e) {
+            caught = true
+        } finally {
+            finally_ran = true
+        }
+    "#;
+
+    let lua_code = compile_with_optimization(source, OptimizationLevel::Moderate).unwrap();
+    let executor = LuaExecutor::new().unwrap();
+
+    let try_ran: bool = executor.execute_and_get(&lua_code, "try_ran").unwrap();
+    let caught: bool = executor.execute_and_get(&lua_code, "caught").unwrap();
+    let finally_ran: bool = executor.execute_and_get(&lua_code, "finally_ran").unwrap();
+    assert!(try_ran);
+    assert!(!caught, "catch block should not run without an error");
+    assert!(finally_ran, "finally block should have run");
+}
@@ -0,0 +1,255 @@
+//! Tests for global value numbering
+//!
+//! These tests verify that:
+//! 1. A value computed before an `if` is reused in its arms and after it
+//! 2. Assignments in `if` arms and loop bodies end the reuse of the old value
+//! 3. Table reads survive stores to tables proven distinct, but not aliased ones
+//! 4. Calls to pure functions are reused until an impure call, nondeterministic
+//!    builtins never are
+//! 5. A value some arms of an `if` hold is computed in the others (PRE)
+//! 6. Locals a closure assigns are never reused
+
+use luanext_core::config::OptimizationLevel;
+use luanext_core::optimizer::pipeline::PassPipelineConfig;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_test_helpers::optimize::{enabling, int, only, optimize, remarks_of};
+
+const PASS: &str = "global-value-numbering";
+
+/// Run global value numbering alone
+fn number_values(source: &str) -> (String, Vec<Remark>) {
+    optimize(source, OptimizationLevel::Aggressive, only(PASS))
+}
+
+#[test]
+fn test_reuse_across_if_arm_and_join() {
+    let source = r#"
+        function area(w, f)
+            local scaled = w * f
+            local seen = 0
+            if w > 1 then
+                seen = w * f
+            end
+            return seen + w * f
+        end
+        result = area(6, 7)
+    "#;
+    let (lua, remarks) = number_values(source);
+
+    assert_eq!(lua.matches("w * f").count(), 1, "{}", lua);
+    assert!(lua.contains("seen = scaled"), "{}", lua);
+    assert_eq!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied).len(),
+        2,
+        "{:?}",
+        remarks
+    );
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied)[0].contains("reused `scaled`"),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "result"), 84);
+}
+
+#[test]
+fn test_assignment_in_arm_blocks_reuse_after_join() {
+    let source = r#"
+        function area(w, f, grow)
+            local scaled = w * f
+            if grow then
+                w = w + 1
+            end
+            return scaled + w * f
+        end
+        result = area(6, 7, true)
+    "#;
+    let (lua, remarks) = number_values(source);
+
+    assert!(lua.contains("scaled + w * f"), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied).is_empty(),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "result"), 91);
+}
+
+#[test]
+fn test_loop_assignment_blocks_reuse_in_body() {
+    let source = r#"
+        function grow(n)
+            local step = n * 2
+            local total = 0
+            for i = 1, 3 do
+                total = total + n * 2
+                n = n + 1
+            end
+            return total
+        end
+        function keep(n)
+            local step = n * 2
+            local total = 0
+            for i = 1, 3 do
+                total = total + n * 2
+            end
+            return total
+        end
+        grown = grow(1)
+        kept = keep(1)
+    "#;
+    let (lua, _) = number_values(source);
+
+    assert!(lua.contains("total = total + n * 2"), "{}", lua);
+    assert!(lua.contains("total = total + step"), "{}", lua);
+    assert_eq!(int(&lua, "grown"), 12);
+    assert_eq!(int(&lua, "kept"), 6);
+}
+
+#[test]
+fn test_table_read_survives_unaliased_store() {
+    let source = r#"
+        function reads(k)
+            local a = {x = k}
+            local b = {x = 0}
+            local first = a.x
+            b.x = 5
+            local second = a.x
+            local r = a
+            r.x = 9
+            local third = a.x
+            return first + second + third
+        end
+        result = reads(1)
+    "#;
+    let (lua, _) = number_values(source);
+
+    assert!(lua.contains("local second = first"), "{}", lua);
+    assert!(lua.contains("local third = a.x"), "{}", lua);
+    assert_eq!(int(&lua, "result"), 11);
+}
+
+#[test]
+fn test_pure_calls_reused_until_impure_call() {
+    let source = r#"
+        function square(n: number): number
+            return n * n
+        end
+        function use(v)
+            local a = square(v)
+            local b = square(v)
+            print(a)
+            local c = square(v)
+            local low = math.random(1, 1)
+            local high = math.random(1, 1)
+            return a + b + c + low + high
+        end
+        result = use(3)
+    "#;
+    let (lua, remarks) = number_values(source);
+
+    assert!(lua.contains("local b = a"), "{}", lua);
+    assert!(lua.contains("local c = square(v)"), "{}", lua);
+    assert!(lua.contains("local high = math.random(1, 1)"), "{}", lua);
+    assert_eq!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied).len(),
+        1,
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "result"), 29);
+}
+
+#[test]
+fn test_partial_redundancy_computed_in_other_arm() {
+    let source = r#"
+        function pick(x, y, fast)
+            local out = 0
+            if fast then
+                local d = x * y
+                out = d
+            end
+            local product = x * y
+            return out + product
+        end
+        fast_result = pick(3, 4, true)
+        slow_result = pick(3, 4, false)
+    "#;
+    let (lua, remarks) = number_values(source);
+
+    assert!(lua.contains("local __gvn_0 = nil"), "{}", lua);
+    assert!(lua.contains("__gvn_0 = d"), "{}", lua);
+    assert!(lua.contains("else"), "{}", lua);
+    assert!(lua.contains("local product = __gvn_0"), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied)
+            .iter()
+            .any(|m| m.contains("computed the value in 1 arm(s)")),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "fast_result"), 24);
+    assert_eq!(int(&lua, "slow_result"), 12);
+}
+
+#[test]
+fn test_closure_assigned_local_not_reused() {
+    let source = r#"
+        function run(x)
+            local a = x + 1
+            local bump = function() x = x + 10 end
+            bump()
+            local b = x + 1
+            return a + b
+        end
+        result = run(1)
+    "#;
+    let (lua, remarks) = number_values(source);
+
+    assert!(lua.contains("local b = x + 1"), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied).is_empty(),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "result"), 14);
+}
+
+#[test]
+fn test_o3_matches_unoptimized() {
+    let source = r#"
+        function square(n: number): number
+            return n * n
+        end
+        function mix(x, y, flag)
+            local t = {v = x}
+            local u = {v = y}
+            local s = t.v * u.v
+            if flag then
+                u.v = square(x)
+                s = s + t.v * u.v
+            elseif x > y then
+                local p = x + y
+                s = s + p
+            end
+            local q = x + y
+            for i = 1, 3 do
+                s = s + square(i) + t.v
+            end
+            return s + q
+        end
+        a = mix(2, 3, true)
+        b = mix(5, 3, false)
+        c = mix(1, 3, false)
+    "#;
+    let (o0, _) = optimize(
+        source,
+        OptimizationLevel::None,
+        PassPipelineConfig::default(),
+    );
+    let (o3, _) = optimize(source, OptimizationLevel::Aggressive, enabling(PASS));
+
+    for var in ["a", "b", "c"] {
+        assert_eq!(int(&o3, var), int(&o0, var), "{}: {}", var, o3);
+    }
+}
@@ -0,0 +1,183 @@
+//! Tests for induction variable optimization
+//!
+//! These tests verify that:
+//! 1. A counter declared before a numeric `for` loop that steps with the loop
+//!    variable is replaced by the loop variable plus a constant
+//! 2. `i * c + d` in the body becomes a counter stepped at the start of the
+//!    body, which stays right with `continue`
+//! 3. A loop variable only used as `i + d` is rebased, simplifying its limit
+//! 4. Counters captured by closures or used after the loop are kept, with a
+//!    missed remark
+//! 5. The pass does not change what the program computes
+
+use luanext_core::config::OptimizationLevel;
+use luanext_core::optimizer::pipeline::PassPipelineConfig;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_test_helpers::optimize::{enabling, int, only, optimize, remarks_of, string};
+
+const PASS: &str = "induction-variables";
+
+/// Run induction variable optimization alone at O3
+fn reduce(source: &str) -> (String, Vec<Remark>) {
+    optimize(source, OptimizationLevel::Aggressive, only(PASS))
+}
+
+const LOOPS: &str = r#"
+    function gather(data: integer[], n: integer): integer[]
+        local out = {}
+        local k = 1
+        for i = 0, n - 1 do
+            out[k] = data[i * 4 + 1]
+            k = k + 1
+        end
+        return out
+    end
+    function odds(n: integer): integer
+        local total = 0
+        for i = 1, n, 2 do
+            if i == 5 then
+                continue
+            end
+            total = total + (i * 3 - 1)
+        end
+        return total
+    end
+    function firsts(xs: integer[]): integer
+        local total = 0
+        for i = 0, 2 do
+            total = total + xs[i + 1]
+        end
+        return total
+    end
+    function count(n: integer): integer
+        local seen = 0
+        for i = 1, n do
+            seen = seen + 1
+        end
+        return seen
+    end
+    function later(n: integer): integer
+        local fs = {}
+        local j = 0
+        for i = 1, n do
+            fs[i] = () => j
+            j = j + 1
+        end
+        return fs[1]()
+    end
+    gathered = table.concat(gather({ 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12 }, 3), ",")
+    o = odds(9)
+    f = firsts({ 10, 20, 30, 40 })
+    c = count(4)
+    l = later(3)
+"#;
+
+#[test]
+fn test_lockstep_counter_is_replaced_by_loop_variable() {
+    let (lua, remarks) = reduce(LOOPS);
+
+    assert!(!lua.contains("local k"), "{}", lua);
+    assert!(lua.contains("out[i] = data[__iv_0]"), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied)
+            .contains(&"replaced counter `k` with loop variable `i`"),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(string(&lua, "gathered"), "1,5,9");
+}
+
+#[test]
+fn test_products_become_additive_counters() {
+    let (lua, remarks) = reduce(LOOPS);
+    let applied = remarks_of(&remarks, PASS, RemarkKind::Applied);
+
+    assert!(lua.contains("local __iv_0 = -3"), "{}", lua);
+    assert!(lua.contains("__iv_0 = __iv_0 + 4"), "{}", lua);
+    assert!(
+        applied.contains(&"strength-reduced `i * 4 + 1` to counter `__iv_0`, stepped by 4"),
+        "{:?}",
+        applied
+    );
+    assert!(lua.contains("local __iv_1 = -4"), "{}", lua);
+    assert!(lua.contains("__iv_1 = __iv_1 + 6"), "{}", lua);
+    assert!(
+        applied.contains(&"strength-reduced `i * 3 - 1` to counter `__iv_1`, stepped by 6"),
+        "{:?}",
+        applied
+    );
+    assert!(!lua.contains("i * 3"), "{}", lua);
+    assert_eq!(int(&lua, "o"), 56);
+}
+
+#[test]
+fn test_offset_loop_variable_is_rebased() {
+    let (lua, remarks) = reduce(LOOPS);
+
+    assert!(lua.contains("for i = 1, n do"), "{}", lua);
+    assert!(lua.contains("for i = 1, 3 do"), "{}", lua);
+    assert!(lua.contains("xs[i]"), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied)
+            .contains(&"rebased loop variable `i` to start at 1, replacing `i + 1` with `i`"),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "f"), 60);
+}
+
+#[test]
+fn test_counters_that_outlive_an_iteration_are_kept() {
+    let (lua, remarks) = reduce(LOOPS);
+    let missed = remarks_of(&remarks, PASS, RemarkKind::Missed);
+
+    assert!(
+        missed.contains(&"counter `seen` is used after the loop"),
+        "{:?}",
+        missed
+    );
+    assert!(
+        missed.contains(&"counter `j` is used by a closure"),
+        "{:?}",
+        missed
+    );
+    assert!(lua.contains("seen = seen + 1"), "{}", lua);
+    assert!(lua.contains("j = j + 1"), "{}", lua);
+    assert_eq!(int(&lua, "c"), 4);
+    assert_eq!(int(&lua, "l"), 3);
+}
+
+#[test]
+fn test_loop_assigning_its_variable_is_left_alone() {
+    let source = r#"
+        function skips(n: integer): integer
+            local total = 0
+            for i = 1, n do
+                total = total + i * 2
+                i = i + 1
+            end
+            return total
+        end
+        s = skips(3)
+    "#;
+    let (lua, remarks) = reduce(source);
+
+    assert!(!lua.contains("__iv_"), "{}", lua);
+    assert!(remarks_of(&remarks, PASS, RemarkKind::Applied).is_empty());
+    assert_eq!(int(&lua, "s"), 12);
+}
+
+#[test]
+fn test_induction_variables_preserve_results_in_full_pipeline() {
+    let (plain, _) = optimize(
+        LOOPS,
+        OptimizationLevel::None,
+        PassPipelineConfig::default(),
+    );
+    let (optimized, _) = optimize(LOOPS, OptimizationLevel::Aggressive, enabling(PASS));
+
+    for var in ["o", "f", "c", "l"] {
+        assert_eq!(int(&plain, var), int(&optimized, var), "{}", var);
+    }
+    assert_eq!(string(&plain, "gathered"), string(&optimized, "gathered"));
+}
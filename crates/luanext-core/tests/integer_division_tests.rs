@@ -0,0 +1,424 @@
+//! Tests for integer-aware code generation
+//!
+//! These tests verify that:
+//! 1. `math.floor(a / b)` on integers becomes `a // b`, which Lua 5.3+ runs
+//!    natively and Lua 5.1 still runs as `math.floor(a / b)`
+//! 2. Non-integer operands and divisors that may be zero are left alone
+//! 3. Constant folding wraps integers around on Lua 5.3+, folds them only
+//!    within ±2^53 on targets that compute in doubles, and keeps integer/float
+//!    results apart
+//! 4. `math.tointeger` is polyfilled on targets without integers
+
+use bumpalo::Bump;
+use luanext_core::codegen::{CodeGenerator, LuaTarget};
+use luanext_core::config::OptimizationLevel;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::optimizer::pipeline::PassPipelineConfig;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_core::optimizer::Optimizer;
+use luanext_core::MutableProgram;
+use luanext_parser::ast::expression::{BinaryOp, Expression, ExpressionKind, Literal, UnaryOp};
+use luanext_parser::ast::pattern::Pattern;
+use luanext_parser::ast::statement::{Statement, VariableDeclaration, VariableKind};
+use luanext_parser::ast::{Program, Spanned};
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::StringInterner;
+use luanext_runtime::integer::TOINTEGER_POLYFILL;
+use luanext_test_helpers::optimize::{enabling, int, only, optimize_for_target, remarks_of};
+use luanext_test_helpers::LuaExecutor;
+use std::sync::Arc;
+
+const PASS: &str = "integer-division";
+
+/// Run integer division rewriting alone
+fn rewrite_divisions(source: &str, target: LuaTarget) -> (String, Vec<Remark>) {
+    optimize_for_target(source, OptimizationLevel::Moderate, only(PASS), target)
+}
+
+const DIVISIONS: &str = r#"
+    function middle(lo: integer, hi: integer): integer
+        return math.floor((lo + hi) / 2)
+    end
+    function halves(n: integer): integer
+        local total: integer = 0
+        for i = 1, n do
+            total = total + math.floor(i / 2)
+        end
+        return total
+    end
+    function half_length(t): integer
+        return math.floor(#t / 2)
+    end
+    mid = middle(3, 8)
+    below = middle(-7, 0)
+    halved = halves(5)
+    len = half_length({1, 2, 3})
+"#;
+
+#[test]
+fn test_floor_of_integer_division_becomes_native() {
+    let (lua, remarks) = rewrite_divisions(DIVISIONS, LuaTarget::Lua54);
+
+    assert!(!lua.contains("math.floor"), "{}", lua);
+    assert_eq!(lua.matches("// 2)").count(), 3, "{}", lua);
+    assert_eq!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied),
+        vec!["rewrote `math.floor` of an integer division into `//`"; 3]
+    );
+    assert_eq!(int(&lua, "mid"), 5);
+    assert_eq!(int(&lua, "below"), -4);
+    assert_eq!(int(&lua, "halved"), 6);
+    assert_eq!(int(&lua, "len"), 1);
+}
+
+#[test]
+fn test_older_targets_keep_math_floor() {
+    let (lua, remarks) = rewrite_divisions(DIVISIONS, LuaTarget::Lua51);
+
+    assert_eq!(lua.matches("math.floor(").count(), 3, "{}", lua);
+    assert!(!lua.contains("//"), "{}", lua);
+    assert_eq!(remarks_of(&remarks, PASS, RemarkKind::Applied).len(), 3);
+    assert_eq!(int(&lua, "mid"), 5);
+    assert_eq!(int(&lua, "below"), -4);
+    assert_eq!(int(&lua, "halved"), 6);
+}
+
+#[test]
+fn test_floats_and_possible_zero_divisors_kept() {
+    let source = r#"
+        function ratio(a: integer, b: integer): integer
+            return math.floor(a / b)
+        end
+        function scaled(x: number): number
+            return math.floor(x / 2)
+        end
+        function by_zero(a: integer): number
+            return math.floor(a / 0)
+        end
+        function stepped(n: integer): number
+            local total = 0
+            for i = 0.5, n do
+                total = total + math.floor(i / 2)
+            end
+            return total
+        end
+        r = ratio(7, 2)
+        s = scaled(7.5)
+        t = stepped(3)
+    "#;
+    let (lua, remarks) = rewrite_divisions(source, LuaTarget::Lua54);
+
+    assert_eq!(lua.matches("math.floor(").count(), 4, "{}", lua);
+    assert!(remarks_of(&remarks, PASS, RemarkKind::Applied).is_empty());
+    let missed = remarks_of(&remarks, PASS, RemarkKind::Missed);
+    assert_eq!(missed.len(), 2, "{:?}", remarks);
+    assert!(
+        missed.iter().all(|m| m.contains("may be zero")),
+        "{:?}",
+        missed
+    );
+    assert_eq!(int(&lua, "r"), 3);
+    assert_eq!(int(&lua, "s"), 3);
+    assert_eq!(int(&lua, "t"), 1);
+}
+
+/// The global declaration `name = left op right`
+fn global<'arena>(
+    name: &str,
+    op: BinaryOp,
+    left: Literal,
+    right: Literal,
+    interner: &StringInterner,
+    arena: &'arena Bump,
+) -> Statement<'arena> {
+    let span = Span::dummy();
+    let literal = |value| arena.alloc(Expression::new(ExpressionKind::Literal(value), span));
+    Statement::Variable(VariableDeclaration {
+        kind: VariableKind::Global,
+        pattern: Pattern::Identifier(Spanned::new(interner.get_or_intern(name), span)),
+        type_annotation: None,
+        initializer: Expression::new(
+            ExpressionKind::Binary(op, literal(left), literal(right)),
+            span,
+        ),
+        span,
+    })
+}
+
+/// The global declaration `name = -value`
+fn negated<'arena>(
+    name: &str,
+    value: i64,
+    interner: &StringInterner,
+    arena: &'arena Bump,
+) -> Statement<'arena> {
+    let span = Span::dummy();
+    let operand = arena.alloc(Expression::new(
+        ExpressionKind::Literal(Literal::Integer(value)),
+        span,
+    ));
+    Statement::Variable(VariableDeclaration {
+        kind: VariableKind::Global,
+        pattern: Pattern::Identifier(Spanned::new(interner.get_or_intern(name), span)),
+        type_annotation: None,
+        initializer: Expression::new(ExpressionKind::Unary(UnaryOp::Negate, operand), span),
+        span,
+    })
+}
+
+/// Run constant folding alone for `target` on `statements`, returning the
+/// Lua and remarks
+fn fold<'arena>(
+    statements: &[Statement<'arena>],
+    interner: &Arc<StringInterner>,
+    arena: &'arena Bump,
+    target: LuaTarget,
+) -> (String, Vec<Remark>) {
+    let program = Program::new(arena.alloc_slice_clone(statements), Span::dummy());
+    let mut program = MutableProgram::from_program(&program);
+
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut optimizer = Optimizer::new(OptimizationLevel::Minimal, handler, interner.clone());
+    optimizer.set_target(target);
+    optimizer.set_pass_pipeline(only("constant-folding"));
+    optimizer.enable_remarks();
+    optimizer
+        .optimize(&mut program, arena)
+        .expect("Optimization failed");
+    let lua = CodeGenerator::new(interner.clone())
+        .with_target(target)
+        .generate(&program);
+    (lua, optimizer.take_remarks())
+}
+
+#[test]
+fn test_integer_constant_folding() {
+    let arena = Bump::new();
+    let interner = Arc::new(StringInterner::new());
+    let statements = [
+        global(
+            "wrapped",
+            BinaryOp::Add,
+            Literal::Integer(i64::MAX),
+            Literal::Integer(1),
+            &interner,
+            &arena,
+        ),
+        global(
+            "floored",
+            BinaryOp::IntegerDivide,
+            Literal::Integer(-7),
+            Literal::Integer(2),
+            &interner,
+            &arena,
+        ),
+        global(
+            "remainder",
+            BinaryOp::Modulo,
+            Literal::Integer(-7),
+            Literal::Integer(3),
+            &interner,
+            &arena,
+        ),
+        global(
+            "fraction",
+            BinaryOp::Divide,
+            Literal::Integer(7),
+            Literal::Integer(2),
+            &interner,
+            &arena,
+        ),
+        global(
+            "float",
+            BinaryOp::Divide,
+            Literal::Integer(6),
+            Literal::Integer(2),
+            &interner,
+            &arena,
+        ),
+        global(
+            "mixed",
+            BinaryOp::Modulo,
+            Literal::Number(-7.5),
+            Literal::Integer(2),
+            &interner,
+            &arena,
+        ),
+    ];
+    let (lua, remarks) = fold(&statements, &interner, &arena, LuaTarget::Lua54);
+
+    assert!(lua.contains("(6 / 2)"), "{}", lua);
+    assert_eq!(
+        remarks_of(&remarks, "constant-folding", RemarkKind::Applied).len(),
+        5,
+        "{:?}",
+        remarks
+    );
+    assert!(
+        remarks_of(&remarks, "constant-folding", RemarkKind::Missed)
+            .iter()
+            .any(|m| m.contains("integral value")),
+        "{:?}",
+        remarks
+    );
+
+    let executor = LuaExecutor::new().unwrap();
+    let int = |var| executor.execute_and_get::<i64>(&lua, var).unwrap();
+    assert_eq!(int("wrapped"), i64::MIN);
+    assert_eq!(int("floored"), -4);
+    assert_eq!(int("remainder"), 2);
+    let float = |var| executor.execute_and_get::<f64>(&lua, var).unwrap();
+    assert_eq!(float("fraction"), 3.5);
+    assert_eq!(float("float"), 3.0);
+    assert_eq!(float("mixed"), 0.5);
+}
+
+#[test]
+fn test_integer_folding_stays_exact_without_integers() {
+    const EXACT: i64 = 1 << 53;
+    let arena = Bump::new();
+    let interner = Arc::new(StringInterner::new());
+    let statements = [
+        global(
+            "edge",
+            BinaryOp::Add,
+            Literal::Integer(EXACT - 1),
+            Literal::Integer(1),
+            &interner,
+            &arena,
+        ),
+        global(
+            "floored",
+            BinaryOp::IntegerDivide,
+            Literal::Integer(-7),
+            Literal::Integer(2),
+            &interner,
+            &arena,
+        ),
+        global(
+            "past_edge",
+            BinaryOp::Add,
+            Literal::Integer(EXACT),
+            Literal::Integer(1),
+            &interner,
+            &arena,
+        ),
+        global(
+            "overflow",
+            BinaryOp::Add,
+            Literal::Integer(i64::MAX),
+            Literal::Integer(1),
+            &interner,
+            &arena,
+        ),
+        global(
+            "square",
+            BinaryOp::Multiply,
+            Literal::Integer(1 << 30),
+            Literal::Integer(1 << 30),
+            &interner,
+            &arena,
+        ),
+        global(
+            "cancelled",
+            BinaryOp::Subtract,
+            Literal::Integer(EXACT * 4 + 1),
+            Literal::Integer(EXACT * 4),
+            &interner,
+            &arena,
+        ),
+        negated("small", 5, &interner, &arena),
+        negated("huge", i64::MIN, &interner, &arena),
+    ];
+
+    for target in [LuaTarget::Lua51, LuaTarget::Lua52, LuaTarget::LuaJIT] {
+        let (lua, remarks) = fold(&statements, &interner, &arena, target);
+
+        assert_eq!(
+            remarks_of(&remarks, "constant-folding", RemarkKind::Applied),
+            vec![
+                format!("folded {} Add 1 to {}", EXACT - 1, EXACT),
+                "folded -7 IntegerDivide 2 to -4".to_string(),
+                "folded -5 to -5".to_string(),
+            ],
+            "{:?}",
+            target
+        );
+        assert_eq!(
+            remarks_of(&remarks, "constant-folding", RemarkKind::Missed)
+                .iter()
+                .filter(|m| m.contains("±2^53"))
+                .count(),
+            4,
+            "{:?}: {:?}",
+            target,
+            remarks
+        );
+        assert!(lua.contains(&format!("{} + 1", EXACT)), "{}", lua);
+    }
+
+    // Lua 5.3+ wrap around like the VM does
+    let (_, remarks) = fold(&statements, &interner, &arena, LuaTarget::Lua53);
+    assert_eq!(
+        remarks_of(&remarks, "constant-folding", RemarkKind::Applied).len(),
+        8,
+        "{:?}",
+        remarks
+    );
+}
+
+#[test]
+fn test_tointeger_polyfilled_on_older_targets() {
+    let source = r#"
+        whole = math.tointeger(6 / 2)
+    "#;
+    let config = PassPipelineConfig::default();
+    let (lua51, _) = optimize_for_target(
+        source,
+        OptimizationLevel::None,
+        config.clone(),
+        LuaTarget::Lua51,
+    );
+    let (luajit, _) = optimize_for_target(
+        source,
+        OptimizationLevel::None,
+        config.clone(),
+        LuaTarget::LuaJIT,
+    );
+    let (lua54, _) = optimize_for_target(source, OptimizationLevel::None, config, LuaTarget::Lua54);
+
+    assert!(lua51.contains(TOINTEGER_POLYFILL), "{}", lua51);
+    assert!(luajit.contains(TOINTEGER_POLYFILL), "{}", luajit);
+    assert!(!lua54.contains(TOINTEGER_POLYFILL), "{}", lua54);
+    assert_eq!(int(&lua51, "whole"), 3);
+
+    // Without a native `math.tointeger`, as on Lua 5.1
+    let polyfilled = format!(
+        "math.tointeger = nil\n{}\nwhole = math.tointeger(3.0)\n\
+         missing = (math.tointeger(3.5) == nil and math.tointeger(\"3\") == nil) and 1 or 0\n",
+        TOINTEGER_POLYFILL
+    );
+    assert_eq!(int(&polyfilled, "whole"), 3);
+    assert_eq!(int(&polyfilled, "missing"), 1);
+}
+
+#[test]
+fn test_o2_matches_unoptimized() {
+    let (o0, _) = optimize_for_target(
+        DIVISIONS,
+        OptimizationLevel::None,
+        PassPipelineConfig::default(),
+        LuaTarget::Lua54,
+    );
+    let (o2, _) = optimize_for_target(
+        DIVISIONS,
+        OptimizationLevel::Moderate,
+        enabling(PASS),
+        LuaTarget::Lua54,
+    );
+
+    assert!(o2.contains("//"), "{}", o2);
+    for var in ["mid", "below", "halved", "len"] {
+        assert_eq!(int(&o2, var), int(&o0, var), "{}: {}", var, o2);
+    }
+}
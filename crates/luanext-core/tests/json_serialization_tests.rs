@@ -0,0 +1,209 @@
+//! Integration tests for `@serializable` classes and the `@std/json` module
+//!
+//! These tests verify that:
+//! 1. `@serializable` classes carry field metadata without importing `@std/reflection`
+//! 2. `encode`/`decode` round-trip nested instances, restoring their metatables
+//! 3. `decode` validates field types, required fields and nullable (`?`) fields
+//! 4. The runtime is only embedded when used, and works on every `LuaTarget`
+//! 5. `@serializable` is stripped and defines no global
+
+use bumpalo::Bump;
+use luanext_core::codegen::{CodeGeneratorBuilder, LuaTarget};
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::MutableProgram;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::LuaExecutor;
+use std::sync::Arc;
+
+fn generate_for(source: &str, target: LuaTarget) -> String {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+
+    let mut generator = CodeGeneratorBuilder::new(interner).target(target).build();
+    generator.generate(&MutableProgram::from_program(&program))
+}
+
+fn generate(source: &str) -> String {
+    generate_for(source, LuaTarget::default())
+}
+
+const SHAPES: &str = r#"
+import { encode, decode } from "@std/json"
+
+@serializable
+class Point {
+    x: number
+    y: number
+    label: string?
+    static count: number = 0
+
+    constructor(x: number, y: number) {
+        self.x = x
+        self.y = y
+    }
+
+    length(): number {
+        return math.sqrt(self.x * self.x + self.y * self.y)
+    }
+}
+
+@serializable
+class Polygon {
+    name: string
+    points: Point[]
+    tags: string[]?
+
+    constructor(name: string, points: Point[]) {
+        self.name = name
+        self.points = points
+    }
+}
+"#;
+
+#[test]
+fn test_serializable_emits_field_metadata() {
+    let lua = generate(SHAPES);
+    assert!(lua.contains("__LuaNextJSON.serializable(Point)"), "{}", lua);
+    assert!(!lua.contains("= serializable("), "{}", lua);
+    assert!(lua.contains("{ name = \"label\", type = \"?s\", _flags = 1 },"));
+    assert!(lua.contains("{ name = \"points\", type = \"[Point]\", _flags = 1 },"));
+    assert!(lua.contains("function Point._buildAllFields()"));
+    // Only field metadata is forced; methods still follow the reflection mode
+    assert!(!lua.contains("__ownMethods"));
+    assert!(!lua.contains("__TypeRegistry"));
+    assert_eq!(lua.matches("JSON Runtime Module").count(), 1);
+    assert!(lua.contains("local encode, decode = _mod.encode, _mod.decode"));
+}
+
+#[test]
+fn test_serializable_defines_no_global() {
+    let source = format!(
+        "{}\npoint = encode(new Point(1, 2))\nleaked = rawget(_G, \"serializable\") ~= nil\n",
+        SHAPES
+    );
+    let lua = generate(&source);
+
+    let executor = LuaExecutor::new().unwrap();
+    let point: String = executor.execute_and_get(&lua, "point").unwrap();
+    assert_eq!(point, r#"{"x":1,"y":2}"#);
+    assert!(!executor.execute_and_get::<bool>(&lua, "leaked").unwrap());
+}
+
+#[test]
+fn test_runtime_not_embedded_when_unused() {
+    let lua = generate("class Plain {\n    x: number\n}\n");
+    assert!(!lua.contains("__LuaNextJSON"));
+    assert!(!lua.contains("__ownFields"));
+}
+
+#[test]
+fn test_round_trip_restores_instances() {
+    let source = format!(
+        r#"{}
+a = new Point(3, 4)
+a.label = "corner"
+shape = new Polygon("line", [a, new Point(0, 1)])
+text = encode(shape)
+copy = decode(text, Polygon)
+length = copy.points[1]::length()
+label = copy.points[1].label
+same_class = getmetatable(copy.points[2]) == Point
+reencoded = encode(copy) == text
+"#,
+        SHAPES
+    );
+    let lua = generate(&source);
+
+    let executor = LuaExecutor::new().unwrap();
+    let text: String = executor.execute_and_get(&lua, "text").unwrap();
+    assert_eq!(
+        text,
+        r#"{"name":"line","points":[{"x":3,"y":4,"label":"corner"},{"x":0,"y":1}]}"#
+    );
+    let length: f64 = executor.execute_and_get(&lua, "length").unwrap();
+    assert_eq!(length, 5.0);
+    let label: String = executor.execute_and_get(&lua, "label").unwrap();
+    assert_eq!(label, "corner");
+    assert!(executor
+        .execute_and_get::<bool>(&lua, "same_class")
+        .unwrap());
+    assert!(executor.execute_and_get::<bool>(&lua, "reencoded").unwrap());
+}
+
+#[test]
+fn test_decode_validates_fields() {
+    let cases = [
+        (
+            r#"{"x": "1", "y": 2}"#,
+            "expected number at $.x, got string",
+        ),
+        (r#"{"x": 1}"#, "missing required field $.y"),
+        (
+            r#"{"x": 1, "y": 2, "label": false}"#,
+            "expected string at $.label",
+        ),
+        (r#"{"x": 1, "y": 2"#, "expected ',' or '}'"),
+    ];
+    for (input, expected) in cases {
+        let source = format!(
+            "{}\nmessage = \"\"\ntry {{\n    decode('{}', Point)\n}} catch (e) {{\n    message = e\n}}\n",
+            SHAPES, input
+        );
+        let lua = generate(&source);
+        let executor = LuaExecutor::new().unwrap();
+        let message: String = executor.execute_and_get(&lua, "message").unwrap();
+        assert!(
+            message.starts_with("JSON decode: ") && message.contains(expected),
+            "{}: {}",
+            input,
+            message
+        );
+    }
+}
+
+#[test]
+fn test_plain_values_and_nullable_fields() {
+    let source = format!(
+        r#"{}
+plain = encode({{ b = [1, 2.5, true], a = "tab\t" }})
+p = decode('{{"x": 1, "y": 2, "label": null}}', Point)
+has_label = p.label ~= nil
+"#,
+        SHAPES
+    );
+    let lua = generate(&source);
+
+    let executor = LuaExecutor::new().unwrap();
+    let plain: String = executor.execute_and_get(&lua, "plain").unwrap();
+    assert_eq!(plain, r#"{"a":"tab\t","b":[1,2.5,true]}"#);
+    assert!(!executor.execute_and_get::<bool>(&lua, "has_label").unwrap());
+}
+
+#[test]
+fn test_works_on_every_target() {
+    let source = format!(
+        "{}\nresult = encode(decode('{{\"x\": 1.5, \"y\": -2}}', Point))\n",
+        SHAPES
+    );
+    for target in [
+        LuaTarget::Lua51,
+        LuaTarget::Lua52,
+        LuaTarget::Lua53,
+        LuaTarget::Lua54,
+        LuaTarget::Lua55,
+        LuaTarget::LuaJIT,
+    ] {
+        let lua = generate_for(&source, target);
+        let executor = LuaExecutor::new().unwrap();
+        let result: String = executor.execute_and_get(&lua, "result").unwrap();
+        assert_eq!(result, r#"{"x":1.5,"y":-2}"#, "{:?}", target);
+    }
+}
@@ -0,0 +1,183 @@
+//! Tests for lambda lifting
+//!
+//! These tests verify that:
+//! 1. Local closures inside top-level functions that capture nothing become
+//!    module-level constants
+//! 2. Closures capturing only locals that are never reassigned are lifted
+//!    with those locals as leading parameters, and their calls pass them
+//! 3. Closures that escape, capture reassigned locals or are used other than
+//!    by direct calls are kept, with a missed remark for local ones
+//! 4. Escaping closures keep their identity: each call creates a new one
+//! 5. Lifting does not change what the program computes
+
+use luanext_core::config::OptimizationLevel;
+use luanext_core::optimizer::pipeline::PassPipelineConfig;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_test_helpers::optimize::{enabling, int, only, optimize, remarks_of};
+
+const PASS: &str = "lambda-lifting";
+
+/// Run lambda lifting alone at O2
+fn lift(source: &str) -> (String, Vec<Remark>) {
+    optimize(source, OptimizationLevel::Moderate, only(PASS))
+}
+
+const CLOSURES: &str = r#"
+    const SCALE = 3
+    function apply(f, x: number): number
+        return f(x)
+    end
+    function doubled(x: number): number
+        const double = (n: number) => n * 2
+        return double(x)
+    end
+    function scaled(x: number): number
+        const triple = (n: number) => n * SCALE
+        return triple(x)
+    end
+    function incremented(x: number): number
+        return apply((n: number) => n + 1, x)
+    end
+    function identity()
+        return (n: number) => n
+    end
+    function scale(xs: number[], k: number): number
+        const by = (x: number) => x * k
+        return by(xs[1]) + by(xs[2])
+    end
+    function counter(): number
+        local count = 0
+        const bump = () => count + 1
+        count = bump()
+        count = bump()
+        return count
+    end
+    function adder(k: number)
+        const add = (x: number) => x + k
+        return add
+    end
+    function twice(k: number): number
+        const get = () => k
+        local t = { get = get }
+        return t.get() + get()
+    end
+    a = doubled(21)
+    b = scaled(5)
+    c = scale({1, 2}, 10)
+    d = counter()
+    e = adder(1)(2)
+    f = twice(4)
+    g = incremented(1)
+    h = (identity() == identity()) and 1 or 0
+"#;
+
+#[test]
+fn test_local_closure_capturing_nothing_becomes_module_constant() {
+    let (lua, remarks) = lift(CLOSURES);
+
+    assert!(lua.contains("__lambda_0 = function(n)"), "{}", lua);
+    assert!(lua.contains("return __lambda_0(x)"), "{}", lua);
+    assert!(lua.contains("return __lambda_1(x)"), "{}", lua);
+    let lifted = lua.find("__lambda_0 = function").unwrap();
+    let user = lua.find("function doubled").unwrap();
+    assert!(lifted < user, "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied)
+            .contains(&"lifted closure `triple` into `__lambda_1`"),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "a"), 42);
+    assert_eq!(int(&lua, "b"), 15);
+}
+
+#[test]
+fn test_escaping_closures_keep_their_identity() {
+    let (lua, _) = lift(CLOSURES);
+
+    // Only `double`, `triple` and `by` are lifted
+    assert!(!lua.contains("__lambda_3"), "{}", lua);
+    assert!(!lua.contains("apply(__lambda_"), "{}", lua);
+    assert_eq!(int(&lua, "g"), 2);
+    assert_eq!(int(&lua, "h"), 0);
+}
+
+#[test]
+fn test_closure_capturing_immutable_local_takes_it_as_parameter() {
+    let (lua, remarks) = lift(CLOSURES);
+
+    assert!(lua.contains("function(k, x)"), "{}", lua);
+    assert!(lua.contains("__lambda_2(k, xs[1])"), "{}", lua);
+    assert!(!lua.contains("by("), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Applied)
+            .contains(&"lifted closure `by` into `__lambda_2`, passing `k` at its calls"),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "c"), 30);
+}
+
+#[test]
+fn test_closures_that_cannot_be_lifted_are_kept() {
+    let (lua, remarks) = lift(CLOSURES);
+    let missed = remarks_of(&remarks, PASS, RemarkKind::Missed);
+
+    assert!(
+        missed.contains(&"closure `bump` captures `count`, which is reassigned"),
+        "{:?}",
+        missed
+    );
+    assert!(
+        missed.contains(&"closure `add` escapes, so its calls cannot be rewritten"),
+        "{:?}",
+        missed
+    );
+    assert!(
+        missed.contains(&"closure `get` is used other than by direct calls"),
+        "{:?}",
+        missed
+    );
+    assert!(lua.contains("bump()"), "{}", lua);
+    assert!(lua.contains("return add"), "{}", lua);
+    assert_eq!(int(&lua, "d"), 2);
+    assert_eq!(int(&lua, "e"), 3);
+    assert_eq!(int(&lua, "f"), 8);
+}
+
+#[test]
+fn test_closure_using_mutable_module_local_is_kept() {
+    let source = r#"
+        local factor = 2
+        function times(x: number): number
+            const by = (n: number) => n * factor
+            return by(x)
+        end
+        factor = 5
+        r = times(3)
+    "#;
+    let (lua, remarks) = lift(source);
+
+    assert!(!lua.contains("__lambda_"), "{}", lua);
+    assert!(
+        remarks_of(&remarks, PASS, RemarkKind::Missed)
+            .contains(&"closure `by` uses `factor`, which is not a module-level constant"),
+        "{:?}",
+        remarks
+    );
+    assert_eq!(int(&lua, "r"), 15);
+}
+
+#[test]
+fn test_lifting_preserves_results_in_full_pipeline() {
+    let (plain, _) = optimize(
+        CLOSURES,
+        OptimizationLevel::None,
+        PassPipelineConfig::default(),
+    );
+    let (optimized, _) = optimize(CLOSURES, OptimizationLevel::Moderate, enabling(PASS));
+
+    for var in ["a", "b", "c", "d", "e", "f", "g", "h"] {
+        assert_eq!(int(&plain, var), int(&optimized, var), "{}", var);
+    }
+}
@@ -0,0 +1,289 @@
+//! Tests for expression-level loop-invariant code motion
+//!
+//! These tests verify that:
+//! 1. Invariant expressions and member chains are hoisted out of `for`,
+//!    `while` and `repeat` loops, guarded when the loop may not run
+//! 2. Table reads stay when the loop may store to the same field, but not for
+//!    stores to a table `AliasAnalyzer` proves distinct
+//! 3. Calls that may write memory keep table reads in the loop, while
+//!    parameters the function never assigns are still hoisted
+//! 4. Expressions only some iterations evaluate are never hoisted
+//! 5. Declarations that depend on the loop are not hoisted
+
+use luanext_core::config::OptimizationLevel;
+use luanext_core::optimizer::pipeline::PassPipelineConfig;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_test_helpers::optimize::{int, only, optimize};
+
+const PASS: &str = "loop-optimization";
+
+/// Run loop optimization alone
+fn optimize_loops(source: &str) -> (String, Vec<Remark>) {
+    optimize(source, OptimizationLevel::Moderate, only(PASS))
+}
+
+fn hoisted(remarks: &[Remark]) -> usize {
+    remarks
+        .iter()
+        .filter(|r| {
+            r.pass == PASS
+                && r.kind == RemarkKind::Applied
+                && r.message.starts_with("hoisted loop-invariant expression")
+        })
+        .count()
+}
+
+#[test]
+fn test_member_chain_hoisted_from_method_loop() {
+    let source = r#"
+        class World {
+            bodies: table
+            config: table
+
+            constructor() {
+                self.bodies = {{vy = 0, y = 0}, {vy = 1, y = 0}}
+                self.config = {gravity = -10}
+            }
+
+            step(dt: number) {
+                for i = 1, #self.bodies do
+                    local body = self.bodies[i]
+                    body.vy = body.vy + self.config.gravity * dt
+                    body.y = body.y + body.vy * dt
+                end
+            }
+        }
+
+        local world = new World()
+        world::step(2)
+        first = world.bodies[1].vy
+        second = world.bodies[2].y
+    "#;
+    let (lua, remarks) = optimize_loops(source);
+
+    assert!(lua.contains("local __licm_0 = nil"), "{}", lua);
+    assert!(
+        lua.contains("__licm_0 = self.config.gravity * dt"),
+        "{}",
+        lua
+    );
+    assert!(lua.contains("body.vy = body.vy + __licm_0"), "{}", lua);
+    assert!(lua.contains("local body = self.bodies[i]"), "{}", lua);
+    assert_eq!(hoisted(&remarks), 1, "{:?}", remarks);
+    assert_eq!(int(&lua, "first"), -20);
+    assert_eq!(int(&lua, "second"), -38);
+}
+
+#[test]
+fn test_store_to_distinct_table_keeps_hoisting() {
+    let source = r#"
+        function distinct(n)
+            local a = {k = 3}
+            local b = {k = 0}
+            local total = 0
+            for i = 1, n do
+                b.k = i
+                total = total + a.k * 2 + b.k
+            end
+            return total
+        end
+        function aliased(n)
+            local a = {k = 3}
+            local r = a
+            local total = 0
+            for i = 1, n do
+                r.k = i
+                total = total + a.k * 2
+            end
+            return total
+        end
+        kept = distinct(3)
+        stored = aliased(3)
+    "#;
+    let (lua, remarks) = optimize_loops(source);
+
+    assert!(lua.contains("__licm_0 = a.k * 2"), "{}", lua);
+    assert!(lua.contains("total = total + __licm_0 + b.k"), "{}", lua);
+    assert!(lua.contains("total = total + a.k * 2"), "{}", lua);
+    assert_eq!(hoisted(&remarks), 1, "{:?}", remarks);
+    assert_eq!(int(&lua, "kept"), 24);
+    assert_eq!(int(&lua, "stored"), 12);
+}
+
+#[test]
+fn test_impure_call_keeps_table_reads() {
+    let source = r#"
+        function bump(t)
+            t.v = t.v + 1
+        end
+        function run(t, n)
+            local total = 0
+            for i = 1, n do
+                total = total + t.v * n + n * n
+                bump(t)
+            end
+            return total
+        end
+        result = run({v = 1}, 3)
+    "#;
+    let (lua, remarks) = optimize_loops(source);
+
+    assert!(lua.contains("__licm_0 = n * n"), "{}", lua);
+    assert!(
+        lua.contains("total = total + t.v * n + __licm_0"),
+        "{}",
+        lua
+    );
+    assert_eq!(hoisted(&remarks), 1, "{:?}", remarks);
+    assert_eq!(int(&lua, "result"), 45);
+}
+
+#[test]
+fn test_while_condition_and_guarded_body() {
+    let source = r#"
+        function count(limit, step)
+            local i = 0
+            local hits = 0
+            while i < limit * 2 do
+                hits = hits + step * 3
+                i = i + 1
+            end
+            return hits
+        end
+        function drain(t, n)
+            local left = n
+            repeat
+                left = left - #t
+            until left <= 0
+            return left
+        end
+        many = count(2, 1)
+        none = count(0, 1)
+        drained = drain({1, 2, 3}, 7)
+    "#;
+    let (lua, remarks) = optimize_loops(source);
+
+    assert!(lua.contains("local __licm_0 = limit * 2"), "{}", lua);
+    assert!(lua.contains("while i < __licm_0 do"), "{}", lua);
+    assert!(lua.contains("if i < __licm_0 then"), "{}", lua);
+    assert!(lua.contains("hits = hits + __licm_1"), "{}", lua);
+    assert!(lua.contains("local __licm_2 = #t"), "{}", lua);
+    assert_eq!(hoisted(&remarks), 3, "{:?}", remarks);
+    assert_eq!(int(&lua, "many"), 12);
+    assert_eq!(int(&lua, "none"), 0);
+    assert_eq!(int(&lua, "drained"), -2);
+}
+
+#[test]
+fn test_conditionally_evaluated_expressions_stay() {
+    let source = r#"
+        function pick(t, flag, n)
+            local total = 0
+            for i = 1, n do
+                local v = flag and t.x * 2
+                if flag then
+                    total = total + t.y * 3
+                end
+                total = total + (v or 1)
+            end
+            return total
+        end
+        function sum(t)
+            local total = 0
+            for _, w in ipairs(t.list) do
+                total = total + t.x * 5
+            end
+            return total
+        end
+        skipped = pick(nil, false, 2)
+        summed = sum({x = 1, list = {1, 2}})
+    "#;
+    let (lua, remarks) = optimize_loops(source);
+
+    assert!(!lua.contains("__licm"), "{}", lua);
+    assert_eq!(hoisted(&remarks), 0, "{:?}", remarks);
+    assert_eq!(int(&lua, "skipped"), 2);
+    assert_eq!(int(&lua, "summed"), 10);
+}
+
+#[test]
+fn test_loop_dependent_declarations_not_hoisted() {
+    let source = r#"
+        function squares(n)
+            local total = 0
+            for i = 1, n do
+                local sq = i * i
+                total = total + sq
+            end
+            return total
+        end
+        function rows(n)
+            local total = 0
+            for i = 1, n do
+                local j = 0
+                while j < 2 do
+                    j = j + 1
+                    total = total + j
+                end
+            end
+            return total
+        end
+        function fresh(n)
+            local all = {}
+            for i = 1, n do
+                local t = {}
+                t.v = i
+                all[i] = t
+            end
+            return all[1].v + all[n].v
+        end
+        squared = squares(3)
+        rowed = rows(3)
+        freshed = fresh(3)
+    "#;
+    let (lua, _) = optimize_loops(source);
+
+    assert_eq!(int(&lua, "squared"), 14);
+    assert_eq!(int(&lua, "rowed"), 9);
+    assert_eq!(int(&lua, "freshed"), 4);
+}
+
+#[test]
+fn test_o2_matches_unoptimized() {
+    let source = r#"
+        function scaled(t, factor, n)
+            local a = {k = factor}
+            local out = 0
+            for i = 1, n do
+                t[i] = a.k * factor + i
+                out = out + math.floor(factor / 2) + #t
+            end
+            for i = n, 1, -1 do
+                out = out - a.k * i
+            end
+            local j = 0
+            while j < n + 1 do
+                out = out + factor * factor
+                j = j + 1
+            end
+            return out
+        end
+        a = scaled({}, 3, 4)
+        b = scaled({1}, 5, 0)
+        c = scaled({}, 2, 1)
+    "#;
+    let (o0, _) = optimize(
+        source,
+        OptimizationLevel::None,
+        PassPipelineConfig::default(),
+    );
+    let (o2, _) = optimize(
+        source,
+        OptimizationLevel::Moderate,
+        PassPipelineConfig::default(),
+    );
+
+    for var in ["a", "b", "c"] {
+        assert_eq!(int(&o2, var), int(&o0, var), "{}: {}", var, o2);
+    }
+}
@@ -0,0 +1,201 @@
+//! Integration tests for LuaLS annotation emission
+//!
+//! These tests verify that:
+//! 1. Classes get `---@class`/`---@field` and annotated constructors and methods
+//! 2. Functions get `---@generic`/`---@param`/`---@return`
+//! 3. Enums get `---@enum`, rich enums a `---@class`
+//! 4. Interfaces and type aliases become standalone annotation blocks
+//! 5. Annotations are off by default and never emitted in minified output
+
+use bumpalo::Bump;
+use luanext_core::codegen::CodeGeneratorBuilder;
+use luanext_core::config::OutputFormat;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::MutableProgram;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::LuaExecutor;
+use std::sync::Arc;
+
+fn generate(source: &str, annotations: bool, format: OutputFormat) -> String {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+
+    let mut generator = CodeGeneratorBuilder::new(interner)
+        .annotations(annotations)
+        .output_format(format)
+        .build();
+    generator.generate(&MutableProgram::from_program(&program))
+}
+
+fn annotated(source: &str) -> String {
+    generate(source, true, OutputFormat::Readable)
+}
+
+const POINT: &str = r#"
+class Point {
+    x: number
+    private cache: table
+
+    constructor(x: number, scale: number = 1) {
+        self.x = x * scale
+        self.cache = {}
+    }
+
+    distance(other: Point): number {
+        return math.abs(self.x - other.x)
+    }
+
+    static origin(): Point {
+        return new Point(0)
+    }
+}
+"#;
+
+#[test]
+fn test_class_annotations() {
+    let lua = annotated(POINT);
+    assert!(
+        lua.contains(
+            "---@class Point\n---@field x number\n---@field private cache table\nlocal Point = {}"
+        ),
+        "{}",
+        lua
+    );
+    assert!(lua.contains("---@param x number\n---@param scale? number\n---@return Point\nfunction Point.new(x, scale)"));
+    assert!(
+        lua.contains("---@param other Point\n---@return number\nfunction Point:distance(other)")
+    );
+    assert!(lua.contains("---@return Point\nfunction Point.origin()"));
+}
+
+#[test]
+fn test_subclass_and_primary_constructor() {
+    let source = r#"
+class Animal(public name: string, private age: integer) {
+}
+
+class Dog extends Animal {
+}
+"#;
+    let lua = annotated(source);
+    assert!(lua.contains(
+        "---@class Animal\n---@field name string\n---@field private _age integer\nlocal Animal = {}"
+    ));
+    assert!(lua.contains("---@param name string\n---@param age integer\n---@return Animal\nfunction Animal.new(name, age)"));
+    assert!(lua.contains("---@class Dog: Animal\nlocal Dog = {}"));
+    assert!(lua.contains("---@return Dog\nfunction Dog.new()"));
+}
+
+#[test]
+fn test_function_annotations() {
+    let source = r#"
+function first<T>(items: T[], fallback?: T): T? {
+    return items[1] or fallback
+}
+
+function sum(...values: number[]): number {
+    return 0
+}
+
+function log(message: string | number, callback: (ok: boolean) => void) {
+    print(message)
+}
+"#;
+    let lua = annotated(source);
+    assert!(lua.contains(
+        "---@generic T\n---@param items T[]\n---@param fallback? T\n---@return T?\nlocal function first(items, fallback)"
+    ));
+    assert!(lua.contains("---@param ... number\n---@return number\nlocal function sum(...)"));
+    // `void` functions get no `---@return`
+    assert!(lua.contains(
+        "---@param message string | number\n---@param callback fun(ok: boolean)\nlocal function log(message, callback)"
+    ));
+}
+
+#[test]
+fn test_type_mappings() {
+    let source = r#"
+function convert(
+    a: (string | nil)[],
+    b: { name: string, age?: number },
+    c: [string, integer],
+    d: unknown,
+    e: "on" | "off",
+    f: Map<string, number>
+) {
+}
+"#;
+    let lua = annotated(source);
+    assert!(lua.contains("---@param a (string | nil)[]"));
+    assert!(lua.contains("---@param b { name: string, age?: number }"));
+    assert!(lua.contains("---@param c [string, integer]"));
+    assert!(lua.contains("---@param d any"));
+    assert!(lua.contains("---@param e \"on\" | \"off\""));
+    assert!(lua.contains("---@param f Map<string, number>"));
+}
+
+#[test]
+fn test_enum_annotations() {
+    let source = r#"
+enum Color {
+    Red,
+    Green,
+}
+
+enum Planet {
+    mass: number
+
+    Mercury(3.3e23),
+    Earth(5.9e24),
+}
+"#;
+    let lua = annotated(source);
+    assert!(lua.contains("---@enum Color\nlocal Color = {"));
+    assert!(lua.contains(
+        "---@class Planet\n---@field mass number\n---@field Mercury Planet\n---@field Earth Planet\nlocal Planet = {}"
+    ));
+}
+
+#[test]
+fn test_interface_and_alias_annotations() {
+    let source = r#"
+interface Shape {
+    name: string
+    area(): number
+}
+
+type Id = string | number
+"#;
+    let lua = annotated(source);
+    assert!(lua.contains(
+        "---@class Shape\n---@field name string\n---@field area fun(self: Shape): number\n"
+    ));
+    assert!(lua.contains("---@alias Id string | number\n"));
+}
+
+#[test]
+fn test_annotations_off_by_default_and_when_minified() {
+    assert!(!generate(POINT, false, OutputFormat::Readable).contains("---@"));
+    assert!(!generate(POINT, true, OutputFormat::Minified).contains("---@"));
+}
+
+#[test]
+fn test_annotated_output_runs() {
+    let source = format!(
+        "{}\np = new Point(2, 3)\nresult = p::distance(Point.origin())\n",
+        POINT
+    );
+    let lua = annotated(&source);
+
+    let executor = LuaExecutor::new().unwrap();
+    let result: f64 = executor.execute_and_get(&lua, "result").unwrap();
+    assert_eq!(result, 6.0);
+}
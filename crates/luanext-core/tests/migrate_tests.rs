@@ -0,0 +1,291 @@
+//! Integration tests for migrating plain Lua sources to LuaNext
+//!
+//! These tests verify that:
+//! 1. `setmetatable`-based classes become `class` declarations
+//! 2. Module tables and returned tables become exports
+//! 3. `require` of project modules becomes `import`
+//! 4. Literal-initialized locals get type annotations
+//! 5. Unconvertible code is marked and listed in the report
+
+use luanext_core::migrate::{
+    migrate_project, migrate_source, MigratedFile, SourceFile, NOTE_MARKER,
+};
+use luanext_test_helpers::compile::compile;
+use luanext_test_helpers::LuaExecutor;
+use std::path::{Path, PathBuf};
+
+fn migrate(source: &str) -> MigratedFile {
+    migrate_source(Path::new("main.lua"), source)
+}
+
+fn file(path: &str, source: &str) -> SourceFile {
+    SourceFile {
+        path: PathBuf::from(path),
+        source: source.to_string(),
+    }
+}
+
+const POINT: &str = r#"local Point = {}
+Point.__index = Point
+
+function Point.new(x, y)
+    local self = setmetatable({}, Point)
+    self.x = x
+    self.y = y
+    return self
+end
+
+function Point:len()
+    return math.sqrt(self.x * self.x + self.y * self.y)
+end
+
+function Point.zero() return Point.new(0, 0) end
+
+return Point
+"#;
+
+#[test]
+fn test_class_conversion() {
+    let out = migrate(POINT);
+    let expected = r#"class Point {
+    constructor(x, y) {
+        self.x = x
+        self.y = y
+    }
+
+    len() {
+        return math.sqrt(self.x * self.x + self.y * self.y)
+    }
+
+    static zero() {
+        return new Point(0, 0)
+    }
+}
+
+export default Point
+"#;
+    assert_eq!(out.source, expected);
+    assert!(out.notes.is_empty());
+    assert_eq!(out.path, PathBuf::from("main.luax"));
+}
+
+#[test]
+fn test_subclass_conversion() {
+    let source = r#"local Base = require("base")
+local Child = {}
+Child.__index = Child
+setmetatable(Child, { __index = Base })
+
+function Child.new(name, age)
+    local self = Base.new(name)
+    setmetatable(self, Child)
+    self.age = age
+    return self
+end
+
+return Child
+"#;
+    let out = migrate(source);
+    assert!(out.source.contains("class Child extends Base {"));
+    assert!(out
+        .source
+        .contains("        super(name)\n        self.age = age\n"));
+    assert!(!out.source.contains("setmetatable"));
+    assert!(!out.source.contains("return self"));
+}
+
+#[test]
+fn test_unrecognized_constructor_is_noted() {
+    let source = r#"local Thing = {}
+Thing.__index = Thing
+
+function Thing.new()
+    local obj = {}
+    return setmetatable(obj, Thing)
+end
+
+return Thing
+"#;
+    let out = migrate(source);
+    assert!(!out.source.contains("class Thing"));
+    assert_eq!(out.notes.len(), 1);
+    assert!(out.notes[0].message.contains("class `Thing` not converted"));
+    assert!(out.source.contains(NOTE_MARKER));
+}
+
+#[test]
+fn test_static_field_is_noted() {
+    let source = "local Counter = {}\nCounter.__index = Counter\nCounter.total = 0\n\
+                  function Counter.new()\n    local self = setmetatable({}, Counter)\n    return self\nend\n\
+                  return Counter\n";
+    let out = migrate(source);
+    assert!(out.source.contains("class Counter {"));
+    assert!(out.source.contains("Counter.total = 0"));
+    assert_eq!(out.notes.len(), 1);
+    assert!(out.notes[0].message.contains("static field"));
+}
+
+#[test]
+fn test_module_table_to_exports() {
+    let source = r#"local M = {}
+M.version = "1.0"
+
+local function helper(x) return x * 2 end
+
+function M.double(x)
+    return helper(x)
+end
+
+function M.quadruple(x)
+    return M.double(M.double(x))
+end
+
+return M
+"#;
+    let out = migrate(source);
+    let expected = r#"export const version = "1.0"
+
+local function helper(x) return x * 2 end
+
+export function double(x)
+    return helper(x)
+end
+
+export function quadruple(x)
+    return double(double(x))
+end
+"#;
+    assert_eq!(out.source, expected);
+    assert!(out.notes.is_empty());
+}
+
+#[test]
+fn test_module_table_used_as_value_is_kept() {
+    let source = "local M = {}\nfunction M.f() return 1 end\nsetmetatable(M, {})\nreturn M\n";
+    let out = migrate(source);
+    assert!(out.source.contains("function M.f()"));
+    assert!(out.source.contains("return M"));
+    assert_eq!(out.notes.len(), 1);
+    assert!(out.notes[0].message.contains("used as a value"));
+}
+
+#[test]
+fn test_forward_reference_is_noted() {
+    let source =
+        "local M = {}\nfunction M.a() return M.b() end\nfunction M.b() return 1 end\nreturn M\n";
+    let out = migrate(source);
+    assert!(out.source.contains("export function a() return b() end"));
+    assert_eq!(out.notes.len(), 1);
+    assert!(out.notes[0]
+        .message
+        .contains("`b` is used before its declaration"));
+}
+
+#[test]
+fn test_returned_table_to_export_list() {
+    let source = "local function a() end\nlocal b = 1\nreturn {\n    a = a,\n    value = b,\n}\n";
+    let out = migrate(source);
+    assert!(out.source.ends_with("export { a, b as value }\n"));
+}
+
+#[test]
+fn test_requires_become_imports() {
+    let files = vec![
+        file("shapes/point.lua", POINT),
+        file(
+            "util/init.lua",
+            "local M = {}\nfunction M.id(x) return x end\nreturn M\n",
+        ),
+        file(
+            "main.lua",
+            "local Point = require(\"shapes.point\")\nlocal util = require 'util'\n\
+             local json = require(\"json\")\nprint(util.id(Point.new(1, 2)))\n",
+        ),
+    ];
+    let report = migrate_project(&files);
+    let main = &report.files[2];
+    assert!(main.source.contains("import Point from \"./shapes/point\""));
+    assert!(main
+        .source
+        .contains("import * as util from \"./util/init\""));
+    // Modules outside the project keep their require
+    assert!(main.source.contains("local json = require(\"json\")"));
+    // Constructor calls on imported classes use `new`
+    assert!(main.source.contains("print(util.id(new Point(1, 2)))"));
+}
+
+#[test]
+fn test_require_inside_function_is_noted() {
+    let out = migrate("local function load()\n    local m = require(\"m\")\n    return m\nend\n");
+    assert!(out.source.contains("local m = require(\"m\")"));
+    assert_eq!(out.notes.len(), 1);
+    assert!(out.notes[0].message.contains("inside a block"));
+}
+
+#[test]
+fn test_literal_type_annotations() {
+    let source = r#"local count = 0
+local name = 'lua'
+local enabled = true
+local label = "x"
+local items = {}
+count = count + 1
+label = nil
+"#;
+    let out = migrate(source);
+    assert!(out.source.contains("local count: number = 0"));
+    assert!(out.source.contains("local name: string = 'lua'"));
+    assert!(out.source.contains("local enabled: boolean = true"));
+    // Later assigned nil: annotation would reject it
+    assert!(out.source.contains("local label = \"x\""));
+    assert!(out.source.contains("local items = {}"));
+}
+
+#[test]
+fn test_unsupported_constructs_in_report() {
+    let files = vec![
+        file(
+            "legacy.lua",
+            "module(\"legacy\", package.seeall)\nfunction f() end\n",
+        ),
+        file("clean.lua", "print(1)\n"),
+        file("names.lua", "local class = 1\nprint(class)\n"),
+    ];
+    let report = migrate_project(&files);
+    assert_eq!(report.note_count(), 2);
+
+    let rendered = report.render();
+    assert!(rendered.contains("Migrated 3 file(s); 2 item(s) need manual attention."));
+    assert!(rendered.contains("## legacy.luax"));
+    assert!(rendered.contains("- line 1: `module()` is not supported"));
+    assert!(rendered.contains("`class` is a LuaNext keyword"));
+    assert!(!rendered.contains("## clean.luax"));
+}
+
+#[test]
+fn test_strings_and_comments_untouched() {
+    let source = "local M = {}\n-- M.x = 1 in a comment\nM.s = \"M.x\"\nreturn M\n";
+    let out = migrate(source);
+    assert!(out.source.contains("-- M.x = 1 in a comment"));
+    assert!(out.source.contains("export const s = \"M.x\""));
+}
+
+#[test]
+fn test_migrated_module_compiles_and_runs() {
+    let source = r#"local M = {}
+M.base = 10
+
+function M.add(x)
+    return M.base + x
+end
+
+return M
+"#;
+    let out = migrate(source);
+    let program = format!("{}\nresult = add(5)\n", out.source);
+    let lua_code = compile(&program).unwrap();
+
+    let executor = LuaExecutor::new().unwrap();
+    let result: i64 = executor.execute_and_get(&lua_code, "result").unwrap();
+    assert_eq!(result, 15);
+}
@@ -0,0 +1,349 @@
+//! Integration tests for multi-entry bundle builds
+//!
+//! These tests verify that:
+//! 1. The `bundle` config section is parsed
+//! 2. Each entry gets its own static import closure from one module graph
+//! 3. Modules used by several entries are extracted into the shared file
+//! 4. Generated entry bundles run with and without a shared file
+//! 5. Modules reached only through a dynamic `import()` are bundled
+
+use bumpalo::Bump;
+use luanext_core::assets::AssetModules;
+use luanext_core::codegen::multi_entry::{bundle_import_map, MultiEntryConfig, MultiEntryPlan};
+use luanext_core::codegen::{CodeGenerator, LuaTarget};
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::module_resolver::ModuleRegistry;
+use luanext_core::optimizer::analysis::module_graph::ModuleGraph;
+use luanext_parser::ast::statement::Statement;
+use luanext_parser::ast::Program;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::LuaExecutor;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn create_program<'arena>(
+    source: &str,
+    interner: &StringInterner,
+    common: &luanext_parser::string_interner::CommonIdentifiers,
+    arena: &'arena Bump,
+) -> Program<'arena> {
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, interner, common, arena);
+    parser.parse().expect("Parsing failed")
+}
+
+/// Client and server entries sharing `shared/log` and `shared/math`
+const PROJECT: &[(&str, &str)] = &[
+    (
+        "/project/client.luax",
+        r#"
+            import { log } from "./shared/log"
+            import { add } from "./shared/math"
+            import { draw } from "./render"
+            client_result = log(draw(add(1, 2)))
+        "#,
+    ),
+    (
+        "/project/server.luax",
+        r#"
+            import { log } from "./shared/log"
+            import { add } from "./shared/math"
+            server_result = log(tostring(add(20, 22)))
+        "#,
+    ),
+    (
+        "/project/render.luax",
+        r#"
+            export function draw(n: number): string
+                return "draw:" .. tostring(n)
+            end
+        "#,
+    ),
+    (
+        "/project/shared/log.luax",
+        r#"
+            export function log(s: string): string
+                return "[log] " .. s
+            end
+        "#,
+    ),
+    (
+        "/project/shared/math.luax",
+        r#"
+            export function add(a: number, b: number): number
+                return a + b
+            end
+        "#,
+    ),
+];
+
+struct Project<'arena> {
+    modules: Vec<(PathBuf, Program<'arena>)>,
+    interner: Arc<StringInterner>,
+}
+
+fn parse_project(arena: &Bump) -> Project<'_> {
+    parse_sources(arena, PROJECT)
+}
+
+fn parse_sources<'arena>(arena: &'arena Bump, sources: &[(&str, &str)]) -> Project<'arena> {
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let modules = sources
+        .iter()
+        .map(|&(path, source)| {
+            (
+                PathBuf::from(path),
+                create_program(source, &interner, &common, arena),
+            )
+        })
+        .collect();
+    Project { modules, interner }
+}
+
+fn entries() -> Vec<PathBuf> {
+    vec![
+        PathBuf::from("/project/client.luax"),
+        PathBuf::from("/project/server.luax"),
+    ]
+}
+
+fn build_plan(project: &Project, extract_shared: bool) -> MultiEntryPlan {
+    build_plan_for(project, &entries(), extract_shared)
+}
+
+fn build_plan_for(project: &Project, entries: &[PathBuf], extract_shared: bool) -> MultiEntryPlan {
+    let module_data: Vec<(PathBuf, &[Statement])> = project
+        .modules
+        .iter()
+        .map(|(path, program)| (path.clone(), program.statements))
+        .collect();
+    let registry = ModuleRegistry::new();
+    let graph = ModuleGraph::build(&module_data, project.interner.clone(), &registry, entries);
+    MultiEntryPlan::build(&graph, entries, extract_shared)
+}
+
+fn bundle_modules<'a, 'arena>(
+    project: &'a Project<'arena>,
+) -> Vec<(String, &'a Program<'arena>, HashMap<String, String>)> {
+    let known: Vec<PathBuf> = project.modules.iter().map(|(p, _)| p.clone()).collect();
+    project
+        .modules
+        .iter()
+        .map(|(path, program)| {
+            (
+                path.to_string_lossy().to_string(),
+                program,
+                bundle_import_map(path, program, &known, &project.interner),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_bundle_config() {
+    let yaml = r#"
+compilerOptions:
+  target: "5.4"
+bundle:
+  entries:
+    - name: client
+      entry: src/client.luax
+      outFile: dist/client.lua
+    - name: server
+      entry: src/server.luax
+      outFile: dist/server.lua
+  sharedFile: dist/shared.lua
+"#;
+
+    let config = MultiEntryConfig::from_config_str(yaml).unwrap().unwrap();
+    assert_eq!(config.entries.len(), 2);
+    assert_eq!(config.entries[0].name, "client");
+    assert_eq!(config.entries[1].out_file, PathBuf::from("dist/server.lua"));
+    assert_eq!(config.shared_require_name().as_deref(), Some("shared"));
+}
+
+#[test]
+fn test_config_without_bundle_section() {
+    let yaml = "compilerOptions:\n  target: \"5.4\"\n";
+    assert!(MultiEntryConfig::from_config_str(yaml).unwrap().is_none());
+}
+
+#[test]
+fn test_plan_without_shared_extraction() {
+    let arena = Bump::new();
+    let project = parse_project(&arena);
+    let plan = build_plan(&project, false);
+
+    assert!(plan.shared.is_empty());
+    assert_eq!(plan.entries.len(), 2);
+
+    let client = &plan.entries[0];
+    assert_eq!(client.modules[0], PathBuf::from("/project/client.luax"));
+    assert!(client
+        .modules
+        .contains(&PathBuf::from("/project/render.luax")));
+    assert!(client
+        .modules
+        .contains(&PathBuf::from("/project/shared/log.luax")));
+
+    let server = &plan.entries[1];
+    assert!(!server
+        .modules
+        .contains(&PathBuf::from("/project/render.luax")));
+    assert!(server
+        .modules
+        .contains(&PathBuf::from("/project/shared/math.luax")));
+}
+
+#[test]
+fn test_plan_extracts_shared_modules() {
+    let arena = Bump::new();
+    let project = parse_project(&arena);
+    let plan = build_plan(&project, true);
+
+    let mut shared = plan.shared.clone();
+    shared.sort();
+    assert_eq!(
+        shared,
+        vec![
+            PathBuf::from("/project/shared/log.luax"),
+            PathBuf::from("/project/shared/math.luax"),
+        ]
+    );
+    for entry in &plan.entries {
+        assert!(entry.modules.iter().all(|m| !plan.shared.contains(m)));
+    }
+}
+
+#[test]
+fn test_execute_bundles_without_shared_file() {
+    let arena = Bump::new();
+    let project = parse_project(&arena);
+    let plan = build_plan(&project, false);
+    let modules = bundle_modules(&project);
+
+    let output = CodeGenerator::generate_multi_entry_bundles(
+        &modules,
+        &plan,
+        "shared",
+        LuaTarget::Lua54,
+        project.interner.clone(),
+        false,
+        &AssetModules::default(),
+    );
+    assert!(output.shared.is_none());
+    assert_eq!(output.bundles.len(), 2);
+
+    let client = LuaExecutor::new().unwrap();
+    let result: String = client
+        .execute_and_get(&output.bundles[0].1, "client_result")
+        .unwrap();
+    assert_eq!(result, "[log] draw:3");
+
+    let server = LuaExecutor::new().unwrap();
+    let result: String = server
+        .execute_and_get(&output.bundles[1].1, "server_result")
+        .unwrap();
+    assert_eq!(result, "[log] 42");
+}
+
+#[test]
+fn test_execute_bundles_with_shared_file() {
+    let arena = Bump::new();
+    let project = parse_project(&arena);
+    let plan = build_plan(&project, true);
+    let modules = bundle_modules(&project);
+
+    let output = CodeGenerator::generate_multi_entry_bundles(
+        &modules,
+        &plan,
+        "shared",
+        LuaTarget::Lua54,
+        project.interner.clone(),
+        false,
+        &AssetModules::default(),
+    );
+    let shared = output.shared.expect("shared file expected");
+    assert!(shared.contains("__modules[\"/project/shared/log.luax\"]"));
+    for (_, code) in &output.bundles {
+        assert!(code.contains("require(\"shared\")(__modules, __require)"));
+        assert!(!code.contains("__modules[\"/project/shared/log.luax\"]"));
+    }
+
+    let preload = format!(
+        "package.preload[\"shared\"] = function() return load([==[{}]==])() end",
+        shared
+    );
+    let client = LuaExecutor::new().unwrap();
+    client.execute(&preload).unwrap();
+    let result: String = client
+        .execute_and_get(&output.bundles[0].1, "client_result")
+        .unwrap();
+    assert_eq!(result, "[log] draw:3");
+}
+
+#[test]
+fn test_dynamic_import_target_is_bundled() {
+    let sources: &[(&str, &str)] = &[
+        (
+            "/project/game.luax",
+            r#"
+                const level = import("./levels/level2")
+                game_result = level.name()
+            "#,
+        ),
+        (
+            "/project/levels/level2.luax",
+            r#"
+                import { title } from "./title"
+                export function name(): string
+                    return title("level 2")
+                end
+            "#,
+        ),
+        (
+            "/project/levels/title.luax",
+            r#"
+                export function title(s: string): string
+                    return "<" .. s .. ">"
+                end
+            "#,
+        ),
+    ];
+    let entries = vec![PathBuf::from("/project/game.luax")];
+
+    let arena = Bump::new();
+    let project = parse_sources(&arena, sources);
+    let plan = build_plan_for(&project, &entries, false);
+    assert_eq!(
+        plan.entries[0].modules,
+        vec![
+            PathBuf::from("/project/game.luax"),
+            PathBuf::from("/project/levels/level2.luax"),
+            PathBuf::from("/project/levels/title.luax"),
+        ]
+    );
+
+    let modules = bundle_modules(&project);
+    let output = CodeGenerator::generate_multi_entry_bundles(
+        &modules,
+        &plan,
+        "shared",
+        LuaTarget::Lua54,
+        project.interner.clone(),
+        false,
+        &AssetModules::default(),
+    );
+
+    let game = LuaExecutor::new().unwrap();
+    let result: String = game
+        .execute_and_get(&output.bundles[0].1, "game_result")
+        .unwrap();
+    assert_eq!(result, "<level 2>");
+}
@@ -26,7 +26,7 @@ fn test_constant_folding_with_dead_code_elimination() {
 
     // Constant folding should evaluate 1 + 2 to 3
     assert!(
-        output.contains("local result = 3"),
+        output.contains("local result <const> = 3"),
         "Expected constant folding of 1 + 2 to 3"
     );
 
@@ -68,7 +68,7 @@ fn test_complex_arithmetic_optimization() {
     // 4. Algebraic simplification: x + 0 → x
 
     assert!(
-        output.contains("local a = 8"),
+        output.contains("local a <const> = 8"),
         "Expected constant folding of 5 + 3"
     );
 }
@@ -88,7 +88,10 @@ fn test_dead_code_after_return() {
     println!("Output:\n{}", output);
 
     // Constant folding: 1 + 1 → 2
-    assert!(output.contains("local x = 2"), "Expected constant folding");
+    assert!(
+        output.contains("local x <const> = 2"),
+        "Expected constant folding"
+    );
 
     // Dead code elimination: code after return should be removed
     // The function should end shortly after the return statement
@@ -171,7 +174,10 @@ fn test_all_optimizations_together() {
     // 5. Dead code elimination: if false block removed
     // 6. Dead code elimination: code after return removed
 
-    assert!(output.contains("local base = 15"), "Expected 10 + 5 → 15");
+    assert!(
+        output.contains("local base <const> = 15"),
+        "Expected 10 + 5 → 15"
+    );
 }
 
 #[test]
@@ -0,0 +1,117 @@
+//! Tests for miscompile bisection
+//!
+//! These tests verify that:
+//! 1. A transformation limit caps how many changes the optimizer keeps
+//! 2. Bisection names the first pass and the transformation that fail a check
+//! 3. Bisection refuses checks that fail unoptimized or pass fully optimized
+
+use bumpalo::Bump;
+use luanext_core::codegen::{CodeGenerator, LuaTarget};
+use luanext_core::config::OptimizationLevel;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::optimizer::bisect;
+use luanext_core::optimizer::pipeline::{OptimizationGoal, PassPipelineConfig};
+use luanext_core::optimizer::Optimizer;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::optimize::parse;
+use std::sync::Arc;
+
+const FOLDABLE: &str = r#"
+    local width = 6 * 7
+    local height = 2 + 3
+    local label = "area"
+    print(label, width * height)
+"#;
+
+/// Optimize `FOLDABLE` at O2 with at most `limit` transformations
+fn optimize_limited(limit: usize) -> (String, usize) {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let mut program = parse(FOLDABLE, &interner, &common, &arena);
+
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut optimizer = Optimizer::new(OptimizationLevel::Moderate, handler, interner.clone());
+    optimizer.set_transformation_limit(limit);
+    optimizer
+        .optimize(&mut program, &arena)
+        .expect("Optimization failed");
+
+    let lua = CodeGenerator::new(interner).generate(&program);
+    (lua, optimizer.transformations_applied())
+}
+
+/// Bisect `FOLDABLE` at O2 against `check`
+fn bisect_foldable(check: &mut dyn FnMut(&str) -> bool) -> Result<bisect::BisectResult, String> {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let program = parse(FOLDABLE, &interner, &common, &arena);
+
+    bisect::bisect(
+        &program,
+        &arena,
+        OptimizationLevel::Moderate,
+        OptimizationGoal::Speed,
+        LuaTarget::default(),
+        &PassPipelineConfig::default(),
+        Arc::new(CollectingDiagnosticHandler::new()),
+        interner.clone(),
+        &mut |program| CodeGenerator::new(interner.clone()).generate(program),
+        check,
+    )
+}
+
+#[test]
+fn test_zero_limit_keeps_program() {
+    let (lua, applied) = optimize_limited(0);
+
+    assert_eq!(applied, 0);
+    assert!(lua.contains("6 * 7"), "{}", lua);
+    assert!(lua.contains("2 + 3"), "{}", lua);
+}
+
+#[test]
+fn test_limit_caps_transformations() {
+    let (unlimited_lua, total) = optimize_limited(usize::MAX);
+    assert!(
+        total >= 2,
+        "{} transformation(s):\n{}",
+        total,
+        unlimited_lua
+    );
+    assert!(!unlimited_lua.contains("6 * 7"), "{}", unlimited_lua);
+
+    // Top-level statements are transformed in order, so only `width` folds
+    let (lua, applied) = optimize_limited(1);
+    assert_eq!(applied, 1);
+    assert!(!lua.contains("6 * 7"), "{}", lua);
+    assert!(lua.contains("2 + 3"), "{}", lua);
+}
+
+#[test]
+fn test_bisect_finds_culprit() {
+    // "Miscompile": folding `2 + 3`
+    let result = bisect_foldable(&mut |lua| lua.contains("2 + 3")).expect("Bisection failed");
+
+    assert_eq!(result.culprit_pass(), "constant-folding", "{:?}", result);
+    assert_eq!(result.failing_transformations, 2, "{:?}", result);
+    let culprit = result.culprit.expect("no culprit transformation");
+    assert_eq!(culprit.index, 2);
+    assert!(
+        culprit.passes.contains(&"constant-folding"),
+        "{:?}",
+        culprit
+    );
+    assert!(culprit.span.line >= 3, "{:?}", culprit);
+    assert!(!result.failing_lua.contains("2 + 3"));
+}
+
+#[test]
+fn test_bisect_rejects_unusable_checks() {
+    let err = bisect_foldable(&mut |_| false).unwrap_err();
+    assert!(err.contains("without optimization"), "{}", err);
+
+    let err = bisect_foldable(&mut |_| true).unwrap_err();
+    assert!(err.contains("every optimizer pass"), "{}", err);
+}
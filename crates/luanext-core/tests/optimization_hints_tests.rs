@@ -0,0 +1,359 @@
+//! Tests for the optimization hint decorators
+//!
+//! These tests verify that:
+//! 1. `@inline`, `@noinline`, `@optimize(...)` and `@pure` are parsed into `FunctionHints`
+//! 2. Hint decorators are stripped from the generated Lua and don't pull in the runtime
+//! 3. The side-effect analyzer trusts methods declared `@pure`
+//! 4. Operator inlining leaves `@noinline` operators alone
+//! 5. `optimizer.hints` config entries apply to free functions: `noinline`
+//!    blocks inlining and cloning with a missed remark, `inline` lifts the
+//!    size threshold
+
+use bumpalo::Bump;
+use luanext_core::codegen::LuaTarget;
+use luanext_core::config::OptimizationLevel;
+use luanext_core::di::DiContainer;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::optimizer::analysis::SideEffectAnalyzer;
+use luanext_core::optimizer::hints::{FreeFunctionHints, FunctionHints, InlineHint, OptimizeHint};
+use luanext_core::optimizer::remarks::{self, Remark, RemarkKind};
+use luanext_parser::ast::statement::{ClassMember, Statement};
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::compile::compile;
+use luanext_test_helpers::optimize::{optimize_with, remarks_of};
+use luanext_test_helpers::LuaExecutor;
+use std::sync::Arc;
+
+const HINTED_CLASS: &str = r#"
+    class Vec {
+        x: number
+
+        constructor(x: number) {
+            self.x = x
+        }
+
+        @inline
+        public double(): number {
+            return self.x * 2
+        }
+
+        @noinline
+        public patched(): number {
+            return self.x
+        }
+
+        @optimize("none")
+        public untouched(): number {
+            return self.x
+        }
+
+        @optimize("speed")
+        @pure
+        public length(): number {
+            return self.x
+        }
+
+        public plain(): number {
+            return self.x
+        }
+    }
+"#;
+
+/// Parse `source` and return the hints of every method of its first class
+fn method_hints(source: &str) -> Vec<(String, FunctionHints)> {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+
+    let class = program
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Statement::Class(class) => Some(class),
+            _ => None,
+        })
+        .expect("no class in source");
+    class
+        .members
+        .iter()
+        .filter_map(|member| match member {
+            ClassMember::Method(method) => Some((
+                interner.resolve(method.name.node).to_string(),
+                FunctionHints::from_decorators(method.decorators, &interner),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+fn hints_for(hints: &[(String, FunctionHints)], name: &str) -> FunctionHints {
+    hints
+        .iter()
+        .find(|(method, _)| method == name)
+        .map(|(_, hints)| *hints)
+        .unwrap_or_else(|| panic!("no method `{}`", name))
+}
+
+/// Optimize `source` with the `optimizer.hints` section in `config`,
+/// returning the Lua and the remarks
+fn optimize_with_hints(
+    source: &str,
+    config: &str,
+    level: OptimizationLevel,
+) -> (String, Vec<Remark>) {
+    let hints = FreeFunctionHints::from_config_str(config).expect("Invalid hints");
+    optimize_with(source, level, LuaTarget::default(), |optimizer, _| {
+        optimizer.set_function_hints(hints)
+    })
+}
+
+#[test]
+fn test_hints_parsed_from_decorators() {
+    let hints = method_hints(HINTED_CLASS);
+
+    let double = hints_for(&hints, "double");
+    assert_eq!(double.inline, InlineHint::Always);
+    assert!(double.forces_inlining());
+
+    let patched = hints_for(&hints, "patched");
+    assert_eq!(patched.inline, InlineHint::Never);
+    assert_eq!(patched.inline_blocker(), Some("marked @noinline"));
+
+    let untouched = hints_for(&hints, "untouched");
+    assert_eq!(untouched.optimize, OptimizeHint::None);
+    assert!(!untouched.allows_inlining());
+
+    let length = hints_for(&hints, "length");
+    assert_eq!(length.optimize, OptimizeHint::Speed);
+    assert!(length.pure);
+
+    assert_eq!(hints_for(&hints, "plain"), FunctionHints::default());
+}
+
+#[test]
+fn test_hint_decorators_stripped_from_output() {
+    let lua = compile(HINTED_CLASS).expect("compile failed");
+
+    for hint in ["inline(", "noinline(", "optimize(", "pure("] {
+        assert!(!lua.contains(hint), "`{}` left in output:\n{}", hint, lua);
+    }
+    assert!(
+        !lua.contains("LuaNext Runtime Library"),
+        "hints should not embed the decorator runtime:\n{}",
+        lua
+    );
+}
+
+#[test]
+fn test_hinted_methods_execute() {
+    let source = format!(
+        "{}\nconst v = new Vec(21)\nresult: number = v::double() + v::patched() - v::untouched()",
+        HINTED_CLASS
+    );
+    let lua = compile(&source).expect("compile failed");
+    let executor = LuaExecutor::new().unwrap();
+
+    let result: i64 = executor.execute_and_get(&lua, "result").unwrap();
+    assert_eq!(result, 42);
+}
+
+#[test]
+fn test_side_effect_analyzer_trusts_pure_methods() {
+    let source = r#"
+        class Counter {
+            @pure
+            public static peek(n: number): number {
+                return n
+            }
+
+            public static bump(n: number): number {
+                return n + 1
+            }
+        }
+
+        function readsPure(): number
+            return Counter.peek(1)
+        end
+
+        function readsImpure(): number
+            return Counter.bump(1)
+        end
+    "#;
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+
+    let info = SideEffectAnalyzer::new(interner.clone()).analyze(program.statements);
+
+    let counter = interner.get_or_intern("Counter");
+    assert!(info.is_pure_method(counter, interner.get_or_intern("peek")));
+    assert!(!info.is_pure_method(counter, interner.get_or_intern("bump")));
+    assert!(info.is_pure(interner.get_or_intern("readsPure")));
+    assert!(!info.is_pure(interner.get_or_intern("readsImpure")));
+}
+
+#[test]
+fn test_operator_inlining_respects_noinline() {
+    let source = r#"
+        class Money {
+            cents: number
+
+            constructor(cents: number) {
+                self.cents = cents
+            }
+
+            @noinline
+            operator +(other: Money): Money {
+                return new Money(self.cents + other.cents)
+            }
+        }
+
+        const a = new Money(1)
+        const b = a + a
+        const c = b + a
+        const d = c + a
+    "#;
+    let mut container = DiContainer::test_default();
+    let (result, collected) = remarks::collect(|| {
+        container.compile_with_optimization(source, OptimizationLevel::Aggressive)
+    });
+    let lua = result.expect("compile failed");
+
+    assert!(!lua.contains("noinline"), "{}", lua);
+    assert!(
+        !collected
+            .iter()
+            .any(|r| r.pass == "operator-inlining" && r.kind == RemarkKind::Applied),
+        "{:?}",
+        collected
+    );
+}
+
+#[test]
+fn test_free_function_hints_parsed_from_config() {
+    let config = r#"
+optimizer:
+  hints:
+    onDamage: [noinline]
+    lerp: [inline, pure]
+    buildTables: ['optimize("none")']
+"#;
+    let hints = FreeFunctionHints::from_config_str(config).expect("Invalid hints");
+
+    assert_eq!(hints.get("onDamage").inline, InlineHint::Never);
+    assert!(hints.get("lerp").forces_inlining());
+    assert!(hints.get("lerp").pure);
+    assert_eq!(hints.get("buildTables").optimize, OptimizeHint::None);
+    assert_eq!(hints.get("other"), FunctionHints::default());
+
+    let err = FreeFunctionHints::from_config_str("optimizer:\n  hints:\n    f: [fast]\n")
+        .expect_err("unknown hint accepted");
+    assert!(err.contains("optimizer.hints.f"), "{}", err);
+}
+
+#[test]
+fn test_noinline_hint_blocks_free_function_inlining() {
+    let source = r#"
+function add(a: number, b: number): number
+    return a + b
+end
+
+local x = add(1, 2)
+print(x)
+"#;
+    let (lua, remarks) = optimize_with_hints(
+        source,
+        "optimizer:\n  hints:\n    add: [noinline]\n",
+        OptimizationLevel::Aggressive,
+    );
+
+    assert!(
+        remarks_of(&remarks, "function-inlining", RemarkKind::Missed)
+            .contains(&"`add` not inlined: marked @noinline"),
+        "{:?}",
+        remarks
+    );
+    assert!(
+        !remarks
+            .iter()
+            .any(|r| r.kind == RemarkKind::Applied && r.message.contains("`add`")),
+        "{:?}",
+        remarks
+    );
+    assert!(lua.contains("add(1, 2)"), "{}", lua);
+}
+
+#[test]
+fn test_inline_hint_lifts_free_function_threshold() {
+    let source = r#"
+function mix(a: number, b: number): number
+    local s = a + b
+    local d = a - b
+    local p = s * d
+    local q = p + a
+    local r = q - b
+    return r * 2
+end
+
+local x = mix(3, 4)
+print(x)
+"#;
+    let (_, remarks) = optimize_with_hints(
+        source,
+        "optimizer:\n  hints:\n    mix: [inline]\n",
+        OptimizationLevel::Moderate,
+    );
+
+    let missed = remarks_of(&remarks, "function-inlining", RemarkKind::Missed);
+    assert!(
+        !missed.iter().any(|m| m.contains("threshold")),
+        "{:?}",
+        remarks
+    );
+    assert!(
+        remarks_of(&remarks, "function-inlining", RemarkKind::Applied)
+            .contains(&"inlined call to `mix`"),
+        "{:?}",
+        remarks
+    );
+}
+
+#[test]
+fn test_noinline_hint_blocks_free_function_cloning() {
+    let source = r#"
+function greet(name: string, loud: boolean): string
+    if loud then
+        return name .. "!"
+    end
+    return name
+end
+
+print(greet("a", true))
+print(greet("b", true))
+"#;
+    let (lua, remarks) = optimize_with_hints(
+        source,
+        "optimizer:\n  hints:\n    greet: [noinline]\n",
+        OptimizationLevel::Aggressive,
+    );
+
+    assert!(
+        remarks_of(&remarks, "function-cloning", RemarkKind::Missed)
+            .iter()
+            .any(|m| *m == "`greet` not cloned: marked @noinline"),
+        "{:?}",
+        remarks
+    );
+    assert!(!lua.contains("greet__clone"), "{}", lua);
+}
@@ -0,0 +1,147 @@
+//! Integration tests for optimization remarks
+//!
+//! These tests verify that:
+//! 1. Remarks are only recorded after `Optimizer::enable_remarks`
+//! 2. Passes report applied transformations with the source span
+//! 3. Passes report missed opportunities with the reason they gave up
+//! 4. Remarks repeated by the fixed-point iterations are reported once
+
+use bumpalo::Bump;
+use luanext_core::config::OptimizationLevel;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_core::optimizer::Optimizer;
+use luanext_core::MutableProgram;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use std::sync::Arc;
+
+fn optimize(source: &str, level: OptimizationLevel, enable_remarks: bool) -> Vec<Remark> {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler.clone(), &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+
+    let mut program = MutableProgram::from_program(&program);
+    let mut optimizer = Optimizer::new(level, handler, interner);
+    if enable_remarks {
+        optimizer.enable_remarks();
+    }
+    optimizer
+        .optimize(&mut program, &arena)
+        .expect("Optimization failed");
+    optimizer.take_remarks()
+}
+
+fn remarks_from<'a>(remarks: &'a [Remark], pass: &str, kind: RemarkKind) -> Vec<&'a Remark> {
+    remarks
+        .iter()
+        .filter(|r| r.pass == pass && r.kind == kind)
+        .collect()
+}
+
+const UNROLLABLE: &str = r#"
+for i = 1, 3 do
+    print(i)
+end
+"#;
+
+#[test]
+fn test_remarks_disabled_by_default() {
+    let remarks = optimize(UNROLLABLE, OptimizationLevel::Aggressive, false);
+    assert!(remarks.is_empty(), "{:?}", remarks);
+}
+
+#[test]
+fn test_loop_unrolling_applied() {
+    let remarks = optimize(UNROLLABLE, OptimizationLevel::Aggressive, true);
+    let applied = remarks_from(&remarks, "loop-unrolling", RemarkKind::Applied);
+    assert_eq!(applied.len(), 1, "{:?}", remarks);
+    assert_eq!(applied[0].message, "unrolled loop with 3 iteration(s)");
+    assert_eq!(applied[0].span.line, 2);
+}
+
+#[test]
+fn test_loop_unrolling_missed() {
+    let source = r#"
+local n = math.random(10)
+for i = 1, n do
+    print(i)
+end
+for j = 1, 100 do
+    print(j)
+end
+"#;
+    let remarks = optimize(source, OptimizationLevel::Aggressive, true);
+    let missed = remarks_from(&remarks, "loop-unrolling", RemarkKind::Missed);
+    let messages: Vec<(u32, &str)> = missed
+        .iter()
+        .map(|r| (r.span.line, r.message.as_str()))
+        .collect();
+    assert!(
+        messages.contains(&(3, "loop bounds are not compile-time constants")),
+        "{:?}",
+        messages
+    );
+    assert!(
+        messages.contains(&(6, "trip count exceeds the unroll limit of 4")),
+        "{:?}",
+        messages
+    );
+}
+
+#[test]
+fn test_function_inlining_applied_and_missed() {
+    let source = r#"
+function add(a: number, b: number): number
+    return a + b
+end
+
+function fact(n: number): number
+    if n <= 1 then
+        return 1
+    end
+    return n * fact(n - 1)
+end
+
+local x = add(1, 2)
+local y = fact(5)
+print(x, y)
+"#;
+    let remarks = optimize(source, OptimizationLevel::Moderate, true);
+
+    let applied = remarks_from(&remarks, "function-inlining", RemarkKind::Applied);
+    assert!(
+        applied
+            .iter()
+            .any(|r| r.message == "inlined call to `add`" && r.span.line == 13),
+        "{:?}",
+        remarks
+    );
+
+    let missed = remarks_from(&remarks, "function-inlining", RemarkKind::Missed);
+    assert!(
+        missed
+            .iter()
+            .any(|r| r.message == "`fact` not inlined: function is recursive"),
+        "{:?}",
+        remarks
+    );
+}
+
+#[test]
+fn test_remarks_reported_once() {
+    let source = r#"
+local x = 1 + 2
+print(x)
+"#;
+    let remarks = optimize(source, OptimizationLevel::Minimal, true);
+    let folded = remarks_from(&remarks, "constant-folding", RemarkKind::Applied);
+    assert_eq!(folded.len(), 1, "{:?}", remarks);
+    assert_eq!(folded[0].span.line, 2);
+}
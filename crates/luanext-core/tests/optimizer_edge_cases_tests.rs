@@ -80,7 +80,7 @@ fn test_algebraic_simplification_multiplication() {
 
     let output = compile_with_opt_level(source, OptimizationLevel::Minimal).unwrap();
     assert!(
-        output.contains("return 0") || output.contains("local x = 0"),
+        output.contains("return 0") || output.contains("local x <const> = 0"),
         "Should simplify 5 * 0 = 0. Got:\n{}",
         output
     );
@@ -96,7 +96,8 @@ fn test_algebraic_simplification_addition() {
 
     let output = compile_with_opt_level(source, OptimizationLevel::Minimal).unwrap();
     assert!(
-        output.contains("15") || output.contains("local x = 5") && output.contains("local y = 10"),
+        output.contains("15")
+            || output.contains("local x <const> = 5") && output.contains("local y <const> = 10"),
         "Should simplify x + 0 and 0 + y. Got:\n{}",
         output
     );
@@ -0,0 +1,244 @@
+//! Tests for the configurable optimizer pass pipeline
+//!
+//! These tests verify that:
+//! 1. The default pipeline registers the same passes per level as before
+//! 2. `optimizer.passes` is read from the config and unknown passes are rejected
+//! 3. `enable`, `disable`, `order` and `maxIterations` change what the optimizer runs
+//! 4. Opt-in passes run only when `enable` lists them
+
+use bumpalo::Bump;
+use luanext_core::config::OptimizationLevel;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::optimizer::pipeline::{OptimizationGoal, PassGroup, PassPipelineConfig, PASSES};
+use luanext_core::optimizer::remarks::RemarkKind;
+use luanext_core::optimizer::Optimizer;
+use luanext_core::MutableProgram;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use std::sync::Arc;
+
+fn create_optimizer(level: OptimizationLevel, config: PassPipelineConfig) -> Optimizer<'static> {
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let interner = Arc::new(StringInterner::new());
+    let mut optimizer = Optimizer::new(level, handler, interner);
+    optimizer.set_pass_pipeline(config);
+    optimizer
+}
+
+fn names(passes: &[&str]) -> Vec<String> {
+    passes.iter().map(|name| name.to_string()).collect()
+}
+
+/// Optimize `source` and return the passes that reported an applied remark
+fn applied_passes(
+    source: &str,
+    level: OptimizationLevel,
+    config: PassPipelineConfig,
+) -> Vec<&'static str> {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler.clone(), &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+
+    let mut program = MutableProgram::from_program(&program);
+    let mut optimizer = Optimizer::new(level, handler, interner);
+    optimizer.set_pass_pipeline(config);
+    optimizer.enable_remarks();
+    optimizer
+        .optimize(&mut program, &arena)
+        .expect("Optimization failed");
+    optimizer
+        .take_remarks()
+        .into_iter()
+        .filter(|r| r.kind == RemarkKind::Applied)
+        .map(|r| r.pass)
+        .collect()
+}
+
+#[test]
+fn test_default_pipeline_per_level() {
+    let o1 = create_optimizer(OptimizationLevel::Minimal, PassPipelineConfig::default());
+    assert_eq!(
+        o1.pass_names(),
+        vec![
+            "constant-folding",
+            "algebraic-simplification",
+            "dead-code-elimination",
+            "global-localization",
+        ]
+    );
+
+    // Every pass except the size passes, which only run when optimizing for
+    // size, and the opt-in passes
+    let speed_passes = PASSES
+        .iter()
+        .filter(|p| p.goal != Some(OptimizationGoal::Size) && !p.opt_in)
+        .count();
+    let o3 = create_optimizer(OptimizationLevel::Aggressive, PassPipelineConfig::default());
+    assert_eq!(o3.pass_names().len(), speed_passes);
+    assert_eq!(o3.pass_count(), speed_passes);
+    assert_eq!(o3.max_iterations(), 10);
+}
+
+#[test]
+fn test_pipeline_grouped_in_execution_order() {
+    let optimizer = create_optimizer(OptimizationLevel::Aggressive, PassPipelineConfig::default());
+    let groups: Vec<PassGroup> = optimizer.pass_pipeline().iter().map(|p| p.group).collect();
+    let mut sorted = groups.clone();
+    sorted.dedup();
+    assert_eq!(
+        sorted,
+        vec![
+            PassGroup::Expression,
+            PassGroup::Elimination,
+            PassGroup::Function,
+            PassGroup::DataStructure,
+            PassGroup::WholeProgram,
+        ]
+    );
+}
+
+#[test]
+fn test_config_parsed_from_yaml() {
+    let yaml = r#"
+compilerOptions:
+  target: "5.4"
+optimizer:
+  passes:
+    enable: [loop-unrolling]
+    disable: [function-inlining, dead-import-elimination]
+    order: [copy-propagation]
+    maxIterations: 2
+"#;
+    let config = PassPipelineConfig::from_config_str(yaml).unwrap();
+    assert_eq!(config.enable, names(&["loop-unrolling"]));
+    assert_eq!(
+        config.disable,
+        names(&["function-inlining", "dead-import-elimination"])
+    );
+    assert_eq!(config.order, names(&["copy-propagation"]));
+    assert_eq!(config.max_iterations(), 2);
+
+    let absent = PassPipelineConfig::from_config_str("compilerOptions:\n  target: \"5.4\"\n");
+    assert_eq!(absent.unwrap(), PassPipelineConfig::default());
+}
+
+#[test]
+fn test_config_rejects_unknown_passes() {
+    let err =
+        PassPipelineConfig::from_config_str("optimizer:\n  passes:\n    enable: [inlining]\n")
+            .unwrap_err();
+    assert!(err.contains("unknown optimizer pass `inlining`"), "{}", err);
+
+    let err = PassPipelineConfig::from_config_str("optimizer:\n  passes:\n    maxIterations: 0\n")
+        .unwrap_err();
+    assert!(err.contains("maxIterations"), "{}", err);
+
+    let err = PassPipelineConfig::from_config_str("optimizer:\n  passes:\n    skip: [sccp]\n")
+        .unwrap_err();
+    assert!(err.contains("skip"), "{}", err);
+}
+
+#[test]
+fn test_enable_disable_and_order() {
+    let config = PassPipelineConfig {
+        enable: names(&["loop-unrolling"]),
+        disable: names(&["constant-folding", "global-localization"]),
+        order: names(&["dead-store-elimination", "sccp"]),
+        max_iterations: Some(3),
+    };
+    let optimizer = create_optimizer(OptimizationLevel::Moderate, config);
+    let pass_names = optimizer.pass_names();
+
+    assert!(pass_names.contains(&"loop-unrolling"));
+    assert!(!pass_names.contains(&"constant-folding"));
+    assert!(!pass_names.contains(&"global-localization"));
+    assert!(!pass_names.contains(&"aggressive-inlining"));
+
+    let elimination: Vec<&str> = optimizer
+        .pass_pipeline()
+        .iter()
+        .filter(|p| p.group == PassGroup::Elimination)
+        .map(|p| p.name)
+        .collect();
+    assert_eq!(
+        elimination,
+        vec![
+            "dead-store-elimination",
+            "sccp",
+            "dead-code-elimination",
+            "jump-threading",
+            "copy-propagation",
+            "common-subexpression-elimination",
+        ]
+    );
+    assert_eq!(optimizer.max_iterations(), 3);
+}
+
+#[test]
+fn test_disabled_pass_does_not_run() {
+    let source = r#"
+for i = 1, 3 do
+    print(i)
+end
+"#;
+    let applied = applied_passes(
+        source,
+        OptimizationLevel::Aggressive,
+        PassPipelineConfig::default(),
+    );
+    assert!(applied.contains(&"loop-unrolling"), "{:?}", applied);
+
+    let config = PassPipelineConfig {
+        disable: names(&["loop-unrolling"]),
+        ..PassPipelineConfig::default()
+    };
+    let applied = applied_passes(source, OptimizationLevel::Aggressive, config);
+    assert!(!applied.contains(&"loop-unrolling"), "{:?}", applied);
+}
+
+#[test]
+fn test_enabled_pass_runs_below_its_level() {
+    let source = r#"
+for i = 1, 3 do
+    print(i)
+end
+"#;
+    let applied = applied_passes(
+        source,
+        OptimizationLevel::Minimal,
+        PassPipelineConfig::default(),
+    );
+    assert!(!applied.contains(&"loop-unrolling"), "{:?}", applied);
+
+    let config = PassPipelineConfig {
+        enable: names(&["loop-unrolling"]),
+        ..PassPipelineConfig::default()
+    };
+    let applied = applied_passes(source, OptimizationLevel::Minimal, config);
+    assert!(applied.contains(&"loop-unrolling"), "{:?}", applied);
+}
+
+#[test]
+fn test_opt_in_pass_runs_only_when_enabled() {
+    let opt_in: Vec<&str> = PASSES.iter().filter(|p| p.opt_in).map(|p| p.name).collect();
+    let o3 = create_optimizer(OptimizationLevel::Aggressive, PassPipelineConfig::default());
+    let pass_names = o3.pass_names();
+    for name in &opt_in {
+        assert!(!pass_names.contains(name), "{} runs by default", name);
+    }
+
+    let config = PassPipelineConfig {
+        enable: names(&["lambda-lifting"]),
+        ..PassPipelineConfig::default()
+    };
+    let optimizer = create_optimizer(OptimizationLevel::Moderate, config);
+    let pass_names = optimizer.pass_names();
+    assert!(pass_names.contains(&"lambda-lifting"));
+    assert!(!pass_names.contains(&"constant-pooling"));
+}
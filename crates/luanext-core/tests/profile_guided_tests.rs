@@ -0,0 +1,368 @@
+//! Tests for profile-guided optimization
+//!
+//! These tests verify that:
+//! 1. Profile files parse, and functions and loops are classified hot or cold
+//! 2. Instrumented programs count calls, loops and branch arms and write them
+//! 3. Cold functions are not inlined and cold loops are not unrolled
+//! 4. Hot functions are inlined above the default size threshold
+//! 5. Branch layout puts the most taken arm of an `if` first
+
+use bumpalo::Bump;
+use luanext_core::codegen::{CodeGeneratorBuilder, LuaTarget};
+use luanext_core::config::OptimizationLevel;
+use luanext_core::optimizer::profile::{self, Counter, CounterKind, Hotness, Profile};
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_parser::ast::statement::{ForStatement, Statement};
+use luanext_parser::span::Span;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::optimize::{enabling, optimize_with, parse, remarks_of};
+use luanext_test_helpers::LuaExecutor;
+use std::sync::Arc;
+
+/// Optimize `source` with the profile `make_profile` writes for its
+/// top-level statements and the opt-in branch layout enabled, returning the
+/// Lua and the remarks
+fn optimize_with_profile(
+    source: &str,
+    level: OptimizationLevel,
+    make_profile: impl Fn(&[Statement]) -> String,
+) -> (String, Vec<Remark>) {
+    optimize_with(source, level, LuaTarget::default(), |optimizer, program| {
+        let text = make_profile(&program.statements);
+        let profile = Profile::parse(&text).expect("Invalid profile");
+        optimizer.set_pass_pipeline(enabling("branch-layout"));
+        optimizer.set_profile(profile.module("main").expect("no `main` module"));
+    })
+}
+
+/// Profile text for module `main`
+fn profile_text(counts: &[(Counter, u64)]) -> String {
+    let mut text = String::from("# luanext profile v1\nmodule\tmain\n");
+    for (counter, count) in counts {
+        text.push_str(&format!("{}\t{}\n", counter.key(), count));
+    }
+    text
+}
+
+fn function_span(stmt: &Statement) -> Span {
+    match stmt {
+        Statement::Function(func) => func.span,
+        _ => panic!("expected a function declaration"),
+    }
+}
+
+fn numeric_for_body<'a, 'arena>(stmt: &'a Statement<'arena>) -> (Span, &'a [Statement<'arena>]) {
+    match stmt {
+        Statement::For(for_stmt) => match &**for_stmt {
+            ForStatement::Numeric(for_num) => (for_num.span, for_num.body.statements),
+            _ => panic!("expected a numeric for"),
+        },
+        _ => panic!("expected a numeric for"),
+    }
+}
+
+fn if_span(stmt: &Statement) -> Span {
+    match stmt {
+        Statement::If(if_stmt) => if_stmt.span,
+        _ => panic!("expected an if"),
+    }
+}
+
+/// Span of the first `if` in the function declared by `stmt`
+fn first_if_in_function(stmt: &Statement) -> Span {
+    match stmt {
+        Statement::Function(func) => if_span(&func.body.statements[0]),
+        _ => panic!("expected a function declaration"),
+    }
+}
+
+#[test]
+fn test_counter_keys_round_trip() {
+    let span = Span::new(0, 10, 7, 5);
+    for kind in [
+        CounterKind::Call,
+        CounterKind::Loop,
+        CounterKind::Branch(0),
+        CounterKind::Branch(3),
+    ] {
+        let counter = Counter::new(kind, span);
+        assert_eq!(Counter::parse(&counter.key()), Some(counter));
+    }
+    assert_eq!(Counter::new(CounterKind::Loop, span).key(), "loop 7:5");
+    assert_eq!(
+        Counter::new(CounterKind::Branch(1), span).key(),
+        "branch 7:5 1"
+    );
+
+    assert_eq!(Counter::parse("call 7"), None);
+    assert_eq!(Counter::parse("branch 7:5"), None);
+    assert_eq!(Counter::parse("loop 7:5 1"), None);
+    assert_eq!(Counter::parse("jump 7:5"), None);
+}
+
+#[test]
+fn test_profile_hotness() {
+    let hot = Span::new(0, 10, 2, 1);
+    let warm = Span::new(20, 30, 6, 1);
+    let unrun = Span::new(40, 50, 9, 1);
+    let text = "# luanext profile v1\n\
+                module\tmain\n\
+                call 2:1\t900\n\
+                call 6:1\t10\n\
+                module\tmain\n\
+                call 2:1\t100\n\
+                module\tutil\n";
+    let profile = Profile::parse(text).expect("Invalid profile");
+
+    let main = profile.module("main").expect("no `main` module");
+    assert_eq!(main.count(Counter::new(CounterKind::Call, hot)), 1000);
+    assert_eq!(main.function_hotness(hot), Hotness::Hot);
+    assert_eq!(main.function_hotness(warm), Hotness::Normal);
+    assert_eq!(main.function_hotness(unrun), Hotness::Cold);
+    assert!(profile.module("util").is_some());
+    assert!(profile.module("never-loaded").is_none());
+
+    let err = Profile::parse("call 2:1\t5\n").unwrap_err();
+    assert!(err.contains("before the first `module` line"), "{}", err);
+    let err = Profile::parse("module\tmain\ncall 2:1\tmany\n").unwrap_err();
+    assert!(err.starts_with("line 2: invalid profile entry"), "{}", err);
+}
+
+#[test]
+fn test_instrumented_program_writes_profile() {
+    let source = r#"
+function classify(n: number): string
+    if n % 10 == 0 then
+        return "round"
+    else
+        return "other"
+    end
+end
+rounds = 0
+for i = 1, 50 do
+    if classify(i) == "round" then
+        rounds = rounds + 1
+    end
+end
+"#;
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let mut program = parse(source, &interner, &common, &arena);
+
+    let classify = function_span(&program.statements[0]);
+    let inner_if = first_if_in_function(&program.statements[0]);
+    let (loop_span, loop_body) = numeric_for_body(&program.statements[2]);
+    let outer_if = if_span(&loop_body[0]);
+
+    let path = std::env::temp_dir().join(format!("luanext-pgo-{}.profile", std::process::id()));
+    profile::instrument(&mut program, &arena, &interner);
+    let lua = CodeGeneratorBuilder::new(interner)
+        .profile_instrumentation("main".to_string(), path.to_string_lossy().to_string())
+        .build()
+        .generate(&program);
+
+    let executor = LuaExecutor::new().unwrap();
+    let rounds: i64 = executor.execute_and_get(&lua, "rounds").unwrap();
+    assert_eq!(rounds, 5);
+    executor.execute("__LuaNextProfile.write()").unwrap();
+
+    let text = std::fs::read_to_string(&path).expect("profile not written");
+    let _ = std::fs::remove_file(&path);
+    let main = Profile::parse(&text)
+        .expect("Invalid profile")
+        .module("main")
+        .expect("no `main` module");
+    let count = |kind, span| main.count(Counter::new(kind, span));
+    assert_eq!(count(CounterKind::Call, classify), 50, "{}", text);
+    assert_eq!(count(CounterKind::Loop, loop_span), 50, "{}", text);
+    assert_eq!(count(CounterKind::Branch(0), inner_if), 5, "{}", text);
+    assert_eq!(count(CounterKind::Branch(1), inner_if), 45, "{}", text);
+    // The outer `if` has no `else`; instrumentation adds one to count it
+    assert_eq!(count(CounterKind::Branch(0), outer_if), 5, "{}", text);
+    assert_eq!(count(CounterKind::Branch(1), outer_if), 45, "{}", text);
+}
+
+#[test]
+fn test_cold_function_not_inlined() {
+    let source = r#"
+function add(a: number, b: number): number
+    return a + b
+end
+
+local x = add(1, 2)
+print(x)
+"#;
+    let (lua, remarks) =
+        optimize_with_profile(source, OptimizationLevel::Moderate, |_| profile_text(&[]));
+
+    assert!(
+        remarks_of(&remarks, "function-inlining", RemarkKind::Missed)
+            .contains(&"`add` not inlined: function never ran in the profile"),
+        "{:?}",
+        remarks
+    );
+    assert!(lua.contains("add(1, 2)"), "{}", lua);
+}
+
+#[test]
+fn test_hot_function_inlined_above_threshold() {
+    let source = r#"
+function mix(a: number, b: number): number
+    local s = a + b
+    local d = a - b
+    local p = s * d
+    local q = p + a
+    local r = q - b
+    return r * 2
+end
+
+local x = mix(3, 4)
+print(x)
+"#;
+    let (_, remarks) = optimize_with_profile(source, OptimizationLevel::Moderate, |stmts| {
+        profile_text(&[(
+            Counter::new(CounterKind::Call, function_span(&stmts[0])),
+            1000,
+        )])
+    });
+
+    let missed = remarks_of(&remarks, "function-inlining", RemarkKind::Missed);
+    assert!(
+        !missed.iter().any(|m| m.contains("threshold")),
+        "{:?}",
+        remarks
+    );
+    assert!(
+        remarks_of(&remarks, "function-inlining", RemarkKind::Applied)
+            .contains(&"inlined call to `mix`"),
+        "{:?}",
+        remarks
+    );
+}
+
+#[test]
+fn test_cold_loop_not_unrolled() {
+    let source = r#"
+for i = 1, 3 do
+    print(i)
+end
+"#;
+    let (lua, remarks) =
+        optimize_with_profile(source, OptimizationLevel::Aggressive, |_| profile_text(&[]));
+
+    assert_eq!(
+        remarks_of(&remarks, "loop-unrolling", RemarkKind::Missed),
+        vec!["loop never ran in the profile"],
+        "{:?}",
+        remarks
+    );
+    assert!(lua.contains("for i = 1, 3"), "{}", lua);
+}
+
+#[test]
+fn test_branch_layout_inverts_hot_else() {
+    let source = r#"
+hits = 0
+misses = 0
+for i = 1, 100 do
+    if i % 10 == 0 then
+        misses = misses + 1
+    else
+        hits = hits + 1
+    end
+end
+"#;
+    let (lua, remarks) = optimize_with_profile(source, OptimizationLevel::Moderate, |stmts| {
+        let (_, body) = numeric_for_body(&stmts[2]);
+        let branch = if_span(&body[0]);
+        profile_text(&[
+            (Counter::new(CounterKind::Branch(0), branch), 10),
+            (Counter::new(CounterKind::Branch(1), branch), 90),
+        ])
+    });
+
+    assert_eq!(
+        remarks_of(&remarks, "branch-layout", RemarkKind::Applied).len(),
+        1,
+        "{:?}",
+        remarks
+    );
+    assert!(lua.contains("~= 0"), "{}", lua);
+    let hits = lua.find("hits = hits").expect("hits arm removed");
+    let misses = lua.find("misses = misses").expect("misses arm removed");
+    assert!(hits < misses, "{}", lua);
+
+    let executor = LuaExecutor::new().unwrap();
+    let hits: i64 = executor.execute_and_get(&lua, "hits").unwrap();
+    let misses: i64 = executor.execute_and_get(&lua, "misses").unwrap();
+    assert_eq!((hits, misses), (90, 10));
+}
+
+#[test]
+fn test_branch_layout_reorders_literal_chain() {
+    let source = r#"
+function apply(op: string, a: number, b: number): number
+    if op == "add" then
+        return a + b
+    elseif op == "sub" then
+        return a - b
+    elseif op == "mul" then
+        return a * b
+    else
+        return 0
+    end
+end
+print(apply("mul", 2, 3))
+"#;
+    let (lua, remarks) = optimize_with_profile(source, OptimizationLevel::Moderate, |stmts| {
+        let branch = first_if_in_function(&stmts[0]);
+        profile_text(&[
+            (
+                Counter::new(CounterKind::Call, function_span(&stmts[0])),
+                56,
+            ),
+            (Counter::new(CounterKind::Branch(0), branch), 1),
+            (Counter::new(CounterKind::Branch(1), branch), 5),
+            (Counter::new(CounterKind::Branch(2), branch), 50),
+        ])
+    });
+
+    assert!(
+        remarks_of(&remarks, "branch-layout", RemarkKind::Applied)
+            .contains(&"reordered 3 arms by profile count, hottest ran 50 times"),
+        "{:?}",
+        remarks
+    );
+    let mul = lua.find("\"mul\"").expect("mul arm removed");
+    let sub = lua.find("\"sub\"").expect("sub arm removed");
+    let add = lua.find("\"add\"").expect("add arm removed");
+    assert!(mul < sub && sub < add, "{}", lua);
+}
+
+#[test]
+fn test_branch_layout_keeps_chains_with_side_effects() {
+    let source = r#"
+function pick(n: number): number
+    if n == 1 then
+        return 10
+    elseif check(n) then
+        return 20
+    end
+    return 0
+end
+"#;
+    let (_, remarks) = optimize_with_profile(source, OptimizationLevel::Moderate, |stmts| {
+        let branch = first_if_in_function(&stmts[0]);
+        profile_text(&[
+            (Counter::new(CounterKind::Branch(0), branch), 1),
+            (Counter::new(CounterKind::Branch(1), branch), 50),
+        ])
+    });
+
+    assert!(
+        remarks_of(&remarks, "branch-layout", RemarkKind::Applied).is_empty(),
+        "{:?}",
+        remarks
+    );
+}
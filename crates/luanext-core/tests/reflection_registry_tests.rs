@@ -0,0 +1,135 @@
+//! Execution tests for reflection metadata beyond classes
+//!
+//! These tests verify that:
+//! 1. `Reflect.typeof` reports `kind = "enum"` for rich enum values
+//! 2. `Reflect.getTypeInfo` describes enums, interfaces, aliases and generic instantiations
+//! 3. `Reflect.implements` checks values structurally against registered interfaces
+
+use bumpalo::Bump;
+use luanext_core::codegen::{CodeGeneratorBuilder, ReflectionMode};
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::MutableProgram;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::LuaExecutor;
+use std::sync::Arc;
+
+fn generate(source: &str) -> String {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+
+    let mut generator = CodeGeneratorBuilder::new(interner)
+        .reflection_mode(ReflectionMode::Full)
+        .build();
+    generator.generate(&MutableProgram::from_program(&program))
+}
+
+/// `Reflect` is only defined at the end of the module, so checks are appended after it
+fn with_checks(source: &str, check: &str) -> String {
+    format!("{}\n{}\n", generate(source), check)
+}
+
+#[test]
+fn test_enum_reflection() {
+    let source = r#"
+enum Planet {
+    mass: number
+
+    Mercury(3.3e23),
+    Earth(5.9e24),
+}
+"#;
+    let check = r#"
+kind = Reflect.typeof(Planet.Earth).kind
+is_planet = Reflect.isInstance(Planet.Earth, "Planet")
+local info = Reflect.getTypeInfo(Planet.Earth)
+variants = table.concat(info.variants, ",")
+field = info.fields[1].name .. ":" .. info.fields[1].type
+"#;
+    let lua = with_checks(source, check);
+    let executor = LuaExecutor::new().unwrap();
+    let kind: String = executor.execute_and_get(&lua, "kind").unwrap();
+    assert_eq!(kind, "enum");
+    let is_planet: bool = executor.execute_and_get(&lua, "is_planet").unwrap();
+    assert!(is_planet);
+    let variants: String = executor.execute_and_get(&lua, "variants").unwrap();
+    assert_eq!(variants, "Mercury,Earth");
+    let field: String = executor.execute_and_get(&lua, "field").unwrap();
+    assert_eq!(field, "mass:n");
+}
+
+#[test]
+fn test_interface_reflection() {
+    let source = r#"
+interface Named {
+    name: string
+}
+
+interface Shape extends Named {
+    tag?: string
+    area(): number
+}
+
+class Square implements Shape {
+    name: string = "square"
+    area(): number {
+        return 4
+    }
+}
+"#;
+    let check = r#"
+square_is_shape = Reflect.implements(Square.new(), "Shape")
+table_is_shape = Reflect.implements({ area = function() return 1 end }, "Shape")
+named_is_shape = Reflect.implements({ name = "n" }, "Named")
+method = Reflect.getTypeInfo("Shape").methods[1].name
+"#;
+    let lua = with_checks(source, check);
+    let executor = LuaExecutor::new().unwrap();
+    let square_is_shape: bool = executor.execute_and_get(&lua, "square_is_shape").unwrap();
+    assert!(square_is_shape);
+    // Missing the `name` required by the extended interface
+    let table_is_shape: bool = executor.execute_and_get(&lua, "table_is_shape").unwrap();
+    assert!(!table_is_shape);
+    let named_is_shape: bool = executor.execute_and_get(&lua, "named_is_shape").unwrap();
+    assert!(named_is_shape);
+    let method: String = executor.execute_and_get(&lua, "method").unwrap();
+    assert_eq!(method, "area");
+}
+
+#[test]
+fn test_alias_and_generic_reflection() {
+    let source = r#"
+class Box<T> {
+    value: T
+}
+
+class Shelf {
+    boxes: Box<string>[]
+}
+
+type Id = string | number
+"#;
+    let check = r#"
+alias_type = Reflect.getTypeInfo("Id").type
+generic_base = Reflect.getTypeInfo("Box<s>").base
+generic_class = Reflect.forName("Box<s>") == Box
+class_kind = Reflect.getTypeInfo("Box").kind
+"#;
+    let lua = with_checks(source, check);
+    let executor = LuaExecutor::new().unwrap();
+    let alias_type: String = executor.execute_and_get(&lua, "alias_type").unwrap();
+    assert_eq!(alias_type, "s|n");
+    let generic_base: String = executor.execute_and_get(&lua, "generic_base").unwrap();
+    assert_eq!(generic_base, "Box");
+    let generic_class: bool = executor.execute_and_get(&lua, "generic_class").unwrap();
+    assert!(generic_class);
+    let class_kind: String = executor.execute_and_get(&lua, "class_kind").unwrap();
+    assert_eq!(class_kind, "class");
+}
@@ -288,3 +288,20 @@ fn test_sra_nested_if_block() {
         "Should compile with field in if condition"
     );
 }
+
+#[test]
+fn test_sra_field_store_runs_on_lua54() {
+    let source = r#"
+        const point = { x: 1, y: 2 }
+        point.x = 5
+        result = point.x + point.y
+    "#;
+
+    let output = compile_o3(source).unwrap();
+
+    // Stores assign the scalars, so they cannot be `<const>` locals
+    assert!(output.contains("local point__x = 1"), "{}", output);
+    let executor = luanext_test_helpers::LuaExecutor::new().unwrap();
+    let result: i64 = executor.execute_and_get(&output, "result").unwrap();
+    assert_eq!(result, 7);
+}
@@ -0,0 +1,221 @@
+//! Tests for optimizing for size
+//!
+//! These tests verify that:
+//! 1. `optimizer.goal: size` swaps the code-growing passes for the size passes
+//! 2. String constant sharing, code outlining and identifier minification
+//!    shrink the output without changing what it computes
+//! 3. The size report credits each pass with the bytes it saves
+
+use bumpalo::Bump;
+use luanext_core::codegen::{CodeGenerator, LuaTarget};
+use luanext_core::config::OptimizationLevel;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::optimizer::pipeline::{OptimizationGoal, PassPipelineConfig};
+use luanext_core::optimizer::remarks::RemarkKind;
+use luanext_core::optimizer::{size_report, Optimizer};
+use luanext_core::MutableProgram;
+use luanext_parser::lexer::Lexer;
+use luanext_parser::parser::Parser;
+use luanext_parser::string_interner::StringInterner;
+use luanext_test_helpers::LuaExecutor;
+use std::sync::Arc;
+
+const REPEATED_STRINGS: &str = r#"
+    function main(): string
+        local parts = {}
+        parts[1] = "connection refused by the remote host"
+        parts[2] = "connection refused by the remote host"
+        parts[3] = "connection refused by the remote host"
+        return parts[1] .. parts[2] .. parts[3]
+    end
+"#;
+
+const REPEATED_STATEMENTS: &str = r#"
+    function main(): number
+        local log = {}
+        log[#log + 1] = "begin transaction"
+        log[#log + 1] = "write checkpoint"
+        print(#log)
+        log[#log + 1] = "begin transaction"
+        log[#log + 1] = "write checkpoint"
+        print(#log)
+        log[#log + 1] = "begin transaction"
+        log[#log + 1] = "write checkpoint"
+        return #log
+    end
+"#;
+
+const LONG_NAMES: &str = r#"
+    function main(): number
+        local firstOperand = 40
+        local secondOperand = 2
+        local combine = (leftValue, rightValue) => leftValue + rightValue
+        return combine(firstOperand, secondOperand)
+    end
+"#;
+
+/// Optimize `source` for size and return the generated Lua and the passes
+/// that reported an applied remark
+fn optimize_for_size(source: &str) -> (String, Vec<&'static str>) {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler.clone(), &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+
+    let mut program = MutableProgram::from_program(&program);
+    let mut optimizer = Optimizer::new(OptimizationLevel::Moderate, handler, interner.clone());
+    optimizer.set_goal(OptimizationGoal::Size);
+    optimizer.enable_remarks();
+    optimizer
+        .optimize(&mut program, &arena)
+        .expect("Optimization failed");
+    let applied = optimizer
+        .take_remarks()
+        .into_iter()
+        .filter(|r| r.kind == RemarkKind::Applied)
+        .map(|r| r.pass)
+        .collect();
+
+    let lua = CodeGenerator::new(interner).generate(&program);
+    (lua, applied)
+}
+
+/// Run the generated Lua and return `tostring(main())`
+fn run_main(lua: &str) -> String {
+    let executor = LuaExecutor::new().unwrap();
+    executor
+        .execute_and_get(&format!("{}\nresult = tostring(main())", lua), "result")
+        .unwrap_or_else(|e| panic!("{}\n{}", e, lua))
+}
+
+#[test]
+fn test_size_goal_pipeline() {
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let interner = Arc::new(StringInterner::new());
+    let mut optimizer = Optimizer::new(OptimizationLevel::Moderate, handler, interner);
+    optimizer.set_goal(OptimizationGoal::Size);
+    let passes = optimizer.pass_names();
+
+    for size_pass in [
+        "code-outlining",
+        "string-constant-sharing",
+        "identifier-minification",
+    ] {
+        assert!(passes.contains(&size_pass), "{:?}", passes);
+    }
+    for speed_pass in [
+        "function-inlining",
+        "method-to-function-conversion",
+        "string-concat-optimization",
+        "global-localization",
+    ] {
+        assert!(!passes.contains(&speed_pass), "{:?}", passes);
+    }
+
+    optimizer.set_goal(OptimizationGoal::Speed);
+    assert!(!optimizer.pass_names().contains(&"code-outlining"));
+    assert!(optimizer.pass_names().contains(&"function-inlining"));
+}
+
+#[test]
+fn test_goal_parsed_from_yaml() {
+    let goal = OptimizationGoal::from_config_str("optimizer:\n  goal: size\n").unwrap();
+    assert_eq!(goal, OptimizationGoal::Size);
+
+    let absent = OptimizationGoal::from_config_str("compilerOptions:\n  target: \"5.4\"\n");
+    assert_eq!(absent.unwrap(), OptimizationGoal::Speed);
+
+    let err = OptimizationGoal::from_config_str("optimizer:\n  goal: tiny\n").unwrap_err();
+    assert!(err.contains("optimizer.goal"), "{}", err);
+}
+
+#[test]
+fn test_string_constants_shared() {
+    let (lua, applied) = optimize_for_size(REPEATED_STRINGS);
+
+    assert!(
+        applied.contains(&"string-constant-sharing"),
+        "{:?}",
+        applied
+    );
+    assert_eq!(
+        lua.matches("connection refused by the remote host").count(),
+        1,
+        "{}",
+        lua
+    );
+    assert_eq!(
+        run_main(&lua),
+        "connection refused by the remote host".repeat(3)
+    );
+}
+
+#[test]
+fn test_repeated_statements_outlined() {
+    let (lua, applied) = optimize_for_size(REPEATED_STATEMENTS);
+
+    assert!(applied.contains(&"code-outlining"), "{:?}", applied);
+    assert_eq!(lua.matches("write checkpoint").count(), 1, "{}", lua);
+    assert_eq!(run_main(&lua), "6");
+}
+
+#[test]
+fn test_locals_minified() {
+    let (lua, applied) = optimize_for_size(LONG_NAMES);
+
+    assert!(
+        applied.contains(&"identifier-minification"),
+        "{:?}",
+        applied
+    );
+    for name in ["firstOperand", "secondOperand", "leftValue", "rightValue"] {
+        assert!(!lua.contains(name), "`{}` left in output:\n{}", name, lua);
+    }
+    // Module-level names are kept
+    assert!(lua.contains("function main"), "{}", lua);
+    assert_eq!(run_main(&lua), "42");
+}
+
+#[test]
+fn test_size_report_credits_passes() {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(REPEATED_STRINGS, handler.clone(), &interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler.clone(), &interner, &common, &arena);
+    let program = parser.parse().expect("Parsing failed");
+    let program = MutableProgram::from_program(&program);
+
+    let report = size_report::measure(
+        &program,
+        &arena,
+        OptimizationLevel::Moderate,
+        OptimizationGoal::Size,
+        LuaTarget::default(),
+        &PassPipelineConfig::default(),
+        handler,
+        interner.clone(),
+        &mut |program| CodeGenerator::new(interner.clone()).generate(program),
+    )
+    .expect("Size report failed");
+
+    assert!(
+        report.optimized_bytes < report.original_bytes,
+        "{:?}",
+        report
+    );
+    let sharing = report
+        .passes
+        .iter()
+        .find(|p| p.pass == "string-constant-sharing")
+        .expect("string-constant-sharing not measured");
+    assert!(sharing.bytes_saved > 0, "{:?}", report);
+    let total: i64 = report.passes.iter().map(|p| p.bytes_saved).sum();
+    assert_eq!(total, report.bytes_saved());
+}
@@ -0,0 +1,233 @@
+//! Integration tests for generating `.d.luax` declarations from LuaLS annotations
+//!
+//! These tests verify that:
+//! 1. `@class`/`@field` blocks become declared interfaces
+//! 2. `@param`/`@return`/`@generic`/`@overload` become function signatures
+//! 3. `@alias` and `@enum` become declared types
+//! 4. Module tables become exports, global tables become namespaces
+//! 5. Malformed annotations are reported and fall back to `any`
+
+use luanext_core::typings::generate;
+use luanext_test_helpers::compile::type_check;
+
+#[test]
+fn test_class_to_interface() {
+    let source = r#"---@class Point
+---@field x number The x coordinate
+---@field y integer
+---@field label? string
+---@field private cache table
+---@field [string] any
+local Point = {}
+Point.__index = Point
+
+---@param other Point
+---@return number
+function Point:distance(other) return 0 end
+
+---@return self
+function Point:clone() return self end
+
+return Point
+"#;
+    let typings = generate(source);
+    let expected = r#"export declare interface Point {
+    x: number
+    y: number
+    label?: string
+    [key: string]: any
+    distance(other: Point): number
+    clone(): Point
+}"#;
+    assert!(typings.source.contains(expected), "{}", typings.source);
+    assert!(!typings.source.contains("cache"));
+    assert!(typings.warnings.is_empty());
+}
+
+#[test]
+fn test_class_inheritance_and_statics() {
+    let source = r#"---@class Animal
+---@field name string
+Animal = {}
+
+---@param name string
+---@return Animal
+function Animal.new(name) end
+
+---@class Dog: Animal
+---@field breed string
+"#;
+    let typings = generate(source);
+    assert!(typings
+        .source
+        .contains("declare interface Dog extends Animal {\n    breed: string\n}"));
+    assert!(typings
+        .source
+        .contains("declare namespace Animal {\n    function new(name: string): Animal\n}"));
+    assert!(!typings.source.contains("export "));
+}
+
+#[test]
+fn test_module_functions_become_exports() {
+    let source = r#"local M = {}
+
+M.version = "1.2"
+
+---@generic T
+---@param list T[]
+---@param fn fun(item: T): boolean
+---@return T[] filtered
+---@return integer count
+function M.filter(list, fn) end
+
+---@param ... string
+function M.join(...) end
+
+function M.untyped(a, b) end
+
+---@param x number
+local function helper(x) end
+
+return M
+"#;
+    let typings = generate(source);
+    let source = &typings.source;
+    assert!(source.contains("export declare const version: string"));
+    assert!(source.contains(
+        "export declare function filter<T>(list: T[], fn: (item: T) => boolean): T[], number"
+    ));
+    assert!(source.contains("export declare function join(...: string): void"));
+    // No annotations at all: parameters and result are unknown
+    assert!(source.contains("export declare function untyped(a: any, b: any): any"));
+    assert!(!source.contains("helper"));
+}
+
+#[test]
+fn test_overloads_and_optional_params() {
+    let source = r#"local M = {}
+
+---@overload fun(name: string): string?
+---@param name string
+---@param fallback? string
+---@return string
+function M.lookup(name, fallback) end
+
+return M
+"#;
+    let typings = generate(source);
+    assert!(typings
+        .source
+        .contains("export declare function lookup(name: string): string?\n"));
+    assert!(typings
+        .source
+        .contains("export declare function lookup(name: string, fallback?: string): string\n"));
+}
+
+#[test]
+fn test_type_conversions() {
+    let source = r#"local M = {}
+
+---@type table<string, integer>
+M.counts = {}
+
+---@type (fun(x: number): string)[]
+M.handlers = {}
+
+---@type { id: integer, tags?: string[] }
+M.record = {}
+
+---@param cb function
+---@param handle userdata
+function M.bind(cb, handle) end
+
+return M
+"#;
+    let typings = generate(source);
+    let source = &typings.source;
+    assert!(source.contains("export declare const counts: { [key: string]: number }"));
+    assert!(source.contains("export declare const handlers: ((x: number) => string)[]"));
+    assert!(source.contains("export declare const record: { id: number, tags?: string[] }"));
+    assert!(
+        source.contains("export declare function bind(cb: (...: any) => any, handle: any): void")
+    );
+}
+
+#[test]
+fn test_alias_and_enum() {
+    let source = r#"---@alias Mode "r" | "w"
+
+---@alias Level
+---| "debug"
+---| "info" # informational
+
+---@enum Dir
+Dir = { Up = "up", Down = "down" }
+"#;
+    let typings = generate(source);
+    let source = &typings.source;
+    assert!(source.contains("declare type Mode = \"r\" | \"w\""));
+    assert!(source.contains("declare type Level = \"debug\" | \"info\""));
+    assert!(source.contains(
+        "declare type Dir = \"up\" | \"down\"\ndeclare const Dir: { Up: Dir, Down: Dir }"
+    ));
+}
+
+#[test]
+fn test_global_tables_become_namespaces() {
+    let source = r#"---@param s string
+---@return string
+function string.trim(s) end
+
+---@param msg string
+function log(msg) end
+
+VERSION = "1"
+"#;
+    let typings = generate(source);
+    let source = &typings.source;
+    assert!(source.contains("declare namespace string {\n    function trim(s: string): string\n}"));
+    assert!(source.contains("declare function log(msg: string): void"));
+    assert!(source.contains("declare const VERSION: string"));
+}
+
+#[test]
+fn test_malformed_annotation_is_reported() {
+    let source = "local M = {}\n\n---@param x ??bad\nfunction M.broken(x) end\n\nreturn M\n";
+    let typings = generate(source);
+    assert!(typings
+        .source
+        .contains("export declare function broken(x: any): void"));
+    assert_eq!(typings.warnings.len(), 1);
+    assert_eq!(typings.warnings[0].line, 3);
+    assert!(typings.warnings[0].message.contains("using `any`"));
+}
+
+#[test]
+fn test_detached_comment_is_ignored() {
+    let source = "---@param x string\n\nfunction f(x) end\n";
+    let typings = generate(source);
+    assert!(typings.source.contains("declare function f(x: any): any"));
+}
+
+#[test]
+fn test_generated_declarations_type_check() {
+    let source = r#"---@class Vec
+---@field x number
+---@field y number
+Vec = {}
+
+---@param x number
+---@param y number
+---@return Vec
+function Vec.new(x, y) end
+
+---@param msg string
+function log(msg) end
+"#;
+    let typings = generate(source);
+    let program = format!(
+        "{}\nconst v: Vec = Vec.new(1, 2)\nlog(\"sum\")\nconst total: number = v.x + v.y\n",
+        typings.source
+    );
+    type_check(&program).unwrap();
+}
@@ -0,0 +1,14 @@
+//! Integer support for targets without an integer subtype.
+//!
+//! Lua 5.1, 5.2 and LuaJIT have only floats, so `math.tointeger` is missing.
+//! The polyfill returns numbers with an integral value in the 64-bit range
+//! unchanged and `nil` for anything else, matching Lua 5.4.
+
+pub const TOINTEGER_POLYFILL: &str = r#"if math.tointeger == nil then
+    math.tointeger = function(x)
+        if type(x) == "number" and x == math.floor(x) and x >= -2^63 and x < 2^63 then
+            return x
+        end
+        return nil
+    end
+end"#;
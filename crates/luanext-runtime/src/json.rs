@@ -0,0 +1,431 @@
+//! JSON runtime support for LuaNext (`@std/json`).
+//!
+//! Encoding and decoding of `@serializable` classes is driven by the
+//! `__ownFields` metadata the compiler emits for them. Pure Lua 5.1 so it
+//! runs unchanged on every target.
+
+pub const JSON_MODULE: &str = r#"-- ============================================================
+-- JSON Runtime Module (@std/json)
+-- ============================================================
+local __LuaNextJSON = rawget(_G, "__LuaNextJSON") or (function()
+    local JSON = {}
+
+    -- @serializable classes, looked up by __typeName when decoding nested fields
+    local serializables = {}
+    local classCache = {}
+
+    function JSON.serializable(target)
+        if type(target) == "table" then
+            rawset(target, "__serializable", true)
+            serializables[#serializables + 1] = target
+        end
+        return target
+    end
+
+    local function findClass(name)
+        local cls = classCache[name]
+        if cls then return cls end
+        for _, candidate in ipairs(serializables) do
+            if rawget(candidate, "__typeName") == name then
+                classCache[name] = candidate
+                return candidate
+            end
+        end
+        return nil
+    end
+
+    -- Instance fields of a serializable class (static fields carry flag 16)
+    local function instanceFields(cls)
+        local fields = {}
+        for _, f in ipairs(cls._buildAllFields()) do
+            if math.floor(f._flags / 16) % 2 == 0 and f.type ~= "f" then
+                fields[#fields + 1] = f
+            end
+        end
+        return fields
+    end
+
+    -- Split a type code on top-level `|`, ignoring bars inside `[...]` and `<...>`
+    local function splitUnion(code)
+        local parts, depth, start = {}, 0, 1
+        for i = 1, #code do
+            local c = code:sub(i, i)
+            if c == "[" or c == "<" then
+                depth = depth + 1
+            elseif c == "]" or c == ">" then
+                depth = depth - 1
+            elseif c == "|" and depth == 0 then
+                parts[#parts + 1] = code:sub(start, i - 1)
+                start = i + 1
+            end
+        end
+        parts[#parts + 1] = code:sub(start)
+        return parts
+    end
+
+    -- ========================================================
+    -- Encoding
+    -- ========================================================
+
+    local escapes = {
+        ['"'] = '\\"', ["\\"] = "\\\\", ["\b"] = "\\b", ["\f"] = "\\f",
+        ["\n"] = "\\n", ["\r"] = "\\r", ["\t"] = "\\t",
+    }
+
+    local function encodeString(s)
+        return '"' .. s:gsub('[%c"\\]', function(c)
+            return escapes[c] or string.format("\\u%04x", c:byte())
+        end) .. '"'
+    end
+
+    local function encodeNumber(n, path)
+        if n ~= n or n == math.huge or n == -math.huge then
+            error("JSON encode: cannot encode " .. tostring(n) .. " at " .. path, 0)
+        end
+        if n % 1 == 0 and n > -2^53 and n < 2^53 then
+            return string.format("%.0f", n)
+        end
+        -- Shortest precision that round-trips
+        local text
+        for precision = 15, 17 do
+            text = string.format("%." .. precision .. "g", n)
+            if tonumber(text) == n then break end
+        end
+        return text
+    end
+
+    local function isArray(t)
+        local count = 0
+        for k in pairs(t) do
+            if type(k) ~= "number" or k < 1 or k % 1 ~= 0 then
+                return false
+            end
+            count = count + 1
+        end
+        return count == #t
+    end
+
+    local encodeValue
+
+    local function encodeInstance(obj, cls, path, seen)
+        if not rawget(cls, "__serializable") then
+            error("JSON encode: class '" .. tostring(cls.__typeName)
+                .. "' is not @serializable at " .. path, 0)
+        end
+        local parts = {}
+        for _, f in ipairs(instanceFields(cls)) do
+            local value = obj[f.name]
+            if value ~= nil then
+                parts[#parts + 1] = encodeString(f.name) .. ":"
+                    .. encodeValue(value, f.type, path .. "." .. f.name, seen)
+            end
+        end
+        return "{" .. table.concat(parts, ",") .. "}"
+    end
+
+    encodeValue = function(value, code, path, seen)
+        local kind = type(value)
+        if value == nil then
+            return "null"
+        elseif kind == "boolean" then
+            return tostring(value)
+        elseif kind == "number" then
+            return encodeNumber(value, path)
+        elseif kind == "string" then
+            return encodeString(value)
+        elseif kind ~= "table" then
+            error("JSON encode: cannot encode " .. kind .. " at " .. path, 0)
+        end
+
+        if seen[value] then
+            error("JSON encode: circular reference at " .. path, 0)
+        end
+        seen[value] = true
+
+        local result
+        local cls = getmetatable(value)
+        if type(cls) == "table" and rawget(cls, "__typeName") then
+            result = encodeInstance(value, cls, path, seen)
+        elseif (code and code:sub(1, 1) == "[") or (next(value) ~= nil and isArray(value)) then
+            local element = code and code:match("^%[(.*)%]$")
+            local parts = {}
+            for i = 1, #value do
+                parts[i] = encodeValue(value[i], element, path .. "[" .. i .. "]", seen)
+            end
+            result = "[" .. table.concat(parts, ",") .. "]"
+        else
+            local keys = {}
+            for k in pairs(value) do
+                if type(k) ~= "string" then
+                    error("JSON encode: object keys must be strings at " .. path, 0)
+                end
+                keys[#keys + 1] = k
+            end
+            table.sort(keys)
+            local parts = {}
+            for i, k in ipairs(keys) do
+                parts[i] = encodeString(k) .. ":" .. encodeValue(value[k], nil, path .. "." .. k, seen)
+            end
+            result = "{" .. table.concat(parts, ",") .. "}"
+        end
+
+        seen[value] = nil
+        return result
+    end
+
+    function JSON.encode(value)
+        return encodeValue(value, nil, "$", {})
+    end
+
+    -- ========================================================
+    -- Parsing
+    -- ========================================================
+
+    local function parseError(text, pos, message)
+        local line = 1
+        for _ in text:sub(1, pos - 1):gmatch("\n") do
+            line = line + 1
+        end
+        error("JSON decode: " .. message .. " at line " .. line, 0)
+    end
+
+    local function skipWhitespace(text, pos)
+        return text:find("[^ \t\r\n]", pos) or #text + 1
+    end
+
+    local function utf8Char(code)
+        if code < 0x80 then
+            return string.char(code)
+        elseif code < 0x800 then
+            return string.char(0xC0 + math.floor(code / 0x40), 0x80 + code % 0x40)
+        elseif code < 0x10000 then
+            return string.char(0xE0 + math.floor(code / 0x1000),
+                0x80 + math.floor(code / 0x40) % 0x40, 0x80 + code % 0x40)
+        end
+        return string.char(0xF0 + math.floor(code / 0x40000),
+            0x80 + math.floor(code / 0x1000) % 0x40,
+            0x80 + math.floor(code / 0x40) % 0x40, 0x80 + code % 0x40)
+    end
+
+    local unescapes = {
+        ['"'] = '"', ["\\"] = "\\", ["/"] = "/", b = "\b",
+        f = "\f", n = "\n", r = "\r", t = "\t",
+    }
+
+    local function parseString(text, pos)
+        local parts = {}
+        local i = pos + 1
+        while true do
+            local stop = text:find('["\\]', i)
+            if not stop then
+                parseError(text, pos, "unterminated string")
+            end
+            parts[#parts + 1] = text:sub(i, stop - 1)
+            if text:sub(stop, stop) == '"' then
+                return table.concat(parts), stop + 1
+            end
+            local esc = text:sub(stop + 1, stop + 1)
+            if esc == "u" then
+                local code = tonumber(text:sub(stop + 2, stop + 5), 16)
+                if not code then
+                    parseError(text, stop, "invalid unicode escape")
+                end
+                i = stop + 6
+                if code >= 0xD800 and code <= 0xDBFF and text:sub(i, i + 1) == "\\u" then
+                    local low = tonumber(text:sub(i + 2, i + 5), 16)
+                    if low and low >= 0xDC00 and low <= 0xDFFF then
+                        code = 0x10000 + (code - 0xD800) * 0x400 + (low - 0xDC00)
+                        i = i + 6
+                    end
+                end
+                parts[#parts + 1] = utf8Char(code)
+            elseif unescapes[esc] then
+                parts[#parts + 1] = unescapes[esc]
+                i = stop + 2
+            else
+                parseError(text, stop, "invalid escape '\\" .. esc .. "'")
+            end
+        end
+    end
+
+    local literals = { ["true"] = true, ["false"] = false }
+
+    local parseValue
+
+    local function parseArray(text, pos)
+        local result, n = {}, 0
+        pos = skipWhitespace(text, pos + 1)
+        if text:sub(pos, pos) == "]" then
+            return result, pos + 1
+        end
+        while true do
+            local value
+            value, pos = parseValue(text, pos)
+            n = n + 1
+            result[n] = value
+            pos = skipWhitespace(text, pos)
+            local c = text:sub(pos, pos)
+            if c == "]" then
+                return result, pos + 1
+            elseif c ~= "," then
+                parseError(text, pos, "expected ',' or ']'")
+            end
+            pos = skipWhitespace(text, pos + 1)
+        end
+    end
+
+    local function parseObject(text, pos)
+        local result = {}
+        pos = skipWhitespace(text, pos + 1)
+        if text:sub(pos, pos) == "}" then
+            return result, pos + 1
+        end
+        while true do
+            if text:sub(pos, pos) ~= '"' then
+                parseError(text, pos, "expected string key")
+            end
+            local key
+            key, pos = parseString(text, pos)
+            pos = skipWhitespace(text, pos)
+            if text:sub(pos, pos) ~= ":" then
+                parseError(text, pos, "expected ':'")
+            end
+            local value
+            value, pos = parseValue(text, skipWhitespace(text, pos + 1))
+            result[key] = value
+            pos = skipWhitespace(text, pos)
+            local c = text:sub(pos, pos)
+            if c == "}" then
+                return result, pos + 1
+            elseif c ~= "," then
+                parseError(text, pos, "expected ',' or '}'")
+            end
+            pos = skipWhitespace(text, pos + 1)
+        end
+    end
+
+    parseValue = function(text, pos)
+        local c = text:sub(pos, pos)
+        if c == "{" then
+            return parseObject(text, pos)
+        elseif c == "[" then
+            return parseArray(text, pos)
+        elseif c == '"' then
+            return parseString(text, pos)
+        end
+        local word = text:match("^%a+", pos)
+        if word == "null" then
+            return nil, pos + 4
+        elseif word and literals[word] ~= nil then
+            return literals[word], pos + #word
+        end
+        local number = text:match("^-?%d+%.?%d*[eE]?[-+]?%d*", pos)
+        if number and tonumber(number) then
+            return tonumber(number), pos + #number
+        end
+        parseError(text, pos, "unexpected character '" .. c .. "'")
+    end
+
+    -- ========================================================
+    -- Validation against field metadata
+    -- ========================================================
+
+    local primitives = { n = "number", s = "string", b = "boolean", t = "table" }
+
+    local hydrate
+
+    -- Returns ok, converted value (or an error message when not ok)
+    local function convert(value, code, path)
+        if code:sub(1, 1) == "?" then
+            if value == nil then return true, nil end
+            return convert(value, code:sub(2), path)
+        end
+
+        local alternatives = splitUnion(code)
+        if #alternatives > 1 then
+            for _, alt in ipairs(alternatives) do
+                local ok, converted = convert(value, alt, path)
+                if ok then return true, converted end
+            end
+            return false, "expected " .. code .. " at " .. path .. ", got " .. type(value)
+        end
+
+        if code == "o" then
+            return true, value
+        elseif code == "v" then
+            if value == nil then return true, nil end
+            return false, "expected nil at " .. path .. ", got " .. type(value)
+        elseif primitives[code] then
+            if type(value) == primitives[code] then return true, value end
+            if value == nil then return false, "missing required field " .. path end
+            return false, "expected " .. primitives[code] .. " at " .. path .. ", got " .. type(value)
+        end
+
+        local element = code:match("^%[(.*)%]$")
+        if element then
+            if type(value) ~= "table" then
+                return false, "expected array at " .. path .. ", got " .. type(value)
+            end
+            local result = {}
+            for i = 1, #value do
+                local ok, converted = convert(value[i], element, path .. "[" .. i .. "]")
+                if not ok then return false, converted end
+                result[i] = converted
+            end
+            return true, result
+        end
+
+        -- Generic instantiations (`Box<n>`) decode as their base class
+        local cls = findClass((code:gsub("<.*$", "")))
+        if not cls then
+            -- Interfaces, aliases and type parameters carry no runtime metadata
+            return true, value
+        end
+        if type(value) ~= "table" then
+            if value == nil then return false, "missing required field " .. path end
+            return false, "expected " .. code .. " at " .. path .. ", got " .. type(value)
+        end
+        return pcall(hydrate, value, cls, path)
+    end
+
+    -- Build an instance of `cls` from decoded data without running its constructor
+    hydrate = function(data, cls, path)
+        local instance = {}
+        for _, f in ipairs(instanceFields(cls)) do
+            local ok, converted = convert(data[f.name], f.type, path .. "." .. f.name)
+            if not ok then error(converted, 0) end
+            instance[f.name] = converted
+        end
+        return setmetatable(instance, cls)
+    end
+
+    function JSON.decode(text, cls)
+        if type(text) ~= "string" then
+            error("JSON decode: expected string, got " .. type(text), 0)
+        end
+        local value, pos = parseValue(text, skipWhitespace(text, 1))
+        pos = skipWhitespace(text, pos)
+        if pos <= #text then
+            parseError(text, pos, "unexpected trailing data")
+        end
+        if cls == nil then
+            return value
+        end
+        if not rawget(cls, "__serializable") then
+            error("JSON decode: class '" .. tostring(rawget(cls, "__typeName"))
+                .. "' is not @serializable", 0)
+        end
+        if type(value) ~= "table" then
+            error("JSON decode: expected object at $, got " .. type(value), 0)
+        end
+        local ok, result = pcall(hydrate, value, cls, "$")
+        if not ok then
+            error("JSON decode: " .. result, 0)
+        end
+        return result
+    end
+
+    rawset(_G, "__LuaNextJSON", JSON)
+    return JSON
+end)()
+"#;
@@ -5,5 +5,8 @@ pub mod bitwise;
 pub mod class;
 pub mod decorator;
 pub mod enum_rt;
+pub mod integer;
+pub mod json;
 pub mod module;
+pub mod profile;
 pub mod reflection;
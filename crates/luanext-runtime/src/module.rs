@@ -19,3 +19,39 @@ local function __require(name)
     return exports
 end
 "#;
+
+/// Chunk loader for code-split bundles.
+///
+/// Must be emitted after `MODULE_PRELUDE`. The generator fills `__chunk_map`
+/// with the chunks each dynamically imported module needs; every chunk file
+/// returns a function that registers its modules into `__modules`.
+pub const CHUNK_LOADER_PRELUDE: &str = r#"-- Chunk registry for dynamic imports
+local __chunk_map = {}
+local __loaded_chunks = {}
+local __import
+
+local function __load_chunk(name)
+    if __loaded_chunks[name] then
+        return
+    end
+
+    local register = require(name)
+    if type(register) ~= "function" then
+        error("Invalid chunk: " .. name)
+    end
+
+    register(__modules, __require, __import)
+    __loaded_chunks[name] = true
+end
+
+-- Load the chunks a module lives in, then require it
+__import = function(name)
+    local chunks = __chunk_map[name]
+    if chunks then
+        for i = 1, #chunks do
+            __load_chunk(chunks[i])
+        end
+    end
+    return __require(name)
+end
+"#;
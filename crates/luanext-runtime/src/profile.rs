@@ -0,0 +1,75 @@
+//! Profile counter runtime for `--profile-generate` builds.
+//!
+//! Instrumented modules call `__LuaNextProfile.counter(module, path)` once and
+//! bump the returned counter at function entries, loop bodies and branch
+//! arms. The counts are written to the profile file when the Lua state
+//! closes or the program calls `os.exit`. Pure Lua 5.1 so it runs unchanged
+//! on every target.
+
+pub const PROFILE_RUNTIME: &str = r#"-- ============================================================
+-- Profile Runtime (--profile-generate)
+-- ============================================================
+local __LuaNextProfile = rawget(_G, "__LuaNextProfile") or (function()
+    local Profile = {}
+
+    -- Counts per module, in the order the modules were loaded
+    local modules = {}
+    local order = {}
+
+    function Profile.counter(module, path)
+        Profile.path = Profile.path or path
+        local counts = modules[module]
+        if not counts then
+            counts = {}
+            modules[module] = counts
+            order[#order + 1] = module
+        end
+        return function(key)
+            counts[key] = (counts[key] or 0) + 1
+        end
+    end
+
+    function Profile.write()
+        local path = os.getenv("LUANEXT_PROFILE_FILE") or Profile.path
+        local file, err = io.open(path, "w")
+        if not file then
+            io.stderr:write("luanext: cannot write profile: " .. tostring(err) .. "\n")
+            return
+        end
+        file:write("# luanext profile v1\n")
+        for _, module in ipairs(order) do
+            file:write("module\t", module, "\n")
+            local counts = modules[module]
+            local keys = {}
+            for key in pairs(counts) do
+                keys[#keys + 1] = key
+            end
+            table.sort(keys)
+            for _, key in ipairs(keys) do
+                file:write(key, "\t", string.format("%d", counts[key]), "\n")
+            end
+        end
+        file:close()
+    end
+
+    -- Write the profile when the Lua state closes (newproxy on 5.1/LuaJIT)
+    local sentinel
+    if newproxy then
+        sentinel = newproxy(true)
+        getmetatable(sentinel).__gc = function() Profile.write() end
+    else
+        sentinel = setmetatable({}, { __gc = function() Profile.write() end })
+    end
+    Profile.sentinel = sentinel
+
+    -- os.exit skips finalizers unless asked to close the state
+    local exit = os.exit
+    os.exit = function(...)
+        Profile.write()
+        return exit(...)
+    end
+
+    rawset(_G, "__LuaNextProfile", Profile)
+    return Profile
+end)()
+"#;
@@ -18,15 +18,56 @@ end
 
 function Reflect.typeof(obj)
     if type(obj) == "table" and obj.__typeName then
+        local info = __TypeInfo[obj.__typeId]
         return {
             id = obj.__typeId,
             name = obj.__typeName,
-            kind = "class"
+            kind = info and info.kind or "class"
         }
     end
     return nil
 end
 
+-- Full descriptor of a registered type, looked up by name or by value.
+-- Covers classes, enums, interfaces, type aliases and generic instantiations.
+function Reflect.getTypeInfo(target)
+    local typeId
+    if type(target) == "string" then
+        typeId = __TypeRegistry[target]
+    elseif type(target) == "table" then
+        typeId = target.__typeId
+    end
+    if not typeId then return nil end
+    return __TypeInfo[typeId]
+end
+
+-- Structural check of obj against a registered interface and the interfaces it extends
+function Reflect.implements(obj, interfaceName)
+    if type(obj) ~= "table" then
+        return false
+    end
+    local info = Reflect.getTypeInfo(interfaceName)
+    if not info or info.kind ~= "interface" then
+        return false
+    end
+    for _, member in ipairs(info.members) do
+        if obj[member.name] == nil and not member.optional then
+            return false
+        end
+    end
+    for _, method in ipairs(info.methods) do
+        if type(obj[method.name]) ~= "function" then
+            return false
+        end
+    end
+    for _, parent in ipairs(info.extends) do
+        if not Reflect.implements(obj, parent) then
+            return false
+        end
+    end
+    return true
+end
+
 function Reflect.getFields(obj)
     if type(obj) == "table" and obj._buildAllFields then
         return obj:_buildAllFields()
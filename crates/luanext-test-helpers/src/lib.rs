@@ -7,6 +7,7 @@ pub mod compile;
 pub mod fixtures;
 pub mod lua_executor;
 pub mod mocks;
+pub mod optimize;
 
 // Re-export commonly used items
 pub use lua_executor::{LuaExecutor, LuaValueExt};
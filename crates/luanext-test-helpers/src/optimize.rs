@@ -0,0 +1,142 @@
+//! Optimizer test harness
+//!
+//! Parses source straight into a `MutableProgram` (no type checking), runs
+//! the optimizer with remarks and the AST verifier enabled and generates
+//! Lua, so pass tests can check both the output and what each pass reported.
+
+use bumpalo::Bump;
+use luanext_core::codegen::{CodeGenerator, LuaTarget};
+use luanext_core::config::OptimizationLevel;
+use luanext_core::diagnostics::CollectingDiagnosticHandler;
+use luanext_core::optimizer::pipeline::{PassPipelineConfig, PASSES};
+use luanext_core::optimizer::remarks::{Remark, RemarkKind};
+use luanext_core::optimizer::Optimizer;
+use luanext_core::MutableProgram;
+use luanext_parser::string_interner::{CommonIdentifiers, StringInterner};
+use luanext_parser::{Lexer, Parser};
+use std::sync::Arc;
+
+use crate::LuaExecutor;
+
+/// Parse `source` into a `MutableProgram`, panicking on syntax errors
+pub fn parse<'arena>(
+    source: &str,
+    interner: &StringInterner,
+    common: &CommonIdentifiers,
+    arena: &'arena Bump,
+) -> MutableProgram<'arena> {
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut lexer = Lexer::new(source, handler.clone(), interner);
+    let tokens = lexer.tokenize().expect("Lexing failed");
+    let mut parser = Parser::new(tokens, handler, interner, common, arena);
+    let program = parser.parse().expect("Parsing failed");
+    MutableProgram::from_program(&program)
+}
+
+/// A pipeline that runs only `pass`
+pub fn only(pass: &str) -> PassPipelineConfig {
+    PassPipelineConfig {
+        enable: vec![pass.to_string()],
+        disable: PASSES
+            .iter()
+            .map(|info| info.name)
+            .filter(|&name| name != pass)
+            .map(str::to_string)
+            .collect(),
+        ..PassPipelineConfig::default()
+    }
+}
+
+/// The default pipeline plus the opt-in `pass`
+pub fn enabling(pass: &str) -> PassPipelineConfig {
+    PassPipelineConfig {
+        enable: vec![pass.to_string()],
+        ..PassPipelineConfig::default()
+    }
+}
+
+/// Optimize `source` at `level` with `config`, returning the Lua and remarks
+pub fn optimize(
+    source: &str,
+    level: OptimizationLevel,
+    config: PassPipelineConfig,
+) -> (String, Vec<Remark>) {
+    optimize_for_target(source, level, config, LuaTarget::default())
+}
+
+/// Like [`optimize`], optimizing and generating Lua for `target`
+pub fn optimize_for_target(
+    source: &str,
+    level: OptimizationLevel,
+    config: PassPipelineConfig,
+    target: LuaTarget,
+) -> (String, Vec<Remark>) {
+    optimize_with(source, level, target, |optimizer, _| {
+        optimizer.set_pass_pipeline(config)
+    })
+}
+
+/// Optimize `source` at `level` for `target` once `configure` has set up the
+/// optimizer, generating Lua for `target`
+///
+/// `configure` also sees the parsed program, for settings keyed by its
+/// spans such as profiles.
+pub fn optimize_with(
+    source: &str,
+    level: OptimizationLevel,
+    target: LuaTarget,
+    configure: impl for<'a> FnOnce(&mut Optimizer<'a>, &MutableProgram<'a>),
+) -> (String, Vec<Remark>) {
+    let arena = Bump::new();
+    let (interner, common) = StringInterner::new_with_common_identifiers();
+    let interner = Arc::new(interner);
+    let mut program = parse(source, &interner, &common, &arena);
+
+    let handler = Arc::new(CollectingDiagnosticHandler::new());
+    let mut optimizer = Optimizer::new(level, handler, interner.clone());
+    optimizer.set_target(target);
+    configure(&mut optimizer, &program);
+    optimizer.enable_remarks();
+    optimizer.enable_verifier();
+    optimizer
+        .optimize(&mut program, &arena)
+        .expect("Optimization failed");
+
+    let lua = CodeGenerator::new(interner)
+        .with_target(target)
+        .generate(&program);
+    (lua, optimizer.take_remarks())
+}
+
+/// Messages of the `kind` remarks `pass` reported
+pub fn remarks_of<'r>(remarks: &'r [Remark], pass: &str, kind: RemarkKind) -> Vec<&'r str> {
+    remarks
+        .iter()
+        .filter(|r| r.pass == pass && r.kind == kind)
+        .map(|r| r.message.as_str())
+        .collect()
+}
+
+/// Run `lua` and read the integer global `var`
+pub fn int(lua: &str, var: &str) -> i64 {
+    LuaExecutor::new()
+        .unwrap()
+        .execute_and_get(lua, var)
+        .unwrap()
+}
+
+/// Run `lua` and read the string global `var`
+pub fn string(lua: &str, var: &str) -> String {
+    LuaExecutor::new()
+        .unwrap()
+        .execute_and_get(lua, var)
+        .unwrap()
+}
+
+/// Run `lua` and read the boolean global `var`
+pub fn boolean(lua: &str, var: &str) -> bool {
+    LuaExecutor::new()
+        .unwrap()
+        .execute_and_get(lua, var)
+        .unwrap()
+}